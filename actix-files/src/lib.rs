@@ -34,10 +34,13 @@ use v_htmlescape::escape as escape_html_entity;
 mod error;
 mod named;
 mod range;
+mod ranged;
+mod write;
 
 use self::error::{FilesError, UriSegmentError};
 pub use crate::named::NamedFile;
 pub use crate::range::HttpRange;
+pub use crate::ranged::{IntoRangedResponse, Ranged};
 
 type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
 type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>;
@@ -251,6 +254,8 @@ pub struct Files {
     mime_override: Option<Rc<MimeOverride>>,
     file_flags: named::Flags,
     guards: Option<Rc<Box<dyn Guard>>>,
+    writable: bool,
+    write_limit: u64,
 }
 
 impl Clone for Files {
@@ -266,6 +271,8 @@ impl Clone for Files {
             path: self.path.clone(),
             mime_override: self.mime_override.clone(),
             guards: self.guards.clone(),
+            writable: self.writable,
+            write_limit: self.write_limit,
         }
     }
 }
@@ -297,6 +304,8 @@ impl Files {
             mime_override: None,
             file_flags: named::Flags::default(),
             guards: None,
+            writable: false,
+            write_limit: write::DEFAULT_WRITE_LIMIT,
         }
     }
 
@@ -371,6 +380,40 @@ impl Files {
         self
     }
 
+    /// Allow writing files with `PUT` and removing them with `DELETE`.
+    ///
+    /// `PUT` creates or replaces file with the request payload, parent
+    /// directory has to exist. Writes are protected from lost updates with
+    /// entity tags: `If-Match` header has to match current `ETag` of the
+    /// file, `If-None-Match: *` creates file only if it does not exist.
+    /// Precondition headers could be required with
+    /// `web::PreconditionsConfig`. Entity tags are checked even if
+    /// `use_etag(false)` is set.
+    ///
+    /// By default files are read only.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App};
+    /// use actix_files as fs;
+    ///
+    /// let app = App::new()
+    ///     .app_data(web::PreconditionsConfig::default().required(true))
+    ///     .service(fs::Files::new("/data", ".").writable());
+    /// ```
+    pub fn writable(mut self) -> Self {
+        self.writable = true;
+        self
+    }
+
+    /// Set maximum size of `PUT` request payload in bytes.
+    ///
+    /// Larger payloads are rejected with `413 Payload Too Large` and
+    /// the file is left unchanged. By default limit is 16Mb.
+    pub fn write_limit(mut self, limit: u64) -> Self {
+        self.write_limit = limit;
+        self
+    }
+
     /// Disable `Content-Disposition` header.
     ///
     /// By default Content-Disposition` header is enabled.
@@ -434,6 +477,8 @@ impl ServiceFactory for Files {
             mime_override: self.mime_override.clone(),
             file_flags: self.file_flags,
             guards: self.guards.clone(),
+            writable: self.writable,
+            write_limit: self.write_limit,
         };
 
         if let Some(ref default) = *self.default.borrow() {
@@ -463,6 +508,8 @@ pub struct FilesService {
     mime_override: Option<Rc<MimeOverride>>,
     file_flags: named::Flags,
     guards: Option<Rc<Box<dyn Guard>>>,
+    writable: bool,
+    write_limit: u64,
 }
 
 impl FilesService {
@@ -504,6 +551,7 @@ impl Service for FilesService {
             // default behaviour
             match *req.method() {
                 Method::HEAD | Method::GET => true,
+                Method::PUT | Method::DELETE => self.writable,
                 _ => false,
             }
         };
@@ -521,6 +569,15 @@ impl Service for FilesService {
             Err(e) => return Either::Left(ok(req.error_response(e))),
         };
 
+        if self.writable
+            && (req.method() == Method::PUT || req.method() == Method::DELETE)
+        {
+            return match write::resolve(&self.directory, &real_path.0) {
+                Ok(path) => Either::Right(write::handle(req, path, self.write_limit)),
+                Err(e) => Either::Left(ok(req.error_response(e))),
+            };
+        }
+
         // full file path
         let path = match self.directory.join(&real_path.0).canonicalize() {
             Ok(path) => path,
@@ -694,6 +751,82 @@ mod tests {
         assert_ne!(resp.status(), StatusCode::NOT_MODIFIED);
     }
 
+    #[actix_rt::test]
+    async fn test_if_none_match_weak() {
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let etag = file.etag().unwrap();
+
+        // weak comparison for cache validation
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, format!("W/{}", etag))
+            .to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[actix_rt::test]
+    async fn test_if_range() {
+        let etag = NamedFile::open("Cargo.toml").unwrap().etag().unwrap();
+        let ranged = |if_range: String| {
+            let file = NamedFile::open("Cargo.toml").unwrap();
+            let req = TestRequest::default()
+                .header(header::RANGE, "bytes=10-20")
+                .header(header::IF_RANGE, if_range)
+                .to_http_request();
+            file.respond_to(&req)
+        };
+
+        let resp = ranged(etag.to_string()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+
+        // outdated or weak tag, whole file is sent
+        let resp = ranged("\"outdated\"".to_owned()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get(header::CONTENT_RANGE).is_none());
+        let resp = ranged(format!("W/{}", etag)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_ranged_response() {
+        let ranged = |range: &str| {
+            let file = NamedFile::open("tests/test.binary").unwrap();
+            let req = TestRequest::default()
+                .header(header::RANGE, range)
+                .to_http_request();
+            file.customize()
+                .header(header::CACHE_CONTROL, "private")
+                .cookie(actix_web::http::Cookie::new("downloaded", "1"))
+                .respond_to(&req)
+        };
+
+        let resp = ranged("bytes=10-20").await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 10-20/100"
+        );
+        assert_eq!(resp.headers().get(header::CACHE_CONTROL).unwrap(), "private");
+        assert_eq!(resp.cookies().next().unwrap().value(), "1");
+
+        let resp = ranged("bytes=10-5").await.unwrap();
+        assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(resp.headers().get(header::CACHE_CONTROL).unwrap(), "private");
+
+        // preconditions are checked as well
+        let file = NamedFile::open("tests/test.binary").unwrap();
+        let req = TestRequest::default()
+            .header(header::IF_MATCH, "\"outdated\"")
+            .to_http_request();
+        let resp = file
+            .customize()
+            .header(header::CACHE_CONTROL, "private")
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+    }
+
     #[actix_rt::test]
     async fn test_named_file_text() {
         assert!(NamedFile::open("test--").is_err());
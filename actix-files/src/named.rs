@@ -12,7 +12,7 @@ use mime;
 use mime_guess::from_path;
 
 use actix_http::body::SizedStream;
-use actix_web::dev::BodyEncoding;
+use actix_web::dev::{BodyEncoding, HttpResponseBuilder};
 use actix_web::http::header::{
     self, Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue,
 };
@@ -21,6 +21,7 @@ use actix_web::{Error, HttpMessage, HttpRequest, HttpResponse, Responder};
 use futures::future::{ready, Ready};
 
 use crate::range::HttpRange;
+use crate::ranged::IntoRangedResponse;
 use crate::ChunkedReadFile;
 
 bitflags! {
@@ -258,9 +259,19 @@ impl NamedFile {
     }
 
     pub fn into_response(self, req: &HttpRequest) -> Result<HttpResponse, Error> {
+        self.into_ranged_response(req, HttpResponse::Ok())
+    }
+}
+
+impl IntoRangedResponse for NamedFile {
+    fn into_ranged_response(
+        self,
+        req: &HttpRequest,
+        mut resp: HttpResponseBuilder,
+    ) -> Result<HttpResponse, Error> {
         if self.status_code != StatusCode::OK {
-            let mut resp = HttpResponse::build(self.status_code);
-            resp.set(header::ContentType(self.content_type.clone()))
+            resp.status(self.status_code)
+                .set(header::ContentType(self.content_type.clone()))
                 .if_true(self.flags.contains(Flags::CONTENT_DISPOSITION), |res| {
                     res.header(
                         header::CONTENT_DISPOSITION,
@@ -325,8 +336,14 @@ impl NamedFile {
             false
         };
 
-        let mut resp = HttpResponse::build(self.status_code);
-        resp.set(header::ContentType(self.content_type.clone()))
+        // range of modified file is not sent, whole file is sent instead
+        let range_allowed = match req.get_header::<header::IfRange>() {
+            Some(if_range) => if_range.matches(etag.as_ref(), last_modified.as_ref()),
+            None => !req.headers().contains_key(&header::IF_RANGE),
+        };
+
+        resp.status(self.status_code)
+            .set(header::ContentType(self.content_type.clone()))
             .if_true(self.flags.contains(Flags::CONTENT_DISPOSITION), |res| {
                 res.header(
                     header::CONTENT_DISPOSITION,
@@ -351,7 +368,8 @@ impl NamedFile {
         let mut offset = 0;
 
         // check for range header
-        if let Some(ranges) = req.headers().get(&header::RANGE) {
+        if let Some(ranges) = req.headers().get(&header::RANGE).filter(|_| range_allowed)
+        {
             if let Ok(rangesheader) = ranges.to_str() {
                 if let Ok(rangesvec) = HttpRange::parse(rangesheader, length) {
                     length = rangesvec[0].length;
@@ -414,16 +432,7 @@ impl DerefMut for NamedFile {
 fn any_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
     match req.get_header::<header::IfMatch>() {
         None | Some(header::IfMatch::Any) => true,
-        Some(header::IfMatch::Items(ref items)) => {
-            if let Some(some_etag) = etag {
-                for item in items {
-                    if item.strong_eq(some_etag) {
-                        return true;
-                    }
-                }
-            }
-            false
-        }
+        Some(if_match) => if_match.matches(etag),
     }
 }
 
@@ -431,16 +440,7 @@ fn any_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
 fn none_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
     match req.get_header::<header::IfNoneMatch>() {
         Some(header::IfNoneMatch::Any) => false,
-        Some(header::IfNoneMatch::Items(ref items)) => {
-            if let Some(some_etag) = etag {
-                for item in items {
-                    if item.weak_eq(some_etag) {
-                        return false;
-                    }
-                }
-            }
-            true
-        }
+        Some(if_none_match) => !if_none_match.matches(etag),
         None => true,
     }
 }
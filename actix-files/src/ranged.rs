@@ -0,0 +1,78 @@
+use std::convert::TryFrom;
+
+use actix_web::dev::HttpResponseBuilder;
+use actix_web::http::header::{HeaderName, IntoHeaderValue};
+use actix_web::http::{Cookie, Error as HttpError};
+use actix_web::{Error, HttpRequest, HttpResponse, Responder};
+use futures::future::{ready, Ready};
+
+/// Response body that honors range and conditional requests.
+///
+/// Body builds response of the request with the given response builder, so
+/// headers and cookies added by the handler are kept while ranges,
+/// `If-Range` and `If-Match` style preconditions are processed by the body.
+///
+/// ```rust
+/// use actix_files::{IntoRangedResponse, NamedFile};
+/// use actix_web::{http::Cookie, Responder};
+///
+/// async fn download() -> actix_web::Result<impl Responder> {
+///     let file = NamedFile::open("report.pdf")?;
+///     Ok(file
+///         .customize()
+///         .header("cache-control", "private")
+///         .cookie(Cookie::new("downloaded", "report")))
+/// }
+/// ```
+pub trait IntoRangedResponse: Sized {
+    /// Build response of the request, headers of the builder are kept.
+    fn into_ranged_response(
+        self,
+        req: &HttpRequest,
+        resp: HttpResponseBuilder,
+    ) -> Result<HttpResponse, Error>;
+
+    /// Add headers and cookies to the response of the body.
+    fn customize(self) -> Ranged<Self> {
+        Ranged {
+            body: self,
+            resp: HttpResponse::Ok(),
+        }
+    }
+}
+
+/// Responder of a ranged body with additional headers and cookies.
+///
+/// Created by `IntoRangedResponse::customize()`.
+pub struct Ranged<T> {
+    body: T,
+    resp: HttpResponseBuilder,
+}
+
+impl<T> Ranged<T> {
+    /// Append header to the response.
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<HttpError>,
+        V: IntoHeaderValue,
+    {
+        self.resp.header(key, value);
+        self
+    }
+
+    /// Add cookie to the response.
+    pub fn cookie(mut self, cookie: Cookie<'_>) -> Self {
+        self.resp.cookie(cookie);
+        self
+    }
+}
+
+impl<T: IntoRangedResponse> Responder for Ranged<T> {
+    type Error = Error;
+    type Future = Ready<Result<HttpResponse, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        ready(self.body.into_ranged_response(req, self.resp))
+    }
+}
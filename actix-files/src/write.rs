@@ -0,0 +1,481 @@
+//! Writable mode of the files service.
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::{BlockingError, Error, PayloadError};
+use actix_web::http::{header, Method};
+use actix_web::{web, FromRequest, HttpResponse};
+use futures::channel::oneshot;
+use futures::future::{FutureExt, LocalBoxFuture};
+use futures::StreamExt;
+
+use crate::error::FilesError;
+use crate::named::NamedFile;
+
+/// Default limit of `PUT` payload size, 16Mb.
+pub(crate) const DEFAULT_WRITE_LIMIT: u64 = 16 * 1024 * 1024;
+
+static NEXT_TMP: AtomicUsize = AtomicUsize::new(0);
+
+/// Resolve path of the file to write, parent directory has to exist within
+/// the served directory.
+pub(crate) fn resolve(directory: &Path, path: &Path) -> Result<PathBuf, Error> {
+    let name = match path.file_name() {
+        Some(name) => name.to_owned(),
+        None => return Err(FilesError::IsDirectory.into()),
+    };
+    let parent = directory.join(path.parent().unwrap_or_else(|| Path::new("")));
+    let parent = parent.canonicalize()?;
+    if !parent.starts_with(directory) {
+        return Err(io::Error::from(io::ErrorKind::NotFound).into());
+    }
+
+    let path = parent.join(name);
+    if path.is_dir() {
+        return Err(FilesError::IsDirectory.into());
+    }
+    Ok(path)
+}
+
+/// Handle `PUT` or `DELETE` request.
+pub(crate) fn handle(
+    req: ServiceRequest,
+    path: PathBuf,
+    limit: u64,
+) -> LocalBoxFuture<'static, Result<ServiceResponse, Error>> {
+    let (req, mut payload) = req.into_parts();
+
+    async move {
+        let pre = web::Preconditions::extract(&req).await?;
+
+        // preconditions are checked before payload is received and again
+        // right before the file is replaced
+        let current = match blocking({
+            let path = path.clone();
+            move || etag(&path)
+        })
+        .await
+        {
+            Ok(current) => current,
+            Err(e) => return Ok(ServiceResponse::from_err(e, req)),
+        };
+        if let Err(e) = pre.check(current.as_ref()) {
+            return Ok(ServiceResponse::from_err(e, req));
+        }
+
+        if req.method() == Method::DELETE {
+            let guard = WriteGuard::acquire(path.clone()).await;
+            let res = blocking(move || {
+                let _guard = guard;
+                let current = etag(&path)?;
+                if let Err(e) = pre.check(current.as_ref()) {
+                    return Ok(Err(e));
+                }
+                if current.is_none() {
+                    return Err(io::Error::from(io::ErrorKind::NotFound));
+                }
+                fs::remove_file(path).map(Ok)
+            })
+            .await;
+            return Ok(match res {
+                Ok(Ok(_)) => {
+                    ServiceResponse::new(req, HttpResponse::NoContent().finish())
+                }
+                Ok(Err(e)) => ServiceResponse::from_err(e, req),
+                Err(e) => ServiceResponse::from_err(e, req),
+            });
+        }
+
+        let length = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        if length.map(|length| length > limit).unwrap_or(false) {
+            return Ok(ServiceResponse::from_err(PayloadError::Overflow, req));
+        }
+
+        // write payload to temporary file next to the target and replace
+        // the target once payload is complete
+        let tmp = tmp_path(&path);
+        let mut file = {
+            let tmp = tmp.clone();
+            match blocking(move || File::create(tmp)).await {
+                Ok(file) => file,
+                Err(e) => return Ok(ServiceResponse::from_err(e, req)),
+            }
+        };
+        let mut size = 0;
+        while let Some(chunk) = payload.next().await {
+            let res = match chunk {
+                Ok(chunk) => {
+                    size += chunk.len() as u64;
+                    if size > limit {
+                        drop(file);
+                        let _ = fs::remove_file(&tmp);
+                        return Ok(ServiceResponse::from_err(
+                            PayloadError::Overflow,
+                            req,
+                        ));
+                    }
+                    blocking(move || file.write_all(&chunk).map(|_| file)).await
+                }
+                Err(e) => {
+                    let _ = fs::remove_file(&tmp);
+                    return Err(e.into());
+                }
+            };
+            file = match res {
+                Ok(file) => file,
+                Err(e) => {
+                    let _ = fs::remove_file(&tmp);
+                    return Ok(ServiceResponse::from_err(e, req));
+                }
+            };
+        }
+        drop(file);
+
+        let guard = WriteGuard::acquire(path.clone()).await;
+        let res = blocking(move || {
+            let _guard = guard;
+            let current = etag(&path).map_err(|e| {
+                let _ = fs::remove_file(&tmp);
+                e
+            })?;
+            if let Err(e) = pre.check(current.as_ref()) {
+                let _ = fs::remove_file(&tmp);
+                return Ok(Err(e));
+            }
+            fs::rename(&tmp, &path).map_err(|e| {
+                let _ = fs::remove_file(&tmp);
+                e
+            })?;
+            Ok(Ok((current.is_some(), etag(&path)?)))
+        })
+        .await;
+
+        Ok(match res {
+            Ok(Ok((existed, etag))) => {
+                let mut res = if existed {
+                    HttpResponse::NoContent()
+                } else {
+                    HttpResponse::Created()
+                };
+                if let Some(etag) = etag {
+                    res.set(header::ETag(etag));
+                }
+                ServiceResponse::new(req, res.finish())
+            }
+            Ok(Err(e)) => ServiceResponse::from_err(e, req),
+            Err(e) => ServiceResponse::from_err(e, req),
+        })
+    }
+    .boxed_local()
+}
+
+type Waiters = VecDeque<oneshot::Sender<WriteGuard>>;
+
+lazy_static::lazy_static! {
+    static ref WRITE_LOCKS: Mutex<HashMap<PathBuf, Waiters>> = Mutex::new(HashMap::new());
+}
+
+/// Serializes final precondition checks and replacements of a file within
+/// the process, so concurrent requests could not replace a file that was
+/// changed after their preconditions were checked. Requests for other files
+/// are not blocked.
+struct WriteGuard(PathBuf);
+
+impl WriteGuard {
+    async fn acquire(path: PathBuf) -> WriteGuard {
+        let rx = {
+            let mut locks = WRITE_LOCKS.lock().unwrap();
+            if let Some(waiters) = locks.get_mut(&path) {
+                let (tx, rx) = oneshot::channel();
+                waiters.push_back(tx);
+                rx
+            } else {
+                locks.insert(path.clone(), VecDeque::new());
+                return WriteGuard(path);
+            }
+        };
+
+        // guard is handed over by the current owner, waiters never
+        // lose their sender
+        rx.await.expect("Write lock is dropped")
+    }
+}
+
+impl Drop for WriteGuard {
+    fn drop(&mut self) {
+        let next = {
+            let mut locks = WRITE_LOCKS.lock().unwrap();
+            match locks
+                .get_mut(&self.0)
+                .and_then(|waiters| waiters.pop_front())
+            {
+                Some(next) => next,
+                None => {
+                    locks.remove(&self.0);
+                    return;
+                }
+            }
+        };
+
+        // if waiter is gone, guard is dropped and passed to the next one
+        let _ = next.send(WriteGuard(self.0.clone()));
+    }
+}
+
+/// Entity tag of the file, `None` if file does not exist.
+fn etag(path: &Path) -> io::Result<Option<header::EntityTag>> {
+    match NamedFile::open(path) {
+        Ok(file) => Ok(file.etag()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Hidden temporary file, it can not be requested from the files service.
+fn tmp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        std::process::id(),
+        NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+async fn blocking<F, T>(f: F) -> io::Result<T>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    web::block(f).await.map_err(|e| match e {
+        BlockingError::Error(e) => e,
+        BlockingError::Canceled => {
+            io::Error::new(io::ErrorKind::Other, "Thread pool is gone")
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+
+    use crate::Files;
+
+    fn tmp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "actix-files-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        dir
+    }
+
+    #[actix_rt::test]
+    async fn test_put_delete() {
+        let dir = tmp_dir("write");
+        let mut srv =
+            test::init_service(App::new().service(Files::new("/", &dir).writable()))
+                .await;
+
+        // create only if file does not exist
+        let req = TestRequest::put()
+            .uri("/sub/doc.txt")
+            .header("if-none-match", "*")
+            .set_payload("first")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let etag = resp.headers().get("etag").unwrap().clone();
+        assert_eq!(std::fs::read(dir.join("sub/doc.txt")).unwrap(), b"first");
+
+        let req = TestRequest::put()
+            .uri("/sub/doc.txt")
+            .header("if-none-match", "*")
+            .set_payload("second")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        // replace with current etag
+        let req = TestRequest::put()
+            .uri("/sub/doc.txt")
+            .header("if-match", etag.clone())
+            .set_payload("second!")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert_eq!(std::fs::read(dir.join("sub/doc.txt")).unwrap(), b"second!");
+
+        // outdated etag
+        let req = TestRequest::delete()
+            .uri("/sub/doc.txt")
+            .header("if-match", etag)
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        let req = TestRequest::get().uri("/sub/doc.txt").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        let etag = resp.headers().get("etag").unwrap().clone();
+
+        let req = TestRequest::delete()
+            .uri("/sub/doc.txt")
+            .header("if-match", etag)
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert!(!dir.join("sub/doc.txt").exists());
+
+        let req = TestRequest::delete().uri("/sub/doc.txt").to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // parent directory has to exist
+        let req = TestRequest::put()
+            .uri("/missing/doc.txt")
+            .set_payload("data")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::put()
+            .uri("/sub")
+            .set_payload("data")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // no leftovers of temporary files
+        assert_eq!(std::fs::read_dir(dir.join("sub")).unwrap().count(), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[actix_rt::test]
+    async fn test_concurrent_put() {
+        use actix_http::Payload;
+        use actix_service::Service;
+        use bytes::Bytes;
+        use futures::channel::{mpsc, oneshot};
+        use futures::StreamExt;
+
+        let dir = tmp_dir("concurrent");
+        let mut srv =
+            test::init_service(App::new().service(Files::new("/", &dir).writable()))
+                .await;
+
+        let req = TestRequest::put()
+            .uri("/doc.txt")
+            .set_payload("first")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        let etag = resp.headers().get("etag").unwrap().clone();
+
+        // first writer passes precondition check and waits for payload,
+        // payload is polled only after the check
+        let (tx, mut rx) = mpsc::unbounded();
+        let (polled_tx, polled_rx) = oneshot::channel();
+        let mut polled_tx = Some(polled_tx);
+        let payload = futures::stream::poll_fn(move |cx| {
+            if let Some(polled_tx) = polled_tx.take() {
+                let _ = polled_tx.send(());
+            }
+            rx.poll_next_unpin(cx)
+        });
+        let mut req = TestRequest::put()
+            .uri("/doc.txt")
+            .header("if-match", etag.clone())
+            .to_request();
+        *req.payload() = Payload::Stream(Box::pin(payload));
+        let fut = srv.call(req);
+        let (res_tx, res_rx) = oneshot::channel();
+        actix_rt::spawn(async move {
+            let _ = res_tx.send(fut.await);
+        });
+        polled_rx.await.unwrap();
+
+        // second writer with the same etag replaces the file meanwhile
+        let req = TestRequest::put()
+            .uri("/doc.txt")
+            .header("if-match", etag)
+            .set_payload("second")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        tx.unbounded_send(Ok(Bytes::from_static(b"stale"))).unwrap();
+        drop(tx);
+        let resp = res_rx.await.unwrap().unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(std::fs::read(dir.join("doc.txt")).unwrap(), b"second");
+
+        // no leftovers of temporary files
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[actix_rt::test]
+    async fn test_write_limit() {
+        let dir = tmp_dir("limit");
+        let mut srv = test::init_service(
+            App::new().service(Files::new("/", &dir).writable().write_limit(4)),
+        )
+        .await;
+
+        let req = TestRequest::put()
+            .uri("/doc.txt")
+            .set_payload("data")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let req = TestRequest::put()
+            .uri("/doc.txt")
+            .set_payload("data!")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // payload without content length is counted while it is received
+        let mut req = TestRequest::put().uri("/doc.txt").to_request();
+        *req.payload() =
+            actix_http::Payload::Stream(Box::pin(futures::stream::iter(vec![
+                Ok(bytes::Bytes::from_static(b"dat")),
+                Ok(bytes::Bytes::from_static(b"a!")),
+            ])));
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        assert_eq!(std::fs::read(dir.join("doc.txt")).unwrap(), b"data");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[actix_rt::test]
+    async fn test_read_only() {
+        let dir = tmp_dir("read-only");
+        let mut srv =
+            test::init_service(App::new().service(Files::new("/", &dir))).await;
+
+        let req = TestRequest::put()
+            .uri("/doc.txt")
+            .set_payload("data")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(!dir.join("doc.txt").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
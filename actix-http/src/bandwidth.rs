@@ -0,0 +1,226 @@
+//! Write bandwidth limits
+use std::cell::RefCell;
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use actix_rt::time::{delay_until, Delay, Instant};
+
+/// Write bandwidth limit of a connection or a response.
+///
+/// Data is written at `bytes_per_second` rate on average, up to `burst`
+/// bytes could be written at once after connection has been idle. By default
+/// burst equals to one second of data.
+///
+/// Connection limit is set by `HttpServiceBuilder::bandwidth_limit()`,
+/// response limit is set by inserting `BandwidthLimit` to response
+/// extensions. Response limit applies in addition to the connection limit.
+///
+/// ```rust
+/// use actix_http::{BandwidthLimit, Response};
+///
+/// let mut res = Response::Ok().body("large download");
+/// res.extensions_mut()
+///     .insert(BandwidthLimit::new(64 * 1024).burst(256 * 1024));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BandwidthLimit {
+    rate: u64,
+    burst: u64,
+}
+
+impl BandwidthLimit {
+    /// Create limit of `bytes_per_second` write rate.
+    pub fn new(bytes_per_second: u64) -> Self {
+        let rate = cmp::max(bytes_per_second, 1);
+        BandwidthLimit { rate, burst: rate }
+    }
+
+    /// Set maximum number of bytes written at once.
+    pub fn burst(mut self, bytes: u64) -> Self {
+        self.burst = cmp::max(bytes, 1);
+        self
+    }
+
+    /// Average write rate in bytes per second.
+    pub fn bytes_per_second(&self) -> u64 {
+        self.rate
+    }
+}
+
+/// Token bucket of a bandwidth limit, one token per byte.
+pub(crate) struct TokenBucket {
+    limit: BandwidthLimit,
+    tokens: u64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(limit: BandwidthLimit) -> Self {
+        TokenBucket {
+            limit,
+            tokens: limit.burst,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Refill the bucket and return number of bytes that could be written.
+    fn available(&mut self, now: Instant) -> u64 {
+        let elapsed = now.saturating_duration_since(self.updated).as_nanos();
+        let added = elapsed * self.limit.rate as u128 / 1_000_000_000;
+
+        if self.tokens + added as u64 >= self.limit.burst {
+            self.tokens = self.limit.burst;
+            self.updated = now;
+        } else if added > 0 {
+            // keep remainder of partially refilled token
+            let nanos = added * 1_000_000_000 / self.limit.rate as u128;
+            self.tokens += added as u64;
+            self.updated += Duration::from_nanos(nanos as u64);
+        }
+        self.tokens
+    }
+
+    fn consume(&mut self, n: u64) {
+        self.tokens = self.tokens.saturating_sub(n);
+    }
+
+    /// Time when the bucket has enough tokens for a reasonably sized write.
+    fn next_write(&self) -> Instant {
+        let quantum = cmp::min(cmp::max(self.limit.rate / 20, 1), self.limit.burst);
+        let needed = quantum.saturating_sub(self.tokens) as u128;
+        let nanos = needed * 1_000_000_000 / self.limit.rate as u128;
+        self.updated + Duration::from_nanos(nanos as u64)
+    }
+}
+
+/// Write limits of a single response stream.
+pub(crate) struct WriteLimit {
+    connection: Option<Rc<RefCell<TokenBucket>>>,
+    response: Option<TokenBucket>,
+    delay: Option<Delay>,
+}
+
+impl WriteLimit {
+    pub(crate) fn new(connection: Option<Rc<RefCell<TokenBucket>>>) -> Self {
+        WriteLimit {
+            connection,
+            response: None,
+            delay: None,
+        }
+    }
+
+    /// Replace limit of the current response.
+    pub(crate) fn set_response(&mut self, limit: Option<BandwidthLimit>) {
+        self.response = limit.map(TokenBucket::new);
+    }
+
+    /// Number of bytes up to `max` that could be written now, registers
+    /// wake up once bytes are available otherwise.
+    pub(crate) fn poll_acquire(
+        &mut self,
+        cx: &mut Context<'_>,
+        max: usize,
+    ) -> Poll<usize> {
+        if self.connection.is_none() && self.response.is_none() {
+            return Poll::Ready(max);
+        }
+
+        loop {
+            let now = Instant::now();
+            let mut available = max as u64;
+            let mut next_write = now;
+
+            let mut check = |bucket: &mut TokenBucket| {
+                let tokens = bucket.available(now);
+                if tokens == 0 {
+                    next_write = cmp::max(next_write, bucket.next_write());
+                }
+                available = cmp::min(available, tokens);
+            };
+            if let Some(ref bucket) = self.connection {
+                check(&mut bucket.borrow_mut());
+            }
+            if let Some(ref mut bucket) = self.response {
+                check(bucket);
+            }
+
+            if available > 0 {
+                return Poll::Ready(available as usize);
+            }
+
+            match self.delay {
+                Some(ref mut delay) => delay.reset(next_write),
+                None => self.delay = Some(delay_until(next_write)),
+            }
+            if Pin::new(self.delay.as_mut().unwrap()).poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    /// Take written bytes from the buckets.
+    pub(crate) fn consume(&mut self, n: usize) {
+        if let Some(ref bucket) = self.connection {
+            bucket.borrow_mut().consume(n as u64);
+        }
+        if let Some(ref mut bucket) = self.response {
+            bucket.consume(n as u64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let mut bucket = TokenBucket::new(BandwidthLimit::new(1000).burst(100));
+        let start = bucket.updated;
+        assert_eq!(bucket.available(start), 100);
+
+        bucket.consume(100);
+        assert_eq!(bucket.available(start), 0);
+        assert_eq!(bucket.next_write(), start + Duration::from_millis(50));
+
+        // partial tokens are not lost
+        assert_eq!(bucket.available(start + Duration::from_micros(1500)), 1);
+        assert_eq!(bucket.available(start + Duration::from_micros(3000)), 3);
+        assert_eq!(bucket.available(start + Duration::from_millis(40)), 40);
+
+        // refill up to burst size
+        assert_eq!(bucket.available(start + Duration::from_secs(10)), 100);
+    }
+
+    #[actix_rt::test]
+    async fn test_write_limit() {
+        let conn = Rc::new(RefCell::new(TokenBucket::new(BandwidthLimit::new(10_000))));
+        let mut limit = WriteLimit::new(Some(conn.clone()));
+        limit.set_response(Some(BandwidthLimit::new(10_000).burst(1_000)));
+
+        let started = Instant::now();
+        let mut written = 0;
+        futures_util::future::poll_fn(|cx| loop {
+            match limit.poll_acquire(cx, 5_000) {
+                Poll::Ready(n) => {
+                    assert!(n <= 1_000);
+                    limit.consume(n);
+                    written += n;
+                    if written >= 2_000 {
+                        return Poll::Ready(());
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        })
+        .await;
+
+        // response burst, then refill of response bucket
+        assert!(Instant::now() - started >= Duration::from_millis(100));
+        assert!(conn.borrow().tokens < 10_000);
+    }
+}
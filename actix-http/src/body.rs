@@ -9,6 +9,7 @@ use futures_util::ready;
 use pin_project::{pin_project, project};
 
 use crate::error::Error;
+use crate::header::HeaderMap;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 /// Body size hint
@@ -41,6 +42,15 @@ pub trait MessageBody {
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, Error>>>;
 
+    /// Trailer headers, sent after the body is complete.
+    ///
+    /// Trailers are taken once `poll_next()` returns `None`. They are sent
+    /// with chunked transfer encoding of http/1.1 and at the end of http/2
+    /// stream, otherwise trailers are dropped.
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        None
+    }
+
     downcast_get_type_id!();
 }
 
@@ -70,6 +80,10 @@ impl<T: MessageBody + Unpin> MessageBody for Box<T> {
     ) -> Poll<Option<Result<Bytes, Error>>> {
         Pin::new(self.get_mut().as_mut()).poll_next(cx)
     }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        Pin::new(self.get_mut().as_mut()).take_trailers()
+    }
 }
 
 #[pin_project]
@@ -122,6 +136,15 @@ impl<B: MessageBody> MessageBody for ResponseBody<B> {
             ResponseBody::Other(body) => body.poll_next(cx),
         }
     }
+
+    #[project]
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        #[project]
+        match self.project() {
+            ResponseBody::Body(body) => body.take_trailers(),
+            ResponseBody::Other(body) => body.take_trailers(),
+        }
+    }
 }
 
 impl<B: MessageBody> Stream for ResponseBody<B> {
@@ -195,6 +218,15 @@ impl MessageBody for Body {
             Body::Message(ref mut body) => Pin::new(body.as_mut()).poll_next(cx),
         }
     }
+
+    #[project]
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        #[project]
+        match self.project() {
+            Body::Message(ref mut body) => Pin::new(body.as_mut()).take_trailers(),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq for Body {
@@ -486,6 +518,66 @@ where
     }
 }
 
+/// Message body with trailer headers.
+///
+/// Trailers are produced by `trailers` closure once the body is complete,
+/// so they could contain values computed while the body is streamed, like
+/// checksums. Trailer names should be declared with `Trailer` response
+/// header. Body is always sent with chunked transfer encoding.
+///
+/// ```rust
+/// use actix_http::body::{Body, WithTrailers};
+/// use actix_http::http::{header, HeaderMap, HeaderValue};
+/// use actix_http::Response;
+///
+/// let body = WithTrailers::new(Body::from("data"), || {
+///     let mut trailers = HeaderMap::new();
+///     trailers.insert(
+///         header::HeaderName::from_static("x-checksum"),
+///         HeaderValue::from_static("8d777f38"),
+///     );
+///     trailers
+/// });
+/// let res = Response::Ok()
+///     .header(header::TRAILER, "x-checksum")
+///     .body(Body::from_message(body));
+/// ```
+#[pin_project]
+pub struct WithTrailers<B> {
+    #[pin]
+    body: B,
+    trailers: Option<Box<dyn FnOnce() -> HeaderMap>>,
+}
+
+impl<B: MessageBody> WithTrailers<B> {
+    pub fn new<F>(body: B, trailers: F) -> Self
+    where
+        F: FnOnce() -> HeaderMap + 'static,
+    {
+        WithTrailers {
+            body,
+            trailers: Some(Box::new(trailers)),
+        }
+    }
+}
+
+impl<B: MessageBody> MessageBody for WithTrailers<B> {
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        self.project().body.poll_next(cx)
+    }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        self.project().trailers.take().map(|trailers| trailers())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -623,6 +715,33 @@ mod tests {
         assert!(poll_fn(|cx| val.as_mut().poll_next(cx)).await.is_none());
     }
 
+    #[actix_rt::test]
+    async fn test_with_trailers() {
+        let body = WithTrailers::new(Body::from("test"), || {
+            let mut trailers = HeaderMap::new();
+            trailers.insert(
+                crate::http::header::HeaderName::from_static("x-test"),
+                crate::http::HeaderValue::from_static("1"),
+            );
+            trailers
+        });
+        let body = Body::from_message(body);
+        pin_mut!(body);
+        assert_eq!(body.size(), BodySize::Stream);
+        assert_eq!(
+            poll_fn(|cx| body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .ok(),
+            Some(Bytes::from("test"))
+        );
+        assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).await.is_none());
+
+        let trailers = body.as_mut().take_trailers().unwrap();
+        assert_eq!(trailers.get("x-test").unwrap(), "1");
+        assert!(body.as_mut().take_trailers().is_none());
+    }
+
     #[actix_rt::test]
     async fn test_body_eq() {
         assert!(Body::None == Body::None);
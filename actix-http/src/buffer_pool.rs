@@ -0,0 +1,124 @@
+//! Connection buffer pool
+use std::cell::RefCell;
+use std::{cmp, mem};
+
+use bytes::BytesMut;
+
+/// Pool of connection buffers of a worker.
+///
+/// Http/1 dispatchers take read and write buffers from the pool and return
+/// them once connection becomes idle or is closed, so connection churn does
+/// not allocate fresh buffers for every connection. Every worker keeps its
+/// own pool of up to `capacity` buffers of `chunk_size` bytes. Buffers of
+/// http/2 connections are managed by the h2 crate and are not pooled.
+///
+/// ```rust
+/// use actix_http::{BufferPool, HttpService, Response};
+/// use futures_util::future::ok;
+///
+/// let srv = HttpService::build()
+///     .buffer_pool(BufferPool::new(1024).chunk_size(16 * 1024))
+///     .finish(|_| ok::<_, ()>(Response::Ok().finish()))
+///     .tcp();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferPool {
+    capacity: usize,
+    chunk_size: usize,
+}
+
+impl BufferPool {
+    /// Create pool that keeps up to `capacity` free buffers.
+    pub fn new(capacity: usize) -> Self {
+        BufferPool {
+            capacity,
+            chunk_size: 32_768,
+        }
+    }
+
+    /// Set size of pooled buffers.
+    ///
+    /// By default buffers are 32kB, size is at least 4kB.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = cmp::max(size, 4096);
+        self
+    }
+}
+
+/// Free buffers of a pool.
+pub(crate) struct Buffers {
+    pool: BufferPool,
+    free: RefCell<Vec<BytesMut>>,
+}
+
+impl Buffers {
+    pub(crate) fn new(pool: BufferPool) -> Self {
+        Buffers {
+            pool,
+            free: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn pool(&self) -> BufferPool {
+        self.pool
+    }
+
+    /// Take free buffer or allocate new one.
+    pub(crate) fn get(&self) -> BytesMut {
+        self.free
+            .borrow_mut()
+            .pop()
+            .unwrap_or_else(|| BytesMut::with_capacity(self.pool.chunk_size))
+    }
+
+    /// Return buffer to the pool, buffer is left empty without capacity.
+    ///
+    /// Data of the buffer is discarded, smaller buffers and buffers over
+    /// pool capacity are dropped.
+    pub(crate) fn release(&self, buf: &mut BytesMut) {
+        let mut buf = mem::replace(buf, BytesMut::new());
+        buf.clear();
+        let mut free = self.free.borrow_mut();
+        if buf.capacity() >= self.pool.chunk_size && free.len() < self.pool.capacity {
+            free.push(buf);
+        }
+    }
+
+    /// Number of free buffers.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.free.borrow().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffers() {
+        let buffers = Buffers::new(BufferPool::new(2).chunk_size(8192));
+        let mut buf1 = buffers.get();
+        let mut buf2 = buffers.get();
+        let mut buf3 = buffers.get();
+        assert!(buf1.capacity() >= 8192);
+
+        buffers.release(&mut BytesMut::with_capacity(1024));
+        assert_eq!(buffers.len(), 0);
+
+        buf1.extend_from_slice(b"data");
+        buffers.release(&mut buf1);
+        assert_eq!(buffers.len(), 1);
+        assert_eq!(buf1.capacity(), 0);
+
+        buffers.release(&mut buf2);
+        buffers.release(&mut buf3);
+        assert_eq!(buffers.len(), 2);
+        assert_eq!(buf2.capacity(), 0);
+
+        let buf = buffers.get();
+        assert!(buf.is_empty());
+        assert!(buf.capacity() >= 8192);
+        assert_eq!(buffers.len(), 1);
+    }
+}
@@ -1,20 +1,29 @@
 use std::marker::PhantomData;
 use std::rc::Rc;
-use std::{fmt, net};
+use std::time::Duration;
+use std::{cmp, fmt, net};
 
 use actix_codec::Framed;
 use actix_service::{IntoServiceFactory, Service, ServiceFactory};
 
+use crate::bandwidth::BandwidthLimit;
 use crate::body::MessageBody;
+use crate::buffer_pool::BufferPool;
 use crate::config::{KeepAlive, ServiceConfig};
 use crate::error::Error;
-use crate::h1::{Codec, ExpectHandler, H1Service, UpgradeHandler};
-use crate::h2::H2Service;
-use crate::helpers::{Data, DataFactory};
+use crate::h1::{
+    Codec, ExpectHandler, H1Service, HeadLimits, LegacyOptions, UpgradeHandler,
+};
+use crate::h2::{H2Service, H2Settings};
+use crate::health::HealthMonitor;
+use crate::helpers::{Data, OnConnect};
+use crate::load_shed::LoadShedPolicy;
 use crate::request::Request;
 use crate::response::Response;
 use crate::service::HttpService;
 
+const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+
 /// A http service builder
 ///
 /// This type can be used to construct an instance of `http service` through a
@@ -25,9 +34,25 @@ pub struct HttpServiceBuilder<T, S, X = ExpectHandler, U = UpgradeHandler<T>> {
     client_disconnect: u64,
     secure: bool,
     local_addr: Option<net::SocketAddr>,
+    read_buf_limit: usize,
+    write_coalesce_limit: usize,
+    date_header: bool,
+    h2_prior_knowledge: bool,
+    proxy_protocol: bool,
+    rate_limit: u32,
+    rate_limit_close: bool,
+    bandwidth_limit: Option<BandwidthLimit>,
+    buffer_pool: Option<BufferPool>,
+    h2_streams: usize,
+    h2_peer_streams: usize,
+    load_shed: Option<LoadShedPolicy>,
+    health_monitor: Option<HealthMonitor>,
+    head_limits: HeadLimits,
+    legacy_options: LegacyOptions,
+    h2_settings: H2Settings,
     expect: X,
     upgrade: Option<U>,
-    on_connect: Option<Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+    on_connect: Option<OnConnect<T>>,
     _t: PhantomData<(T, S)>,
 }
 
@@ -46,6 +71,22 @@ where
             client_disconnect: 0,
             secure: false,
             local_addr: None,
+            read_buf_limit: 32_768,
+            write_coalesce_limit: 4096,
+            date_header: true,
+            h2_prior_knowledge: false,
+            proxy_protocol: false,
+            rate_limit: 0,
+            rate_limit_close: false,
+            bandwidth_limit: None,
+            buffer_pool: None,
+            h2_streams: 0,
+            h2_peer_streams: 0,
+            load_shed: None,
+            health_monitor: None,
+            head_limits: HeadLimits::default(),
+            legacy_options: LegacyOptions::default(),
+            h2_settings: H2Settings::default(),
             expect: ExpectHandler,
             upgrade: None,
             on_connect: None,
@@ -116,6 +157,325 @@ where
         self
     }
 
+    /// Set maximum size of a single read from the connection.
+    ///
+    /// Read buffer starts small and grows up to this limit while requests
+    /// fill it up, it is released when connection becomes idle, so idle
+    /// keep-alive connections hold little memory.
+    ///
+    /// By default limit is set to 32kB, limit is at least 4kB. Only http/1
+    /// connections are affected.
+    pub fn read_buf_limit(mut self, limit: usize) -> Self {
+        self.read_buf_limit = cmp::max(limit, 4096);
+        self
+    }
+
+    /// Set size of response payload chunks that are written without copying.
+    ///
+    /// Smaller chunks are copied to write buffer after response head, chunks
+    /// of this size and larger are written together with buffered data using
+    /// vectored writes. Zero copies all chunks.
+    ///
+    /// By default limit is set to 4kB. Only http/1 connections are affected.
+    pub fn write_coalesce_limit(mut self, limit: usize) -> Self {
+        self.write_coalesce_limit = limit;
+        self
+    }
+
+    /// Insert `Date` header to responses without one.
+    ///
+    /// Disable if the header is set by a gateway in front of the server.
+    /// Responses could set the header explicitly with
+    /// `ResponseBuilder::date()`, explicit header is never replaced.
+    ///
+    /// By default the header is inserted.
+    pub fn date_header(mut self, enabled: bool) -> Self {
+        self.date_header = enabled;
+        self
+    }
+
+    /// Set maximum number of request headers.
+    ///
+    /// Requests with more headers are rejected with
+    /// *REQUEST HEADER FIELDS TOO LARGE* response.
+    ///
+    /// By default limit is set to 96 headers. Only http/1 connections are affected.
+    pub fn max_headers(mut self, num: usize) -> Self {
+        self.head_limits.max_headers = num;
+        self
+    }
+
+    /// Set maximum size of request head in bytes, request line included.
+    ///
+    /// Requests with larger head are rejected with
+    /// *REQUEST HEADER FIELDS TOO LARGE* response.
+    ///
+    /// By default limit is set to 128kB. Only http/1 connections are affected.
+    pub fn max_header_size(mut self, size: usize) -> Self {
+        self.head_limits.max_header_size = size;
+        self
+    }
+
+    /// Set maximum length of request line in bytes.
+    ///
+    /// Requests with longer request line are rejected with *URI TOO LONG*
+    /// response.
+    ///
+    /// By default limit is set to 64kB. Only http/1 connections are affected.
+    pub fn max_request_line(mut self, size: usize) -> Self {
+        self.head_limits.max_request_line = size;
+        self
+    }
+
+    /// Reject ambiguous requests that could be used for request smuggling.
+    ///
+    /// Requests with both `Transfer-Encoding` and `Content-Length` headers,
+    /// with duplicate `Content-Length` or `Transfer-Encoding` headers, with
+    /// `Transfer-Encoding` other than `chunked` or with obsolete line folding
+    /// of header values are rejected with *BAD REQUEST* response.
+    ///
+    /// By default strict parsing is disabled. Only http/1 connections are
+    /// affected.
+    pub fn strict_parsing(mut self, enabled: bool) -> Self {
+        self.head_limits.strict = enabled;
+        self
+    }
+
+    /// Keep connections of http/1.0 requests with `Connection: keep-alive`
+    /// header open.
+    ///
+    /// By default keep-alive of http/1.0 clients is honored.
+    pub fn http10_keep_alive(mut self, enabled: bool) -> Self {
+        self.legacy_options.keep_alive = enabled;
+        self
+    }
+
+    /// Use chunked transfer encoding for http/1.0 responses with body of
+    /// unknown length.
+    ///
+    /// Chunked encoding is defined by http/1.1 and is not understood by
+    /// some legacy clients. If disabled, body is delimited by closing the
+    /// connection.
+    ///
+    /// By default chunked encoding is used.
+    pub fn http10_chunked(mut self, enabled: bool) -> Self {
+        self.legacy_options.chunked = enabled;
+        self
+    }
+
+    /// Send `Content-Length: 0` header with empty bodies of http/1.0
+    /// responses.
+    ///
+    /// If disabled, the header is omitted and connection is closed after
+    /// the response.
+    ///
+    /// By default the header is sent.
+    pub fn http10_empty_length(mut self, enabled: bool) -> Self {
+        self.legacy_options.empty_length = enabled;
+        self
+    }
+
+    /// Accept HTTP/2 connections with prior knowledge on plain tcp.
+    ///
+    /// Connections that start with HTTP/2 connection preface are served by
+    /// HTTP/2 dispatcher, all other connections by HTTP/1. Use it for
+    /// services behind a trusted load balancer that speaks HTTP/2 without
+    /// tls. Switching to HTTP/2 with `Upgrade: h2c` request is not supported.
+    ///
+    /// By default it is disabled. Only `HttpService::tcp()` is affected.
+    pub fn h2_prior_knowledge(mut self, enabled: bool) -> Self {
+        self.h2_prior_knowledge = enabled;
+        self
+    }
+
+    /// Read PROXY protocol v1 or v2 header of accepted connections.
+    ///
+    /// Source address of the proxied connection is used as peer address of
+    /// requests. Connections without the header are closed, enable it only
+    /// for listeners behind a proxy, i.e. HAProxy or AWS network load balancer.
+    ///
+    /// By default PROXY protocol is disabled. `HttpService::tcp()`,
+    /// `HttpService::openssl()` and `HttpService::rustls()` are affected.
+    pub fn proxy_protocol(mut self, enabled: bool) -> Self {
+        self.proxy_protocol = enabled;
+        self
+    }
+
+    /// Set maximum number of requests per second of a single connection.
+    ///
+    /// Pipelined http/1 requests and http/2 streams over the limit are
+    /// responded with `429 Too Many Requests` before reaching the service.
+    ///
+    /// By default requests are not limited, set to `0` to disable.
+    pub fn max_requests_per_second(mut self, limit: u32) -> Self {
+        self.rate_limit = limit;
+        self
+    }
+
+    /// Close connections that exceed request rate.
+    ///
+    /// Http/1 connection is closed after `429 Too Many Requests` response,
+    /// http/2 connection is shut down gracefully with `GOAWAY` frame.
+    ///
+    /// By default connections are kept open.
+    pub fn close_on_rate_limit(mut self, close: bool) -> Self {
+        self.rate_limit_close = close;
+        self
+    }
+
+    /// Limit write bandwidth of a single connection.
+    ///
+    /// Limit of a single response is set by inserting `BandwidthLimit` to
+    /// response extensions, both limits apply to the response.
+    ///
+    /// By default bandwidth is not limited, set to `None` to disable.
+    pub fn bandwidth_limit<L: Into<Option<BandwidthLimit>>>(mut self, limit: L) -> Self {
+        self.bandwidth_limit = limit.into();
+        self
+    }
+
+    /// Take read and write buffers of http/1 connections from a per-worker
+    /// pool.
+    ///
+    /// By default every connection allocates own buffers, set to `None` to
+    /// disable.
+    pub fn buffer_pool<P: Into<Option<BufferPool>>>(mut self, pool: P) -> Self {
+        self.buffer_pool = pool.into();
+        self
+    }
+
+    /// Set maximum number of concurrently processed streams of a http/2
+    /// connection.
+    ///
+    /// Streams over the limit wait until processing of earlier streams
+    /// completes. Up to `limit` streams are queued, further streams are reset
+    /// with `REFUSED_STREAM` error. Limit applies regardless of
+    /// `SETTINGS_MAX_CONCURRENT_STREAMS` advertised to the client. Pushed
+    /// streams are counted as well and wait for a free slot.
+    ///
+    /// By default streams are not limited, set to `0` to disable.
+    pub fn h2_max_streams(mut self, limit: usize) -> Self {
+        self.h2_streams = limit;
+        self
+    }
+
+    /// Set maximum number of concurrently processed http/2 streams of a
+    /// peer address, over all connections of the worker.
+    ///
+    /// Streams over the limit are reset with `REFUSED_STREAM` error,
+    /// queued and pushed streams are counted as well.
+    ///
+    /// By default streams are not limited, set to `0` to disable.
+    pub fn h2_max_peer_streams(mut self, limit: usize) -> Self {
+        self.h2_peer_streams = limit;
+        self
+    }
+
+    /// Set `SETTINGS_MAX_CONCURRENT_STREAMS` advertised to http/2 clients.
+    ///
+    /// Clients must not open more streams, streams over the limit are reset
+    /// by the h2 crate with `REFUSED_STREAM` error.
+    ///
+    /// By default number of streams is not advertised, set to `None` to
+    /// disable.
+    pub fn h2_max_concurrent_streams<M: Into<Option<u32>>>(mut self, max: M) -> Self {
+        self.h2_settings.max_concurrent_streams = max.into();
+        self
+    }
+
+    /// Set initial flow control window of http/2 streams in bytes.
+    ///
+    /// Larger windows let clients send bigger request bodies without
+    /// waiting for `WINDOW_UPDATE` frames. Panics if `size` is over
+    /// 2<sup>31</sup>-1.
+    ///
+    /// By default window is 64kB, set to `None` to use default.
+    pub fn h2_initial_window_size<W: Into<Option<u32>>>(mut self, size: W) -> Self {
+        let size = size.into();
+        assert!(size <= Some(MAX_WINDOW_SIZE), "Window size is over 2^31-1");
+        self.h2_settings.initial_window_size = size;
+        self
+    }
+
+    /// Set initial flow control window of http/2 connections in bytes.
+    ///
+    /// Window is shared by all streams of the connection. Panics if `size`
+    /// is over 2<sup>31</sup>-1.
+    ///
+    /// By default window is 64kB, set to `None` to use default.
+    pub fn h2_initial_connection_window_size<W>(mut self, size: W) -> Self
+    where
+        W: Into<Option<u32>>,
+    {
+        let size = size.into();
+        assert!(size <= Some(MAX_WINDOW_SIZE), "Window size is over 2^31-1");
+        self.h2_settings.initial_connection_window_size = size;
+        self
+    }
+
+    /// Set maximum size of http/2 frames the server accepts in bytes.
+    ///
+    /// Panics if `size` is not within 16kB and 16MB-1.
+    ///
+    /// By default frames are up to 16kB, set to `None` to use default.
+    pub fn h2_max_frame_size<F: Into<Option<u32>>>(mut self, size: F) -> Self {
+        let size = size.into();
+        if let Some(size) = size {
+            assert!(
+                (16_384..=16_777_215).contains(&size),
+                "Frame size is not within 16kB and 16MB-1"
+            );
+        }
+        self.h2_settings.max_frame_size = size;
+        self
+    }
+
+    /// Send http/2 `PING` frames to clients in the interval.
+    ///
+    /// Connections of clients that do not answer a ping within
+    /// ping timeout are closed. Pings detect dead peers of idle long-lived
+    /// connections, i.e. of streaming rpc calls.
+    ///
+    /// By default pings are not sent, set to `None` to disable.
+    pub fn h2_keep_alive_interval<D>(mut self, interval: D) -> Self
+    where
+        D: Into<Option<Duration>>,
+    {
+        self.h2_settings.ping_interval = interval.into();
+        self
+    }
+
+    /// Set time to wait for answer of http/2 keep-alive ping.
+    ///
+    /// By default timeout is 20 seconds. Takes effect only if
+    /// `h2_keep_alive_interval()` is set.
+    pub fn h2_keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.h2_settings.ping_timeout = timeout;
+        self
+    }
+
+    /// Reject new requests of an overloaded worker.
+    ///
+    /// Policy is consulted before http/1 requests and http/2 streams reach
+    /// the service, rejected requests are responded with
+    /// `503 Service Unavailable` and `Retry-After` header.
+    ///
+    /// By default requests are not rejected, set to `None` to disable.
+    pub fn load_shed<P: Into<Option<LoadShedPolicy>>>(mut self, policy: P) -> Self {
+        self.load_shed = policy.into();
+        self
+    }
+
+    /// Log unhealthy runtime of workers.
+    ///
+    /// Event loop lag is sampled on every worker regardless of the monitor,
+    /// samples are available as `Load::current()` signals. By default
+    /// nothing is logged, set to `None` to disable.
+    pub fn health_monitor<M: Into<Option<HealthMonitor>>>(mut self, monitor: M) -> Self {
+        self.health_monitor = monitor.into();
+        self
+    }
+
     /// Provide service for `EXPECT: 100-Continue` support.
     ///
     /// Service get called with request that contains `EXPECT` header.
@@ -135,6 +495,22 @@ where
             client_disconnect: self.client_disconnect,
             secure: self.secure,
             local_addr: self.local_addr,
+            read_buf_limit: self.read_buf_limit,
+            write_coalesce_limit: self.write_coalesce_limit,
+            date_header: self.date_header,
+            h2_prior_knowledge: self.h2_prior_knowledge,
+            proxy_protocol: self.proxy_protocol,
+            rate_limit: self.rate_limit,
+            rate_limit_close: self.rate_limit_close,
+            bandwidth_limit: self.bandwidth_limit,
+            buffer_pool: self.buffer_pool,
+            h2_streams: self.h2_streams,
+            h2_peer_streams: self.h2_peer_streams,
+            load_shed: self.load_shed,
+            health_monitor: self.health_monitor,
+            head_limits: self.head_limits,
+            legacy_options: self.legacy_options,
+            h2_settings: self.h2_settings,
             expect: expect.into_factory(),
             upgrade: self.upgrade,
             on_connect: self.on_connect,
@@ -164,6 +540,22 @@ where
             client_disconnect: self.client_disconnect,
             secure: self.secure,
             local_addr: self.local_addr,
+            read_buf_limit: self.read_buf_limit,
+            write_coalesce_limit: self.write_coalesce_limit,
+            date_header: self.date_header,
+            h2_prior_knowledge: self.h2_prior_knowledge,
+            proxy_protocol: self.proxy_protocol,
+            rate_limit: self.rate_limit,
+            rate_limit_close: self.rate_limit_close,
+            bandwidth_limit: self.bandwidth_limit,
+            buffer_pool: self.buffer_pool,
+            h2_streams: self.h2_streams,
+            h2_peer_streams: self.h2_peer_streams,
+            load_shed: self.load_shed,
+            health_monitor: self.health_monitor,
+            head_limits: self.head_limits,
+            legacy_options: self.legacy_options,
+            h2_settings: self.h2_settings,
             expect: self.expect,
             upgrade: Some(upgrade.into_factory()),
             on_connect: self.on_connect,
@@ -180,7 +572,48 @@ where
         F: Fn(&T) -> I + 'static,
         I: Clone + 'static,
     {
-        self.on_connect = Some(Rc::new(move |io| Box::new(Data(f(io)))));
+        self.on_connect = Some(Rc::new(move |io, _| Some(Box::new(Data(f(io))))));
+        self
+    }
+
+    /// Set on-connect callback that can reject connections.
+    ///
+    /// It get called once per connection before any request is read, with
+    /// the stream and peer address of the connection. Peer address is the
+    /// client address from PROXY protocol header if it is enabled. If
+    /// callback returns error, connection is closed, i.e. for banned peer
+    /// addresses or connections over a quota. Otherwise result of the call
+    /// get stored to the request's extensions.
+    ///
+    /// ```rust
+    /// use std::net::SocketAddr;
+    ///
+    /// use actix_http::{HttpService, Response};
+    /// use actix_rt::net::TcpStream;
+    /// use futures_util::future::ok;
+    ///
+    /// let srv = HttpService::build()
+    ///     .on_connect_ext(|_: &TcpStream, addr: Option<SocketAddr>| match addr {
+    ///         Some(addr) if addr.ip().is_loopback() => Ok(addr),
+    ///         Some(addr) => Err(format!("{} is not allowed", addr)),
+    ///         None => Err("unknown peer address".to_owned()),
+    ///     })
+    ///     .finish(|_| ok::<_, ()>(Response::Ok().finish()))
+    ///     .tcp();
+    /// ```
+    pub fn on_connect_ext<F, I, E>(mut self, f: F) -> Self
+    where
+        F: Fn(&T, Option<net::SocketAddr>) -> Result<I, E> + 'static,
+        I: Clone + 'static,
+        E: fmt::Display,
+    {
+        self.on_connect = Some(Rc::new(move |io, addr| match f(io, addr) {
+            Ok(data) => Some(Box::new(Data(data))),
+            Err(e) => {
+                debug!("Connection from {:?} is rejected: {}", addr, e);
+                None
+            }
+        }));
         self
     }
 
@@ -199,7 +632,17 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_read_buf_limit(self.read_buf_limit)
+        .with_write_coalesce_limit(self.write_coalesce_limit)
+        .with_date_header(self.date_header)
+        .with_head_limits(self.head_limits)
+        .with_legacy_options(self.legacy_options)
+        .with_rate_limit(self.rate_limit, self.rate_limit_close)
+        .with_bandwidth_limit(self.bandwidth_limit)
+        .with_buffer_pool(self.buffer_pool)
+        .with_load_shed(self.load_shed)
+        .with_health_monitor(self.health_monitor);
         H1Service::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
@@ -222,7 +665,18 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_read_buf_limit(self.read_buf_limit)
+        .with_write_coalesce_limit(self.write_coalesce_limit)
+        .with_date_header(self.date_header)
+        .with_head_limits(self.head_limits)
+        .with_legacy_options(self.legacy_options)
+        .with_rate_limit(self.rate_limit, self.rate_limit_close)
+        .with_bandwidth_limit(self.bandwidth_limit)
+        .with_h2_stream_limits(self.h2_streams, self.h2_peer_streams)
+        .with_h2_settings(self.h2_settings)
+        .with_load_shed(self.load_shed)
+        .with_health_monitor(self.health_monitor);
         H2Service::with_config(cfg, service.into_factory()).on_connect(self.on_connect)
     }
 
@@ -242,7 +696,21 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_read_buf_limit(self.read_buf_limit)
+        .with_write_coalesce_limit(self.write_coalesce_limit)
+        .with_date_header(self.date_header)
+        .with_head_limits(self.head_limits)
+        .with_legacy_options(self.legacy_options)
+        .with_rate_limit(self.rate_limit, self.rate_limit_close)
+        .with_bandwidth_limit(self.bandwidth_limit)
+        .with_buffer_pool(self.buffer_pool)
+        .with_h2_stream_limits(self.h2_streams, self.h2_peer_streams)
+        .with_h2_settings(self.h2_settings)
+        .with_load_shed(self.load_shed)
+        .with_health_monitor(self.health_monitor)
+        .with_h2_prior_knowledge(self.h2_prior_knowledge)
+        .with_proxy_protocol(self.proxy_protocol);
         HttpService::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
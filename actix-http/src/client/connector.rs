@@ -7,13 +7,14 @@ use actix_connect::{
     default_connector, Connect as TcpConnect, Connection as TcpConnection,
 };
 use actix_rt::net::TcpStream;
-use actix_service::{apply_fn, Service};
+use actix_service::Service;
 use actix_utils::timeout::{TimeoutError, TimeoutService};
 use http::Uri;
 
 use super::config::ConnectorConfig;
 use super::connection::Connection;
 use super::error::ConnectError;
+use super::policy::{ConnectPolicy, PolicyConnector};
 use super::pool::{ConnectionPool, Protocol};
 use super::Connect;
 
@@ -50,6 +51,7 @@ type SslConnector = ();
 pub struct Connector<T, U> {
     connector: T,
     config: ConnectorConfig,
+    policy: Option<ConnectPolicy>,
     #[allow(dead_code)]
     ssl: SslConnector,
     _t: PhantomData<U>,
@@ -72,6 +74,7 @@ impl Connector<(), ()> {
             ssl: Self::build_ssl(vec![b"h2".to_vec(), b"http/1.1".to_vec()]),
             connector: default_connector(),
             config: ConnectorConfig::default(),
+            policy: None,
             _t: PhantomData,
         }
     }
@@ -125,6 +128,7 @@ impl<T, U> Connector<T, U> {
         Connector {
             connector,
             config: self.config,
+            policy: self.policy,
             ssl: self.ssl,
             _t: PhantomData,
         }
@@ -236,6 +240,14 @@ where
         self
     }
 
+    /// Set policy for validating resolved addresses before connecting.
+    ///
+    /// By default connector does not restrict outbound connections.
+    pub fn policy(mut self, policy: ConnectPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
     /// Finish configuration process and create connector service.
     /// The Connector builder always concludes by calling `finish()` last in
     /// its combinator chain.
@@ -247,11 +259,8 @@ where
         {
             let connector = TimeoutService::new(
                 self.config.timeout,
-                apply_fn(self.connector, |msg: Connect, srv| {
-                    srv.call(TcpConnect::new(msg.uri).set_addr(msg.addr))
-                })
-                .map_err(ConnectError::from)
-                .map(|stream| (stream.into_parts().0, Protocol::Http1)),
+                PolicyConnector::new(self.connector, self.policy)
+                    .map(|stream| (stream.into_parts().0, Protocol::Http1)),
             )
             .map_err(|e| match e {
                 TimeoutError::Service(e) => e,
@@ -276,12 +285,10 @@ where
 
             let ssl_service = TimeoutService::new(
                 self.config.timeout,
-                pipeline(
-                    apply_fn(self.connector.clone(), |msg: Connect, srv| {
-                        srv.call(TcpConnect::new(msg.uri).set_addr(msg.addr))
-                    })
-                    .map_err(ConnectError::from),
-                )
+                pipeline(PolicyConnector::new(
+                    self.connector.clone(),
+                    self.policy.clone(),
+                ))
                 .and_then(match self.ssl {
                     #[cfg(feature = "openssl")]
                     SslConnector::Openssl(ssl) => service(
@@ -329,11 +336,8 @@ where
 
             let tcp_service = TimeoutService::new(
                 self.config.timeout,
-                apply_fn(self.connector, |msg: Connect, srv| {
-                    srv.call(TcpConnect::new(msg.uri).set_addr(msg.addr))
-                })
-                .map_err(ConnectError::from)
-                .map(|stream| (stream.into_parts().0, Protocol::Http1)),
+                PolicyConnector::new(self.connector, self.policy)
+                    .map(|stream| (stream.into_parts().0, Protocol::Http1)),
             )
             .map_err(|e| match e {
                 TimeoutError::Service(e) => e,
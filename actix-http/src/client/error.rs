@@ -1,4 +1,5 @@
 use std::io;
+use std::net::SocketAddr;
 
 use actix_connect::resolver::ResolveError;
 use derive_more::{Display, From};
@@ -8,6 +9,7 @@ use actix_connect::ssl::openssl::{HandshakeError, SslError};
 
 use crate::error::{Error, ParseError, ResponseError};
 use crate::http::{Error as HttpError, StatusCode};
+use crate::signature::SignatureError;
 
 /// A set of errors that can occur while connecting to an HTTP host
 #[derive(Debug, Display, From)]
@@ -53,6 +55,11 @@ pub enum ConnectError {
     /// Connection io error
     #[display(fmt = "{}", _0)]
     Io(io::Error),
+
+    /// Connection is denied by connect policy
+    #[display(fmt = "Connection to {} is denied by connect policy", _0)]
+    #[from(ignore)]
+    Denied(SocketAddr),
 }
 
 impl std::error::Error for ConnectError {}
@@ -117,6 +124,9 @@ pub enum SendRequestError {
     TunnelNotSupported,
     /// Error sending request body
     Body(Error),
+    /// Error signing request
+    #[display(fmt = "{}", _0)]
+    Signature(SignatureError),
 }
 
 impl std::error::Error for SendRequestError {}
@@ -143,6 +153,9 @@ pub enum FreezeRequestError {
     /// Http error
     #[display(fmt = "{}", _0)]
     Http(HttpError),
+    /// Error signing request
+    #[display(fmt = "{}", _0)]
+    Signature(SignatureError),
 }
 
 impl std::error::Error for FreezeRequestError {}
@@ -152,6 +165,7 @@ impl From<FreezeRequestError> for SendRequestError {
         match e {
             FreezeRequestError::Url(e) => e.into(),
             FreezeRequestError::Http(e) => e.into(),
+            FreezeRequestError::Signature(e) => e.into(),
         }
     }
 }
@@ -7,11 +7,13 @@ mod connector;
 mod error;
 mod h1proto;
 mod h2proto;
+mod policy;
 mod pool;
 
 pub use self::connection::Connection;
 pub use self::connector::Connector;
 pub use self::error::{ConnectError, FreezeRequestError, InvalidUrl, SendRequestError};
+pub use self::policy::ConnectPolicy;
 pub use self::pool::Protocol;
 
 #[derive(Clone)]
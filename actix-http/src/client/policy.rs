@@ -0,0 +1,314 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_connect::{Connect as TcpConnect, Connection as TcpConnection, Resolver};
+use actix_service::Service;
+use futures_util::future::{FutureExt, LocalBoxFuture, TryFutureExt};
+use http::Uri;
+
+use super::error::ConnectError;
+use super::Connect;
+
+/// Policy for outbound connections.
+///
+/// Policy validates resolved socket addresses before connector opens a
+/// connection, which protects services fetching user supplied urls from server
+/// side request forgery.
+///
+/// By default policy denies connections to addresses that are not publicly
+/// routable: loopback, private (RFC 1918), shared (RFC 6598), link-local,
+/// unique local, multicast, broadcast, unspecified, documentation,
+/// benchmarking and reserved ranges. Ipv6 addresses embedding an ipv4
+/// address, i.e. mapped, NAT64 or 6to4 addresses, are checked by the
+/// embedded address.
+/// [`allow`](#method.allow) callback can be used to permit specific internal
+/// hosts.
+///
+/// ```rust,ignore
+/// use actix_http::client::{ConnectPolicy, Connector};
+///
+/// let connector = Connector::new()
+///     .policy(
+///         ConnectPolicy::new()
+///             .allow_ports(&[80, 443])
+///             .allow(|host, _| host == "metadata.internal"),
+///     )
+///     .finish();
+/// ```
+#[derive(Clone)]
+pub struct ConnectPolicy {
+    ports: Option<Vec<u16>>,
+    allow: Option<Rc<dyn Fn(&str, &SocketAddr) -> bool>>,
+}
+
+impl ConnectPolicy {
+    /// Create policy that denies connections to non public addresses.
+    pub fn new() -> Self {
+        ConnectPolicy {
+            ports: None,
+            allow: None,
+        }
+    }
+
+    /// Only allow connections to the listed ports.
+    pub fn allow_ports(mut self, ports: &[u16]) -> Self {
+        self.ports = Some(ports.to_vec());
+        self
+    }
+
+    /// Set allowlist callback.
+    ///
+    /// Callback receives host name and resolved socket address, addresses
+    /// denied by default rules are allowed if callback returns `true`. Port
+    /// restrictions are applied regardless of the callback result.
+    pub fn allow<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, &SocketAddr) -> bool + 'static,
+    {
+        self.allow = Some(Rc::new(f));
+        self
+    }
+
+    /// Check if connection to the `addr` resolved for `host` is allowed.
+    pub fn is_allowed(&self, host: &str, addr: &SocketAddr) -> bool {
+        if let Some(ref ports) = self.ports {
+            if !ports.contains(&addr.port()) {
+                return false;
+            }
+        }
+
+        is_public(&addr.ip())
+            || self.allow.as_ref().map(|f| f(host, addr)).unwrap_or(false)
+    }
+}
+
+impl Default for ConnectPolicy {
+    fn default() -> Self {
+        ConnectPolicy::new()
+    }
+}
+
+impl fmt::Debug for ConnectPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectPolicy")
+            .field("ports", &self.ports)
+            .field("allow", &self.allow.is_some())
+            .finish()
+    }
+}
+
+fn is_public(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+fn is_public_v4(ip: &Ipv4Addr) -> bool {
+    let octets = ip.octets();
+    !(octets[0] == 0
+        || ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // shared address space, 100.64.0.0/10
+        || (octets[0] == 100 && (octets[1] & 0b1100_0000) == 0b0100_0000)
+        // ietf protocol assignments, 192.0.0.0/24
+        || (octets[0] == 192 && octets[1] == 0 && octets[2] == 0)
+        // benchmarking, 198.18.0.0/15
+        || (octets[0] == 198 && (octets[1] & 0xfe) == 18)
+        // reserved, 240.0.0.0/4
+        || octets[0] >= 240)
+}
+
+fn is_public_v6(ip: &Ipv6Addr) -> bool {
+    let segments = ip.segments();
+
+    // ipv4 mapped, ::ffff:0:0/96, and ipv4 compatible addresses, ::/96
+    if segments[..5].iter().all(|s| *s == 0)
+        && (segments[5] == 0xffff || segments[5] == 0)
+    {
+        return is_public_v4(&embedded_v4(segments[6], segments[7]));
+    }
+    // nat64 well-known prefix, 64:ff9b::/96
+    if segments[0] == 0x64
+        && segments[1] == 0xff9b
+        && segments[2..6].iter().all(|s| *s == 0)
+    {
+        return is_public_v4(&embedded_v4(segments[6], segments[7]));
+    }
+    // 6to4, 2002::/16
+    if segments[0] == 0x2002 {
+        return is_public_v4(&embedded_v4(segments[1], segments[2]));
+    }
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // unique local, fc00::/7
+        || (segments[0] & 0xfe00) == 0xfc00
+        // link-local, fe80::/10
+        || (segments[0] & 0xffc0) == 0xfe80
+        // documentation, 2001:db8::/32
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8)
+        // local-use nat64, 64:ff9b:1::/48
+        || (segments[0] == 0x64 && segments[1] == 0xff9b && segments[2] == 1))
+}
+
+fn embedded_v4(hi: u16, lo: u16) -> Ipv4Addr {
+    let [a, b] = hi.to_be_bytes();
+    let [c, d] = lo.to_be_bytes();
+    Ipv4Addr::new(a, b, c, d)
+}
+
+/// Tcp connect service that applies connect policy to resolved addresses.
+#[derive(Clone)]
+pub(crate) struct PolicyConnector<T> {
+    connector: T,
+    resolver: Resolver<Uri>,
+    policy: Option<ConnectPolicy>,
+}
+
+impl<T> PolicyConnector<T> {
+    pub(crate) fn new(connector: T, policy: Option<ConnectPolicy>) -> Self {
+        PolicyConnector {
+            connector,
+            resolver: Resolver::default(),
+            policy,
+        }
+    }
+}
+
+impl<T, U> Service for PolicyConnector<T>
+where
+    U: 'static,
+    T: Service<
+            Request = TcpConnect<Uri>,
+            Response = TcpConnection<Uri, U>,
+            Error = actix_connect::ConnectError,
+        > + Clone
+        + 'static,
+{
+    type Request = Connect;
+    type Response = TcpConnection<Uri, U>;
+    type Error = ConnectError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.connector.poll_ready(cx).map_err(ConnectError::from)
+    }
+
+    fn call(&mut self, msg: Connect) -> Self::Future {
+        let req = TcpConnect::new(msg.uri).set_addr(msg.addr);
+
+        let policy = if let Some(ref policy) = self.policy {
+            policy.clone()
+        } else {
+            return self
+                .connector
+                .call(req)
+                .map_err(ConnectError::from)
+                .boxed_local();
+        };
+
+        let mut resolver = self.resolver.clone();
+        let mut connector = self.connector.clone();
+        async move {
+            let mut req = resolver.call(req).await?;
+
+            let addrs: Vec<_> = req.take_addrs().collect();
+            let allowed: Vec<_> = addrs
+                .iter()
+                .filter(|addr| policy.is_allowed(req.host(), addr))
+                .cloned()
+                .collect();
+
+            if allowed.is_empty() {
+                return match addrs.into_iter().next() {
+                    Some(addr) => {
+                        debug!(
+                            "Connection to {:?} ({}) is denied by connect policy",
+                            req.host(),
+                            addr
+                        );
+                        Err(ConnectError::Denied(addr))
+                    }
+                    None => Err(ConnectError::NoRecords),
+                };
+            }
+
+            Ok(connector.call(req.set_addrs(allowed)).await?)
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_default_policy() {
+        let policy = ConnectPolicy::new();
+
+        for denied in &[
+            "127.0.0.1:80",
+            "10.1.2.3:80",
+            "172.16.0.1:80",
+            "192.168.1.1:80",
+            "169.254.169.254:80",
+            "100.64.0.1:80",
+            "0.0.0.0:80",
+            "255.255.255.255:80",
+            "[::1]:80",
+            "[::]:80",
+            "[fd00::1]:80",
+            "[fe80::1]:80",
+            "[::ffff:127.0.0.1]:80",
+            "192.0.0.8:80",
+            "198.18.0.1:80",
+            "198.19.255.1:80",
+            "240.0.0.1:80",
+            "[::10.0.0.1]:80",
+            "[64:ff9b::a9fe:a9fe]:80",
+            "[64:ff9b:1::1]:80",
+            "[2002:7f00:1::1]:80",
+            "[2002:c0a8:101::1]:80",
+        ] {
+            assert!(!policy.is_allowed("host", &addr(denied)), "{}", denied);
+        }
+
+        for allowed in &[
+            "93.184.216.34:80",
+            "100.128.0.1:443",
+            "198.20.0.1:80",
+            "[2606:4700::1]:80",
+            "[::ffff:93.184.216.34]:80",
+            "[64:ff9b::5db8:d822]:80",
+            "[2002:5db8:d822::1]:80",
+        ] {
+            assert!(policy.is_allowed("host", &addr(allowed)), "{}", allowed);
+        }
+    }
+
+    #[test]
+    fn test_ports_and_allowlist() {
+        let policy = ConnectPolicy::new()
+            .allow_ports(&[443])
+            .allow(|host, _| host == "internal");
+
+        assert!(policy.is_allowed("example.com", &addr("93.184.216.34:443")));
+        assert!(!policy.is_allowed("example.com", &addr("93.184.216.34:80")));
+        assert!(policy.is_allowed("internal", &addr("10.0.0.1:443")));
+        assert!(!policy.is_allowed("internal", &addr("10.0.0.1:22")));
+        assert!(!policy.is_allowed("other", &addr("10.0.0.1:443")));
+    }
+}
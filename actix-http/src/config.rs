@@ -1,13 +1,20 @@
-use std::cell::Cell;
-use std::fmt::Write;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::net;
 use std::rc::Rc;
 use std::time::Duration;
-use std::{fmt, net};
 
 use actix_rt::time::{delay_for, delay_until, Delay, Instant};
 use bytes::BytesMut;
 use futures_util::{future, FutureExt};
-use time::OffsetDateTime;
+
+use crate::bandwidth::{BandwidthLimit, TokenBucket};
+use crate::buffer_pool::{BufferPool, Buffers};
+use crate::h1::{HeadLimits, LegacyOptions};
+use crate::h2::H2Settings;
+use crate::header::HttpDate;
+use crate::health::HealthMonitor;
+use crate::load_shed::LoadShedPolicy;
 
 // "Sun, 06 Nov 1994 08:49:37 GMT".len()
 const DATE_VALUE_LENGTH: usize = 29;
@@ -49,6 +56,23 @@ struct Inner {
     ka_enabled: bool,
     secure: bool,
     local_addr: Option<std::net::SocketAddr>,
+    read_buf_limit: usize,
+    write_coalesce_limit: usize,
+    date_header: bool,
+    h2_prior_knowledge: bool,
+    proxy_protocol: bool,
+    rate_limit: u32,
+    rate_limit_close: bool,
+    bandwidth_limit: Option<BandwidthLimit>,
+    buffers: Option<Buffers>,
+    h2_streams: usize,
+    h2_peer_streams: usize,
+    peer_streams: RefCell<HashMap<net::IpAddr, usize>>,
+    load_shed: Option<LoadShedPolicy>,
+    health_monitor: Option<HealthMonitor>,
+    head_limits: HeadLimits,
+    legacy_options: LegacyOptions,
+    h2_settings: H2Settings,
     timer: DateService,
 }
 
@@ -91,10 +115,143 @@ impl ServiceConfig {
             client_disconnect,
             secure,
             local_addr,
+            read_buf_limit: 32_768,
+            write_coalesce_limit: 4096,
+            date_header: true,
+            h2_prior_knowledge: false,
+            proxy_protocol: false,
+            rate_limit: 0,
+            rate_limit_close: false,
+            bandwidth_limit: None,
+            buffers: None,
+            h2_streams: 0,
+            h2_peer_streams: 0,
+            peer_streams: RefCell::new(HashMap::new()),
+            load_shed: None,
+            health_monitor: None,
+            head_limits: HeadLimits::default(),
+            legacy_options: LegacyOptions::default(),
+            h2_settings: H2Settings::default(),
             timer: DateService::new(),
         }))
     }
 
+    /// Set maximum size of a single read from the connection.
+    pub(crate) fn with_read_buf_limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .read_buf_limit = limit;
+        self
+    }
+
+    /// Set size of payload chunks written without copying to write buffer.
+    pub(crate) fn with_write_coalesce_limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .write_coalesce_limit = limit;
+        self
+    }
+
+    /// Insert `Date` header to responses without one.
+    pub(crate) fn with_date_header(mut self, enabled: bool) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .date_header = enabled;
+        self
+    }
+
+    /// Accept http/2 connections with prior knowledge on plain tcp.
+    pub(crate) fn with_h2_prior_knowledge(mut self, enabled: bool) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .h2_prior_knowledge = enabled;
+        self
+    }
+
+    /// Read PROXY protocol header of accepted tcp connections.
+    pub(crate) fn with_proxy_protocol(mut self, enabled: bool) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .proxy_protocol = enabled;
+        self
+    }
+
+    /// Limit requests per second of a single connection.
+    pub(crate) fn with_rate_limit(mut self, limit: u32, close: bool) -> Self {
+        let inner = Rc::get_mut(&mut self.0).expect("Multiple copies exist");
+        inner.rate_limit = limit;
+        inner.rate_limit_close = close;
+        self
+    }
+
+    /// Limit write bandwidth of a single connection.
+    pub(crate) fn with_bandwidth_limit(mut self, limit: Option<BandwidthLimit>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .bandwidth_limit = limit;
+        self
+    }
+
+    /// Pool connection buffers.
+    pub(crate) fn with_buffer_pool(mut self, pool: Option<BufferPool>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .buffers = pool.map(Buffers::new);
+        self
+    }
+
+    /// Limit concurrently processed http/2 streams of a connection and of a peer.
+    pub(crate) fn with_h2_stream_limits(
+        mut self,
+        connection: usize,
+        peer: usize,
+    ) -> Self {
+        let inner = Rc::get_mut(&mut self.0).expect("Multiple copies exist");
+        inner.h2_streams = connection;
+        inner.h2_peer_streams = peer;
+        self
+    }
+
+    /// Reject new requests under load.
+    pub(crate) fn with_load_shed(mut self, policy: Option<LoadShedPolicy>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .load_shed = policy;
+        self
+    }
+
+    /// Monitor health of worker runtime.
+    pub(crate) fn with_health_monitor(mut self, monitor: Option<HealthMonitor>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .health_monitor = monitor;
+        self
+    }
+
+    /// Set limits of http/1 request head.
+    pub(crate) fn with_head_limits(mut self, limits: HeadLimits) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .head_limits = limits;
+        self
+    }
+
+    /// Set handling of http/1.0 clients.
+    pub(crate) fn with_legacy_options(mut self, options: LegacyOptions) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .legacy_options = options;
+        self
+    }
+
+    /// Set transport settings of http/2 connections.
+    pub(crate) fn with_h2_settings(mut self, settings: H2Settings) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Multiple copies exist")
+            .h2_settings = settings;
+        self
+    }
+
     #[inline]
     /// Returns true if connection is secure(https)
     pub fn secure(&self) -> bool {
@@ -107,6 +264,154 @@ impl ServiceConfig {
         self.0.local_addr
     }
 
+    #[inline]
+    /// Maximum size of a single read from the connection.
+    pub fn read_buf_limit(&self) -> usize {
+        self.0.read_buf_limit
+    }
+
+    #[inline]
+    /// Payload chunks of this size and larger are written to the connection
+    /// without copying to write buffer, zero means chunks are always copied.
+    pub fn write_coalesce_limit(&self) -> usize {
+        self.0.write_coalesce_limit
+    }
+
+    #[inline]
+    /// Returns true if `Date` header is inserted to responses without one.
+    pub fn date_header(&self) -> bool {
+        self.0.date_header
+    }
+
+    #[inline]
+    /// Returns true if http/2 connections with prior knowledge are accepted
+    /// on plain tcp.
+    pub fn h2_prior_knowledge(&self) -> bool {
+        self.0.h2_prior_knowledge
+    }
+
+    #[inline]
+    /// Returns true if accepted tcp connections start with PROXY protocol
+    /// header.
+    pub fn proxy_protocol(&self) -> bool {
+        self.0.proxy_protocol
+    }
+
+    #[inline]
+    /// Maximum number of requests per second of a single connection,
+    /// `0` if requests are not limited.
+    pub fn max_requests_per_second(&self) -> u32 {
+        self.0.rate_limit
+    }
+
+    #[inline]
+    /// Returns true if connections are closed once request rate is exceeded.
+    pub fn close_on_rate_limit(&self) -> bool {
+        self.0.rate_limit_close
+    }
+
+    /// Request rate limiter for a new connection.
+    pub(crate) fn rate_limiter(&self) -> Option<RateLimiter> {
+        if self.0.rate_limit != 0 {
+            Some(RateLimiter {
+                limit: self.0.rate_limit,
+                window: self.now(),
+                count: 0,
+            })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    /// Write bandwidth limit of a single connection.
+    pub fn bandwidth_limit(&self) -> Option<BandwidthLimit> {
+        self.0.bandwidth_limit
+    }
+
+    #[inline]
+    /// Pool of connection buffers.
+    pub fn buffer_pool(&self) -> Option<BufferPool> {
+        self.0.buffers.as_ref().map(Buffers::pool)
+    }
+
+    /// Free buffers of the pool.
+    pub(crate) fn buffers(&self) -> Option<&Buffers> {
+        self.0.buffers.as_ref()
+    }
+
+    /// Write bandwidth token bucket for a new connection.
+    pub(crate) fn write_bucket(&self) -> Option<Rc<RefCell<TokenBucket>>> {
+        self.0
+            .bandwidth_limit
+            .map(|limit| Rc::new(RefCell::new(TokenBucket::new(limit))))
+    }
+
+    #[inline]
+    /// Maximum number of concurrently processed streams of a http/2 connection.
+    pub fn h2_max_streams(&self) -> usize {
+        self.0.h2_streams
+    }
+
+    #[inline]
+    /// Maximum number of concurrently processed http/2 streams of a peer
+    /// address, over all connections of the worker.
+    pub fn h2_max_peer_streams(&self) -> usize {
+        self.0.h2_peer_streams
+    }
+
+    /// Count stream of the peer, returns false if the peer is over limit.
+    pub(crate) fn acquire_peer_stream(&self, ip: net::IpAddr) -> bool {
+        let mut streams = self.0.peer_streams.borrow_mut();
+        let count = streams.entry(ip).or_insert(0);
+        if *count < self.0.h2_peer_streams {
+            *count += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn release_peer_stream(&self, ip: net::IpAddr) {
+        let mut streams = self.0.peer_streams.borrow_mut();
+        if let Some(count) = streams.get_mut(&ip) {
+            *count -= 1;
+            if *count == 0 {
+                streams.remove(&ip);
+            }
+        }
+    }
+
+    #[inline]
+    /// Load shed policy of new requests.
+    pub fn load_shed(&self) -> Option<&LoadShedPolicy> {
+        self.0.load_shed.as_ref()
+    }
+
+    #[inline]
+    /// Health monitor of worker runtime.
+    pub fn health_monitor(&self) -> Option<HealthMonitor> {
+        self.0.health_monitor
+    }
+
+    #[inline]
+    /// Limits of http/1 request head.
+    pub(crate) fn head_limits(&self) -> HeadLimits {
+        self.0.head_limits
+    }
+
+    #[inline]
+    /// Handling of http/1.0 clients.
+    pub(crate) fn legacy_options(&self) -> LegacyOptions {
+        self.0.legacy_options
+    }
+
+    #[inline]
+    /// Transport settings of http/2 connections.
+    pub(crate) fn h2_settings(&self) -> H2Settings {
+        self.0.h2_settings
+    }
+
     #[inline]
     /// Keep alive duration if configured.
     pub fn keep_alive(&self) -> Option<Duration> {
@@ -194,38 +499,35 @@ impl ServiceConfig {
     }
 }
 
+/// Per connection request counter over one second windows.
+pub(crate) struct RateLimiter {
+    limit: u32,
+    window: Instant,
+    count: u32,
+}
+
+impl RateLimiter {
+    /// Count a new request, returns false if request rate is exceeded.
+    pub(crate) fn acquire(&mut self, now: Instant) -> bool {
+        if now >= self.window + Duration::from_secs(1) {
+            self.window = now;
+            self.count = 0;
+        }
+        self.count = self.count.saturating_add(1);
+        self.count <= self.limit
+    }
+}
+
 #[derive(Copy, Clone)]
 struct Date {
     bytes: [u8; DATE_VALUE_LENGTH],
-    pos: usize,
 }
 
 impl Date {
     fn new() -> Date {
-        let mut date = Date {
-            bytes: [0; DATE_VALUE_LENGTH],
-            pos: 0,
-        };
-        date.update();
-        date
-    }
-    fn update(&mut self) {
-        self.pos = 0;
-        write!(
-            self,
-            "{}",
-            OffsetDateTime::now_utc().format("%a, %d %b %Y %H:%M:%S GMT")
-        )
-        .unwrap();
-    }
-}
-
-impl fmt::Write for Date {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        let len = s.len();
-        self.bytes[self.pos..self.pos + len].copy_from_slice(s.as_bytes());
-        self.pos += len;
-        Ok(())
+        Date {
+            bytes: HttpDate::now().to_bytes(),
+        }
     }
 }
 
@@ -287,6 +589,41 @@ impl DateService {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_rate_limiter() {
+        let now = Instant::now();
+        let mut limiter = RateLimiter {
+            limit: 2,
+            window: now,
+            count: 0,
+        };
+        assert!(limiter.acquire(now));
+        assert!(limiter.acquire(now + Duration::from_millis(500)));
+        assert!(!limiter.acquire(now + Duration::from_millis(900)));
+        // next window
+        assert!(limiter.acquire(now + Duration::from_millis(1100)));
+        assert!(limiter.acquire(now + Duration::from_millis(1200)));
+        assert!(!limiter.acquire(now + Duration::from_millis(1300)));
+    }
+
+    #[test]
+    fn test_peer_streams() {
+        let config = ServiceConfig::default().with_h2_stream_limits(0, 2);
+        let ip = net::IpAddr::from([127, 0, 0, 1]);
+        let other = net::IpAddr::from([127, 0, 0, 2]);
+
+        assert!(config.acquire_peer_stream(ip));
+        assert!(config.acquire_peer_stream(ip));
+        assert!(!config.acquire_peer_stream(ip));
+        assert!(config.acquire_peer_stream(other));
+
+        config.release_peer_stream(ip);
+        assert!(config.acquire_peer_stream(ip));
+
+        config.release_peer_stream(other);
+        assert!(!config.0.peer_streams.borrow().contains_key(&other));
+    }
+
     // Test modifying the date from within the closure
     // passed to `set_date`
     #[test]
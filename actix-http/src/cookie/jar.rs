@@ -7,7 +7,7 @@ use super::delta::DeltaCookie;
 use super::Cookie;
 
 #[cfg(feature = "secure-cookies")]
-use super::secure::{Key, PrivateJar, SignedJar};
+use super::secure::{Key, KeyRing, PrivateJar, SignedJar};
 
 /// A collection of cookies that tracks its modifications.
 ///
@@ -423,6 +423,58 @@ impl CookieJar {
     pub fn signed(&mut self, key: &Key) -> SignedJar<'_> {
         SignedJar::new(self, key)
     }
+
+    /// Returns a `PrivateJar` with `self` as its parent jar using the key ring
+    /// `keys` to encrypt/decrypt cookies added/retrieved from the child jar.
+    ///
+    /// Cookies are encrypted with the primary key of the ring and can be
+    /// decrypted with any of its keys, which allows rotating keys without
+    /// invalidating existing cookies.
+    ///
+    /// This method is only available when the `secure` feature is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use actix_http::cookie::{Cookie, CookieJar, Key, KeyRing};
+    ///
+    /// let old_key = Key::generate();
+    /// let keys = KeyRing::new(Key::generate()).fallback(old_key.clone());
+    ///
+    /// let mut jar = CookieJar::new();
+    /// jar.private(&old_key).add(Cookie::new("private", "text"));
+    /// assert_eq!(jar.private_with(&keys).get("private").unwrap().value(), "text");
+    /// ```
+    #[cfg(feature = "secure-cookies")]
+    pub fn private_with(&mut self, keys: &KeyRing) -> PrivateJar<'_> {
+        PrivateJar::with_keyring(self, keys)
+    }
+
+    /// Returns a `SignedJar` with `self` as its parent jar using the key ring
+    /// `keys` to sign/verify cookies added/retrieved from the child jar.
+    ///
+    /// Cookies are signed with the primary key of the ring and can be verified
+    /// with any of its keys, which allows rotating keys without invalidating
+    /// existing cookies.
+    ///
+    /// This method is only available when the `secure` feature is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use actix_http::cookie::{Cookie, CookieJar, Key, KeyRing};
+    ///
+    /// let old_key = Key::generate();
+    /// let keys = KeyRing::new(Key::generate()).fallback(old_key.clone());
+    ///
+    /// let mut jar = CookieJar::new();
+    /// jar.signed(&old_key).add(Cookie::new("signed", "text"));
+    /// assert_eq!(jar.signed_with(&keys).get("signed").unwrap().value(), "text");
+    /// ```
+    #[cfg(feature = "secure-cookies")]
+    pub fn signed_with(&mut self, keys: &KeyRing) -> SignedJar<'_> {
+        SignedJar::with_keyring(self, keys)
+    }
 }
 
 use std::collections::hash_set::Iter as HashSetIter;
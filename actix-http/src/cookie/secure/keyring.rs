@@ -0,0 +1,108 @@
+use super::Key;
+
+/// A set of cryptographic keys used for cookie key rotation.
+///
+/// A `KeyRing` has a single _primary_ key and any number of _fallback_ keys.
+/// Cookies added through a key ring backed jar are always signed/encrypted with
+/// the primary key, while cookies retrieved from it are verified/decrypted with
+/// the primary key first and then with each fallback key, in the order they
+/// were added. This allows rolling out a new key without invalidating cookies
+/// issued with the previous one.
+///
+/// This type is only available when the `secure` feature is enabled.
+///
+/// # Example
+///
+/// ```rust
+/// use actix_http::cookie::{Cookie, CookieJar, Key, KeyRing};
+///
+/// let old_key = Key::generate();
+/// let mut jar = CookieJar::new();
+/// jar.signed(&old_key).add(Cookie::new("name", "value"));
+///
+/// // cookies signed with the old key are still accepted
+/// let keys = KeyRing::new(Key::generate()).fallback(old_key);
+/// assert_eq!(jar.signed_with(&keys).get("name").unwrap().value(), "value");
+/// ```
+#[derive(Clone)]
+pub struct KeyRing {
+    primary: Key,
+    fallbacks: Vec<Key>,
+}
+
+impl KeyRing {
+    /// Creates a new key ring with `primary` as the signing/encryption key.
+    pub fn new(primary: Key) -> KeyRing {
+        KeyRing {
+            primary,
+            fallbacks: Vec::new(),
+        }
+    }
+
+    /// Adds a fallback key that is only used to verify/decrypt cookies.
+    pub fn fallback(mut self, key: Key) -> Self {
+        self.fallbacks.push(key);
+        self
+    }
+
+    /// Returns the key used to sign/encrypt new cookies.
+    pub fn primary(&self) -> &Key {
+        &self.primary
+    }
+
+    /// Returns the keys accepted in addition to the primary key.
+    pub fn fallbacks(&self) -> &[Key] {
+        &self.fallbacks
+    }
+}
+
+impl From<Key> for KeyRing {
+    fn from(key: Key) -> KeyRing {
+        KeyRing::new(key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Key, KeyRing};
+    use crate::cookie::{Cookie, CookieJar};
+
+    #[test]
+    fn signed_rotation() {
+        let old = Key::generate();
+        let new = Key::generate();
+        let keys = KeyRing::new(new.clone()).fallback(old.clone());
+
+        let mut jar = CookieJar::new();
+        jar.signed(&old).add(Cookie::new("old", "value"));
+        jar.signed_with(&keys).add(Cookie::new("new", "value"));
+
+        assert_eq!(jar.signed_with(&keys).get("old").unwrap().value(), "value");
+        assert_eq!(jar.signed_with(&keys).get("new").unwrap().value(), "value");
+
+        // new cookies are signed with the primary key only
+        assert!(jar.signed(&new).get("new").is_some());
+        assert!(jar.signed(&old).get("new").is_none());
+
+        // keys outside of the ring are rejected
+        assert!(jar.signed_with(&KeyRing::new(new)).get("old").is_none());
+    }
+
+    #[test]
+    fn private_rotation() {
+        let old = Key::generate();
+        let new = Key::generate();
+        let keys = KeyRing::new(new.clone()).fallback(old.clone());
+
+        let mut jar = CookieJar::new();
+        jar.private(&old).add(Cookie::new("old", "value"));
+        jar.private_with(&keys).add(Cookie::new("new", "value"));
+
+        assert_eq!(jar.private_with(&keys).get("old").unwrap().value(), "value");
+        assert_eq!(jar.private_with(&keys).get("new").unwrap().value(), "value");
+
+        assert!(jar.private(&new).get("new").is_some());
+        assert!(jar.private(&old).get("new").is_none());
+        assert!(jar.private_with(&KeyRing::new(new)).get("old").is_none());
+    }
+}
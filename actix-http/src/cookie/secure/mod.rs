@@ -2,9 +2,11 @@
 #[macro_use]
 mod macros;
 mod key;
+mod keyring;
 mod private;
 mod signed;
 
 pub use self::key::*;
+pub use self::keyring::*;
 pub use self::private::*;
 pub use self::signed::*;
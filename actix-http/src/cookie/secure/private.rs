@@ -1,11 +1,11 @@
-use std::str;
+use std::{iter, str};
 
 use log::warn;
 use ring::aead::{Aad, Algorithm, Nonce, AES_256_GCM};
 use ring::aead::{LessSafeKey, UnboundKey};
 use ring::rand::{SecureRandom, SystemRandom};
 
-use super::Key;
+use super::{Key, KeyRing};
 use crate::cookie::{Cookie, CookieJar};
 
 // Keep these in sync, and keep the key len synced with the `private` docs as
@@ -26,6 +26,7 @@ pub const KEY_LEN: usize = 32;
 pub struct PrivateJar<'a> {
     parent: &'a mut CookieJar,
     key: [u8; KEY_LEN],
+    fallback_keys: Vec<[u8; KEY_LEN]>,
 }
 
 impl<'a> PrivateJar<'a> {
@@ -34,11 +35,23 @@ impl<'a> PrivateJar<'a> {
     /// `CookieJar`.
     #[doc(hidden)]
     pub fn new(parent: &'a mut CookieJar, key: &Key) -> PrivateJar<'a> {
-        let mut key_array = [0u8; KEY_LEN];
-        key_array.copy_from_slice(key.encryption());
         PrivateJar {
             parent,
-            key: key_array,
+            key: encryption_key(key),
+            fallback_keys: Vec::new(),
+        }
+    }
+
+    /// Creates a new child `PrivateJar` with parent `parent` that encrypts
+    /// cookies with the primary key of `keys` and decrypts them with any key of
+    /// the ring. This method is typically called indirectly via the
+    /// `private_with` method of `CookieJar`.
+    #[doc(hidden)]
+    pub fn with_keyring(parent: &'a mut CookieJar, keys: &KeyRing) -> PrivateJar<'a> {
+        PrivateJar {
+            parent,
+            key: encryption_key(keys.primary()),
+            fallback_keys: keys.fallbacks().iter().map(encryption_key).collect(),
         }
     }
 
@@ -47,32 +60,28 @@ impl<'a> PrivateJar<'a> {
     /// verifies and decrypts the sealed value and returns it. If there's a
     /// problem, returns an `Err` with a string describing the issue.
     fn unseal(&self, name: &str, value: &str) -> Result<String, &'static str> {
-        let mut data = base64::decode(value).map_err(|_| "bad base64 value")?;
+        let data = base64::decode(value).map_err(|_| "bad base64 value")?;
         if data.len() <= NONCE_LEN {
             return Err("length of decoded data is <= NONCE_LEN");
         }
 
-        let ad = Aad::from(name.as_bytes());
-        let key = LessSafeKey::new(
-            UnboundKey::new(&ALGO, &self.key).expect("matching key length"),
-        );
-        let (nonce, mut sealed) = data.split_at_mut(NONCE_LEN);
-        let nonce =
-            Nonce::try_assume_unique_for_key(nonce).expect("invalid length of `nonce`");
-        let unsealed = key
-            .open_in_place(nonce, ad, &mut sealed)
-            .map_err(|_| "invalid key/nonce/value: bad seal")?;
-
-        if let Ok(unsealed_utf8) = str::from_utf8(unsealed) {
-            Ok(unsealed_utf8.to_string())
-        } else {
-            warn!(
-                "Private cookie does not have utf8 content!
+        for key in iter::once(&self.key).chain(self.fallback_keys.iter()) {
+            let mut data = data.clone();
+            if let Ok(unsealed) = open_name_value(name.as_bytes(), &mut data, key) {
+                return if let Ok(unsealed_utf8) = str::from_utf8(unsealed) {
+                    Ok(unsealed_utf8.to_string())
+                } else {
+                    warn!(
+                        "Private cookie does not have utf8 content!
 It is likely the secret key used to encrypt them has been leaked.
 Please change it as soon as possible."
-            );
-            Err("bad unsealed utf8")
+                    );
+                    Err("bad unsealed utf8")
+                };
+            }
         }
+
+        Err("invalid key/nonce/value: bad seal")
     }
 
     /// Returns a reference to the `Cookie` inside this jar with the name `name`
@@ -197,6 +206,29 @@ Please change it as soon as possible."
     }
 }
 
+fn encryption_key(key: &Key) -> [u8; KEY_LEN] {
+    let mut key_array = [0u8; KEY_LEN];
+    key_array.copy_from_slice(key.encryption());
+    key_array
+}
+
+fn open_name_value<'d>(
+    name: &[u8],
+    data: &'d mut [u8],
+    key: &[u8],
+) -> Result<&'d [u8], ()> {
+    let key =
+        LessSafeKey::new(UnboundKey::new(&ALGO, key).expect("matching key length"));
+    let (nonce, sealed) = data.split_at_mut(NONCE_LEN);
+    let nonce =
+        Nonce::try_assume_unique_for_key(nonce).expect("invalid length of `nonce`");
+
+    // Cookie's name is used as associated data to prevent value swapping.
+    key.open_in_place(nonce, Aad::from(name), sealed)
+        .map(|unsealed| &*unsealed)
+        .map_err(|_| ())
+}
+
 fn encrypt_name_value(name: &[u8], value: &[u8], key: &[u8]) -> Vec<u8> {
     // Create the `SealingKey` structure.
     let unbound = UnboundKey::new(&ALGO, key).expect("matching key length");
@@ -1,6 +1,6 @@
 use ring::hmac::{self, sign, verify};
 
-use super::Key;
+use super::{Key, KeyRing};
 use crate::cookie::{Cookie, CookieJar};
 
 // Keep these in sync, and keep the key len synced with the `signed` docs as
@@ -21,6 +21,7 @@ pub const KEY_LEN: usize = 32;
 pub struct SignedJar<'a> {
     parent: &'a mut CookieJar,
     key: hmac::Key,
+    fallback_keys: Vec<hmac::Key>,
 }
 
 impl<'a> SignedJar<'a> {
@@ -32,6 +33,24 @@ impl<'a> SignedJar<'a> {
         SignedJar {
             parent,
             key: hmac::Key::new(HMAC_DIGEST, key.signing()),
+            fallback_keys: Vec::new(),
+        }
+    }
+
+    /// Creates a new child `SignedJar` with parent `parent` that signs cookies
+    /// with the primary key of `keys` and verifies them with any key of the ring.
+    /// This method is typically called indirectly via the `signed_with` method
+    /// of `CookieJar`.
+    #[doc(hidden)]
+    pub fn with_keyring(parent: &'a mut CookieJar, keys: &KeyRing) -> SignedJar<'a> {
+        SignedJar {
+            parent,
+            key: hmac::Key::new(HMAC_DIGEST, keys.primary().signing()),
+            fallback_keys: keys
+                .fallbacks()
+                .iter()
+                .map(|key| hmac::Key::new(HMAC_DIGEST, key.signing()))
+                .collect(),
         }
     }
 
@@ -46,9 +65,17 @@ impl<'a> SignedJar<'a> {
         let (digest_str, value) = cookie_value.split_at(BASE64_DIGEST_LEN);
         let sig = base64::decode(digest_str).map_err(|_| "bad base64 digest")?;
 
-        verify(&self.key, value.as_bytes(), &sig)
-            .map(|_| value.to_string())
-            .map_err(|_| "value did not verify")
+        let verified = verify(&self.key, value.as_bytes(), &sig).is_ok()
+            || self
+                .fallback_keys
+                .iter()
+                .any(|key| verify(key, value.as_bytes(), &sig).is_ok());
+
+        if verified {
+            Ok(value.to_string())
+        } else {
+            Err("value did not verify")
+        }
     }
 
     /// Returns a reference to the `Cookie` inside this jar with the name `name`
@@ -0,0 +1,247 @@
+//! Shared dictionary compression (Compression Dictionary Transport)
+use std::{fmt, io, ptr, slice};
+
+use brotli_sys::{
+    BrotliEncoderCompressStream, BrotliEncoderCreateInstance,
+    BrotliEncoderDestroyInstance, BrotliEncoderHasMoreOutput, BrotliEncoderIsFinished,
+    BrotliEncoderOperation, BrotliEncoderSetCustomDictionary, BrotliEncoderSetParameter,
+    BrotliEncoderState, BrotliEncoderTakeOutput, BROTLI_OPERATION_FINISH,
+    BROTLI_OPERATION_PROCESS, BROTLI_PARAM_LGWIN, BROTLI_PARAM_QUALITY,
+};
+use bytes::{Bytes, BytesMut};
+use ring::digest;
+
+/// Content encoding token for dictionary-compressed brotli.
+pub const DCB: &str = "dcb";
+
+/// Magic signature of dictionary-compressed brotli stream.
+const DCB_MAGIC: [u8; 4] = [0xff, 0x44, 0x43, 0x42];
+
+const MIN_WINDOW: u32 = 22;
+const MAX_WINDOW: u32 = 24;
+
+/// Preregistered compression dictionary.
+///
+/// Dictionary is identified by sha-256 hash of its content, client
+/// advertises available dictionary with `Available-Dictionary` header.
+#[derive(Clone)]
+pub struct Dictionary {
+    id: Option<String>,
+    data: Bytes,
+    hash: [u8; 32],
+}
+
+impl Dictionary {
+    /// Create dictionary from content.
+    ///
+    /// Dictionaries larger than 16Mb are not fully used.
+    pub fn new<T: Into<Bytes>>(data: T) -> Self {
+        let data = data.into();
+        let mut hash = [0; 32];
+        hash.copy_from_slice(digest::digest(&digest::SHA256, &data).as_ref());
+
+        Dictionary {
+            id: None,
+            data,
+            hash,
+        }
+    }
+
+    /// Set dictionary id.
+    ///
+    /// Id is sent by client with `Dictionary-ID` header, if id is set
+    /// dictionary is used only for requests with the same id.
+    pub fn id<T: Into<String>>(mut self, id: T) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Dictionary id.
+    pub fn get_id(&self) -> Option<&str> {
+        self.id.as_ref().map(String::as_str)
+    }
+
+    /// Dictionary content.
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Sha-256 hash of dictionary content.
+    pub fn hash(&self) -> &[u8; 32] {
+        &self.hash
+    }
+}
+
+impl fmt::Debug for Dictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dictionary")
+            .field("id", &self.id)
+            .field("size", &self.data.len())
+            .finish()
+    }
+}
+
+/// Brotli encoder with custom dictionary.
+pub(super) struct DictionaryEncoder {
+    state: *mut BrotliEncoderState,
+    // encoder references dictionary content
+    _data: Bytes,
+    buf: BytesMut,
+}
+
+// encoder state is not shared, it is only moved between threads
+unsafe impl Send for DictionaryEncoder {}
+
+impl DictionaryEncoder {
+    pub(super) fn new(dict: &Dictionary, quality: u32) -> Self {
+        let mut window = MIN_WINDOW;
+        while window < MAX_WINDOW && (1 << window) - 16 < dict.data.len() {
+            window += 1;
+        }
+
+        let state = unsafe {
+            let state = BrotliEncoderCreateInstance(None, None, ptr::null_mut());
+            assert!(!state.is_null(), "Can not allocate brotli encoder");
+            BrotliEncoderSetParameter(state, BROTLI_PARAM_QUALITY, quality);
+            BrotliEncoderSetParameter(state, BROTLI_PARAM_LGWIN, window);
+            BrotliEncoderSetCustomDictionary(state, dict.data.len(), dict.data.as_ptr());
+            state
+        };
+
+        let mut buf = BytesMut::with_capacity(8192);
+        buf.extend_from_slice(&DCB_MAGIC);
+        buf.extend_from_slice(&dict.hash);
+
+        DictionaryEncoder {
+            state,
+            buf,
+            _data: dict.data.clone(),
+        }
+    }
+
+    pub(super) fn take(&mut self) -> Bytes {
+        self.buf.split().freeze()
+    }
+
+    pub(super) fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.compress(BROTLI_OPERATION_PROCESS, data)
+    }
+
+    pub(super) fn finish(mut self) -> io::Result<Bytes> {
+        self.compress(BROTLI_OPERATION_FINISH, &[])?;
+        Ok(self.take())
+    }
+
+    fn compress(&mut self, op: BrotliEncoderOperation, data: &[u8]) -> io::Result<()> {
+        let mut available_in = data.len();
+        let mut next_in = data.as_ptr();
+
+        loop {
+            let mut available_out = 0;
+            let mut next_out = ptr::null_mut();
+
+            unsafe {
+                if BrotliEncoderCompressStream(
+                    self.state,
+                    op,
+                    &mut available_in,
+                    &mut next_in,
+                    &mut available_out,
+                    &mut next_out,
+                    ptr::null_mut(),
+                ) == 0
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        "brotli dictionary encoder error",
+                    ));
+                }
+
+                let mut size = 0;
+                let out = BrotliEncoderTakeOutput(self.state, &mut size);
+                if size > 0 {
+                    self.buf.extend_from_slice(slice::from_raw_parts(out, size));
+                }
+
+                if available_in == 0
+                    && BrotliEncoderHasMoreOutput(self.state) == 0
+                    && (op != BROTLI_OPERATION_FINISH
+                        || BrotliEncoderIsFinished(self.state) != 0)
+                {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DictionaryEncoder {
+    fn drop(&mut self) {
+        unsafe { BrotliEncoderDestroyInstance(self.state) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use brotli_sys::{
+        BrotliDecoderCreateInstance, BrotliDecoderDecompressStream,
+        BrotliDecoderDestroyInstance, BrotliDecoderSetCustomDictionary,
+        BrotliDecoderTakeOutput, BROTLI_DECODER_RESULT_NEEDS_MORE_OUTPUT,
+        BROTLI_DECODER_RESULT_SUCCESS,
+    };
+
+    use super::*;
+
+    fn decode(dict: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut result = Vec::new();
+        unsafe {
+            let state = BrotliDecoderCreateInstance(None, None, ptr::null_mut());
+            BrotliDecoderSetCustomDictionary(state, dict.len(), dict.as_ptr());
+
+            let mut available_in = data.len();
+            let mut next_in = data.as_ptr();
+            let res = loop {
+                let mut available_out = 0;
+                let mut next_out = ptr::null_mut();
+                let res = BrotliDecoderDecompressStream(
+                    state,
+                    &mut available_in,
+                    &mut next_in,
+                    &mut available_out,
+                    &mut next_out,
+                    ptr::null_mut(),
+                );
+                let mut size = 0;
+                let out = BrotliDecoderTakeOutput(state, &mut size);
+                result.extend_from_slice(slice::from_raw_parts(out, size));
+                if res != BROTLI_DECODER_RESULT_NEEDS_MORE_OUTPUT {
+                    break res;
+                }
+            };
+            BrotliDecoderDestroyInstance(state);
+            assert_eq!(res, BROTLI_DECODER_RESULT_SUCCESS);
+        }
+        result
+    }
+
+    #[test]
+    fn test_dictionary_encoder() {
+        let content = "function app() { return 'actix-web dictionary'; }\n".repeat(10);
+        let dict = Dictionary::new(content.clone()).id("v1");
+        assert_eq!(dict.get_id(), Some("v1"));
+        assert_eq!(dict.hash(), Dictionary::new(content.clone()).hash());
+
+        let mut enc = DictionaryEncoder::new(&dict, 3);
+        enc.write(content.as_bytes()).unwrap();
+        enc.write(b"// v2").unwrap();
+        let data = enc.finish().unwrap();
+
+        assert_eq!(&data[..4], &DCB_MAGIC);
+        assert_eq!(&data[4..36], dict.hash());
+        // content is encoded with references to dictionary
+        assert!(data.len() < 36 + 32);
+
+        let decoded = decode(content.as_bytes(), &data[36..]);
+        assert_eq!(decoded, format!("{}// v2", content).as_bytes());
+    }
+}
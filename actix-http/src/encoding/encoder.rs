@@ -1,23 +1,29 @@
 //! Stream encoder
+use std::cmp;
 use std::future::Future;
 use std::io::{self, Write};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use actix_threadpool::{run, CpuFuture};
+use actix_threadpool::CpuFuture;
 use brotli2::write::BrotliEncoder;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use flate2::write::{GzEncoder, ZlibEncoder};
 use futures_core::ready;
 use pin_project::{pin_project, project};
 
 use crate::body::{Body, BodySize, MessageBody, ResponseBody};
 use crate::http::header::{ContentEncoding, CONTENT_ENCODING};
-use crate::http::{HeaderValue, StatusCode};
-use crate::{Error, ResponseHead};
+use crate::http::{HeaderMap, HeaderValue, StatusCode};
+use crate::{run_blocking, Error, ResponseHead};
 
+#[cfg(feature = "compress-dictionary")]
+use super::dictionary::{Dictionary, DictionaryEncoder, DCB};
 use super::Writer;
 
+#[cfg(feature = "compress-zstd")]
+type ZstdEncoder = zstd::stream::write::Encoder<Writer>;
+
 const INPLACE: usize = 1024;
 
 #[pin_project]
@@ -26,7 +32,73 @@ pub struct Encoder<B> {
     #[pin]
     body: EncoderBody<B>,
     encoder: Option<ContentEncoder>,
-    fut: Option<CpuFuture<ContentEncoder, io::Error>>,
+    fut: Option<CpuFuture<(ContentEncoder, Option<Tee>), io::Error>>,
+    tee: Option<Tee>,
+    on_variants: Option<Box<dyn FnOnce(Variants)>>,
+}
+
+/// Compression levels of content encodings.
+///
+/// Levels are clamped to the range supported by the encoding: `0-9` for
+/// `gzip` and `deflate`, `0-11` for `br` and `1-22` for `zstd`, level `0` of
+/// `zstd` is its default level. By default fast levels are used, `1` for
+/// `gzip` and `deflate`, `3` for `br` and `zstd`.
+///
+/// ```rust
+/// use actix_http::encoding::CompressionLevels;
+/// use actix_http::http::ContentEncoding;
+///
+/// let levels = CompressionLevels::default()
+///     .level(ContentEncoding::Gzip, 6)
+///     .level(ContentEncoding::Br, 5);
+/// assert_eq!(levels.get(ContentEncoding::Gzip), Some(6));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionLevels {
+    gzip: u32,
+    deflate: u32,
+    br: u32,
+    zstd: u32,
+}
+
+impl Default for CompressionLevels {
+    fn default() -> Self {
+        CompressionLevels {
+            gzip: 1,
+            deflate: 1,
+            br: 3,
+            zstd: 3,
+        }
+    }
+}
+
+impl CompressionLevels {
+    /// Set compression level of the `encoding`.
+    ///
+    /// Encodings without compression levels are ignored.
+    pub fn level(mut self, encoding: ContentEncoding, level: u32) -> Self {
+        match encoding {
+            ContentEncoding::Gzip => self.gzip = cmp::min(level, 9),
+            ContentEncoding::Deflate => self.deflate = cmp::min(level, 9),
+            ContentEncoding::Br => self.br = cmp::min(level, 11),
+            ContentEncoding::Zstd => {
+                self.zstd = if level == 0 { 3 } else { cmp::min(level, 22) }
+            }
+            _ => (),
+        }
+        self
+    }
+
+    /// Compression level of the `encoding`.
+    pub fn get(&self, encoding: ContentEncoding) -> Option<u32> {
+        match encoding {
+            ContentEncoding::Gzip => Some(self.gzip),
+            ContentEncoding::Deflate => Some(self.deflate),
+            ContentEncoding::Br => Some(self.br),
+            ContentEncoding::Zstd => Some(self.zstd),
+            _ => None,
+        }
+    }
 }
 
 impl<B: MessageBody> Encoder<B> {
@@ -34,6 +106,21 @@ impl<B: MessageBody> Encoder<B> {
         encoding: ContentEncoding,
         head: &mut ResponseHead,
         body: ResponseBody<B>,
+    ) -> ResponseBody<Encoder<B>> {
+        Encoder::response_with_levels(
+            encoding,
+            &CompressionLevels::default(),
+            head,
+            body,
+        )
+    }
+
+    /// Encode response body with `encoding` compressed at configured level.
+    pub fn response_with_levels(
+        encoding: ContentEncoding,
+        levels: &CompressionLevels,
+        head: &mut ResponseHead,
+        body: ResponseBody<B>,
     ) -> ResponseBody<Encoder<B>> {
         let can_encode = !(head.headers().contains_key(&CONTENT_ENCODING)
             || head.status == StatusCode::SWITCHING_PROTOCOLS
@@ -59,7 +146,7 @@ impl<B: MessageBody> Encoder<B> {
 
         if can_encode {
             // Modify response body only if encoder is not None
-            if let Some(enc) = ContentEncoder::encoder(encoding) {
+            if let Some(enc) = ContentEncoder::encoder(encoding, levels) {
                 update_head(encoding, head);
                 head.no_chunking(false);
                 return ResponseBody::Body(Encoder {
@@ -67,6 +154,8 @@ impl<B: MessageBody> Encoder<B> {
                     eof: false,
                     fut: None,
                     encoder: Some(enc),
+                    tee: None,
+                    on_variants: None,
                 });
             }
         }
@@ -75,6 +164,95 @@ impl<B: MessageBody> Encoder<B> {
             eof: false,
             fut: None,
             encoder: None,
+            tee: None,
+            on_variants: None,
+        })
+    }
+
+    /// Encode response body with `encoding` and, in the same pass, encode
+    /// it with every encoding of `encodings`.
+    ///
+    /// Once response body is complete, `on_complete` is called with all
+    /// encoded variants, so a cache could store them before other clients
+    /// request them. Variants are not produced if response is already
+    /// encoded, body is empty or larger than `limit`, or body stream fails.
+    pub fn response_variants<F>(
+        encoding: ContentEncoding,
+        encodings: &[ContentEncoding],
+        levels: &CompressionLevels,
+        limit: usize,
+        head: &mut ResponseHead,
+        body: ResponseBody<B>,
+        on_complete: F,
+    ) -> ResponseBody<Encoder<B>>
+    where
+        F: FnOnce(Variants) + 'static,
+    {
+        let can_prime = !(head.headers().contains_key(&CONTENT_ENCODING)
+            || head.status == StatusCode::SWITCHING_PROTOCOLS
+            || head.status == StatusCode::NO_CONTENT);
+
+        let body = Encoder::response_with_levels(encoding, levels, head, body);
+        if !can_prime {
+            return body;
+        }
+
+        match body {
+            ResponseBody::Body(mut enc) => {
+                enc.tee = Some(Tee::new(encodings, levels, limit));
+                enc.on_variants = Some(Box::new(on_complete));
+                ResponseBody::Body(enc)
+            }
+            // identity encoded bytes, variants are ready right away
+            ResponseBody::Other(Body::Bytes(buf)) => {
+                let mut tee = Tee::new(encodings, levels, limit);
+                tee.write(&buf);
+                if let Some(variants) = tee.finish() {
+                    on_complete(variants);
+                }
+                ResponseBody::Other(Body::Bytes(buf))
+            }
+            body => body,
+        }
+    }
+
+    /// Encode response body with `dcb` encoding, brotli compression with
+    /// shared dictionary.
+    #[cfg(feature = "compress-dictionary")]
+    pub fn dictionary_response(
+        dict: &Dictionary,
+        head: &mut ResponseHead,
+        body: ResponseBody<B>,
+    ) -> ResponseBody<Encoder<B>> {
+        if head.headers().contains_key(&CONTENT_ENCODING)
+            || head.status == StatusCode::SWITCHING_PROTOCOLS
+            || head.status == StatusCode::NO_CONTENT
+        {
+            return Encoder::response(ContentEncoding::Identity, head, body);
+        }
+
+        let body = match body {
+            ResponseBody::Other(b) => match b {
+                Body::None => return ResponseBody::Other(Body::None),
+                Body::Empty => return ResponseBody::Other(Body::Empty),
+                Body::Bytes(buf) => EncoderBody::Bytes(buf),
+                Body::Message(stream) => EncoderBody::BoxedStream(stream),
+            },
+            ResponseBody::Body(stream) => EncoderBody::Stream(stream),
+        };
+
+        head.headers_mut()
+            .insert(CONTENT_ENCODING, HeaderValue::from_static(DCB));
+        head.no_chunking(false);
+        ResponseBody::Body(Encoder {
+            body,
+            eof: false,
+            fut: None,
+            encoder: Some(ContentEncoder::Dictionary(Box::new(
+                DictionaryEncoder::new(dict, 3),
+            ))),
+            tee: None,
+            on_variants: None,
         })
     }
 }
@@ -113,6 +291,16 @@ impl<B: MessageBody> MessageBody for EncoderBody<B> {
             EncoderBody::BoxedStream(ref mut b) => Pin::new(b.as_mut()).poll_next(cx),
         }
     }
+
+    #[project]
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        #[project]
+        match self.project() {
+            EncoderBody::Bytes(_) => None,
+            EncoderBody::Stream(b) => b.take_trailers(),
+            EncoderBody::BoxedStream(ref mut b) => Pin::new(b.as_mut()).take_trailers(),
+        }
+    }
 }
 
 impl<B: MessageBody> MessageBody for Encoder<B> {
@@ -135,12 +323,13 @@ impl<B: MessageBody> MessageBody for Encoder<B> {
             }
 
             if let Some(ref mut fut) = this.fut {
-                let mut encoder = match ready!(Pin::new(fut).poll(cx)) {
+                let (mut encoder, tee) = match ready!(Pin::new(fut).poll(cx)) {
                     Ok(item) => item,
                     Err(e) => return Poll::Ready(Some(Err(e.into()))),
                 };
                 let chunk = encoder.take();
                 *this.encoder = Some(encoder);
+                *this.tee = tee;
                 this.fut.take();
                 if !chunk.is_empty() {
                     return Poll::Ready(Some(Ok(chunk)));
@@ -153,6 +342,9 @@ impl<B: MessageBody> MessageBody for Encoder<B> {
                 Poll::Ready(Some(Ok(chunk))) => {
                     if let Some(mut encoder) = this.encoder.take() {
                         if chunk.len() < INPLACE {
+                            if let Some(ref mut tee) = this.tee {
+                                tee.write(&chunk);
+                            }
                             encoder.write(&chunk)?;
                             let chunk = encoder.take();
                             *this.encoder = Some(encoder);
@@ -160,16 +352,29 @@ impl<B: MessageBody> MessageBody for Encoder<B> {
                                 return Poll::Ready(Some(Ok(chunk)));
                             }
                         } else {
-                            *this.fut = Some(run(move || {
+                            let mut tee = this.tee.take();
+                            *this.fut = Some(run_blocking(move || {
+                                if let Some(ref mut tee) = tee {
+                                    tee.write(&chunk);
+                                }
                                 encoder.write(&chunk)?;
-                                Ok(encoder)
+                                Ok((encoder, tee))
                             }));
                         }
                     } else {
+                        if let Some(ref mut tee) = this.tee {
+                            tee.write(&chunk);
+                        }
                         return Poll::Ready(Some(Ok(chunk)));
                     }
                 }
                 Poll::Ready(None) => {
+                    if let Some(variants) = this.tee.take().and_then(Tee::finish) {
+                        if let Some(on_complete) = this.on_variants.take() {
+                            on_complete(variants);
+                        }
+                    }
+
                     if let Some(encoder) = this.encoder.take() {
                         let chunk = encoder.finish()?;
                         if chunk.is_empty() {
@@ -186,6 +391,114 @@ impl<B: MessageBody> MessageBody for Encoder<B> {
             }
         }
     }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        self.project().body.take_trailers()
+    }
+}
+
+/// Encoded variants of a response body.
+#[derive(Debug, Clone)]
+pub struct Variants {
+    identity: Bytes,
+    encoded: Vec<(ContentEncoding, Bytes)>,
+}
+
+impl Variants {
+    /// Response body without content coding.
+    pub fn identity(&self) -> &Bytes {
+        &self.identity
+    }
+
+    /// Response body encoded with `encoding`.
+    pub fn get(&self, encoding: ContentEncoding) -> Option<&Bytes> {
+        if encoding == ContentEncoding::Identity {
+            return Some(&self.identity);
+        }
+        self.encoded
+            .iter()
+            .find(|(enc, _)| *enc == encoding)
+            .map(|(_, body)| body)
+    }
+
+    /// Iterate over encoded variants, `identity` variant is not included.
+    pub fn iter(&self) -> impl Iterator<Item = (ContentEncoding, &Bytes)> {
+        self.encoded.iter().map(|(enc, body)| (*enc, body))
+    }
+}
+
+/// Encoders that receive a copy of the response body.
+struct Tee {
+    identity: BytesMut,
+    encoders: Vec<(ContentEncoding, ContentEncoder)>,
+    limit: usize,
+    failed: bool,
+}
+
+impl Tee {
+    fn new(
+        encodings: &[ContentEncoding],
+        levels: &CompressionLevels,
+        limit: usize,
+    ) -> Tee {
+        let mut encoders: Vec<(ContentEncoding, ContentEncoder)> = Vec::new();
+        for enc in encodings {
+            if encoders.iter().all(|(e, _)| e != enc) {
+                if let Some(encoder) = ContentEncoder::encoder(*enc, levels) {
+                    encoders.push((*enc, encoder));
+                }
+            }
+        }
+
+        Tee {
+            identity: BytesMut::new(),
+            encoders,
+            limit,
+            failed: false,
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        if self.failed {
+            return;
+        }
+        if self.identity.len() + data.len() > self.limit {
+            trace!("Response body is too large for encoded variants");
+            self.fail();
+            return;
+        }
+
+        self.identity.extend_from_slice(data);
+        for (_, encoder) in self.encoders.iter_mut() {
+            if encoder.write(data).is_err() {
+                self.failed = true;
+            }
+        }
+        if self.failed {
+            self.fail();
+        }
+    }
+
+    fn fail(&mut self) {
+        self.failed = true;
+        self.identity = BytesMut::new();
+        self.encoders.clear();
+    }
+
+    fn finish(self) -> Option<Variants> {
+        if self.failed || self.identity.is_empty() {
+            return None;
+        }
+
+        let mut encoded = Vec::with_capacity(self.encoders.len());
+        for (enc, encoder) in self.encoders {
+            encoded.push((enc, encoder.finish().ok()?));
+        }
+        Some(Variants {
+            identity: self.identity.freeze(),
+            encoded,
+        })
+    }
 }
 
 fn update_head(encoding: ContentEncoding, head: &mut ResponseHead) {
@@ -199,22 +512,31 @@ enum ContentEncoder {
     Deflate(ZlibEncoder<Writer>),
     Gzip(GzEncoder<Writer>),
     Br(BrotliEncoder<Writer>),
+    #[cfg(feature = "compress-zstd")]
+    Zstd(Box<ZstdEncoder>),
+    #[cfg(feature = "compress-dictionary")]
+    Dictionary(Box<DictionaryEncoder>),
 }
 
 impl ContentEncoder {
-    fn encoder(encoding: ContentEncoding) -> Option<Self> {
+    fn encoder(encoding: ContentEncoding, levels: &CompressionLevels) -> Option<Self> {
         match encoding {
             ContentEncoding::Deflate => Some(ContentEncoder::Deflate(ZlibEncoder::new(
                 Writer::new(),
-                flate2::Compression::fast(),
+                flate2::Compression::new(levels.deflate),
             ))),
             ContentEncoding::Gzip => Some(ContentEncoder::Gzip(GzEncoder::new(
                 Writer::new(),
-                flate2::Compression::fast(),
+                flate2::Compression::new(levels.gzip),
             ))),
-            ContentEncoding::Br => {
-                Some(ContentEncoder::Br(BrotliEncoder::new(Writer::new(), 3)))
-            }
+            ContentEncoding::Br => Some(ContentEncoder::Br(BrotliEncoder::new(
+                Writer::new(),
+                levels.br,
+            ))),
+            #[cfg(feature = "compress-zstd")]
+            ContentEncoding::Zstd => ZstdEncoder::new(Writer::new(), levels.zstd as i32)
+                .ok()
+                .map(|encoder| ContentEncoder::Zstd(Box::new(encoder))),
             _ => None,
         }
     }
@@ -225,6 +547,10 @@ impl ContentEncoder {
             ContentEncoder::Br(ref mut encoder) => encoder.get_mut().take(),
             ContentEncoder::Deflate(ref mut encoder) => encoder.get_mut().take(),
             ContentEncoder::Gzip(ref mut encoder) => encoder.get_mut().take(),
+            #[cfg(feature = "compress-zstd")]
+            ContentEncoder::Zstd(ref mut encoder) => encoder.get_mut().take(),
+            #[cfg(feature = "compress-dictionary")]
+            ContentEncoder::Dictionary(ref mut encoder) => encoder.take(),
         }
     }
 
@@ -242,6 +568,13 @@ impl ContentEncoder {
                 Ok(writer) => Ok(writer.buf.freeze()),
                 Err(err) => Err(err),
             },
+            #[cfg(feature = "compress-zstd")]
+            ContentEncoder::Zstd(encoder) => match encoder.finish() {
+                Ok(writer) => Ok(writer.buf.freeze()),
+                Err(err) => Err(err),
+            },
+            #[cfg(feature = "compress-dictionary")]
+            ContentEncoder::Dictionary(encoder) => encoder.finish(),
         }
     }
 
@@ -268,6 +601,22 @@ impl ContentEncoder {
                     Err(err)
                 }
             },
+            #[cfg(feature = "compress-zstd")]
+            ContentEncoder::Zstd(ref mut encoder) => match encoder.write_all(data) {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    trace!("Error encoding zstd encoding: {}", err);
+                    Err(err)
+                }
+            },
+            #[cfg(feature = "compress-dictionary")]
+            ContentEncoder::Dictionary(ref mut encoder) => match encoder.write(data) {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    trace!("Error encoding dcb encoding: {}", err);
+                    Err(err)
+                }
+            },
         }
     }
 }
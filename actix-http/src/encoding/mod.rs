@@ -4,10 +4,14 @@ use std::io;
 use bytes::{Bytes, BytesMut};
 
 mod decoder;
+#[cfg(feature = "compress-dictionary")]
+mod dictionary;
 mod encoder;
 
 pub use self::decoder::Decoder;
-pub use self::encoder::Encoder;
+#[cfg(feature = "compress-dictionary")]
+pub use self::dictionary::{Dictionary, DCB};
+pub use self::encoder::{CompressionLevels, Encoder, Variants};
 
 pub(self) struct Writer {
     buf: BytesMut,
@@ -243,6 +243,12 @@ pub enum ParseError {
     /// A message head is too large to be reasonable.
     #[display(fmt = "Message head is too large")]
     TooLarge,
+    /// Request header fields exceed configured count or size limit.
+    #[display(fmt = "Request header fields are too large")]
+    HeaderTooLarge,
+    /// Request line exceeds configured length limit.
+    #[display(fmt = "Request line is too long")]
+    UriTooLong,
     /// A message reached EOF, but is not complete.
     #[display(fmt = "Message is incomplete")]
     Incomplete,
@@ -265,7 +271,11 @@ pub enum ParseError {
 /// Return `BadRequest` for `ParseError`
 impl ResponseError for ParseError {
     fn status_code(&self) -> StatusCode {
-        StatusCode::BAD_REQUEST
+        match *self {
+            ParseError::HeaderTooLarge => StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            ParseError::UriTooLong => StatusCode::URI_TOO_LONG,
+            _ => StatusCode::BAD_REQUEST,
+        }
     }
 }
 
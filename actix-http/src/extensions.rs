@@ -59,6 +59,19 @@ impl Extensions {
     pub fn clear(&mut self) {
         self.map.clear();
     }
+
+    /// Check if container is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Move all extensions of `other` into this `Extensions`.
+    ///
+    /// Extensions of the same type are replaced.
+    pub fn extend(&mut self, other: Extensions) {
+        self.map.extend(other.map);
+    }
 }
 
 impl fmt::Debug for Extensions {
@@ -70,7 +83,24 @@ impl fmt::Debug for Extensions {
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
+    #[test]
+    fn test_extend() {
+        let mut map = Extensions::new();
+        map.insert::<i8>(1);
+        map.insert::<u8>(2);
+
+        let mut other = Extensions::new();
+        assert!(other.is_empty());
+        other.insert::<u8>(3);
+        other.insert::<bool>(true);
+
+        map.extend(other);
+        assert_eq!(map.get::<i8>(), Some(&1));
+        assert_eq!(map.get::<u8>(), Some(&3));
+        assert_eq!(map.get::<bool>(), Some(&true));
+    }
+
     #[test]
     fn test_remove() {
         let mut map = Extensions::new();
@@ -1,9 +1,10 @@
+use std::collections::VecDeque;
 use std::{fmt, io};
 
 use actix_codec::{Decoder, Encoder};
 use bitflags::bitflags;
-use bytes::BytesMut;
-use http::{Method, Version};
+use bytes::{Bytes, BytesMut};
+use http::{Method, StatusCode, Version};
 
 use super::decoder::{PayloadDecoder, PayloadItem, PayloadType};
 use super::{decoder, encoder};
@@ -11,6 +12,7 @@ use super::{Message, MessageType};
 use crate::body::BodySize;
 use crate::config::ServiceConfig;
 use crate::error::ParseError;
+use crate::header::HeaderMap;
 use crate::message::ConnectionType;
 use crate::request::Request;
 use crate::response::Response;
@@ -23,6 +25,29 @@ bitflags! {
     }
 }
 
+/// Handling of http/1.0 clients
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LegacyOptions {
+    /// Honor `Connection: keep-alive` of http/1.0 requests
+    pub(crate) keep_alive: bool,
+    /// Use chunked encoding for bodies of unknown length, otherwise body is
+    /// delimited by closing the connection
+    pub(crate) chunked: bool,
+    /// Send `Content-Length: 0` for empty bodies, otherwise body is delimited
+    /// by closing the connection
+    pub(crate) empty_length: bool,
+}
+
+impl Default for LegacyOptions {
+    fn default() -> Self {
+        LegacyOptions {
+            keep_alive: true,
+            chunked: true,
+            empty_length: true,
+        }
+    }
+}
+
 /// HTTP/1 Codec
 pub struct Codec {
     config: ServiceConfig,
@@ -59,9 +84,9 @@ impl Codec {
             Flags::empty()
         };
         Codec {
+            decoder: decoder::MessageDecoder::new(config.head_limits()),
             config,
             flags,
-            decoder: decoder::MessageDecoder::default(),
             payload: None,
             version: Version::HTTP_11,
             ctype: ConnectionType::Close,
@@ -103,6 +128,31 @@ impl Codec {
     pub fn config(&self) -> &ServiceConfig {
         &self.config
     }
+
+    /// Encode end of payload with trailer headers.
+    ///
+    /// Trailers are sent only if payload uses chunked transfer encoding.
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        dst: &mut BytesMut,
+    ) -> io::Result<()> {
+        self.encoder.encode_trailers(trailers, dst)
+    }
+
+    /// Encode payload chunk without copying it to the buffer.
+    ///
+    /// Buffered data and the chunk are moved to the queue, queue has to be
+    /// written before the buffer.
+    pub(crate) fn encode_vectored(
+        &mut self,
+        chunk: Bytes,
+        dst: &mut BytesMut,
+        queue: &mut VecDeque<Bytes>,
+    ) -> io::Result<()> {
+        self.encoder.encode_chunk_vectored(chunk, dst, queue)?;
+        Ok(())
+    }
 }
 
 impl Decoder for Codec {
@@ -125,7 +175,9 @@ impl Decoder for Codec {
             self.version = head.version;
             self.ctype = head.connection_type();
             if self.ctype == ConnectionType::KeepAlive
-                && !self.flags.contains(Flags::KEEPALIVE_ENABLED)
+                && (!self.flags.contains(Flags::KEEPALIVE_ENABLED)
+                    || (self.version < Version::HTTP_11
+                        && !self.config.legacy_options().keep_alive))
             {
                 self.ctype = ConnectionType::Close
             }
@@ -154,7 +206,7 @@ impl Encoder for Codec {
         dst: &mut BytesMut,
     ) -> Result<(), Self::Error> {
         match item {
-            Message::Item((mut res, length)) => {
+            Message::Item((mut res, mut length)) => {
                 // set response version
                 res.head_mut().version = self.version;
 
@@ -169,6 +221,30 @@ impl Encoder for Codec {
                     self.ctype
                 };
 
+                // http/1.0 bodies without length are delimited by closing
+                // the connection
+                if self.version < Version::HTTP_11 {
+                    let legacy = self.config.legacy_options();
+                    let close = match length {
+                        BodySize::Stream if !legacy.chunked => {
+                            res.head_mut().no_chunking(true);
+                            true
+                        }
+                        BodySize::Empty
+                            if !legacy.empty_length
+                                && res.status() != StatusCode::NO_CONTENT
+                                && res.status() != StatusCode::NOT_MODIFIED =>
+                        {
+                            length = BodySize::None;
+                            true
+                        }
+                        _ => false,
+                    };
+                    if close && self.ctype == ConnectionType::KeepAlive {
+                        self.ctype = ConnectionType::Close;
+                    }
+                }
+
                 // encode message
                 self.encoder.encode(
                     dst,
@@ -237,4 +313,55 @@ mod tests {
         assert_eq!(*req.method(), Method::POST);
         assert!(req.chunked().unwrap());
     }
+
+    fn encode_http10(codec: &mut Codec, length: BodySize) -> String {
+        let mut buf =
+            BytesMut::from("GET /test HTTP/1.0\r\nconnection: keep-alive\r\n\r\n");
+        codec.decode(&mut buf).unwrap().unwrap();
+        let res = Response::Ok().finish().drop_body();
+        codec
+            .encode(Message::Item((res, length)), &mut buf)
+            .unwrap();
+        String::from_utf8(buf.to_vec()).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_http10_legacy_options() {
+        let mut codec = Codec::default();
+        let data = encode_http10(&mut codec, BodySize::Stream);
+        assert!(data.contains("transfer-encoding: chunked\r\n"));
+        assert!(data.contains("connection: keep-alive\r\n"));
+        assert!(codec.keepalive());
+
+        let data = encode_http10(&mut codec, BodySize::Empty);
+        assert!(data.contains("content-length: 0\r\n"));
+        assert!(codec.keepalive());
+
+        let config = ServiceConfig::default().with_legacy_options(LegacyOptions {
+            keep_alive: true,
+            chunked: false,
+            empty_length: false,
+        });
+        let mut codec = Codec::new(config);
+        let data = encode_http10(&mut codec, BodySize::Stream);
+        assert!(!data.contains("transfer-encoding"));
+        assert!(!data.contains("connection: keep-alive"));
+        assert!(!codec.keepalive());
+
+        let data = encode_http10(&mut codec, BodySize::Empty);
+        assert!(!data.contains("content-length"));
+        assert!(!codec.keepalive());
+
+        let data = encode_http10(&mut codec, BodySize::Sized(4));
+        assert!(data.contains("content-length: 4\r\n"));
+        assert!(codec.keepalive());
+
+        let config = ServiceConfig::default().with_legacy_options(LegacyOptions {
+            keep_alive: false,
+            ..LegacyOptions::default()
+        });
+        let mut codec = Codec::new(config);
+        encode_http10(&mut codec, BodySize::Sized(4));
+        assert!(!codec.keepalive());
+    }
 }
@@ -18,8 +18,35 @@ use crate::request::Request;
 const MAX_BUFFER_SIZE: usize = 131_072;
 const MAX_HEADERS: usize = 96;
 
+/// Limits of incoming message head
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HeadLimits {
+    /// Maximum number of headers
+    pub(crate) max_headers: usize,
+    /// Maximum size of the message head, request line included
+    pub(crate) max_header_size: usize,
+    /// Maximum length of the request line
+    pub(crate) max_request_line: usize,
+    /// Reject requests with ambiguous message framing
+    pub(crate) strict: bool,
+}
+
+impl Default for HeadLimits {
+    fn default() -> Self {
+        HeadLimits {
+            max_headers: MAX_HEADERS,
+            max_header_size: MAX_BUFFER_SIZE,
+            max_request_line: 65_536,
+            strict: false,
+        }
+    }
+}
+
 /// Incoming message decoder
-pub(crate) struct MessageDecoder<T: MessageType>(PhantomData<T>);
+pub(crate) struct MessageDecoder<T: MessageType> {
+    limits: HeadLimits,
+    _t: PhantomData<T>,
+}
 
 #[derive(Debug)]
 /// Incoming request type
@@ -31,7 +58,16 @@ pub(crate) enum PayloadType {
 
 impl<T: MessageType> Default for MessageDecoder<T> {
     fn default() -> Self {
-        MessageDecoder(PhantomData)
+        MessageDecoder::new(HeadLimits::default())
+    }
+}
+
+impl<T: MessageType> MessageDecoder<T> {
+    pub(crate) fn new(limits: HeadLimits) -> Self {
+        MessageDecoder {
+            limits,
+            _t: PhantomData,
+        }
     }
 }
 
@@ -40,7 +76,7 @@ impl<T: MessageType> Decoder for MessageDecoder<T> {
     type Error = ParseError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        T::decode(src)
+        T::decode(src, &self.limits)
     }
 }
 
@@ -57,18 +93,24 @@ pub(crate) trait MessageType: Sized {
 
     fn headers_mut(&mut self) -> &mut HeaderMap;
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError>;
+    fn decode(
+        src: &mut BytesMut,
+        limits: &HeadLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError>;
 
     fn set_headers(
         &mut self,
         slice: &Bytes,
         raw_headers: &[HeaderIndex],
+        strict: bool,
     ) -> Result<PayloadLength, ParseError> {
         let mut ka = None;
         let mut has_upgrade = false;
         let mut expect = false;
         let mut chunked = false;
         let mut content_length = None;
+        let mut has_content_length = false;
+        let mut has_transfer_encoding = false;
 
         {
             let headers = self.headers_mut();
@@ -85,6 +127,12 @@ pub(crate) trait MessageType: Sized {
                 };
                 match name {
                     header::CONTENT_LENGTH => {
+                        if strict && has_content_length {
+                            debug!("duplicate Content-Length");
+                            return Err(ParseError::Header);
+                        }
+                        has_content_length = true;
+
                         if let Ok(s) = value.to_str() {
                             if let Ok(len) = s.parse::<u64>() {
                                 if len != 0 {
@@ -101,8 +149,17 @@ pub(crate) trait MessageType: Sized {
                     }
                     // transfer-encoding
                     header::TRANSFER_ENCODING => {
+                        if strict && has_transfer_encoding {
+                            debug!("duplicate Transfer-Encoding");
+                            return Err(ParseError::Header);
+                        }
+                        has_transfer_encoding = true;
                         if let Ok(s) = value.to_str().map(|s| s.trim()) {
                             chunked = s.eq_ignore_ascii_case("chunked");
+                            if strict && !chunked {
+                                debug!("unsupported Transfer-Encoding: {:?}", s);
+                                return Err(ParseError::Header);
+                            }
                         } else {
                             return Err(ParseError::Header);
                         }
@@ -145,6 +202,10 @@ pub(crate) trait MessageType: Sized {
                 headers.append(name, value);
             }
         }
+        if strict && has_content_length && has_transfer_encoding {
+            debug!("both Transfer-Encoding and Content-Length are set");
+            return Err(ParseError::Header);
+        }
         self.set_connection_type(ka);
         if expect {
             self.set_expect()
@@ -185,19 +246,58 @@ impl MessageType for Request {
     }
 
     #[allow(clippy::uninit_assumed_init)]
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        limits: &HeadLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
+        // request line has to end within the limit
+        let line_end = src
+            .iter()
+            .take(limits.max_request_line + 2)
+            .position(|b| *b == b'\n');
+        if line_end.is_none() && src.len() >= limits.max_request_line + 2 {
+            trace!("Request line is longer than {}", limits.max_request_line);
+            return Err(ParseError::UriTooLong);
+        }
+
         // Unsafe: we read only this data only after httparse parses headers into.
         // performance bump for pipeline benchmarks.
-        let mut headers: [HeaderIndex; MAX_HEADERS] =
+        let mut stack_headers: [HeaderIndex; MAX_HEADERS] =
             unsafe { MaybeUninit::uninit().assume_init() };
+        let mut heap_headers;
+        let headers: &mut [HeaderIndex] = if limits.max_headers <= MAX_HEADERS {
+            &mut stack_headers
+        } else {
+            heap_headers = vec![HeaderIndex::default(); limits.max_headers];
+            &mut heap_headers
+        };
 
         let (len, method, uri, ver, h_len) = {
-            let mut parsed: [httparse::Header<'_>; MAX_HEADERS] =
+            let mut stack_parsed: [httparse::Header<'_>; MAX_HEADERS] =
                 unsafe { MaybeUninit::uninit().assume_init() };
+            let mut heap_parsed;
+            let parsed: &mut [httparse::Header<'_>] =
+                if limits.max_headers <= MAX_HEADERS {
+                    &mut stack_parsed[..limits.max_headers]
+                } else {
+                    heap_parsed = vec![httparse::EMPTY_HEADER; limits.max_headers];
+                    &mut heap_parsed
+                };
 
-            let mut req = httparse::Request::new(&mut parsed);
-            match req.parse(src)? {
+            let mut req = httparse::Request::new(parsed);
+            let status = req.parse(src).map_err(|e| match e {
+                httparse::Error::TooManyHeaders => ParseError::HeaderTooLarge,
+                e => e.into(),
+            })?;
+            match status {
                 httparse::Status::Complete(len) => {
+                    if len > limits.max_header_size {
+                        return Err(ParseError::HeaderTooLarge);
+                    }
+                    if limits.strict && has_obs_fold(&src[..len]) {
+                        debug!("obsolete line folding of header value");
+                        return Err(ParseError::Header);
+                    }
                     let method = Method::from_bytes(req.method.unwrap().as_bytes())
                         .map_err(|_| ParseError::Method)?;
                     let uri = Uri::try_from(req.path.unwrap())?;
@@ -206,18 +306,28 @@ impl MessageType for Request {
                     } else {
                         Version::HTTP_10
                     };
-                    HeaderIndex::record(src, req.headers, &mut headers);
+                    HeaderIndex::record(src, req.headers, headers);
 
                     (len, method, uri, version, req.headers.len())
                 }
-                httparse::Status::Partial => return Ok(None),
+                httparse::Status::Partial => {
+                    if src.len() > limits.max_header_size {
+                        trace!("Request head is larger than {}", limits.max_header_size);
+                        return Err(ParseError::HeaderTooLarge);
+                    }
+                    return Ok(None);
+                }
             }
         };
 
         let mut msg = Request::new();
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length = msg.set_headers(
+            &src.split_to(len).freeze(),
+            &headers[..h_len],
+            limits.strict,
+        )?;
 
         // payload decoder
         let decoder = match length {
@@ -261,7 +371,10 @@ impl MessageType for ResponseHead {
     }
 
     #[allow(clippy::uninit_assumed_init)]
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        _: &HeadLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
         // Unsafe: we read only this data only after httparse parses headers into.
         // performance bump for pipeline benchmarks.
         let mut headers: [HeaderIndex; MAX_HEADERS] =
@@ -293,7 +406,8 @@ impl MessageType for ResponseHead {
         msg.version = ver;
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length =
+            msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len], false)?;
 
         // message payload
         let decoder = if let PayloadLength::Payload(pl) = length {
@@ -318,7 +432,13 @@ impl MessageType for ResponseHead {
     }
 }
 
-#[derive(Clone, Copy)]
+/// Header line of the message head starts with whitespace.
+fn has_obs_fold(head: &[u8]) -> bool {
+    head.windows(2)
+        .any(|w| w[0] == b'\n' && (w[1] == b' ' || w[1] == b'\t'))
+}
+
+#[derive(Clone, Copy, Default)]
 pub(crate) struct HeaderIndex {
     pub(crate) name: (usize, usize),
     pub(crate) value: (usize, usize),
@@ -1054,6 +1174,118 @@ mod tests {
         assert!(pl.is_unhandled());
     }
 
+    #[test]
+    fn test_head_limits() {
+        let limits = HeadLimits {
+            max_headers: 2,
+            max_header_size: 64,
+            max_request_line: 20,
+            ..HeadLimits::default()
+        };
+        let mut reader = MessageDecoder::<Request>::new(limits);
+
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\na: 1\r\nb: 2\r\n\r\n");
+        assert!(reader.decode(&mut buf).unwrap().is_some());
+
+        let mut buf = BytesMut::from("GET /test/long HTTP/1.1\r\n\r\n");
+        match reader.decode(&mut buf) {
+            Err(ParseError::UriTooLong) => (),
+            _ => unreachable!("Parse error expected"),
+        }
+        // partial request line
+        let mut buf = BytesMut::from("GET /test/long/path/more");
+        match reader.decode(&mut buf) {
+            Err(ParseError::UriTooLong) => (),
+            _ => unreachable!("Parse error expected"),
+        }
+
+        let mut buf = BytesMut::from("GET / HTTP/1.1\r\na: 1\r\nb: 2\r\nc: 3\r\n\r\n");
+        match reader.decode(&mut buf) {
+            Err(ParseError::HeaderTooLarge) => (),
+            _ => unreachable!("Parse error expected"),
+        }
+        let mut buf = BytesMut::from(
+            format!("GET / HTTP/1.1\r\na: {}\r\n", "x".repeat(64)).as_str(),
+        );
+        match reader.decode(&mut buf) {
+            Err(ParseError::HeaderTooLarge) => (),
+            _ => unreachable!("Parse error expected"),
+        }
+
+        // more headers than fit into stack buffer
+        let limits = HeadLimits {
+            max_headers: 128,
+            ..HeadLimits::default()
+        };
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        let mut raw = "GET / HTTP/1.1\r\n".to_owned();
+        for idx in 0..100 {
+            raw.push_str(&format!("x-{}: {}\r\n", idx, idx));
+        }
+        raw.push_str("\r\n");
+        let mut buf = BytesMut::from(raw.as_str());
+        let (req, _) = reader.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(req.headers().len(), 100);
+        assert_eq!(req.headers().get("x-99").unwrap(), "99");
+    }
+
+    #[test]
+    fn test_strict_framing() {
+        let strict = HeadLimits {
+            strict: true,
+            ..HeadLimits::default()
+        };
+        let requests = [
+            "POST / HTTP/1.1\r\ncontent-length: 5\r\n\
+             transfer-encoding: chunked\r\n\r\n",
+            "POST / HTTP/1.1\r\ncontent-length: 5\r\ncontent-length: 5\r\n\r\n",
+            "GET / HTTP/1.1\r\nx-test: a\r\n b\r\n\r\n",
+            "GET / HTTP/1.1\r\nx-test: a\r\n\tb\r\n\r\n",
+            "POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\
+             transfer-encoding: chunked\r\n\r\n",
+            "POST / HTTP/1.1\r\ntransfer-encoding: gzip\r\n\
+             transfer-encoding: chunked\r\n\r\n",
+            "POST / HTTP/1.1\r\ntransfer-encoding: gzip, chunked\r\n\r\n",
+            "POST / HTTP/1.1\r\ntransfer-encoding: chunked, identity\r\n\r\n",
+            "POST / HTTP/1.1\r\ntransfer-encoding: identity\r\n\r\n",
+        ];
+        for raw in requests.iter() {
+            let mut buf = BytesMut::from(*raw);
+            match MessageDecoder::<Request>::new(strict).decode(&mut buf) {
+                Err(ParseError::Header) => (),
+                _ => unreachable!("Parse error expected for {:?}", raw),
+            }
+        }
+
+        // ambiguous length is accepted by default
+        for raw in requests[..2].iter() {
+            let mut buf = BytesMut::from(*raw);
+            let reader = MessageDecoder::<Request>::default().decode(&mut buf);
+            assert!(reader.unwrap().is_some());
+        }
+
+        let mut buf = BytesMut::from(
+            "POST / HTTP/1.1\r\ncontent-length: 4\r\nx-test: a b\r\n\r\ntest",
+        );
+        let (req, pl) = MessageDecoder::<Request>::new(strict)
+            .decode(&mut buf)
+            .unwrap()
+            .unwrap();
+        assert_eq!(req.headers().get("x-test").unwrap(), "a b");
+        let chunk = pl.unwrap().decode(&mut buf).unwrap().unwrap().chunk();
+        assert_eq!(chunk, Bytes::from_static(b"test"));
+
+        let mut buf = BytesMut::from(
+            "POST / HTTP/1.1\r\ntransfer-encoding: Chunked\r\n\r\n4\r\ntest\r\n",
+        );
+        let (_, pl) = MessageDecoder::<Request>::new(strict)
+            .decode(&mut buf)
+            .unwrap()
+            .unwrap();
+        let chunk = pl.unwrap().decode(&mut buf).unwrap().unwrap().chunk();
+        assert_eq!(chunk, Bytes::from_static(b"test"));
+    }
+
     #[test]
     fn test_http_request_parser_utf8() {
         let mut buf = BytesMut::from(
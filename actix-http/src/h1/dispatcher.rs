@@ -3,34 +3,70 @@
 
 use std::collections::VecDeque;
 use std::future::Future;
+use std::io::IoSlice;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::task::{Context, Poll};
-use std::{fmt, io, net};
+use std::time::Duration;
+use std::{cmp, fmt, io, net};
 
 use actix_codec::{AsyncRead, AsyncWrite, Decoder, Encoder, Framed, FramedParts};
-use actix_rt::time::{delay_until, Delay, Instant};
+use actix_rt::time::{delay_for, delay_until, Delay, Instant};
 use actix_service::Service;
 use bitflags::bitflags;
-use bytes::{Buf, BytesMut};
+use bytes::{Buf, Bytes, BytesMut};
 use log::{error, trace};
-use pin_project::pin_project;
+use pin_project::{pin_project, pinned_drop};
 
+use crate::bandwidth::{BandwidthLimit, WriteLimit};
 use crate::body::{Body, BodySize, MessageBody, ResponseBody};
+use crate::buffer_pool::Buffers;
 use crate::cloneable::CloneableService;
-use crate::config::ServiceConfig;
+use crate::config::{RateLimiter, ServiceConfig};
 use crate::error::{DispatchError, Error};
-use crate::error::{ParseError, PayloadError};
+use crate::error::{ParseError, PayloadError, ResponseError};
+use crate::header::RETRY_AFTER;
 use crate::helpers::DataFactory;
+use crate::http::Version;
 use crate::httpmessage::HttpMessage;
+use crate::load_shed::{monitor_loop_lag, retry_after_secs, Counter, Tracked};
+use crate::message::ConnectionType;
 use crate::request::Request;
 use crate::response::Response;
 
 use super::codec::Codec;
+use super::informational::Informational;
 use super::payload::{Payload, PayloadSender, PayloadStatus};
 use super::{Message, MessageType};
 
 const LW_BUFFER_SIZE: usize = 4096;
 const HW_BUFFER_SIZE: usize = 32_768;
+
+/// Idle time after which keep-alive connection releases its buffers.
+const IDLE_RELEASE_DELAY: Duration = Duration::from_secs(1);
+
+static HIBERNATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of idle keep-alive connections with released buffers.
+pub fn hibernated_connections() -> usize {
+    HIBERNATED.load(Ordering::Relaxed)
+}
+
+/// Marker of hibernated connection, it is dropped on the next read.
+struct Hibernated;
+
+impl Hibernated {
+    fn new() -> Self {
+        HIBERNATED.fetch_add(1, Ordering::Relaxed);
+        Hibernated
+    }
+}
+
+impl Drop for Hibernated {
+    fn drop(&mut self) {
+        HIBERNATED.fetch_sub(1, Ordering::Relaxed);
+    }
+}
 const MAX_PIPELINED_MESSAGES: usize = 16;
 
 bitflags! {
@@ -42,6 +78,7 @@ bitflags! {
         const READ_DISCONNECT    = 0b0001_0000;
         const WRITE_DISCONNECT   = 0b0010_0000;
         const UPGRADE            = 0b0100_0000;
+        const FORCE_CLOSE        = 0b1000_0000;
     }
 }
 
@@ -76,7 +113,7 @@ where
     Upgrade(Pin<Box<U::Future>>),
 }
 
-#[pin_project]
+#[pin_project(PinnedDrop)]
 struct InnerDispatcher<T, S, B, X, U>
 where
     S: Service<Request = Request>,
@@ -102,15 +139,26 @@ where
 
     ka_expire: Instant,
     ka_timer: Option<Delay>,
+    rate_limiter: Option<RateLimiter>,
 
     io: Option<T>,
     read_buf: BytesMut,
+    read_size: usize,
+    read_limit: usize,
     write_buf: BytesMut,
+    write_queue: VecDeque<Bytes>,
+    write_coalesce: usize,
+    write_limit: WriteLimit,
     codec: Codec,
+    hibernated: Option<Hibernated>,
+    idle_timer: Option<Delay>,
+    in_flight: Option<Tracked>,
+    informational: Option<Informational>,
+    _connection: Tracked,
 }
 
 enum DispatcherMessage {
-    Item(Request),
+    Item(Request, Tracked),
     Upgrade(Request),
     Error(Response<()>),
 }
@@ -198,7 +246,7 @@ where
             stream,
             Codec::new(config.clone()),
             config,
-            BytesMut::with_capacity(HW_BUFFER_SIZE),
+            BytesMut::new(),
             None,
             service,
             expect,
@@ -221,6 +269,9 @@ where
         on_connect: Option<Box<dyn DataFactory>>,
         peer_addr: Option<net::SocketAddr>,
     ) -> Self {
+        // event loop lag is sampled on every worker, monitor adds reports
+        monitor_loop_lag(config.health_monitor());
+
         let keepalive = config.keep_alive_enabled();
         let flags = if keepalive {
             Flags::KEEPALIVE
@@ -237,9 +288,17 @@ where
             (config.now(), None)
         };
 
+        let (read_buf, write_buf) = match config.buffers() {
+            Some(pool) if read_buf.capacity() == 0 => (pool.get(), pool.get()),
+            Some(pool) => (read_buf, pool.get()),
+            None => (read_buf, BytesMut::with_capacity(HW_BUFFER_SIZE)),
+        };
+
         Dispatcher {
             inner: DispatcherState::Normal(InnerDispatcher {
-                write_buf: BytesMut::with_capacity(HW_BUFFER_SIZE),
+                write_buf,
+                write_queue: VecDeque::new(),
+                write_coalesce: config.write_coalesce_limit(),
                 payload: None,
                 state: State::None,
                 error: None,
@@ -247,6 +306,14 @@ where
                 io: Some(io),
                 codec,
                 read_buf,
+                read_size: LW_BUFFER_SIZE,
+                read_limit: config.read_buf_limit(),
+                write_limit: WriteLimit::new(config.write_bucket()),
+                hibernated: None,
+                idle_timer: None,
+                in_flight: None,
+                informational: None,
+                _connection: Tracked::new(Counter::Connections),
                 service,
                 expect,
                 upgrade,
@@ -255,11 +322,33 @@ where
                 peer_addr,
                 ka_expire,
                 ka_timer,
+                rate_limiter: config.rate_limiter(),
             }),
         }
     }
 }
 
+#[pinned_drop]
+impl<T, S, B, X, U> PinnedDrop for InnerDispatcher<T, S, B, X, U>
+where
+    S: Service<Request = Request>,
+    S::Error: Into<Error>,
+    B: MessageBody,
+    X: Service<Request = Request, Response = Request>,
+    X::Error: Into<Error>,
+    U: Service<Request = (Request, Framed<T, Codec>), Response = ()>,
+    U::Error: fmt::Display,
+{
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+        // return buffers of the closed connection
+        if let Some(pool) = this.codec.config().buffers() {
+            pool.release(this.read_buf);
+            pool.release(this.write_buf);
+        }
+    }
+}
+
 impl<T, S, B, X, U> InnerDispatcher<T, S, B, X, U>
 where
     T: AsyncRead + AsyncWrite + Unpin,
@@ -304,17 +393,63 @@ where
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Result<bool, DispatchError> {
-        if self.write_buf.is_empty() {
+        if self.write_buf.is_empty() && self.write_queue.is_empty() {
             return Ok(false);
         }
 
-        let len = self.write_buf.len();
-        let mut written = 0;
         #[project]
-        let InnerDispatcher { io, write_buf, .. } = self.project();
+        let InnerDispatcher {
+            io,
+            write_buf,
+            write_queue,
+            write_limit,
+            ..
+        } = self.project();
         let mut io = Pin::new(io.as_mut().unwrap());
+
+        // queued payload chunks are written together with buffered data
+        while !write_queue.is_empty() {
+            let len =
+                write_queue.iter().map(Bytes::len).sum::<usize>() + write_buf.len();
+            let limit = match write_limit.poll_acquire(cx, len) {
+                Poll::Ready(n) => n,
+                Poll::Pending => return Ok(true),
+            };
+            let mut chunks = WriteChunks {
+                queue: write_queue,
+                buf: write_buf,
+                limit,
+            };
+            match phase!("write", io.as_mut().poll_write_buf(cx, &mut chunks)) {
+                Poll::Ready(Ok(0)) => {
+                    return Err(DispatchError::Io(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "",
+                    )));
+                }
+                Poll::Ready(Ok(n)) => write_limit.consume(n),
+                Poll::Pending => return Ok(true),
+                Poll::Ready(Err(err)) => return Err(DispatchError::Io(err)),
+            }
+        }
+        if write_buf.is_empty() {
+            return Ok(false);
+        }
+
+        let len = write_buf.len();
+        let mut written = 0;
         while written < len {
-            match io.as_mut().poll_write(cx, &write_buf[written..]) {
+            let end = match write_limit.poll_acquire(cx, len - written) {
+                Poll::Ready(n) => written + n,
+                Poll::Pending => {
+                    write_buf.advance(written);
+                    return Ok(true);
+                }
+            };
+            match phase!(
+                "write",
+                io.as_mut().poll_write(cx, &write_buf[written..end])
+            ) {
                 Poll::Ready(Ok(0)) => {
                     return Err(DispatchError::Io(io::Error::new(
                         io::ErrorKind::WriteZero,
@@ -322,6 +457,7 @@ where
                     )));
                 }
                 Poll::Ready(Ok(n)) => {
+                    write_limit.consume(n);
                     written += n;
                 }
                 Poll::Pending => {
@@ -347,18 +483,32 @@ where
         body: ResponseBody<B>,
     ) -> Result<State<S, B, X>, DispatchError> {
         let mut this = self.project();
-        this.codec
-            .encode(Message::Item((message, body.size())), &mut this.write_buf)
-            .map_err(|err| {
-                if let Some(mut payload) = this.payload.take() {
-                    payload.set_error(PayloadError::Incomplete(None));
-                }
-                DispatchError::Io(err)
-            })?;
+        let force_close = message.head().ctype() == Some(ConnectionType::Close);
+        let limit = message.extensions().get::<BandwidthLimit>().copied();
+        this.write_limit.set_response(limit);
+        let result = phase!(
+            "encode",
+            this.codec
+                .encode(Message::Item((message, body.size())), &mut this.write_buf)
+        );
+        result.map_err(|err| {
+            if let Some(mut payload) = this.payload.take() {
+                payload.set_error(PayloadError::Incomplete(None));
+            }
+            DispatchError::Io(err)
+        })?;
 
         this.flags.set(Flags::KEEPALIVE, this.codec.keepalive());
+        if force_close {
+            // connection is closed after the response, drop pipelined requests
+            this.flags.insert(Flags::FORCE_CLOSE);
+            this.messages.clear();
+        }
         match body.size() {
-            BodySize::None | BodySize::Empty => Ok(State::None),
+            BodySize::None | BodySize::Empty => {
+                *this.in_flight = None;
+                Ok(State::None)
+            }
             _ => Ok(State::SendPayload(body)),
         }
     }
@@ -369,6 +519,30 @@ where
             .extend_from_slice(b"HTTP/1.1 100 Continue\r\n\r\n");
     }
 
+    /// Store informational responses sender in extensions of http/1.1
+    /// request, 1xx responses are not allowed for http/1.0 clients.
+    fn attach_informational(self: Pin<&mut Self>, req: Request) -> Request {
+        if req.head().version == Version::HTTP_11 {
+            let info = Informational::new();
+            req.extensions_mut().insert(info.clone());
+            *self.project().informational = Some(info);
+        }
+        req
+    }
+
+    /// Write informational responses of the service call, `done` means
+    /// final response is ready.
+    fn send_informational(self: Pin<&mut Self>, cx: &mut Context<'_>, done: bool) {
+        let this = self.project();
+        if done {
+            if let Some(info) = this.informational.take() {
+                info.finish(this.write_buf, cx.waker());
+            }
+        } else if let Some(ref info) = this.informational {
+            info.encode(this.write_buf, cx.waker());
+        }
+    }
+
     #[pin_project::project]
     fn poll_response(
         mut self: Pin<&mut Self>,
@@ -379,7 +553,8 @@ where
             #[project]
             let state = match this.state.project() {
                 State::None => match this.messages.pop_front() {
-                    Some(DispatcherMessage::Item(req)) => {
+                    Some(DispatcherMessage::Item(req, queued)) => {
+                        drop(queued);
                         Some(self.as_mut().handle_request(req, cx)?)
                     }
                     Some(DispatcherMessage::Error(res)) => Some(
@@ -394,6 +569,7 @@ where
                 State::ExpectCall(fut) => match fut.as_mut().poll(cx) {
                     Poll::Ready(Ok(req)) => {
                         self.as_mut().send_continue();
+                        let req = self.as_mut().attach_informational(req);
                         this = self.as_mut().project();
                         this.state
                             .set(State::ServiceCall(Box::pin(this.service.call(req))));
@@ -406,8 +582,10 @@ where
                     }
                     Poll::Pending => None,
                 },
-                State::ServiceCall(fut) => match fut.as_mut().poll(cx) {
+                State::ServiceCall(fut) => match phase!("handle", fut.as_mut().poll(cx))
+                {
                     Poll::Ready(Ok(res)) => {
+                        self.as_mut().send_informational(cx, true);
                         let (res, body) = res.into().replace_body(());
                         let state = self.as_mut().send_response(res, body)?;
                         this = self.as_mut().project();
@@ -415,30 +593,59 @@ where
                         continue;
                     }
                     Poll::Ready(Err(e)) => {
+                        self.as_mut().send_informational(cx, true);
                         let res: Response = e.into().into();
                         let (res, body) = res.replace_body(());
                         Some(self.as_mut().send_response(res, body.into_body())?)
                     }
-                    Poll::Pending => None,
+                    Poll::Pending => {
+                        self.as_mut().send_informational(cx, false);
+                        None
+                    }
                 },
                 State::SendPayload(mut stream) => {
                     loop {
-                        if this.write_buf.len() < HW_BUFFER_SIZE {
+                        let queued: usize =
+                            this.write_queue.iter().map(Bytes::len).sum();
+                        if this.write_buf.len() + queued < HW_BUFFER_SIZE {
                             match stream.as_mut().poll_next(cx) {
                                 Poll::Ready(Some(Ok(item))) => {
-                                    this.codec.encode(
-                                        Message::Chunk(Some(item)),
-                                        &mut this.write_buf,
-                                    )?;
+                                    if *this.write_coalesce != 0
+                                        && item.len() >= *this.write_coalesce
+                                    {
+                                        phase!(
+                                            "encode",
+                                            this.codec.encode_vectored(
+                                                item,
+                                                this.write_buf,
+                                                this.write_queue,
+                                            )
+                                        )?;
+                                    } else {
+                                        phase!(
+                                            "encode",
+                                            this.codec.encode(
+                                                Message::Chunk(Some(item)),
+                                                &mut this.write_buf,
+                                            )
+                                        )?;
+                                    }
                                     continue;
                                 }
                                 Poll::Ready(None) => {
-                                    this.codec.encode(
-                                        Message::Chunk(None),
-                                        &mut this.write_buf,
-                                    )?;
+                                    match stream.as_mut().take_trailers() {
+                                        Some(trailers) => this.codec.encode_trailers(
+                                            &trailers,
+                                            &mut this.write_buf,
+                                        )?,
+                                        None => this.codec.encode(
+                                            Message::Chunk(None),
+                                            &mut this.write_buf,
+                                        )?,
+                                    }
                                     this = self.as_mut().project();
                                     this.state.set(State::None);
+                                    *this.in_flight = None;
                                 }
                                 Poll::Ready(Some(Err(_))) => {
                                     return Err(DispatchError::Unknown)
@@ -484,6 +691,8 @@ where
         req: Request,
         cx: &mut Context<'_>,
     ) -> Result<State<S, B, X>, DispatchError> {
+        *self.as_mut().project().in_flight = Some(Tracked::new(Counter::InFlight));
+
         // Handle `EXPECT: 100-Continue` header
         let req = if req.head().expect() {
             let mut task = Box::pin(self.as_mut().project().expect.call(req));
@@ -503,16 +712,22 @@ where
         } else {
             req
         };
+        let req = self.as_mut().attach_informational(req);
 
         // Call service
         let mut task = Box::pin(self.as_mut().project().service.call(req));
-        match task.as_mut().poll(cx) {
+        match phase!("handle", task.as_mut().poll(cx)) {
             Poll::Ready(Ok(res)) => {
+                self.as_mut().send_informational(cx, true);
                 let (res, body) = res.into().replace_body(());
                 self.send_response(res, body)
             }
-            Poll::Pending => Ok(State::ServiceCall(task)),
+            Poll::Pending => {
+                self.as_mut().send_informational(cx, false);
+                Ok(State::ServiceCall(task))
+            }
             Poll::Ready(Err(e)) => {
+                self.as_mut().send_informational(cx, true);
                 let res: Response = e.into().into();
                 let (res, body) = res.replace_body(());
                 self.send_response(res, body.into_body())
@@ -533,14 +748,55 @@ where
         let mut updated = false;
         let mut this = self.as_mut().project();
         loop {
-            match this.codec.decode(&mut this.read_buf) {
+            match phase!("parse", this.codec.decode(&mut this.read_buf)) {
                 Ok(Some(msg)) => {
                     updated = true;
                     this.flags.insert(Flags::STARTED);
 
                     match msg {
                         Message::Item(mut req) => {
+                            if this.flags.contains(Flags::FORCE_CLOSE) {
+                                this.flags.insert(Flags::READ_DISCONNECT);
+                                break;
+                            }
                             let pl = this.codec.message_type();
+
+                            if let Some(ref mut limiter) = this.rate_limiter {
+                                let config = this.codec.config();
+                                if !limiter.acquire(config.now()) {
+                                    trace!("Request rate limit exceeded");
+                                    let close = config.close_on_rate_limit();
+                                    this.messages.push_back(DispatcherMessage::Error(
+                                        too_many_requests(close),
+                                    ));
+                                    if close {
+                                        this.flags.insert(Flags::READ_DISCONNECT);
+                                        break;
+                                    }
+                                    // discard payload of rejected request
+                                    if pl == MessageType::Payload
+                                        || pl == MessageType::Stream
+                                    {
+                                        *this.payload = Some(Payload::create(false).0);
+                                    }
+                                    continue;
+                                }
+                            }
+
+                            let policy = this.codec.config().load_shed();
+                            if let Some(retry_after) = policy.and_then(|p| p.check()) {
+                                trace!("Request is rejected by load shed policy");
+                                this.messages.push_back(DispatcherMessage::Error(
+                                    service_unavailable(retry_after),
+                                ));
+                                if pl == MessageType::Payload
+                                    || pl == MessageType::Stream
+                                {
+                                    *this.payload = Some(Payload::create(false).0);
+                                }
+                                continue;
+                            }
+
                             req.head_mut().peer_addr = *this.peer_addr;
 
                             // set on_connect data
@@ -566,7 +822,10 @@ where
                                 this = self.as_mut().project();
                                 this.state.set(state);
                             } else {
-                                this.messages.push_back(DispatcherMessage::Item(req));
+                                this.messages.push_back(DispatcherMessage::Item(
+                                    req,
+                                    Tracked::new(Counter::Queued),
+                                ));
                             }
                         }
                         Message::Chunk(Some(chunk)) => {
@@ -611,9 +870,10 @@ where
                         payload.set_error(PayloadError::EncodingCorrupted);
                     }
 
-                    // Malformed requests should be responded with 400
+                    // Malformed requests should be responded with 400,
+                    // requests over head limits with 414 or 431
                     this.messages.push_back(DispatcherMessage::Error(
-                        Response::BadRequest().finish().drop_body(),
+                        Response::new(e.status_code()).drop_body(),
                     ));
                     this.flags.insert(Flags::READ_DISCONNECT);
                     *this.error = Some(e.into());
@@ -660,7 +920,10 @@ where
                     return Err(DispatchError::DisconnectTimeout);
                 } else if this.ka_timer.as_mut().unwrap().deadline() >= *this.ka_expire {
                     // check for any outstanding tasks
-                    if this.state.is_empty() && this.write_buf.is_empty() {
+                    if this.state.is_empty()
+                        && this.write_buf.is_empty()
+                        && this.write_queue.is_empty()
+                    {
                         if this.flags.contains(Flags::STARTED) {
                             trace!("Keep-alive timeout, close connection");
                             this.flags.insert(Flags::SHUTDOWN);
@@ -742,7 +1005,10 @@ where
                     } else {
                         // flush buffer
                         inner.as_mut().poll_flush(cx)?;
-                        if !inner.write_buf.is_empty() || inner.io.is_none() {
+                        if !inner.write_buf.is_empty()
+                            || !inner.write_queue.is_empty()
+                            || inner.io.is_none()
+                        {
                             Poll::Pending
                         } else {
                             match Pin::new(inner.project().io)
@@ -762,14 +1028,25 @@ where
                     let should_disconnect =
                         if !inner.flags.contains(Flags::READ_DISCONNECT) {
                             let mut inner_p = inner.as_mut().project();
-                            read_available(
-                                cx,
-                                inner_p.io.as_mut().unwrap(),
-                                &mut inner_p.read_buf,
+                            phase!(
+                                "read",
+                                read_available(
+                                    cx,
+                                    inner_p.io.as_mut().unwrap(),
+                                    &mut inner_p.read_buf,
+                                    &mut inner_p.read_size,
+                                    *inner_p.read_limit,
+                                    inner_p.codec.config().buffers(),
+                                )
                             )?
                         } else {
                             None
                         };
+                    if should_disconnect.is_some() {
+                        let inner_p = inner.as_mut().project();
+                        inner_p.hibernated.take();
+                        inner_p.idle_timer.take();
+                    }
 
                     inner.as_mut().poll_request(cx)?;
                     if let Some(true) = should_disconnect {
@@ -784,8 +1061,15 @@ where
                         let inner_p = inner.as_mut().project();
                         let remaining =
                             inner_p.write_buf.capacity() - inner_p.write_buf.len();
-                        if remaining < LW_BUFFER_SIZE {
-                            inner_p.write_buf.reserve(HW_BUFFER_SIZE - remaining);
+                        if remaining < LW_BUFFER_SIZE && inner_p.hibernated.is_none() {
+                            match inner_p.codec.config().buffers() {
+                                Some(pool) if inner_p.write_buf.capacity() == 0 => {
+                                    *inner_p.write_buf = pool.get()
+                                }
+                                _ => {
+                                    inner_p.write_buf.reserve(HW_BUFFER_SIZE - remaining)
+                                }
+                            }
                         }
                         let result = inner.as_mut().poll_response(cx)?;
                         let drain = result == PollResponse::DrainWriteBuf;
@@ -802,6 +1086,14 @@ where
                                 inner_p.write_buf,
                                 BytesMut::default(),
                             );
+                            if !inner_p.write_queue.is_empty() {
+                                let mut buf = BytesMut::new();
+                                for chunk in inner_p.write_queue.drain(..) {
+                                    buf.extend_from_slice(&chunk);
+                                }
+                                buf.extend_from_slice(&parts.write_buf);
+                                parts.write_buf = buf;
+                            }
                             let framed = Framed::from_parts(parts);
                             let upgrade =
                                 inner_p.upgrade.take().unwrap().call((req, framed));
@@ -834,7 +1126,10 @@ where
                     }
 
                     // keep-alive and stream errors
-                    if is_empty && inner_p.write_buf.is_empty() {
+                    if is_empty
+                        && inner_p.write_buf.is_empty()
+                        && inner_p.write_queue.is_empty()
+                    {
                         if let Some(err) = inner_p.error.take() {
                             Poll::Ready(Err(err))
                         }
@@ -849,6 +1144,30 @@ where
                         else if inner_p.flags.contains(Flags::SHUTDOWN) {
                             self.poll(cx)
                         } else {
+                            // release buffers of connection that stays idle
+                            // until the next request
+                            if inner_p.hibernated.is_none()
+                                && inner_p.read_buf.is_empty()
+                                && inner_p.messages.is_empty()
+                                && inner_p.payload.is_none()
+                            {
+                                let timer =
+                                    inner_p.idle_timer.get_or_insert_with(|| {
+                                        delay_for(IDLE_RELEASE_DELAY)
+                                    });
+                                if Pin::new(timer).poll(cx).is_ready() {
+                                    *inner_p.idle_timer = None;
+                                    if let Some(pool) = inner_p.codec.config().buffers()
+                                    {
+                                        pool.release(inner_p.read_buf);
+                                        pool.release(inner_p.write_buf);
+                                    } else {
+                                        *inner_p.read_buf = BytesMut::new();
+                                        *inner_p.write_buf = BytesMut::new();
+                                    }
+                                    *inner_p.hibernated = Some(Hibernated::new());
+                                }
+                            }
                             Poll::Pending
                         }
                     } else {
@@ -864,10 +1183,35 @@ where
     }
 }
 
+/// Response to requests over the connection rate limit.
+fn too_many_requests(close: bool) -> Response<()> {
+    let mut res = Response::TooManyRequests();
+    res.header(RETRY_AFTER, "1");
+    if close {
+        res.force_close();
+    }
+    res.finish().drop_body()
+}
+
+fn service_unavailable(retry_after: Duration) -> Response<()> {
+    Response::ServiceUnavailable()
+        .header(RETRY_AFTER, retry_after_secs(retry_after))
+        .finish()
+        .drop_body()
+}
+
+/// Read all available data from the io.
+///
+/// Read size grows while reads fill the buffer up and is reset once
+/// connection has no unprocessed data. Buffers released by idle connections
+/// are taken from the pool on the next read.
 fn read_available<T>(
     cx: &mut Context<'_>,
     io: &mut T,
     buf: &mut BytesMut,
+    read_size: &mut usize,
+    read_limit: usize,
+    pool: Option<&Buffers>,
 ) -> Result<Option<bool>, io::Error>
 where
     T: AsyncRead + Unpin,
@@ -876,18 +1220,30 @@ where
     loop {
         let remaining = buf.capacity() - buf.len();
         if remaining < LW_BUFFER_SIZE {
-            buf.reserve(HW_BUFFER_SIZE - remaining);
+            match pool {
+                Some(pool) if buf.capacity() == 0 => *buf = pool.get(),
+                _ => buf.reserve(*read_size),
+            }
         }
+        let available = buf.capacity() - buf.len();
 
         match read(cx, io, buf) {
             Poll::Pending => {
-                return if read_some { Ok(Some(false)) } else { Ok(None) };
+                return if read_some {
+                    Ok(Some(false))
+                } else {
+                    reset_read_size(buf, read_size);
+                    Ok(None)
+                };
             }
             Poll::Ready(Ok(n)) => {
                 if n == 0 {
                     return Ok(Some(true));
                 } else {
                     read_some = true;
+                    if n == available {
+                        *read_size = cmp::min(*read_size * 2, read_limit);
+                    }
                 }
             }
             Poll::Ready(Err(e)) => {
@@ -895,6 +1251,7 @@ where
                     if read_some {
                         Ok(Some(false))
                     } else {
+                        reset_read_size(buf, read_size);
                         Ok(None)
                     }
                 } else if e.kind() == io::ErrorKind::ConnectionReset && read_some {
@@ -907,6 +1264,74 @@ where
     }
 }
 
+/// Queued payload chunks followed by write buffer, up to `limit` bytes.
+struct WriteChunks<'a> {
+    queue: &'a mut VecDeque<Bytes>,
+    buf: &'a mut BytesMut,
+    limit: usize,
+}
+
+impl<'a> Buf for WriteChunks<'a> {
+    fn remaining(&self) -> usize {
+        let len = self.queue.iter().map(Bytes::len).sum::<usize>() + self.buf.len();
+        cmp::min(len, self.limit)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        let chunk = match self.queue.front() {
+            Some(chunk) => chunk.as_ref(),
+            None => self.buf.as_ref(),
+        };
+        &chunk[..cmp::min(chunk.len(), self.limit)]
+    }
+
+    fn bytes_vectored<'b>(&'b self, dst: &mut [IoSlice<'b>]) -> usize {
+        let mut limit = self.limit;
+        let mut n = 0;
+        let chunks = self.queue.iter().map(Bytes::as_ref);
+        for chunk in chunks.chain(Some(self.buf.as_ref())) {
+            if n == dst.len() || limit == 0 {
+                break;
+            }
+            if !chunk.is_empty() {
+                let len = cmp::min(chunk.len(), limit);
+                dst[n] = IoSlice::new(&chunk[..len]);
+                limit -= len;
+                n += 1;
+            }
+        }
+        n
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        self.limit -= cnt;
+        while cnt > 0 {
+            match self.queue.front_mut() {
+                Some(chunk) if chunk.len() <= cnt => {
+                    cnt -= chunk.len();
+                    self.queue.pop_front();
+                }
+                Some(chunk) => {
+                    chunk.advance(cnt);
+                    cnt = 0;
+                }
+                None => {
+                    self.buf.advance(cnt);
+                    cnt = 0;
+                }
+            }
+        }
+    }
+}
+
+/// Reset read size of connection without unprocessed data, buffer is kept
+/// for the next request.
+fn reset_read_size(buf: &BytesMut, read_size: &mut usize) {
+    if buf.is_empty() {
+        *read_size = LW_BUFFER_SIZE;
+    }
+}
+
 fn read<T>(
     cx: &mut Context<'_>,
     io: &mut T,
@@ -959,4 +1384,291 @@ mod tests {
         })
         .await;
     }
+
+    #[actix_rt::test]
+    async fn test_rate_limit() {
+        lazy(|cx| {
+            let req = "GET /test HTTP/1.1\r\n\r\n";
+            for &close in &[false, true] {
+                let buf = TestBuffer::new(req.repeat(3).as_str());
+                let cfg = ServiceConfig::default().with_rate_limit(2, close);
+
+                let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+                    buf,
+                    cfg,
+                    CloneableService::new(
+                        (|_| ok::<_, Error>(Response::Ok().finish())).into_service(),
+                    ),
+                    CloneableService::new(ExpectHandler),
+                    None,
+                    None,
+                    None,
+                );
+                let _ = Pin::new(&mut h1).poll(cx);
+
+                if let DispatcherState::Normal(ref mut inner) = h1.inner {
+                    assert_eq!(inner.flags.contains(Flags::READ_DISCONNECT), close);
+                    let io = inner.io.take().unwrap();
+                    let resp = String::from_utf8_lossy(&io.write_buf);
+                    assert_eq!(resp.matches("HTTP/1.1 200 OK\r\n").count(), 2);
+                    assert_eq!(resp.matches("HTTP/1.1 429 ").count(), 1);
+                    assert!(resp.contains("retry-after: 1\r\n"));
+                    assert_eq!(resp.contains("connection: close\r\n"), close);
+                }
+            }
+        })
+        .await;
+    }
+
+    #[actix_rt::test]
+    async fn test_load_shed() {
+        use std::sync::atomic::AtomicUsize;
+        use std::sync::Arc;
+
+        use crate::load_shed::{Load, LoadShedPolicy};
+
+        lazy(|cx| {
+            let buf = TestBuffer::new("GET /test HTTP/1.1\r\n\r\n".repeat(3).as_str());
+            // reject the second request
+            let checked = Arc::new(AtomicUsize::new(0));
+            let checked2 = checked.clone();
+            let policy = LoadShedPolicy::new(move |load: &Load| {
+                assert_eq!(load.connections, 1);
+                if checked2.fetch_add(1, Ordering::Relaxed) == 1 {
+                    Some(Duration::from_millis(1500))
+                } else {
+                    None
+                }
+            });
+            let cfg = ServiceConfig::default().with_load_shed(Some(policy));
+
+            let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+                buf,
+                cfg,
+                CloneableService::new(
+                    (|_| ok::<_, Error>(Response::Ok().finish())).into_service(),
+                ),
+                CloneableService::new(ExpectHandler),
+                None,
+                None,
+                None,
+            );
+            let _ = Pin::new(&mut h1).poll(cx);
+            assert_eq!(checked.load(Ordering::Relaxed), 3);
+            assert_eq!(Load::current().in_flight, 0);
+
+            if let DispatcherState::Normal(ref mut inner) = h1.inner {
+                let io = inner.io.take().unwrap();
+                let resp = String::from_utf8_lossy(&io.write_buf);
+                assert_eq!(resp.matches("HTTP/1.1 200 OK\r\n").count(), 2);
+                assert_eq!(resp.matches("HTTP/1.1 503 ").count(), 1);
+                assert!(resp.contains("retry-after: 2\r\n"));
+            }
+            drop(h1);
+            assert_eq!(Load::current().connections, 0);
+        })
+        .await;
+    }
+
+    #[actix_rt::test]
+    async fn test_read_buf_size() {
+        lazy(|cx| {
+            let mut io = TestBuffer::new(&[b'a'; 20_000][..]);
+            let mut buf = BytesMut::new();
+            let mut size = LW_BUFFER_SIZE;
+
+            let res = read_available(cx, &mut io, &mut buf, &mut size, 16_384, None);
+            assert_eq!(res.unwrap(), Some(false));
+            assert_eq!(buf.len(), 20_000);
+            assert_eq!(size, 16_384);
+
+            // read size is reset once data is processed, buffer is kept
+            buf.clear();
+            let res = read_available(cx, &mut io, &mut buf, &mut size, 16_384, None);
+            assert_eq!(res.unwrap(), None);
+            assert!(buf.capacity() >= 16_384);
+            assert_eq!(size, LW_BUFFER_SIZE);
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_write_chunks() {
+        let mut queue: VecDeque<_> =
+            vec![Bytes::from_static(b"head"), Bytes::from_static(b"body")].into();
+        let mut buf = BytesMut::from("tail");
+        let mut chunks = WriteChunks {
+            queue: &mut queue,
+            buf: &mut buf,
+            limit: 10,
+        };
+        assert_eq!(chunks.remaining(), 10);
+
+        let mut slices = [IoSlice::new(&[]), IoSlice::new(&[]), IoSlice::new(&[])];
+        assert_eq!(chunks.bytes_vectored(&mut slices), 3);
+        assert_eq!(&*slices[0], b"head");
+        assert_eq!(&*slices[2], b"ta");
+
+        chunks.advance(6);
+        assert_eq!(chunks.bytes(), b"dy");
+        chunks.advance(4);
+        assert_eq!(chunks.remaining(), 0);
+        assert!(queue.is_empty());
+        assert_eq!(&buf[..], b"il");
+    }
+
+    #[actix_rt::test]
+    async fn test_hibernate() {
+        let buf = TestBuffer::new("GET /test HTTP/1.1\r\n\r\n");
+        let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+            buf,
+            ServiceConfig::default(),
+            CloneableService::new(
+                (|_| ok::<_, Error>(Response::Ok().finish())).into_service(),
+            ),
+            CloneableService::new(ExpectHandler),
+            None,
+            None,
+            None,
+        );
+
+        // buffers are kept while connection is busy
+        lazy(|cx| {
+            assert!(Pin::new(&mut h1).poll(cx).is_pending());
+            if let DispatcherState::Normal(ref mut inner) = h1.inner {
+                assert!(inner.hibernated.is_none());
+                assert!(inner.read_buf.capacity() > 0);
+
+                let io = inner.io.as_mut().unwrap();
+                assert!(io.write_buf.starts_with(b"HTTP/1.1 200 OK\r\n"));
+                io.write_buf.clear();
+                io.extend_read_buf("GET /test HTTP/1.1\r\n\r\n");
+            }
+            assert!(Pin::new(&mut h1).poll(cx).is_pending());
+            if let DispatcherState::Normal(ref mut inner) = h1.inner {
+                assert!(inner.hibernated.is_none());
+                let io = inner.io.as_ref().unwrap();
+                assert!(io.write_buf.starts_with(b"HTTP/1.1 200 OK\r\n"));
+            }
+        })
+        .await;
+
+        delay_for(IDLE_RELEASE_DELAY + Duration::from_millis(50)).await;
+        lazy(|cx| {
+            assert!(Pin::new(&mut h1).poll(cx).is_pending());
+            if let DispatcherState::Normal(ref mut inner) = h1.inner {
+                assert!(inner.hibernated.is_some());
+                assert_eq!(inner.read_buf.capacity(), 0);
+                assert_eq!(inner.write_buf.capacity(), 0);
+
+                let io = inner.io.as_mut().unwrap();
+                io.write_buf.clear();
+                io.extend_read_buf("GET /test HTTP/1.1\r\n\r\n");
+            }
+
+            // connection wakes up on the next request
+            assert!(Pin::new(&mut h1).poll(cx).is_pending());
+            if let DispatcherState::Normal(ref mut inner) = h1.inner {
+                assert!(inner.hibernated.is_none());
+                let io = inner.io.as_ref().unwrap();
+                assert!(io.write_buf.starts_with(b"HTTP/1.1 200 OK\r\n"));
+            }
+        })
+        .await;
+    }
+
+    #[actix_rt::test]
+    async fn test_buffer_pool() {
+        let buf = TestBuffer::new("GET /test HTTP/1.1\r\n\r\n");
+        let config = ServiceConfig::default()
+            .with_buffer_pool(Some(crate::BufferPool::new(4).chunk_size(8192)));
+
+        let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+            buf,
+            config.clone(),
+            CloneableService::new(
+                (|_| ok::<_, Error>(Response::Ok().finish())).into_service(),
+            ),
+            CloneableService::new(ExpectHandler),
+            None,
+            None,
+            None,
+        );
+        lazy(|cx| assert!(Pin::new(&mut h1).poll(cx).is_pending())).await;
+        let pool = config.buffers().unwrap();
+        assert_eq!(pool.len(), 0);
+
+        delay_for(IDLE_RELEASE_DELAY + Duration::from_millis(50)).await;
+        lazy(|cx| assert!(Pin::new(&mut h1).poll(cx).is_pending())).await;
+
+        // buffers of hibernated connection are returned to the pool
+        if let DispatcherState::Normal(ref mut inner) = h1.inner {
+            assert!(inner.hibernated.is_some());
+            assert_eq!(inner.write_buf.capacity(), 0);
+            let io = inner.io.as_mut().unwrap();
+            assert!(io.write_buf.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        }
+        assert!(pool.len() >= 1);
+
+        let free = pool.len();
+        let buf = pool.get();
+        assert!(buf.capacity() >= 8192);
+        drop(buf);
+        assert_eq!(pool.len(), free - 1);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[actix_rt::test]
+    async fn test_phase_spans() {
+        use std::sync::{Arc, Mutex};
+        use tracing_lib::span::{Attributes, Id, Record};
+        use tracing_lib::{Event, Metadata, Subscriber};
+
+        #[derive(Clone, Default)]
+        struct Phases(Arc<Mutex<Vec<&'static str>>>);
+
+        impl Subscriber for Phases {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut phases = self.0.lock().unwrap();
+                phases.push(span.metadata().name());
+                Id::from_u64(phases.len() as u64)
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let phases = Phases::default();
+        lazy(|cx| {
+            let buf = TestBuffer::new("GET /test HTTP/1.1\r\n\r\n");
+
+            let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+                buf,
+                ServiceConfig::default(),
+                CloneableService::new(
+                    (|_| ok::<_, Error>(Response::Ok().body("test"))).into_service(),
+                ),
+                CloneableService::new(ExpectHandler),
+                None,
+                None,
+                None,
+            );
+            tracing_lib::subscriber::with_default(phases.clone(), || {
+                let _ = Pin::new(&mut h1).poll(cx);
+            });
+        })
+        .await;
+
+        let phases = phases.0.lock().unwrap();
+        for phase in &["read", "parse", "handle", "encode", "write"] {
+            assert!(phases.contains(phase), "{}", phase);
+        }
+    }
 }
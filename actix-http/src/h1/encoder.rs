@@ -1,10 +1,11 @@
+use std::collections::VecDeque;
 use std::io::Write;
 use std::marker::PhantomData;
 use std::ptr::copy_nonoverlapping;
 use std::slice::from_raw_parts_mut;
 use std::{cmp, io};
 
-use bytes::{buf::BufMutExt, BufMut, BytesMut};
+use bytes::{buf::BufMutExt, BufMut, Bytes, BytesMut};
 
 use crate::body::BodySize;
 use crate::config::ServiceConfig;
@@ -226,7 +227,7 @@ pub(crate) trait MessageType: Sized {
         }
 
         // optimized date header, set_date writes \r\n
-        if !has_date {
+        if !has_date && config.date_header() {
             config.set_date(dst);
         } else {
             // msg eof
@@ -318,11 +319,30 @@ impl<T: MessageType> MessageEncoder<T> {
         self.te.encode(msg, buf)
     }
 
+    /// Encode message without copying, see `TransferEncoding::encode_vectored`
+    pub fn encode_chunk_vectored(
+        &mut self,
+        msg: Bytes,
+        buf: &mut BytesMut,
+        queue: &mut VecDeque<Bytes>,
+    ) -> io::Result<bool> {
+        self.te.encode_vectored(msg, buf, queue)
+    }
+
     /// Encode eof
     pub fn encode_eof(&mut self, buf: &mut BytesMut) -> io::Result<()> {
         self.te.encode_eof(buf)
     }
 
+    /// Encode eof with trailer headers
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        self.te.encode_trailers(trailers, buf)
+    }
+
     pub fn encode(
         &mut self,
         dst: &mut BytesMut,
@@ -452,6 +472,59 @@ impl TransferEncoding {
         }
     }
 
+    /// Encode message without copying it to the buffer. Return `EOF` state
+    /// of encoder
+    ///
+    /// Buffered data is moved to the queue and message is queued after it,
+    /// data encoded afterwards is written to the buffer again. Queue is
+    /// written to the connection before the buffer.
+    pub fn encode_vectored(
+        &mut self,
+        msg: Bytes,
+        buf: &mut BytesMut,
+        queue: &mut VecDeque<Bytes>,
+    ) -> io::Result<bool> {
+        match self.kind {
+            TransferEncodingKind::Eof => {
+                let eof = msg.is_empty();
+                enqueue(msg, buf, queue);
+                Ok(eof)
+            }
+            TransferEncodingKind::Chunked(ref mut eof) => {
+                if *eof {
+                    return Ok(true);
+                }
+
+                if msg.is_empty() {
+                    *eof = true;
+                    buf.extend_from_slice(b"0\r\n\r\n");
+                } else {
+                    writeln!(Writer(buf), "{:X}\r", msg.len())
+                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+                    enqueue(msg, buf, queue);
+                    buf.extend_from_slice(b"\r\n");
+                }
+                Ok(*eof)
+            }
+            TransferEncodingKind::Length(ref mut remaining) => {
+                if *remaining > 0 {
+                    if msg.is_empty() {
+                        return Ok(*remaining == 0);
+                    }
+                    let len = cmp::min(*remaining, msg.len() as u64);
+
+                    enqueue(msg.slice(..len as usize), buf, queue);
+
+                    *remaining -= len as u64;
+                    Ok(*remaining == 0)
+                } else {
+                    Ok(true)
+                }
+            }
+        }
+    }
+
     /// Encode eof. Return `EOF` state of encoder
     #[inline]
     pub fn encode_eof(&mut self, buf: &mut BytesMut) -> io::Result<()> {
@@ -473,6 +546,42 @@ impl TransferEncoding {
             }
         }
     }
+
+    /// Encode eof with trailer headers, trailers are dropped unless
+    /// transfer encoding is chunked
+    #[inline]
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        match self.kind {
+            TransferEncodingKind::Chunked(ref mut eof) => {
+                if !*eof {
+                    *eof = true;
+                    buf.extend_from_slice(b"0\r\n");
+                    for (key, value) in trailers.iter() {
+                        buf.extend_from_slice(key.as_str().as_bytes());
+                        buf.extend_from_slice(b": ");
+                        buf.extend_from_slice(value.as_bytes());
+                        buf.extend_from_slice(b"\r\n");
+                    }
+                    buf.extend_from_slice(b"\r\n");
+                }
+                Ok(())
+            }
+            _ => self.encode_eof(buf),
+        }
+    }
+}
+
+fn enqueue(msg: Bytes, buf: &mut BytesMut, queue: &mut VecDeque<Bytes>) {
+    if !msg.is_empty() {
+        if !buf.is_empty() {
+            queue.push_back(buf.split().freeze());
+        }
+        queue.push_back(msg);
+    }
 }
 
 struct Writer<'a>(pub &'a mut BytesMut);
@@ -527,7 +636,7 @@ mod tests {
     use http::header::AUTHORIZATION;
 
     use super::*;
-    use crate::http::header::{HeaderValue, CONTENT_TYPE};
+    use crate::http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
     use crate::RequestHead;
 
     #[test]
@@ -544,6 +653,85 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_chunked_trailers() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            HeaderName::from_static("x-checksum"),
+            HeaderValue::from_static("abc"),
+        );
+
+        let mut bytes = BytesMut::new();
+        let mut enc = TransferEncoding::chunked();
+        assert!(!enc.encode(b"test", &mut bytes).unwrap());
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        assert_eq!(
+            bytes.split().freeze(),
+            Bytes::from_static(b"4\r\ntest\r\n0\r\nx-checksum: abc\r\n\r\n")
+        );
+
+        // trailers are dropped without chunked encoding
+        let mut enc = TransferEncoding::length(4);
+        assert!(enc.encode(b"test", &mut bytes).unwrap());
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        assert_eq!(bytes.split().freeze(), Bytes::from_static(b"test"));
+    }
+
+    #[test]
+    fn test_vectored_te() {
+        let mut bytes = BytesMut::from("HTTP/1.1 200 OK\r\n\r\n");
+        let mut queue = VecDeque::new();
+        let mut enc = TransferEncoding::chunked();
+        let body = Bytes::from_static(b"test");
+        assert!(!enc
+            .encode_vectored(body.clone(), &mut bytes, &mut queue)
+            .unwrap());
+        assert_eq!(queue.len(), 2);
+        assert_eq!(
+            queue[0],
+            Bytes::from_static(b"HTTP/1.1 200 OK\r\n\r\n4\r\n")
+        );
+        assert_eq!(queue[1].as_ptr(), body.as_ptr());
+        assert!(enc
+            .encode_vectored(Bytes::new(), &mut bytes, &mut queue)
+            .unwrap());
+        assert_eq!(queue.len(), 2);
+        assert_eq!(bytes.split().freeze(), Bytes::from_static(b"\r\n0\r\n\r\n"));
+
+        let mut queue = VecDeque::new();
+        let mut enc = TransferEncoding::length(6);
+        assert!(!enc
+            .encode_vectored(Bytes::from_static(b"test"), &mut bytes, &mut queue)
+            .unwrap());
+        assert!(enc
+            .encode_vectored(Bytes::from_static(b"test"), &mut bytes, &mut queue)
+            .unwrap());
+        assert!(bytes.is_empty());
+        assert_eq!(
+            queue,
+            vec![Bytes::from_static(b"test"), Bytes::from_static(b"te")]
+        );
+    }
+
+    #[test]
+    fn test_no_date_header() {
+        let mut bytes = BytesMut::with_capacity(2048);
+        let mut head = RequestHeadType::Owned(RequestHead::default());
+        let config = ServiceConfig::default().with_date_header(false);
+
+        let _ = head.encode_headers(
+            &mut bytes,
+            Version::HTTP_11,
+            BodySize::Empty,
+            ConnectionType::KeepAlive,
+            &config,
+        );
+        let data =
+            String::from_utf8(Vec::from(bytes.split().freeze().as_ref())).unwrap();
+        assert!(!data.contains("date: "));
+        assert!(data.ends_with("content-length: 0\r\n\r\n"));
+    }
+
     #[test]
     fn test_camel_case() {
         let mut bytes = BytesMut::with_capacity(2048);
@@ -0,0 +1,163 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::task::Waker;
+
+use bytes::BytesMut;
+use http::header::{HeaderName, HeaderValue, LINK};
+use http::StatusCode;
+
+use crate::header::HeaderMap;
+
+/// Sender of informational `1xx` responses of a http/1.1 request.
+///
+/// H1 dispatcher stores `Informational` in extensions of received http/1.1
+/// requests. Informational responses are written to the connection while
+/// the service prepares the final response, i.e. `103 Early Hints` let
+/// clients preload resources of the page.
+///
+/// ```rust
+/// use actix_http::h1::Informational;
+/// use actix_http::{HttpMessage, Request, Response};
+///
+/// async fn index(req: Request) -> Result<Response, actix_http::Error> {
+///     if let Some(info) = req.extensions().get::<Informational>() {
+///         info.early_hints(&["</style.css>; rel=preload; as=style"]);
+///     }
+///     Ok(Response::Ok().body("<link rel=\"stylesheet\" href=\"/style.css\">"))
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Informational(Rc<RefCell<Inner>>);
+
+struct Inner {
+    queued: Vec<(StatusCode, Vec<(HeaderName, HeaderValue)>)>,
+    finished: bool,
+    waker: Option<Waker>,
+}
+
+impl Informational {
+    pub(crate) fn new() -> Self {
+        Informational(Rc::new(RefCell::new(Inner {
+            queued: Vec::new(),
+            finished: false,
+            waker: None,
+        })))
+    }
+
+    /// Send informational response with the headers.
+    ///
+    /// Returns false if status is not informational, `101 Switching
+    /// Protocols` is not allowed as well, or final response is already
+    /// sent.
+    pub fn send(&self, status: StatusCode, headers: HeaderMap) -> bool {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        self.send_headers(status, headers)
+    }
+
+    fn send_headers(
+        &self,
+        status: StatusCode,
+        headers: Vec<(HeaderName, HeaderValue)>,
+    ) -> bool {
+        if !status.is_informational() || status == StatusCode::SWITCHING_PROTOCOLS {
+            return false;
+        }
+
+        let mut inner = self.0.borrow_mut();
+        if inner.finished {
+            return false;
+        }
+        inner.queued.push((status, headers));
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+
+    /// Send `103 Early Hints` response with `Link` header for each of the
+    /// links.
+    ///
+    /// Returns false if a link is not a valid header value or final response
+    /// is already sent.
+    pub fn early_hints<S: AsRef<str>>(&self, links: &[S]) -> bool {
+        let mut headers = Vec::with_capacity(links.len());
+        for link in links {
+            match HeaderValue::from_str(link.as_ref()) {
+                Ok(value) => headers.push((LINK, value)),
+                Err(_) => return false,
+            }
+        }
+        self.send_headers(StatusCode::from_u16(103).unwrap(), headers)
+    }
+
+    /// Write queued responses to the buffer, dispatcher is woken up by
+    /// responses sent afterwards.
+    pub(crate) fn encode(&self, dst: &mut BytesMut, waker: &Waker) {
+        let mut inner = self.0.borrow_mut();
+        for (status, headers) in inner.queued.drain(..) {
+            let reason = match status.as_u16() {
+                103 => "Early Hints",
+                _ => status.canonical_reason().unwrap_or(""),
+            };
+            dst.extend_from_slice(b"HTTP/1.1 ");
+            dst.extend_from_slice(status.as_str().as_bytes());
+            dst.extend_from_slice(b" ");
+            dst.extend_from_slice(reason.as_bytes());
+            dst.extend_from_slice(b"\r\n");
+            for (name, value) in headers {
+                dst.extend_from_slice(name.as_str().as_bytes());
+                dst.extend_from_slice(b": ");
+                dst.extend_from_slice(value.as_bytes());
+                dst.extend_from_slice(b"\r\n");
+            }
+            dst.extend_from_slice(b"\r\n");
+        }
+        inner.waker = Some(waker.clone());
+    }
+
+    /// Write queued responses before the final response, no more responses
+    /// are accepted afterwards.
+    pub(crate) fn finish(&self, dst: &mut BytesMut, waker: &Waker) {
+        self.encode(dst, waker);
+        let mut inner = self.0.borrow_mut();
+        inner.finished = true;
+        inner.waker = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::task::noop_waker;
+
+    use super::*;
+
+    #[test]
+    fn test_informational() {
+        let waker = noop_waker();
+        let info = Informational::new();
+        assert!(!info.send(StatusCode::OK, HeaderMap::new()));
+        assert!(!info.send(StatusCode::SWITCHING_PROTOCOLS, HeaderMap::new()));
+        assert!(!info.early_hints(&["</a.css>\n"]));
+
+        assert!(info.early_hints(&["</a.css>; rel=preload", "</b.js>; rel=preload"]));
+        assert!(info.send(StatusCode::PROCESSING, HeaderMap::new()));
+
+        let mut buf = BytesMut::new();
+        info.encode(&mut buf, &waker);
+        assert_eq!(
+            &buf[..],
+            &b"HTTP/1.1 103 Early Hints\r\n\
+               link: </a.css>; rel=preload\r\n\
+               link: </b.js>; rel=preload\r\n\r\n\
+               HTTP/1.1 102 Processing\r\n\r\n"[..]
+        );
+
+        buf.clear();
+        info.finish(&mut buf, &waker);
+        assert!(buf.is_empty());
+        assert!(!info.early_hints(&["</a.css>; rel=preload"]));
+    }
+}
@@ -7,6 +7,7 @@ mod decoder;
 mod dispatcher;
 mod encoder;
 mod expect;
+mod informational;
 mod payload;
 mod service;
 mod upgrade;
@@ -14,8 +15,11 @@ mod utils;
 
 pub use self::client::{ClientCodec, ClientPayloadCodec};
 pub use self::codec::Codec;
-pub use self::dispatcher::Dispatcher;
+pub(crate) use self::codec::LegacyOptions;
+pub(crate) use self::decoder::HeadLimits;
+pub use self::dispatcher::{hibernated_connections, Dispatcher};
 pub use self::expect::ExpectHandler;
+pub use self::informational::Informational;
 pub use self::payload::Payload;
 pub use self::service::{H1Service, H1ServiceHandler, OneRequest};
 pub use self::upgrade::UpgradeHandler;
@@ -1,7 +1,6 @@
 use std::future::Future;
 use std::marker::PhantomData;
 use std::pin::Pin;
-use std::rc::Rc;
 use std::task::{Context, Poll};
 use std::{fmt, net};
 
@@ -9,13 +8,13 @@ use actix_codec::{AsyncRead, AsyncWrite, Framed};
 use actix_rt::net::TcpStream;
 use actix_service::{pipeline_factory, IntoServiceFactory, Service, ServiceFactory};
 use futures_core::ready;
-use futures_util::future::{ok, Ready};
+use futures_util::future::{ok, Either, Ready};
 
 use crate::body::MessageBody;
 use crate::cloneable::CloneableService;
 use crate::config::ServiceConfig;
 use crate::error::{DispatchError, Error, ParseError};
-use crate::helpers::DataFactory;
+use crate::helpers::OnConnect;
 use crate::request::Request;
 use crate::response::Response;
 
@@ -29,7 +28,7 @@ pub struct H1Service<T, S, B, X = ExpectHandler, U = UpgradeHandler<T>> {
     cfg: ServiceConfig,
     expect: X,
     upgrade: Option<U>,
-    on_connect: Option<Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+    on_connect: Option<OnConnect<T>>,
     _t: PhantomData<(T, B)>,
 }
 
@@ -234,10 +233,7 @@ where
     }
 
     /// Set on connect callback.
-    pub(crate) fn on_connect(
-        mut self,
-        f: Option<Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
-    ) -> Self {
+    pub(crate) fn on_connect(mut self, f: Option<OnConnect<T>>) -> Self {
         self.on_connect = f;
         self
     }
@@ -302,7 +298,7 @@ where
     fut_upg: Option<U::Future>,
     expect: Option<X::Service>,
     upgrade: Option<U::Service>,
-    on_connect: Option<Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+    on_connect: Option<OnConnect<T>>,
     cfg: Option<ServiceConfig>,
     _t: PhantomData<(T, B)>,
 }
@@ -368,7 +364,7 @@ pub struct H1ServiceHandler<T, S: Service, B, X: Service, U: Service> {
     srv: CloneableService<S>,
     expect: CloneableService<X>,
     upgrade: Option<CloneableService<U>>,
-    on_connect: Option<Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+    on_connect: Option<OnConnect<T>>,
     cfg: ServiceConfig,
     _t: PhantomData<(T, B)>,
 }
@@ -389,7 +385,7 @@ where
         srv: S,
         expect: X,
         upgrade: Option<U>,
-        on_connect: Option<Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+        on_connect: Option<OnConnect<T>>,
     ) -> H1ServiceHandler<T, S, B, X, U> {
         H1ServiceHandler {
             srv: CloneableService::new(srv),
@@ -417,7 +413,7 @@ where
     type Request = (T, Option<net::SocketAddr>);
     type Response = ();
     type Error = DispatchError;
-    type Future = Dispatcher<T, S, B, X, U>;
+    type Future = Either<Ready<Result<(), DispatchError>>, Dispatcher<T, S, B, X, U>>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         let ready = self
@@ -462,13 +458,15 @@ where
     }
 
     fn call(&mut self, (io, addr): Self::Request) -> Self::Future {
-        let on_connect = if let Some(ref on_connect) = self.on_connect {
-            Some(on_connect(&io))
-        } else {
-            None
+        let on_connect = match self.on_connect {
+            Some(ref on_connect) => match on_connect(&io, addr) {
+                Some(data) => Some(data),
+                None => return Either::Left(ok(())),
+            },
+            None => None,
         };
 
-        Dispatcher::new(
+        Either::Right(Dispatcher::new(
             io,
             self.cfg.clone(),
             self.srv.clone(),
@@ -476,7 +474,7 @@ where
             self.upgrade.clone(),
             on_connect,
             addr,
-        )
+        ))
     }
 }
 
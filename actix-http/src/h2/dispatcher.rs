@@ -1,30 +1,41 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::convert::TryFrom;
 use std::future::Future;
 use std::marker::PhantomData;
 use std::net;
 use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
 
 use actix_codec::{AsyncRead, AsyncWrite};
-use actix_rt::time::{Delay, Instant};
+use actix_rt::time::{delay_until, Delay, Instant};
 use actix_service::Service;
 use bytes::{Bytes, BytesMut};
-use h2::server::{Connection, SendResponse};
-use h2::SendStream;
-use http::header::{HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
+use h2::server::{Connection, SendPushedResponse, SendResponse};
+use h2::{Ping, PingPong, Reason, SendStream};
+use http::header::{
+    HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, RETRY_AFTER, TRANSFER_ENCODING,
+};
 use log::{error, trace};
 
+use crate::bandwidth::{BandwidthLimit, TokenBucket, WriteLimit};
 use crate::body::{BodySize, MessageBody, ResponseBody};
 use crate::cloneable::CloneableService;
-use crate::config::ServiceConfig;
+use crate::config::{RateLimiter, ServiceConfig};
 use crate::error::{DispatchError, Error};
+use crate::header::Priority;
 use crate::helpers::DataFactory;
 use crate::httpmessage::HttpMessage;
+use crate::load_shed::{monitor_loop_lag, retry_after_secs, Counter, Tracked};
 use crate::message::ResponseHead;
 use crate::payload::Payload;
 use crate::request::Request;
 use crate::response::Response;
 
+use super::ServerPush;
+
 const CHUNK_SIZE: usize = 16_384;
 
 /// Dispatcher for HTTP/2 protocol
@@ -40,6 +51,20 @@ where
     peer_addr: Option<net::SocketAddr>,
     ka_expire: Instant,
     ka_timer: Option<Delay>,
+    ping: Option<KeepAlivePing>,
+    rate_limiter: Option<RateLimiter>,
+    scheduler: Scheduler,
+    write_bucket: Option<Rc<RefCell<TokenBucket>>>,
+    pushes: PushQueue,
+    active: ActiveStreams,
+    queued: VecDeque<(
+        Request,
+        SendResponse<Bytes>,
+        ServerPush,
+        StreamSlot,
+        Tracked,
+    )>,
+    _connection: Tracked,
     _t: PhantomData<B>,
 }
 
@@ -54,12 +79,15 @@ where
 {
     pub(crate) fn new(
         service: CloneableService<S>,
-        connection: Connection<T, Bytes>,
+        mut connection: Connection<T, Bytes>,
         on_connect: Option<Box<dyn DataFactory>>,
         config: ServiceConfig,
         timeout: Option<Delay>,
         peer_addr: Option<net::SocketAddr>,
     ) -> Self {
+        // event loop lag is sampled on every worker, monitor adds reports
+        monitor_loop_lag(config.health_monitor());
+
         // let keepalive = config.keep_alive_enabled();
         // let flags = if keepalive {
         // Flags::KEEPALIVE | Flags::KEEPALIVE_ENABLED
@@ -76,14 +104,34 @@ where
             (config.now(), None)
         };
 
+        // keep-alive pings
+        let settings = config.h2_settings();
+        let ping = settings.ping_interval.and_then(|interval| {
+            connection.ping_pong().map(|ping_pong| KeepAlivePing {
+                ping_pong,
+                interval,
+                timeout: settings.ping_timeout,
+                timer: delay_until(Instant::now() + interval),
+                sent: false,
+            })
+        });
+
         Dispatcher {
             service,
+            rate_limiter: config.rate_limiter(),
+            write_bucket: config.write_bucket(),
             config,
             peer_addr,
             connection,
             on_connect,
             ka_expire,
             ka_timer,
+            ping,
+            scheduler: Scheduler::default(),
+            pushes: PushQueue::default(),
+            active: ActiveStreams::default(),
+            queued: VecDeque::new(),
+            _connection: Tracked::new(Counter::Connections),
             _t: PhantomData,
         }
     }
@@ -104,11 +152,79 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
+        if let Some(ref mut ping) = this.ping {
+            if !ping.poll_alive(cx) {
+                trace!("Keep-alive ping timed out, closing http/2 connection");
+                return Poll::Ready(Ok(()));
+            }
+        }
+
+        // dispatch promised requests, pushed streams are counted towards
+        // stream limits and wait for processing of earlier streams as well
+        let limit = this.config.h2_max_streams();
+        while this.pushes.poll_ready(cx)
+            && (limit == 0 || this.active.poll_acquire(limit, cx))
+        {
+            let (req, mut send) = this.pushes.pop().unwrap();
+            let mut slot = StreamSlot::default();
+            if limit != 0 {
+                slot.connection = Some(this.active.clone());
+            }
+            let peer_limit = this.config.h2_max_peer_streams();
+            if let Some(addr) = this.peer_addr.filter(|_| peer_limit != 0) {
+                let ip = addr.ip();
+                if !this.config.acquire_peer_stream(ip) {
+                    trace!("Peer stream limit exceeded");
+                    send.send_reset(Reason::REFUSED_STREAM);
+                    continue;
+                }
+                slot.peer = Some((this.config.clone(), ip));
+            }
+            this.dispatch(req, Responder::Pushed(send), None, slot);
+        }
+
+        // dispatch queued streams once processing of earlier streams completes
+        while !this.queued.is_empty() && this.active.poll_acquire(limit, cx) {
+            let (req, res, push, mut slot, _) = this.queued.pop_front().unwrap();
+            slot.connection = Some(this.active.clone());
+            this.dispatch(req, Responder::Request(res), Some(push), slot);
+        }
+
         loop {
-            match Pin::new(&mut this.connection).poll_accept(cx) {
+            match phase!("read", Pin::new(&mut this.connection).poll_accept(cx)) {
                 Poll::Ready(None) => return Poll::Ready(Ok(())),
                 Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err.into())),
-                Poll::Ready(Some(Ok((req, res)))) => {
+                Poll::Ready(Some(Ok((req, mut res)))) => {
+                    if let Some(ref mut limiter) = this.rate_limiter {
+                        if !limiter.acquire(this.config.now()) {
+                            trace!("Request rate limit exceeded");
+                            let mut head = http::Response::new(());
+                            *head.status_mut() = http::StatusCode::TOO_MANY_REQUESTS;
+                            head.headers_mut()
+                                .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+                            if let Err(e) = res.send_response(head, true) {
+                                trace!("Error sending h2 response: {:?}", e);
+                            }
+                            if this.config.close_on_rate_limit() {
+                                this.connection.graceful_shutdown();
+                            }
+                            continue;
+                        }
+                    }
+
+                    let policy = this.config.load_shed();
+                    if let Some(retry_after) = policy.and_then(|p| p.check()) {
+                        trace!("Request is rejected by load shed policy");
+                        let mut head = http::Response::new(());
+                        *head.status_mut() = http::StatusCode::SERVICE_UNAVAILABLE;
+                        head.headers_mut()
+                            .insert(RETRY_AFTER, retry_after_secs(retry_after).into());
+                        if let Err(e) = res.send_response(head, true) {
+                            trace!("Error sending h2 response: {:?}", e);
+                        }
+                        continue;
+                    }
+
                     // update keep-alive expire
                     if this.ka_timer.is_some() {
                         if let Some(expire) = this.config.keep_alive_expire() {
@@ -128,27 +244,37 @@ where
                     head.method = parts.method;
                     head.version = parts.version;
                     head.headers = parts.headers.into();
-                    head.peer_addr = this.peer_addr;
 
-                    // set on_connect data
-                    if let Some(ref on_connect) = this.on_connect {
-                        on_connect.set(&mut req.extensions_mut());
+                    let push = ServerPush::new(req.head());
+                    req.extensions_mut().insert(push.clone());
+
+                    let mut slot = StreamSlot::default();
+                    let peer_limit = this.config.h2_max_peer_streams();
+                    if let Some(addr) = this.peer_addr.filter(|_| peer_limit != 0) {
+                        let ip = addr.ip();
+                        if !this.config.acquire_peer_stream(ip) {
+                            trace!("Peer stream limit exceeded");
+                            res.send_reset(Reason::REFUSED_STREAM);
+                            continue;
+                        }
+                        slot.peer = Some((this.config.clone(), ip));
                     }
 
-                    actix_rt::spawn(ServiceResponse::<
-                        S::Future,
-                        S::Response,
-                        S::Error,
-                        B,
-                    > {
-                        state: ServiceResponseState::ServiceCall(
-                            this.service.call(req),
-                            Some(res),
-                        ),
-                        config: this.config.clone(),
-                        buffer: None,
-                        _t: PhantomData,
-                    });
+                    if limit != 0 {
+                        if this.queued.is_empty() && this.active.poll_acquire(limit, cx)
+                        {
+                            slot.connection = Some(this.active.clone());
+                        } else if this.queued.len() < limit {
+                            let queued = Tracked::new(Counter::Queued);
+                            this.queued.push_back((req, res, push, slot, queued));
+                            continue;
+                        } else {
+                            trace!("Connection stream limit exceeded");
+                            res.send_reset(Reason::REFUSED_STREAM);
+                            continue;
+                        }
+                    }
+                    this.dispatch(req, Responder::Request(res), Some(push), slot);
                 }
                 Poll::Pending => return Poll::Pending,
             }
@@ -156,18 +282,71 @@ where
     }
 }
 
+impl<T, S, B> Dispatcher<T, S, B>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    S: Service<Request = Request>,
+    S::Error: Into<Error> + 'static,
+    S::Future: 'static,
+    S::Response: Into<Response<B>> + 'static,
+    B: MessageBody + 'static,
+{
+    /// Call the service and spawn response stream task.
+    fn dispatch(
+        &mut self,
+        mut req: Request,
+        send: Responder,
+        push: Option<ServerPush>,
+        slot: StreamSlot,
+    ) {
+        let head = req.head_mut();
+        head.peer_addr = self.peer_addr;
+        let urgency = Priority::from_headers(&head.headers)
+            .unwrap_or_default()
+            .urgency;
+
+        // set on_connect data
+        if let Some(ref on_connect) = self.on_connect {
+            on_connect.set(&mut req.extensions_mut());
+        }
+
+        actix_rt::spawn(ServiceResponse::<S::Future, S::Response, S::Error, B> {
+            state: ServiceResponseState::ServiceCall(self.service.call(req), Some(send)),
+            config: self.config.clone(),
+            buffer: None,
+            urgency,
+            scheduler: self.scheduler.clone(),
+            turn: None,
+            write_limit: WriteLimit::new(self.write_bucket.clone()),
+            push,
+            pushes: self.pushes.clone(),
+            _slot: slot,
+            _in_flight: Tracked::new(Counter::InFlight),
+            _t: PhantomData,
+        });
+    }
+}
+
 #[pin_project::pin_project]
 struct ServiceResponse<F, I, E, B> {
     #[pin]
     state: ServiceResponseState<F, B>,
     config: ServiceConfig,
     buffer: Option<Bytes>,
+    urgency: u8,
+    scheduler: Scheduler,
+    turn: Option<Turn>,
+    write_limit: WriteLimit,
+    push: Option<ServerPush>,
+    pushes: PushQueue,
+    _slot: StreamSlot,
+    _in_flight: Tracked,
     _t: PhantomData<(I, E)>,
 }
 
 #[pin_project::pin_project]
 enum ServiceResponseState<F, B> {
-    ServiceCall(#[pin] F, Option<SendResponse<Bytes>>),
+    ServiceCall(#[pin] F, Option<Responder>),
     SendPayload(SendStream<Bytes>, #[pin] ResponseBody<B>),
 }
 
@@ -228,7 +407,7 @@ where
         }
 
         // set date header
-        if !has_date {
+        if !has_date && self.config.date_header() {
             let mut bytes = BytesMut::with_capacity(29);
             self.config.set_date_header(&mut bytes);
             res.headers_mut().insert(DATE, unsafe {
@@ -255,78 +434,142 @@ where
 
         #[project]
         match this.state.project() {
-            ServiceResponseState::ServiceCall(call, send) => match call.poll(cx) {
-                Poll::Ready(Ok(res)) => {
-                    let (res, body) = res.into().replace_body(());
+            ServiceResponseState::ServiceCall(call, send) => {
+                match phase!("handle", call.poll(cx)) {
+                    Poll::Ready(Ok(res)) => {
+                        let (res, body) = res.into().replace_body(());
+                        if let Some(priority) = Priority::from_headers(res.headers()) {
+                            *this.urgency = priority.urgency;
+                        }
+                        let limit = res.extensions().get::<BandwidthLimit>().copied();
+                        this.write_limit.set_response(limit);
+
+                        let mut send = send.take().unwrap();
+                        if let (Some(push), Responder::Request(ref mut send)) =
+                            (this.push.take(), &mut send)
+                        {
+                            for promised in push.take() {
+                                let mut req = Request::new();
+                                let head = req.head_mut();
+                                head.uri = promised.uri().clone();
+                                head.method = promised.method().clone();
+                                head.version = http::Version::HTTP_2;
+                                head.headers = promised.headers().clone().into();
+
+                                match send.push_request(promised) {
+                                    Ok(pushed) => this.pushes.push(req, pushed),
+                                    Err(e) => {
+                                        trace!("Error sending h2 push promise: {:?}", e)
+                                    }
+                                }
+                            }
+                        }
 
-                    let mut send = send.take().unwrap();
-                    let mut size = body.size();
-                    let h2_res = self.as_mut().prepare_response(res.head(), &mut size);
-                    this = self.as_mut().project();
+                        let mut size = body.size();
+                        let h2_res = phase!(
+                            "encode",
+                            self.as_mut().prepare_response(res.head(), &mut size)
+                        );
+                        this = self.as_mut().project();
 
-                    let stream = match send.send_response(h2_res, size.is_eof()) {
-                        Err(e) => {
-                            trace!("Error sending h2 response: {:?}", e);
-                            return Poll::Ready(());
+                        let stream = match send.send_response(h2_res, size.is_eof()) {
+                            Err(e) => {
+                                trace!("Error sending h2 response: {:?}", e);
+                                return Poll::Ready(());
+                            }
+                            Ok(stream) => stream,
+                        };
+
+                        if size.is_eof() {
+                            Poll::Ready(())
+                        } else {
+                            this.state
+                                .set(ServiceResponseState::SendPayload(stream, body));
+                            self.poll(cx)
                         }
-                        Ok(stream) => stream,
-                    };
-
-                    if size.is_eof() {
-                        Poll::Ready(())
-                    } else {
-                        this.state
-                            .set(ServiceResponseState::SendPayload(stream, body));
-                        self.poll(cx)
                     }
-                }
-                Poll::Pending => Poll::Pending,
-                Poll::Ready(Err(e)) => {
-                    let res: Response = e.into().into();
-                    let (res, body) = res.replace_body(());
-
-                    let mut send = send.take().unwrap();
-                    let mut size = body.size();
-                    let h2_res = self.as_mut().prepare_response(res.head(), &mut size);
-                    this = self.as_mut().project();
-
-                    let stream = match send.send_response(h2_res, size.is_eof()) {
-                        Err(e) => {
-                            trace!("Error sending h2 response: {:?}", e);
-                            return Poll::Ready(());
+                    Poll::Pending => Poll::Pending,
+                    Poll::Ready(Err(e)) => {
+                        let res: Response = e.into().into();
+                        let (res, body) = res.replace_body(());
+
+                        // promised resources are not pushed with error responses
+                        if let Some(push) = this.push.take() {
+                            push.take();
                         }
-                        Ok(stream) => stream,
-                    };
 
-                    if size.is_eof() {
-                        Poll::Ready(())
-                    } else {
-                        this.state.set(ServiceResponseState::SendPayload(
-                            stream,
-                            body.into_body(),
-                        ));
-                        self.poll(cx)
+                        let mut send = send.take().unwrap();
+                        let mut size = body.size();
+                        let h2_res = phase!(
+                            "encode",
+                            self.as_mut().prepare_response(res.head(), &mut size)
+                        );
+                        this = self.as_mut().project();
+
+                        let stream = match send.send_response(h2_res, size.is_eof()) {
+                            Err(e) => {
+                                trace!("Error sending h2 response: {:?}", e);
+                                return Poll::Ready(());
+                            }
+                            Ok(stream) => stream,
+                        };
+
+                        if size.is_eof() {
+                            Poll::Ready(())
+                        } else {
+                            this.state.set(ServiceResponseState::SendPayload(
+                                stream,
+                                body.into_body(),
+                            ));
+                            self.poll(cx)
+                        }
                     }
                 }
-            },
+            }
             ServiceResponseState::SendPayload(ref mut stream, ref mut body) => loop {
                 loop {
                     if let Some(ref mut buffer) = this.buffer {
+                        // streams with higher urgency are written first,
+                        // blocked streams do not hold the turn
+                        if let Some(ref mut turn) = this.turn {
+                            turn.set_blocked(false);
+                        }
+                        if !this.scheduler.poll_turn(*this.urgency, cx) {
+                            stream.reserve_capacity(0);
+                            return Poll::Pending;
+                        }
+                        let max = match this.write_limit.poll_acquire(cx, buffer.len()) {
+                            Poll::Ready(max) => max,
+                            Poll::Pending => {
+                                stream.reserve_capacity(0);
+                                if let Some(ref mut turn) = this.turn {
+                                    turn.set_blocked(true);
+                                }
+                                return Poll::Pending;
+                            }
+                        };
+                        stream.reserve_capacity(std::cmp::min(max, CHUNK_SIZE));
+
                         match stream.poll_capacity(cx) {
-                            Poll::Pending => return Poll::Pending,
+                            Poll::Pending => {
+                                if let Some(ref mut turn) = this.turn {
+                                    turn.set_blocked(true);
+                                }
+                                return Poll::Pending;
+                            }
                             Poll::Ready(None) => return Poll::Ready(()),
                             Poll::Ready(Some(Ok(cap))) => {
-                                let len = buffer.len();
-                                let bytes = buffer.split_to(std::cmp::min(cap, len));
+                                let bytes = buffer.split_to(std::cmp::min(cap, max));
+                                this.write_limit.consume(bytes.len());
 
-                                if let Err(e) = stream.send_data(bytes, false) {
+                                if let Err(e) =
+                                    phase!("write", stream.send_data(bytes, false))
+                                {
                                     warn!("{:?}", e);
                                     return Poll::Ready(());
-                                } else if !buffer.is_empty() {
-                                    let cap = std::cmp::min(buffer.len(), CHUNK_SIZE);
-                                    stream.reserve_capacity(cap);
-                                } else {
+                                } else if buffer.is_empty() {
                                     this.buffer.take();
+                                    this.turn.take();
                                 }
                             }
                             Poll::Ready(Some(Err(e))) => {
@@ -338,16 +581,26 @@ where
                         match body.as_mut().poll_next(cx) {
                             Poll::Pending => return Poll::Pending,
                             Poll::Ready(None) => {
-                                if let Err(e) = stream.send_data(Bytes::new(), true) {
+                                let res = match body.as_mut().take_trailers() {
+                                    Some(trailers) => {
+                                        let mut map = http::HeaderMap::new();
+                                        for (key, value) in trailers.iter() {
+                                            map.append(key, value.clone());
+                                        }
+                                        stream.send_trailers(map)
+                                    }
+                                    None => stream.send_data(Bytes::new(), true),
+                                };
+                                if let Err(e) = res {
                                     warn!("{:?}", e);
                                 }
                                 return Poll::Ready(());
                             }
                             Poll::Ready(Some(Ok(chunk))) => {
-                                stream.reserve_capacity(std::cmp::min(
-                                    chunk.len(),
-                                    CHUNK_SIZE,
-                                ));
+                                if this.turn.is_none() {
+                                    *this.turn =
+                                        Some(this.scheduler.acquire(*this.urgency));
+                                }
                                 *this.buffer = Some(chunk);
                             }
                             Poll::Ready(Some(Err(e))) => {
@@ -361,3 +614,286 @@ where
         }
     }
 }
+
+/// Response stream of a received or a promised request.
+enum Responder {
+    Request(SendResponse<Bytes>),
+    Pushed(SendPushedResponse<Bytes>),
+}
+
+impl Responder {
+    fn send_response(
+        &mut self,
+        res: http::Response<()>,
+        end_of_stream: bool,
+    ) -> Result<SendStream<Bytes>, h2::Error> {
+        match self {
+            Responder::Request(send) => send.send_response(res, end_of_stream),
+            Responder::Pushed(send) => send.send_response(res, end_of_stream),
+        }
+    }
+}
+
+/// Keep-alive pings of a connection.
+struct KeepAlivePing {
+    ping_pong: PingPong,
+    interval: Duration,
+    timeout: Duration,
+    timer: Delay,
+    sent: bool,
+}
+
+impl KeepAlivePing {
+    /// Send ping once interval elapses, returns false if peer did not
+    /// answer the ping within timeout.
+    fn poll_alive(&mut self, cx: &mut Context<'_>) -> bool {
+        loop {
+            if self.sent {
+                match self.ping_pong.poll_pong(cx) {
+                    Poll::Ready(Ok(_)) => {
+                        self.sent = false;
+                        self.timer.reset(Instant::now() + self.interval);
+                        continue;
+                    }
+                    Poll::Ready(Err(e)) => {
+                        // connection errors are reported by the connection
+                        trace!("Error receiving h2 pong: {:?}", e);
+                        return true;
+                    }
+                    Poll::Pending => (),
+                }
+                return Pin::new(&mut self.timer).poll(cx).is_pending();
+            }
+
+            match Pin::new(&mut self.timer).poll(cx) {
+                Poll::Ready(()) => {
+                    if let Err(e) = self.ping_pong.send_ping(Ping::opaque()) {
+                        trace!("Error sending h2 ping: {:?}", e);
+                        return true;
+                    }
+                    self.sent = true;
+                    self.timer.reset(Instant::now() + self.timeout);
+                }
+                Poll::Pending => return true,
+            }
+        }
+    }
+}
+
+/// Number of streams processed by a connection.
+#[derive(Clone, Default)]
+struct ActiveStreams(Rc<RefCell<ActiveStreamsInner>>);
+
+#[derive(Default)]
+struct ActiveStreamsInner {
+    count: usize,
+    waker: Option<Waker>,
+}
+
+impl ActiveStreams {
+    /// Count new stream if connection is below the limit, register waker
+    /// otherwise.
+    fn poll_acquire(&self, limit: usize, cx: &mut Context<'_>) -> bool {
+        let mut inner = self.0.borrow_mut();
+        if inner.count < limit {
+            inner.count += 1;
+            true
+        } else {
+            inner.waker = Some(cx.waker().clone());
+            false
+        }
+    }
+
+    fn release(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.count -= 1;
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Stream counted towards concurrency limits, released on drop.
+#[derive(Default)]
+struct StreamSlot {
+    connection: Option<ActiveStreams>,
+    peer: Option<(ServiceConfig, net::IpAddr)>,
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        if let Some(ref active) = self.connection {
+            active.release();
+        }
+        if let Some((ref config, ip)) = self.peer {
+            config.release_peer_stream(ip);
+        }
+    }
+}
+
+/// Promised requests of a connection, waiting for dispatch.
+#[derive(Clone, Default)]
+struct PushQueue(Rc<RefCell<PushQueueInner>>);
+
+#[derive(Default)]
+struct PushQueueInner {
+    requests: VecDeque<(Request, SendPushedResponse<Bytes>)>,
+    waker: Option<Waker>,
+}
+
+impl PushQueue {
+    fn push(&self, req: Request, send: SendPushedResponse<Bytes>) {
+        let mut inner = self.0.borrow_mut();
+        inner.requests.push_back((req, send));
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Check if promised requests are waiting, register waker otherwise.
+    fn poll_ready(&self, cx: &mut Context<'_>) -> bool {
+        let mut inner = self.0.borrow_mut();
+        if inner.requests.is_empty() {
+            inner.waker = Some(cx.waker().clone());
+            false
+        } else {
+            true
+        }
+    }
+
+    fn pop(&self) -> Option<(Request, SendPushedResponse<Bytes>)> {
+        self.0.borrow_mut().requests.pop_front()
+    }
+}
+
+/// Write order of response streams of a connection.
+///
+/// Streams with buffered data are counted per urgency level, streams wait
+/// while any stream with higher urgency has data to write. Streams that wait
+/// for flow control capacity or bandwidth are not counted, so they do not
+/// stall streams with lower urgency.
+#[derive(Clone, Default)]
+struct Scheduler(Rc<RefCell<SchedulerInner>>);
+
+#[derive(Default)]
+struct SchedulerInner {
+    pending: [usize; 8],
+    waiters: Vec<Waker>,
+}
+
+impl Scheduler {
+    /// Mark stream as having data to write.
+    fn acquire(&self, urgency: u8) -> Turn {
+        let urgency = std::cmp::min(urgency, 7);
+        self.0.borrow_mut().pending[urgency as usize] += 1;
+        Turn {
+            scheduler: self.clone(),
+            urgency,
+            blocked: false,
+        }
+    }
+
+    /// Check if stream could write, register waker otherwise.
+    fn poll_turn(&self, urgency: u8, cx: &mut Context<'_>) -> bool {
+        let mut inner = self.0.borrow_mut();
+        let urgency = std::cmp::min(urgency, 7) as usize;
+        if inner.pending[..urgency].iter().all(|n| *n == 0) {
+            true
+        } else {
+            if !inner.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+                inner.waiters.push(cx.waker().clone());
+            }
+            false
+        }
+    }
+}
+
+/// Buffered data of a stream, released once data is written or stream
+/// is dropped.
+struct Turn {
+    scheduler: Scheduler,
+    urgency: u8,
+    blocked: bool,
+}
+
+impl Turn {
+    /// Stream could not write buffered data, let other streams write.
+    fn set_blocked(&mut self, blocked: bool) {
+        if self.blocked == blocked {
+            return;
+        }
+        self.blocked = blocked;
+
+        let mut inner = self.scheduler.0.borrow_mut();
+        if blocked {
+            inner.pending[self.urgency as usize] -= 1;
+            for waker in inner.waiters.drain(..) {
+                waker.wake();
+            }
+        } else {
+            inner.pending[self.urgency as usize] += 1;
+        }
+    }
+}
+
+impl Drop for Turn {
+    fn drop(&mut self) {
+        if !self.blocked {
+            let mut inner = self.scheduler.0.borrow_mut();
+            inner.pending[self.urgency as usize] -= 1;
+            for waker in inner.waiters.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::task::noop_waker;
+
+    use super::*;
+
+    #[test]
+    fn test_scheduler() {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let scheduler = Scheduler::default();
+
+        let css = scheduler.acquire(0);
+        let image = scheduler.acquire(5);
+        assert!(scheduler.poll_turn(0, &mut cx));
+        assert!(!scheduler.poll_turn(5, &mut cx));
+        assert!(!scheduler.poll_turn(1, &mut cx));
+        assert_eq!(scheduler.0.borrow().waiters.len(), 1);
+
+        drop(css);
+        assert!(scheduler.0.borrow().waiters.is_empty());
+        assert!(scheduler.poll_turn(5, &mut cx));
+        assert!(!scheduler.poll_turn(6, &mut cx));
+
+        drop(image);
+        assert!(scheduler.poll_turn(7, &mut cx));
+    }
+
+    #[test]
+    fn test_scheduler_blocked() {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let scheduler = Scheduler::default();
+
+        // stream with high urgency is stalled by flow control
+        let mut css = scheduler.acquire(0);
+        assert!(!scheduler.poll_turn(5, &mut cx));
+        css.set_blocked(true);
+        assert!(scheduler.0.borrow().waiters.is_empty());
+        assert!(scheduler.poll_turn(5, &mut cx));
+
+        css.set_blocked(false);
+        assert!(!scheduler.poll_turn(5, &mut cx));
+        css.set_blocked(true);
+        css.set_blocked(true);
+        drop(css);
+        assert_eq!(scheduler.0.borrow().pending, [0; 8]);
+    }
+}
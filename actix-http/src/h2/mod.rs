@@ -1,18 +1,71 @@
 //! HTTP/2 implementation
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
+use actix_codec::{AsyncRead, AsyncWrite};
 use bytes::Bytes;
 use futures_core::Stream;
+use h2::server::{Builder, Handshake};
 use h2::RecvStream;
 
 mod dispatcher;
+mod push;
 mod service;
 
 pub use self::dispatcher::Dispatcher;
+pub use self::push::ServerPush;
 pub use self::service::H2Service;
 use crate::error::PayloadError;
 
+/// Transport settings of http/2 connections, unset values use defaults of
+/// the h2 crate.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct H2Settings {
+    pub(crate) max_concurrent_streams: Option<u32>,
+    pub(crate) initial_window_size: Option<u32>,
+    pub(crate) initial_connection_window_size: Option<u32>,
+    pub(crate) max_frame_size: Option<u32>,
+    pub(crate) ping_interval: Option<Duration>,
+    pub(crate) ping_timeout: Duration,
+}
+
+impl Default for H2Settings {
+    fn default() -> Self {
+        H2Settings {
+            max_concurrent_streams: None,
+            initial_window_size: None,
+            initial_connection_window_size: None,
+            max_frame_size: None,
+            ping_interval: None,
+            ping_timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+impl H2Settings {
+    /// Start server handshake of the connection.
+    pub(crate) fn handshake<T>(&self, io: T) -> Handshake<T, Bytes>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut builder = Builder::new();
+        if let Some(max) = self.max_concurrent_streams {
+            builder.max_concurrent_streams(max);
+        }
+        if let Some(size) = self.initial_window_size {
+            builder.initial_window_size(size);
+        }
+        if let Some(size) = self.initial_connection_window_size {
+            builder.initial_connection_window_size(size);
+        }
+        if let Some(max) = self.max_frame_size {
+            builder.max_frame_size(max);
+        }
+        builder.handshake(io)
+    }
+}
+
 /// H2 receive stream
 pub struct Payload {
     pl: RecvStream,
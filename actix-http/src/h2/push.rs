@@ -0,0 +1,120 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use http::header::{ACCEPT_ENCODING, ACCEPT_LANGUAGE};
+use http::uri::{PathAndQuery, Uri};
+use http::Method;
+
+use crate::message::RequestHead;
+
+/// Server push of a http/2 request.
+///
+/// H2 dispatcher stores `ServerPush` in extensions of received requests.
+/// Promised requests are sent to the client along with headers of the
+/// response, then dispatched to the service as regular `GET` requests and
+/// their responses are sent on pushed streams.
+///
+/// ```rust
+/// use actix_http::h2::ServerPush;
+/// use actix_http::{HttpMessage, Request, Response};
+///
+/// async fn index(req: Request) -> Result<Response, actix_http::Error> {
+///     if let Some(push) = req.extensions().get::<ServerPush>() {
+///         push.push("/style.css");
+///     }
+///     Ok(Response::Ok().body("<link rel=\"stylesheet\" href=\"/style.css\">"))
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ServerPush(Rc<RefCell<Inner>>);
+
+struct Inner {
+    uri: Uri,
+    headers: http::HeaderMap,
+    promised: Vec<http::Request<()>>,
+    sent: bool,
+}
+
+impl ServerPush {
+    pub(crate) fn new(head: &RequestHead) -> Self {
+        // content negotiation headers apply to pushed resources as well
+        let mut headers = http::HeaderMap::new();
+        for name in &[ACCEPT_ENCODING, ACCEPT_LANGUAGE] {
+            for value in head.headers.get_all(name) {
+                headers.append(name, value.clone());
+            }
+        }
+
+        ServerPush(Rc::new(RefCell::new(Inner {
+            uri: head.uri.clone(),
+            headers,
+            promised: Vec::new(),
+            sent: false,
+        })))
+    }
+
+    /// Promise `GET` request of the `path` to the client.
+    ///
+    /// Path could contain query string. Returns false if the path is not
+    /// valid or response headers are already sent.
+    pub fn push(&self, path: &str) -> bool {
+        let mut inner = self.0.borrow_mut();
+        if inner.sent || !path.starts_with('/') {
+            return false;
+        }
+        let path = match path.parse::<PathAndQuery>() {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+
+        let mut parts = inner.uri.clone().into_parts();
+        parts.path_and_query = Some(path);
+        let uri = match Uri::from_parts(parts) {
+            Ok(uri) => uri,
+            Err(_) => return false,
+        };
+
+        let mut req = http::Request::new(());
+        *req.method_mut() = Method::GET;
+        *req.uri_mut() = uri;
+        *req.headers_mut() = inner.headers.clone();
+        inner.promised.push(req);
+        true
+    }
+
+    /// Take promised requests, no more pushes are accepted afterwards.
+    pub(crate) fn take(&self) -> Vec<http::Request<()>> {
+        let mut inner = self.0.borrow_mut();
+        inner.sent = true;
+        inner.promised.split_off(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HeaderValue;
+
+    #[test]
+    fn test_push() {
+        let mut head = RequestHead::default();
+        head.uri = Uri::from_static("https://example.com/index.html");
+        head.headers
+            .insert(ACCEPT_ENCODING, HeaderValue::from_static("gzip"));
+        let push = ServerPush::new(&head);
+
+        assert!(push.push("/style.css?v=1"));
+        assert!(!push.push("style.css"));
+        assert!(!push.push("/style css"));
+
+        let promised = push.take();
+        assert_eq!(promised.len(), 1);
+        assert_eq!(promised[0].method(), Method::GET);
+        assert_eq!(promised[0].uri(), "https://example.com/style.css?v=1");
+        assert_eq!(promised[0].headers().get(ACCEPT_ENCODING).unwrap(), "gzip");
+
+        // response is sent
+        assert!(!push.push("/script.js"));
+        assert!(push.take().is_empty());
+    }
+}
@@ -1,8 +1,8 @@
 use std::future::Future;
 use std::marker::PhantomData;
+use std::net;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use std::{net, rc};
 
 use actix_codec::{AsyncRead, AsyncWrite};
 use actix_rt::net::TcpStream;
@@ -13,14 +13,14 @@ use actix_service::{
 use bytes::Bytes;
 use futures_core::ready;
 use futures_util::future::ok;
-use h2::server::{self, Handshake};
+use h2::server::Handshake;
 use log::error;
 
 use crate::body::MessageBody;
 use crate::cloneable::CloneableService;
 use crate::config::ServiceConfig;
 use crate::error::{DispatchError, Error};
-use crate::helpers::DataFactory;
+use crate::helpers::{DataFactory, OnConnect};
 use crate::request::Request;
 use crate::response::Response;
 
@@ -30,7 +30,7 @@ use super::dispatcher::Dispatcher;
 pub struct H2Service<T, S, B> {
     srv: S,
     cfg: ServiceConfig,
-    on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+    on_connect: Option<OnConnect<T>>,
     _t: PhantomData<(T, B)>,
 }
 
@@ -56,10 +56,7 @@ where
     }
 
     /// Set on connect callback.
-    pub(crate) fn on_connect(
-        mut self,
-        f: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
-    ) -> Self {
+    pub(crate) fn on_connect(mut self, f: Option<OnConnect<T>>) -> Self {
         self.on_connect = f;
         self
     }
@@ -214,7 +211,7 @@ pub struct H2ServiceResponse<T, S: ServiceFactory, B> {
     #[pin]
     fut: S::Future,
     cfg: Option<ServiceConfig>,
-    on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+    on_connect: Option<OnConnect<T>>,
     _t: PhantomData<(T, B)>,
 }
 
@@ -247,7 +244,7 @@ where
 pub struct H2ServiceHandler<T, S: Service, B> {
     srv: CloneableService<S>,
     cfg: ServiceConfig,
-    on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+    on_connect: Option<OnConnect<T>>,
     _t: PhantomData<(T, B)>,
 }
 
@@ -261,7 +258,7 @@ where
 {
     fn new(
         cfg: ServiceConfig,
-        on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+        on_connect: Option<OnConnect<T>>,
         srv: S,
     ) -> H2ServiceHandler<T, S, B> {
         H2ServiceHandler {
@@ -296,10 +293,16 @@ where
     }
 
     fn call(&mut self, (io, addr): Self::Request) -> Self::Future {
-        let on_connect = if let Some(ref on_connect) = self.on_connect {
-            Some(on_connect(&io))
-        } else {
-            None
+        let on_connect = match self.on_connect {
+            Some(ref on_connect) => match on_connect(&io, addr) {
+                Some(data) => Some(data),
+                None => {
+                    return H2ServiceHandlerResponse {
+                        state: State::Rejected,
+                    }
+                }
+            },
+            None => None,
         };
 
         H2ServiceHandlerResponse {
@@ -308,7 +311,7 @@ where
                 Some(self.cfg.clone()),
                 addr,
                 on_connect,
-                server::handshake(io),
+                self.cfg.h2_settings().handshake(io),
             ),
         }
     }
@@ -327,6 +330,7 @@ where
         Option<Box<dyn DataFactory>>,
         Handshake<T, Bytes>,
     ),
+    Rejected,
 }
 
 pub struct H2ServiceHandlerResponse<T, S, B>
@@ -355,6 +359,7 @@ where
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         match self.state {
             State::Incoming(ref mut disp) => Pin::new(disp).poll(cx),
+            State::Rejected => Poll::Ready(Ok(())),
             State::Handshake(
                 ref mut srv,
                 ref mut config,
@@ -0,0 +1,45 @@
+use http::header::HeaderName;
+
+header! {
+    /// `Accept-CH` header, defined in
+    /// [RFC8942](https://tools.ietf.org/html/rfc8942#section-3.1)
+    ///
+    /// The `Accept-CH` response header field advertises client hints the
+    /// server is interested in. Clients send the hints with subsequent
+    /// requests to the origin.
+    ///
+    /// # ABNF
+    ///
+    /// ```text
+    /// Accept-CH = #field-name
+    /// ```
+    ///
+    /// # Example values
+    /// * `Sec-CH-DPR, Sec-CH-Width`
+    /// * `Save-Data`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use actix_http::Response;
+    /// use actix_http::http::header::{AcceptCh, HeaderName};
+    ///
+    /// let mut builder = Response::Ok();
+    /// builder.set(AcceptCh(vec![
+    ///     HeaderName::from_static("sec-ch-dpr"),
+    ///     HeaderName::from_static("save-data"),
+    /// ]));
+    /// ```
+    (AcceptCh, HeaderName::from_static("accept-ch")) => (HeaderName)*
+
+    test_accept_ch {
+        test_header!(
+            test1,
+            vec![b"sec-ch-dpr, sec-ch-width"],
+            Some(HeaderField(vec![
+                HeaderName::from_static("sec-ch-dpr"),
+                HeaderName::from_static("sec-ch-width"),
+            ])));
+        test_header!(test2, vec![b"Save-Data"]);
+    }
+}
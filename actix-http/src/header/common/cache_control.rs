@@ -30,10 +30,10 @@ use crate::header::{
 /// # Examples
 /// ```rust
 /// use actix_http::Response;
-/// use actix_http::http::header::{CacheControl, CacheDirective};
+/// use actix_http::http::header::CacheControl;
 ///
 /// let mut builder = Response::Ok();
-/// builder.set(CacheControl(vec![CacheDirective::MaxAge(86400u32)]));
+/// builder.insert_header(CacheControl::max_age(86400));
 /// ```
 ///
 /// ```rust
@@ -53,6 +53,29 @@ pub struct CacheControl(pub Vec<CacheDirective>);
 
 __hyper__deref!(CacheControl => Vec<CacheDirective>);
 
+impl CacheControl {
+    /// `max-age=secs`, response is fresh for `secs` seconds.
+    pub fn max_age(secs: u32) -> Self {
+        CacheControl(vec![CacheDirective::MaxAge(secs)])
+    }
+
+    /// `no-cache`, response has to be revalidated before reuse.
+    pub fn no_cache() -> Self {
+        CacheControl(vec![CacheDirective::NoCache])
+    }
+
+    /// `no-store`, response must not be stored by caches.
+    pub fn no_store() -> Self {
+        CacheControl(vec![CacheDirective::NoStore])
+    }
+
+    /// Add directive to the header.
+    pub fn with(mut self, directive: CacheDirective) -> Self {
+        self.0.push(directive);
+        self
+    }
+}
+
 //TODO: this could just be the header! macro
 impl Header for CacheControl {
     fn name() -> header::HeaderName {
@@ -248,6 +271,14 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_constructors() {
+        let cache = CacheControl::max_age(3600).with(CacheDirective::Public);
+        assert_eq!(cache.to_string(), "max-age=3600, public");
+        assert_eq!(CacheControl::no_cache().to_string(), "no-cache");
+        assert_eq!(CacheControl::no_store().to_string(), "no-store");
+    }
+
     #[test]
     fn test_parse_bad_syntax() {
         let req = TestRequest::with_header(header::CACHE_CONTROL, "foo=").finish();
@@ -1,3 +1,5 @@
+use std::time::{Duration, SystemTime};
+
 use crate::header::{HttpDate, EXPIRES};
 
 header! {
@@ -37,3 +39,16 @@ header! {
         test_header!(test1, vec![b"Thu, 01 Dec 1994 16:00:00 GMT"]);
     }
 }
+
+impl Expires {
+    /// `Expires` header with date after `duration` from now
+    pub fn after(duration: Duration) -> Expires {
+        Expires(HttpDate::after(duration))
+    }
+}
+
+impl From<SystemTime> for Expires {
+    fn from(time: SystemTime) -> Expires {
+        Expires(time.into())
+    }
+}
@@ -68,3 +68,20 @@ header! {
         test_header!(test3, vec![b"*"], Some(IfMatch::Any));
     }
 }
+
+impl IfMatch {
+    /// Check if the header matches current entity tag of the resource,
+    /// `None` if resource does not exist.
+    ///
+    /// `*` matches any existing resource, entity tags are compared with the
+    /// strong comparison function.
+    pub fn matches(&self, current: Option<&EntityTag>) -> bool {
+        match (self, current) {
+            (_, None) => false,
+            (IfMatch::Any, Some(_)) => true,
+            (IfMatch::Items(ref items), Some(etag)) => {
+                items.iter().any(|item| item.strong_eq(etag))
+            }
+        }
+    }
+}
@@ -63,6 +63,23 @@ header! {
     }
 }
 
+impl IfNoneMatch {
+    /// Check if the header matches current entity tag of the resource,
+    /// `None` if resource does not exist.
+    ///
+    /// `*` matches any existing resource, entity tags are compared with the
+    /// weak comparison function.
+    pub fn matches(&self, current: Option<&EntityTag>) -> bool {
+        match (self, current) {
+            (_, None) => false,
+            (IfNoneMatch::Any, Some(_)) => true,
+            (IfNoneMatch::Items(ref items), Some(etag)) => {
+                items.iter().any(|item| item.weak_eq(etag))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::IfNoneMatch;
@@ -89,4 +106,16 @@ mod tests {
         entities.push(weak_etag);
         assert_eq!(if_none_match.ok(), Some(IfNoneMatch::Items(entities)));
     }
+
+    #[test]
+    fn test_matches() {
+        let etag = EntityTag::strong("foobar".to_owned());
+
+        assert!(IfNoneMatch::Any.matches(Some(&etag)));
+        assert!(!IfNoneMatch::Any.matches(None));
+
+        let header = IfNoneMatch::Items(vec![EntityTag::weak("foobar".to_owned())]);
+        assert!(header.matches(Some(&etag)));
+        assert!(!header.matches(Some(&EntityTag::strong("other".to_owned()))));
+    }
 }
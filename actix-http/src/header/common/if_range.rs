@@ -1,4 +1,5 @@
 use std::fmt::{self, Display, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::error::ParseError;
 use crate::header::{
@@ -86,6 +87,33 @@ impl Header for IfRange {
     }
 }
 
+impl IfRange {
+    /// Check if the range request applies to current representation of the
+    /// resource, otherwise the whole representation has to be sent.
+    ///
+    /// Entity tags are compared with the strong comparison function, date
+    /// has to be equal to last modification date of the resource.
+    pub fn matches(
+        &self,
+        etag: Option<&EntityTag>,
+        last_modified: Option<&HttpDate>,
+    ) -> bool {
+        match (self, etag, last_modified) {
+            (IfRange::EntityTag(ref tag), Some(etag), _) => tag.strong_eq(etag),
+            (IfRange::Date(ref date), _, Some(modified)) => {
+                let t1: SystemTime = (*date).into();
+                let t2: SystemTime = (*modified).into();
+                // http dates have one second resolution
+                match (t1.duration_since(UNIX_EPOCH), t2.duration_since(UNIX_EPOCH)) {
+                    (Ok(t1), Ok(t2)) => t1.as_secs() == t2.as_secs(),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
 impl Display for IfRange {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -113,4 +141,25 @@ mod test_if_range {
     test_header!(test1, vec![b"Sat, 29 Oct 1994 19:43:31 GMT"]);
     test_header!(test2, vec![b"\"xyzzy\""]);
     test_header!(test3, vec![b"this-is-invalid"], None::<IfRange>);
+
+    #[test]
+    fn test_matches() {
+        let etag = EntityTag::strong("xyzzy".to_owned());
+        let date: HttpDate = "Sat, 29 Oct 1994 19:43:31 GMT".parse().unwrap();
+
+        let if_range = HeaderField::EntityTag(etag.clone());
+        assert!(if_range.matches(Some(&etag), None));
+        assert!(!if_range.matches(Some(&EntityTag::strong("other".to_owned())), None));
+        assert!(!if_range.matches(None, Some(&date)));
+
+        // weak tags are not used for ranges
+        let if_range = HeaderField::EntityTag(EntityTag::weak("xyzzy".to_owned()));
+        assert!(!if_range.matches(Some(&etag), None));
+
+        let if_range = HeaderField::Date(date);
+        assert!(if_range.matches(Some(&etag), Some(&date)));
+        let modified: HttpDate = "Sat, 29 Oct 1994 19:43:32 GMT".parse().unwrap();
+        assert!(!if_range.matches(Some(&etag), Some(&modified)));
+        assert!(!if_range.matches(Some(&etag), None));
+    }
 }
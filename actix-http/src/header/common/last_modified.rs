@@ -1,3 +1,5 @@
+use std::time::SystemTime;
+
 use crate::header::{HttpDate, LAST_MODIFIED};
 
 header! {
@@ -36,3 +38,9 @@ header! {
             // Test case from RFC
             test_header!(test1, vec![b"Sat, 29 Oct 1994 19:43:31 GMT"]);}
 }
+
+impl From<SystemTime> for LastModified {
+    fn from(time: SystemTime) -> LastModified {
+        LastModified(time.into())
+    }
+}
@@ -11,6 +11,7 @@ pub use self::accept_charset::AcceptCharset;
 //pub use self::accept_encoding::AcceptEncoding;
 pub use self::accept_language::AcceptLanguage;
 pub use self::accept::Accept;
+pub use self::accept_ch::AcceptCh;
 pub use self::allow::Allow;
 pub use self::cache_control::{CacheControl, CacheDirective};
 pub use self::content_disposition::{ContentDisposition, DispositionType, DispositionParam};
@@ -26,6 +27,8 @@ pub use self::if_none_match::IfNoneMatch;
 pub use self::if_range::IfRange;
 pub use self::if_unmodified_since::IfUnmodifiedSince;
 pub use self::last_modified::LastModified;
+pub use self::priority::Priority;
+pub use self::retry_after::RetryAfter;
 //pub use self::range::{Range, ByteRangeSpec};
 
 #[doc(hidden)]
@@ -335,6 +338,7 @@ mod accept_charset;
 //mod accept_encoding;
 mod accept_language;
 mod accept;
+mod accept_ch;
 mod allow;
 mod cache_control;
 mod content_disposition;
@@ -350,3 +354,5 @@ mod if_none_match;
 mod if_range;
 mod if_unmodified_since;
 mod last_modified;
+mod priority;
+mod retry_after;
@@ -0,0 +1,172 @@
+use std::fmt::{self, Display};
+
+use crate::error::ParseError;
+use crate::header::sfv::{BareItem, Dictionary, ListEntry};
+use crate::header::{
+    Header, HeaderMap, HeaderName, HeaderValue, IntoHeaderValue, InvalidHeaderValue,
+    Writer,
+};
+use crate::httpmessage::HttpMessage;
+
+/// `Priority` header, defined in
+/// [RFC9218](https://tools.ietf.org/html/rfc9218#section-5)
+///
+/// The `Priority` header field is a structured field dictionary carrying
+/// priority parameters of a response. Unknown or invalid parameters are
+/// ignored, missing parameters take their default values.
+///
+/// # ABNF
+///
+/// ```text
+/// Priority = sf-dictionary
+/// ```
+///
+/// # Example values
+///
+/// * `u=1`
+/// * `u=5, i`
+///
+/// # Examples
+///
+/// ```rust
+/// use actix_http::Response;
+/// use actix_http::http::header::Priority;
+///
+/// let mut builder = Response::Ok();
+/// builder.set(Priority {
+///     urgency: 1,
+///     incremental: true,
+/// });
+/// ```
+///
+/// Http/2 dispatcher writes data of responses with higher urgency first.
+/// Urgency is taken from the response header if it is set, otherwise from
+/// the request header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Priority {
+    /// Urgency from 0 (highest) to 7 (lowest), `u` parameter
+    pub urgency: u8,
+    /// Response could be processed incrementally, `i` parameter
+    pub incremental: bool,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority {
+            urgency: 3,
+            incremental: false,
+        }
+    }
+}
+
+impl Priority {
+    /// Parse `Priority` header of the header map.
+    ///
+    /// Returns `None` if the header is missing or malformed.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Priority> {
+        if headers.contains_key(Self::name()) {
+            Self::from_lines(headers.get_all(Self::name())).ok()
+        } else {
+            None
+        }
+    }
+
+    fn from_lines<'a, I>(lines: I) -> Result<Self, ParseError>
+    where
+        I: IntoIterator<Item = &'a HeaderValue>,
+    {
+        let dict = Dictionary::from_headers(lines)?;
+        let mut priority = Priority::default();
+        let bare_item = |key| {
+            dict.get(key)
+                .and_then(ListEntry::as_item)
+                .map(|item| &item.bare_item)
+        };
+        if let Some(BareItem::Integer(u @ 0..=7)) = bare_item("u") {
+            priority.urgency = *u as u8;
+        }
+        if let Some(BareItem::Boolean(i)) = bare_item("i") {
+            priority.incremental = *i;
+        }
+        Ok(priority)
+    }
+}
+
+impl Header for Priority {
+    fn name() -> HeaderName {
+        HeaderName::from_static("priority")
+    }
+
+    #[inline]
+    fn parse<T>(msg: &T) -> Result<Self, ParseError>
+    where
+        T: HttpMessage,
+    {
+        Self::from_lines(msg.headers().get_all(Self::name()))
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "u={}", self.urgency)?;
+        if self.incremental {
+            f.write_str(", i")?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoHeaderValue for Priority {
+    type Error = InvalidHeaderValue;
+
+    fn try_into(self) -> Result<HeaderValue, Self::Error> {
+        let mut writer = Writer::new();
+        let _ = fmt::Write::write_fmt(&mut writer, format_args!("{}", self));
+        HeaderValue::from_maybe_shared(writer.take())
+    }
+}
+
+#[cfg(test)]
+mod test_priority {
+    use super::Priority as HeaderField;
+    use crate::header::*;
+    use std::str;
+    test_header!(test1, vec![b"u=1"]);
+    test_header!(test2, vec![b"u=5, i"]);
+
+    test_header!(
+        test3,
+        vec![&b"u=2"[..], &b"i"[..]],
+        Some(HeaderField {
+            urgency: 2,
+            incremental: true,
+        })
+    );
+    test_header!(test4, vec![b"u=1,"], None::<HeaderField>);
+
+    #[test]
+    fn test_ignored_params() {
+        let req =
+            crate::test::TestRequest::with_header(HeaderField::name(), "u=9, x=(a b)")
+                .finish();
+        assert_eq!(HeaderField::parse(&req).unwrap(), HeaderField::default());
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(HeaderField::from_headers(&headers), None);
+
+        headers.insert(HeaderField::name(), HeaderValue::from_static("u=1,"));
+        assert_eq!(HeaderField::from_headers(&headers), None);
+
+        headers.insert(HeaderField::name(), HeaderValue::from_static("i"));
+        assert_eq!(
+            HeaderField::from_headers(&headers),
+            Some(HeaderField {
+                urgency: 3,
+                incremental: true,
+            })
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use crate::error::ParseError;
+use crate::header::{
+    self, from_one_raw_str, Header, HeaderName, HeaderValue, HttpDate, IntoHeaderValue,
+    InvalidHeaderValue,
+};
+use crate::httpmessage::HttpMessage;
+
+/// `Retry-After` header, defined in
+/// [RFC7231](http://tools.ietf.org/html/rfc7231#section-7.1.3)
+///
+/// The `Retry-After` header field indicates how long the user agent
+/// ought to wait before making a follow-up request.
+///
+/// # ABNF
+///
+/// ```text
+/// Retry-After = HTTP-date / delay-seconds
+/// ```
+///
+/// # Example values
+///
+/// * `Fri, 31 Dec 1999 23:59:59 GMT`
+/// * `120`
+///
+/// # Examples
+///
+/// ```rust
+/// use actix_http::Response;
+/// use actix_http::http::header::RetryAfter;
+/// use std::time::{Duration, SystemTime};
+///
+/// let mut builder = Response::ServiceUnavailable();
+/// builder.set(RetryAfter::Delay(Duration::from_secs(120)));
+///
+/// let mut builder = Response::ServiceUnavailable();
+/// let retry = SystemTime::now() + Duration::from_secs(60 * 60);
+/// builder.set(RetryAfter::Date(retry.into()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RetryAfter {
+    /// Delay, in whole seconds
+    Delay(Duration),
+    /// Date after which follow-up request could be made
+    Date(HttpDate),
+}
+
+impl Header for RetryAfter {
+    fn name() -> HeaderName {
+        header::RETRY_AFTER
+    }
+
+    #[inline]
+    fn parse<T>(msg: &T) -> Result<Self, ParseError>
+    where
+        T: HttpMessage,
+    {
+        let delay: Result<u64, _> =
+            from_one_raw_str(msg.headers().get(&header::RETRY_AFTER));
+        if let Ok(delay) = delay {
+            return Ok(RetryAfter::Delay(Duration::from_secs(delay)));
+        }
+        let date: Result<HttpDate, _> =
+            from_one_raw_str(msg.headers().get(&header::RETRY_AFTER));
+        if let Ok(date) = date {
+            return Ok(RetryAfter::Date(date));
+        }
+        Err(ParseError::Header)
+    }
+}
+
+impl Display for RetryAfter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RetryAfter::Delay(ref x) => Display::fmt(&x.as_secs(), f),
+            RetryAfter::Date(ref x) => Display::fmt(x, f),
+        }
+    }
+}
+
+impl IntoHeaderValue for RetryAfter {
+    type Error = InvalidHeaderValue;
+
+    fn try_into(self) -> Result<HeaderValue, Self::Error> {
+        match self {
+            RetryAfter::Delay(x) => Ok(HeaderValue::from(x.as_secs())),
+            RetryAfter::Date(x) => x.try_into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_retry_after {
+    use super::RetryAfter as HeaderField;
+    use crate::header::*;
+    use std::str;
+    test_header!(test1, vec![b"Fri, 31 Dec 1999 23:59:59 GMT"]);
+    test_header!(test2, vec![b"120"]);
+    test_header!(test3, vec![b"this-is-invalid"], None::<RetryAfter>);
+}
@@ -1,4 +1,4 @@
-use std::collections::hash_map::{self, Entry};
+use std::collections::hash_map;
 use std::convert::TryFrom;
 
 use either::Either;
@@ -48,6 +48,13 @@ impl Value {
             Value::Multi(ref mut vec) => vec.push(val),
         }
     }
+
+    fn into_vec(self) -> Vec<HeaderValue> {
+        match self {
+            Value::One(val) => vec![val],
+            Value::Multi(vec) => vec,
+        }
+    }
 }
 
 impl HeaderMap {
@@ -209,7 +216,7 @@ impl HeaderMap {
     /// the key and all previous values are removed. **Note** that only a single
     /// one of the previous values is returned. If there are multiple values
     /// that have been previously associated with the key, then the first one is
-    /// returned. See `insert_mult` on `OccupiedEntry` for an API that returns
+    /// returned. See `insert` on `OccupiedEntry` for an API that returns
     /// all values.
     ///
     /// The key is not updated, though; this matters for types that can be `==`
@@ -229,8 +236,8 @@ impl HeaderMap {
     /// identical.
     pub fn append(&mut self, key: HeaderName, value: HeaderValue) {
         match self.inner.entry(key) {
-            Entry::Occupied(mut entry) => entry.get_mut().append(value),
-            Entry::Vacant(entry) => {
+            hash_map::Entry::Occupied(mut entry) => entry.get_mut().append(value),
+            hash_map::Entry::Vacant(entry) => {
                 entry.insert(Value::One(value));
             }
         }
@@ -249,6 +256,158 @@ impl HeaderMap {
             }
         }
     }
+
+    /// Gets the given key's entry in the map for in-place manipulation of
+    /// all its values.
+    pub fn entry(&mut self, key: HeaderName) -> Entry<'_> {
+        match self.inner.entry(key) {
+            hash_map::Entry::Occupied(entry) => Entry::Occupied(OccupiedEntry(entry)),
+            hash_map::Entry::Vacant(entry) => Entry::Vacant(VacantEntry(entry)),
+        }
+    }
+
+    /// Retains only the headers specified by the predicate.
+    ///
+    /// Predicate is called once per value, key is removed once all of its
+    /// values are removed.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&HeaderName, &HeaderValue) -> bool,
+    {
+        self.inner.retain(|name, value| match value {
+            Value::One(ref val) => f(name, val),
+            Value::Multi(ref mut vec) => {
+                vec.retain(|val| f(name, val));
+                !vec.is_empty()
+            }
+        })
+    }
+
+    /// Clears the map, returning all headers as an iterator. Keeps the
+    /// allocated memory for reuse.
+    ///
+    /// Each key is yielded once per associated value, like with `iter`.
+    /// Remaining headers are removed if the iterator is dropped.
+    pub fn drain(&mut self) -> Drain<'_> {
+        Drain {
+            iter: self.inner.drain(),
+            current: None,
+        }
+    }
+}
+
+/// A view into a single entry of a `HeaderMap`, which may either be vacant or
+/// occupied.
+#[derive(Debug)]
+pub enum Entry<'a> {
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a>),
+    /// A vacant entry.
+    Vacant(VacantEntry<'a>),
+}
+
+impl<'a> Entry<'a> {
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &HeaderName {
+        match self {
+            Entry::Occupied(ref entry) => entry.key(),
+            Entry::Vacant(ref entry) => entry.key(),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the default if empty,
+    /// returns a mutable reference to the first value of the entry.
+    pub fn or_insert(self, default: HeaderValue) -> &'a mut HeaderValue {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the
+    /// default function if empty, returns a mutable reference to the first
+    /// value of the entry.
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut HeaderValue
+    where
+        F: FnOnce() -> HeaderValue,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+}
+
+/// A view into an occupied entry of a `HeaderMap`, the key has at least
+/// one value.
+#[derive(Debug)]
+pub struct OccupiedEntry<'a>(hash_map::OccupiedEntry<'a, HeaderName, Value>);
+
+impl<'a> OccupiedEntry<'a> {
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &HeaderName {
+        self.0.key()
+    }
+
+    /// Returns a reference to the first value of the entry.
+    pub fn get(&self) -> &HeaderValue {
+        self.0.get().get()
+    }
+
+    /// Returns a mutable reference to the first value of the entry.
+    pub fn get_mut(&mut self) -> &mut HeaderValue {
+        self.0.get_mut().get_mut()
+    }
+
+    /// Converts the entry into a mutable reference to its first value.
+    pub fn into_mut(self) -> &'a mut HeaderValue {
+        self.0.into_mut().get_mut()
+    }
+
+    /// Returns an iterator of all values of the entry.
+    pub fn iter(&self) -> GetAll<'_> {
+        GetAll {
+            idx: 0,
+            item: Some(self.0.get()),
+        }
+    }
+
+    /// Replaces all values of the entry with the value, returns previous
+    /// values.
+    pub fn insert(&mut self, value: HeaderValue) -> Vec<HeaderValue> {
+        self.0.insert(Value::One(value)).into_vec()
+    }
+
+    /// Pushes the value to the end of values of the entry.
+    pub fn append(&mut self, value: HeaderValue) {
+        self.0.get_mut().append(value)
+    }
+
+    /// Removes the entry from the map, returns all of its values.
+    pub fn remove(self) -> Vec<HeaderValue> {
+        self.0.remove().into_vec()
+    }
+}
+
+/// A view into a vacant entry of a `HeaderMap`.
+#[derive(Debug)]
+pub struct VacantEntry<'a>(hash_map::VacantEntry<'a, HeaderName, Value>);
+
+impl<'a> VacantEntry<'a> {
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &HeaderName {
+        self.0.key()
+    }
+
+    /// Take ownership of the key.
+    pub fn into_key(self) -> HeaderName {
+        self.0.into_key()
+    }
+
+    /// Inserts the value into the map, returns a mutable reference to it.
+    pub fn insert(self, value: HeaderValue) -> &'a mut HeaderValue {
+        self.0.insert(Value::One(value)).get_mut()
+    }
 }
 
 #[doc(hidden)]
@@ -383,3 +542,114 @@ impl<'a> Iterator for Iter<'a> {
         }
     }
 }
+
+/// A draining iterator of `HeaderMap` headers.
+pub struct Drain<'a> {
+    iter: hash_map::Drain<'a, HeaderName, Value>,
+    current: Option<(HeaderName, std::vec::IntoIter<HeaderValue>)>,
+}
+
+impl<'a> Iterator for Drain<'a> {
+    type Item = (HeaderName, HeaderValue);
+
+    #[inline]
+    fn next(&mut self) -> Option<(HeaderName, HeaderValue)> {
+        if let Some((ref name, ref mut values)) = self.current {
+            if let Some(value) = values.next() {
+                if !values.as_slice().is_empty() {
+                    return Some((name.clone(), value));
+                }
+                // last value of the key, name is not cloned
+                let name = self.current.take().unwrap().0;
+                return Some((name, value));
+            }
+        }
+        match self.iter.next() {
+            Some((name, Value::One(value))) => Some((name, value)),
+            Some((name, Value::Multi(vec))) => {
+                self.current = Some((name, vec.into_iter()));
+                self.next()
+            }
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header::{ACCEPT, CONTENT_TYPE, SET_COOKIE};
+
+    fn map() -> HeaderMap {
+        let mut map = HeaderMap::new();
+        map.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        map.append(SET_COOKIE, HeaderValue::from_static("a=1"));
+        map.append(SET_COOKIE, HeaderValue::from_static("b=2"));
+        map.append(SET_COOKIE, HeaderValue::from_static("c=3"));
+        map
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut map = map();
+
+        match map.entry(SET_COOKIE) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), SET_COOKIE);
+                assert_eq!(entry.iter().count(), 3);
+                entry.append(HeaderValue::from_static("d=4"));
+                let mut prev = entry.insert(HeaderValue::from_static("e=5"));
+                prev.sort();
+                assert_eq!(prev, vec!["a=1", "b=2", "c=3", "d=4"]);
+                assert_eq!(entry.get(), "e=5");
+            }
+            Entry::Vacant(_) => panic!(),
+        }
+        assert_eq!(map.get_all(SET_COOKIE).collect::<Vec<_>>(), vec!["e=5"]);
+
+        let value = map
+            .entry(ACCEPT)
+            .or_insert_with(|| HeaderValue::from_static("*/*"));
+        assert_eq!(value, "*/*");
+        *map.entry(CONTENT_TYPE)
+            .or_insert(HeaderValue::from_static("-")) =
+            HeaderValue::from_static("text/html");
+        assert_eq!(map.get(CONTENT_TYPE).unwrap(), "text/html");
+
+        match map.entry(ACCEPT) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), vec!["*/*"]),
+            Entry::Vacant(_) => panic!(),
+        }
+        assert!(!map.contains_key(ACCEPT));
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut map = map();
+
+        map.retain(|name, value| name != CONTENT_TYPE && value != "b=2");
+        assert!(!map.contains_key(CONTENT_TYPE));
+        let mut cookies = map.get_all(SET_COOKIE).collect::<Vec<_>>();
+        cookies.sort();
+        assert_eq!(cookies, vec!["a=1", "c=3"]);
+
+        map.retain(|_, value| value != "a=1" && value != "c=3");
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut map = map();
+
+        let mut headers = map.drain().collect::<Vec<_>>();
+        headers.sort_by(|a, b| (a.0.as_str(), &a.1).cmp(&(b.0.as_str(), &b.1)));
+        assert_eq!(headers.len(), 4);
+        assert_eq!(
+            headers[0],
+            (CONTENT_TYPE, HeaderValue::from_static("text/plain"))
+        );
+        let cookies = headers[1..].iter().map(|h| &h.1).collect::<Vec<_>>();
+        assert_eq!(cookies, vec!["a=1", "b=2", "c=3"]);
+        assert!(map.is_empty());
+    }
+}
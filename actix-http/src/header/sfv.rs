@@ -0,0 +1,856 @@
+//! Structured field values, defined in
+//! [RFC8941](https://tools.ietf.org/html/rfc8941)
+//!
+//! Structured fields are used by modern headers like `Priority`,
+//! `Signature-Input` or client hints. Field value is a list, a dictionary
+//! or a single item, every item and inner list carries parameters.
+//! Serialization fails for values that are out of range of structured
+//! fields, i.e. integers with more than 15 digits or non-ascii strings.
+//!
+//! ```rust
+//! use actix_http::http::header::sfv::{BareItem, Dictionary};
+//!
+//! let dict: Dictionary = "u=1, i".parse().unwrap();
+//! assert_eq!(dict.get("u").and_then(|u| u.as_item()).map(|u| &u.bare_item),
+//!            Some(&BareItem::Integer(1)));
+//! assert_eq!(dict.serialize().unwrap(), "u=1, i");
+//! ```
+use std::fmt::Write;
+use std::str::FromStr;
+
+use derive_more::Display;
+
+use crate::error::ParseError;
+use crate::header::HeaderValue;
+
+const MAX_INTEGER: i64 = 999_999_999_999_999;
+
+/// Bare item value
+#[derive(Clone, Debug, PartialEq)]
+pub enum BareItem {
+    /// Integer, up to 15 digits
+    Integer(i64),
+    /// Decimal, up to 12 integer and 3 fractional digits
+    Decimal(f64),
+    /// Printable ascii string
+    String(String),
+    /// Token, i.e. `text/html`
+    Token(String),
+    /// Byte sequence, serialized as base64
+    ByteSequence(Vec<u8>),
+    /// Boolean, `?1` or `?0`
+    Boolean(bool),
+}
+
+/// Ordered parameters of an item or an inner list
+pub type Parameters = Vec<(String, BareItem)>;
+
+/// Item with parameters
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    /// Item value
+    pub bare_item: BareItem,
+    /// Item parameters
+    pub params: Parameters,
+}
+
+/// Inner list of items with parameters
+#[derive(Clone, Debug, PartialEq)]
+pub struct InnerList {
+    /// Items of the list
+    pub items: Vec<Item>,
+    /// Inner list parameters
+    pub params: Parameters,
+}
+
+/// Member of a list or a dictionary
+#[derive(Clone, Debug, PartialEq)]
+pub enum ListEntry {
+    /// Single item
+    Item(Item),
+    /// Inner list
+    InnerList(InnerList),
+}
+
+/// List field value, i.e. `sugar, tea, rum`
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct List(pub Vec<ListEntry>);
+
+/// Dictionary field value, i.e. `a=1, b=2;x=1;y=2, c=(a b c)`
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Dictionary(pub Vec<(String, ListEntry)>);
+
+impl Item {
+    /// Create item without parameters.
+    pub fn new(bare_item: BareItem) -> Self {
+        Item {
+            bare_item,
+            params: Vec::new(),
+        }
+    }
+
+    /// Add parameter to the item.
+    pub fn param<K: Into<String>>(mut self, key: K, value: BareItem) -> Self {
+        set(&mut self.params, key.into(), value);
+        self
+    }
+
+    /// Get parameter of the item.
+    pub fn get_param(&self, key: &str) -> Option<&BareItem> {
+        get(&self.params, key)
+    }
+}
+
+impl ListEntry {
+    /// Single item of the entry, `None` for inner lists.
+    pub fn as_item(&self) -> Option<&Item> {
+        match self {
+            ListEntry::Item(item) => Some(item),
+            ListEntry::InnerList(_) => None,
+        }
+    }
+
+    /// Inner list of the entry, `None` for single items.
+    pub fn as_inner_list(&self) -> Option<&InnerList> {
+        match self {
+            ListEntry::Item(_) => None,
+            ListEntry::InnerList(list) => Some(list),
+        }
+    }
+}
+
+impl List {
+    /// Parse list split over several header lines.
+    pub fn from_headers<'a, I>(values: I) -> Result<Self, ParseError>
+    where
+        I: IntoIterator<Item = &'a HeaderValue>,
+    {
+        join(values)?.parse()
+    }
+}
+
+impl Dictionary {
+    /// Parse dictionary split over several header lines.
+    pub fn from_headers<'a, I>(values: I) -> Result<Self, ParseError>
+    where
+        I: IntoIterator<Item = &'a HeaderValue>,
+    {
+        join(values)?.parse()
+    }
+
+    /// Get member of the dictionary.
+    pub fn get(&self, key: &str) -> Option<&ListEntry> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Insert member, existing member with the same key is replaced.
+    pub fn insert<K: Into<String>>(&mut self, key: K, value: ListEntry) {
+        set(&mut self.0, key.into(), value);
+    }
+}
+
+fn get<'a, T>(map: &'a [(String, T)], key: &str) -> Option<&'a T> {
+    map.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+fn set<T>(map: &mut Vec<(String, T)>, key: String, value: T) {
+    if let Some(entry) = map.iter_mut().find(|(k, _)| *k == key) {
+        entry.1 = value;
+    } else {
+        map.push((key, value));
+    }
+}
+
+fn join<'a, I>(values: I) -> Result<String, ParseError>
+where
+    I: IntoIterator<Item = &'a HeaderValue>,
+{
+    let mut joined = String::new();
+    for value in values {
+        if !joined.is_empty() {
+            joined.push_str(", ");
+        }
+        joined.push_str(value.to_str().map_err(|_| ParseError::Header)?);
+    }
+    Ok(joined)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        let mut parser = Parser {
+            input: input.as_bytes(),
+            pos: 0,
+        };
+        parser.skip_sp();
+        parser
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let ch = self.peek();
+        if ch.is_some() {
+            self.pos += 1;
+        }
+        ch
+    }
+
+    fn expect(&mut self, ch: u8) -> Result<(), ParseError> {
+        if self.next() == Some(ch) {
+            Ok(())
+        } else {
+            Err(ParseError::Header)
+        }
+    }
+
+    fn skip_sp(&mut self) {
+        while self.peek() == Some(b' ') {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_ows(&mut self) {
+        while let Some(b' ') | Some(b'\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn finish(mut self) -> Result<(), ParseError> {
+        self.skip_sp();
+        if self.pos == self.input.len() {
+            Ok(())
+        } else {
+            Err(ParseError::Header)
+        }
+    }
+
+    /// Parse members separated by commas, empty input is an empty list.
+    fn members<F>(&mut self, mut member: F) -> Result<(), ParseError>
+    where
+        F: FnMut(&mut Self) -> Result<(), ParseError>,
+    {
+        while self.peek().is_some() {
+            member(self)?;
+            self.skip_ows();
+            if self.peek().is_none() {
+                return Ok(());
+            }
+            self.expect(b',')?;
+            self.skip_ows();
+            if self.peek().is_none() {
+                // trailing comma
+                return Err(ParseError::Header);
+            }
+        }
+        Ok(())
+    }
+
+    fn list(&mut self) -> Result<List, ParseError> {
+        let mut members = Vec::new();
+        self.members(|p| {
+            members.push(p.entry()?);
+            Ok(())
+        })?;
+        Ok(List(members))
+    }
+
+    fn dictionary(&mut self) -> Result<Dictionary, ParseError> {
+        let mut dict = Dictionary::default();
+        self.members(|p| {
+            let key = p.key()?;
+            let member = if p.peek() == Some(b'=') {
+                p.pos += 1;
+                p.entry()?
+            } else {
+                ListEntry::Item(Item {
+                    bare_item: BareItem::Boolean(true),
+                    params: p.params()?,
+                })
+            };
+            dict.insert(key, member);
+            Ok(())
+        })?;
+        Ok(dict)
+    }
+
+    fn entry(&mut self) -> Result<ListEntry, ParseError> {
+        if self.peek() == Some(b'(') {
+            self.inner_list().map(ListEntry::InnerList)
+        } else {
+            self.item().map(ListEntry::Item)
+        }
+    }
+
+    fn inner_list(&mut self) -> Result<InnerList, ParseError> {
+        self.expect(b'(')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_sp();
+            if self.peek() == Some(b')') {
+                self.pos += 1;
+                return Ok(InnerList {
+                    items,
+                    params: self.params()?,
+                });
+            }
+            items.push(self.item()?);
+            match self.peek() {
+                Some(b' ') | Some(b')') => (),
+                _ => return Err(ParseError::Header),
+            }
+        }
+    }
+
+    fn item(&mut self) -> Result<Item, ParseError> {
+        Ok(Item {
+            bare_item: self.bare_item()?,
+            params: self.params()?,
+        })
+    }
+
+    fn params(&mut self) -> Result<Parameters, ParseError> {
+        let mut params = Vec::new();
+        while self.peek() == Some(b';') {
+            self.pos += 1;
+            self.skip_sp();
+            let key = self.key()?;
+            let value = if self.peek() == Some(b'=') {
+                self.pos += 1;
+                self.bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+            set(&mut params, key, value);
+        }
+        Ok(params)
+    }
+
+    fn key(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        match self.next() {
+            Some(b'a'..=b'z') | Some(b'*') => (),
+            _ => return Err(ParseError::Header),
+        }
+        while let Some(b'a'..=b'z') | Some(b'0'..=b'9') | Some(b'_') | Some(b'-')
+        | Some(b'.') | Some(b'*') = self.peek()
+        {
+            self.pos += 1;
+        }
+        Ok(self.slice(start))
+    }
+
+    fn bare_item(&mut self) -> Result<BareItem, ParseError> {
+        match self.peek() {
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(b'"') => self.string(),
+            Some(b':') => self.byte_sequence(),
+            Some(b'?') => self.boolean(),
+            Some(b'a'..=b'z') | Some(b'A'..=b'Z') | Some(b'*') => self.token(),
+            _ => Err(ParseError::Header),
+        }
+    }
+
+    fn number(&mut self) -> Result<BareItem, ParseError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        let int_start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        let int_len = self.pos - int_start;
+        if int_len == 0 {
+            return Err(ParseError::Header);
+        }
+
+        if self.peek() != Some(b'.') {
+            if int_len > 15 {
+                return Err(ParseError::Header);
+            }
+            let num = self.slice(start).parse().map_err(|_| ParseError::Header)?;
+            return Ok(BareItem::Integer(num));
+        }
+
+        self.pos += 1;
+        let frac_start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        let frac_len = self.pos - frac_start;
+        if int_len > 12 || frac_len == 0 || frac_len > 3 {
+            return Err(ParseError::Header);
+        }
+        let num = self.slice(start).parse().map_err(|_| ParseError::Header)?;
+        Ok(BareItem::Decimal(num))
+    }
+
+    fn string(&mut self) -> Result<BareItem, ParseError> {
+        self.expect(b'"')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some(b'"') => return Ok(BareItem::String(s)),
+                Some(b'\\') => match self.next() {
+                    Some(ch @ b'"') | Some(ch @ b'\\') => s.push(ch as char),
+                    _ => return Err(ParseError::Header),
+                },
+                Some(ch @ 0x20..=0x7e) => s.push(ch as char),
+                _ => return Err(ParseError::Header),
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<BareItem, ParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        while let Some(ch) = self.peek() {
+            if is_tchar(ch) || ch == b':' || ch == b'/' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        Ok(BareItem::Token(self.slice(start)))
+    }
+
+    fn byte_sequence(&mut self) -> Result<BareItem, ParseError> {
+        self.expect(b':')?;
+        let start = self.pos;
+        while let Some(ch) = self.peek() {
+            if ch.is_ascii_alphanumeric() || ch == b'+' || ch == b'/' || ch == b'=' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let data = base64::decode(&self.input[start..self.pos])
+            .map_err(|_| ParseError::Header)?;
+        self.expect(b':')?;
+        Ok(BareItem::ByteSequence(data))
+    }
+
+    fn boolean(&mut self) -> Result<BareItem, ParseError> {
+        self.expect(b'?')?;
+        match self.next() {
+            Some(b'1') => Ok(BareItem::Boolean(true)),
+            Some(b'0') => Ok(BareItem::Boolean(false)),
+            _ => Err(ParseError::Header),
+        }
+    }
+
+    fn slice(&self, start: usize) -> String {
+        // only ascii bytes are consumed
+        String::from_utf8_lossy(&self.input[start..self.pos]).into_owned()
+    }
+}
+
+fn is_tchar(ch: u8) -> bool {
+    ch.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&ch)
+}
+
+impl FromStr for Item {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let item = parser.item()?;
+        parser.finish()?;
+        Ok(item)
+    }
+}
+
+impl FromStr for List {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let list = parser.list()?;
+        parser.finish()?;
+        Ok(list)
+    }
+}
+
+impl FromStr for Dictionary {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let dict = parser.dictionary()?;
+        parser.finish()?;
+        Ok(dict)
+    }
+}
+
+/// Value that can not be serialized as a structured field
+#[derive(Debug, Display, PartialEq)]
+pub enum SerializeError {
+    /// Integer has more than 15 digits
+    #[display(fmt = "Integer is out of range")]
+    Integer,
+    /// Decimal is not finite or has more than 12 integer digits
+    #[display(fmt = "Decimal is out of range")]
+    Decimal,
+    /// String contains characters other than printable ascii
+    #[display(fmt = "String contains invalid characters")]
+    String,
+    /// Token is empty or contains invalid characters
+    #[display(fmt = "Invalid token")]
+    Token,
+    /// Key of a parameter or a dictionary member is invalid
+    #[display(fmt = "Invalid key")]
+    Key,
+}
+
+impl std::error::Error for SerializeError {}
+
+impl BareItem {
+    /// Serialize bare item.
+    pub fn serialize(&self) -> Result<String, SerializeError> {
+        serialize(|out| write_bare_item(self, out))
+    }
+}
+
+impl Item {
+    /// Serialize item with its parameters.
+    pub fn serialize(&self) -> Result<String, SerializeError> {
+        serialize(|out| write_item(self, out))
+    }
+}
+
+impl InnerList {
+    /// Serialize inner list with its parameters.
+    pub fn serialize(&self) -> Result<String, SerializeError> {
+        serialize(|out| write_inner_list(self, out))
+    }
+}
+
+impl List {
+    /// Serialize list field value.
+    pub fn serialize(&self) -> Result<String, SerializeError> {
+        serialize(|out| {
+            for (idx, entry) in self.0.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                write_entry(entry, out)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Dictionary {
+    /// Serialize dictionary field value.
+    pub fn serialize(&self) -> Result<String, SerializeError> {
+        serialize(|out| {
+            for (idx, (key, entry)) in self.0.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                write_key(key, out)?;
+                match entry {
+                    ListEntry::Item(item)
+                        if item.bare_item == BareItem::Boolean(true) =>
+                    {
+                        write_params(&item.params, out)?
+                    }
+                    entry => {
+                        out.push('=');
+                        write_entry(entry, out)?
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+fn serialize<F>(f: F) -> Result<String, SerializeError>
+where
+    F: FnOnce(&mut String) -> Result<(), SerializeError>,
+{
+    let mut out = String::new();
+    f(&mut out)?;
+    Ok(out)
+}
+
+fn write_bare_item(item: &BareItem, out: &mut String) -> Result<(), SerializeError> {
+    match item {
+        BareItem::Integer(num) => {
+            if *num < -MAX_INTEGER || *num > MAX_INTEGER {
+                return Err(SerializeError::Integer);
+            }
+            let _ = write!(out, "{}", num);
+        }
+        BareItem::Decimal(num) => {
+            if !num.is_finite() {
+                return Err(SerializeError::Decimal);
+            }
+            // rounded to 3 fractional digits
+            let s = format!("{:.3}", num);
+            let int_len = s.trim_start_matches('-').find('.').unwrap_or(0);
+            if int_len > 12 {
+                return Err(SerializeError::Decimal);
+            }
+            let s = s.trim_end_matches('0');
+            out.push_str(s);
+            if s.ends_with('.') {
+                out.push('0');
+            }
+        }
+        BareItem::String(s) => {
+            if !s.bytes().all(|ch| (0x20..=0x7e).contains(&ch)) {
+                return Err(SerializeError::String);
+            }
+            out.push('"');
+            for ch in s.chars() {
+                if ch == '"' || ch == '\\' {
+                    out.push('\\');
+                }
+                out.push(ch);
+            }
+            out.push('"');
+        }
+        BareItem::Token(token) => {
+            let mut bytes = token.bytes();
+            let valid = match bytes.next() {
+                Some(ch) => ch.is_ascii_alphabetic() || ch == b'*',
+                None => false,
+            } && bytes.all(|ch| is_tchar(ch) || ch == b':' || ch == b'/');
+            if !valid {
+                return Err(SerializeError::Token);
+            }
+            out.push_str(token);
+        }
+        BareItem::ByteSequence(data) => {
+            out.push(':');
+            out.push_str(&base64::encode(data));
+            out.push(':');
+        }
+        BareItem::Boolean(true) => out.push_str("?1"),
+        BareItem::Boolean(false) => out.push_str("?0"),
+    }
+    Ok(())
+}
+
+fn write_key(key: &str, out: &mut String) -> Result<(), SerializeError> {
+    let mut bytes = key.bytes();
+    let valid = match bytes.next() {
+        Some(ch) => ch.is_ascii_lowercase() || ch == b'*',
+        None => false,
+    } && bytes.all(|ch| {
+        ch.is_ascii_lowercase() || ch.is_ascii_digit() || b"_-.*".contains(&ch)
+    });
+    if !valid {
+        return Err(SerializeError::Key);
+    }
+    out.push_str(key);
+    Ok(())
+}
+
+fn write_params(
+    params: &[(String, BareItem)],
+    out: &mut String,
+) -> Result<(), SerializeError> {
+    for (key, value) in params {
+        out.push(';');
+        write_key(key, out)?;
+        if *value != BareItem::Boolean(true) {
+            out.push('=');
+            write_bare_item(value, out)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_item(item: &Item, out: &mut String) -> Result<(), SerializeError> {
+    write_bare_item(&item.bare_item, out)?;
+    write_params(&item.params, out)
+}
+
+fn write_inner_list(list: &InnerList, out: &mut String) -> Result<(), SerializeError> {
+    out.push('(');
+    for (idx, item) in list.items.iter().enumerate() {
+        if idx > 0 {
+            out.push(' ');
+        }
+        write_item(item, out)?;
+    }
+    out.push(')');
+    write_params(&list.params, out)
+}
+
+fn write_entry(entry: &ListEntry, out: &mut String) -> Result<(), SerializeError> {
+    match entry {
+        ListEntry::Item(item) => write_item(item, out),
+        ListEntry::InnerList(list) => write_inner_list(list, out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item() {
+        let item: Item = "  42;a=1;b  ".parse().unwrap();
+        assert_eq!(item.bare_item, BareItem::Integer(42));
+        assert_eq!(item.get_param("a"), Some(&BareItem::Integer(1)));
+        assert_eq!(item.get_param("b"), Some(&BareItem::Boolean(true)));
+        assert_eq!(item.serialize().unwrap(), "42;a=1;b");
+
+        let item: Item = "-1.50".parse().unwrap();
+        assert_eq!(item.bare_item, BareItem::Decimal(-1.5));
+        assert_eq!(item.serialize().unwrap(), "-1.5");
+
+        let item: Item = r#""say \"hi\"""#.parse().unwrap();
+        assert_eq!(item.bare_item, BareItem::String("say \"hi\"".to_owned()));
+        assert_eq!(item.serialize().unwrap(), r#""say \"hi\"""#);
+
+        let item: Item = "text/html;q=0.5".parse().unwrap();
+        assert_eq!(item.bare_item, BareItem::Token("text/html".to_owned()));
+        assert_eq!(item.get_param("q"), Some(&BareItem::Decimal(0.5)));
+
+        let item: Item = ":cHJldGVuZCB0aGlzIGlzIGJpbmFyeSBjb250ZW50Lg==:"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            item.bare_item,
+            BareItem::ByteSequence(b"pretend this is binary content.".to_vec())
+        );
+        assert_eq!(
+            item.serialize().unwrap(),
+            ":cHJldGVuZCB0aGlzIGlzIGJpbmFyeSBjb250ZW50Lg==:"
+        );
+
+        assert_eq!(
+            "?0".parse::<Item>().unwrap().bare_item,
+            BareItem::Boolean(false)
+        );
+
+        for invalid in &[
+            "",
+            "1234567890123456",
+            "1.2345",
+            "1.",
+            "\"unterminated",
+            "\"bad \\escape\"",
+            "?2",
+            "a;A=1",
+            "1 2",
+            ":not base64*:",
+        ] {
+            assert!(invalid.parse::<Item>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_list() {
+        let list: List = "sugar, tea,\trum".parse().unwrap();
+        assert_eq!(list.0.len(), 3);
+        assert_eq!(list.serialize().unwrap(), "sugar, tea, rum");
+
+        let list: List = r#"("foo" "bar");lvl=5, ("baz"), ()"#.parse().unwrap();
+        let inner = list.0[0].as_inner_list().unwrap();
+        assert_eq!(inner.items.len(), 2);
+        assert_eq!(inner.params, vec![("lvl".to_owned(), BareItem::Integer(5))]);
+        assert!(list.0[2].as_inner_list().unwrap().items.is_empty());
+        assert_eq!(
+            list.serialize().unwrap(),
+            r#"("foo" "bar");lvl=5, ("baz"), ()"#
+        );
+
+        assert_eq!("".parse::<List>().unwrap(), List::default());
+        assert!("a,".parse::<List>().is_err());
+        assert!("a,,b".parse::<List>().is_err());
+        assert!("(a b".parse::<List>().is_err());
+
+        let values = vec![HeaderValue::from_static("a"), HeaderValue::from_static("b")];
+        let list = List::from_headers(&values).unwrap();
+        assert_eq!(list.serialize().unwrap(), "a, b");
+    }
+
+    #[test]
+    fn test_dictionary() {
+        let dict: Dictionary = "a=?0, b, c;foo=bar, d=(1 2), a=1".parse().unwrap();
+        assert_eq!(dict.0.len(), 4);
+        // duplicate key keeps position and takes the last value
+        assert_eq!(dict.0[0].0, "a");
+        assert_eq!(
+            dict.get("a")
+                .and_then(|a| a.as_item())
+                .map(|a| &a.bare_item),
+            Some(&BareItem::Integer(1))
+        );
+        assert_eq!(
+            dict.get("c")
+                .and_then(|c| c.as_item())
+                .and_then(|c| c.get_param("foo")),
+            Some(&BareItem::Token("bar".to_owned()))
+        );
+        assert_eq!(dict.serialize().unwrap(), "a=1, b, c;foo=bar, d=(1 2)");
+
+        assert!("A=1".parse::<Dictionary>().is_err());
+        assert!("a=".parse::<Dictionary>().is_err());
+
+        let mut dict = Dictionary::default();
+        dict.insert(
+            "sig1",
+            ListEntry::Item(
+                Item::new(BareItem::String("value".to_owned()))
+                    .param("created", BareItem::Integer(1_618_884_475)),
+            ),
+        );
+        assert_eq!(
+            dict.serialize().unwrap(),
+            "sig1=\"value\";created=1618884475"
+        );
+    }
+
+    #[test]
+    fn test_serialize_error() {
+        for (item, err) in vec![
+            (BareItem::Integer(MAX_INTEGER + 1), SerializeError::Integer),
+            (BareItem::Integer(i64::MIN), SerializeError::Integer),
+            (BareItem::Decimal(std::f64::NAN), SerializeError::Decimal),
+            (
+                BareItem::Decimal(std::f64::INFINITY),
+                SerializeError::Decimal,
+            ),
+            (BareItem::Decimal(1e12), SerializeError::Decimal),
+            (
+                BareItem::String("caf\u{e9}".to_owned()),
+                SerializeError::String,
+            ),
+            (BareItem::String("a\nb".to_owned()), SerializeError::String),
+            (BareItem::Token("".to_owned()), SerializeError::Token),
+            (BareItem::Token("1abc".to_owned()), SerializeError::Token),
+            (BareItem::Token("a b".to_owned()), SerializeError::Token),
+        ] {
+            assert_eq!(item.serialize(), Err(err), "{:?}", item);
+        }
+        assert_eq!(
+            BareItem::Integer(-MAX_INTEGER).serialize().unwrap(),
+            "-999999999999999"
+        );
+        assert_eq!(
+            BareItem::Decimal(999_999_999_999.999).serialize().unwrap(),
+            "999999999999.999"
+        );
+
+        let item = Item::new(BareItem::Integer(1)).param("Key", BareItem::Integer(1));
+        assert_eq!(item.serialize(), Err(SerializeError::Key));
+
+        let mut dict = Dictionary::default();
+        dict.insert("", ListEntry::Item(Item::new(BareItem::Integer(1))));
+        assert_eq!(dict.serialize(), Err(SerializeError::Key));
+    }
+}
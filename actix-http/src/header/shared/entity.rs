@@ -75,6 +75,17 @@ impl EntityTag {
         EntityTag::new(false, tag)
     }
 
+    /// Constructs a new strong EntityTag from the representation data.
+    ///
+    /// Tag is a hex encoded 64-bit FNV-1a hash of the data, it is stable
+    /// between application versions and restarts.
+    pub fn from_data(data: &[u8]) -> EntityTag {
+        let hash = data.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| {
+            (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+        });
+        EntityTag::strong(format!("{:016x}", hash))
+    }
+
     /// Get the tag.
     pub fn tag(&self) -> &str {
         self.tag.as_ref()
@@ -262,4 +273,15 @@ mod tests {
         assert!(!etag1.strong_ne(&etag2));
         assert!(!etag1.weak_ne(&etag2));
     }
+
+    #[test]
+    fn test_etag_from_data() {
+        let etag = EntityTag::from_data(b"");
+        assert_eq!(etag, EntityTag::strong("cbf29ce484222325".to_owned()));
+
+        let etag = EntityTag::from_data(b"hello");
+        assert!(!etag.weak);
+        assert_eq!(etag.tag(), "a430d84680aabd0b");
+        assert!(etag.strong_ne(&EntityTag::from_data(b"hello!")));
+    }
 }
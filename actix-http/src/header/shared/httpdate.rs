@@ -1,9 +1,8 @@
 use std::fmt::{self, Display};
-use std::io::Write;
 use std::str::FromStr;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use bytes::{buf::BufMutExt, BytesMut};
+use bytes::Bytes;
 use http::header::{HeaderValue, InvalidHeaderValue};
 use time::{offset, OffsetDateTime, PrimitiveDateTime};
 
@@ -11,10 +10,56 @@ use crate::error::ParseError;
 use crate::header::IntoHeaderValue;
 use crate::time_parser;
 
+/// Length of formatted HTTP-date, i.e. `Sun, 06 Nov 1994 08:49:37 GMT`
+pub const HTTP_DATE_LENGTH: usize = 29;
+
+const WEEKDAYS: [&[u8; 3]; 7] = [b"Mon", b"Tue", b"Wed", b"Thu", b"Fri", b"Sat", b"Sun"];
+const MONTHS: [&[u8; 3]; 12] = [
+    b"Jan", b"Feb", b"Mar", b"Apr", b"May", b"Jun", b"Jul", b"Aug", b"Sep", b"Oct",
+    b"Nov", b"Dec",
+];
+
 /// A timestamp with HTTP formatting and parsing
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct HttpDate(OffsetDateTime);
 
+impl HttpDate {
+    /// Current time
+    pub fn now() -> HttpDate {
+        HttpDate(OffsetDateTime::now_utc())
+    }
+
+    /// Time after `duration` from now
+    pub fn after(duration: Duration) -> HttpDate {
+        HttpDate::from(SystemTime::now() + duration)
+    }
+
+    /// Format date in IMF-fixdate format, i.e. `Sun, 06 Nov 1994 08:49:37 GMT`
+    ///
+    /// Formatting does not allocate.
+    pub fn to_bytes(&self) -> [u8; HTTP_DATE_LENGTH] {
+        let dt = self.0.to_offset(offset!(UTC));
+        let mut buf = *b"Thu, 01 Jan 1970 00:00:00 GMT";
+
+        let year = dt.year().max(0).min(9999) as u16;
+        buf[..3].copy_from_slice(WEEKDAYS[dt.weekday().number_days_from_monday() as usize]);
+        write_digits(&mut buf[5..7], u16::from(dt.day()));
+        buf[8..11].copy_from_slice(MONTHS[dt.month() as usize - 1]);
+        write_digits(&mut buf[12..16], year);
+        write_digits(&mut buf[17..19], u16::from(dt.hour()));
+        write_digits(&mut buf[20..22], u16::from(dt.minute()));
+        write_digits(&mut buf[23..25], u16::from(dt.second()));
+        buf
+    }
+}
+
+fn write_digits(buf: &mut [u8], mut value: u16) {
+    for b in buf.iter_mut().rev() {
+        *b = b'0' + (value % 10) as u8;
+        value /= 10;
+    }
+}
+
 impl FromStr for HttpDate {
     type Err = ParseError;
 
@@ -28,7 +73,9 @@ impl FromStr for HttpDate {
 
 impl Display for HttpDate {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Display::fmt(&self.0.format("%a, %d %b %Y %H:%M:%S GMT"), f)
+        let buf = self.to_bytes();
+        // formatted date is always ascii
+        f.pad(std::str::from_utf8(&buf).unwrap())
     }
 }
 
@@ -48,16 +95,7 @@ impl IntoHeaderValue for HttpDate {
     type Error = InvalidHeaderValue;
 
     fn try_into(self) -> Result<HeaderValue, Self::Error> {
-        let mut wrt = BytesMut::with_capacity(29).writer();
-        write!(
-            wrt,
-            "{}",
-            self.0
-                .to_offset(offset!(UTC))
-                .format("%a, %d %b %Y %H:%M:%S GMT")
-        )
-        .unwrap();
-        HeaderValue::from_maybe_shared(wrt.get_mut().split().freeze())
+        HeaderValue::from_maybe_shared(Bytes::copy_from_slice(&self.to_bytes()))
     }
 }
 
@@ -97,4 +135,22 @@ mod tests {
         );
         assert!("this-is-no-date".parse::<HttpDate>().is_err());
     }
+
+    #[test]
+    fn test_format() {
+        let date = HttpDate(
+            PrimitiveDateTime::new(date!(1994 - 11 - 06), time!(8:49:37)).assume_utc(),
+        );
+        assert_eq!(&date.to_bytes(), b"Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(date.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+
+        let date = HttpDate(
+            PrimitiveDateTime::new(date!(2020 - 02 - 29), time!(23:05:09)).assume_utc(),
+        );
+        assert_eq!(&date.to_bytes(), b"Sat, 29 Feb 2020 23:05:09 GMT");
+        assert_eq!(
+            date.to_string().parse::<HttpDate>().unwrap().to_bytes(),
+            date.to_bytes()
+        );
+    }
 }
@@ -3,7 +3,7 @@
 pub use self::charset::Charset;
 pub use self::encoding::Encoding;
 pub use self::entity::EntityTag;
-pub use self::httpdate::HttpDate;
+pub use self::httpdate::{HttpDate, HTTP_DATE_LENGTH};
 pub use self::quality_item::{q, qitem, Quality, QualityItem};
 pub use language_tags::LanguageTag;
 
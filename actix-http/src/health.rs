@@ -0,0 +1,195 @@
+//! Runtime health monitoring
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use actix_threadpool::CpuFuture;
+
+use crate::load_shed::Load;
+
+static BLOCKING_QUEUED: AtomicUsize = AtomicUsize::new(0);
+static BLOCKING_ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// Execute blocking function on a thread pool, returns future that resolves
+/// to result of the function execution.
+///
+/// Jobs are counted in `Load::blocking_queued` and `Load::blocking_active`
+/// signals of the blocking thread pool.
+pub fn run_blocking<F, I, E>(f: F) -> CpuFuture<I, E>
+where
+    F: FnOnce() -> Result<I, E> + Send + 'static,
+    I: Send + 'static,
+    E: Send + std::fmt::Debug + 'static,
+{
+    let mut job = Job::new();
+    // job of dropped future is skipped by the pool, counter is released
+    // once the closure is dropped
+    actix_threadpool::run(move || {
+        job.start();
+        f()
+    })
+}
+
+/// Number of queued and running jobs of the blocking thread pool.
+pub(crate) fn blocking_load() -> (usize, usize) {
+    (
+        BLOCKING_QUEUED.load(Ordering::Relaxed),
+        BLOCKING_ACTIVE.load(Ordering::Relaxed),
+    )
+}
+
+/// Counter of blocking job.
+struct Job {
+    started: bool,
+}
+
+impl Job {
+    fn new() -> Self {
+        BLOCKING_QUEUED.fetch_add(1, Ordering::Relaxed);
+        Job { started: false }
+    }
+
+    fn start(&mut self) {
+        BLOCKING_QUEUED.fetch_sub(1, Ordering::Relaxed);
+        BLOCKING_ACTIVE.fetch_add(1, Ordering::Relaxed);
+        self.started = true;
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        if self.started {
+            BLOCKING_ACTIVE.fetch_sub(1, Ordering::Relaxed);
+        } else {
+            BLOCKING_QUEUED.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Self monitoring of worker runtime.
+///
+/// Event loop lag of every worker is sampled every 100 milliseconds, once
+/// the worker accepts its first connection, with or without monitor.
+/// Samples are available as `Load::current()` signals. Monitor logs lagging
+/// event loops and long queue of the blocking thread pool with `warn` level
+/// if thresholds are set, at most once per second per worker. Lag is usually caused by blocking
+/// calls in handlers, move them to `run_blocking()`.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use actix_http::{HealthMonitor, HttpService, Response};
+/// use futures_util::future::ok;
+///
+/// let srv = HttpService::build()
+///     .health_monitor(
+///         HealthMonitor::new()
+///             .loop_lag(Duration::from_millis(50))
+///             .blocking_queue(100),
+///     )
+///     .finish(|_| ok::<_, ()>(Response::Ok().finish()))
+///     .tcp();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HealthMonitor {
+    loop_lag: Option<Duration>,
+    blocking_queue: Option<usize>,
+}
+
+impl HealthMonitor {
+    /// Create monitor without logging thresholds.
+    pub fn new() -> Self {
+        HealthMonitor::default()
+    }
+
+    /// Log event loop lag longer than `threshold`.
+    pub fn loop_lag(mut self, threshold: Duration) -> Self {
+        self.loop_lag = Some(threshold);
+        self
+    }
+
+    /// Log blocking thread pool queue longer than `threshold` jobs.
+    pub fn blocking_queue(mut self, threshold: usize) -> Self {
+        self.blocking_queue = Some(threshold);
+        self
+    }
+
+    /// Log exceeded thresholds, returns true if anything is logged.
+    pub(crate) fn report(&self, load: &Load) -> bool {
+        let mut reported = false;
+        if let Some(threshold) = self.loop_lag {
+            if load.loop_lag > threshold {
+                warn!(
+                    "Event loop of {} lags for {:?}, threshold is {:?}",
+                    thread::current().name().unwrap_or("worker"),
+                    load.loop_lag,
+                    threshold
+                );
+                reported = true;
+            }
+        }
+        if let Some(threshold) = self.blocking_queue {
+            if load.blocking_queued > threshold {
+                warn!(
+                    "Blocking thread pool has {} queued jobs, threshold is {}",
+                    load.blocking_queued, threshold
+                );
+                reported = true;
+            }
+        }
+        reported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_run_blocking() {
+        let (started_tx, started_rx) = std::sync::mpsc::channel::<()>();
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let fut = run_blocking(move || {
+            started_tx.send(()).unwrap();
+            rx.recv().map_err(|_| ())
+        });
+        assert!(blocking_load().0 + blocking_load().1 >= 1);
+
+        started_rx.recv().unwrap();
+        assert!(blocking_load().1 >= 1);
+        tx.send(()).unwrap();
+        fut.await.unwrap();
+
+        let res = run_blocking(|| Err::<(), _>("error")).await;
+        assert!(res.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_monitor() {
+        crate::load_shed::monitor_loop_lag(Some(
+            HealthMonitor::new().loop_lag(Duration::from_millis(50)),
+        ));
+        actix_rt::time::delay_for(Duration::from_millis(10)).await;
+
+        // blocking call delays lag measurement
+        thread::sleep(Duration::from_millis(300));
+        actix_rt::time::delay_for(Duration::from_millis(50)).await;
+        assert!(Load::current().loop_lag >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_report() {
+        let mut load = Load::default();
+        let monitor = HealthMonitor::new()
+            .loop_lag(Duration::from_millis(50))
+            .blocking_queue(10);
+        assert!(!monitor.report(&load));
+
+        load.loop_lag = Duration::from_millis(100);
+        assert!(monitor.report(&load));
+
+        load.loop_lag = Duration::from_millis(0);
+        load.blocking_queued = 11;
+        assert!(monitor.report(&load));
+        assert!(!HealthMonitor::new().report(&load));
+    }
+}
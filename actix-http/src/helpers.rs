@@ -1,4 +1,5 @@
-use std::{io, ptr};
+use std::rc::Rc;
+use std::{io, net, ptr};
 
 use bytes::{BufMut, BytesMut};
 use http::Version;
@@ -179,6 +180,11 @@ pub(crate) trait DataFactory {
     fn set(&self, ext: &mut Extensions);
 }
 
+/// On-connect callback, called with the stream and peer address of the
+/// connection. Connection is rejected if callback returns `None`.
+pub(crate) type OnConnect<T> =
+    Rc<dyn Fn(&T, Option<net::SocketAddr>) -> Option<Box<dyn DataFactory>>>;
+
 pub(crate) struct Data<T>(pub(crate) T);
 
 impl<T: Clone + 'static> DataFactory for Data<T> {
@@ -30,8 +30,17 @@ pub trait HttpMessage: Sized {
     /// Mutable reference to a the request's extensions container
     fn extensions_mut(&self) -> RefMut<'_, Extensions>;
 
-    #[doc(hidden)]
-    /// Get a header
+    /// Get a typed header, `None` if the header is missing or can not be
+    /// parsed.
+    ///
+    /// ```rust
+    /// use actix_http::http::header::{EntityTag, IfNoneMatch};
+    /// use actix_http::{test::TestRequest, HttpMessage};
+    ///
+    /// let req = TestRequest::with_header("if-none-match", "\"xyzzy\"").finish();
+    /// let if_none_match = req.get_header::<IfNoneMatch>().unwrap();
+    /// assert!(if_none_match.matches(Some(&EntityTag::strong("xyzzy".to_owned()))));
+    /// ```
     fn get_header<H: Header>(&self) -> Option<H>
     where
         Self: Sized,
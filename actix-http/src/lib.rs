@@ -13,7 +13,9 @@ extern crate log;
 #[macro_use]
 mod macros;
 
+mod bandwidth;
 pub mod body;
+mod buffer_pool;
 mod builder;
 pub mod client;
 mod cloneable;
@@ -22,11 +24,14 @@ mod config;
 pub mod encoding;
 mod extensions;
 mod header;
+mod health;
 mod helpers;
 mod httpcodes;
 pub mod httpmessage;
+mod load_shed;
 mod message;
 mod payload;
+mod proxy_protocol;
 mod request;
 mod response;
 mod service;
@@ -36,14 +41,19 @@ pub mod cookie;
 pub mod error;
 pub mod h1;
 pub mod h2;
+pub mod signature;
 pub mod test;
 pub mod ws;
 
+pub use self::bandwidth::BandwidthLimit;
+pub use self::buffer_pool::BufferPool;
 pub use self::builder::HttpServiceBuilder;
 pub use self::config::{KeepAlive, ServiceConfig};
 pub use self::error::{Error, ResponseError, Result};
 pub use self::extensions::Extensions;
+pub use self::health::{run_blocking, HealthMonitor};
 pub use self::httpmessage::HttpMessage;
+pub use self::load_shed::{Load, LoadShedPolicy};
 pub use self::message::{Message, RequestHead, RequestHeadType, ResponseHead};
 pub use self::payload::{Payload, PayloadStream};
 pub use self::request::Request;
@@ -68,6 +78,11 @@ pub mod http {
     }
     pub use crate::header::ContentEncoding;
     pub use crate::message::ConnectionType;
+
+    /// HTTP message signatures
+    pub mod signature {
+        pub use crate::signature::*;
+    }
 }
 
 /// Http protocol
@@ -0,0 +1,242 @@
+//! Load shedding
+use std::cell::Cell;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_rt::time::{delay_for, Instant};
+
+use crate::health::{self, HealthMonitor};
+
+/// Interval of event loop lag measurements.
+const LAG_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Minimal interval between health reports of a worker.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+thread_local!(static LOAD: WorkerLoad = WorkerLoad::default());
+
+#[derive(Default)]
+struct WorkerLoad {
+    in_flight: Cell<usize>,
+    queued: Cell<usize>,
+    connections: Cell<usize>,
+    loop_lag: Cell<Duration>,
+    monitored: Cell<bool>,
+    health: Cell<Option<HealthMonitor>>,
+}
+
+/// Load signals of the current worker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Load {
+    /// Requests dispatched to the service, response is not sent yet.
+    pub in_flight: usize,
+    /// Requests waiting for dispatch: pipelined http/1 requests and http/2
+    /// streams queued by stream limits.
+    pub queued: usize,
+    /// Open connections.
+    pub connections: usize,
+    /// Delay of event loop timers, measured every 100 milliseconds.
+    pub loop_lag: Duration,
+    /// Jobs waiting for a thread of the blocking thread pool, shared by all
+    /// workers.
+    pub blocking_queued: usize,
+    /// Jobs running on the blocking thread pool, shared by all workers.
+    pub blocking_active: usize,
+}
+
+impl Load {
+    /// Load of the current worker thread.
+    ///
+    /// Event loop lag is measured once the worker accepts its first
+    /// connection.
+    pub fn current() -> Load {
+        let (blocking_queued, blocking_active) = health::blocking_load();
+        LOAD.with(|load| Load {
+            in_flight: load.in_flight.get(),
+            queued: load.queued.get(),
+            connections: load.connections.get(),
+            loop_lag: load.loop_lag.get(),
+            blocking_queued,
+            blocking_active,
+        })
+    }
+}
+
+/// Policy of rejecting new requests of an overloaded worker.
+///
+/// Dispatcher consults the policy before a new request reaches the service.
+/// Policy returns `Retry-After` delay to reject the request with
+/// `503 Service Unavailable` response, or `None` to accept it.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use actix_http::{HttpService, LoadShedPolicy, Response};
+/// use futures_util::future::ok;
+///
+/// let policy = LoadShedPolicy::new(|load| {
+///     if load.in_flight > 1000 || load.loop_lag > Duration::from_millis(50) {
+///         Some(Duration::from_secs(5))
+///     } else {
+///         None
+///     }
+/// });
+///
+/// let srv = HttpService::build()
+///     .load_shed(policy)
+///     .finish(|_| ok::<_, ()>(Response::Ok().finish()))
+///     .tcp();
+/// ```
+#[derive(Clone)]
+pub struct LoadShedPolicy(Arc<dyn Fn(&Load) -> Option<Duration> + Send + Sync>);
+
+impl LoadShedPolicy {
+    /// Create policy from a callback.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&Load) -> Option<Duration> + Send + Sync + 'static,
+    {
+        LoadShedPolicy(Arc::new(f))
+    }
+
+    /// Check load of the current worker, returns `Retry-After` delay if
+    /// request has to be rejected.
+    pub(crate) fn check(&self) -> Option<Duration> {
+        monitor_loop_lag(None);
+        (self.0)(&Load::current())
+    }
+}
+
+impl fmt::Debug for LoadShedPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LoadShedPolicy")
+    }
+}
+
+/// Start event loop lag measurements on the current worker, measurements
+/// are reported to health `monitor`.
+pub(crate) fn monitor_loop_lag(monitor: Option<HealthMonitor>) {
+    LOAD.with(|load| {
+        if monitor.is_some() {
+            load.health.set(monitor);
+        }
+        if !load.monitored.replace(true) {
+            actix_rt::spawn(async {
+                let mut reported: Option<Instant> = None;
+                loop {
+                    let start = Instant::now();
+                    delay_for(LAG_INTERVAL).await;
+                    let now = Instant::now();
+                    let lag =
+                        (now - start).checked_sub(LAG_INTERVAL).unwrap_or_default();
+                    let monitor = LOAD.with(|load| {
+                        load.loop_lag.set(lag);
+                        load.health.get()
+                    });
+
+                    if let Some(monitor) = monitor {
+                        let due = match reported {
+                            Some(at) => now - at >= REPORT_INTERVAL,
+                            None => true,
+                        };
+                        if due && monitor.report(&Load::current()) {
+                            reported = Some(now);
+                        }
+                    }
+                }
+            });
+        }
+    })
+}
+
+/// Seconds of `Retry-After` header, rounded up.
+pub(crate) fn retry_after_secs(delay: Duration) -> u64 {
+    let secs = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+    std::cmp::max(secs, 1)
+}
+
+/// Counter of worker load.
+#[derive(Clone, Copy)]
+pub(crate) enum Counter {
+    InFlight,
+    Queued,
+    Connections,
+}
+
+/// Marker of counted request or connection, it is uncounted on drop.
+pub(crate) struct Tracked(Counter);
+
+impl Tracked {
+    pub(crate) fn new(counter: Counter) -> Self {
+        LOAD.with(|load| {
+            let cell = load.counter(counter);
+            cell.set(cell.get() + 1);
+        });
+        Tracked(counter)
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        LOAD.with(|load| {
+            let cell = load.counter(self.0);
+            cell.set(cell.get() - 1);
+        });
+    }
+}
+
+impl WorkerLoad {
+    fn counter(&self, counter: Counter) -> &Cell<usize> {
+        match counter {
+            Counter::InFlight => &self.in_flight,
+            Counter::Queued => &self.queued,
+            Counter::Connections => &self.connections,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracked() {
+        let before = Load::current();
+        let conn = Tracked::new(Counter::Connections);
+        let req = Tracked::new(Counter::InFlight);
+        let queued = Tracked::new(Counter::Queued);
+
+        let load = Load::current();
+        assert_eq!(load.connections, before.connections + 1);
+        assert_eq!(load.in_flight, before.in_flight + 1);
+        assert_eq!(load.queued, before.queued + 1);
+
+        drop((conn, req, queued));
+        let load = Load::current();
+        assert_eq!(load.connections, before.connections);
+        assert_eq!(load.in_flight, before.in_flight);
+        assert_eq!(load.queued, before.queued);
+    }
+
+    #[test]
+    fn test_retry_after_secs() {
+        assert_eq!(retry_after_secs(Duration::from_secs(0)), 1);
+        assert_eq!(retry_after_secs(Duration::from_millis(1500)), 2);
+        assert_eq!(retry_after_secs(Duration::from_secs(30)), 30);
+    }
+
+    #[actix_rt::test]
+    async fn test_policy() {
+        let policy = LoadShedPolicy::new(|load| {
+            if load.in_flight > 0 {
+                Some(Duration::from_secs(3))
+            } else {
+                None
+            }
+        });
+        assert_eq!(policy.check(), None);
+
+        let _req = Tracked::new(Counter::InFlight);
+        assert_eq!(policy.check(), Some(Duration::from_secs(3)));
+    }
+}
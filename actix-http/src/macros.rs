@@ -93,3 +93,21 @@ mod tests {
         assert!(not_body.is_none());
     }
 }
+
+/// Evaluate expression inside of a `tracing` span of a dispatcher phase.
+///
+/// Expands to the expression itself if `tracing` feature is disabled.
+#[cfg(feature = "tracing")]
+macro_rules! phase {
+    ($name:literal, $e:expr) => {{
+        let _span = tracing_lib::trace_span!($name).entered();
+        $e
+    }};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! phase {
+    ($name:literal, $e:expr) => {
+        $e
+    };
+}
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::cell::{Ref, RefCell, RefMut};
 use std::net;
 use std::rc::Rc;
@@ -216,7 +217,7 @@ pub struct ResponseHead {
     pub version: Version,
     pub status: StatusCode,
     pub headers: HeaderMap,
-    pub reason: Option<&'static str>,
+    pub reason: Option<Cow<'static, str>>,
     pub(crate) extensions: RefCell<Extensions>,
     flags: Flags,
 }
@@ -299,7 +300,7 @@ impl ResponseHead {
     /// Get custom reason for the response
     #[inline]
     pub fn reason(&self) -> &str {
-        if let Some(reason) = self.reason {
+        if let Some(ref reason) = self.reason {
             reason
         } else {
             self.status
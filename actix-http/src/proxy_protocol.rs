@@ -0,0 +1,170 @@
+//! PROXY protocol header, defined in
+//! [haproxy documentation](https://www.haproxy.org/download/2.0/doc/proxy-protocol.txt)
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::str::FromStr;
+
+use actix_codec::AsyncRead;
+use actix_rt::net::TcpStream;
+use futures_util::future::poll_fn;
+
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+const V1_MAX_LENGTH: usize = 107;
+
+/// Read PROXY protocol header from the connection.
+///
+/// Only the header is consumed, returns source address of the proxied
+/// connection or `None` for local connections and unknown protocols.
+pub(crate) async fn read_header(io: &mut TcpStream) -> io::Result<Option<SocketAddr>> {
+    let mut buf = [0; 16];
+    // shortest v1 header, `PROXY UNKNOWN\r\n`, is longer than v2 signature
+    read_exact(io, &mut buf[..12]).await?;
+
+    if &buf[..12] == V2_SIGNATURE {
+        read_exact(io, &mut buf[12..]).await?;
+        let len = u16::from_be_bytes([buf[14], buf[15]]) as usize;
+        let mut data = vec![0; len];
+        read_exact(io, &mut data).await?;
+        parse_v2(buf[12], buf[13], &data)
+    } else if buf.starts_with(b"PROXY ") {
+        let mut line = buf[..12].to_vec();
+        let mut chunk = [0; V1_MAX_LENGTH];
+        while !line.ends_with(b"\n") {
+            if line.len() >= V1_MAX_LENGTH {
+                return Err(invalid());
+            }
+            let max = V1_MAX_LENGTH - line.len();
+            let n = io.peek(&mut chunk[..max]).await?;
+            if n == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            // consume the rest of the line only
+            let n = match chunk[..n].iter().position(|b| *b == b'\n') {
+                Some(pos) => pos + 1,
+                None => n,
+            };
+            read_exact(io, &mut chunk[..n]).await?;
+            line.extend_from_slice(&chunk[..n]);
+        }
+        parse_v1(&line)
+    } else {
+        Err(invalid())
+    }
+}
+
+async fn read_exact(io: &mut TcpStream, buf: &mut [u8]) -> io::Result<()> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n =
+            poll_fn(|cx| Pin::new(&mut *io).poll_read(cx, &mut buf[filled..])).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        filled += n;
+    }
+    Ok(())
+}
+
+fn invalid() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "Invalid PROXY protocol header")
+}
+
+fn parse_v1(line: &[u8]) -> io::Result<Option<SocketAddr>> {
+    if !line.ends_with(b"\r\n") {
+        return Err(invalid());
+    }
+    let line = std::str::from_utf8(&line[..line.len() - 2]).map_err(|_| invalid())?;
+    let mut parts = line.split(' ').skip(1);
+
+    match parts.next() {
+        Some("TCP4") | Some("TCP6") => (),
+        Some("UNKNOWN") => return Ok(None),
+        _ => return Err(invalid()),
+    }
+    let src: IpAddr = field(parts.next())?;
+    let dst: IpAddr = field(parts.next())?;
+    let port: u16 = field(parts.next())?;
+    let _: u16 = field(parts.next())?;
+    if parts.next().is_some() || src.is_ipv4() != dst.is_ipv4() {
+        return Err(invalid());
+    }
+    Ok(Some(SocketAddr::new(src, port)))
+}
+
+fn field<T: FromStr>(part: Option<&str>) -> io::Result<T> {
+    part.and_then(|s| s.parse().ok()).ok_or_else(invalid)
+}
+
+fn parse_v2(ver_cmd: u8, family: u8, data: &[u8]) -> io::Result<Option<SocketAddr>> {
+    if ver_cmd >> 4 != 2 {
+        return Err(invalid());
+    }
+    match ver_cmd & 0x0f {
+        // LOCAL, connection is established by the proxy itself
+        0 => return Ok(None),
+        1 => (),
+        _ => return Err(invalid()),
+    }
+
+    match family >> 4 {
+        1 if data.len() >= 12 => {
+            let mut ip = [0; 4];
+            ip.copy_from_slice(&data[..4]);
+            let port = u16::from_be_bytes([data[8], data[9]]);
+            Ok(Some(SocketAddr::new(Ipv4Addr::from(ip).into(), port)))
+        }
+        2 if data.len() >= 36 => {
+            let mut ip = [0; 16];
+            ip.copy_from_slice(&data[..16]);
+            let port = u16::from_be_bytes([data[32], data[33]]);
+            Ok(Some(SocketAddr::new(Ipv6Addr::from(ip).into(), port)))
+        }
+        1 | 2 => Err(invalid()),
+        // unspecified and unix sockets
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_v1() {
+        assert_eq!(
+            parse_v1(b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n").unwrap(),
+            Some("192.168.0.1:56324".parse().unwrap())
+        );
+        assert_eq!(
+            parse_v1(b"PROXY TCP6 ::1 ::2 56324 443\r\n").unwrap(),
+            Some("[::1]:56324".parse().unwrap())
+        );
+        assert_eq!(parse_v1(b"PROXY UNKNOWN ffff::1 ::2\r\n").unwrap(), None);
+        assert!(parse_v1(b"PROXY TCP4 192.168.0.1 ::1 56324 443\r\n").is_err());
+        assert!(parse_v1(b"PROXY TCP4 192.168.0.1 192.168.0.11 56324\r\n").is_err());
+        assert!(parse_v1(b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\n").is_err());
+        assert!(parse_v1(b"PROXY UDP4 192.168.0.1 192.168.0.11 56324 443\r\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_v2() {
+        let data = [127, 0, 0, 1, 127, 0, 0, 2, 0x1f, 0x90, 0, 80];
+        assert_eq!(
+            parse_v2(0x21, 0x11, &data).unwrap(),
+            Some("127.0.0.1:8080".parse().unwrap())
+        );
+        assert_eq!(parse_v2(0x20, 0x11, &data).unwrap(), None);
+        assert_eq!(parse_v2(0x21, 0x00, &[]).unwrap(), None);
+        assert!(parse_v2(0x11, 0x11, &data).is_err());
+        assert!(parse_v2(0x21, 0x21, &data).is_err());
+
+        let mut data = [0; 36];
+        data[15] = 1;
+        data[33] = 80;
+        assert_eq!(
+            parse_v2(0x21, 0x21, &data).unwrap(),
+            Some("[::1]:80".parse().unwrap())
+        );
+    }
+}
@@ -1,9 +1,11 @@
 //! Http response
+use std::borrow::Cow;
 use std::cell::{Ref, RefMut};
 use std::convert::TryFrom;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::SystemTime;
 use std::{fmt, str};
 
 use bytes::{Bytes, BytesMut};
@@ -14,7 +16,7 @@ use crate::body::{Body, BodyStream, MessageBody, ResponseBody};
 use crate::cookie::{Cookie, CookieJar};
 use crate::error::Error;
 use crate::extensions::Extensions;
-use crate::header::{Header, IntoHeaderValue};
+use crate::header::{Header, HttpDate, IntoHeaderValue};
 use crate::http::header::{self, HeaderName, HeaderValue};
 use crate::http::{Error as HttpError, HeaderMap, StatusCode};
 use crate::message::{BoxedResponseHead, ConnectionType, ResponseHead};
@@ -146,6 +148,13 @@ impl<B> Response<B> {
             .map_err(|e| e.into())
     }
 
+    /// Insert a typed header, replacing existing values of the header.
+    pub fn insert_header<H: Header>(&mut self, hdr: H) -> Result<(), HttpError> {
+        let value = hdr.try_into().map_err(|e| e.into())?;
+        self.head.headers.insert(H::name(), value);
+        Ok(())
+    }
+
     /// Remove all cookies with the given name from this response. Returns
     /// the number of cookies removed.
     #[inline]
@@ -270,7 +279,7 @@ impl<B: MessageBody> fmt::Debug for Response<B> {
             "\nResponse {:?} {}{}",
             self.head.version,
             self.head.status,
-            self.head.reason.unwrap_or(""),
+            self.head.reason.as_ref().map_or("", |r| r.as_ref()),
         );
         let _ = writeln!(f, "  headers:");
         for (key, val) in self.head.headers.iter() {
@@ -367,6 +376,30 @@ impl ResponseBuilder {
         self
     }
 
+    /// Insert a typed header, replacing existing values of the header.
+    ///
+    /// ```rust
+    /// use actix_http::http::header::{CacheControl, ContentType};
+    /// use actix_http::Response;
+    ///
+    /// let res = Response::Ok()
+    ///     .insert_header(CacheControl::max_age(3600))
+    ///     .insert_header(ContentType::json())
+    ///     .finish();
+    /// assert_eq!(res.headers().get("cache-control").unwrap(), "max-age=3600");
+    /// ```
+    pub fn insert_header<H: Header>(&mut self, hdr: H) -> &mut Self {
+        if let Some(parts) = parts(&mut self.head, &self.err) {
+            match hdr.try_into() {
+                Ok(value) => {
+                    parts.headers.insert(H::name(), value);
+                }
+                Err(e) => self.err = Some(e.into()),
+            }
+        }
+        self
+    }
+
     /// Append a header to existing headers.
     ///
     /// ```rust
@@ -432,10 +465,28 @@ impl ResponseBuilder {
     }
 
     /// Set the custom reason for the response.
+    ///
+    /// Reason is written after the status code of http/1.x responses, http/2
+    /// responses do not have reason phrase.
+    ///
+    /// ```rust
+    /// use actix_http::{http::StatusCode, Response};
+    ///
+    /// let res = Response::build(StatusCode::from_u16(420).unwrap())
+    ///     .reason("Enhance Your Calm")
+    ///     .finish();
+    /// assert_eq!(res.head().reason(), "Enhance Your Calm");
+    /// ```
+    ///
+    /// Reason with control characters, i.e. line breaks, is an error.
     #[inline]
-    pub fn reason(&mut self, reason: &'static str) -> &mut Self {
+    pub fn reason<R: Into<Cow<'static, str>>>(&mut self, reason: R) -> &mut Self {
         if let Some(parts) = parts(&mut self.head, &self.err) {
-            parts.reason = Some(reason);
+            let reason = reason.into();
+            match HeaderValue::from_str(&reason) {
+                Ok(_) => parts.reason = Some(reason),
+                Err(e) => self.err = Some(e.into()),
+            }
         }
         self
     }
@@ -462,6 +513,10 @@ impl ResponseBuilder {
     }
 
     /// Force close connection, even if it is marked as keep-alive
+    ///
+    /// Http/1 connection is closed once the response is sent, requests
+    /// pipelined after the current one are not served. Use
+    /// `ResponseHead::set_connection_type()` for already built responses.
     #[inline]
     pub fn force_close(&mut self) -> &mut Self {
         if let Some(parts) = parts(&mut self.head, &self.err) {
@@ -503,6 +558,12 @@ impl ResponseBuilder {
         self.header(header::CONTENT_LENGTH, len)
     }
 
+    /// Set `Date` header, dispatcher does not replace explicit header.
+    #[inline]
+    pub fn date(&mut self, time: SystemTime) -> &mut Self {
+        self.set_header(header::DATE, HttpDate::from(time))
+    }
+
     /// Set a cookie
     ///
     /// ```rust
@@ -744,7 +805,7 @@ impl<'a> From<&'a ResponseHead> for ResponseBuilder {
 
         let mut msg = BoxedResponseHead::new(head.status);
         msg.version = head.version;
-        msg.reason = head.reason;
+        msg.reason = head.reason.clone();
         for (k, v) in &head.headers {
             msg.headers.append(k.clone(), v.clone());
         }
@@ -775,7 +836,7 @@ impl fmt::Debug for ResponseBuilder {
             "\nResponseBuilder {:?} {}{}",
             head.version,
             head.status,
-            head.reason.unwrap_or(""),
+            head.reason.as_ref().map_or("", |r| r.as_ref()),
         );
         let _ = writeln!(f, "  headers:");
         for (key, val) in head.headers.iter() {
@@ -929,6 +990,27 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[test]
+    fn test_insert_header() {
+        use crate::http::header::{CacheControl, ETag, EntityTag};
+
+        let mut resp = Response::Ok()
+            .set(CacheControl::no_cache())
+            .insert_header(CacheControl::max_age(60))
+            .finish();
+        let values: Vec<_> = resp.headers().get_all(header::CACHE_CONTROL).collect();
+        assert_eq!(values, vec!["max-age=60"]);
+
+        resp.insert_header(CacheControl::no_store()).unwrap();
+        resp.insert_header(ETag(EntityTag::strong("xyzzy".to_owned())))
+            .unwrap();
+        assert_eq!(
+            resp.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-store"
+        );
+        assert_eq!(resp.headers().get(header::ETAG).unwrap(), "\"xyzzy\"");
+    }
+
     #[test]
     fn test_upgrade() {
         let resp = Response::build(StatusCode::OK)
@@ -941,12 +1023,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reason() {
+        let resp = Response::build(StatusCode::from_u16(420).unwrap())
+            .reason(String::from("Enhance Your Calm"))
+            .finish();
+        assert_eq!(resp.head().reason(), "Enhance Your Calm");
+
+        let resp = Response::build(StatusCode::OK)
+            .reason("Fine\r\nx-injected: 1")
+            .finish();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
     #[test]
     fn test_force_close() {
         let resp = Response::build(StatusCode::OK).force_close().finish();
         assert!(!resp.keep_alive())
     }
 
+    #[test]
+    fn test_date() {
+        let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(784_111_777);
+        let resp = Response::build(StatusCode::OK).date(time).finish();
+        assert_eq!(
+            resp.headers().get(header::DATE).unwrap(),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+    }
+
     #[test]
     fn test_content_type() {
         let resp = Response::build(StatusCode::OK)
@@ -1,15 +1,15 @@
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use std::{fmt, net, rc};
+use std::{fmt, net};
 
 use actix_codec::{AsyncRead, AsyncWrite, Framed};
 use actix_rt::net::TcpStream;
 use actix_service::{pipeline_factory, IntoServiceFactory, Service, ServiceFactory};
 use bytes::Bytes;
 use futures_core::{ready, Future};
-use futures_util::future::ok;
-use h2::server::{self, Handshake};
+use futures_util::future::{select, Either, FutureExt};
+use h2::server::Handshake;
 use pin_project::{pin_project, project};
 
 use crate::body::MessageBody;
@@ -17,10 +17,10 @@ use crate::builder::HttpServiceBuilder;
 use crate::cloneable::CloneableService;
 use crate::config::{KeepAlive, ServiceConfig};
 use crate::error::{DispatchError, Error};
-use crate::helpers::DataFactory;
+use crate::helpers::{DataFactory, OnConnect};
 use crate::request::Request;
 use crate::response::Response;
-use crate::{h1, h2::Dispatcher, Protocol};
+use crate::{h1, h2::Dispatcher, proxy_protocol, Protocol};
 
 /// `ServiceFactory` HTTP1.1/HTTP2 transport implementation
 pub struct HttpService<T, S, B, X = h1::ExpectHandler, U = h1::UpgradeHandler<T>> {
@@ -28,7 +28,7 @@ pub struct HttpService<T, S, B, X = h1::ExpectHandler, U = h1::UpgradeHandler<T>
     cfg: ServiceConfig,
     expect: X,
     upgrade: Option<U>,
-    on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+    on_connect: Option<OnConnect<T>>,
     _t: PhantomData<(T, B)>,
 }
 
@@ -143,10 +143,7 @@ where
     }
 
     /// Set on connect callback.
-    pub(crate) fn on_connect(
-        mut self,
-        f: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
-    ) -> Self {
+    pub(crate) fn on_connect(mut self, f: Option<OnConnect<T>>) -> Self {
         self.on_connect = f;
         self
     }
@@ -174,6 +171,9 @@ where
     <U::Service as Service>::Future: 'static,
 {
     /// Create simple tcp stream service
+    ///
+    /// If HTTP/2 with prior knowledge is enabled, connections that start with
+    /// HTTP/2 connection preface are served by HTTP/2 dispatcher.
     pub fn tcp(
         self,
     ) -> impl ServiceFactory<
@@ -183,19 +183,80 @@ where
         Error = DispatchError,
         InitError = (),
     > {
-        pipeline_factory(|io: TcpStream| {
-            let peer_addr = io.peer_addr().ok();
-            ok((io, Protocol::Http1, peer_addr))
+        let cfg = self.cfg.clone();
+        pipeline_factory(move |mut io: TcpStream| {
+            let cfg = cfg.clone();
+            async move {
+                let peer_addr = peer_addr(&mut io, &cfg).await?;
+                let proto = if cfg.h2_prior_knowledge() {
+                    h2c_protocol(&mut io, &cfg).await?
+                } else {
+                    Protocol::Http1
+                };
+                Ok((io, proto, peer_addr))
+            }
         })
         .and_then(self)
     }
 }
 
+/// Peer address of the connection, taken from PROXY protocol header if
+/// it is enabled.
+async fn peer_addr(
+    io: &mut TcpStream,
+    cfg: &ServiceConfig,
+) -> Result<Option<net::SocketAddr>, DispatchError> {
+    let peer_addr = io.peer_addr().ok();
+    if !cfg.proxy_protocol() {
+        return Ok(peer_addr);
+    }
+
+    let header = proxy_protocol::read_header(io).boxed_local();
+    let addr = match cfg.client_timer() {
+        Some(delay) => match select(header, delay).await {
+            Either::Left((res, _)) => res?,
+            Either::Right(_) => return Err(DispatchError::SlowRequestTimeout),
+        },
+        None => header.await?,
+    };
+    Ok(addr.or(peer_addr))
+}
+
+/// HTTP/2 connection preface of prior knowledge clients
+const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Peek at the first bytes of the connection and select protocol.
+///
+/// Connection is served by HTTP/1 if client does not send anything within
+/// client timeout, HTTP/1 dispatcher responds with timeout error.
+async fn h2c_protocol(
+    io: &mut TcpStream,
+    cfg: &ServiceConfig,
+) -> Result<Protocol, DispatchError> {
+    let mut buf = [0; 24];
+    let n = match cfg.client_timer() {
+        Some(delay) => match select(io.peek(&mut buf).boxed_local(), delay).await {
+            Either::Left((res, _)) => res?,
+            Either::Right(_) => return Ok(Protocol::Http1),
+        },
+        None => io.peek(&mut buf).await?,
+    };
+
+    // "PRI " is enough to tell preface from http/1 request line
+    if n >= 4 && buf[..n] == H2_PREFACE[..n] {
+        Ok(Protocol::Http2)
+    } else {
+        Ok(Protocol::Http1)
+    }
+}
+
 #[cfg(feature = "openssl")]
 mod openssl {
     use super::*;
+    use actix_service::apply_fn_factory;
     use actix_tls::openssl::{Acceptor, SslAcceptor, SslStream};
     use actix_tls::{openssl::HandshakeError, SslError};
+    use futures_util::future::{ok, TryFutureExt};
 
     impl<S, B, X, U> HttpService<SslStream<TcpStream>, S, B, X, U>
     where
@@ -229,12 +290,21 @@ mod openssl {
             Error = SslError<HandshakeError<TcpStream>, DispatchError>,
             InitError = (),
         > {
-            pipeline_factory(
+            let cfg = self.cfg.clone();
+            pipeline_factory(move |mut io: TcpStream| {
+                let cfg = cfg.clone();
+                async move {
+                    let peer_addr = peer_addr(&mut io, &cfg).await;
+                    Ok((io, peer_addr.map_err(SslError::Service)?))
+                }
+            })
+            .and_then(apply_fn_factory(
                 Acceptor::new(acceptor)
                     .map_err(SslError::Ssl)
                     .map_init_err(|_| panic!()),
-            )
-            .and_then(|io: SslStream<TcpStream>| {
+                |(io, peer_addr), srv| srv.call(io).map_ok(move |io| (io, peer_addr)),
+            ))
+            .and_then(|(io, peer_addr): (SslStream<TcpStream>, _)| {
                 let proto = if let Some(protos) = io.ssl().selected_alpn_protocol() {
                     if protos.windows(2).any(|window| window == b"h2") {
                         Protocol::Http2
@@ -244,7 +314,6 @@ mod openssl {
                 } else {
                     Protocol::Http1
                 };
-                let peer_addr = io.get_ref().peer_addr().ok();
                 ok((io, proto, peer_addr))
             })
             .and_then(self.map_err(SslError::Service))
@@ -255,8 +324,10 @@ mod openssl {
 #[cfg(feature = "rustls")]
 mod rustls {
     use super::*;
+    use actix_service::apply_fn_factory;
     use actix_tls::rustls::{Acceptor, ServerConfig, Session, TlsStream};
     use actix_tls::SslError;
+    use futures_util::future::{ok, TryFutureExt};
     use std::io;
 
     impl<S, B, X, U> HttpService<TlsStream<TcpStream>, S, B, X, U>
@@ -294,12 +365,21 @@ mod rustls {
             let protos = vec!["h2".to_string().into(), "http/1.1".to_string().into()];
             config.set_protocols(&protos);
 
-            pipeline_factory(
+            let cfg = self.cfg.clone();
+            pipeline_factory(move |mut io: TcpStream| {
+                let cfg = cfg.clone();
+                async move {
+                    let peer_addr = peer_addr(&mut io, &cfg).await;
+                    Ok((io, peer_addr.map_err(SslError::Service)?))
+                }
+            })
+            .and_then(apply_fn_factory(
                 Acceptor::new(config)
                     .map_err(SslError::Ssl)
                     .map_init_err(|_| panic!()),
-            )
-            .and_then(|io: TlsStream<TcpStream>| {
+                |(io, peer_addr), srv| srv.call(io).map_ok(move |io| (io, peer_addr)),
+            ))
+            .and_then(|(io, peer_addr): (TlsStream<TcpStream>, _)| {
                 let proto = if let Some(protos) = io.get_ref().1.get_alpn_protocol() {
                     if protos.windows(2).any(|window| window == b"h2") {
                         Protocol::Http2
@@ -309,7 +389,6 @@ mod rustls {
                 } else {
                     Protocol::Http1
                 };
-                let peer_addr = io.get_ref().0.peer_addr().ok();
                 ok((io, proto, peer_addr))
             })
             .and_then(self.map_err(SslError::Service))
@@ -378,7 +457,7 @@ pub struct HttpServiceResponse<
     fut_upg: Option<U::Future>,
     expect: Option<X::Service>,
     upgrade: Option<U::Service>,
-    on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+    on_connect: Option<OnConnect<T>>,
     cfg: ServiceConfig,
     _t: PhantomData<(T, B)>,
 }
@@ -448,7 +527,7 @@ pub struct HttpServiceHandler<T, S: Service, B, X: Service, U: Service> {
     expect: CloneableService<X>,
     upgrade: Option<CloneableService<U>>,
     cfg: ServiceConfig,
-    on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+    on_connect: Option<OnConnect<T>>,
     _t: PhantomData<(T, B, X)>,
 }
 
@@ -469,7 +548,7 @@ where
         srv: S,
         expect: X,
         upgrade: Option<U>,
-        on_connect: Option<rc::Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
+        on_connect: Option<OnConnect<T>>,
     ) -> HttpServiceHandler<T, S, B, X, U> {
         HttpServiceHandler {
             cfg,
@@ -543,16 +622,22 @@ where
     }
 
     fn call(&mut self, (io, proto, peer_addr): Self::Request) -> Self::Future {
-        let on_connect = if let Some(ref on_connect) = self.on_connect {
-            Some(on_connect(&io))
-        } else {
-            None
+        let on_connect = match self.on_connect {
+            Some(ref on_connect) => match on_connect(&io, peer_addr) {
+                Some(data) => Some(data),
+                None => {
+                    return HttpServiceHandlerResponse {
+                        state: State::Rejected,
+                    }
+                }
+            },
+            None => None,
         };
 
         match proto {
             Protocol::Http2 => HttpServiceHandlerResponse {
                 state: State::H2Handshake(Some((
-                    server::handshake(io),
+                    self.cfg.h2_settings().handshake(io),
                     self.cfg.clone(),
                     self.srv.clone(),
                     on_connect,
@@ -598,6 +683,7 @@ where
             Option<net::SocketAddr>,
         )>,
     ),
+    Rejected,
 }
 
 #[pin_project]
@@ -659,6 +745,7 @@ where
         match self.as_mut().project() {
             State::H1(disp) => disp.poll(cx),
             State::H2(disp) => disp.poll(cx),
+            State::Rejected => Poll::Ready(Ok(())),
             State::H2Handshake(ref mut data) => {
                 let conn = if let Some(ref mut item) = data {
                     match Pin::new(&mut item.0).poll(cx) {
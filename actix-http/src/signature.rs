@@ -0,0 +1,620 @@
+//! HTTP message signatures, defined in
+//! [RFC9421](https://tools.ietf.org/html/rfc9421)
+//!
+//! Signature covers an ordered set of message components: derived
+//! components like `@method` or `@authority` and header fields. Components,
+//! creation time and key id are serialized into *signature base*, the base is
+//! signed with the key of the signer. Signature parameters are sent in
+//! `Signature-Input` header and signature itself in `Signature` header,
+//! both headers are dictionaries keyed by signature label.
+//!
+//! Signing and verification algorithms are not provided, keys are supplied
+//! as callbacks.
+//!
+//! ```rust
+//! use actix_http::http::signature::{self, Component, SignatureParams};
+//! use actix_http::http::{HeaderMap, Method, Uri};
+//!
+//! let uri = Uri::from_static("https://example.com/foo?param=value");
+//! let mut headers = HeaderMap::new();
+//!
+//! let mut params = SignatureParams::new(vec![Component::Method, Component::Path]);
+//! params.created = Some(1_618_884_473);
+//! params.keyid = Some("test-key".to_owned());
+//!
+//! signature::sign("sig1", &params, &Method::POST, &uri, &mut headers, |base| {
+//!     assert_eq!(
+//!         std::str::from_utf8(base).unwrap(),
+//!         "\"@method\": POST\n\
+//!          \"@path\": /foo\n\
+//!          \"@signature-params\": (\"@method\" \"@path\");\
+//!          created=1618884473;keyid=\"test-key\""
+//!     );
+//!     b"signature".to_vec()
+//! })
+//! .unwrap();
+//!
+//! let sig = signature::signatures(&headers).unwrap().pop().unwrap();
+//! assert_eq!(sig.label, "sig1");
+//! assert_eq!(sig.params, params);
+//! assert_eq!(sig.value, b"signature");
+//! ```
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use derive_more::Display;
+use http::{Method, StatusCode, Uri};
+
+use crate::error::ResponseError;
+use crate::header::sfv::{BareItem, Dictionary, InnerList, Item, ListEntry};
+use crate::header::{HeaderMap, HeaderName, HeaderValue};
+
+/// Message component covered by a signature
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Component {
+    /// Request method, `@method`
+    Method,
+    /// Full target uri, `@target-uri`
+    TargetUri,
+    /// Host and non-default port of the target uri, `@authority`
+    Authority,
+    /// Scheme of the target uri, `@scheme`
+    Scheme,
+    /// Path and query of the target uri, `@request-target`
+    RequestTarget,
+    /// Path of the target uri, `@path`
+    Path,
+    /// Query of the target uri with leading `?`, `@query`
+    Query,
+    /// Header field, values of several header lines are joined
+    Header(HeaderName),
+}
+
+impl Component {
+    /// Component identifier, i.e. `@method` or `content-digest`.
+    pub fn name(&self) -> &str {
+        match self {
+            Component::Method => "@method",
+            Component::TargetUri => "@target-uri",
+            Component::Authority => "@authority",
+            Component::Scheme => "@scheme",
+            Component::RequestTarget => "@request-target",
+            Component::Path => "@path",
+            Component::Query => "@query",
+            Component::Header(name) => name.as_str(),
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Self, SignatureError> {
+        Ok(match name {
+            "@method" => Component::Method,
+            "@target-uri" => Component::TargetUri,
+            "@authority" => Component::Authority,
+            "@scheme" => Component::Scheme,
+            "@request-target" => Component::RequestTarget,
+            "@path" => Component::Path,
+            "@query" => Component::Query,
+            name if name.starts_with('@') => {
+                return Err(SignatureError::UnsupportedComponent(name.to_owned()))
+            }
+            name => {
+                // header names have to be lowercased by the signer
+                let header = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| SignatureError::Malformed)?;
+                if header.as_str() != name {
+                    return Err(SignatureError::Malformed);
+                }
+                Component::Header(header)
+            }
+        })
+    }
+
+    fn value(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+    ) -> Result<String, SignatureError> {
+        let missing = || SignatureError::MissingComponent(self.name().to_owned());
+
+        Ok(match self {
+            Component::Method => method.as_str().to_owned(),
+            Component::TargetUri => {
+                if uri.scheme().is_none() || uri.authority().is_none() {
+                    return Err(missing());
+                }
+                uri.to_string()
+            }
+            Component::Authority => {
+                let host = uri.host().ok_or_else(missing)?.to_ascii_lowercase();
+                let default_port = match uri.scheme_str() {
+                    Some("https") | Some("wss") => Some(443),
+                    Some("http") | Some("ws") => Some(80),
+                    _ => None,
+                };
+                match uri.port_u16() {
+                    Some(port) if Some(port) != default_port => {
+                        format!("{}:{}", host, port)
+                    }
+                    _ => host,
+                }
+            }
+            Component::Scheme => {
+                uri.scheme_str().ok_or_else(missing)?.to_ascii_lowercase()
+            }
+            Component::RequestTarget => match uri.path_and_query() {
+                Some(pq) => pq.as_str().to_owned(),
+                None => "/".to_owned(),
+            },
+            Component::Path => uri.path().to_owned(),
+            Component::Query => format!("?{}", uri.query().unwrap_or("")),
+            Component::Header(name) => {
+                let mut value = String::new();
+                for (idx, val) in headers.get_all(name).enumerate() {
+                    let val = val.to_str().map_err(|_| SignatureError::Malformed)?;
+                    if idx > 0 {
+                        value.push_str(", ");
+                    }
+                    value.push_str(val.trim());
+                }
+                if !headers.contains_key(name) {
+                    return Err(missing());
+                }
+                value
+            }
+        })
+    }
+}
+
+/// Parameters of a signature
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SignatureParams {
+    /// Covered components in signing order
+    pub components: Vec<Component>,
+    /// Creation time, seconds since unix epoch
+    pub created: Option<u64>,
+    /// Expiration time, seconds since unix epoch
+    pub expires: Option<u64>,
+    /// Nonce of the signature
+    pub nonce: Option<String>,
+    /// Signing algorithm, i.e. `ed25519`
+    pub alg: Option<String>,
+    /// Identifier of the signing key
+    pub keyid: Option<String>,
+    /// Application specific tag
+    pub tag: Option<String>,
+}
+
+impl SignatureParams {
+    /// Create parameters for the list of covered components.
+    pub fn new(components: Vec<Component>) -> Self {
+        SignatureParams {
+            components,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if signature covers the component.
+    pub fn covers(&self, component: &Component) -> bool {
+        self.components.contains(component)
+    }
+
+    /// Signature base of the message for the parameters.
+    pub fn signature_base(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+    ) -> Result<String, SignatureError> {
+        base(
+            &self.components,
+            &self.to_inner_list(),
+            method,
+            uri,
+            headers,
+        )
+    }
+
+    fn to_inner_list(&self) -> InnerList {
+        let items = self
+            .components
+            .iter()
+            .map(|c| Item::new(BareItem::String(c.name().to_owned())))
+            .collect();
+
+        let mut params = Vec::new();
+        if let Some(created) = self.created {
+            params.push(("created".to_owned(), BareItem::Integer(created as i64)));
+        }
+        if let Some(expires) = self.expires {
+            params.push(("expires".to_owned(), BareItem::Integer(expires as i64)));
+        }
+        let strings = [
+            ("nonce", &self.nonce),
+            ("alg", &self.alg),
+            ("keyid", &self.keyid),
+            ("tag", &self.tag),
+        ];
+        for (key, value) in strings.iter() {
+            if let Some(value) = value {
+                params.push(((*key).to_owned(), BareItem::String(value.clone())));
+            }
+        }
+        InnerList { items, params }
+    }
+
+    fn from_inner_list(list: &InnerList) -> Result<Self, SignatureError> {
+        let mut params = SignatureParams::default();
+        for item in &list.items {
+            if !item.params.is_empty() {
+                return Err(SignatureError::UnsupportedComponent(
+                    item.serialize().unwrap_or_default(),
+                ));
+            }
+            let component = match item.bare_item {
+                BareItem::String(ref name) => Component::from_name(name)?,
+                _ => return Err(SignatureError::Malformed),
+            };
+            if params.covers(&component) {
+                return Err(SignatureError::Malformed);
+            }
+            params.components.push(component);
+        }
+
+        for (key, value) in &list.params {
+            match (key.as_str(), value) {
+                ("created", BareItem::Integer(n)) if *n >= 0 => {
+                    params.created = Some(*n as u64)
+                }
+                ("expires", BareItem::Integer(n)) if *n >= 0 => {
+                    params.expires = Some(*n as u64)
+                }
+                ("nonce", BareItem::String(s)) => params.nonce = Some(s.clone()),
+                ("alg", BareItem::String(s)) => params.alg = Some(s.clone()),
+                ("keyid", BareItem::String(s)) => params.keyid = Some(s.clone()),
+                ("tag", BareItem::String(s)) => params.tag = Some(s.clone()),
+                ("created", _)
+                | ("expires", _)
+                | ("nonce", _)
+                | ("alg", _)
+                | ("keyid", _)
+                | ("tag", _) => return Err(SignatureError::Malformed),
+                // unknown parameters are part of the signature base only
+                _ => (),
+            }
+        }
+        Ok(params)
+    }
+}
+
+fn base(
+    components: &[Component],
+    input: &InnerList,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Result<String, SignatureError> {
+    let mut base = String::new();
+    for component in components {
+        let value = component.value(method, uri, headers)?;
+        let _ = writeln!(base, "\"{}\": {}", component.name(), value);
+    }
+    let input = input.serialize().map_err(|_| SignatureError::Malformed)?;
+    let _ = write!(base, "\"@signature-params\": {}", input);
+    Ok(base)
+}
+
+/// Signature of a message
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    /// Label of the signature
+    pub label: String,
+    /// Parameters of the signature
+    pub params: SignatureParams,
+    /// Signature bytes
+    pub value: Vec<u8>,
+    input: InnerList,
+}
+
+impl Signature {
+    /// Signature base of the message.
+    ///
+    /// Signature parameters are serialized as they were received, including
+    /// unknown parameters.
+    pub fn signature_base(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+    ) -> Result<String, SignatureError> {
+        base(&self.params.components, &self.input, method, uri, headers)
+    }
+
+    /// Check `created` and `expires` parameters against current time.
+    ///
+    /// Signatures created more than `max_age` seconds ago are expired,
+    /// creation time is required if `max_age` is set. Clock skew of
+    /// `leeway` seconds is tolerated for both parameters.
+    pub fn check_time(
+        &self,
+        max_age: Option<u64>,
+        leeway: u64,
+    ) -> Result<(), SignatureError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        if let Some(expires) = self.params.expires {
+            if expires.saturating_add(leeway) <= now {
+                return Err(SignatureError::Expired);
+            }
+        }
+        match self.params.created {
+            Some(created) if created > now.saturating_add(leeway) => {
+                Err(SignatureError::Invalid)
+            }
+            Some(created) => match max_age {
+                Some(max_age) if now - created.min(now) > max_age + leeway => {
+                    Err(SignatureError::Expired)
+                }
+                _ => Ok(()),
+            },
+            None if max_age.is_some() => {
+                Err(SignatureError::MissingComponent("created".to_owned()))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Sign the message and add signature to `Signature-Input` and `Signature`
+/// headers.
+///
+/// `signer` receives signature base and returns signature bytes. Existing
+/// signatures of the message are kept, signature with the same label is
+/// replaced.
+pub fn sign<F>(
+    label: &str,
+    params: &SignatureParams,
+    method: &Method,
+    uri: &Uri,
+    headers: &mut HeaderMap,
+    signer: F,
+) -> Result<(), SignatureError>
+where
+    F: FnOnce(&[u8]) -> Vec<u8>,
+{
+    let input = params.to_inner_list();
+    let base = base(&params.components, &input, method, uri, headers)?;
+    let value = signer(base.as_bytes());
+
+    // existing signatures are merged into one header line
+    let mut inputs = Dictionary::from_headers(headers.get_all(signature_input_header()))
+        .map_err(|_| SignatureError::Malformed)?;
+    let mut values = Dictionary::from_headers(headers.get_all(signature_header()))
+        .map_err(|_| SignatureError::Malformed)?;
+    inputs.insert(label, ListEntry::InnerList(input));
+    values.insert(
+        label,
+        ListEntry::Item(Item::new(BareItem::ByteSequence(value))),
+    );
+
+    let inputs = header_value(&inputs)?;
+    let values = header_value(&values)?;
+    headers.insert(signature_input_header(), inputs);
+    headers.insert(signature_header(), values);
+    Ok(())
+}
+
+fn header_value(dict: &Dictionary) -> Result<HeaderValue, SignatureError> {
+    let value = dict.serialize().map_err(|_| SignatureError::Malformed)?;
+    HeaderValue::from_str(&value).map_err(|_| SignatureError::Malformed)
+}
+
+/// Parse signatures of the message.
+pub fn signatures(headers: &HeaderMap) -> Result<Vec<Signature>, SignatureError> {
+    if !headers.contains_key(signature_input_header()) {
+        return Err(SignatureError::Missing);
+    }
+    let inputs = Dictionary::from_headers(headers.get_all(signature_input_header()))
+        .map_err(|_| SignatureError::Malformed)?;
+    let values = Dictionary::from_headers(headers.get_all(signature_header()))
+        .map_err(|_| SignatureError::Malformed)?;
+
+    let mut signatures = Vec::new();
+    for (label, input) in inputs.0 {
+        let input = match input {
+            ListEntry::InnerList(input) => input,
+            ListEntry::Item(_) => return Err(SignatureError::Malformed),
+        };
+        let value = match values.get(&label).and_then(ListEntry::as_item) {
+            Some(Item {
+                bare_item: BareItem::ByteSequence(value),
+                ..
+            }) => value.clone(),
+            Some(_) => return Err(SignatureError::Malformed),
+            None => return Err(SignatureError::Missing),
+        };
+        signatures.push(Signature {
+            params: SignatureParams::from_inner_list(&input)?,
+            label,
+            value,
+            input,
+        });
+    }
+    Ok(signatures)
+}
+
+fn signature_header() -> HeaderName {
+    HeaderName::from_static("signature")
+}
+
+fn signature_input_header() -> HeaderName {
+    HeaderName::from_static("signature-input")
+}
+
+/// A set of errors that can occur during signing or verifying a message
+#[derive(Debug, Display, PartialEq)]
+pub enum SignatureError {
+    /// Message is not signed
+    #[display(fmt = "Message signature is missing")]
+    Missing,
+    /// Signature headers could not be parsed
+    #[display(fmt = "Malformed message signature")]
+    Malformed,
+    /// Covered component is not present in the message
+    #[display(fmt = "Signed component is missing: {}", _0)]
+    MissingComponent(String),
+    /// Covered component is not supported
+    #[display(fmt = "Signed component is not supported: {}", _0)]
+    UnsupportedComponent(String),
+    /// Signature is expired
+    #[display(fmt = "Message signature is expired")]
+    Expired,
+    /// Signature verification failed
+    #[display(fmt = "Message signature is invalid")]
+    Invalid,
+}
+
+impl std::error::Error for SignatureError {}
+
+/// Return `BadRequest` for malformed signatures and `Unauthorized` for
+/// missing or invalid ones
+impl ResponseError for SignatureError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SignatureError::Missing
+            | SignatureError::Expired
+            | SignatureError::Invalid => StatusCode::UNAUTHORIZED,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_base() {
+        let uri = Uri::from_static("https://Example.com:443/foo?param=Value&Pet=dog");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("content-type"),
+            HeaderValue::from_static("application/json"),
+        );
+        headers.insert(
+            HeaderName::from_static("x-list"),
+            HeaderValue::from_static(" a, b "),
+        );
+
+        let mut params = SignatureParams::new(vec![
+            Component::Method,
+            Component::TargetUri,
+            Component::Authority,
+            Component::Scheme,
+            Component::RequestTarget,
+            Component::Path,
+            Component::Query,
+            Component::Header(HeaderName::from_static("content-type")),
+            Component::Header(HeaderName::from_static("x-list")),
+        ]);
+        params.created = Some(1_618_884_473);
+        params.keyid = Some("test-key-rsa-pss".to_owned());
+
+        assert_eq!(
+            params
+                .signature_base(&Method::POST, &uri, &headers)
+                .unwrap(),
+            "\"@method\": POST\n\
+             \"@target-uri\": https://Example.com:443/foo?param=Value&Pet=dog\n\
+             \"@authority\": example.com\n\
+             \"@scheme\": https\n\
+             \"@request-target\": /foo?param=Value&Pet=dog\n\
+             \"@path\": /foo\n\
+             \"@query\": ?param=Value&Pet=dog\n\
+             \"content-type\": application/json\n\
+             \"x-list\": a, b\n\
+             \"@signature-params\": (\"@method\" \"@target-uri\" \"@authority\" \
+             \"@scheme\" \"@request-target\" \"@path\" \"@query\" \"content-type\" \
+             \"x-list\");created=1618884473;keyid=\"test-key-rsa-pss\""
+        );
+
+        let params = SignatureParams::new(vec![Component::Header(
+            HeaderName::from_static("digest"),
+        )]);
+        assert_eq!(
+            params.signature_base(&Method::GET, &uri, &headers),
+            Err(SignatureError::MissingComponent("digest".to_owned()))
+        );
+        let params = SignatureParams::new(vec![Component::TargetUri]);
+        assert_eq!(
+            params.signature_base(&Method::GET, &Uri::from_static("/foo"), &headers),
+            Err(SignatureError::MissingComponent("@target-uri".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_signatures() {
+        let uri = Uri::from_static("http://example.com:8080/");
+        let mut headers = HeaderMap::new();
+        assert_eq!(signatures(&headers), Err(SignatureError::Missing));
+
+        let mut params = SignatureParams::new(vec![Component::Authority]);
+        params.expires = Some(1);
+        sign("a", &params, &Method::GET, &uri, &mut headers, |base| {
+            assert!(base.starts_with(b"\"@authority\": example.com:8080\n"));
+            vec![1, 2, 3]
+        })
+        .unwrap();
+        sign("b", &params, &Method::GET, &uri, &mut headers, |_| vec![4]).unwrap();
+
+        let sigs = signatures(&headers).unwrap();
+        assert_eq!(sigs.len(), 2);
+        assert_eq!(sigs[0].label, "a");
+        assert_eq!(sigs[0].value, vec![1, 2, 3]);
+        assert_eq!(sigs[1].label, "b");
+        assert_eq!(sigs[1].params, params);
+        assert_eq!(sigs[1].check_time(None, 0), Err(SignatureError::Expired));
+
+        // unknown parameters are kept in the signature base
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            signature_input_header(),
+            HeaderValue::from_static(r#"sig=("@method");x=1;created=5"#),
+        );
+        headers.insert(signature_header(), HeaderValue::from_static("sig=:AQ==:"));
+        let sig = signatures(&headers).unwrap().pop().unwrap();
+        assert_eq!(sig.params.created, Some(5));
+        assert_eq!(
+            sig.signature_base(&Method::GET, &uri, &headers).unwrap(),
+            "\"@method\": GET\n\"@signature-params\": (\"@method\");x=1;created=5"
+        );
+        assert_eq!(sig.check_time(None, 0), Ok(()));
+        assert_eq!(sig.check_time(Some(60), 0), Err(SignatureError::Expired));
+
+        for input in &[
+            r#"sig=("@method" "@method")"#,
+            r#"sig=("Host")"#,
+            r#"sig=("@method");created="5""#,
+            "sig=1",
+        ] {
+            headers.insert(signature_input_header(), HeaderValue::from_static(input));
+            assert_eq!(signatures(&headers), Err(SignatureError::Malformed));
+        }
+        headers.insert(
+            signature_input_header(),
+            HeaderValue::from_static(r#"sig=("@status")"#),
+        );
+        assert_eq!(
+            signatures(&headers),
+            Err(SignatureError::UnsupportedComponent("@status".to_owned()))
+        );
+        headers.insert(
+            signature_input_header(),
+            HeaderValue::from_static(r#"other=("@method")"#),
+        );
+        assert_eq!(signatures(&headers), Err(SignatureError::Missing));
+    }
+}
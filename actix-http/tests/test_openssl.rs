@@ -13,7 +13,7 @@ use actix_http::error::{ErrorBadRequest, PayloadError};
 use actix_http::http::header::{self, HeaderName, HeaderValue};
 use actix_http::http::{Method, StatusCode, Version};
 use actix_http::httpmessage::HttpMessage;
-use actix_http::{body, Error, HttpService, Request, Response};
+use actix_http::{body, BandwidthLimit, Error, HttpService, Request, Response};
 
 async fn load_body<S>(stream: S) -> Result<BytesMut, PayloadError>
 where
@@ -116,6 +116,25 @@ async fn test_h2_body() -> io::Result<()> {
     Ok(())
 }
 
+#[actix_rt::test]
+async fn test_h2_bandwidth_limit() -> io::Result<()> {
+    let mut srv = test_server(move || {
+        HttpService::build()
+            .bandwidth_limit(BandwidthLimit::new(100_000).burst(10_000))
+            .h2(|_| ok::<_, Error>(Response::Ok().body(vec![b'x'; 60_000])))
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    })
+    .await;
+
+    let start = std::time::Instant::now();
+    let response = srv.sget("/").send().await.unwrap();
+    let body = srv.load_body(response).await.unwrap();
+    assert_eq!(body.len(), 60_000);
+    assert!(start.elapsed() >= std::time::Duration::from_millis(400));
+    Ok(())
+}
+
 #[actix_rt::test]
 async fn test_h2_content_length() {
     let srv = test_server(move || {
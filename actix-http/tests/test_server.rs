@@ -1,5 +1,5 @@
 use std::io::{Read, Write};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{net, thread};
 
 use actix_http_test::test_server;
@@ -10,9 +10,12 @@ use futures::future::{self, err, ok, ready, FutureExt};
 use futures::stream::{once, StreamExt};
 use regex::Regex;
 
+use actix_http::h1::Informational;
+use actix_http::h2::ServerPush;
 use actix_http::httpmessage::HttpMessage;
 use actix_http::{
-    body, error, http, http::header, Error, HttpService, KeepAlive, Request, Response,
+    body, error, http, http::header, BandwidthLimit, BufferPool, Error, HttpService,
+    KeepAlive, Request, Response,
 };
 
 #[actix_rt::test]
@@ -83,6 +86,95 @@ async fn test_expect_continue() {
     assert!(data.starts_with("HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\n"));
 }
 
+#[actix_rt::test]
+async fn test_early_hints() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(fn_service(|req: Request| {
+                let info = req.extensions().get::<Informational>().cloned();
+                async move {
+                    if let Some(ref info) = info {
+                        info.early_hints(&["</style.css>; rel=preload; as=style"]);
+                    }
+                    delay_for(Duration::from_millis(20)).await;
+                    if let Some(ref info) = info {
+                        info.early_hints(&["</app.js>; rel=preload; as=script"]);
+                    }
+                    delay_for(Duration::from_millis(20)).await;
+                    Ok::<_, ()>(Response::Ok().finish())
+                }
+            }))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\nconnection: close\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with(
+        "HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload; as=style\r\n\r\n\
+         HTTP/1.1 103 Early Hints\r\nlink: </app.js>; rel=preload; as=script\r\n\r\n\
+         HTTP/1.1 200 OK\r\n"
+    ));
+
+    // informational responses are not sent to http/1.0 clients
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.0\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.0 200 OK\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_h1_reason() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(|req: Request| {
+                let reason = format!("Enhance Your {}", &req.path()[1..]);
+                ok::<_, ()>(
+                    Response::build(http::StatusCode::from_u16(420).unwrap())
+                        .reason(reason)
+                        .finish(),
+                )
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /Calm HTTP/1.1\r\nconnection: close\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 420 Enhance Your Calm\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_h1_http10_framing() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .http10_chunked(false)
+            .h1(|_| {
+                let body = once(ok::<_, Error>(Bytes::from_static(b"streamed")));
+                ok::<_, ()>(Response::Ok().streaming(body))
+            })
+            .tcp()
+    })
+    .await;
+
+    // body is delimited by closing the connection
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.0\r\nconnection: keep-alive\r\n\r\n");
+    let mut data = String::new();
+    stream.read_to_string(&mut data).unwrap();
+    assert!(data.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(!data.contains("transfer-encoding"));
+    assert!(data.ends_with("\r\n\r\nstreamed"));
+}
+
 #[actix_rt::test]
 async fn test_expect_continue_h1() {
     let srv = test_server(|| {
@@ -267,6 +359,36 @@ async fn test_http1_keepalive_close() {
     assert_eq!(res, 0);
 }
 
+#[actix_rt::test]
+async fn test_http1_keepalive_force_close() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(|req: Request| {
+                let mut res = Response::Ok();
+                if req.path() == "/close" {
+                    res.force_close();
+                }
+                future::ok::<_, ()>(res.finish())
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let _ = stream.read(&mut data);
+    assert_eq!(&data[..17], b"HTTP/1.1 200 OK\r\n");
+
+    // pipelined request after forced close is not served
+    let _ = stream.write_all(b"GET /close HTTP/1.1\r\n\r\nGET /test HTTP/1.1\r\n\r\n");
+    let mut data = Vec::new();
+    let _ = stream.read_to_end(&mut data);
+    let data = String::from_utf8_lossy(&data);
+    assert_eq!(data.matches("HTTP/1.1 200 OK\r\n").count(), 1);
+    assert!(data.contains("connection: close\r\n"));
+}
+
 #[actix_rt::test]
 async fn test_http10_keepalive_default_close() {
     let srv = test_server(|| {
@@ -443,6 +565,33 @@ const STR: &str = "Hello World Hello World Hello World Hello World Hello World \
                    Hello World Hello World Hello World Hello World Hello World \
                    Hello World Hello World Hello World Hello World Hello World";
 
+#[actix_rt::test]
+async fn test_h1_date_header() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .date_header(false)
+            .h1(|req: Request| {
+                let mut res = Response::Ok();
+                if req.path() == "/date" {
+                    res.date(std::time::SystemTime::UNIX_EPOCH);
+                }
+                ok::<_, ()>(res.finish())
+            })
+            .tcp()
+    })
+    .await;
+
+    let response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert!(!response.headers().contains_key(header::DATE));
+
+    let response = srv.get("/date").send().await.unwrap();
+    assert_eq!(
+        response.headers().get(header::DATE).unwrap(),
+        "Thu, 01 Jan 1970 00:00:00 GMT"
+    );
+}
+
 #[actix_rt::test]
 async fn test_h1_body() {
     let mut srv = test_server(|| {
@@ -460,6 +609,32 @@ async fn test_h1_body() {
     assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
 }
 
+#[actix_rt::test]
+async fn test_h1_bandwidth_limit() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .bandwidth_limit(BandwidthLimit::new(200_000).burst(20_000))
+            .h1(|req: Request| {
+                let mut res = Response::Ok().body(vec![b'x'; 60_000]);
+                if req.path() == "/slow" {
+                    res.extensions_mut()
+                        .insert(BandwidthLimit::new(100_000).burst(10_000));
+                }
+                ok::<_, ()>(res)
+            })
+            .tcp()
+    })
+    .await;
+
+    for &(path, min) in &[("/", 200), ("/slow", 400)] {
+        let start = std::time::Instant::now();
+        let response = srv.get(path).send().await.unwrap();
+        let bytes = srv.load_body(response).await.unwrap();
+        assert_eq!(bytes.len(), 60_000);
+        assert!(start.elapsed() >= Duration::from_millis(min));
+    }
+}
+
 #[actix_rt::test]
 async fn test_h1_head_empty() {
     let mut srv = test_server(|| {
@@ -555,6 +730,66 @@ async fn test_h1_body_length() {
     assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
 }
 
+#[actix_rt::test]
+async fn test_h1_body_vectored() {
+    let sizes = [10, 5000, 20, 70_000, 3, 4096];
+    let mut srv = test_server(move || {
+        HttpService::build()
+            .write_coalesce_limit(4096)
+            .h1(move |req: Request| {
+                let chunks = sizes.iter().enumerate().map(|(i, size)| {
+                    Ok::<_, Error>(Bytes::from(vec![b'a' + i as u8; *size]))
+                });
+                let body = futures::stream::iter(chunks.collect::<Vec<_>>());
+                ok::<_, ()>(if req.path() == "/sized" {
+                    let len = sizes.iter().sum::<usize>() as u64;
+                    Response::Ok().body(body::SizedStream::new(len, body))
+                } else {
+                    Response::Ok().streaming(body)
+                })
+            })
+            .tcp()
+    })
+    .await;
+
+    let expected: Vec<u8> = sizes
+        .iter()
+        .enumerate()
+        .flat_map(|(i, size)| vec![b'a' + i as u8; *size])
+        .collect();
+    for path in &["/sized", "/chunked"] {
+        let response = srv.get(*path).send().await.unwrap();
+        assert!(response.status().is_success());
+        let bytes = srv.load_body(response).await.unwrap();
+        assert_eq!(bytes, Bytes::from(expected.clone()));
+    }
+}
+
+#[actix_rt::test]
+async fn test_h1_buffer_pool() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .buffer_pool(BufferPool::new(2).chunk_size(8192))
+            .h1(fn_service(|mut request: Request| {
+                request
+                    .take_payload()
+                    .fold(0usize, |acc, chunk| ready(acc + chunk.unwrap().len()))
+                    .map(|size| {
+                        Ok::<_, Error>(Response::Ok().body(format!("size={}", size)))
+                    })
+            }))
+            .tcp()
+    })
+    .await;
+
+    for size in &[10, 40_000, 0] {
+        let response = srv.post("/").send_body(vec![b'a'; *size]).await.unwrap();
+        assert!(response.status().is_success());
+        let bytes = srv.load_body(response).await.unwrap();
+        assert_eq!(bytes, Bytes::from(format!("size={}", size)));
+    }
+}
+
 #[actix_rt::test]
 async fn test_h1_body_chunked_explicit() {
     let mut srv = test_server(|| {
@@ -676,3 +911,610 @@ async fn test_h1_on_connect() {
     let response = srv.get("/").send().await.unwrap();
     assert!(response.status().is_success());
 }
+
+#[actix_rt::test]
+async fn test_h1_on_connect_ext() {
+    let srv = test_server(|| {
+        let connections = std::cell::Cell::new(0);
+        HttpService::build()
+            .on_connect_ext(move |_, _| {
+                connections.set(connections.get() + 1);
+                if connections.get() > 1 {
+                    Err("connection quota exceeded")
+                } else {
+                    Ok(10usize)
+                }
+            })
+            .h1(|req: Request| {
+                assert!(req.extensions().contains::<usize>());
+                future::ok::<_, ()>(Response::Ok().finish())
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\nconnection: close\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    // connection is closed before request is read
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.is_empty());
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .on_connect_ext(|_, _| Err::<(), _>("banned"))
+            .finish(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_loop_lag() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .h1(|_| {
+                async {
+                    // lag is sampled without load shed policy or health monitor
+                    delay_for(Duration::from_millis(10)).await;
+                    thread::sleep(Duration::from_millis(300));
+                    delay_for(Duration::from_millis(20)).await;
+                    let lag = actix_http::Load::current().loop_lag;
+                    Ok::<_, ()>(Response::Ok().body(lag.as_millis().to_string()))
+                }
+            })
+            .tcp()
+    })
+    .await;
+
+    let response = srv.get("/").send().await.unwrap();
+    let bytes = srv.load_body(response).await.unwrap();
+    let lag: u128 = std::str::from_utf8(&bytes).unwrap().parse().unwrap();
+    assert!(lag >= 100, "{}", lag);
+}
+
+#[actix_rt::test]
+async fn test_h1_trailers() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(|_| {
+                let body = body::WithTrailers::new(
+                    body::BodyStream::new(once(ok::<_, Error>(Bytes::from("data")))),
+                    || {
+                        let mut trailers = http::HeaderMap::new();
+                        trailers.insert(
+                            header::HeaderName::from_static("x-checksum"),
+                            header::HeaderValue::from_static("abc"),
+                        );
+                        trailers
+                    },
+                );
+                ok::<_, ()>(
+                    Response::Ok()
+                        .header(header::TRAILER, "x-checksum")
+                        .body(body::Body::from_message(body)),
+                )
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\nconnection: close\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.contains("transfer-encoding: chunked\r\n"));
+    assert!(data.ends_with("4\r\ndata\r\n0\r\nx-checksum: abc\r\n\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_h2c_trailers() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_prior_knowledge(true)
+            .finish(|_| {
+                let body = body::WithTrailers::new(
+                    body::BodyStream::new(once(ok::<_, Error>(Bytes::from("data")))),
+                    || {
+                        let mut trailers = http::HeaderMap::new();
+                        trailers.insert(
+                            header::HeaderName::from_static("x-checksum"),
+                            header::HeaderValue::from_static("abc"),
+                        );
+                        trailers
+                    },
+                );
+                ok::<_, ()>(
+                    Response::Ok()
+                        .header(header::TRAILER, "x-checksum")
+                        .body(body::Body::from_message(body)),
+                )
+            })
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(conn.map(|_| ()));
+
+    let req = ::http::Request::get("/").body(()).unwrap();
+    let (response, _) = client.send_request(req, true).unwrap();
+    let response = response.await.unwrap();
+    assert!(response.status().is_success());
+    let mut body = response.into_body();
+    let data = body.data().await.unwrap().unwrap();
+    assert_eq!(data, Bytes::from_static(b"data"));
+    // data frames do not end the stream, trailers are sent in headers frame
+    assert!(body.data().await.is_none());
+    let trailers = body.trailers().await.unwrap().unwrap();
+    assert_eq!(trailers.get("x-checksum").unwrap(), "abc");
+    assert!(body.is_end_stream());
+}
+
+#[actix_rt::test]
+async fn test_h2c() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_prior_knowledge(true)
+            .finish(|req: Request| {
+                let body = format!("{:?}", req.version());
+                ok::<_, ()>(Response::Ok().body(body))
+            })
+            .tcp()
+    })
+    .await;
+
+    // prior knowledge
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(conn.map(|_| ()));
+
+    let req = ::http::Request::get("/").body(()).unwrap();
+    let (response, _) = client.send_request(req, true).unwrap();
+    let response = response.await.unwrap();
+    assert!(response.status().is_success());
+    let mut body = response.into_body();
+    let data = body.data().await.unwrap().unwrap();
+    assert_eq!(data, Bytes::from_static(b"HTTP/2.0"));
+}
+
+#[cfg(feature = "tracing")]
+#[actix_rt::test]
+async fn test_h2c_phase_spans() {
+    use std::sync::{Arc, Mutex};
+    use tracing_lib::span::{Attributes, Id, Record};
+    use tracing_lib::{Event, Metadata, Subscriber};
+
+    #[derive(Clone, Default)]
+    struct Phases(Arc<Mutex<Vec<&'static str>>>);
+
+    impl Subscriber for Phases {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut phases = self.0.lock().unwrap();
+            phases.push(span.metadata().name());
+            Id::from_u64(phases.len() as u64)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    // server runs on its own thread, subscriber has to be global
+    let phases = Phases::default();
+    tracing_lib::subscriber::set_global_default(phases.clone()).unwrap();
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_prior_knowledge(true)
+            .finish(|_| ok::<_, ()>(Response::Ok().body("test")))
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(conn.map(|_| ()));
+
+    let req = ::http::Request::get("/").body(()).unwrap();
+    let (response, _) = client.send_request(req, true).unwrap();
+    let response = response.await.unwrap();
+    let mut body = response.into_body();
+    let data = body.data().await.unwrap().unwrap();
+    assert_eq!(data, Bytes::from_static(b"test"));
+
+    let phases = phases.0.lock().unwrap();
+    for phase in &["read", "handle", "encode", "write"] {
+        assert!(phases.contains(phase), "{}", phase);
+    }
+}
+
+#[actix_rt::test]
+async fn test_h2c_push() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_prior_knowledge(true)
+            .finish(|req: Request| {
+                if req.path() == "/" {
+                    let push = req.extensions().get::<ServerPush>().cloned().unwrap();
+                    assert!(push.push("/style.css"));
+                    assert!(!push.push("style.css"));
+                }
+                let body = format!("{} {}", req.method(), req.path());
+                ok::<_, ()>(Response::Ok().body(body))
+            })
+            .tcp()
+    })
+    .await;
+
+    // server could open pushed streams once client advertises the limit
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::Builder::new()
+        .max_concurrent_streams(10)
+        .handshake::<_, Bytes>(io)
+        .await
+        .unwrap();
+    actix_rt::spawn(conn.map(|_| ()));
+
+    let req = ::http::Request::get("http://localhost/").body(()).unwrap();
+    let (mut response, _) = client.send_request(req, true).unwrap();
+    let mut promises = response.push_promises();
+
+    let response = response.await.unwrap();
+    let data = response.into_body().data().await.unwrap().unwrap();
+    assert_eq!(data, Bytes::from_static(b"GET /"));
+
+    let (promised, pushed) =
+        promises.push_promise().await.unwrap().unwrap().into_parts();
+    assert_eq!(promised.uri(), "http://localhost/style.css");
+    let pushed = pushed.await.unwrap();
+    assert!(pushed.status().is_success());
+    let data = pushed.into_body().data().await.unwrap().unwrap();
+    assert_eq!(data, Bytes::from_static(b"GET /style.css"));
+    assert!(promises.push_promise().await.is_none());
+}
+
+#[actix_rt::test]
+async fn test_h2c_push_stream_limit() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let sent = Arc::new(AtomicBool::new(false));
+    let ordered = Arc::new(AtomicBool::new(false));
+    let (sent2, ordered2) = (sent.clone(), ordered.clone());
+    let srv = test_server(move || {
+        let (sent, ordered) = (sent2.clone(), ordered2.clone());
+        HttpService::build()
+            .h2_prior_knowledge(true)
+            .h2_max_streams(1)
+            .finish(move |req: Request| {
+                if req.path() == "/" {
+                    let push = req.extensions().get::<ServerPush>().cloned().unwrap();
+                    assert!(push.push("/style.css"));
+
+                    let sent = sent.clone();
+                    let body = once(Box::pin(async move {
+                        delay_for(Duration::from_millis(100)).await;
+                        sent.store(true, Ordering::SeqCst);
+                        Ok::<_, Error>(Bytes::from_static(b"GET /"))
+                    }));
+                    ok::<_, ()>(Response::Ok().streaming(body))
+                } else {
+                    // pushed stream waits for the slot of the first stream
+                    ordered.store(sent.load(Ordering::SeqCst), Ordering::SeqCst);
+                    ok::<_, ()>(Response::Ok().body("GET /style.css"))
+                }
+            })
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::Builder::new()
+        .max_concurrent_streams(10)
+        .handshake::<_, Bytes>(io)
+        .await
+        .unwrap();
+    actix_rt::spawn(conn.map(|_| ()));
+
+    let req = ::http::Request::get("http://localhost/").body(()).unwrap();
+    let (mut response, _) = client.send_request(req, true).unwrap();
+    let mut promises = response.push_promises();
+
+    let response = response.await.unwrap();
+    let data = response.into_body().data().await.unwrap().unwrap();
+    assert_eq!(data, Bytes::from_static(b"GET /"));
+
+    let (_, pushed) = promises.push_promise().await.unwrap().unwrap().into_parts();
+    let pushed = pushed.await.unwrap();
+    let data = pushed.into_body().data().await.unwrap().unwrap();
+    assert_eq!(data, Bytes::from_static(b"GET /style.css"));
+    assert!(ordered.load(Ordering::SeqCst));
+}
+
+#[actix_rt::test]
+async fn test_h2c_stream_limits() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_prior_knowledge(true)
+            .h2_max_streams(1)
+            .h2_max_peer_streams(2)
+            .finish(|_: Request| async {
+                delay_for(Duration::from_millis(200)).await;
+                Ok::<_, ()>(Response::Ok().finish())
+            })
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(conn.map(|_| ()));
+    let mut client = client.ready().await.unwrap();
+
+    // first stream is processed, second is queued, third is refused
+    let mut responses = Vec::new();
+    for _ in 0..3 {
+        let req = ::http::Request::get("/").body(()).unwrap();
+        responses.push(client.send_request(req, true).unwrap().0);
+    }
+    let refused = responses.pop().unwrap().await.unwrap_err();
+    assert_eq!(refused.reason(), Some(h2::Reason::REFUSED_STREAM));
+
+    // stream of the other connection, peer has processed and queued stream
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut other, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(conn.map(|_| ()));
+    let req = ::http::Request::get("/").body(()).unwrap();
+    let err = other.send_request(req, true).unwrap().0.await.unwrap_err();
+    assert_eq!(err.reason(), Some(h2::Reason::REFUSED_STREAM));
+
+    let started = Instant::now();
+    for response in responses {
+        assert!(response.await.unwrap().status().is_success());
+    }
+    // queued stream is processed after the first one
+    assert!(started.elapsed() >= Duration::from_millis(300));
+}
+
+#[actix_rt::test]
+async fn test_h2c_stalled_priority() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_prior_knowledge(true)
+            .finish(|_: Request| {
+                ok::<_, ()>(Response::Ok().body(Bytes::from(vec![b'x'; 262_144])))
+            })
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::Builder::new()
+        .initial_connection_window_size(1 << 24)
+        .handshake::<_, Bytes>(io)
+        .await
+        .unwrap();
+    actix_rt::spawn(conn.map(|_| ()));
+
+    // client never reads urgent response, it stalls on stream window
+    let req = ::http::Request::get("/urgent")
+        .header("priority", "u=0")
+        .body(())
+        .unwrap();
+    let (urgent, _) = client.send_request(req, true).unwrap();
+    let _urgent = urgent.await.unwrap();
+
+    let req = ::http::Request::get("/background")
+        .header("priority", "u=7")
+        .body(())
+        .unwrap();
+    let (background, _) = client.send_request(req, true).unwrap();
+    let read = async move {
+        let mut body = background.await.unwrap().into_body();
+        let mut size = 0;
+        while let Some(data) = body.data().await {
+            let data = data.unwrap();
+            size += data.len();
+            let _ = body.flow_control().release_capacity(data.len());
+        }
+        size
+    };
+    let size = actix_rt::time::timeout(Duration::from_secs(5), read)
+        .await
+        .expect("stream with lower urgency is starved");
+    assert_eq!(size, 262_144);
+}
+
+#[actix_rt::test]
+async fn test_h2c_settings() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_prior_knowledge(true)
+            .h2_max_concurrent_streams(8)
+            .h2_initial_window_size(1 << 20)
+            .h2_max_frame_size(32_768)
+            .h2_keep_alive_interval(Duration::from_millis(100))
+            .h2_keep_alive_timeout(Duration::from_millis(100))
+            .finish(|_: Request| ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    })
+    .await;
+
+    // client sends preface and empty settings, but never answers pings
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let _ = stream.write_all(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\0\0\0\x04\0\0\0\0\0");
+    let mut data = Vec::new();
+    stream.read_to_end(&mut data).unwrap();
+
+    let mut frames = Vec::new();
+    let mut buf = &data[..];
+    while buf.len() >= 9 {
+        let len = (buf[0] as usize) << 16 | (buf[1] as usize) << 8 | buf[2] as usize;
+        frames.push((buf[3], buf[4], buf[9..9 + len].to_vec()));
+        buf = &buf[9 + len..];
+    }
+
+    // max concurrent streams, initial window size and max frame size
+    let (kind, _, settings) = &frames[0];
+    assert_eq!(*kind, 0x4);
+    let has = |s: &[u8]| settings.chunks(6).any(|v| v == s);
+    assert!(has(&[0, 3, 0, 0, 0, 8]));
+    assert!(has(&[0, 4, 0, 0x10, 0, 0]));
+    assert!(has(&[0, 5, 0, 0, 0x80, 0]));
+
+    // connection is closed once ping is not acknowledged
+    assert!(frames
+        .iter()
+        .any(|(kind, flags, _)| *kind == 0x6 && *flags == 0));
+}
+
+#[actix_rt::test]
+async fn test_h1_head_limits() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .max_headers(2)
+            .max_request_line(32)
+            .h1(|_| ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\na: 1\r\nb: 2\r\nc: 3\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test/with/a/very/long/path HTTP/1.1\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 414 URI Too Long\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_h1_strict_parsing() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .strict_parsing(true)
+            .h1(|_| ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"POST /test HTTP/1.1\r\nContent-Length: 4\r\n\
+          Transfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\nConnection: close\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_proxy_protocol() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .proxy_protocol(true)
+            .finish(|req: Request| {
+                let body = format!("{:?}", req.peer_addr());
+                ok::<_, ()>(Response::Ok().body(body))
+            })
+            .tcp()
+    })
+    .await;
+
+    // v1 header split over several packets
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"PROXY TCP4 192.168.0.1 ");
+    thread::sleep(Duration::from_millis(50));
+    let _ = stream.write_all(b"192.168.0.11 56324 443\r\nGET / HTTP/1.1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    let data = String::from_utf8_lossy(&data[..n]);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(data.ends_with("Some(192.168.0.1:56324)"));
+
+    // v2 header
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"\r\n\r\n\0\r\nQUIT\n\x21\x11\x00\x0c\
+          \x0a\x00\x00\x01\x0a\x00\x00\x02\x1f\x90\x00\x50\
+          GET / HTTP/1.1\r\n\r\n",
+    );
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    let data = String::from_utf8_lossy(&data[..n]);
+    assert!(data.ends_with("Some(10.0.0.1:8080)"));
+
+    // connections without header are closed
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_proxy_protocol_on_connect_ext() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .proxy_protocol(true)
+            .on_connect_ext(|_, addr: Option<net::SocketAddr>| match addr {
+                Some(addr) if addr.ip().is_loopback() => Err("loopback peer"),
+                _ => Ok(addr),
+            })
+            .finish(|req: Request| {
+                let addr = req.extensions().get::<Option<net::SocketAddr>>().cloned();
+                ok::<_, ()>(Response::Ok().body(format!("{:?}", addr)))
+            })
+            .tcp()
+    })
+    .await;
+
+    // callback sees client address of the header, not the proxy address
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n");
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    let data = String::from_utf8_lossy(&data[..n]);
+    assert!(data.ends_with("Some(Some(192.168.0.1:56324))"));
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"PROXY TCP4 127.0.0.1 192.168.0.11 56324 443\r\n");
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.is_empty());
+}
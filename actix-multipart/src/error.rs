@@ -31,6 +31,9 @@ pub enum MultipartError {
     /// Not consumed
     #[display(fmt = "Multipart stream is not consumed")]
     NotConsumed,
+    /// Buffer limit is reached
+    #[display(fmt = "Multipart buffer limit is reached")]
+    Overflow,
 }
 
 impl std::error::Error for MultipartError {}
@@ -5,4 +5,4 @@ mod extractor;
 mod server;
 
 pub use self::error::MultipartError;
-pub use self::server::{Field, Multipart};
+pub use self::server::{Field, Multipart, Progress};
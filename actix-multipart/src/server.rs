@@ -34,6 +34,25 @@ pub struct Multipart {
     inner: Option<Rc<RefCell<InnerMultipart>>>,
 }
 
+/// Upload progress of a multipart stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    received: u64,
+    total: Option<u64>,
+}
+
+impl Progress {
+    /// Number of bytes received so far
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Total size of the payload from the `Content-Length` header, if known
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+}
+
 enum InnerMultipartItem {
     None,
     Field(Rc<RefCell<InnerField>>),
@@ -56,6 +75,7 @@ struct InnerMultipart {
     boundary: String,
     state: InnerState,
     item: InnerMultipartItem,
+    limit: usize,
 }
 
 impl Multipart {
@@ -65,16 +85,25 @@ impl Multipart {
         S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
     {
         match Self::boundary(headers) {
-            Ok(boundary) => Multipart {
-                error: None,
-                safety: Safety::new(),
-                inner: Some(Rc::new(RefCell::new(InnerMultipart {
-                    boundary,
-                    payload: PayloadRef::new(PayloadBuffer::new(Box::new(stream))),
-                    state: InnerState::FirstBoundary,
-                    item: InnerMultipartItem::None,
-                }))),
-            },
+            Ok(boundary) => {
+                let mut payload = PayloadBuffer::new(Box::new(stream));
+                payload.total = headers
+                    .get(&header::CONTENT_LENGTH)
+                    .and_then(|len| len.to_str().ok())
+                    .and_then(|len| len.parse().ok());
+
+                Multipart {
+                    error: None,
+                    safety: Safety::new(),
+                    inner: Some(Rc::new(RefCell::new(InnerMultipart {
+                        boundary,
+                        payload: PayloadRef::new(payload),
+                        state: InnerState::FirstBoundary,
+                        item: InnerMultipartItem::None,
+                        limit: usize::MAX,
+                    }))),
+                }
+            }
             Err(err) => Multipart {
                 error: Some(err),
                 safety: Safety::new(),
@@ -103,6 +132,46 @@ impl Multipart {
             Err(MultipartError::NoContentType)
         }
     }
+
+    /// Set limit of the internal read-ahead buffer, in bytes.
+    ///
+    /// Payload stream is not polled while the buffer holds `limit` bytes, so
+    /// memory stays bounded if fields are consumed slowly. Field headers and
+    /// boundaries have to fit into the buffer, otherwise
+    /// `MultipartError::Overflow` is returned. By default buffer is not
+    /// limited. Limit can be changed for a single field with
+    /// [`Field::set_buffer_limit`](struct.Field.html#method.set_buffer_limit).
+    pub fn buffer_limit(self, limit: usize) -> Self {
+        if let Some(ref inner) = self.inner {
+            let mut inner = inner.borrow_mut();
+            inner.limit = limit;
+            inner.payload.payload.borrow_mut().limit = limit;
+        }
+        self
+    }
+
+    /// Set callback that is called with upload progress every time a chunk
+    /// of the payload is received.
+    pub fn on_progress<F>(self, f: F) -> Self
+    where
+        F: Fn(Progress) + 'static,
+    {
+        if let Some(ref inner) = self.inner {
+            inner.borrow().payload.payload.borrow_mut().on_progress = Some(Box::new(f));
+        }
+        self
+    }
+
+    /// Get current upload progress.
+    pub fn progress(&self) -> Progress {
+        match self.inner {
+            Some(ref inner) => inner.borrow().payload.payload.borrow().progress(),
+            None => Progress {
+                received: 0,
+                total: None,
+            },
+        }
+    }
 }
 
 impl Stream for Multipart {
@@ -117,10 +186,15 @@ impl Stream for Multipart {
         } else if self.safety.current() {
             let this = self.get_mut();
             let mut inner = this.inner.as_mut().unwrap().borrow_mut();
-            if let Some(mut payload) = inner.payload.get_mut(&this.safety) {
-                payload.poll_stream(cx)?;
+            loop {
+                if let Some(mut payload) = inner.payload.get_mut(&this.safety) {
+                    payload.poll_stream(cx)?;
+                }
+                match inner.poll(&this.safety, cx) {
+                    Poll::Pending if inner.payload.poll_again(&this.safety)? => continue,
+                    res => return res,
+                }
             }
-            inner.poll(&this.safety, cx)
         } else if !self.safety.is_clean() {
             Poll::Ready(Some(Err(MultipartError::NotConsumed)))
         } else {
@@ -271,6 +345,9 @@ impl InnerMultipart {
                     };
                     if stop {
                         self.item = InnerMultipartItem::None;
+                        if let Some(mut payload) = self.payload.get_mut(safety) {
+                            payload.limit = self.limit;
+                        }
                     }
                     if let InnerMultipartItem::None = self.item {
                         break;
@@ -413,6 +490,15 @@ impl Field {
             None
         }
     }
+
+    /// Set limit of the internal read-ahead buffer while this field is read.
+    ///
+    /// Limit is reset to the multipart default when the next field starts.
+    pub fn set_buffer_limit(&mut self, limit: usize) {
+        if let Some(ref payload) = self.inner.borrow().payload {
+            payload.payload.borrow_mut().limit = limit;
+        }
+    }
 }
 
 impl Stream for Field {
@@ -421,12 +507,25 @@ impl Stream for Field {
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
         if self.safety.current() {
             let mut inner = self.inner.borrow_mut();
-            if let Some(mut payload) =
-                inner.payload.as_ref().unwrap().get_mut(&self.safety)
-            {
-                payload.poll_stream(cx)?;
+            loop {
+                if let Some(mut payload) =
+                    inner.payload.as_ref().unwrap().get_mut(&self.safety)
+                {
+                    payload.poll_stream(cx)?;
+                }
+                match inner.poll(&self.safety) {
+                    Poll::Pending
+                        if inner
+                            .payload
+                            .as_ref()
+                            .unwrap()
+                            .poll_again(&self.safety)? =>
+                    {
+                        continue
+                    }
+                    res => return res,
+                }
             }
-            inner.poll(&self.safety)
         } else if !self.safety.is_clean() {
             Poll::Ready(Some(Err(MultipartError::NotConsumed)))
         } else {
@@ -651,6 +750,19 @@ impl PayloadRef {
             None
         }
     }
+
+    /// Check if payload stream has to be polled again after parser returned
+    /// `Pending`.
+    ///
+    /// Stream is not polled while the buffer is full, so parser fails if it
+    /// needs more data than the buffer fits.
+    fn poll_again(&self, s: &Safety) -> Result<bool, MultipartError> {
+        match self.get_mut(s) {
+            Some(ref payload) if payload.is_full() => Err(MultipartError::Overflow),
+            Some(ref payload) => Ok(payload.limited),
+            None => Ok(false),
+        }
+    }
 }
 
 impl Clone for PayloadRef {
@@ -720,6 +832,11 @@ impl Drop for Safety {
 struct PayloadBuffer {
     eof: bool,
     buf: BytesMut,
+    limit: usize,
+    limited: bool,
+    received: u64,
+    total: Option<u64>,
+    on_progress: Option<Box<dyn Fn(Progress)>>,
     stream: LocalBoxStream<'static, Result<Bytes, PayloadError>>,
 }
 
@@ -732,14 +849,42 @@ impl PayloadBuffer {
         PayloadBuffer {
             eof: false,
             buf: BytesMut::new(),
+            limit: usize::MAX,
+            limited: false,
+            received: 0,
+            total: None,
+            on_progress: None,
             stream: stream.boxed_local(),
         }
     }
 
+    fn progress(&self) -> Progress {
+        Progress {
+            received: self.received,
+            total: self.total,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        !self.eof && self.buf.len() >= self.limit
+    }
+
     fn poll_stream(&mut self, cx: &mut Context) -> Result<(), PayloadError> {
+        self.limited = false;
         loop {
+            if self.is_full() {
+                self.limited = true;
+                return Ok(());
+            }
+
             match Pin::new(&mut self.stream).poll_next(cx) {
-                Poll::Ready(Some(Ok(data))) => self.buf.extend_from_slice(&data),
+                Poll::Ready(Some(Ok(data))) => {
+                    self.received += data.len() as u64;
+                    self.buf.extend_from_slice(&data);
+                    if let Some(ref f) = self.on_progress {
+                        f(self.progress());
+                    }
+                }
                 Poll::Ready(Some(Err(e))) => return Err(e),
                 Poll::Ready(None) => {
                     self.eof = true;
@@ -1056,6 +1201,63 @@ mod tests {
         }
     }
 
+    fn chunked(bytes: Bytes, size: usize) -> Vec<Result<Bytes, PayloadError>> {
+        (0..bytes.len())
+            .step_by(size)
+            .map(|pos| Ok(bytes.slice(pos..cmp::min(pos + size, bytes.len()))))
+            .collect()
+    }
+
+    #[actix_rt::test]
+    async fn test_progress() {
+        let (bytes, mut headers) = create_simple_request_with_header();
+        let len = bytes.len() as u64;
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let reported2 = reported.clone();
+        let payload = futures::stream::iter(chunked(bytes, 16));
+        let mut multipart = Multipart::new(&headers, payload)
+            .on_progress(move |p| reported2.borrow_mut().push(p.received()));
+        assert_eq!(multipart.progress().received(), 0);
+        assert_eq!(multipart.progress().total(), Some(len));
+
+        while let Some(field) = multipart.next().await {
+            get_whole_field(&mut field.unwrap()).await;
+        }
+        assert_eq!(multipart.progress().received(), len);
+
+        let reported = reported.borrow();
+        assert_eq!(reported.len(), (len as usize + 15) / 16);
+        assert_eq!(*reported.last().unwrap(), len);
+    }
+
+    #[actix_rt::test]
+    async fn test_buffer_limit() {
+        let (bytes, headers) = create_simple_request_with_header();
+        let payload = futures::stream::iter(chunked(bytes.clone(), 8));
+        let mut multipart = Multipart::new(&headers, payload).buffer_limit(128);
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert!(multipart.progress().received() < bytes.len() as u64);
+        field.set_buffer_limit(8);
+        assert_eq!(get_whole_field(&mut field).await, "test");
+        drop(field);
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert_eq!(get_whole_field(&mut field).await, "data");
+        drop(field);
+        assert!(multipart.next().await.is_none());
+
+        // field headers do not fit into the buffer
+        let payload = futures::stream::iter(chunked(bytes, 8));
+        let mut multipart = Multipart::new(&headers, payload).buffer_limit(32);
+        match multipart.next().await {
+            Some(Err(MultipartError::Overflow)) => (),
+            _ => unreachable!(),
+        }
+    }
+
     #[actix_rt::test]
     async fn test_basic() {
         let (_, payload) = Payload::create(false);
@@ -1,6 +1,8 @@
 #![allow(clippy::borrow_interior_mutable_const)]
 //! Actix actors integration for Actix web framework
 mod context;
+pub mod protocol;
+mod registry;
 pub mod ws;
 
 pub use self::context::HttpContext;
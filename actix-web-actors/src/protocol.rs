@@ -0,0 +1,377 @@
+//! Typed message protocol over websocket
+//!
+//! Websocket actor implements `Protocol` trait instead of handling raw
+//! frames. Text and binary frames are deserialized to `Protocol::Request`
+//! messages, responses are serialized with the same `Format`. Frames that
+//! can not be decoded are answered with error message, connection stays
+//! open. Ping, pong and close frames are handled automatically.
+//!
+//! ```rust
+//! use actix::{Actor, AsyncContext};
+//! use actix_web::{web, App, Error, HttpRequest, HttpResponse};
+//! use actix_web_actors::protocol::{self, Json, Protocol};
+//! use actix_web_actors::ws;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize)]
+//! #[serde(tag = "type", rename_all = "lowercase")]
+//! enum Request {
+//!     Join { room: String },
+//!     Say { text: String },
+//! }
+//!
+//! #[derive(Serialize)]
+//! #[serde(tag = "type", rename_all = "lowercase")]
+//! enum Response {
+//!     Joined { room: String },
+//!     Said { text: String },
+//! }
+//!
+//! struct Chat {
+//!     version: Option<&'static str>,
+//! }
+//!
+//! impl Actor for Chat {
+//!     type Context = ws::WebsocketContext<Self>;
+//! }
+//!
+//! impl Protocol for Chat {
+//!     type Request = Request;
+//!     type Response = Response;
+//!     type Format = Json;
+//!
+//!     const VERSIONS: &'static [&'static str] = &["chat.v2", "chat.v1"];
+//!
+//!     fn handle(&mut self, msg: Request, ctx: &mut Self::Context) {
+//!         let res = match msg {
+//!             Request::Join { room } => Response::Joined { room },
+//!             Request::Say { text } => Response::Said { text },
+//!         };
+//!         let _ = ctx.send_message(&res);
+//!     }
+//! }
+//!
+//! async fn index(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
+//!     protocol::start(&req, stream, |version| Chat { version })
+//! }
+//!
+//! # fn main() {
+//! let app = App::new().route("/ws/", web::get().to(index));
+//! # }
+//! ```
+use std::error::Error as StdError;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use actix::fut::ActorFuture;
+use actix::{Actor, ActorContext, AsyncContext};
+use actix_web::error::{Error, ErrorBadRequest, PayloadError};
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse};
+use bytes::Bytes;
+use futures::Stream;
+use pin_project::pin_project;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::ws::{self, CloseCode, Message, ProtocolError, WebsocketContext};
+
+/// Websocket actor with typed messages.
+pub trait Protocol: Actor<Context = WebsocketContext<Self>> {
+    /// Messages received from the client.
+    type Request: DeserializeOwned;
+
+    /// Messages sent to the client.
+    type Response: Serialize;
+
+    /// Serialization format of messages.
+    type Format: Format;
+
+    /// Supported protocol versions in order of preference.
+    ///
+    /// Versions are negotiated with `Sec-WebSocket-Protocol` header. Client
+    /// that requests only unknown versions is rejected with
+    /// *BAD REQUEST* response.
+    const VERSIONS: &'static [&'static str] = &[];
+
+    /// Method is called for every decoded client message.
+    fn handle(&mut self, msg: Self::Request, ctx: &mut Self::Context);
+
+    /// Method is called when client message can not be decoded.
+    ///
+    /// By default error message `{"error": "<description>"}` is sent to
+    /// the client.
+    fn error(&mut self, err: CodecError, ctx: &mut Self::Context) {
+        let msg = ErrorMessage {
+            error: err.to_string(),
+        };
+        if let Ok(msg) = Self::Format::encode(&msg) {
+            ctx.write_raw(msg);
+        }
+    }
+
+    /// Method is called when client closes the stream.
+    ///
+    /// By default actor is stopped.
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        ctx.stop()
+    }
+}
+
+/// Serialization format of protocol messages.
+///
+/// Only `Json` format is provided, binary formats have to be implemented by
+/// the application on top of corresponding serde crate.
+pub trait Format {
+    /// Serialize message to websocket frame.
+    fn encode<T: Serialize>(msg: &T) -> Result<Message, CodecError>;
+
+    /// Deserialize message from payload of text or binary frame.
+    fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<T, CodecError>;
+}
+
+/// Json format, messages are sent as text frames.
+#[derive(Debug, Clone, Copy)]
+pub struct Json;
+
+impl Format for Json {
+    fn encode<T: Serialize>(msg: &T) -> Result<Message, CodecError> {
+        serde_json::to_string(msg)
+            .map(Message::Text)
+            .map_err(|e| CodecError::Encode(Box::new(e)))
+    }
+
+    fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<T, CodecError> {
+        serde_json::from_slice(data).map_err(|e| CodecError::Decode(Box::new(e)))
+    }
+}
+
+/// Protocol message serialization error
+#[derive(Debug)]
+pub enum CodecError {
+    /// Message can not be serialized
+    Encode(Box<dyn StdError>),
+    /// Message can not be deserialized
+    Decode(Box<dyn StdError>),
+    /// Fragmented messages are not supported
+    Fragmented,
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Encode(e) => write!(f, "Can not encode message: {}", e),
+            CodecError::Decode(e) => write!(f, "Can not decode message: {}", e),
+            CodecError::Fragmented => {
+                f.write_str("Fragmented messages are not supported")
+            }
+        }
+    }
+}
+
+impl StdError for CodecError {}
+
+#[derive(Serialize)]
+struct ErrorMessage {
+    error: String,
+}
+
+impl<A> WebsocketContext<A>
+where
+    A: Protocol,
+{
+    /// Serialize and send protocol message
+    pub fn send_message(&mut self, msg: &A::Response) -> Result<(), CodecError> {
+        self.write_raw(A::Format::encode(msg)?);
+        Ok(())
+    }
+}
+
+/// Do websocket handshake, negotiate protocol version and start protocol
+/// actor.
+///
+/// Actor is created by `f` with negotiated version, version is `None`
+/// if client does not request any version or protocol does not declare
+/// versions.
+pub fn start<A, F, T>(req: &HttpRequest, stream: T, f: F) -> Result<HttpResponse, Error>
+where
+    A: Protocol,
+    F: FnOnce(Option<&'static str>) -> A,
+    T: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+{
+    let mut res = ws::handshake(req)?;
+    let version = negotiate(req, A::VERSIONS)?;
+    if let Some(version) = version {
+        res.header(header::SEC_WEBSOCKET_PROTOCOL, version);
+    }
+
+    Ok(res.streaming(WebsocketContext::with_handler(
+        f(version),
+        stream,
+        ProtocolStream::new,
+    )))
+}
+
+/// Select most preferred version requested by the client.
+fn negotiate(
+    req: &HttpRequest,
+    versions: &[&'static str],
+) -> Result<Option<&'static str>, Error> {
+    if versions.is_empty() {
+        return Ok(None);
+    }
+
+    let requested: Vec<&str> = req
+        .headers()
+        .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(|val| val.trim())
+        .filter(|val| !val.is_empty())
+        .collect();
+    if requested.is_empty() {
+        return Ok(None);
+    }
+
+    versions
+        .iter()
+        .find(|ver| requested.contains(ver))
+        .map(|ver| Some(*ver))
+        .ok_or_else(|| ErrorBadRequest("Unsupported protocol version"))
+}
+
+/// Decodes incoming messages and dispatches them to the protocol actor.
+#[pin_project]
+struct ProtocolStream<A, S> {
+    #[pin]
+    stream: S,
+    _t: PhantomData<A>,
+}
+
+impl<A, S> ProtocolStream<A, S> {
+    fn new(stream: S) -> Self {
+        ProtocolStream {
+            stream,
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<A, S> ActorFuture for ProtocolStream<A, S>
+where
+    A: Protocol,
+    S: Stream<Item = Result<Message, ProtocolError>>,
+{
+    type Output = ();
+    type Actor = A;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut WebsocketContext<A>,
+        task: &mut Context<'_>,
+    ) -> Poll<()> {
+        let this = self.project();
+
+        match this.stream.poll_next(task) {
+            Poll::Ready(Some(Ok(msg))) => {
+                let res = match msg {
+                    Message::Text(text) => A::Format::decode(text.as_bytes()),
+                    Message::Binary(data) => A::Format::decode(&data),
+                    Message::Continuation(_) => Err(CodecError::Fragmented),
+                    Message::Ping(data) => {
+                        ctx.pong(&data);
+                        return wake(ctx, task);
+                    }
+                    Message::Close(reason) => {
+                        ctx.close(reason);
+                        ctx.stop();
+                        return Poll::Ready(());
+                    }
+                    Message::Pong(_) | Message::Nop => return wake(ctx, task),
+                };
+
+                match res {
+                    Ok(msg) => <A as Protocol>::handle(act, msg, ctx),
+                    Err(err) => act.error(err, ctx),
+                }
+                wake(ctx, task)
+            }
+            Poll::Ready(Some(Err(_))) => {
+                ctx.close(Some(CloseCode::Protocol.into()));
+                ctx.stop();
+                Poll::Ready(())
+            }
+            Poll::Ready(None) => {
+                <A as Protocol>::finished(act, ctx);
+                Poll::Ready(())
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+fn wake<A: Protocol>(ctx: &WebsocketContext<A>, task: &mut Context<'_>) -> Poll<()> {
+    if !ctx.waiting() {
+        // next message could be ready right away
+        task.waker().wake_by_ref();
+    }
+    Poll::Pending
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn request(protocol: Option<&'static str>) -> HttpRequest {
+        let mut req = TestRequest::default()
+            .header(header::UPGRADE, "websocket")
+            .header(header::CONNECTION, "upgrade")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, "13");
+        if let Some(protocol) = protocol {
+            req = req.header(header::SEC_WEBSOCKET_PROTOCOL, protocol);
+        }
+        req.to_http_request()
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let versions = ["chat.v2", "chat.v1"];
+
+        let req = request(None);
+        assert_eq!(negotiate(&req, &versions).unwrap(), None);
+
+        let req = request(Some("chat.v1, chat.v2"));
+        assert_eq!(negotiate(&req, &versions).unwrap(), Some("chat.v2"));
+
+        let req = request(Some("chat.v1,other"));
+        assert_eq!(negotiate(&req, &versions).unwrap(), Some("chat.v1"));
+
+        let req = request(Some("chat.v3"));
+        let err = negotiate(&req, &versions).err().unwrap();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+
+        assert_eq!(negotiate(&req, &[]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_json() {
+        let msg = Json::encode(&vec![1, 2]).unwrap();
+        assert_eq!(msg, Message::Text("[1,2]".to_owned()));
+
+        let val: Vec<u32> = Json::decode(b"[1,2]").unwrap();
+        assert_eq!(val, vec![1, 2]);
+
+        let err = Json::decode::<Vec<u32>>(b"{").err().unwrap();
+        assert!(err.to_string().starts_with("Can not decode message: "));
+    }
+}
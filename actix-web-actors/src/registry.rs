@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use actix::fut::ActorFuture;
+use actix::{Actor, ActorContext, AsyncContext, SpawnHandle};
+use bytes::Bytes;
+use futures::channel::mpsc::{channel, Receiver, Sender};
+use futures::{SinkExt, Stream};
+use pin_project::pin_project;
+
+use crate::ws::{CloseReason, Message, WebsocketContext};
+
+/// Queue of a session, locked while `Registry::send()` waits for free space.
+type Session = Arc<futures::lock::Mutex<Sender<Push>>>;
+
+/// Message pushed to registered websocket sessions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Push {
+    /// Text frame
+    Text(String),
+    /// Binary frame
+    Binary(Bytes),
+    /// Close frame, session is stopped after the frame is sent
+    Close(Option<CloseReason>),
+}
+
+impl From<String> for Push {
+    fn from(s: String) -> Self {
+        Push::Text(s)
+    }
+}
+
+impl<'a> From<&'a str> for Push {
+    fn from(s: &'a str) -> Self {
+        Push::Text(s.to_owned())
+    }
+}
+
+impl From<Bytes> for Push {
+    fn from(b: Bytes) -> Self {
+        Push::Binary(b)
+    }
+}
+
+/// Registry of websocket sessions.
+///
+/// Sessions register under keys, like user id or room name, and messages
+/// could be pushed to all sessions of a key from any handler. Registry is
+/// shared between all workers of the server, it has to be created outside
+/// of the `HttpServer::new()` closure. Session could be registered under
+/// multiple keys, session is removed from registry when it stops.
+///
+/// Every session has a bounded queue of pushed messages (default: 64
+/// messages). [`push`](#method.push) skips sessions with full queues,
+/// [`send`](#method.send) waits until slow sessions have free space.
+///
+/// ```rust
+/// use actix::{Actor, StreamHandler};
+/// use actix_web::{web, App, Error, HttpRequest, HttpResponse, HttpServer};
+/// use actix_web_actors::ws;
+///
+/// struct Session {
+///     user: String,
+///     registry: ws::Registry,
+/// }
+///
+/// impl Actor for Session {
+///     type Context = ws::WebsocketContext<Self>;
+///
+///     fn started(&mut self, ctx: &mut Self::Context) {
+///         self.registry.register(self.user.clone(), ctx);
+///     }
+/// }
+///
+/// impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Session {
+///     fn handle(&mut self, _: Result<ws::Message, ws::ProtocolError>, _: &mut Self::Context) {}
+/// }
+///
+/// async fn connect(
+///     req: HttpRequest,
+///     stream: web::Payload,
+///     user: web::Path<String>,
+///     registry: web::Data<ws::Registry>,
+/// ) -> Result<HttpResponse, Error> {
+///     let registry = registry.get_ref().clone();
+///     ws::start(Session { user: user.into_inner(), registry }, &req, stream)
+/// }
+///
+/// async fn notify(user: web::Path<String>, registry: web::Data<ws::Registry>) -> HttpResponse {
+///     let sent = registry.push(&user, "You have new message");
+///     HttpResponse::Ok().body(format!("sent to {} sessions", sent))
+/// }
+///
+/// # fn main() {
+/// let registry = ws::Registry::new();
+/// HttpServer::new(move || {
+///     App::new()
+///         .data(registry.clone())
+///         .route("/ws/{user}", web::get().to(connect))
+///         .route("/notify/{user}", web::post().to(notify))
+/// });
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Registry {
+    sessions: Arc<Mutex<HashMap<String, Vec<Session>>>>,
+    capacity: usize,
+}
+
+impl Registry {
+    /// Create empty registry.
+    pub fn new() -> Self {
+        Registry::with_capacity(64)
+    }
+
+    /// Create empty registry with the capacity of session queues.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Registry {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            capacity,
+        }
+    }
+
+    /// Register session under the key.
+    ///
+    /// Pushed messages are written to the session by future spawned in the
+    /// session context, session is unregistered when the future is
+    /// cancelled with returned handle or the session stops.
+    pub fn register<A, K>(&self, key: K, ctx: &mut WebsocketContext<A>) -> SpawnHandle
+    where
+        A: Actor<Context = WebsocketContext<A>>,
+        K: Into<String>,
+    {
+        let (tx, rx) = channel(self.capacity);
+        let mut sessions = self.sessions.lock().unwrap();
+        let entry = sessions.entry(key.into()).or_default();
+        entry.retain(|tx| !is_closed(tx));
+        entry.push(Arc::new(futures::lock::Mutex::new(tx)));
+
+        ctx.spawn(Forward {
+            rx,
+            _t: PhantomData,
+        })
+    }
+
+    /// Push message to all sessions registered under the key.
+    ///
+    /// Message is not delivered to sessions with full queues. Returns number
+    /// of sessions message is sent to.
+    pub fn push<M: Into<Push>>(&self, key: &str, msg: M) -> usize {
+        let msg = msg.into();
+        let mut sessions = self.sessions.lock().unwrap();
+
+        let entry = match sessions.get_mut(key) {
+            Some(entry) => entry,
+            None => return 0,
+        };
+        let mut sent = 0;
+        for tx in entry.iter() {
+            if let Some(mut tx) = tx.try_lock() {
+                if tx.try_send(msg.clone()).is_ok() {
+                    sent += 1;
+                }
+            }
+        }
+        entry.retain(|tx| !is_closed(tx));
+        if entry.is_empty() {
+            sessions.remove(key);
+        }
+        sent
+    }
+
+    /// Send message to all sessions registered under the key, waiting for
+    /// free space in queues of slow sessions.
+    ///
+    /// Returns number of sessions message is sent to.
+    pub async fn send<M: Into<Push>>(&self, key: &str, msg: M) -> usize {
+        let msg = msg.into();
+        let senders = match self.sessions.lock().unwrap().get(key) {
+            Some(entry) => entry.clone(),
+            None => return 0,
+        };
+
+        let mut sent = 0;
+        for tx in senders {
+            let mut tx = tx.lock().await;
+            if tx.feed(msg.clone()).await.is_ok() {
+                sent += 1;
+            }
+        }
+        sent
+    }
+
+    /// Number of active sessions registered under the key.
+    pub fn sessions(&self, key: &str) -> usize {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(key)
+            .map_or(0, |entry| entry.iter().filter(|tx| !is_closed(tx)).count())
+    }
+
+    /// Keys with active sessions.
+    pub fn keys(&self) -> Vec<String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, entry| {
+            entry.retain(|tx| !is_closed(tx));
+            !entry.is_empty()
+        });
+        sessions.keys().cloned().collect()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry::new()
+    }
+}
+
+/// Session is closed when its receiver is dropped, locked queue is in use.
+fn is_closed(tx: &Session) -> bool {
+    match tx.try_lock() {
+        Some(tx) => tx.is_closed(),
+        None => false,
+    }
+}
+
+/// Writes pushed messages to the session.
+#[pin_project]
+struct Forward<A> {
+    #[pin]
+    rx: Receiver<Push>,
+    _t: PhantomData<A>,
+}
+
+impl<A> ActorFuture for Forward<A>
+where
+    A: Actor<Context = WebsocketContext<A>>,
+{
+    type Output = ();
+    type Actor = A;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _: &mut A,
+        ctx: &mut WebsocketContext<A>,
+        task: &mut Context<'_>,
+    ) -> Poll<()> {
+        let mut this = self.project();
+
+        loop {
+            match this.rx.as_mut().poll_next(task) {
+                Poll::Ready(Some(Push::Text(text))) => {
+                    ctx.write_raw(Message::Text(text))
+                }
+                Poll::Ready(Some(Push::Binary(data))) => {
+                    ctx.write_raw(Message::Binary(data))
+                }
+                Poll::Ready(Some(Push::Close(reason))) => {
+                    ctx.close(reason);
+                    ctx.stop();
+                    return Poll::Ready(());
+                }
+                Poll::Ready(None) => return Poll::Ready(()),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+
+    #[test]
+    fn test_push_without_sessions() {
+        let registry = Registry::new();
+        assert_eq!(registry.push("user", "test"), 0);
+        assert_eq!(registry.sessions("user"), 0);
+
+        let (tx, rx) = channel(1);
+        registry.sessions.lock().unwrap().insert(
+            "user".to_owned(),
+            vec![Arc::new(futures::lock::Mutex::new(tx))],
+        );
+        assert_eq!(registry.sessions("user"), 1);
+        assert_eq!(registry.keys(), vec!["user".to_owned()]);
+
+        // closed sessions are removed
+        drop(rx);
+        assert_eq!(registry.sessions("user"), 0);
+        assert_eq!(registry.push("user", Bytes::from_static(b"test")), 0);
+        assert!(registry.keys().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_full_queue() {
+        let registry = Registry::with_capacity(0);
+        let (tx, mut rx) = channel(0);
+        registry.sessions.lock().unwrap().insert(
+            "user".to_owned(),
+            vec![Arc::new(futures::lock::Mutex::new(tx))],
+        );
+
+        // queue has a single slot per sender
+        assert_eq!(registry.push("user", "first"), 1);
+        assert_eq!(registry.push("user", "second"), 0);
+        assert_eq!(registry.sessions("user"), 1);
+
+        let send = registry.send("user", "third");
+        futures::pin_mut!(send);
+        assert!(futures::poll!(send.as_mut()).is_pending());
+        assert_eq!(rx.next().await, Some(Push::Text("first".to_owned())));
+        assert_eq!(send.await, 1);
+        assert_eq!(rx.next().await, Some(Push::Text("third".to_owned())));
+    }
+}
@@ -26,6 +26,8 @@ use bytes::{Bytes, BytesMut};
 use futures::channel::oneshot::Sender;
 use futures::{Future, Stream};
 
+pub use crate::registry::{Push, Registry};
+
 /// Do websocket handshake and start ws actor.
 pub fn start<A, T>(actor: A, req: &HttpRequest, stream: T) -> Result<HttpResponse, Error>
 where
@@ -319,6 +321,28 @@ where
 
         WebsocketContextFut::new(ctx, act, mb, Codec::new())
     }
+
+    /// Create a new Websocket context, incoming messages are processed by
+    /// actor future created by `f` instead of `StreamHandler`.
+    pub(crate) fn with_handler<S, F, H>(
+        actor: A,
+        stream: S,
+        f: F,
+    ) -> impl Stream<Item = Result<Bytes, Error>>
+    where
+        F: FnOnce(WsStream<S>) -> H,
+        H: ActorFuture<Output = (), Actor = A> + 'static,
+        S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+    {
+        let mb = Mailbox::default();
+        let mut ctx = WebsocketContext {
+            inner: ContextParts::new(mb.sender_producer()),
+            messages: VecDeque::new(),
+        };
+        ctx.spawn(f(WsStream::new(stream, Codec::new())));
+
+        WebsocketContextFut::new(ctx, actor, mb, Codec::new())
+    }
 }
 
 impl<A> WebsocketContext<A>
@@ -463,7 +487,7 @@ where
 }
 
 #[pin_project::pin_project]
-struct WsStream<S> {
+pub(crate) struct WsStream<S> {
     #[pin]
     stream: S,
     decoder: Codec,
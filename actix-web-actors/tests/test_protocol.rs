@@ -0,0 +1,103 @@
+use actix::prelude::*;
+use actix_web::{test, web, App, HttpRequest};
+use actix_web_actors::protocol::{self, Json, Protocol};
+use actix_web_actors::*;
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Request {
+    Add { a: i64, b: i64 },
+    Version,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Response {
+    Sum(i64),
+    Version(Option<&'static str>),
+}
+
+struct Calc {
+    version: Option<&'static str>,
+}
+
+impl Actor for Calc {
+    type Context = ws::WebsocketContext<Self>;
+}
+
+impl Protocol for Calc {
+    type Request = Request;
+    type Response = Response;
+    type Format = Json;
+
+    const VERSIONS: &'static [&'static str] = &["calc.v1"];
+
+    fn handle(&mut self, msg: Request, ctx: &mut Self::Context) {
+        let res = match msg {
+            Request::Add { a, b } => Response::Sum(a + b),
+            Request::Version => Response::Version(self.version),
+        };
+        ctx.send_message(&res).unwrap();
+    }
+}
+
+#[actix_rt::test]
+async fn test_protocol() {
+    let mut srv = test::start(|| {
+        App::new().service(web::resource("/").to(
+            |req: HttpRequest, stream: web::Payload| async move {
+                protocol::start(&req, stream, |version| Calc { version })
+            },
+        ))
+    });
+
+    let mut framed = srv.ws().await.unwrap();
+    framed
+        .send(ws::Message::Text(
+            r#"{"type":"add","a":1,"b":2}"#.to_owned(),
+        ))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(br#"{"sum":3}"#)));
+
+    framed
+        .send(ws::Message::Binary(r#"{"type":"version"}"#.into()))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(
+        item,
+        ws::Frame::Text(Bytes::from_static(br#"{"version":null}"#))
+    );
+
+    // invalid message is answered with error, connection stays open
+    framed
+        .send(ws::Message::Text(r#"{"type":"mul"}"#.to_owned()))
+        .await
+        .unwrap();
+    match framed.next().await.unwrap().unwrap() {
+        ws::Frame::Text(data) => {
+            let err: serde_json::Value = serde_json::from_slice(&data).unwrap();
+            assert!(err["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("Can not decode message"));
+        }
+        frame => panic!("unexpected frame {:?}", frame),
+    }
+
+    framed.send(ws::Message::Ping("text".into())).await.unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Pong(Bytes::copy_from_slice(b"text")));
+
+    framed
+        .send(ws::Message::Close(Some(ws::CloseCode::Normal.into())))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_web::{test, web, App, Error, HttpRequest, HttpResponse};
+use actix_web_actors::*;
+use bytes::Bytes;
+use futures::future::ok;
+use futures::StreamExt;
+
+struct Session {
+    user: String,
+    registry: ws::Registry,
+}
+
+impl Actor for Session {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.registry.register(self.user.clone(), ctx);
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Session {
+    fn handle(
+        &mut self,
+        _: Result<ws::Message, ws::ProtocolError>,
+        _: &mut Self::Context,
+    ) {
+    }
+}
+
+#[actix_rt::test]
+async fn test_registry() {
+    let registry = ws::Registry::new();
+    let registry2 = registry.clone();
+
+    let mut srv = test::start(move || {
+        App::new()
+            .data(registry2.clone())
+            .route(
+                "/ws/{user}",
+                web::get().to(
+                    |req: HttpRequest,
+                     stream: web::Payload,
+                     user: web::Path<String>,
+                     registry: web::Data<ws::Registry>| {
+                        let session = Session {
+                            user: user.into_inner(),
+                            registry: registry.get_ref().clone(),
+                        };
+                        async move { ws::start(session, &req, stream) }
+                    },
+                ),
+            )
+            .route(
+                "/push/{user}",
+                web::post().to(
+                    |user: web::Path<String>, registry: web::Data<ws::Registry>| {
+                        let sent = registry.push(&user, "hello");
+                        ok::<_, Error>(HttpResponse::Ok().body(sent.to_string()))
+                    },
+                ),
+            )
+    });
+
+    let mut framed = srv.ws_at("/ws/alice").await.unwrap();
+
+    // session is registered when actor starts
+    while registry.sessions("alice") == 0 {
+        actix_rt::time::delay_for(Duration::from_millis(10)).await;
+    }
+    assert_eq!(registry.keys(), vec!["alice".to_owned()]);
+
+    let mut res = srv.post("/push/alice").send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"1"));
+    let mut res = srv.post("/push/bob").send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"0"));
+
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"hello")));
+
+    assert_eq!(registry.push("alice", Bytes::from_static(b"bin")), 1);
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Binary(Bytes::from_static(b"bin")));
+
+    assert_eq!(
+        registry.push("alice", ws::Push::Close(Some(ws::CloseCode::Away.into()))),
+        1
+    );
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Away.into())));
+}
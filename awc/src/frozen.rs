@@ -12,6 +12,7 @@ use actix_http::http::header::IntoHeaderValue;
 use actix_http::http::{Error as HttpError, HeaderMap, HeaderName, Method, Uri};
 use actix_http::{Error, RequestHead};
 
+use crate::hedge::{Hedge, HedgedClientRequest};
 use crate::sender::{RequestSender, SendClientRequest};
 use crate::ClientConfig;
 
@@ -103,6 +104,13 @@ impl FrozenClientRequest {
         )
     }
 
+    /// Send an empty body, hedging request with the policy.
+    ///
+    /// See [`Hedge`](struct.Hedge.html) for details.
+    pub fn send_hedged(&self, hedge: &Hedge) -> HedgedClientRequest {
+        HedgedClientRequest::new(self, hedge)
+    }
+
     /// Create a `FrozenSendBuilder` with extra headers
     pub fn extra_headers(&self, extra_headers: HeaderMap) -> FrozenSendBuilder {
         FrozenSendBuilder::new(self.clone(), extra_headers)
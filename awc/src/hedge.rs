@@ -0,0 +1,273 @@
+//! Request hedging
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::net;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use actix_http::http::Method;
+use actix_rt::time::{delay_for, Delay};
+
+use crate::frozen::FrozenClientRequest;
+use crate::sender::{RequestSender, SendClientRequest};
+
+const WINDOW: usize = 200;
+const MIN_SAMPLES: usize = 20;
+
+/// Request hedging policy.
+///
+/// Hedged request is sent once more if the response does not arrive in time,
+/// the first successful response is used and the other request is dropped.
+/// Hedging delay is the configured latency percentile of recent primary
+/// requests sent with the policy, initial delay is used until enough
+/// latencies are recorded. Dropped primary request is recorded with the
+/// time it was in flight.
+///
+/// Only safe methods (`GET`, `HEAD`, `OPTIONS` and `TRACE`) are hedged by
+/// default, requests with other methods are sent once.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// #[actix_rt::main]
+/// async fn main() {
+///     let hedge = awc::Hedge::new(Duration::from_millis(50)).percentile(95.0);
+///     let req = awc::Client::new()
+///         .get("http://www.rust-lang.org")
+///         .freeze()
+///         .unwrap();
+///
+///     let res = req.send_hedged(&hedge).await;
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Hedge {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    delay: Duration,
+    percentile: Option<f64>,
+    addr: Option<net::SocketAddr>,
+    all_methods: bool,
+    latencies: RefCell<VecDeque<Duration>>,
+}
+
+impl Hedge {
+    /// Create hedging policy with initial delay.
+    pub fn new(delay: Duration) -> Self {
+        Hedge {
+            inner: Rc::new(Inner {
+                delay,
+                percentile: None,
+                addr: None,
+                all_methods: false,
+                latencies: RefCell::new(VecDeque::new()),
+            }),
+        }
+    }
+
+    /// Derive delay from the latency percentile of recent requests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not in `0..=100` range.
+    pub fn percentile(mut self, percentile: f64) -> Self {
+        assert!(
+            (0.0..=100.0).contains(&percentile),
+            "Percentile has to be in 0..=100 range"
+        );
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .percentile = Some(percentile);
+        self
+    }
+
+    /// Send hedged request to a different endpoint.
+    ///
+    /// By default hedged request is sent to the same address over another
+    /// connection.
+    pub fn address(mut self, addr: net::SocketAddr) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .addr = Some(addr);
+        self
+    }
+
+    /// Hedge requests regardless of the method.
+    ///
+    /// Only use for idempotent requests, server may receive both requests.
+    pub fn all_methods(mut self) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .all_methods = true;
+        self
+    }
+
+    /// Current hedging delay.
+    pub fn delay(&self) -> Duration {
+        let percentile = match self.inner.percentile {
+            Some(percentile) => percentile,
+            None => return self.inner.delay,
+        };
+
+        let latencies = self.inner.latencies.borrow();
+        if latencies.len() < MIN_SAMPLES {
+            return self.inner.delay;
+        }
+
+        let mut sorted: Vec<_> = latencies.iter().cloned().collect();
+        sorted.sort();
+        let idx = ((sorted.len() - 1) as f64 * percentile / 100.0).round() as usize;
+        sorted[idx]
+    }
+
+    fn is_hedged(&self, method: &Method) -> bool {
+        const SAFE: [Method; 4] =
+            [Method::GET, Method::HEAD, Method::OPTIONS, Method::TRACE];
+        self.inner.all_methods || SAFE.contains(method)
+    }
+
+    fn record(&self, latency: Duration) {
+        let mut latencies = self.inner.latencies.borrow_mut();
+        if latencies.len() == WINDOW {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+}
+
+/// Future that resolves to the first successful response of hedged requests.
+#[must_use = "futures do nothing unless polled"]
+pub struct HedgedClientRequest {
+    req: FrozenClientRequest,
+    hedge: Hedge,
+    started: Instant,
+    primary: Option<SendClientRequest>,
+    secondary: Option<SendClientRequest>,
+    delay: Option<Delay>,
+}
+
+impl HedgedClientRequest {
+    pub(crate) fn new(req: &FrozenClientRequest, hedge: &Hedge) -> Self {
+        let delay = if hedge.is_hedged(req.get_method()) {
+            Some(delay_for(hedge.delay()))
+        } else {
+            None
+        };
+
+        HedgedClientRequest {
+            primary: Some(req.send()),
+            secondary: None,
+            req: req.clone(),
+            hedge: hedge.clone(),
+            started: Instant::now(),
+            delay,
+        }
+    }
+
+    fn send_secondary(&mut self) {
+        self.delay = None;
+        let addr = self.hedge.inner.addr.or(self.req.addr);
+        self.secondary = Some(RequestSender::Rc(self.req.head.clone(), None).send(
+            addr,
+            self.req.response_decompress,
+            self.req.timeout,
+            self.req.config.as_ref(),
+        ));
+    }
+}
+
+impl Future for HedgedClientRequest {
+    type Output = <SendClientRequest as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if let Some(ref mut delay) = this.delay {
+            if Pin::new(delay).poll(cx).is_ready() {
+                log::trace!("Sending hedged request to {}", this.req.get_uri());
+                this.send_secondary();
+            }
+        }
+
+        if let Some(ref mut fut) = this.primary {
+            if let Poll::Ready(res) = Pin::new(fut).poll(cx) {
+                this.primary = None;
+                match res {
+                    Ok(res) => {
+                        this.hedge.record(this.started.elapsed());
+                        return Poll::Ready(Ok(res));
+                    }
+                    Err(e) => {
+                        // do not wait for the delay if primary request failed
+                        if this.delay.is_some() {
+                            this.send_secondary();
+                        } else if this.secondary.is_none() {
+                            return Poll::Ready(Err(e));
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(ref mut fut) = this.secondary {
+            if let Poll::Ready(res) = Pin::new(fut).poll(cx) {
+                this.secondary = None;
+                match res {
+                    Ok(res) => {
+                        // slow primary request is dropped, its latency is at
+                        // least the elapsed time, which is recorded instead so
+                        // the delay is not biased towards fast requests
+                        if this.primary.take().is_some() {
+                            this.hedge.record(this.started.elapsed());
+                        }
+                        return Poll::Ready(Ok(res));
+                    }
+                    Err(e) => {
+                        if this.primary.is_none() {
+                            return Poll::Ready(Err(e));
+                        }
+                    }
+                }
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hedge_delay() {
+        let hedge = Hedge::new(Duration::from_millis(100)).percentile(90.0);
+        for ms in 1..=(MIN_SAMPLES as u64 - 1) {
+            hedge.record(Duration::from_millis(ms));
+        }
+        assert_eq!(hedge.delay(), Duration::from_millis(100));
+
+        for ms in MIN_SAMPLES as u64..=100 {
+            hedge.record(Duration::from_millis(ms));
+        }
+        assert_eq!(hedge.delay(), Duration::from_millis(90));
+
+        for _ in 0..WINDOW {
+            hedge.record(Duration::from_millis(5));
+        }
+        assert_eq!(hedge.delay(), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_hedge_methods() {
+        let hedge = Hedge::new(Duration::from_millis(10));
+        assert!(hedge.is_hedged(&Method::GET));
+        assert!(!hedge.is_hedged(&Method::POST));
+        assert!(hedge.all_methods().is_hedged(&Method::POST));
+    }
+}
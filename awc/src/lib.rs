@@ -28,7 +28,8 @@ use std::convert::TryFrom;
 use std::rc::Rc;
 use std::time::Duration;
 
-pub use actix_http::{client::Connector, cookie, http};
+pub use actix_http::client::{ConnectPolicy, Connector};
+pub use actix_http::{cookie, http};
 
 use actix_http::http::{Error as HttpError, HeaderMap, Method, Uri};
 use actix_http::RequestHead;
@@ -37,18 +38,24 @@ mod builder;
 mod connect;
 pub mod error;
 mod frozen;
+mod hedge;
 mod request;
 mod response;
+mod route;
 mod sender;
+mod signer;
 pub mod test;
 pub mod ws;
 
 pub use self::builder::ClientBuilder;
 pub use self::connect::BoxedSocket;
 pub use self::frozen::{FrozenClientRequest, FrozenSendBuilder};
+pub use self::hedge::{Hedge, HedgedClientRequest};
 pub use self::request::ClientRequest;
 pub use self::response::{ClientResponse, JsonBody, MessageBody};
+pub use self::route::TypedRoute;
 pub use self::sender::SendClientRequest;
+pub use self::signer::Signer;
 
 use self::connect::{Connect, ConnectorWrapper};
 
@@ -194,6 +201,61 @@ impl Client {
         self.request(Method::OPTIONS, url)
     }
 
+    /// Construct HTTP request of a typed route.
+    ///
+    /// Path of the route is built from `params` and appended to `base`,
+    /// i.e. `http://localhost:8080`. Panics if `params` do not provide all
+    /// parameters of the route.
+    ///
+    /// ```rust
+    /// use awc::{http::Method, Client, TypedRoute};
+    ///
+    /// struct DeleteUser;
+    ///
+    /// impl TypedRoute for DeleteUser {
+    ///     const NAME: &'static str = "delete_user";
+    ///     const PATH: &'static str = "/users/{id}";
+    ///     type Params = u32;
+    /// }
+    ///
+    /// #[actix_rt::main]
+    /// async fn main() {
+    ///     let req = Client::new().request_route::<DeleteUser>(
+    ///         Method::DELETE,
+    ///         "http://localhost:8080",
+    ///         &42,
+    ///     );
+    ///     assert_eq!(req.get_uri(), "http://localhost:8080/users/42");
+    /// }
+    /// ```
+    pub fn request_route<R: TypedRoute>(
+        &self,
+        method: Method,
+        base: &str,
+        params: &R::Params,
+    ) -> ClientRequest {
+        let url = format!("{}{}", base.trim_end_matches('/'), R::path(params));
+        self.request(method, url)
+    }
+
+    /// Construct HTTP *GET* request of a typed route.
+    pub fn get_route<R: TypedRoute>(
+        &self,
+        base: &str,
+        params: &R::Params,
+    ) -> ClientRequest {
+        self.request_route::<R>(Method::GET, base, params)
+    }
+
+    /// Construct HTTP *POST* request of a typed route.
+    pub fn post_route<R: TypedRoute>(
+        &self,
+        base: &str,
+        params: &R::Params,
+    ) -> ClientRequest {
+        self.request_route::<R>(Method::POST, base, params)
+    }
+
     /// Construct WebSockets request.
     pub fn ws<U>(&self, url: U) -> ws::WebsocketsRequest
     where
@@ -21,6 +21,7 @@ use actix_http::{Error, RequestHead};
 use crate::error::{FreezeRequestError, InvalidUrl};
 use crate::frozen::FrozenClientRequest;
 use crate::sender::{PrepForSendingError, RequestSender, SendClientRequest};
+use crate::signer::Signer;
 use crate::ClientConfig;
 
 #[cfg(any(feature = "flate2-zlib", feature = "flate2-rust"))]
@@ -57,6 +58,7 @@ pub struct ClientRequest {
     cookies: Option<CookieJar>,
     response_decompress: bool,
     timeout: Option<Duration>,
+    signer: Option<Signer>,
     config: Rc<ClientConfig>,
 }
 
@@ -74,6 +76,7 @@ impl ClientRequest {
             addr: None,
             cookies: None,
             timeout: None,
+            signer: None,
             response_decompress: true,
         }
         .method(method)
@@ -351,6 +354,15 @@ impl ClientRequest {
         self
     }
 
+    /// Sign request with HTTP message signature.
+    ///
+    /// Signature is computed right before sending, `Signature-Input` and
+    /// `Signature` headers are added to the request.
+    pub fn sign(mut self, signer: &Signer) -> Self {
+        self.signer = Some(signer.clone());
+        self
+    }
+
     /// This method calls provided closure with builder reference if
     /// value is `true`.
     pub fn if_true<F>(self, value: bool, f: F) -> Self
@@ -560,6 +572,10 @@ impl ClientRequest {
             };
         }
 
+        if let Some(signer) = slf.signer.take() {
+            signer.sign(&mut slf.head)?;
+        }
+
         Ok(slf)
     }
 }
@@ -0,0 +1,170 @@
+//! Typed routes
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Characters of a path segment that are percent-encoded.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Characters of a tail parameter that are percent-encoded, slashes are kept.
+const TAIL: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Named route of a server, shared by the server and its clients.
+///
+/// Route describes path pattern and parameters of a server resource. Server
+/// registers the resource with `web::resource_for::<R>()` and extracts
+/// parameters with `web::Path<R::Params>`, clients build requests of the
+/// route with [`Client::get_route()`](struct.Client.html#method.get_route),
+/// so both sides agree on urls of the route.
+///
+/// Parameters are a struct with fields named after the parameters of the
+/// pattern, a tuple of parameters in order of the pattern or a single value.
+///
+/// ```rust
+/// use awc::TypedRoute;
+///
+/// struct GetUser;
+///
+/// impl TypedRoute for GetUser {
+///     const NAME: &'static str = "get_user";
+///     const PATH: &'static str = "/orgs/{org}/users/{id}";
+///     type Params = (String, u32);
+/// }
+///
+/// let params = ("rust lang".to_owned(), 42);
+/// assert_eq!(GetUser::path(&params), "/orgs/rust%20lang/users/42");
+/// ```
+pub trait TypedRoute {
+    /// Name of the route, resource of the server is registered with the name.
+    const NAME: &'static str;
+
+    /// Path pattern of the route, i.e. `/users/{id}`.
+    const PATH: &'static str;
+
+    /// Parameters of the path pattern.
+    type Params: Serialize;
+
+    /// Build path of the route, parameter values are percent-encoded.
+    ///
+    /// Panics if `params` do not provide a value of every parameter of the
+    /// pattern.
+    fn path(params: &Self::Params) -> String {
+        let params =
+            serde_json::to_value(params).expect("Can not serialize route params");
+        format_path(Self::PATH, &params)
+    }
+}
+
+/// Substitute parameters of the pattern with values of `params`.
+fn format_path(pattern: &str, params: &Value) -> String {
+    let mut path = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    let mut idx = 0;
+
+    while let Some(start) = rest.find('{') {
+        path.push_str(&rest[..start]);
+
+        // regex of the parameter may contain braces
+        let mut depth = 0;
+        let mut end = rest.len();
+        for (pos, ch) in rest[start..].char_indices() {
+            match ch {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = start + pos;
+                        break;
+                    }
+                }
+                _ => (),
+            }
+        }
+        assert!(end < rest.len(), "Unclosed parameter of route path");
+
+        let name = rest[start + 1..end].split(':').next().unwrap().trim();
+        rest = &rest[end + 1..];
+        let tail = rest.starts_with('*');
+        if tail {
+            rest = &rest[1..];
+        }
+
+        let value = match params {
+            Value::Object(ref map) => map.get(name),
+            Value::Array(ref items) => items.get(idx),
+            value if idx == 0 => Some(value),
+            _ => None,
+        };
+        let value = match value {
+            Some(Value::String(ref s)) => s.clone(),
+            Some(Value::Number(ref n)) => n.to_string(),
+            Some(Value::Bool(ref b)) => b.to_string(),
+            _ => panic!("Route parameter `{}` is missing", name),
+        };
+        let set = if tail { TAIL } else { SEGMENT };
+        path.extend(utf8_percent_encode(&value, set));
+        idx += 1;
+    }
+    path.push_str(rest);
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Files;
+
+    impl TypedRoute for Files {
+        const NAME: &'static str = "files";
+        const PATH: &'static str = "/files/{bucket:[a-z]{2,8}}/{path}*";
+        type Params = (&'static str, &'static str);
+    }
+
+    struct Item;
+
+    impl TypedRoute for Item {
+        const NAME: &'static str = "item";
+        const PATH: &'static str = "/items/{id}/";
+        type Params = u64;
+    }
+
+    #[test]
+    fn test_path() {
+        assert_eq!(
+            Files::path(&("docs", "a b/c?.txt")),
+            "/files/docs/a%20b/c%3F.txt"
+        );
+        assert_eq!(Item::path(&7), "/items/7/");
+
+        let params = serde_json::json!({ "id": "a/b" });
+        assert_eq!(format_path("/items/{id}", &params), "/items/a%2Fb");
+    }
+
+    #[test]
+    #[should_panic(expected = "Route parameter `path` is missing")]
+    fn test_missing_param() {
+        format_path(Files::PATH, &serde_json::json!(["docs"]));
+    }
+}
@@ -12,6 +12,7 @@ use serde::Serialize;
 
 use actix_http::body::{Body, BodyStream};
 use actix_http::http::header::{self, IntoHeaderValue};
+use actix_http::http::signature::SignatureError;
 use actix_http::http::{Error as HttpError, HeaderMap, HeaderName};
 use actix_http::{Error, RequestHead};
 
@@ -30,6 +31,7 @@ use crate::ClientConfig;
 pub(crate) enum PrepForSendingError {
     Url(InvalidUrl),
     Http(HttpError),
+    Signature(SignatureError),
 }
 
 impl Into<FreezeRequestError> for PrepForSendingError {
@@ -37,6 +39,7 @@ impl Into<FreezeRequestError> for PrepForSendingError {
         match self {
             PrepForSendingError::Url(e) => FreezeRequestError::Url(e),
             PrepForSendingError::Http(e) => FreezeRequestError::Http(e),
+            PrepForSendingError::Signature(e) => FreezeRequestError::Signature(e),
         }
     }
 }
@@ -46,6 +49,7 @@ impl Into<SendRequestError> for PrepForSendingError {
         match self {
             PrepForSendingError::Url(e) => SendRequestError::Url(e),
             PrepForSendingError::Http(e) => SendRequestError::Http(e),
+            PrepForSendingError::Signature(e) => SendRequestError::Signature(e),
         }
     }
 }
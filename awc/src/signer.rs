@@ -0,0 +1,133 @@
+//! Request signing
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_http::http::signature::{self, Component, SignatureError, SignatureParams};
+use actix_http::RequestHead;
+
+/// HTTP message signature signer.
+///
+/// Requests are signed right before sending, after cookies and default
+/// headers are set. Signature covers `@method` and `@target-uri` components
+/// and `created` time by default, other components are added with
+/// [`component()`](#method.component). Signing algorithm is supplied as
+/// a callback, it receives signature base and returns signature bytes.
+///
+/// Frozen requests are signed once, on `freeze()`.
+///
+/// ```rust
+/// use awc::http::signature::Component;
+/// use awc::http::HeaderName;
+///
+/// # fn sign_with_my_key(_: &[u8]) -> Vec<u8> { Vec::new() }
+/// #[actix_rt::main]
+/// async fn main() {
+///     let signer = awc::Signer::new("my-key", |base| sign_with_my_key(base))
+///         .alg("ed25519")
+///         .component(Component::Header(HeaderName::from_static("content-digest")));
+///
+///     let digest = "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:";
+///     let res = awc::Client::new()
+///         .post("http://www.rust-lang.org")
+///         .header("content-digest", digest)
+///         .sign(&signer)
+///         .send_body("{\"hello\": \"world\"}")
+///         .await;
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Signer {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    label: String,
+    keyid: String,
+    alg: Option<String>,
+    tag: Option<String>,
+    components: Vec<Component>,
+    expires_in: Option<Duration>,
+    sign: Box<dyn Fn(&[u8]) -> Vec<u8>>,
+}
+
+impl Signer {
+    /// Create signer for the key id and signing callback.
+    pub fn new<K, F>(keyid: K, sign: F) -> Self
+    where
+        K: Into<String>,
+        F: Fn(&[u8]) -> Vec<u8> + 'static,
+    {
+        Signer {
+            inner: Rc::new(Inner {
+                label: "sig1".to_owned(),
+                keyid: keyid.into(),
+                alg: None,
+                tag: None,
+                components: vec![Component::Method, Component::TargetUri],
+                expires_in: None,
+                sign: Box::new(sign),
+            }),
+        }
+    }
+
+    /// Set signature label, default label is `sig1`.
+    pub fn label<T: Into<String>>(mut self, label: T) -> Self {
+        self.inner_mut().label = label.into();
+        self
+    }
+
+    /// Set `alg` parameter of the signature.
+    pub fn alg<T: Into<String>>(mut self, alg: T) -> Self {
+        self.inner_mut().alg = Some(alg.into());
+        self
+    }
+
+    /// Set `tag` parameter of the signature.
+    pub fn tag<T: Into<String>>(mut self, tag: T) -> Self {
+        self.inner_mut().tag = Some(tag.into());
+        self
+    }
+
+    /// Add component covered by the signature.
+    pub fn component(mut self, component: Component) -> Self {
+        let inner = self.inner_mut();
+        if !inner.components.contains(&component) {
+            inner.components.push(component);
+        }
+        self
+    }
+
+    /// Set `expires` parameter of the signature relative to signing time.
+    pub fn expires_in(mut self, expires_in: Duration) -> Self {
+        self.inner_mut().expires_in = Some(expires_in);
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    pub(crate) fn sign(&self, head: &mut RequestHead) -> Result<(), SignatureError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let inner = &self.inner;
+        let mut params = SignatureParams::new(inner.components.clone());
+        params.created = Some(now);
+        params.expires = inner.expires_in.map(|exp| now + exp.as_secs());
+        params.keyid = Some(inner.keyid.clone());
+        params.alg = inner.alg.clone();
+        params.tag = inner.tag.clone();
+
+        signature::sign(
+            &inner.label,
+            &params,
+            &head.method,
+            &head.uri,
+            &mut head.headers,
+            |base| (inner.sign)(base),
+        )
+    }
+}
@@ -21,7 +21,7 @@ use actix_web::middleware::Compress;
 use actix_web::{
     http::header, test, web, App, Error, HttpMessage, HttpRequest, HttpResponse,
 };
-use awc::error::SendRequestError;
+use awc::error::{ConnectError, SendRequestError};
 
 const STR: &str = "Hello World Hello World Hello World Hello World Hello World \
                    Hello World Hello World Hello World Hello World Hello World \
@@ -132,6 +132,126 @@ async fn test_timeout() {
     }
 }
 
+#[actix_rt::test]
+async fn test_hedged_request() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter2 = counter.clone();
+    let srv = test::start(move || {
+        let counter = counter2.clone();
+        App::new().service(web::resource("/").to(move || {
+            // first request is slow, hedged one is not
+            let slow = counter.fetch_add(1, Ordering::SeqCst) == 0;
+            async move {
+                if slow {
+                    actix_rt::time::delay_for(Duration::from_secs(5)).await;
+                }
+                Ok::<_, Error>(HttpResponse::Ok().body(if slow {
+                    "slow"
+                } else {
+                    "fast"
+                }))
+            }
+        }))
+    });
+
+    let hedge = awc::Hedge::new(Duration::from_millis(50));
+    let req = srv.get("/").freeze().unwrap();
+    let mut res = req.send_hedged(&hedge).await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"fast"));
+    assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+    // unsafe methods are not hedged
+    counter.store(0, Ordering::SeqCst);
+    let client = awc::Client::build()
+        .timeout(Duration::from_millis(200))
+        .finish();
+    let req = client.post(srv.url("/")).freeze().unwrap();
+    match req.send_hedged(&hedge).await {
+        Err(SendRequestError::Timeout) => (),
+        _ => panic!(),
+    }
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+}
+
+#[actix_rt::test]
+async fn test_connect_policy() {
+    let srv = test::start(|| {
+        App::new().service(web::resource("/").route(web::to(|| HttpResponse::Ok())))
+    });
+
+    let client = awc::Client::build()
+        .connector(
+            awc::Connector::new()
+                .policy(awc::ConnectPolicy::new())
+                .finish(),
+        )
+        .finish();
+    match client.get(srv.url("/")).send().await {
+        Err(SendRequestError::Connect(ConnectError::Denied(addr))) => {
+            assert_eq!(addr, srv.addr())
+        }
+        _ => panic!(),
+    }
+
+    let port = srv.addr().port();
+    let client = awc::Client::build()
+        .connector(
+            awc::Connector::new()
+                .policy(awc::ConnectPolicy::new().allow(move |_, addr| {
+                    addr.ip().is_loopback() && addr.port() == port
+                }))
+                .finish(),
+        )
+        .finish();
+    let response = client.get(srv.url("/")).send().await.unwrap();
+    assert!(response.status().is_success());
+}
+
+#[actix_rt::test]
+async fn test_signature() {
+    use actix_web::http::signature::{Component, SignatureParams};
+    use actix_web::middleware::VerifySignature;
+
+    let srv = test::start(|| {
+        App::new()
+            .wrap(
+                VerifySignature::new(|params: &SignatureParams| {
+                    if params.keyid.as_deref() == Some("key") {
+                        Some(|base: &[u8], sig: &[u8]| sig == [b"key:", base].concat())
+                    } else {
+                        None
+                    }
+                })
+                .require(Component::Method)
+                .require(Component::TargetUri)
+                .require(Component::Header(header::CONTENT_TYPE)),
+            )
+            .service(web::resource("/").route(web::to(|| HttpResponse::Ok())))
+    });
+
+    let signer = awc::Signer::new("key", |base| [b"key:", base].concat())
+        .component(Component::Header(header::CONTENT_TYPE))
+        .expires_in(Duration::from_secs(60));
+    let response = srv
+        .post("/?page=1")
+        .content_type("application/json")
+        .sign(&signer)
+        .send_body("{}")
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let response = srv.post("/").send_body("{}").await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    // covered header is missing
+    match srv.post("/").sign(&signer).send().await {
+        Err(SendRequestError::Signature(_)) => (),
+        _ => panic!(),
+    }
+}
+
 #[actix_rt::test]
 async fn test_timeout_override() {
     let srv = test::start(|| {
@@ -5,7 +5,7 @@ use std::sync::Arc;
 use actix_http::HttpService;
 use actix_http_test::test_server;
 use actix_service::{map_config, pipeline_factory, ServiceFactory};
-use actix_web::http::Version;
+use actix_web::http::{header, StatusCode, Version};
 use actix_web::{dev::AppConfig, web, App, HttpResponse};
 use futures::future::ok;
 use open_ssl::ssl::{SslAcceptor, SslConnector, SslFiletype, SslMethod, SslVerifyMode};
@@ -81,3 +81,35 @@ async fn test_connection_reuse_h2() {
     // one connection
     assert_eq!(num.load(Ordering::Relaxed), 1);
 }
+
+#[actix_rt::test]
+async fn test_rate_limit_h2() {
+    let srv = test_server(move || {
+        HttpService::build()
+            .max_requests_per_second(2)
+            .close_on_rate_limit(true)
+            .h2(map_config(
+                App::new()
+                    .service(web::resource("/").route(web::to(|| HttpResponse::Ok()))),
+                |_| AppConfig::default(),
+            ))
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    })
+    .await;
+
+    let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+    builder.set_verify(SslVerifyMode::NONE);
+    let _ = builder.set_alpn_protos(b"\x02h2");
+    let client = awc::Client::build()
+        .connector(awc::Connector::new().ssl(builder.build()).finish())
+        .finish();
+
+    for _ in 0..2 {
+        let response = client.get(srv.surl("/")).send().await.unwrap();
+        assert!(response.status().is_success());
+    }
+    let response = client.get(srv.surl("/")).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "1");
+}
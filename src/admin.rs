@@ -0,0 +1,608 @@
+//! Admin control plane endpoints
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::{fmt, fmt::Write};
+
+use actix_http::error::{
+    Error, ErrorBadRequest, ErrorForbidden, ErrorNotFound, ErrorServiceUnavailable,
+};
+use actix_http::http::header::{HeaderValue, AUTHORIZATION, RETRY_AFTER};
+use actix_http::Load;
+use actix_service::{Service, ServiceFactory, Transform};
+use futures::future::{ok, Either, Ready};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::data::Data;
+use crate::request::HttpRequest;
+use crate::scope::Scope;
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::types::Json;
+use crate::{web, HttpResponse};
+
+/// Authorizer of admin requests.
+///
+/// Authorizer is implemented for closures, i.e.
+/// `|req: &HttpRequest| req.peer_addr().map(|a| a.ip().is_loopback()) == Some(true)`.
+pub trait Authorizer: Send + Sync {
+    /// Check that request is allowed to use admin endpoints.
+    fn authorize(&self, req: &HttpRequest) -> bool;
+}
+
+impl<F> Authorizer for F
+where
+    F: Fn(&HttpRequest) -> bool + Send + Sync,
+{
+    fn authorize(&self, req: &HttpRequest) -> bool {
+        (self)(req)
+    }
+}
+
+/// Authorizer of requests with `Authorization: Bearer <token>` header.
+pub struct BearerToken(String);
+
+impl BearerToken {
+    /// Create authorizer accepting the token.
+    pub fn new(token: &str) -> Self {
+        BearerToken(token.to_owned())
+    }
+}
+
+impl Authorizer for BearerToken {
+    fn authorize(&self, req: &HttpRequest) -> bool {
+        let token = match req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|val| val.to_str().ok())
+        {
+            Some(val) if val.starts_with("Bearer ") => val[7..].trim().as_bytes(),
+            _ => return false,
+        };
+
+        // constant time comparison
+        let expected = self.0.as_bytes();
+        token.len() == expected.len()
+            && token
+                .iter()
+                .zip(expected)
+                .fold(0, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+}
+
+impl fmt::Debug for BearerToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BearerToken")
+    }
+}
+
+/// Endpoints of the admin scope.
+const ADMIN_ROUTES: &[&str] = &[
+    "/routes",
+    "/metrics",
+    "/config",
+    "/log-level",
+    "/maintenance",
+];
+
+/// Runtime controls of the server, exposed by
+/// [`web::admin()`](fn.admin.html) scope.
+///
+/// `Admin` is shared by all workers, so the same instance could be used by
+/// the application and by the admin scope mounted on a separate internal
+/// listener. Scope provides following endpoints, every request is checked
+/// by the [`Authorizer`](trait.Authorizer.html) and rejected with
+/// `403 Forbidden` response if it is not allowed:
+///
+/// - `GET /routes` - json list of route patterns of the application the
+///   scope is registered with, or of the [`route_list`](#method.route_list)
+/// - `GET /metrics` - metrics in text format, load signals of the worker by
+///   default
+/// - `GET /config` - json configuration dump, values of sensitive keys are
+///   redacted
+/// - `GET /log-level`, `PUT /log-level` - get or set maximum log level, i.e.
+///   `debug`
+/// - `GET /maintenance`, `PUT /maintenance` - get or set maintenance mode,
+///   json `true` or `false`
+///
+/// ```rust
+/// use actix_web::web::{self, Admin, BearerToken};
+/// use actix_web::{App, HttpResponse, HttpServer};
+///
+/// # fn main() {
+/// let admin = Admin::new(BearerToken::new("secret"))
+///     .config(&serde_json::json!({"db": {"url": "pg://", "password": "pass"}}));
+///
+/// let public = admin.clone();
+/// let server = HttpServer::new(move || {
+///     App::new()
+///         .wrap(public.maintenance())
+///         .route("/", web::get().to(|| HttpResponse::Ok()))
+/// });
+/// let internal = HttpServer::new(move || App::new().service(web::admin("/admin", &admin)));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Admin {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    authorizer: Box<dyn Authorizer>,
+    maintenance: AtomicBool,
+    metrics: Option<Box<dyn Fn() -> String + Send + Sync>>,
+    config: Option<Value>,
+    redact: Vec<String>,
+    routes: Option<Vec<String>>,
+    scopes: RwLock<Vec<String>>,
+}
+
+impl Admin {
+    /// Create admin controls protected by the authorizer.
+    pub fn new<A: Authorizer + 'static>(authorizer: A) -> Self {
+        Admin {
+            inner: Arc::new(Inner {
+                authorizer: Box::new(authorizer),
+                maintenance: AtomicBool::new(false),
+                metrics: None,
+                config: None,
+                redact: ["password", "secret", "token", "key"]
+                    .iter()
+                    .map(|s| (*s).to_owned())
+                    .collect(),
+                routes: None,
+                scopes: RwLock::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Set metrics renderer of `GET /metrics` endpoint.
+    pub fn metrics<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        Arc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .metrics = Some(Box::new(f));
+        self
+    }
+
+    /// Set configuration of `GET /config` endpoint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if configuration can not be serialized to json.
+    pub fn config<T: Serialize>(mut self, config: &T) -> Self {
+        let config = serde_json::to_value(config).expect("Can not serialize config");
+        Arc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .config = Some(config);
+        self
+    }
+
+    /// Redact values of configuration keys containing `word`, matched
+    /// case-insensitively.
+    ///
+    /// By default `password`, `secret`, `token` and `key` are redacted.
+    pub fn redact(mut self, word: &str) -> Self {
+        Arc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .redact
+            .push(word.to_ascii_lowercase());
+        self
+    }
+
+    /// Set route patterns of `GET /routes` endpoint, i.e. routes of the
+    /// public application when admin scope is mounted on a separate
+    /// listener.
+    pub fn route_list<I, S>(mut self, routes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Arc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .routes = Some(routes.into_iter().map(|s| s.into()).collect());
+        self
+    }
+
+    /// Maintenance mode is enabled.
+    pub fn is_maintenance(&self) -> bool {
+        self.inner.maintenance.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable maintenance mode.
+    pub fn set_maintenance(&self, enabled: bool) {
+        self.inner.maintenance.store(enabled, Ordering::Relaxed);
+        log::info!(
+            "Maintenance mode is {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+    }
+
+    /// `Middleware` rejecting requests with `503 Service Unavailable`
+    /// response while maintenance mode is enabled.
+    ///
+    /// Requests to admin scopes of the same application are not rejected,
+    /// so maintenance mode could be disabled.
+    pub fn maintenance(&self) -> Maintenance {
+        Maintenance(self.clone())
+    }
+
+    /// Create admin scope for the path.
+    pub(crate) fn scope(
+        &self,
+        path: &str,
+    ) -> Scope<
+        impl ServiceFactory<
+            Config = (),
+            Request = ServiceRequest,
+            Response = ServiceResponse,
+            Error = Error,
+            InitError = (),
+        >,
+    > {
+        let local = format!("/{}", path.trim_matches('/'));
+        let mut scopes = self.inner.scopes.write().unwrap();
+        if !scopes.iter().any(|p| *p == local) {
+            scopes.push(local);
+        }
+        drop(scopes);
+
+        let admin = self.clone();
+        Scope::new(path)
+            .data(self.clone())
+            .wrap_fn(move |req, srv| {
+                if admin.inner.authorizer.authorize(req.request()) {
+                    Either::Left(srv.call(req))
+                } else {
+                    log::debug!("Admin request to {:?} is not allowed", req.path());
+                    Either::Right(ok(
+                        req.error_response(ErrorForbidden("Admin access is denied"))
+                    ))
+                }
+            })
+            .route("/routes", web::get().to(routes))
+            .route("/metrics", web::get().to(metrics))
+            .route("/config", web::get().to(config))
+            .route("/log-level", web::get().to(log_level))
+            .route("/log-level", web::put().to(set_log_level))
+            .route("/maintenance", web::get().to(maintenance))
+            .route("/maintenance", web::put().to(set_maintenance))
+    }
+
+    /// Check that request path is routed to an admin endpoint.
+    ///
+    /// Scope paths are recorded relative to the parent scope, so they are
+    /// compared with the pattern of the innermost scope of the matched
+    /// resource.
+    fn is_admin_path(&self, req: &ServiceRequest) -> bool {
+        let patterns = match req.resource_map().match_patterns(req.path()) {
+            Some(patterns) => patterns,
+            None => return false,
+        };
+        let n = patterns.len();
+        if n < 2 || !ADMIN_ROUTES.contains(&patterns[n - 1].as_str()) {
+            return false;
+        }
+        let scope = patterns[n - 2].trim_matches('/');
+        self.inner
+            .scopes
+            .read()
+            .unwrap()
+            .iter()
+            .any(|local| local.trim_start_matches('/') == scope)
+    }
+
+    fn redacted(&self, value: &Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, val)| {
+                        let key_lower = key.to_ascii_lowercase();
+                        let val = if self
+                            .inner
+                            .redact
+                            .iter()
+                            .any(|word| key_lower.contains(word.as_str()))
+                        {
+                            Value::String("[redacted]".to_owned())
+                        } else {
+                            self.redacted(val)
+                        };
+                        (key.clone(), val)
+                    })
+                    .collect(),
+            ),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|val| self.redacted(val)).collect())
+            }
+            val => val.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for Admin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Admin")
+            .field("maintenance", &self.is_maintenance())
+            .finish()
+    }
+}
+
+async fn routes(admin: Data<Admin>, req: HttpRequest) -> HttpResponse {
+    match admin.inner.routes {
+        Some(ref routes) => HttpResponse::Ok().json(routes),
+        None => HttpResponse::Ok().json(req.resource_map().patterns()),
+    }
+}
+
+async fn metrics(admin: Data<Admin>) -> HttpResponse {
+    let body = match admin.inner.metrics {
+        Some(ref metrics) => metrics(),
+        None => {
+            let load = Load::current();
+            let mut body = String::new();
+            let _ = writeln!(body, "actix_requests_in_flight {}", load.in_flight);
+            let _ = writeln!(body, "actix_requests_queued {}", load.queued);
+            let _ = writeln!(body, "actix_connections {}", load.connections);
+            let _ = writeln!(
+                body,
+                "actix_event_loop_lag_seconds {}",
+                load.loop_lag.as_secs_f64()
+            );
+            let _ = writeln!(body, "actix_blocking_queued {}", load.blocking_queued);
+            let _ = writeln!(body, "actix_blocking_active {}", load.blocking_active);
+            body
+        }
+    };
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(body)
+}
+
+async fn config(admin: Data<Admin>) -> Result<HttpResponse, Error> {
+    match admin.inner.config {
+        Some(ref config) => Ok(HttpResponse::Ok().json(admin.redacted(config))),
+        None => Err(ErrorNotFound("Config is not set")),
+    }
+}
+
+async fn log_level() -> HttpResponse {
+    HttpResponse::Ok().body(log::max_level().to_string().to_ascii_lowercase())
+}
+
+async fn set_log_level(body: String) -> Result<HttpResponse, Error> {
+    let level = log::LevelFilter::from_str(body.trim())
+        .map_err(|_| ErrorBadRequest("Invalid log level"))?;
+    log::set_max_level(level);
+    log::info!("Maximum log level is set to {}", level);
+    Ok(log_level().await)
+}
+
+async fn maintenance(admin: Data<Admin>) -> HttpResponse {
+    HttpResponse::Ok().json(admin.is_maintenance())
+}
+
+async fn set_maintenance(admin: Data<Admin>, enabled: Json<bool>) -> HttpResponse {
+    admin.set_maintenance(enabled.into_inner());
+    HttpResponse::Ok().json(admin.is_maintenance())
+}
+
+/// `Middleware` rejecting requests while maintenance mode is enabled, see
+/// [`Admin::maintenance()`](struct.Admin.html#method.maintenance).
+#[derive(Clone)]
+pub struct Maintenance(Admin);
+
+impl<S, B> Transform<S> for Maintenance
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MaintenanceMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(MaintenanceMiddleware {
+            service,
+            admin: self.0.clone(),
+        })
+    }
+}
+
+pub struct MaintenanceMiddleware<S> {
+    service: S,
+    admin: Admin,
+}
+
+impl<S, B> Service for MaintenanceMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if self.admin.is_maintenance() && !self.admin.is_admin_path(&req) {
+            let mut res =
+                req.error_response(ErrorServiceUnavailable("Service is in maintenance"));
+            res.headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from_static("60"));
+            return Either::Right(ok(res));
+        }
+        Either::Left(self.service.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::App;
+
+    #[actix_rt::test]
+    async fn test_admin_authorizer() {
+        let admin = Admin::new(BearerToken::new("secret"));
+        let mut srv = init_service(
+            App::new()
+                .service(web::admin("/admin", &admin))
+                .route("/users/{id}", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/admin/routes").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = TestRequest::with_uri("/admin/routes")
+            .header(AUTHORIZATION, "Bearer other")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = TestRequest::with_uri("/admin/routes")
+            .header(AUTHORIZATION, "Bearer secret")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let routes: Vec<String> =
+            serde_json::from_slice(&read_body(resp).await).unwrap();
+        assert!(routes.contains(&"/admin/routes".to_owned()));
+        assert!(routes.contains(&"/users/{id}".to_owned()));
+    }
+
+    #[actix_rt::test]
+    async fn test_admin_controls() {
+        let admin = Admin::new(|_: &HttpRequest| true)
+            .config(&serde_json::json!({
+                "bind": ["127.0.0.1:8080"],
+                "db": {"url": "pg://localhost", "Password": "pass"},
+                "api_token": "token",
+            }))
+            .metrics(|| "requests 10\n".to_owned());
+        let public = admin.clone();
+        let mut srv = init_service(
+            App::new()
+                .wrap(public.maintenance())
+                .service(web::admin("/admin", &admin))
+                .route("/", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/admin/config").to_request();
+        let resp = call_service(&mut srv, req).await;
+        let config: Value = serde_json::from_slice(&read_body(resp).await).unwrap();
+        assert_eq!(
+            config,
+            serde_json::json!({
+                "bind": ["127.0.0.1:8080"],
+                "db": {"url": "pg://localhost", "Password": "[redacted]"},
+                "api_token": "[redacted]",
+            })
+        );
+
+        let req = TestRequest::with_uri("/admin/metrics").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"requests 10\n"));
+
+        // maintenance mode
+        let req = TestRequest::put()
+            .uri("/admin/maintenance")
+            .set_json(&true)
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"true"));
+        assert!(admin.is_maintenance());
+
+        let req = TestRequest::default().to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers().get(RETRY_AFTER).unwrap(), "60");
+
+        let req = TestRequest::put()
+            .uri("/admin/maintenance")
+            .set_json(&false)
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"false"));
+
+        let req = TestRequest::default().to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_maintenance_admin_path() {
+        let admin = Admin::new(|_: &HttpRequest| true);
+        let public = admin.clone();
+        let mut srv = init_service(
+            App::new()
+                .wrap(public.maintenance())
+                .service(web::scope("/internal").service(web::admin("/admin", &admin)))
+                .route(
+                    "/administrator/config",
+                    web::get().to(|| HttpResponse::Ok()),
+                )
+                .route("/admin/config", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+        admin.set_maintenance(true);
+
+        let req = TestRequest::with_uri("/internal/admin/maintenance").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        for path in &["/administrator/config", "/admin/config", "/internal/admin"] {
+            let req = TestRequest::with_uri(path).to_request();
+            let resp = call_service(&mut srv, req).await;
+            assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE, "{}", path);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_admin_log_level() {
+        let admin = Admin::new(|_: &HttpRequest| true).route_list(vec!["/"]);
+        let mut srv =
+            init_service(App::new().service(web::admin("/admin", &admin))).await;
+        let level = log::max_level();
+
+        let req = TestRequest::with_uri("/admin/routes").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"[\"/\"]"));
+
+        let req = TestRequest::put()
+            .uri("/admin/log-level")
+            .set_payload("debug")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"debug"));
+        assert_eq!(log::max_level(), log::LevelFilter::Debug);
+
+        let req = TestRequest::put()
+            .uri("/admin/log-level")
+            .set_payload("loud")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/admin/config").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        log::set_max_level(level);
+    }
+}
@@ -1,28 +1,35 @@
 use std::cell::RefCell;
 use std::fmt;
+use std::fs;
 use std::future::Future;
+use std::io;
 use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use actix_http::body::{Body, MessageBody};
-use actix_http::Extensions;
+use actix_http::{Extensions, ResponseHead};
 use actix_service::boxed::{self, BoxServiceFactory};
 use actix_service::{
-    apply, apply_fn_factory, IntoServiceFactory, ServiceFactory, Transform,
+    apply, apply_fn_factory, IntoServiceFactory, Service, ServiceFactory, Transform,
 };
+use bytes::BytesMut;
 use futures::future::FutureExt;
+use futures::stream::StreamExt;
 
-use crate::app_service::{AppEntry, AppInit, AppRoutingFactory};
+use crate::app_service::{AppEntry, AppInit, AppRoutingFactory, ResponseHook};
 use crate::config::ServiceConfig;
 use crate::data::{Data, DataFactory, FnDataFactory};
 use crate::dev::ResourceDef;
 use crate::error::Error;
+use crate::middleware::ProfileSettings;
 use crate::resource::Resource;
 use crate::route::Route;
 use crate::service::{
     AppServiceFactory, HttpServiceFactory, ServiceFactoryWrapper, ServiceRequest,
     ServiceResponse,
 };
+use crate::test;
 
 type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>;
 
@@ -32,11 +39,13 @@ pub struct App<T, B> {
     endpoint: T,
     services: Vec<Box<dyn AppServiceFactory>>,
     default: Option<Rc<HttpNewService>>,
+    method_not_allowed: Option<Rc<HttpNewService>>,
     factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     data: Vec<Box<dyn DataFactory>>,
     data_factories: Vec<FnDataFactory>,
     external: Vec<ResourceDef>,
     extensions: Extensions,
+    response_hooks: Vec<ResponseHook>,
     _t: PhantomData<B>,
 }
 
@@ -50,9 +59,11 @@ impl App<AppEntry, Body> {
             data_factories: Vec::new(),
             services: Vec::new(),
             default: None,
+            method_not_allowed: None,
             factory_ref: fref,
             external: Vec::new(),
             extensions: Extensions::new(),
+            response_hooks: Vec::new(),
             _t: PhantomData,
         }
     }
@@ -182,6 +193,7 @@ where
         self.data.extend(cfg.data);
         self.services.extend(cfg.services);
         self.external.extend(cfg.external);
+        self.extensions.extend(cfg.extensions);
         self
     }
 
@@ -283,6 +295,43 @@ where
         self
     }
 
+    /// Service to be used if request matches resource path, but none of
+    /// the resource routes.
+    ///
+    /// Applies to all resources that do not have default service, unless
+    /// scope of the resource registers its own method not allowed service.
+    /// If not registered, *405* response get returned.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .service(web::resource("/index.html").route(web::get().to(|| HttpResponse::Ok())))
+    ///         .method_not_allowed_service(
+    ///             web::to(|| HttpResponse::MethodNotAllowed().body("method not allowed"))
+    ///         );
+    /// }
+    /// ```
+    pub fn method_not_allowed_service<F, U>(mut self, f: F) -> Self
+    where
+        F: IntoServiceFactory<U>,
+        U: ServiceFactory<
+                Config = (),
+                Request = ServiceRequest,
+                Response = ServiceResponse,
+                Error = Error,
+            > + 'static,
+        U::InitError: fmt::Debug,
+    {
+        self.method_not_allowed =
+            Some(Rc::new(boxed::factory(f.into_factory().map_init_err(
+                |e| log::error!("Can not construct method not allowed service: {:?}", e),
+            ))));
+
+        self
+    }
+
     /// Register an external resource.
     ///
     /// External resources are useful for URL generation purposes only
@@ -375,9 +424,11 @@ where
             data_factories: self.data_factories,
             services: self.services,
             default: self.default,
+            method_not_allowed: self.method_not_allowed,
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            response_hooks: self.response_hooks,
             _t: PhantomData,
         }
     }
@@ -437,17 +488,86 @@ where
             data_factories: self.data_factories,
             services: self.services,
             default: self.default,
+            method_not_allowed: self.method_not_allowed,
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            response_hooks: self.response_hooks,
             _t: PhantomData,
         }
     }
+
+    /// Apply deployment profile settings, i.e. error pages, CORS and security
+    /// headers.
+    ///
+    /// This is same as wrapping application with
+    /// [`ProfileSettings`](middleware/struct.ProfileSettings.html) middleware.
+    ///
+    /// ```rust
+    /// use actix_web::middleware::Profile;
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .profile(Profile::Prod.settings().hsts(None))
+    ///         .route("/index.html", web::get().to(|| HttpResponse::Ok()));
+    /// }
+    /// ```
+    pub fn profile<P>(
+        self,
+        profile: P,
+    ) -> App<
+        impl ServiceFactory<
+            Config = (),
+            Request = ServiceRequest,
+            Response = ServiceResponse<B>,
+            Error = Error,
+            InitError = (),
+        >,
+        B,
+    >
+    where
+        P: Into<ProfileSettings>,
+        <T::Service as Service>::Future: 'static,
+        B: MessageBody + 'static,
+    {
+        self.wrap(profile.into())
+    }
+
+    /// Register a response hook.
+    ///
+    /// Response hooks are called with the head of every response produced
+    /// by the application, after all middleware, just before response is
+    /// passed to the server for encoding. Hooks are called in the order of
+    /// registration.
+    ///
+    /// Hooks are plain functions and do not wrap the application service, so
+    /// they are cheaper than middleware for last-mile concerns, like header
+    /// scrubbing. Errors returned by middleware are not passed to hooks.
+    ///
+    /// ```rust
+    /// use actix_web::{http::header, web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .response_hook(|head| {
+    ///             head.headers_mut().remove(header::SERVER);
+    ///         })
+    ///         .route("/index.html", web::get().to(|| HttpResponse::Ok()));
+    /// }
+    /// ```
+    pub fn response_hook<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&mut ResponseHead) + 'static,
+    {
+        self.response_hooks.push(Rc::new(f));
+        self
+    }
 }
 
 impl<T, B> IntoServiceFactory<AppInit<T, B>> for App<T, B>
 where
-    B: MessageBody,
+    B: MessageBody + 'static,
     T: ServiceFactory<
         Config = (),
         Request = ServiceRequest,
@@ -455,6 +575,8 @@ where
         Error = Error,
         InitError = (),
     >,
+    T::Service: 'static,
+    <T::Service as Service>::Future: 'static,
 {
     fn into_factory(self) -> AppInit<T, B> {
         AppInit {
@@ -464,12 +586,136 @@ where
             services: Rc::new(RefCell::new(self.services)),
             external: RefCell::new(self.external),
             default: self.default,
+            method_not_allowed: self.method_not_allowed,
             factory_ref: self.factory_ref,
             extensions: RefCell::new(Some(self.extensions)),
+            response_hooks: Rc::new(self.response_hooks),
+        }
+    }
+}
+
+impl<T, B> App<T, B>
+where
+    B: MessageBody + Unpin + 'static,
+    T: ServiceFactory<
+        Config = (),
+        Request = ServiceRequest,
+        Response = ServiceResponse<B>,
+        Error = Error,
+        InitError = (),
+    >,
+    T::Service: 'static,
+    <T::Service as Service>::Future: 'static,
+{
+    /// Render `GET` routes of the application to files in `out_dir`.
+    ///
+    /// Application is driven in-process, the same way as by the
+    /// [`test`](test/index.html) utilities, so pages can be pre-rendered
+    /// without starting http server. Routes are mapped to files as follows:
+    ///
+    /// - `/` and routes ending with `/` are written to `index.html` of the
+    ///   corresponding directory
+    /// - routes with an extension in the last segment, i.e. `/app.css`, are
+    ///   written as is
+    /// - other routes, i.e. `/about`, are written to `about/index.html`
+    ///
+    /// Returns list of written files. Export fails if any of the routes does
+    /// not respond with a successful status code.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// #[actix_rt::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     let out_dir = std::env::temp_dir().join("actix-export-doc");
+    ///
+    ///     App::new()
+    ///         .route("/", web::get().to(|| HttpResponse::Ok().body("home")))
+    ///         .route("/about", web::get().to(|| HttpResponse::Ok().body("about")))
+    ///         .export_static(&["/", "/about"], &out_dir)
+    ///         .await?;
+    ///
+    ///     assert!(out_dir.join("about/index.html").exists());
+    ///     # std::fs::remove_dir_all(out_dir)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn export_static<P: AsRef<Path>>(
+        self,
+        routes: &[&str],
+        out_dir: P,
+    ) -> io::Result<Vec<PathBuf>> {
+        let out_dir = out_dir.as_ref();
+        let mut srv = test::try_init_service(self).await.map_err(|_| {
+            io::Error::new(io::ErrorKind::Other, "Can not initialize application")
+        })?;
+
+        let mut files = Vec::with_capacity(routes.len());
+        for route in routes {
+            let path = export_path(out_dir, route)?;
+
+            let req = test::TestRequest::get().uri(route).to_request();
+            let res = srv.call(req).await;
+            let status = match res {
+                Ok(ref res) => res.status(),
+                Err(ref e) => e.as_response_error().status_code(),
+            };
+            let mut res = match res {
+                Ok(res) if status.is_success() => res,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("Route {} responded with {}", route, status),
+                    ))
+                }
+            };
+
+            let mut body = res.take_body();
+            let mut bytes = BytesMut::new();
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk.map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("Route {} body error: {}", route, e),
+                    )
+                })?;
+                bytes.extend_from_slice(&chunk);
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, &bytes)?;
+            files.push(path);
         }
+        Ok(files)
     }
 }
 
+/// Map exported route to a file path in `out_dir`.
+fn export_path(out_dir: &Path, route: &str) -> io::Result<PathBuf> {
+    let route = route.split(&['?', '#'][..]).next().unwrap_or("");
+    let mut path = out_dir.to_path_buf();
+
+    let mut segments = route.split('/').filter(|s| !s.is_empty()).peekable();
+    let mut has_ext = false;
+    while let Some(segment) = segments.next() {
+        if segment == "." || segment == ".." || segment.contains('\\') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid export route: {}", route),
+            ));
+        }
+        if segments.peek().is_none() && !route.ends_with('/') {
+            has_ext = segment.contains('.');
+        }
+        path.push(segment);
+    }
+    if !has_ext {
+        path.push("index.html");
+    }
+    Ok(path)
+}
+
 #[cfg(test)]
 mod tests {
     use actix_service::Service;
@@ -668,6 +914,63 @@ mod tests {
         );
     }
 
+    #[actix_rt::test]
+    async fn test_response_hook() {
+        let mut srv = init_service(
+            App::new()
+                .response_hook(|head| {
+                    head.headers_mut().remove(header::CONTENT_TYPE);
+                })
+                .response_hook(|head| {
+                    let n = head.headers().len();
+                    head.headers_mut().insert(
+                        header::HeaderName::from_static("x-headers"),
+                        HeaderValue::from_str(&n.to_string()).unwrap(),
+                    );
+                })
+                .wrap(
+                    DefaultHeaders::new()
+                        .header(header::CONTENT_TYPE, HeaderValue::from_static("0001")),
+                )
+                .route("/test", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get(header::CONTENT_TYPE).is_none());
+        assert_eq!(resp.headers().get("x-headers").unwrap(), "0");
+
+        let req = TestRequest::with_uri("/unknown").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert!(resp.headers().get("x-headers").is_some());
+    }
+
+    #[actix_rt::test]
+    async fn test_response_hook_error() {
+        let mut srv = init_service(
+            App::new()
+                .response_hook(|head| {
+                    head.headers_mut().insert(
+                        header::HeaderName::from_static("x-hook"),
+                        HeaderValue::from_static("1"),
+                    );
+                })
+                .wrap_fn(|_, _| {
+                    err::<ServiceResponse, _>(crate::error::ErrorBadRequest("bad"))
+                })
+                .route("/test", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test").to_request();
+        let err = srv.call(req).await.err().unwrap();
+        let resp = err.as_response_error().error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(resp.headers().get("x-hook").unwrap(), "1");
+        assert_eq!(err.to_string(), "bad");
+    }
+
     #[actix_rt::test]
     async fn test_external_resource() {
         let mut srv = init_service(
@@ -690,4 +993,63 @@ mod tests {
         let body = read_body(resp).await;
         assert_eq!(body, Bytes::from_static(b"https://youtube.com/watch/12345"));
     }
+
+    #[test]
+    fn test_export_path() {
+        let out = Path::new("out");
+        assert_eq!(export_path(out, "/").unwrap(), out.join("index.html"));
+        assert_eq!(
+            export_path(out, "/about?x=1").unwrap(),
+            out.join("about").join("index.html")
+        );
+        assert_eq!(
+            export_path(out, "/blog/").unwrap(),
+            out.join("blog").join("index.html")
+        );
+        assert_eq!(
+            export_path(out, "/static/app.css").unwrap(),
+            out.join("static").join("app.css")
+        );
+        assert!(export_path(out, "/../etc/passwd").is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_export_static() {
+        let out_dir =
+            std::env::temp_dir().join(format!("actix-export-{}", rand::random::<u64>()));
+
+        let files = App::new()
+            .route("/", web::get().to(|| HttpResponse::Ok().body("home")))
+            .route("/about", web::get().to(|| HttpResponse::Ok().body("about")))
+            .export_static(&["/", "/about"], &out_dir)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(fs::read(out_dir.join("index.html")).unwrap(), b"home");
+        assert_eq!(
+            fs::read(out_dir.join("about").join("index.html")).unwrap(),
+            b"about"
+        );
+
+        let res = App::new()
+            .route("/", web::get().to(|| HttpResponse::Ok()))
+            .export_static(&["/", "/missing"], &out_dir)
+            .await;
+        assert!(res.is_err());
+
+        let res = App::new()
+            .route(
+                "/",
+                web::get().to(|| {
+                    let err = crate::error::ErrorInternalServerError("broken");
+                    let body = futures::stream::iter(vec![Err::<Bytes, _>(err)]);
+                    HttpResponse::Ok().streaming(body)
+                }),
+            )
+            .export_static(&["/"], &out_dir)
+            .await;
+        assert!(res.is_err());
+
+        fs::remove_dir_all(out_dir).unwrap();
+    }
 }
@@ -5,24 +5,28 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
-use actix_http::{Extensions, Request, Response};
+use actix_http::body::{Body, MessageBody, ResponseBody};
+use actix_http::{Extensions, Request, Response, ResponseHead};
 use actix_router::{Path, ResourceDef, ResourceInfo, Router, Url};
 use actix_service::boxed::{self, BoxService, BoxServiceFactory};
 use actix_service::{fn_service, Service, ServiceFactory};
-use futures::future::{join_all, ok, FutureExt, LocalBoxFuture};
+use futures::future::{join_all, ok, poll_fn, FutureExt, LocalBoxFuture};
 
 use crate::config::{AppConfig, AppService};
 use crate::data::{FnDataFactory, DataFactory};
-use crate::error::Error;
+use crate::error::{Error, InternalError};
 use crate::guard::Guard;
+use crate::local::{Dispatch, LocalBody, LocalClient};
 use crate::request::{HttpRequest, HttpRequestPool};
 use crate::rmap::ResourceMap;
 use crate::service::{AppServiceFactory, ServiceRequest, ServiceResponse};
+use crate::vary;
 
 type Guards = Vec<Box<dyn Guard>>;
 type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
 type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>;
 type BoxResponse = LocalBoxFuture<'static, Result<ServiceResponse, Error>>;
+pub(crate) type ResponseHook = Rc<dyn Fn(&mut ResponseHead)>;
 
 /// Service factory to convert `Request` to a `ServiceRequest<S>`.
 /// It also executes data factories.
@@ -42,8 +46,10 @@ where
     pub(crate) data_factories: Rc<Vec<FnDataFactory>>,
     pub(crate) services: Rc<RefCell<Vec<Box<dyn AppServiceFactory>>>>,
     pub(crate) default: Option<Rc<HttpNewService>>,
+    pub(crate) method_not_allowed: Option<Rc<HttpNewService>>,
     pub(crate) factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     pub(crate) external: RefCell<Vec<ResourceDef>>,
+    pub(crate) response_hooks: Rc<Vec<ResponseHook>>,
 }
 
 impl<T, B> ServiceFactory for AppInit<T, B>
@@ -55,6 +61,9 @@ where
         Error = Error,
         InitError = (),
     >,
+    T::Service: 'static,
+    <T::Service as Service>::Future: 'static,
+    B: MessageBody + 'static,
 {
     type Config = AppConfig;
     type Request = Request;
@@ -74,6 +83,7 @@ where
 
         // App config
         let mut config = AppService::new(config, default.clone(), self.data.clone());
+        config.set_method_not_allowed_service(self.method_not_allowed.clone());
 
         // register services
         std::mem::replace(&mut *self.services.borrow_mut(), Vec::new())
@@ -124,6 +134,7 @@ where
             ),
             config,
             rmap,
+            response_hooks: self.response_hooks.clone(),
             _t: PhantomData,
         }
     }
@@ -148,6 +159,7 @@ where
     config: AppConfig,
     data: Rc<Vec<Box<dyn DataFactory>>>,
     extensions: Option<Extensions>,
+    response_hooks: Rc<Vec<ResponseHook>>,
 
     _t: PhantomData<B>,
 }
@@ -161,6 +173,9 @@ where
         Error = Error,
         InitError = (),
     >,
+    T::Service: 'static,
+    <T::Service as Service>::Future: 'static,
+    B: MessageBody + 'static,
 {
     type Output = Result<AppInitService<T::Service, B>, ()>;
 
@@ -198,13 +213,22 @@ where
                 f.create(&mut data);
             }
 
-            return Poll::Ready(Ok(AppInitService {
-                service: this.endpoint.take().unwrap(),
+            // local client is available to the app, it dispatches
+            // requests to the same service
+            let client = LocalClient::default();
+            data.insert(client.clone());
+
+            let srv = AppInitService {
+                service: Rc::new(RefCell::new(this.endpoint.take().unwrap())),
                 rmap: this.rmap.clone(),
                 config: this.config.clone(),
                 data: Rc::new(data),
                 pool: HttpRequestPool::create(),
-            }));
+                response_hooks: this.response_hooks.clone(),
+            };
+            client.set(srv.dispatcher());
+
+            return Poll::Ready(Ok(srv));
         }
 
         Poll::Pending
@@ -216,11 +240,12 @@ pub struct AppInitService<T, B>
 where
     T: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
 {
-    service: T,
+    service: Rc<RefCell<T>>,
     rmap: Rc<ResourceMap>,
     config: AppConfig,
     data: Rc<Extensions>,
     pool: &'static HttpRequestPool,
+    response_hooks: Rc<Vec<ResponseHook>>,
 }
 
 impl<T, B> Service for AppInitService<T, B>
@@ -230,10 +255,10 @@ where
     type Request = Request;
     type Response = ServiceResponse<B>;
     type Error = T::Error;
-    type Future = T::Future;
+    type Future = AppInitServiceResponse<T::Future, B>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.service.poll_ready(cx)
+        self.service.borrow_mut().poll_ready(cx)
     }
 
     fn call(&mut self, req: Request) -> Self::Future {
@@ -258,10 +283,117 @@ where
                 self.pool,
             )
         };
-        self.service.call(ServiceRequest::new(req))
+        AppInitServiceResponse {
+            fut: self.service.borrow_mut().call(ServiceRequest::new(req)),
+            response_hooks: self.response_hooks.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T, B> AppInitService<T, B>
+where
+    T: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    T::Future: 'static,
+    B: MessageBody + 'static,
+{
+    /// Dispatcher of `LocalClient` requests.
+    ///
+    /// App data is held weakly, data container owns the client.
+    fn dispatcher(&self) -> Dispatch {
+        let service = self.service.clone();
+        let rmap = self.rmap.clone();
+        let config = self.config.clone();
+        let data = Rc::downgrade(&self.data);
+        let pool = self.pool;
+        let response_hooks = self.response_hooks.clone();
+
+        Box::new(move |req: Request| {
+            let data = match data.upgrade() {
+                Some(data) => data,
+                None => {
+                    return ok(Response::ServiceUnavailable().finish()).boxed_local()
+                }
+            };
+            let (head, payload) = req.into_parts();
+            let req = HttpRequest::new(
+                Path::new(Url::new(head.uri.clone())),
+                head,
+                payload,
+                rmap.clone(),
+                config.clone(),
+                data,
+                pool,
+            );
+            let service = service.clone();
+            let response_hooks = response_hooks.clone();
+
+            async move {
+                poll_fn(|cx| service.borrow_mut().poll_ready(cx)).await?;
+                let fut = service.borrow_mut().call(ServiceRequest::new(req));
+                let res = AppInitServiceResponse {
+                    fut,
+                    response_hooks,
+                    _t: PhantomData,
+                }
+                .await?;
+
+                Ok(res
+                    .map_body(|_, body| {
+                        ResponseBody::Other(Body::from_message(LocalBody(Box::pin(
+                            body,
+                        ))))
+                    })
+                    .into())
+            }
+            .boxed_local()
+        })
+    }
+}
+
+/// Future of `AppInitService` response, applies response hooks
+/// to the response head.
+#[doc(hidden)]
+#[pin_project::pin_project]
+pub struct AppInitServiceResponse<F, B> {
+    #[pin]
+    fut: F,
+    response_hooks: Rc<Vec<ResponseHook>>,
+    _t: PhantomData<B>,
+}
+
+impl<F, B> Future for AppInitServiceResponse<F, B>
+where
+    F: Future<Output = Result<ServiceResponse<B>, Error>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        match futures::ready!(this.fut.poll(cx)) {
+            Ok(mut res) => {
+                apply_hooks(this.response_hooks, res.response_mut().head_mut());
+                Poll::Ready(Ok(res))
+            }
+            Err(e) => {
+                // error response is rendered here, so hooks see it as well
+                let mut res = e.as_response_error().error_response();
+                apply_hooks(this.response_hooks, res.head_mut());
+                Poll::Ready(Err(InternalError::from_response(e, res).into()))
+            }
+        }
     }
 }
 
+fn apply_hooks(hooks: &[ResponseHook], head: &mut ResponseHead) {
+    for hook in hooks.iter() {
+        hook(head);
+    }
+    vary::merge(head);
+}
+
 impl<T, B> Drop for AppInitService<T, B>
 where
     T: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
@@ -402,16 +534,21 @@ impl Service for AppRouting {
     }
 
     fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
-        let res = self.router.recognize_mut_checked(&mut req, |req, guards| {
-            if let Some(ref guards) = guards {
-                for f in guards {
-                    if !f.check(req.head()) {
-                        return false;
+        let res = {
+            #[cfg(feature = "tracing")]
+            let _span = tracing_lib::trace_span!("route").entered();
+
+            self.router.recognize_mut_checked(&mut req, |req, guards| {
+                if let Some(ref guards) = guards {
+                    for f in guards {
+                        if !f.check(req.head()) {
+                            return false;
+                        }
                     }
                 }
-            }
-            true
-        });
+                true
+            })
+        };
 
         if let Some((srv, _info)) = res {
             srv.call(req)
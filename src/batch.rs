@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+use std::task::{Context, Poll};
+
+use actix_http::error::{Error, ErrorBadRequest};
+use actix_http::http::{header, HeaderName, HeaderValue, Method, StatusCode, Uri};
+use actix_http::Response;
+use actix_service::{Service, ServiceFactory};
+use bytes::{Bytes, BytesMut};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::local::{nested, LocalClient};
+use crate::request::HttpRequest;
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::types::json::JsonBody;
+
+/// Service that dispatches a batch of sub-requests through the application
+/// and returns aggregated responses.
+///
+/// Batch request is a json array of sub-requests, every sub-request is
+/// described by `method` (`GET` by default), `path`, optional `headers` and
+/// optional json `body`. Sub-requests inherit headers of the batch request,
+/// like cookies or authorization, and are dispatched with
+/// [`LocalClient`](struct.LocalClient.html), so they go through the same
+/// middlewares as regular requests.
+///
+/// Response is a json array in order of sub-requests, every item contains
+/// `status`, `headers` and `body` of the sub-response. Json bodies are
+/// embedded as is, other bodies are embedded as strings.
+///
+/// ```rust
+/// use actix_web::{web, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .service(web::service("/batch").finish(web::batch().concurrency(4)))
+///         .route("/users/{id}", web::get().to(|| HttpResponse::Ok().json("user")));
+/// }
+/// ```
+///
+/// Batch request:
+///
+/// ```json
+/// [
+///     {"path": "/users/1"},
+///     {"method": "POST", "path": "/users", "body": {"name": "alice"}}
+/// ]
+/// ```
+#[derive(Debug, Clone)]
+pub struct Batch {
+    concurrency: usize,
+    max_requests: usize,
+    limit: usize,
+    response_limit: usize,
+}
+
+impl Batch {
+    pub(crate) fn new() -> Self {
+        Batch {
+            concurrency: 8,
+            max_requests: 32,
+            limit: 262_144,
+            response_limit: 262_144,
+        }
+    }
+
+    /// Maximum number of sub-requests dispatched at the same time.
+    ///
+    /// By default 8 sub-requests are dispatched concurrently.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = std::cmp::max(concurrency, 1);
+        self
+    }
+
+    /// Maximum number of sub-requests in a batch.
+    ///
+    /// Larger batches are rejected with *BAD REQUEST* response. By default
+    /// batch could contain up to 32 sub-requests.
+    pub fn max_requests(mut self, max: usize) -> Self {
+        self.max_requests = max;
+        self
+    }
+
+    /// Change max size of batch payload. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Change max size of sub-response body. By default max size is 256Kb
+    ///
+    /// Sub-responses with larger bodies are reported with
+    /// *INTERNAL SERVER ERROR* status.
+    pub fn response_limit(mut self, limit: usize) -> Self {
+        self.response_limit = limit;
+        self
+    }
+}
+
+#[derive(Deserialize)]
+struct SubRequest {
+    #[serde(default)]
+    method: Option<String>,
+    path: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct SubResponse {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: Value,
+}
+
+impl ServiceFactory for Batch {
+    type Config = ();
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type InitError = ();
+    type Service = Batch;
+    type Future = Ready<Result<Batch, ()>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        ok(self.clone())
+    }
+}
+
+impl Service for Batch {
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<ServiceResponse, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let (req, mut payload) = req.into_parts();
+        let body =
+            JsonBody::<Vec<SubRequest>>::new(&req, &mut payload, None).limit(self.limit);
+        let cfg = self.clone();
+
+        async move {
+            let subs = match body.await {
+                Ok(subs) => subs,
+                Err(e) => return Ok(ServiceResponse::from_err(e, req)),
+            };
+            if subs.len() > cfg.max_requests {
+                let e = ErrorBadRequest(format!(
+                    "Batch contains more than {} requests",
+                    cfg.max_requests
+                ));
+                return Ok(ServiceResponse::from_err(e, req));
+            }
+
+            let client = LocalClient::from_app(&req);
+            let responses: Vec<SubResponse> = stream::iter(subs)
+                .map(|sub| dispatch(&req, &client, sub, cfg.response_limit))
+                .buffered(cfg.concurrency)
+                .collect()
+                .await;
+
+            let res = Response::Ok().json(responses);
+            Ok(ServiceResponse::new(req, res))
+        }
+        .boxed_local()
+    }
+}
+
+/// Dispatch sub-request, errors are reported as sub-responses.
+async fn dispatch(
+    req: &HttpRequest,
+    client: &LocalClient,
+    sub: SubRequest,
+    limit: usize,
+) -> SubResponse {
+    let res = match sub_request(req, sub) {
+        Ok(sub) => client.send(sub).await,
+        Err(e) => Err(e),
+    };
+    let mut res = res.unwrap_or_else(Response::from);
+
+    let mut headers = BTreeMap::new();
+    for (name, value) in res.headers().iter() {
+        if let Ok(value) = value.to_str() {
+            headers
+                .entry(name.as_str().to_owned())
+                .and_modify(|val: &mut String| {
+                    val.push_str(", ");
+                    val.push_str(value);
+                })
+                .or_insert_with(|| value.to_owned());
+        }
+    }
+
+    let mut body = res.take_body();
+    let mut bytes = BytesMut::new();
+    while let Some(item) = body.next().await {
+        match item {
+            Ok(chunk) => {
+                if bytes.len() + chunk.len() > limit {
+                    return SubResponse {
+                        status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                        headers: BTreeMap::new(),
+                        body: Value::String(
+                            "Sub-response body is larger than limit".to_owned(),
+                        ),
+                    };
+                }
+                bytes.extend_from_slice(&chunk)
+            }
+            Err(e) => {
+                log::debug!("Error reading batch sub-response body: {}", e);
+                break;
+            }
+        }
+    }
+
+    SubResponse {
+        status: res.status().as_u16(),
+        body: embed(&res, bytes.freeze()),
+        headers,
+    }
+}
+
+fn sub_request(
+    req: &HttpRequest,
+    sub: SubRequest,
+) -> Result<actix_http::Request, Error> {
+    let method = match sub.method {
+        Some(ref method) => Method::from_bytes(method.to_uppercase().as_bytes())
+            .map_err(|_| ErrorBadRequest(format!("Invalid method: {}", method)))?,
+        None => Method::GET,
+    };
+    if sub.path.parse::<Uri>().is_err() {
+        return Err(ErrorBadRequest(format!("Invalid path: {}", sub.path)));
+    }
+    let mut sub_req = nested(req, method, &sub.path)?;
+
+    for (name, value) in sub.headers.iter() {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| ErrorBadRequest(format!("Invalid header name: {}", name)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| ErrorBadRequest(format!("Invalid header value: {}", value)))?;
+        sub_req.headers_mut().insert(name, value);
+    }
+
+    if let Some(body) = sub.body {
+        let body = serde_json::to_vec(&body).map_err(ErrorBadRequest)?;
+        let headers = sub_req.headers_mut();
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+        if !headers.contains_key(header::CONTENT_TYPE) {
+            headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+        }
+
+        let mut payload = actix_http::h1::Payload::empty();
+        payload.unread_data(Bytes::from(body));
+        sub_req = sub_req.replace_payload(payload.into()).0;
+    }
+
+    Ok(sub_req)
+}
+
+/// Embed json bodies as values, other bodies as strings.
+fn embed(res: &Response, body: Bytes) -> Value {
+    if body.is_empty() {
+        return Value::Null;
+    }
+
+    let mime = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .and_then(|ct| ct.parse::<mime::Mime>().ok());
+
+    if let Some(mime) = mime {
+        if mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON) {
+            if let Ok(value) = serde_json::from_slice(&body) {
+                return value;
+            }
+        }
+    }
+    Value::String(String::from_utf8_lossy(&body).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{call_service, init_service, read_body_json, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn test_batch() {
+        let mut srv = init_service(
+            App::new()
+                .service(
+                    web::service("/batch")
+                        .finish(web::batch().max_requests(3).response_limit(64)),
+                )
+                .route(
+                    "/users/{id}",
+                    web::get().to(|id: web::Path<u32>, req: HttpRequest| {
+                        HttpResponse::Ok()
+                            .header(
+                                "x-user",
+                                req.headers().get("x-user").unwrap().clone(),
+                            )
+                            .json(serde_json::json!({ "id": *id }))
+                    }),
+                )
+                .route(
+                    "/echo",
+                    web::post().to(|body: web::Json<Value>| {
+                        HttpResponse::Created().json(body.0)
+                    }),
+                )
+                .route("/text", web::get().to(|| HttpResponse::Ok().body("text")))
+                .route(
+                    "/large",
+                    web::get().to(|| HttpResponse::Ok().body("x".repeat(65))),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/batch")
+            .header("x-user", "alice")
+            .set_json(&serde_json::json!([
+                {"path": "/users/1"},
+                {"method": "post", "path": "/echo", "body": {"name": "bob"}},
+                {"path": "/text", "headers": {"x-test": "1"}}
+            ]))
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = read_body_json(res).await;
+
+        assert_eq!(body[0]["status"], 200);
+        assert_eq!(body[0]["headers"]["x-user"], "alice");
+        assert_eq!(body[0]["body"], serde_json::json!({"id": 1}));
+        assert_eq!(body[1]["status"], 201);
+        assert_eq!(body[1]["body"], serde_json::json!({"name": "bob"}));
+        assert_eq!(body[2]["body"], "text");
+
+        // sub-request errors are reported as sub-responses
+        let req = TestRequest::post()
+            .uri("/batch")
+            .set_json(&serde_json::json!([
+                {"path": "/unknown"},
+                {"method": "in valid", "path": "/text"},
+                {"path": "/large"}
+            ]))
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        let body: Value = read_body_json(res).await;
+        assert_eq!(body[0]["status"], 404);
+        assert_eq!(body[1]["status"], 400);
+        assert_eq!(body[2]["status"], 500);
+        assert_eq!(body[2]["body"], "Sub-response body is larger than limit");
+
+        let req = TestRequest::post()
+            .uri("/batch")
+            .set_json(&serde_json::json!([{"path": "/"}, {"path": "/"}, {"path": "/"}, {"path": "/"}]))
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,142 @@
+use std::{error, fmt, io, net};
+
+/// Bind policy of the [`HttpServer::try_bind_all`] method.
+///
+/// [`HttpServer::try_bind_all`]: ../struct.HttpServer.html#method.try_bind_all
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindPolicy {
+    /// All addresses have to be bound.
+    All,
+    /// Proceed if at least one address is bound.
+    Any,
+}
+
+/// Report of binding multiple addresses.
+#[derive(Debug, Default)]
+pub struct BindReport {
+    pub(crate) bound: Vec<net::SocketAddr>,
+    pub(crate) failed: Vec<BindFailure>,
+}
+
+/// Address that could not be bound.
+#[derive(Debug)]
+pub struct BindFailure {
+    pub(crate) target: String,
+    pub(crate) addr: Option<net::SocketAddr>,
+    pub(crate) error: io::Error,
+}
+
+impl BindReport {
+    /// Get bound addresses.
+    pub fn bound(&self) -> &[net::SocketAddr] {
+        &self.bound
+    }
+
+    /// Get failed binds.
+    pub fn failed(&self) -> &[BindFailure] {
+        &self.failed
+    }
+
+    /// Check if all addresses are bound.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty() && !self.bound.is_empty()
+    }
+
+    /// Check if any ipv4 address is bound.
+    pub fn has_ipv4(&self) -> bool {
+        self.bound.iter().any(|addr| addr.is_ipv4())
+    }
+
+    /// Check if any ipv6 address is bound.
+    pub fn has_ipv6(&self) -> bool {
+        self.bound.iter().any(|addr| addr.is_ipv6())
+    }
+
+    /// Check if bound addresses satisfy the policy.
+    pub fn satisfies(&self, policy: BindPolicy) -> bool {
+        match policy {
+            BindPolicy::All => self.is_complete(),
+            BindPolicy::Any => !self.bound.is_empty(),
+        }
+    }
+
+    pub(crate) fn fail<T: fmt::Display>(
+        &mut self,
+        target: &T,
+        addr: Option<net::SocketAddr>,
+        error: io::Error,
+    ) {
+        self.failed.push(BindFailure {
+            target: target.to_string(),
+            addr,
+            error,
+        });
+    }
+}
+
+impl fmt::Display for BindReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.failed.is_empty() {
+            if self.bound.is_empty() {
+                return write!(f, "No addresses to bind");
+            }
+            return write!(f, "Bound {} address(es)", self.bound.len());
+        }
+
+        for (idx, failure) in self.failed.iter().enumerate() {
+            if idx > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for BindReport {}
+
+impl From<BindReport> for io::Error {
+    fn from(report: BindReport) -> io::Error {
+        let kind = report
+            .failed
+            .first()
+            .map(|failure| failure.error.kind())
+            .unwrap_or(io::ErrorKind::Other);
+        io::Error::new(kind, report)
+    }
+}
+
+impl BindFailure {
+    /// Get address as it was passed to the bind method.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Get resolved socket address, `None` if address resolution failed.
+    pub fn addr(&self) -> Option<net::SocketAddr> {
+        self.addr
+    }
+
+    /// Get bind error.
+    pub fn error(&self) -> &io::Error {
+        &self.error
+    }
+
+    /// Check if address is already in use.
+    pub fn is_addr_in_use(&self) -> bool {
+        self.error.kind() == io::ErrorKind::AddrInUse
+    }
+}
+
+impl fmt::Display for BindFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.addr {
+            Some(addr) if addr.to_string() != self.target => write!(
+                f,
+                "Can not bind to {} ({}): {}",
+                self.target, addr, self.error
+            ),
+            _ => write!(f, "Can not bind to {}: {}", self.target, self.error),
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use actix_http::error::{Error, ErrorInternalServerError};
+use futures::channel::mpsc::{channel, Receiver, Sender};
+use futures::future::{err, ok, Ready};
+use futures::{SinkExt, Stream, StreamExt};
+
+use crate::dev::Payload;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Worker hubs of all broadcasts used by current thread.
+    static HUBS: RefCell<HashMap<usize, Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// Broadcast channel shared by all server workers.
+///
+/// Messages sent from any worker, or any other thread, are delivered to
+/// subscribers on all workers. Every worker receives a single copy of the
+/// message through its own channel and fans it out to local subscribers,
+/// so subscribers could live on `!Send` worker arbiters.
+///
+/// Worker and subscriber queues are bounded (default: 64 messages).
+/// [`send`](#method.send) waits for free space in worker queues, workers
+/// wait for slow subscribers before delivering the next message.
+/// [`try_send`](#method.try_send) skips workers with full queues.
+///
+/// Broadcast has to be created outside of the `HttpServer::new()` closure
+/// and registered with `App::app_data()`.
+///
+/// ```rust
+/// use actix_web::web::{self, Bytes, ServerBroadcast};
+/// use actix_web::{App, Error, HttpResponse, HttpServer};
+/// use futures::StreamExt;
+///
+/// async fn events(events: ServerBroadcast<String>) -> HttpResponse {
+///     HttpResponse::Ok()
+///         .content_type("text/event-stream")
+///         .streaming(
+///             events
+///                 .subscribe()
+///                 .map(|ev| Ok::<_, Error>(Bytes::from(format!("data: {}\n\n", ev)))),
+///         )
+/// }
+///
+/// async fn publish(body: String, events: ServerBroadcast<String>) -> HttpResponse {
+///     events.send(body).await;
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// # fn main() {
+/// let broadcast = ServerBroadcast::<String>::new();
+/// HttpServer::new(move || {
+///     App::new()
+///         .app_data(broadcast.clone())
+///         .route("/events", web::get().to(events))
+///         .route("/publish", web::post().to(publish))
+/// });
+/// # }
+/// ```
+pub struct ServerBroadcast<T> {
+    inner: Arc<Shared<T>>,
+}
+
+/// Queue of a worker, locked while `ServerBroadcast::send()` waits for free
+/// space.
+type WorkerQueue<T> = Arc<futures::lock::Mutex<Sender<T>>>;
+
+struct Shared<T> {
+    id: usize,
+    capacity: usize,
+    workers: Mutex<Vec<WorkerQueue<T>>>,
+}
+
+/// Subscribers of the broadcast on a single worker.
+struct Hub<T> {
+    tx: Sender<T>,
+    subscribers: Rc<RefCell<Vec<Sender<T>>>>,
+}
+
+impl<T: Clone + Send + 'static> ServerBroadcast<T> {
+    /// Create new broadcast channel.
+    pub fn new() -> Self {
+        ServerBroadcast::with_capacity(64)
+    }
+
+    /// Create new broadcast channel with the capacity of worker and
+    /// subscriber queues.
+    pub fn with_capacity(capacity: usize) -> Self {
+        ServerBroadcast {
+            inner: Arc::new(Shared {
+                id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+                capacity,
+                workers: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Send message to subscribers on all workers, waiting for free space
+    /// in queues of busy workers.
+    pub async fn send(&self, msg: T) {
+        let workers = self.inner.workers.lock().unwrap().clone();
+        for tx in workers {
+            let _ = tx.lock().await.feed(msg.clone()).await;
+        }
+        self.inner
+            .workers
+            .lock()
+            .unwrap()
+            .retain(|tx| !is_closed(tx));
+    }
+
+    /// Send message to subscribers on workers with free space in queues.
+    ///
+    /// Returns number of workers message is sent to.
+    pub fn try_send(&self, msg: T) -> usize {
+        let mut workers = self.inner.workers.lock().unwrap();
+        let mut sent = 0;
+        for tx in workers.iter() {
+            if let Some(mut tx) = tx.try_lock() {
+                if tx.try_send(msg.clone()).is_ok() {
+                    sent += 1;
+                }
+            }
+        }
+        workers.retain(|tx| !is_closed(tx));
+        sent
+    }
+
+    /// Subscribe to messages on current worker.
+    ///
+    /// Subscription has to be created within running actix system, worker
+    /// channel is served by a task spawned on the current arbiter.
+    pub fn subscribe(&self) -> Subscription<T> {
+        let (tx, rx) = channel(self.inner.capacity);
+
+        HUBS.with(|hubs| {
+            let mut hubs = hubs.borrow_mut();
+            let alive = hubs
+                .get(&self.inner.id)
+                .and_then(|hub| hub.downcast_ref::<Hub<T>>())
+                .map_or(false, |hub| !hub.tx.is_closed());
+            if !alive {
+                hubs.insert(self.inner.id, Box::new(self.start_hub()));
+            }
+
+            let hub = hubs[&self.inner.id].downcast_ref::<Hub<T>>().unwrap();
+            hub.subscribers.borrow_mut().push(tx);
+        });
+
+        Subscription { rx }
+    }
+
+    /// Register current worker and start fan out task.
+    fn start_hub(&self) -> Hub<T> {
+        let (tx, mut rx) = channel::<T>(self.inner.capacity);
+        self.inner
+            .workers
+            .lock()
+            .unwrap()
+            .push(Arc::new(futures::lock::Mutex::new(tx.clone())));
+
+        let subscribers = Rc::new(RefCell::new(Vec::<Sender<T>>::new()));
+        let subs = subscribers.clone();
+        actix_rt::spawn(async move {
+            while let Some(msg) = rx.next().await {
+                // subscribers are taken out while waiting for slow ones,
+                // new subscribers receive next message
+                let current: Vec<_> = subs.borrow_mut().drain(..).collect();
+                let mut alive = Vec::with_capacity(current.len());
+                for mut tx in current {
+                    if tx.feed(msg.clone()).await.is_ok() {
+                        alive.push(tx);
+                    }
+                }
+                let mut subs = subs.borrow_mut();
+                alive.extend(subs.drain(..));
+                *subs = alive;
+            }
+        });
+
+        Hub { tx, subscribers }
+    }
+}
+
+/// Worker queue is closed when fan out task is stopped, locked queue is in
+/// use.
+fn is_closed<T>(tx: &WorkerQueue<T>) -> bool {
+    match tx.try_lock() {
+        Some(tx) => tx.is_closed(),
+        None => false,
+    }
+}
+
+impl<T: Clone + Send + 'static> Default for ServerBroadcast<T> {
+    fn default() -> Self {
+        ServerBroadcast::new()
+    }
+}
+
+impl<T> Clone for ServerBroadcast<T> {
+    fn clone(&self) -> Self {
+        ServerBroadcast {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: 'static> FromRequest for ServerBroadcast<T> {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(broadcast) = req.app_data::<ServerBroadcast<T>>() {
+            ok(broadcast.clone())
+        } else {
+            log::debug!(
+                "Failed to construct ServerBroadcast extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            err(ErrorInternalServerError(
+                "Broadcast is not configured, to configure use App::app_data()",
+            ))
+        }
+    }
+}
+
+/// Stream of broadcast messages, created with
+/// [`ServerBroadcast::subscribe()`](struct.ServerBroadcast.html#method.subscribe).
+pub struct Subscription<T> {
+    rx: Receiver<T>,
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+
+    use super::*;
+    use crate::test::{call_service, init_service, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[test]
+    fn test_broadcast_workers() {
+        let broadcast = ServerBroadcast::<usize>::new();
+        let (tx, rx) = mpsc::channel();
+
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                let broadcast = broadcast.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    actix_rt::System::new("worker").block_on(async move {
+                        let mut sub1 = broadcast.subscribe();
+                        let mut sub2 = broadcast.subscribe();
+                        tx.send(()).unwrap();
+                        assert_eq!(sub1.next().await, Some(1));
+                        assert_eq!(sub2.next().await, Some(1));
+                        assert_eq!(sub1.next().await, Some(2));
+                    })
+                })
+            })
+            .collect();
+
+        rx.recv().unwrap();
+        rx.recv().unwrap();
+        // single channel per worker
+        assert_eq!(broadcast.inner.workers.lock().unwrap().len(), 2);
+
+        futures::executor::block_on(broadcast.send(1));
+        futures::executor::block_on(broadcast.send(2));
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_extractor() {
+        let broadcast = ServerBroadcast::<String>::new();
+        let mut sub = broadcast.subscribe();
+
+        let mut srv = init_service(App::new().app_data(broadcast.clone()).route(
+            "/",
+            web::post().to(|body: String, b: ServerBroadcast<String>| async move {
+                b.send(body).await;
+                "sent"
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::post().set_payload("event").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(sub.next().await.unwrap(), "event");
+
+        let mut srv = init_service(App::new().route(
+            "/",
+            web::get().to(|_: ServerBroadcast<String>| HttpResponse::Ok()),
+        ))
+        .await;
+        let resp = call_service(&mut srv, TestRequest::default().to_request()).await;
+        assert!(resp.status().is_server_error());
+    }
+
+    #[actix_rt::test]
+    async fn test_full_queue() {
+        let broadcast = ServerBroadcast::<usize>::with_capacity(0);
+        let (tx, mut rx) = channel(0);
+        broadcast
+            .inner
+            .workers
+            .lock()
+            .unwrap()
+            .push(Arc::new(futures::lock::Mutex::new(tx)));
+
+        // queue has a single slot per sender
+        assert_eq!(broadcast.try_send(1), 1);
+        assert_eq!(broadcast.try_send(2), 0);
+
+        let send = broadcast.send(3);
+        futures::pin_mut!(send);
+        assert!(futures::poll!(send.as_mut()).is_pending());
+        assert_eq!(rx.next().await, Some(1));
+        send.await;
+        assert_eq!(rx.next().await, Some(3));
+    }
+}
@@ -25,6 +25,8 @@ pub struct AppService {
     config: AppConfig,
     root: bool,
     default: Rc<HttpNewService>,
+    scope_default: Option<Rc<HttpNewService>>,
+    method_not_allowed: Option<Rc<HttpNewService>>,
     services: Vec<(
         ResourceDef,
         HttpNewService,
@@ -44,6 +46,8 @@ impl AppService {
         AppService {
             config,
             default,
+            scope_default: None,
+            method_not_allowed: None,
             service_data,
             root: true,
             services: Vec::new(),
@@ -73,6 +77,8 @@ impl AppService {
         AppService {
             config: self.config.clone(),
             default: self.default.clone(),
+            scope_default: self.scope_default.clone(),
+            method_not_allowed: self.method_not_allowed.clone(),
             services: Vec::new(),
             root: false,
             service_data: self.service_data.clone(),
@@ -89,6 +95,32 @@ impl AppService {
         self.default.clone()
     }
 
+    /// Default service of the enclosing scope or app, used by scopes that
+    /// fall through to the parent's default service.
+    pub(crate) fn fall_through_service(&self) -> Rc<HttpNewService> {
+        self.scope_default
+            .clone()
+            .unwrap_or_else(|| self.default.clone())
+    }
+
+    pub(crate) fn set_scope_default_service(&mut self, default: Rc<HttpNewService>) {
+        self.scope_default = Some(default);
+    }
+
+    /// Service for requests that match resource path but none of its routes
+    pub fn method_not_allowed_service(&self) -> Option<Rc<HttpNewService>> {
+        self.method_not_allowed.clone()
+    }
+
+    pub(crate) fn set_method_not_allowed_service(
+        &mut self,
+        service: Option<Rc<HttpNewService>>,
+    ) {
+        if service.is_some() {
+            self.method_not_allowed = service;
+        }
+    }
+
     /// Set global route data
     pub fn set_service_data(&self, extensions: &mut Extensions) -> bool {
         for f in self.service_data.iter() {
@@ -177,6 +209,7 @@ pub struct ServiceConfig {
     pub(crate) services: Vec<Box<dyn AppServiceFactory>>,
     pub(crate) data: Vec<Box<dyn DataFactory>>,
     pub(crate) external: Vec<ResourceDef>,
+    pub(crate) extensions: Extensions,
 }
 
 impl ServiceConfig {
@@ -185,6 +218,7 @@ impl ServiceConfig {
             services: Vec::new(),
             data: Vec::new(),
             external: Vec::new(),
+            extensions: Extensions::new(),
         }
     }
 
@@ -197,6 +231,14 @@ impl ServiceConfig {
         self
     }
 
+    /// Set arbitrary data item.
+    ///
+    /// This is same as `App::app_data()` method.
+    pub fn app_data<U: 'static>(&mut self, ext: U) -> &mut Self {
+        self.extensions.insert(ext);
+        self
+    }
+
     /// Configure route for a specific path.
     ///
     /// This is same as `App::route()` method.
@@ -265,6 +307,37 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[actix_rt::test]
+    async fn test_app_data() {
+        let cfg = |cfg: &mut ServiceConfig| {
+            cfg.app_data(10usize);
+        };
+
+        let mut srv = init_service(App::new().configure(cfg).service(
+            web::resource("/").to(|req: HttpRequest| {
+                assert_eq!(req.app_data::<usize>(), Some(&10));
+                HttpResponse::Ok()
+            }),
+        ))
+        .await;
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let mut srv = init_service(
+            App::new().service(web::scope("/scope").configure(cfg).service(
+                web::resource("/").to(|req: HttpRequest| {
+                    assert_eq!(req.app_data::<usize>(), Some(&10));
+                    HttpResponse::Ok()
+                }),
+            )),
+        )
+        .await;
+        let req = TestRequest::with_uri("/scope/").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     // #[actix_rt::test]
     // async fn test_data_factory() {
     //     let cfg = |cfg: &mut ServiceConfig| {
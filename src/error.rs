@@ -131,6 +131,84 @@ impl ResponseError for QueryPayloadError {
     }
 }
 
+/// A set of errors that can occur during extracting signed cookies
+#[derive(Debug, Display, From)]
+pub enum SignedCookieError {
+    /// Signing keys are not configured
+    #[display(fmt = "Signed cookie keys are not configured")]
+    NotConfigured,
+    /// Cookie is not present in the request
+    #[display(fmt = "Signed cookie is not found")]
+    NotFound,
+    /// Cookie signature or encryption can not be verified
+    #[display(fmt = "Signed cookie verification failed")]
+    Verification,
+    /// Serialize or deserialize error
+    #[display(fmt = "Signed cookie json error: {}", _0)]
+    Json(JsonError),
+}
+
+impl std::error::Error for SignedCookieError {}
+
+/// Return `BadRequest` for `SignedCookieError`
+impl ResponseError for SignedCookieError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            SignedCookieError::NotConfigured => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// A set of errors that can occur during checking request preconditions
+#[derive(Debug, Display, PartialEq)]
+pub enum PreconditionError {
+    /// Precondition headers do not match current state of the resource
+    #[display(fmt = "Precondition failed")]
+    Failed,
+    /// Request does not have precondition headers
+    #[display(fmt = "Precondition required")]
+    Required,
+}
+
+impl std::error::Error for PreconditionError {}
+
+/// Return `PreconditionFailed` or `PreconditionRequired` for `PreconditionError`
+impl ResponseError for PreconditionError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            PreconditionError::Failed => StatusCode::PRECONDITION_FAILED,
+            PreconditionError::Required => StatusCode::PRECONDITION_REQUIRED,
+        }
+    }
+}
+
+/// Error that can occur during template rendering
+#[derive(Debug, Display)]
+#[display(fmt = "Template render error: {}", _0)]
+pub struct TemplateError(Box<dyn std::error::Error + Send + Sync>);
+
+impl TemplateError {
+    /// Create template error from any error type
+    pub fn new<E>(err: E) -> Self
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        TemplateError(err.into())
+    }
+}
+
+impl From<std::io::Error> for TemplateError {
+    fn from(err: std::io::Error) -> Self {
+        TemplateError(Box::new(err))
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// `InternalServerError` for `TemplateError`
+impl ResponseError for TemplateError {}
+
 /// Error type returned when reading body as lines.
 #[derive(From, Display, Debug)]
 pub enum ReadlinesError {
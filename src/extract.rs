@@ -191,7 +191,18 @@ impl FromRequest for () {
     }
 }
 
-macro_rules! tuple_from_req ({$fut_type:ident, $(($n:tt, $T:ident)),+} => {
+macro_rules! tuple_from_req {
+    ($fut_type:ident, $(($n:tt, $T:ident)),+) => {
+        tuple_from_req!(@impl $fut_type, ($($T::Config),+), $(($n, $T)),+);
+    };
+
+    // tuples of more than 12 items do not implement `Default`, extractors
+    // of large tuples are configured individually only
+    (@large $fut_type:ident, $(($n:tt, $T:ident)),+) => {
+        tuple_from_req!(@impl $fut_type, (), $(($n, $T)),+);
+    };
+
+    (@impl $fut_type:ident, $config:ty, $(($n:tt, $T:ident)),+) => {
 
     // This module is a trick to get around the inability of
     // `macro_rules!` macros to make new idents. We want to make
@@ -224,11 +235,11 @@ macro_rules! tuple_from_req ({$fut_type:ident, $(($n:tt, $T:ident)),+} => {
         {
             type Error = Error;
             type Future = $fut_type<$($T),+>;
-            type Config = ($($T::Config),+);
+            type Config = $config;
 
             fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
                 $fut_type {
-                    items: <($(Option<$T>,)+)>::default(),
+                    items: ($(None::<$T>,)+),
                     futs: FutWrapper($($T::from_request(req, payload),)+),
                 }
             }
@@ -272,7 +283,8 @@ macro_rules! tuple_from_req ({$fut_type:ident, $(($n:tt, $T:ident)),+} => {
             }
         }
     }
-});
+    };
+}
 
 #[rustfmt::skip]
 mod m {
@@ -288,6 +300,22 @@ tuple_from_req!(TupleFromRequest7, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F
 tuple_from_req!(TupleFromRequest8, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H));
 tuple_from_req!(TupleFromRequest9, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I));
 tuple_from_req!(TupleFromRequest10, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J));
+tuple_from_req!(TupleFromRequest11, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K));
+tuple_from_req!(TupleFromRequest12, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L));
+tuple_from_req!(@large TupleFromRequest13, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M));
+tuple_from_req!(@large TupleFromRequest14, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N));
+tuple_from_req!(@large TupleFromRequest15, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O));
+tuple_from_req!(@large TupleFromRequest16, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P));
+tuple_from_req!(@large TupleFromRequest17, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P), (16, Q));
+tuple_from_req!(@large TupleFromRequest18, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P), (16, Q), (17, R));
+tuple_from_req!(@large TupleFromRequest19, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P), (16, Q), (17, R), (18, S));
+tuple_from_req!(@large TupleFromRequest20, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P), (16, Q), (17, R), (18, S), (19, T));
+tuple_from_req!(@large TupleFromRequest21, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P), (16, Q), (17, R), (18, S), (19, T), (20, U));
+tuple_from_req!(@large TupleFromRequest22, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P), (16, Q), (17, R), (18, S), (19, T), (20, U), (21, V));
+tuple_from_req!(@large TupleFromRequest23, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P), (16, Q), (17, R), (18, S), (19, T), (20, U), (21, V), (22, W));
+tuple_from_req!(@large TupleFromRequest24, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P), (16, Q), (17, R), (18, S), (19, T), (20, U), (21, V), (22, W), (23, X));
+tuple_from_req!(@large TupleFromRequest25, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P), (16, Q), (17, R), (18, S), (19, T), (20, U), (21, V), (22, W), (23, X), (24, Y));
+tuple_from_req!(@large TupleFromRequest26, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P), (16, Q), (17, R), (18, S), (19, T), (20, U), (21, V), (22, W), (23, X), (24, Y), (25, Z));
 }
 
 #[cfg(test)]
@@ -385,4 +413,52 @@ mod tests {
             .unwrap();
         assert!(r.is_err());
     }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn many_extractors(
+        req: HttpRequest,
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        _: (),
+        body: String,
+    ) -> String {
+        format!("{} {}", req.path(), body)
+    }
+
+    #[actix_rt::test]
+    async fn test_large_tuple() {
+        use crate::test::{call_service, init_service, read_body};
+        use crate::{web, App};
+
+        let mut srv =
+            init_service(App::new().route("/test", web::post().to(many_extractors)))
+                .await;
+        let req = TestRequest::post()
+            .uri("/test")
+            .set_payload(Bytes::from_static(b"body"))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"/test body"));
+    }
 }
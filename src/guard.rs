@@ -24,11 +24,23 @@
 //!     );
 //! }
 //! ```
+//!
+//! Guards that need to do I/O, i.e. lookup in a database, implement
+//! `AsyncGuard` trait and are registered with `Route::async_guard()`.
+//! Async guards do not take part in route selection. Router selects route
+//! by its sync guards first, async guards are checked afterwards, before
+//! route handler is called. If async guard does not match, request is not
+//! passed to other routes, *404 Not Found* response is returned instead.
 #![allow(non_snake_case)]
 use std::convert::TryFrom;
+use std::future::Future;
 
 use actix_http::http::{self, header, uri::Uri};
 use actix_http::RequestHead;
+use futures::future::{FutureExt, LocalBoxFuture};
+use regex::Regex;
+
+use crate::request::HttpRequest;
 
 /// Trait defines resource guards. Guards are used for route selection.
 ///
@@ -122,6 +134,29 @@ impl Guard for AnyGuard {
     }
 }
 
+/// Return guard that matches if any of guards in the collection matches.
+///
+/// ```rust
+/// use actix_web::{web, guard, App, HttpResponse};
+///
+/// fn main() {
+///     let methods: Vec<Box<dyn guard::Guard>> =
+///         vec![Box::new(guard::Get()), Box::new(guard::Head())];
+///
+///     App::new().service(web::resource("/index.html").route(
+///         web::route()
+///              .guard(guard::AnyOf(methods))
+///              .to(|| HttpResponse::Ok()))
+///     );
+/// }
+/// ```
+pub fn AnyOf<I>(guards: I) -> AnyGuard
+where
+    I: IntoIterator<Item = Box<dyn Guard>>,
+{
+    AnyGuard(guards.into_iter().collect())
+}
+
 /// Return guard that matches if all of the supplied guards.
 ///
 /// ```rust
@@ -162,6 +197,16 @@ impl Guard for AllGuard {
     }
 }
 
+/// Return guard that matches if all of guards in the collection match.
+///
+/// Empty collection matches any request.
+pub fn AllOf<I>(guards: I) -> AllGuard
+where
+    I: IntoIterator<Item = Box<dyn Guard>>,
+{
+    AllGuard(guards.into_iter().collect())
+}
+
 /// Return guard that matches if supplied guard does not match.
 pub fn Not<F: Guard + 'static>(guard: F) -> NotGuard {
     NotGuard(Box::new(guard))
@@ -176,6 +221,14 @@ impl Guard for NotGuard {
     }
 }
 
+/// Return guard that matches if none of guards in the collection matches.
+pub fn NoneOf<I>(guards: I) -> NotGuard
+where
+    I: IntoIterator<Item = Box<dyn Guard>>,
+{
+    NotGuard(Box::new(AnyOf(guards)))
+}
+
 /// Http method guard
 #[doc(hidden)]
 pub struct MethodGuard(http::Method);
@@ -257,6 +310,61 @@ impl Guard for HeaderGuard {
     }
 }
 
+/// Return predicate that matches if request contains specified header and
+/// header value matches regular expression.
+///
+/// Panics if `re` is not a valid regular expression.
+///
+/// ```rust
+/// use actix_web::{web, guard, App, HttpResponse};
+///
+/// fn main() {
+///     App::new().service(web::resource("/index.html").route(
+///         web::route()
+///              .guard(guard::HeaderRegex("accept", r"^application/(.+\+)?json"))
+///              .to(|| HttpResponse::Ok()))
+///     );
+/// }
+/// ```
+pub fn HeaderRegex(name: &'static str, re: &str) -> HeaderRegexGuard {
+    HeaderRegexGuard(
+        header::HeaderName::try_from(name).unwrap(),
+        Regex::new(re).unwrap(),
+    )
+}
+
+#[doc(hidden)]
+pub struct HeaderRegexGuard(header::HeaderName, Regex);
+
+impl Guard for HeaderRegexGuard {
+    fn check(&self, req: &RequestHead) -> bool {
+        if let Some(val) = req.headers.get(&self.0) {
+            if let Ok(val) = val.to_str() {
+                return self.1.is_match(val);
+            }
+        }
+        false
+    }
+}
+
+/// Return predicate that matches if request contains specified header and
+/// header value starts with `prefix`.
+pub fn HeaderPrefix(name: &'static str, prefix: &'static str) -> HeaderPrefixGuard {
+    HeaderPrefixGuard(header::HeaderName::try_from(name).unwrap(), prefix)
+}
+
+#[doc(hidden)]
+pub struct HeaderPrefixGuard(header::HeaderName, &'static str);
+
+impl Guard for HeaderPrefixGuard {
+    fn check(&self, req: &RequestHead) -> bool {
+        if let Some(val) = req.headers.get(&self.0) {
+            return val.as_bytes().starts_with(self.1.as_bytes());
+        }
+        false
+    }
+}
+
 /// Return predicate that matches if request contains specified Host name.
 ///
 /// ```rust
@@ -321,6 +429,93 @@ impl Guard for HostGuard {
     }
 }
 
+/// Return predicate that matches if `Sec-Fetch-Site` request header is
+/// the specified site.
+///
+/// Requests without fetch metadata headers do not match.
+///
+/// ```rust
+/// use actix_web::middleware::FetchSite;
+/// use actix_web::{guard, web, App, HttpResponse};
+///
+/// fn main() {
+///     App::new().service(
+///         web::resource("/settings")
+///             .guard(guard::FetchSite(FetchSite::SameOrigin))
+///             .route(web::post().to(|| HttpResponse::Ok()))
+///     );
+/// }
+/// ```
+pub fn FetchSite(site: crate::middleware::FetchSite) -> FetchSiteGuard {
+    FetchSiteGuard(site)
+}
+
+#[doc(hidden)]
+pub struct FetchSiteGuard(crate::middleware::FetchSite);
+
+impl Guard for FetchSiteGuard {
+    fn check(&self, req: &RequestHead) -> bool {
+        match crate::middleware::FetchMetadata::from_head(req) {
+            Some(meta) => meta.site() == self.0,
+            None => false,
+        }
+    }
+}
+
+/// Trait defines guards that need to do I/O.
+///
+/// Async guards are checked after route is selected, see
+/// [module level documentation](index.html) for details.
+pub trait AsyncGuard {
+    /// Check if request matches predicate
+    fn check(&self, request: &HttpRequest) -> LocalBoxFuture<'static, bool>;
+}
+
+/// Create async guard object for supplied function.
+///
+/// ```rust
+/// use actix_web::{guard, web, App, HttpResponse};
+///
+/// async fn is_allowed(token: Option<String>) -> bool {
+///     // lookup token in a database
+///     token.is_some()
+/// }
+///
+/// fn main() {
+///     App::new().service(web::resource("/index.html").route(
+///         web::get()
+///             .async_guard(guard::fn_async_guard(|req| {
+///                 let token = req
+///                     .headers()
+///                     .get("x-token")
+///                     .and_then(|v| v.to_str().ok())
+///                     .map(|v| v.to_owned());
+///                 is_allowed(token)
+///             }))
+///             .to(|| HttpResponse::Ok()))
+///     );
+/// }
+/// ```
+pub fn fn_async_guard<F, R>(f: F) -> impl AsyncGuard
+where
+    F: Fn(&HttpRequest) -> R,
+    R: Future<Output = bool> + 'static,
+{
+    FnAsyncGuard(f)
+}
+
+struct FnAsyncGuard<F>(F);
+
+impl<F, R> AsyncGuard for FnAsyncGuard<F>
+where
+    F: Fn(&HttpRequest) -> R,
+    R: Future<Output = bool> + 'static,
+{
+    fn check(&self, req: &HttpRequest) -> LocalBoxFuture<'static, bool> {
+        (self.0)(req).boxed_local()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use actix_http::http::{header, Method};
@@ -328,6 +523,19 @@ mod tests {
     use super::*;
     use crate::test::TestRequest;
 
+    #[test]
+    fn test_fetch_site() {
+        use crate::middleware::FetchSite as Site;
+
+        let req = TestRequest::with_header("sec-fetch-site", "same-origin")
+            .to_http_request();
+        assert!(FetchSite(Site::SameOrigin).check(req.head()));
+        assert!(!FetchSite(Site::CrossSite).check(req.head()));
+
+        let req = TestRequest::default().to_http_request();
+        assert!(!FetchSite(Site::SameOrigin).check(req.head()));
+    }
+
     #[test]
     fn test_header() {
         let req = TestRequest::with_header(header::TRANSFER_ENCODING, "chunked")
@@ -343,6 +551,35 @@ mod tests {
         assert!(!pred.check(req.head()));
     }
 
+    #[test]
+    fn test_header_regex_prefix() {
+        let req = TestRequest::with_header(header::ACCEPT, "application/vnd.api+json")
+            .to_http_request();
+
+        assert!(HeaderRegex("accept", r"^application/(.+\+)?json$").check(req.head()));
+        assert!(!HeaderRegex("accept", r"^text/").check(req.head()));
+        assert!(!HeaderRegex("content-type", r".*").check(req.head()));
+
+        assert!(HeaderPrefix("accept", "application/").check(req.head()));
+        assert!(!HeaderPrefix("accept", "text/").check(req.head()));
+        assert!(!HeaderPrefix("content-type", "").check(req.head()));
+    }
+
+    #[test]
+    fn test_collections() {
+        let req = TestRequest::default().method(Method::PUT).to_http_request();
+
+        let guards =
+            || -> Vec<Box<dyn Guard>> { vec![Box::new(Get()), Box::new(Put())] };
+        assert!(AnyOf(guards()).check(req.head()));
+        assert!(!AllOf(guards()).check(req.head()));
+        assert!(!NoneOf(guards()).check(req.head()));
+
+        assert!(!AnyOf(Vec::new()).check(req.head()));
+        assert!(AllOf(Vec::new()).check(req.head()));
+        assert!(NoneOf(vec![Box::new(Get()) as Box<dyn Guard>]).check(req.head()));
+    }
+
     #[test]
     fn test_host() {
         let req = TestRequest::default()
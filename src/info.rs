@@ -1,7 +1,16 @@
 use std::cell::Ref;
+#[cfg(unix)]
+use std::fmt;
+use std::rc::Rc;
 
-use crate::dev::{AppConfig, RequestHead};
+use actix_http::error::{Error, ErrorBadRequest};
+use bytes::Bytes;
+use futures::future::{err, ok, Ready};
+
+use crate::dev::{AppConfig, Payload, RequestHead};
+use crate::extract::FromRequest;
 use crate::http::header::{self, HeaderName};
+use crate::request::HttpRequest;
 
 const X_FORWARDED_FOR: &[u8] = b"x-forwarded-for";
 const X_FORWARDED_HOST: &[u8] = b"x-forwarded-host";
@@ -20,7 +29,8 @@ impl ConnectionInfo {
     /// Create *ConnectionInfo* instance for a request.
     pub fn get<'a>(req: &'a RequestHead, cfg: &AppConfig) -> Ref<'a, Self> {
         if !req.extensions().contains::<ConnectionInfo>() {
-            req.extensions_mut().insert(ConnectionInfo::new(req, cfg));
+            let info = ConnectionInfo::new(req, cfg);
+            req.extensions_mut().insert(info);
         }
         Ref::map(req.extensions(), |e| e.get().unwrap())
     }
@@ -119,6 +129,12 @@ impl ConnectionInfo {
             if remote.is_none() {
                 // get peeraddr from socketaddr
                 peer = req.peer_addr.map(|addr| format!("{}", addr));
+                #[cfg(unix)]
+                {
+                    if peer.is_none() {
+                        peer = PeerCredentials::get(req).map(|cred| cred.to_string());
+                    }
+                }
             }
         }
 
@@ -163,6 +179,9 @@ impl ConnectionInfo {
     /// - X-Forwarded-For
     /// - peer name of opened socket
     ///
+    /// Peer of unix domain socket is formatted as `unix:pid=<pid>,uid=<uid>`,
+    /// see [`PeerCredentials`](../web/struct.PeerCredentials.html).
+    ///
     /// # Security
     /// Do not use this function for security purposes, unless you can ensure the Forwarded and
     /// X-Forwarded-For headers cannot be spoofed by the client. If you want the client's socket
@@ -180,6 +199,226 @@ impl ConnectionInfo {
     }
 }
 
+/// Certificate chain presented by tls client.
+///
+/// Openssl and rustls listeners of `HttpServer` store certificates of the
+/// client in request extensions. Client presents certificate only if client
+/// authentication is configured for the acceptor. Extractor fails with
+/// *BAD REQUEST* response if certificate is not presented, use
+/// `Option<PeerCertificate>` for optional authentication.
+///
+/// ```rust
+/// use actix_web::{web, App, HttpResponse};
+///
+/// async fn index(cert: web::PeerCertificate) -> HttpResponse {
+///     HttpResponse::Ok().body(format!("Certificate of {} bytes", cert.der().len()))
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/", web::get().to(index));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerCertificate(Rc<Vec<Bytes>>);
+
+impl PeerCertificate {
+    /// Create certificate of DER encoded certificate chain, end-entity
+    /// certificate goes first. Returns `None` if chain is empty.
+    pub fn new(chain: Vec<Bytes>) -> Option<Self> {
+        if chain.is_empty() {
+            None
+        } else {
+            Some(PeerCertificate(Rc::new(chain)))
+        }
+    }
+
+    /// DER encoded end-entity certificate of the client.
+    pub fn der(&self) -> &[u8] {
+        &self.0[0]
+    }
+
+    /// DER encoded certificate chain, end-entity certificate goes first.
+    pub fn chain(&self) -> &[Bytes] {
+        &self.0
+    }
+
+    /// Get certificate of the request, if client presented one.
+    pub(crate) fn get(req: &RequestHead) -> Option<PeerCertificate> {
+        req.extensions()
+            .get::<Option<PeerCertificate>>()
+            .and_then(|cert| cert.clone())
+    }
+
+    #[cfg(feature = "openssl")]
+    pub(crate) fn from_openssl(
+        io: &actix_tls::openssl::SslStream<actix_rt::net::TcpStream>,
+    ) -> Option<Self> {
+        let ssl = io.ssl();
+        let cert = ssl.peer_certificate()?;
+        let mut chain = vec![Bytes::from(cert.to_der().ok()?)];
+
+        // chain of server side connection does not include peer certificate
+        if let Some(certs) = ssl.peer_cert_chain() {
+            for cert in certs {
+                chain.push(Bytes::from(cert.to_der().ok()?));
+            }
+        }
+        PeerCertificate::new(chain)
+    }
+
+    #[cfg(feature = "rustls")]
+    pub(crate) fn from_rustls(
+        io: &actix_tls::rustls::TlsStream<actix_rt::net::TcpStream>,
+    ) -> Option<Self> {
+        use actix_tls::rustls::Session;
+
+        let certs = io.get_ref().1.get_peer_certificates()?;
+        PeerCertificate::new(certs.into_iter().map(|c| Bytes::from(c.0)).collect())
+    }
+}
+
+impl FromRequest for PeerCertificate {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<PeerCertificate, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match req.peer_certificate() {
+            Some(cert) => ok(cert),
+            None => err(ErrorBadRequest("Client certificate is not presented")),
+        }
+    }
+}
+
+/// Credentials of the process connected to unix domain socket.
+///
+/// Unix domain socket listeners of `HttpServer` store credentials of the
+/// peer process in request extensions. Process id is available only on
+/// linux. Extractor fails with *BAD REQUEST* response for connections of
+/// other listeners, use `Option<PeerCredentials>` if application is served
+/// over tcp as well.
+///
+/// ```rust
+/// use actix_web::{web, App, HttpResponse};
+///
+/// async fn index(cred: web::PeerCredentials) -> HttpResponse {
+///     if cred.uid() == 0 {
+///         HttpResponse::Ok().body("Hello, root!")
+///     } else {
+///         HttpResponse::Forbidden().finish()
+///     }
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/", web::get().to(index));
+/// }
+/// ```
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCredentials {
+    pid: Option<u32>,
+    uid: u32,
+    gid: u32,
+}
+
+#[cfg(unix)]
+impl PeerCredentials {
+    /// Process id of the peer, available only on linux.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// User id of the peer process.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Group id of the peer process.
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Get credentials of the request peer, if it is connected to unix
+    /// domain socket.
+    pub(crate) fn get(req: &RequestHead) -> Option<PeerCredentials> {
+        req.extensions()
+            .get::<Option<PeerCredentials>>()
+            .and_then(|cred| *cred)
+    }
+
+    pub(crate) fn from_unix(io: &actix_rt::net::UnixStream) -> Option<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::io::AsRawFd;
+
+            PeerCredentials::from_fd(io.as_raw_fd())
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let cred = io.peer_cred().ok()?;
+            Some(PeerCredentials {
+                pid: None,
+                uid: cred.uid,
+                gid: cred.gid,
+            })
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn from_fd(fd: std::os::unix::io::RawFd) -> Option<Self> {
+        let mut cred = libc::ucred {
+            pid: 0,
+            uid: 0,
+            gid: 0,
+        };
+        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+        let res = unsafe {
+            libc::getsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                &mut cred as *mut libc::ucred as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if res != 0 || len as usize != std::mem::size_of::<libc::ucred>() {
+            return None;
+        }
+
+        Some(PeerCredentials {
+            pid: Some(cred.pid as u32),
+            uid: cred.uid,
+            gid: cred.gid,
+        })
+    }
+}
+
+#[cfg(unix)]
+impl fmt::Display for PeerCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pid {
+            Some(pid) => write!(f, "unix:pid={},uid={}", pid, self.uid),
+            None => write!(f, "unix:uid={}", self.uid),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl FromRequest for PeerCredentials {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<PeerCredentials, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match req.peer_credentials() {
+            Some(cred) => ok(cred),
+            None => err(ErrorBadRequest("Peer is not connected to unix socket")),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -232,4 +471,48 @@ mod tests {
         let info = req.connection_info();
         assert_eq!(info.scheme(), "https");
     }
+
+    #[actix_rt::test]
+    async fn test_peer_certificate() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        assert!(PeerCertificate::from_request(&req, &mut pl).await.is_err());
+        assert!(PeerCertificate::new(Vec::new()).is_none());
+
+        let cert = PeerCertificate::new(vec![
+            Bytes::from_static(b"client"),
+            Bytes::from_static(b"ca"),
+        ]);
+        req.extensions_mut().insert(cert);
+
+        let cert = PeerCertificate::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(cert.der(), b"client");
+        assert_eq!(cert.chain().len(), 2);
+        assert_eq!(req.peer_certificate(), Some(cert));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[actix_rt::test]
+    async fn test_peer_credentials() {
+        use std::os::unix::io::AsRawFd;
+
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        assert!(PeerCredentials::from_request(&req, &mut pl).await.is_err());
+
+        let (sock, _peer) = std::os::unix::net::UnixStream::pair().unwrap();
+        let cred = PeerCredentials::from_fd(sock.as_raw_fd()).unwrap();
+        assert_eq!(cred.pid(), Some(std::process::id()));
+        assert_eq!(cred.uid(), unsafe { libc::getuid() });
+        req.extensions_mut().insert(Some(cred));
+
+        let info = req.connection_info();
+        assert_eq!(
+            info.remote().unwrap(),
+            format!("unix:pid={},uid={}", std::process::id(), cred.uid())
+        );
+        drop(info);
+        assert_eq!(
+            PeerCredentials::from_request(&req, &mut pl).await.unwrap(),
+            cred
+        );
+    }
 }
@@ -76,16 +76,32 @@
 //!
 //! ## Package feature
 //!
+//! * `admin` - enables [`web::admin()`](web/fn.admin.html) control plane scope
 //! * `client` - enables http client (default enabled)
 //! * `compress` - enables content encoding compression support (default enabled)
+//! * `compress-dictionary` - enables shared dictionary compression in
+//!   `Compress` middleware, includes `ring` crate as dependency
+//! * `compress-zstd` - enables `zstd` content encoding, requires c compiler
 //! * `openssl` - enables ssl support via `openssl` crate, supports `http/2`
 //! * `rustls` - enables ssl support via `rustls` crate, supports `http/2`
 //! * `secure-cookies` - enables secure cookies support, includes `ring` crate as
 //!   dependency
+//! * `askama`, `tera`, `minijinja` - enables template engine adapters in
+//!   [template](template/index.html) module
+//! * `toml`, `yaml` - enables [`ServerConfigFile`](struct.ServerConfigFile.html)
+//!   loaders for the corresponding formats
+//! * `tracing` - enables `tracing` spans for request processing phases: `read`,
+//!   `parse`, `route`, `handle`, `encode` and `write`, i.e. for flamegraphs
+//!   with `tracing-flame`
 #![allow(clippy::type_complexity, clippy::new_without_default)]
 
+#[cfg(feature = "admin")]
+mod admin;
 mod app;
 mod app_service;
+mod batch;
+mod bind;
+mod broadcast;
 mod config;
 mod data;
 pub mod error;
@@ -93,7 +109,9 @@ mod extract;
 pub mod guard;
 mod handler;
 mod info;
+mod local;
 pub mod middleware;
+mod redirect;
 mod request;
 mod resource;
 mod responder;
@@ -101,10 +119,16 @@ mod rmap;
 mod route;
 mod scope;
 mod server;
+mod server_config;
 mod service;
+mod split;
+mod sticky;
+pub mod template;
 pub mod test;
 mod types;
+mod vary;
 pub mod web;
+mod worker;
 
 #[doc(hidden)]
 pub use actix_web_codegen::*;
@@ -121,6 +145,11 @@ pub use crate::responder::{Either, Responder};
 pub use crate::route::Route;
 pub use crate::scope::Scope;
 pub use crate::server::HttpServer;
+#[cfg(feature = "rustls")]
+pub use crate::server::RustlsReload;
+pub use crate::server_config::ServerConfigFile;
+pub use crate::sticky::StickyRouting;
+pub use crate::worker::WorkerAffinity;
 
 pub mod dev {
     //! The `actix-web` prelude for library developers
@@ -133,14 +162,20 @@ pub mod dev {
     //! use actix_web::dev::*;
     //! ```
 
+    pub use crate::bind::{BindFailure, BindPolicy, BindReport};
     pub use crate::config::{AppConfig, AppService};
     #[doc(hidden)]
     pub use crate::handler::Factory;
     pub use crate::info::ConnectionInfo;
     pub use crate::rmap::ResourceMap;
+    #[cfg(feature = "openssl")]
+    pub use crate::server::ClientHello;
+    pub use crate::server_config::{CompressionSection, LimitsSection, TlsSection};
+    pub use crate::vary::VaryOn;
     pub use crate::service::{
         HttpServiceFactory, ServiceRequest, ServiceResponse, WebService,
     };
+    pub use crate::split::{Split, SplitService};
 
     pub use crate::types::form::UrlEncoded;
     pub use crate::types::json::JsonBody;
@@ -149,9 +184,11 @@ pub mod dev {
     pub use actix_http::body::{Body, BodySize, MessageBody, ResponseBody, SizedStream};
     #[cfg(feature = "compress")]
     pub use actix_http::encoding::Decoder as Decompress;
+    pub use actix_http::h1::Informational;
     pub use actix_http::ResponseBuilder as HttpResponseBuilder;
     pub use actix_http::{
-        Extensions, Payload, PayloadStream, RequestHead, ResponseHead,
+        BandwidthLimit, BufferPool, Extensions, HealthMonitor, Load, LoadShedPolicy,
+        Payload, PayloadStream, Protocol, RequestHead, ResponseHead,
     };
     pub use actix_router::{Path, ResourceDef, ResourcePath, Url};
     pub use actix_server::Server;
@@ -171,6 +208,9 @@ pub mod dev {
 
     struct Enc(ContentEncoding);
 
+    /// Marks response body as encoded by handler.
+    pub(crate) struct PreEncoded;
+
     /// Helper trait that allows to set specific encoding for response.
     pub trait BodyEncoding {
         /// Get content encoding
@@ -178,6 +218,23 @@ pub mod dev {
 
         /// Set content encoding
         fn encoding(&mut self, encoding: ContentEncoding) -> &mut Self;
+
+        /// Send body as is, without compression
+        ///
+        /// `Compress` middleware skips the response regardless of negotiated
+        /// encoding, i.e. for already compressed downloads or event streams.
+        fn no_compression(&mut self) -> &mut Self {
+            self.encoding(ContentEncoding::Identity)
+        }
+    }
+
+    /// Helper trait that allows to send already encoded response body.
+    pub trait BodyPreEncoding: BodyEncoding {
+        /// Set content encoding of already encoded body
+        ///
+        /// `Compress` middleware sets `Content-Encoding` header and sends body
+        /// as is, i.e. for pre-compressed static files.
+        fn pre_encoded(&mut self, encoding: ContentEncoding) -> &mut Self;
     }
 
     impl BodyEncoding for ResponseBuilder {
@@ -195,6 +252,14 @@ pub mod dev {
         }
     }
 
+    impl BodyPreEncoding for ResponseBuilder {
+        fn pre_encoded(&mut self, encoding: ContentEncoding) -> &mut Self {
+            self.extensions_mut().insert(Enc(encoding));
+            self.extensions_mut().insert(PreEncoded);
+            self
+        }
+    }
+
     impl<B> BodyEncoding for Response<B> {
         fn get_encoding(&self) -> Option<ContentEncoding> {
             if let Some(ref enc) = self.extensions().get::<Enc>() {
@@ -209,6 +274,14 @@ pub mod dev {
             self
         }
     }
+
+    impl<B> BodyPreEncoding for Response<B> {
+        fn pre_encoded(&mut self, encoding: ContentEncoding) -> &mut Self {
+            self.extensions_mut().insert(Enc(encoding));
+            self.extensions_mut().insert(PreEncoded);
+            self
+        }
+    }
 }
 
 pub mod client {
@@ -234,5 +307,6 @@ pub mod client {
     };
     pub use awc::{
         test, Client, ClientBuilder, ClientRequest, ClientResponse, Connector,
+        TypedRoute,
     };
 }
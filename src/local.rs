@@ -0,0 +1,322 @@
+use std::cell::RefCell;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::body::{BodySize, MessageBody};
+use actix_http::error::{Error, ErrorInternalServerError};
+use actix_http::http::{header, HeaderMap, Method, Uri};
+use actix_http::{HttpMessage, Request, Response};
+use bytes::Bytes;
+use futures::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::dev::Payload;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+/// Maximum depth of nested internal requests.
+const MAX_DEPTH: usize = 16;
+
+pub(crate) type Dispatch =
+    Box<dyn Fn(Request) -> LocalBoxFuture<'static, Result<Response, Error>>>;
+
+/// Depth of internal request, stored in request extensions.
+#[derive(Clone, Copy)]
+struct Depth(usize);
+
+/// Client that dispatches requests through the application's own service
+/// stack, without opening sockets.
+///
+/// Requests go through app data, middlewares, routing and response hooks
+/// the same way as requests received by the server. Client is available
+/// for every application, it is registered in application data on app
+/// start. It could be used for composition patterns like server side
+/// includes or internal batch endpoints.
+///
+/// ```rust
+/// use actix_web::web::{self, LocalClient};
+/// use actix_web::{App, Error, HttpResponse};
+///
+/// async fn page(client: LocalClient) -> Result<HttpResponse, Error> {
+///     let mut header = client.get("/fragments/header").await?;
+///     Ok(HttpResponse::Ok().streaming(header.take_body()))
+/// }
+///
+/// # fn main() {
+/// let app = App::new()
+///     .route("/", web::get().to(page))
+///     .route("/fragments/header", web::get().to(|| HttpResponse::Ok().body("<h1>")));
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct LocalClient {
+    dispatch: Rc<RefCell<Option<Dispatch>>>,
+}
+
+impl LocalClient {
+    pub(crate) fn set(&self, dispatch: Dispatch) {
+        *self.dispatch.borrow_mut() = Some(dispatch);
+    }
+
+    /// Client of the request's application.
+    pub(crate) fn from_app(req: &HttpRequest) -> LocalClient {
+        req.app_data::<LocalClient>().cloned().unwrap_or_default()
+    }
+
+    /// Dispatch request through the application.
+    ///
+    /// Request payload is passed to the application as is.
+    pub fn send(
+        &self,
+        req: Request,
+    ) -> LocalBoxFuture<'static, Result<Response, Error>> {
+        let dispatch = self.dispatch.clone();
+
+        async move {
+            let fut = match *dispatch.borrow() {
+                Some(ref dispatch) => dispatch(req),
+                None => {
+                    return Err(ErrorInternalServerError("Application is not running"))
+                }
+            };
+            fut.await
+        }
+        .boxed_local()
+    }
+
+    /// Dispatch `GET` request for the path.
+    pub fn get(&self, path: &str) -> LocalBoxFuture<'static, Result<Response, Error>> {
+        match request(Method::GET, path) {
+            Ok(req) => self.send(req),
+            Err(e) => err(e).boxed_local(),
+        }
+    }
+}
+
+/// Create request for internal dispatch, path could include query string.
+pub(crate) fn request(method: Method, path: &str) -> Result<Request, Error> {
+    let uri = path.parse::<Uri>().map_err(|_| {
+        ErrorInternalServerError(format!("Invalid internal request path: {}", path))
+    })?;
+
+    let mut req = Request::new();
+    req.head_mut().method = method;
+    req.head_mut().uri = uri;
+    Ok(req)
+}
+
+impl FromRequest for LocalClient {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(LocalClient::from_app(req))
+    }
+}
+
+impl HttpRequest {
+    /// Dispatch `GET` request for the path through the application and
+    /// return its response.
+    ///
+    /// Internal request inherits headers, http version and peer address of
+    /// the current request, it has empty payload. Nested internal requests
+    /// are limited to 16 levels, deeper requests fail with
+    /// *INTERNAL SERVER ERROR*.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, Error, HttpRequest, HttpResponse};
+    ///
+    /// async fn index(req: HttpRequest) -> Result<HttpResponse, Error> {
+    ///     let mut res = req.forward_internal("/fragment").await?;
+    ///     Ok(HttpResponse::Ok().streaming(res.take_body()))
+    /// }
+    /// # fn main() {}
+    /// ```
+    pub fn forward_internal(
+        &self,
+        path: &str,
+    ) -> LocalBoxFuture<'static, Result<Response, Error>> {
+        match nested(self, Method::GET, path) {
+            Ok(req) => LocalClient::from_app(self).send(req),
+            Err(e) => err(e).boxed_local(),
+        }
+    }
+}
+
+/// Create internal request that inherits headers, http version, peer address
+/// and nesting depth of the parent request.
+pub(crate) fn nested(
+    parent: &HttpRequest,
+    method: Method,
+    path: &str,
+) -> Result<Request, Error> {
+    let depth = parent
+        .extensions()
+        .get::<Depth>()
+        .map_or(0, |depth| depth.0)
+        + 1;
+    if depth > MAX_DEPTH {
+        return Err(ErrorInternalServerError(
+            "Internal request nesting is too deep",
+        ));
+    }
+
+    let mut req = request(method, path)?;
+    {
+        let head = req.head_mut();
+        head.version = parent.version();
+        head.peer_addr = parent.peer_addr();
+        for (name, value) in parent.headers().iter() {
+            if !is_payload_header(name) {
+                head.headers.append(name.clone(), value.clone());
+            }
+        }
+    }
+    req.extensions_mut().insert(Depth(depth));
+    Ok(req)
+}
+
+/// Headers that describe payload of original request.
+fn is_payload_header(name: &header::HeaderName) -> bool {
+    name == header::CONTENT_LENGTH
+        || name == header::CONTENT_TYPE
+        || name == header::CONTENT_ENCODING
+        || name == header::TRANSFER_ENCODING
+        || name == header::EXPECT
+}
+
+/// Application response body, boxed so it could be stored in `Body`.
+pub(crate) struct LocalBody<B>(pub(crate) Pin<Box<B>>);
+
+impl<B: MessageBody> MessageBody for LocalBody<B> {
+    fn size(&self) -> BodySize {
+        self.0.size()
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        self.0.as_mut().poll_next(cx)
+    }
+    fn take_trailers(mut self: Pin<&mut Self>) -> Option<HeaderMap> {
+        self.0.as_mut().take_trailers()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_service::Service;
+    use bytes::BytesMut;
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn test_local_client() {
+        let mut srv = init_service(
+            App::new()
+                .data(10usize)
+                .wrap_fn(|req, srv| {
+                    let fut = srv.call(req);
+                    async {
+                        let mut res = fut.await?;
+                        res.headers_mut().insert(
+                            header::HeaderName::from_static("x-test"),
+                            header::HeaderValue::from_static("1"),
+                        );
+                        Ok(res)
+                    }
+                })
+                .route(
+                    "/",
+                    web::get().to(|client: LocalClient| async move {
+                        let mut res = client.get("/inner?name=test").await?;
+                        assert_eq!(res.headers().get("x-test").unwrap(), "1");
+                        let body = read_response(&mut res).await;
+                        Ok::<_, Error>(HttpResponse::Ok().body(body))
+                    }),
+                )
+                .route(
+                    "/inner",
+                    web::get().to(|req: HttpRequest, data: web::Data<usize>| {
+                        HttpResponse::Ok().body(format!(
+                            "{}:{}",
+                            req.query_string(),
+                            data.get_ref()
+                        ))
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, Bytes::from_static(b"name=test:10"));
+    }
+
+    #[actix_rt::test]
+    async fn test_forward_internal() {
+        let mut srv = init_service(
+            App::new()
+                .route(
+                    "/",
+                    web::post().to(|req: HttpRequest| async move {
+                        let mut res = req.forward_internal("/inner").await?;
+                        let body = read_response(&mut res).await;
+                        Ok::<_, Error>(HttpResponse::Ok().body(body))
+                    }),
+                )
+                .route(
+                    "/inner",
+                    web::get().to(|req: HttpRequest| {
+                        HttpResponse::Ok().body(format!(
+                            "{}:{}",
+                            req.headers().get("x-user").unwrap().to_str().unwrap(),
+                            req.headers().contains_key(header::CONTENT_LENGTH)
+                        ))
+                    }),
+                )
+                .route(
+                    "/loop",
+                    web::get().to(|req: HttpRequest| req.forward_internal("/loop")),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/")
+            .header("x-user", "alice")
+            .set_payload("data")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(read_body(res).await, Bytes::from_static(b"alice:false"));
+
+        let req = TestRequest::with_uri("/loop").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[actix_rt::test]
+    async fn test_not_running() {
+        let req = TestRequest::default().to_http_request();
+        let res = req.forward_internal("/").await;
+        assert!(res.is_err());
+        assert!(req.extensions().get::<Depth>().is_none());
+    }
+
+    async fn read_response(res: &mut Response) -> Bytes {
+        let mut body = res.take_body();
+        let mut bytes = BytesMut::new();
+        while let Some(item) = body.next().await {
+            bytes.extend_from_slice(&item.unwrap());
+        }
+        bytes.freeze()
+    }
+}
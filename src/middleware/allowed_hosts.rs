@@ -0,0 +1,309 @@
+//! `Middleware` for rejecting requests to unknown hosts
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::error::{Error, ErrorBadRequest};
+use actix_http::http::header;
+use actix_service::{Service, Transform};
+use futures::future::{ok, Either, Ready};
+
+use crate::service::{ServiceRequest, ServiceResponse};
+
+/// `Middleware` for rejecting requests whose host is not in the allowlist.
+///
+/// Urls generated by `HttpRequest::url_for()` and absolute redirects use
+/// host of the request, so a forged `Host` or `X-Forwarded-Host` header
+/// could point generated links to another site or poison caches that key
+/// responses by path. Middleware checks `Host` header, authority of the
+/// request uri and host of the connection info, requests with a host that
+/// does not match any allowed host are rejected with `400 Bad Request`
+/// response before they reach routing.
+///
+/// Allowed host without a port matches any port of the host, host with a
+/// port matches only that port. Host starting with `*.` matches any
+/// subdomain of the domain, but not the domain itself, and `*` matches any
+/// host. Hosts are compared case-insensitively.
+///
+/// ```rust
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(middleware::AllowedHosts::new(vec![
+///         "example.com",
+///         "*.example.com",
+///         "localhost:8080",
+///     ]))
+///     .route("/", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AllowedHosts {
+    hosts: Rc<Vec<Pattern>>,
+}
+
+struct Pattern {
+    host: String,
+    wildcard: bool,
+    port: Option<u16>,
+}
+
+impl AllowedHosts {
+    /// Create middleware that allows requests to the hosts.
+    pub fn new<I, S>(hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        AllowedHosts {
+            hosts: Rc::new(Vec::new()),
+        }
+        .hosts(hosts)
+    }
+
+    /// Allow requests to the host.
+    ///
+    /// Panics if port of the host is not a valid port number.
+    pub fn host(mut self, host: &str) -> Self {
+        let (name, port) =
+            split_port(host.trim()).expect("Invalid port of allowed host");
+        let name = normalize(name);
+        let (host, wildcard) = if name.starts_with("*.") {
+            (name[1..].to_owned(), true)
+        } else {
+            (name, false)
+        };
+        Rc::get_mut(&mut self.hosts)
+            .expect("Multiple copies exist")
+            .push(Pattern {
+                host,
+                wildcard,
+                port,
+            });
+        self
+    }
+
+    /// Allow requests to the hosts.
+    pub fn hosts<I, S>(self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        hosts
+            .into_iter()
+            .fold(self, |this, host| this.host(host.as_ref()))
+    }
+}
+
+impl Pattern {
+    fn matches(&self, host: &str, port: Option<u16>) -> bool {
+        if self.port.is_some() && self.port != port {
+            return false;
+        }
+        if self.host == "*" {
+            true
+        } else if self.wildcard {
+            // `.example.com` suffix with a non-empty subdomain
+            host.len() > self.host.len() && host.ends_with(self.host.as_str())
+        } else {
+            host == self.host
+        }
+    }
+}
+
+/// Split host into name and port, ipv6 addresses are enclosed in brackets.
+///
+/// Returns `None` if port is empty or is not a valid port number.
+fn split_port(host: &str) -> Option<(&str, Option<u16>)> {
+    let start = if host.starts_with('[') {
+        host.find(']').unwrap_or(0)
+    } else {
+        0
+    };
+    match host[start..].rfind(':') {
+        Some(idx) => {
+            let port = host[start + idx + 1..].parse().ok()?;
+            Some((&host[..start + idx], Some(port)))
+        }
+        None => Some((host, None)),
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+impl<S, B> Transform<S> for AllowedHosts
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AllowedHostsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AllowedHostsMiddleware {
+            service,
+            hosts: self.hosts.clone(),
+        })
+    }
+}
+
+pub struct AllowedHostsMiddleware<S> {
+    service: S,
+    hosts: Rc<Vec<Pattern>>,
+}
+
+impl<S> AllowedHostsMiddleware<S> {
+    fn allows(&self, host: &str) -> bool {
+        let (name, port) = match split_port(host.trim()) {
+            Some(parts) => parts,
+            None => return false,
+        };
+        let name = normalize(name);
+        self.hosts.iter().any(|pat| pat.matches(&name, port))
+    }
+
+    fn check(&self, req: &ServiceRequest) -> Result<(), String> {
+        let head = req.head();
+        let host = match head.headers.get(header::HOST) {
+            Some(host) => Some(host.to_str().map_err(|_| String::new())?),
+            None => head.uri.authority().map(|auth| auth.as_str()),
+        };
+        if let Some(host) = host {
+            if !self.allows(host) {
+                return Err(host.to_owned());
+            }
+        }
+
+        // forwarded host is used by url generation
+        let info = req.connection_info();
+        if !self.allows(info.host()) {
+            return Err(info.host().to_owned());
+        }
+        Ok(())
+    }
+}
+
+impl<S, B> Service for AllowedHostsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if let Err(host) = self.check(&req) {
+            log::debug!("Rejected request to host {:?}", host);
+            return Either::Right(ok(
+                req.error_response(ErrorBadRequest("Host is not allowed"))
+            ));
+        }
+        Either::Left(self.service.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[test]
+    fn test_split_port() {
+        assert_eq!(split_port("example.com"), Some(("example.com", None)));
+        assert_eq!(
+            split_port("example.com:80"),
+            Some(("example.com", Some(80)))
+        );
+        assert_eq!(split_port("[::1]"), Some(("[::1]", None)));
+        assert_eq!(split_port("[::1]:8080"), Some(("[::1]", Some(8080))));
+        assert_eq!(split_port("example.com:"), None);
+        assert_eq!(split_port("example.com:abc"), None);
+        assert_eq!(split_port("example.com:65536"), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_allowed_hosts() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(AllowedHosts::new(vec![
+                    "Example.com",
+                    "*.example.org",
+                    "[::1]:8080",
+                ]))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let allowed = vec![
+            "example.com",
+            "EXAMPLE.com.",
+            "example.com:8443",
+            "api.example.org",
+            "a.b.example.org:80",
+            "[::1]:8080",
+        ];
+        for host in allowed {
+            let req = TestRequest::with_header("host", host).to_request();
+            let res = call_service(&mut srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK, "{}", host);
+        }
+
+        let rejected = vec![
+            "evil.com",
+            "example.com.evil.com",
+            "notexample.com",
+            "example.org",
+            "evilexample.org",
+            "[::1]",
+            "[::1]:80",
+            "example.com:",
+            "example.com:abc",
+        ];
+        for host in rejected {
+            let req = TestRequest::with_header("host", host).to_request();
+            let res = call_service(&mut srv, req).await;
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{}", host);
+        }
+
+        // forwarded host is checked as well
+        let req = TestRequest::with_header("host", "example.com")
+            .header("x-forwarded-host", "evil.com")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("http://api.example.org/").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_any_host() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(AllowedHosts::new(vec!["*:8080"]))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::with_header("host", "any.host:8080").to_request();
+        assert_eq!(call_service(&mut srv, req).await.status(), StatusCode::OK);
+        let req = TestRequest::with_header("host", "any.host").to_request();
+        assert_eq!(
+            call_service(&mut srv, req).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
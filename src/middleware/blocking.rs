@@ -0,0 +1,394 @@
+//! `Middleware` for detecting blocking handlers during development
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, Weak};
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actix_service::{Service, Transform};
+use futures::future::{ok, Either, Ready};
+use pin_project::pin_project;
+
+use crate::error::Error;
+use crate::service::{ServiceRequest, ServiceResponse};
+
+thread_local!(static SLOT: Arc<Slot> = Slot::start());
+
+/// Poll of a handler that blocked the worker thread.
+#[derive(Debug, Clone)]
+pub struct BlockedPoll {
+    /// Request method and route pattern, or path if route is not resolved.
+    pub route: String,
+    /// Name of the blocked worker thread.
+    pub thread: String,
+    /// Duration of the poll, so far if poll is not finished.
+    pub elapsed: Duration,
+    /// Poll is finished, otherwise worker thread is still blocked.
+    pub finished: bool,
+}
+
+/// `Middleware` for detecting handlers that block worker threads during
+/// development.
+///
+/// Each poll of the wrapped service is timed. Polls longer than `threshold`,
+/// typically caused by synchronous database calls or cpu heavy work, are
+/// logged with `warn` level along with the route pattern. Watchdog thread
+/// reports polls that are still running, so deadlocked and looping handlers
+/// are reported as well. Stack of the blocked thread is not captured, attach
+/// a debugger or profiler to the reported thread to inspect it.
+///
+/// Middleware is only active in debug builds, it has to be
+/// [`forced`](#method.force) to detect blocking in release builds.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(middleware::DetectBlocking::new(Duration::from_millis(10)))
+///     .route(
+///         "/users/{id}",
+///         web::get().to(|| async {
+///             // blocks the worker, reported as `GET /users/{id}`
+///             std::thread::sleep(Duration::from_millis(50));
+///             HttpResponse::Ok()
+///         }),
+///     );
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DetectBlocking {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    enabled: bool,
+    threshold: Duration,
+    report: Arc<dyn Fn(&BlockedPoll) + Send + Sync>,
+}
+
+impl DetectBlocking {
+    /// Construct `DetectBlocking` middleware that reports polls longer than
+    /// `threshold`.
+    pub fn new(threshold: Duration) -> Self {
+        DetectBlocking {
+            inner: Rc::new(Inner {
+                enabled: cfg!(debug_assertions),
+                threshold,
+                report: Arc::new(log_blocked),
+            }),
+        }
+    }
+
+    /// Detect blocking in release builds as well.
+    pub fn force(mut self) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .enabled = true;
+        self
+    }
+
+    /// Report blocked polls with the callback instead of logging them.
+    ///
+    /// Callback is called once per blocked poll, on the watchdog thread if
+    /// the poll is still running, on the worker thread otherwise.
+    pub fn on_blocked<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&BlockedPoll) + Send + Sync + 'static,
+    {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .report = Arc::new(f);
+        self
+    }
+}
+
+fn log_blocked(poll: &BlockedPoll) {
+    if poll.finished {
+        log::warn!(
+            "Handler of {} blocked worker thread {} for {:?}",
+            poll.route,
+            poll.thread,
+            poll.elapsed
+        );
+    } else {
+        log::warn!(
+            "Handler of {} is blocking worker thread {} for {:?}",
+            poll.route,
+            poll.thread,
+            poll.elapsed
+        );
+    }
+}
+
+impl<S, B> Transform<S> for DetectBlocking
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = DetectBlockingMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(DetectBlockingMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct DetectBlockingMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for DetectBlockingMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, DetectBlockingResponse<S::Future>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if !self.inner.enabled {
+            return Either::Left(self.service.call(req));
+        }
+
+        let route = format!(
+            "{} {}",
+            req.method(),
+            req.request()
+                .match_pattern()
+                .unwrap_or_else(|| req.path().to_owned())
+        );
+        let fut = self.service.call(req);
+        Either::Right(DetectBlockingResponse {
+            fut,
+            route: Arc::from(route),
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+#[doc(hidden)]
+#[pin_project]
+pub struct DetectBlockingResponse<F> {
+    #[pin]
+    fut: F,
+    route: Arc<str>,
+    inner: Rc<Inner>,
+}
+
+impl<F: Future> Future for DetectBlockingResponse<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        SLOT.with(|slot| {
+            // nested middlewares, outer one times the poll
+            if !slot.begin(this.route, this.inner) {
+                return this.fut.poll(cx);
+            }
+            let res = this.fut.poll(cx);
+            slot.end();
+            res
+        })
+    }
+}
+
+/// Poll running on a worker thread.
+struct Running {
+    route: Arc<str>,
+    started: Instant,
+    threshold: Duration,
+    report: Arc<dyn Fn(&BlockedPoll) + Send + Sync>,
+    reported: bool,
+}
+
+impl Running {
+    fn blocked(&self, thread: &str, finished: bool) -> BlockedPoll {
+        BlockedPoll {
+            route: self.route.to_string(),
+            thread: thread.to_owned(),
+            elapsed: self.started.elapsed(),
+            finished,
+        }
+    }
+}
+
+/// Poll of a worker thread, shared with its watchdog thread.
+struct Slot {
+    thread: String,
+    running: Mutex<Option<Running>>,
+    /// Watchdog is parked until a poll begins.
+    idle: AtomicBool,
+    watchdog: thread::Thread,
+}
+
+impl Slot {
+    fn start() -> Arc<Slot> {
+        let current = thread::current();
+        let name = current.name().unwrap_or("<unnamed>").to_owned();
+
+        let (tx, rx) = mpsc::channel();
+        let watchdog = thread::Builder::new()
+            .name(format!("{}:watchdog", name))
+            .spawn(move || {
+                if let Ok(slot) = rx.recv() {
+                    watch(slot)
+                }
+            })
+            .expect("Can not spawn watchdog thread");
+
+        let slot = Arc::new(Slot {
+            thread: name,
+            running: Mutex::new(None),
+            idle: AtomicBool::new(false),
+            watchdog: watchdog.thread().clone(),
+        });
+        let _ = tx.send(Arc::downgrade(&slot));
+        slot
+    }
+
+    fn begin(&self, route: &Arc<str>, inner: &Inner) -> bool {
+        let mut running = self.running.lock().unwrap();
+        if running.is_some() {
+            return false;
+        }
+        *running = Some(Running {
+            route: route.clone(),
+            started: Instant::now(),
+            threshold: inner.threshold,
+            report: inner.report.clone(),
+            reported: false,
+        });
+        if self.idle.swap(false, Ordering::AcqRel) {
+            self.watchdog.unpark();
+        }
+        true
+    }
+
+    fn end(&self) {
+        let running = self.running.lock().unwrap().take();
+        if let Some(running) = running {
+            if !running.reported && running.started.elapsed() > running.threshold {
+                (*running.report)(&running.blocked(&self.thread, true));
+            }
+        }
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        // parked watchdog exits once it can not upgrade the slot
+        self.watchdog.unpark();
+    }
+}
+
+/// Report polls of the worker thread that are still running.
+///
+/// Watchdog sleeps until threshold of the running poll expires and is
+/// parked while worker thread does not poll.
+fn watch(slot: Weak<Slot>) {
+    loop {
+        let slot = match slot.upgrade() {
+            Some(slot) => slot,
+            None => return,
+        };
+
+        let mut guard = slot.running.lock().unwrap();
+        let deadline = match *guard {
+            Some(ref mut running) if !running.reported => {
+                let deadline = running.started + running.threshold;
+                if Instant::now() > deadline {
+                    running.reported = true;
+                    let blocked = running.blocked(&slot.thread, false);
+                    let report = running.report.clone();
+                    drop(guard);
+                    (*report)(&blocked);
+                    continue;
+                }
+                Some(deadline)
+            }
+            _ => {
+                slot.idle.store(true, Ordering::Release);
+                None
+            }
+        };
+        drop(guard);
+        drop(slot);
+
+        match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                if deadline > now {
+                    thread::park_timeout(deadline - now);
+                }
+            }
+            None => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn test_detect_blocking() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reported = reports.clone();
+
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    DetectBlocking::new(Duration::from_millis(20))
+                        .force()
+                        .on_blocked(move |poll| {
+                            reported.lock().unwrap().push(poll.clone())
+                        }),
+                )
+                .route(
+                    "/block/{id}",
+                    web::get().to(|| async {
+                        thread::sleep(Duration::from_millis(100));
+                        HttpResponse::Ok()
+                    }),
+                )
+                .route("/", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(reports.lock().unwrap().is_empty());
+
+        let req = TestRequest::with_uri("/block/1").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // reported once, by watchdog while the handler is blocking
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].route, "GET /block/{id}");
+        assert!(!reports[0].finished);
+        assert!(reports[0].elapsed >= Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,210 @@
+//! `Middleware` for client hints advertisement
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::http::header::{AcceptCh, HeaderName, HeaderValue, IntoHeaderValue};
+use actix_service::{Service, Transform};
+use futures::future::{ok, Ready};
+use pin_project::pin_project;
+
+use crate::dev::VaryOn;
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::types::{ClientHint, ClientHintsUsed};
+use crate::Error;
+
+/// `Middleware` for advertising client hints.
+///
+/// Responses get `Accept-CH` header with the hints, unless handler set
+/// `Accept-CH` header itself. Clients send advertised hints with subsequent
+/// requests, handlers read them with
+/// [`web::ClientHints`](../web/struct.ClientHints.html) extractor.
+///
+/// Responses of handlers that extracted `web::ClientHints` vary on all
+/// advertised hints, so caches keep separate copies of device-adapted
+/// responses.
+///
+/// ```rust
+/// use actix_web::web::{self, ClientHint};
+/// use actix_web::{middleware, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(middleware::ClientHints::new(&[
+///         ClientHint::Dpr,
+///         ClientHint::Width,
+///         ClientHint::ViewportWidth,
+///     ]))
+///     .route("/", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ClientHints {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    headers: Vec<HeaderName>,
+    accept_ch: HeaderValue,
+}
+
+impl ClientHints {
+    /// Construct `ClientHints` middleware for the list of advertised hints.
+    pub fn new(hints: &[ClientHint]) -> Self {
+        let mut headers: Vec<HeaderName> = Vec::new();
+        for hint in hints {
+            let name = hint.header_name();
+            if !headers.contains(&name) {
+                headers.push(name);
+            }
+        }
+        let accept_ch = AcceptCh(headers.clone()).try_into().unwrap();
+
+        ClientHints {
+            inner: Rc::new(Inner { headers, accept_ch }),
+        }
+    }
+}
+
+impl<S, B> Transform<S> for ClientHints
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ClientHintsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ClientHintsMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct ClientHintsMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for ClientHintsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = ClientHintsResponse<S>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        ClientHintsResponse {
+            fut: self.service.call(req),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[pin_project]
+pub struct ClientHintsResponse<S: Service> {
+    #[pin]
+    fut: S::Future,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Future for ClientHintsResponse<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = Result<ServiceResponse<B>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        let mut res = futures::ready!(this.fut.poll(cx))?;
+        let name = HeaderName::from_static("accept-ch");
+        if !res.headers().contains_key(&name) {
+            res.headers_mut().insert(name, this.inner.accept_ch.clone());
+        }
+        if res.request().extensions().contains::<ClientHintsUsed>() {
+            let res = res.response_mut();
+            for name in &this.inner.headers {
+                res.vary_on(name.clone());
+            }
+        }
+        Poll::Ready(Ok(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::VARY;
+    use crate::test::{call_service, init_service, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn test_client_hints() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(ClientHints::new(&[
+                    ClientHint::Dpr,
+                    ClientHint::Width,
+                    ClientHint::Dpr,
+                ]))
+                .route(
+                    "/image",
+                    web::get().to(|hints: web::ClientHints| {
+                        HttpResponse::Ok().body(format!("{:?}", hints.width()))
+                    }),
+                )
+                .route(
+                    "/",
+                    web::get().to(|| HttpResponse::Ok().header(VARY, "cookie").finish()),
+                )
+                .route(
+                    "/custom",
+                    web::get().to(|| {
+                        HttpResponse::Ok().header("accept-ch", "save-data").finish()
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/image")
+            .header("sec-ch-width", "320")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(
+            resp.headers().get("accept-ch").unwrap(),
+            "sec-ch-dpr, sec-ch-width"
+        );
+        assert_eq!(
+            resp.headers().get(VARY).unwrap(),
+            "sec-ch-dpr, sec-ch-width"
+        );
+
+        // handlers that do not extract hints do not vary on them
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(
+            resp.headers().get("accept-ch").unwrap(),
+            "sec-ch-dpr, sec-ch-width"
+        );
+        assert_eq!(resp.headers().get(VARY).unwrap(), "cookie");
+
+        let req = TestRequest::with_uri("/custom").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get("accept-ch").unwrap(), "save-data");
+    }
+}
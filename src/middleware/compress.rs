@@ -1,27 +1,50 @@
 //! `Middleware` for compressing response body.
-use std::cmp;
 use std::future::Future;
 use std::marker::PhantomData;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::str::FromStr;
 use std::task::{Context, Poll};
+use std::{fmt, ops};
 
-use actix_http::body::MessageBody;
-use actix_http::encoding::Encoder;
-use actix_http::http::header::{ContentEncoding, ACCEPT_ENCODING};
-use actix_http::Error;
+use actix_http::body::{BodySize, MessageBody};
+use actix_http::encoding::{CompressionLevels, Encoder, Variants};
+#[cfg(feature = "compress-dictionary")]
+use actix_http::encoding::{Dictionary, DCB};
+#[cfg(feature = "compress-dictionary")]
+use actix_http::http::header::HeaderName;
+use actix_http::http::header::{
+    ContentEncoding, HeaderValue, ACCEPT_ENCODING, AUTHORIZATION, CACHE_CONTROL,
+    CONTENT_ENCODING, COOKIE, SET_COOKIE, VARY,
+};
+use actix_http::http::{HeaderMap, Method, StatusCode};
+use actix_http::{Error, HttpMessage, Payload};
 use actix_service::{Service, Transform};
-use futures::future::{ok, Ready};
+use futures::future::{err, ok, Ready};
 use pin_project::pin_project;
 
-use crate::dev::BodyEncoding;
+use crate::dev::{BodyEncoding, PreEncoded, VaryOn};
+use crate::error::ErrorInternalServerError;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
 use crate::service::{ServiceRequest, ServiceResponse};
 
 #[derive(Debug, Clone)]
 /// `Middleware` for compressing response body.
 ///
+/// Encoding is negotiated with `Accept-Encoding` request header, quality
+/// values are respected and `identity` is considered acceptable unless it is
+/// excluded with `identity;q=0` or `*;q=0`. Negotiated encoding is available
+/// to handlers with `NegotiatedEncoding` extractor. Once the response is
+/// produced, request extension is updated with the encoding that is actually
+/// applied, after `min_size()` and `BodyEncoding` overrides are taken
+/// into account. With `compress-zstd` feature, `zstd` encoding is negotiated
+/// as well.
+///
 /// Use `BodyEncoding` trait for overriding response compression.
-/// To disable compression set encoding to `ContentEncoding::Identity` value.
+/// To disable compression of a response use `BodyEncoding::no_compression()`.
+/// Responses with bodies encoded by handler are marked with
+/// `BodyPreEncoding::pre_encoded()`.
 ///
 /// ```rust
 /// use actix_web::{web, middleware, App, HttpResponse};
@@ -36,12 +59,165 @@ use crate::service::{ServiceRequest, ServiceResponse};
 ///         );
 /// }
 /// ```
-pub struct Compress(ContentEncoding);
+///
+/// With `compress-dictionary` feature, responses could be compressed with
+/// preregistered shared dictionaries. Client that has a dictionary
+/// advertises it with `Available-Dictionary` header and `dcb` encoding in
+/// `Accept-Encoding` header, response is compressed with brotli using the
+/// dictionary, unless quality of `dcb` is lower than quality of negotiated
+/// encoding. Dictionaries are usually previous versions of versioned
+/// resources, client gets them with `Use-As-Dictionary` response header.
+///
+/// Compression levels, minimum size of compressed bodies and preference of
+/// encodings are configurable.
+///
+/// ```rust
+/// use actix_web::http::ContentEncoding;
+/// use actix_web::middleware::Compress;
+///
+/// let compress = Compress::default()
+///     .level(ContentEncoding::Gzip, 6)
+///     .level(ContentEncoding::Br, 5)
+///     .min_size(1024)
+///     .preference(&[ContentEncoding::Gzip, ContentEncoding::Br]);
+/// ```
+pub struct Compress {
+    encoding: ContentEncoding,
+    levels: CompressionLevels,
+    min_size: u64,
+    preference: Rc<Vec<ContentEncoding>>,
+    #[cfg(feature = "compress-dictionary")]
+    dictionaries: Rc<Vec<Dictionary>>,
+    prime: Option<Prime>,
+}
+
+/// Maximum size of response body with primed variants.
+const PRIME_LIMIT: usize = 1_048_576;
+
+#[derive(Clone)]
+struct Prime {
+    encodings: Rc<Vec<ContentEncoding>>,
+    store: Rc<dyn Fn(PrimedResponse)>,
+}
+
+impl fmt::Debug for Prime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prime")
+            .field("encodings", &self.encodings)
+            .finish()
+    }
+}
+
+/// Encoded variants of a cacheable response, produced by `Compress::prime()`.
+#[derive(Debug, Clone)]
+pub struct PrimedResponse {
+    /// Path and query of the request
+    pub path: String,
+    /// Response status
+    pub status: StatusCode,
+    /// Response headers, without `Content-Encoding` header
+    pub headers: HeaderMap,
+    /// Encoded response bodies
+    pub variants: Variants,
+}
 
 impl Compress {
     /// Create new `Compress` middleware with default encoding.
     pub fn new(encoding: ContentEncoding) -> Self {
-        Compress(encoding)
+        Compress {
+            encoding,
+            levels: CompressionLevels::default(),
+            min_size: 0,
+            preference: Rc::new(vec![
+                ContentEncoding::Br,
+                #[cfg(feature = "compress-zstd")]
+                ContentEncoding::Zstd,
+                ContentEncoding::Gzip,
+                ContentEncoding::Deflate,
+            ]),
+            #[cfg(feature = "compress-dictionary")]
+            dictionaries: Rc::new(Vec::new()),
+            prime: None,
+        }
+    }
+
+    /// Set compression level of the `encoding`.
+    ///
+    /// By default fast levels are used, see `CompressionLevels`.
+    pub fn level(mut self, encoding: ContentEncoding, level: u32) -> Self {
+        self.levels = self.levels.level(encoding, level);
+        self
+    }
+
+    /// Do not compress response bodies smaller than `size` bytes.
+    ///
+    /// Streaming bodies of unknown size are compressed. Encoding set by
+    /// handler with `BodyEncoding` is not affected.
+    pub fn min_size(mut self, size: u64) -> Self {
+        self.min_size = size;
+        self
+    }
+
+    /// Set encodings negotiated by `ContentEncoding::Auto`, in order of
+    /// preference.
+    ///
+    /// If client accepts several encodings with equal quality, the first
+    /// listed encoding is used. By default `br` is preferred, then `zstd`
+    /// with `compress-zstd` feature, `gzip` and `deflate`.
+    pub fn preference(mut self, encodings: &[ContentEncoding]) -> Self {
+        self.preference = Rc::new(
+            encodings
+                .iter()
+                .copied()
+                .filter(|enc| enc.is_compression())
+                .collect(),
+        );
+        self
+    }
+
+    /// Produce all `encodings` of cacheable responses in one pass and pass
+    /// them to the `store`, i.e. a response cache.
+    ///
+    /// Response body is encoded with negotiated encoding for the client and,
+    /// at the same time, with every listed encoding, so the first request
+    /// primes all variants. Successful responses to `GET` requests, that are
+    /// not marked with `no-store` or `private` cache directives, are primed.
+    /// Responses larger than 1Mb, with encoding set by handler, with
+    /// `Set-Cookie` or `Vary: *` headers, and responses to requests with
+    /// `Authorization` or `Cookie` headers are not primed.
+    ///
+    /// ```rust
+    /// use actix_web::http::ContentEncoding;
+    /// use actix_web::middleware::Compress;
+    ///
+    /// let compress = Compress::default().prime(
+    ///     &[ContentEncoding::Gzip, ContentEncoding::Br],
+    ///     |res| println!("{}: {} variants", res.path, res.variants.iter().count()),
+    /// );
+    /// ```
+    pub fn prime<F>(mut self, encodings: &[ContentEncoding], store: F) -> Self
+    where
+        F: Fn(PrimedResponse) + 'static,
+    {
+        self.prime = Some(Prime {
+            encodings: Rc::new(encodings.to_vec()),
+            store: Rc::new(store),
+        });
+        self
+    }
+
+    /// Register shared dictionary.
+    ///
+    /// ```rust
+    /// use actix_web::middleware::{Compress, Dictionary};
+    ///
+    /// let compress = Compress::default()
+    ///     .dictionary(Dictionary::new(&b"function app() { /* v1 */ }"[..]).id("app-v1"));
+    /// ```
+    #[cfg(feature = "compress-dictionary")]
+    pub fn dictionary(mut self, dict: Dictionary) -> Self {
+        Rc::make_mut(&mut self.dictionaries).push(dict);
+        self
     }
 }
 
@@ -66,7 +242,13 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ok(CompressMiddleware {
             service,
-            encoding: self.0,
+            encoding: self.encoding,
+            levels: self.levels,
+            min_size: self.min_size,
+            preference: self.preference.clone(),
+            #[cfg(feature = "compress-dictionary")]
+            dictionaries: self.dictionaries.clone(),
+            prime: self.prime.clone(),
         })
     }
 }
@@ -74,6 +256,12 @@ where
 pub struct CompressMiddleware<S> {
     service: S,
     encoding: ContentEncoding,
+    levels: CompressionLevels,
+    min_size: u64,
+    preference: Rc<Vec<ContentEncoding>>,
+    #[cfg(feature = "compress-dictionary")]
+    dictionaries: Rc<Vec<Dictionary>>,
+    prime: Option<Prime>,
 }
 
 impl<S, B> Service for CompressMiddleware<S>
@@ -92,24 +280,87 @@ where
 
     fn call(&mut self, req: ServiceRequest) -> Self::Future {
         // negotiate content-encoding
-        let encoding = if let Some(val) = req.headers().get(&ACCEPT_ENCODING) {
-            if let Ok(enc) = val.to_str() {
-                AcceptEncoding::parse(enc, self.encoding)
-            } else {
-                ContentEncoding::Identity
+        let accept = req
+            .headers()
+            .get(&ACCEPT_ENCODING)
+            .map(|val| AcceptEncoding::parse(val.to_str().unwrap_or("")));
+        let encoding = match accept {
+            Some(ref accept) => accept.negotiate(self.encoding, &self.preference),
+            None => ContentEncoding::Identity,
+        };
+        req.extensions_mut().insert(NegotiatedEncoding(encoding));
+
+        // dictionary compression is used only if client prefers it at least
+        // as much as negotiated encoding, including explicit `identity`
+        #[cfg(feature = "compress-dictionary")]
+        let dictionary = match accept {
+            Some(ref accept)
+                if self.encoding != ContentEncoding::Identity
+                    && accept.quality(DCB) > 0.0
+                    && accept.quality(DCB) >= accept.quality(encoding.as_str()) =>
+            {
+                select_dictionary(&req, &self.dictionaries)
             }
-        } else {
-            ContentEncoding::Identity
+            _ => None,
+        };
+
+        let prime = match self.prime {
+            Some(ref prime) if req.method() == Method::GET => {
+                let path = req
+                    .uri()
+                    .path_and_query()
+                    .map(|pq| pq.as_str())
+                    .unwrap_or_else(|| req.path());
+                Some((prime.clone(), path.to_owned()))
+            }
+            _ => None,
         };
 
         CompressResponse {
             encoding,
+            levels: self.levels,
+            min_size: self.min_size,
+            #[cfg(feature = "compress-dictionary")]
+            dictionary,
+            prime,
             fut: self.service.call(req),
             _t: PhantomData,
         }
     }
 }
 
+/// Find registered dictionary advertised by client.
+#[cfg(feature = "compress-dictionary")]
+fn select_dictionary(req: &ServiceRequest, dicts: &[Dictionary]) -> Option<Dictionary> {
+    if dicts.is_empty() {
+        return None;
+    }
+
+    // structured field byte sequence, `:<base64 sha-256>:`
+    let hash = req
+        .headers()
+        .get("available-dictionary")
+        .and_then(|val| val.to_str().ok())
+        .map(|val| val.trim())
+        .filter(|val| val.len() > 2 && val.starts_with(':') && val.ends_with(':'))
+        .and_then(|val| base64::decode(&val[1..val.len() - 1]).ok())?;
+
+    // structured field string, `"<id>"`
+    let id = req
+        .headers()
+        .get("dictionary-id")
+        .and_then(|val| val.to_str().ok())
+        .map(|val| val.trim().trim_matches('"'));
+
+    dicts
+        .iter()
+        .find(|dict| {
+            &dict.hash()[..] == hash.as_slice()
+                && (dict.get_id().is_none() || dict.get_id() == id)
+        })
+        .cloned()
+}
+
 #[doc(hidden)]
 #[pin_project]
 pub struct CompressResponse<S, B>
@@ -120,6 +371,11 @@ where
     #[pin]
     fut: S::Future,
     encoding: ContentEncoding,
+    levels: CompressionLevels,
+    min_size: u64,
+    #[cfg(feature = "compress-dictionary")]
+    dictionary: Option<Dictionary>,
+    prime: Option<(Prime, String)>,
     _t: PhantomData<B>,
 }
 
@@ -134,89 +390,715 @@ where
         let this = self.project();
 
         match futures::ready!(this.fut.poll(cx)) {
-            Ok(resp) => {
+            Ok(mut resp) => {
                 let enc = if let Some(enc) = resp.response().get_encoding() {
+                    resp.request()
+                        .extensions_mut()
+                        .insert(NegotiatedEncoding(enc));
+                    if resp.response().extensions().contains::<PreEncoded>() {
+                        // body is encoded by handler
+                        return Poll::Ready(Ok(resp.map_body(move |head, body| {
+                            if enc.is_compression() {
+                                head.headers_mut().insert(
+                                    CONTENT_ENCODING,
+                                    HeaderValue::from_static(enc.as_str()),
+                                );
+                            }
+                            Encoder::response(ContentEncoding::Identity, head, body)
+                        })));
+                    }
                     enc
                 } else {
-                    *this.encoding
+                    resp.response_mut().vary_on(ACCEPT_ENCODING);
+
+                    let small = match resp.response().body().size() {
+                        BodySize::Sized(size) => (size as u64) < *this.min_size,
+                        BodySize::Sized64(size) => size < *this.min_size,
+                        _ => false,
+                    };
+
+                    #[cfg(feature = "compress-dictionary")]
+                    {
+                        if let Some(dict) = this.dictionary.take().filter(|_| !small) {
+                            return Poll::Ready(Ok(resp.map_body(move |head, body| {
+                                let body = Encoder::dictionary_response(&dict, head, body);
+                                if head.headers().get(&CONTENT_ENCODING)
+                                    == Some(&HeaderValue::from_static(DCB))
+                                {
+                                    head.extensions_mut().vary_on(
+                                        HeaderName::from_static("available-dictionary"),
+                                    );
+                                }
+                                body
+                            })));
+                        }
+                    }
+                    let enc = if small {
+                        ContentEncoding::Identity
+                    } else {
+                        *this.encoding
+                    };
+                    resp.request()
+                        .extensions_mut()
+                        .insert(NegotiatedEncoding(enc));
+                    if let Some((prime, path)) = this.prime.take() {
+                        if is_cacheable(
+                            resp.request().headers(),
+                            resp.response().status(),
+                            resp.headers(),
+                        ) {
+                            let levels = *this.levels;
+                            return Poll::Ready(Ok(resp.map_body(move |head, body| {
+                                let status = head.status;
+                                let headers = head.headers().clone();
+                                let store = prime.store;
+                                Encoder::response_variants(
+                                    enc,
+                                    &prime.encodings,
+                                    &levels,
+                                    PRIME_LIMIT,
+                                    head,
+                                    body,
+                                    move |variants| {
+                                        (*store)(PrimedResponse {
+                                            path,
+                                            status,
+                                            headers,
+                                            variants,
+                                        })
+                                    },
+                                )
+                            })));
+                        }
+                    }
+                    enc
                 };
 
-                Poll::Ready(Ok(
-                    resp.map_body(move |head, body| Encoder::response(enc, head, body))
-                ))
+                let levels = *this.levels;
+                Poll::Ready(Ok(resp.map_body(move |head, body| {
+                    Encoder::response_with_levels(enc, &levels, head, body)
+                })))
             }
             Err(e) => Poll::Ready(Err(e)),
         }
     }
 }
 
-struct AcceptEncoding {
-    encoding: ContentEncoding,
-    quality: f64,
+/// Response could be stored by shared cache.
+///
+/// Responses to requests with credentials and responses setting cookies
+/// are private to the client.
+fn is_cacheable(req: &HeaderMap, status: StatusCode, headers: &HeaderMap) -> bool {
+    if status != StatusCode::OK
+        || req.contains_key(&AUTHORIZATION)
+        || req.contains_key(&COOKIE)
+        || headers.contains_key(&SET_COOKIE)
+    {
+        return false;
+    }
+    for value in headers.get_all(&VARY) {
+        let any = value
+            .to_str()
+            .map(|value| value.split(',').any(|name| name.trim() == "*"))
+            .unwrap_or(true);
+        if any {
+            return false;
+        }
+    }
+    for value in headers.get_all(&CACHE_CONTROL) {
+        if let Ok(value) = value.to_str() {
+            let private = value.split(',').any(|directive| {
+                let directive = directive.trim().to_ascii_lowercase();
+                directive == "no-store" || directive.starts_with("private")
+            });
+            if private {
+                return false;
+            }
+        }
+    }
+    true
 }
 
-impl Eq for AcceptEncoding {}
+/// Content encoding negotiated by `Compress` middleware.
+///
+/// Handlers get encoding negotiated from `Accept-Encoding` header. After
+/// the response is produced, value stored in request extensions is replaced
+/// with the encoding chosen for the response body.
+///
+/// ```rust
+/// use actix_web::middleware::NegotiatedEncoding;
+///
+/// async fn index(encoding: NegotiatedEncoding) -> String {
+///     format!("Response is encoded with {}", encoding.as_str())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NegotiatedEncoding(ContentEncoding);
 
-impl Ord for AcceptEncoding {
-    #[allow(clippy::comparison_chain)]
-    fn cmp(&self, other: &AcceptEncoding) -> cmp::Ordering {
-        if self.quality > other.quality {
-            cmp::Ordering::Less
-        } else if self.quality < other.quality {
-            cmp::Ordering::Greater
-        } else {
-            cmp::Ordering::Equal
-        }
+impl NegotiatedEncoding {
+    /// Deconstruct to the inner value
+    pub fn into_inner(self) -> ContentEncoding {
+        self.0
     }
 }
 
-impl PartialOrd for AcceptEncoding {
-    fn partial_cmp(&self, other: &AcceptEncoding) -> Option<cmp::Ordering> {
-        Some(self.cmp(other))
+impl ops::Deref for NegotiatedEncoding {
+    type Target = ContentEncoding;
+
+    fn deref(&self) -> &ContentEncoding {
+        &self.0
     }
 }
 
-impl PartialEq for AcceptEncoding {
-    fn eq(&self, other: &AcceptEncoding) -> bool {
-        self.quality == other.quality
+impl fmt::Display for NegotiatedEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.as_str())
     }
 }
 
-impl AcceptEncoding {
-    fn new(tag: &str) -> Option<AcceptEncoding> {
-        let parts: Vec<&str> = tag.split(';').collect();
-        let encoding = match parts.len() {
-            0 => return None,
-            _ => ContentEncoding::from(parts[0]),
-        };
-        let quality = match parts.len() {
-            1 => encoding.quality(),
-            _ => match f64::from_str(parts[1]) {
-                Ok(q) => q,
-                Err(_) => 0.0,
-            },
-        };
-        Some(AcceptEncoding { encoding, quality })
+impl FromRequest for NegotiatedEncoding {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<NegotiatedEncoding, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(enc) = req.extensions().get::<NegotiatedEncoding>() {
+            ok(*enc)
+        } else {
+            log::debug!(
+                "Failed to construct NegotiatedEncoding extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            err(ErrorInternalServerError(
+                "Compression is not configured, to configure use \
+                 App::wrap(middleware::Compress::default())",
+            ))
+        }
     }
+}
+
+/// Parsed `Accept-Encoding` header.
+struct AcceptEncoding {
+    items: Vec<(String, f64)>,
+}
 
-    /// Parse a raw Accept-Encoding header value into an ordered list.
-    pub fn parse(raw: &str, encoding: ContentEncoding) -> ContentEncoding {
-        let mut encodings: Vec<_> = raw
-            .replace(' ', "")
+impl AcceptEncoding {
+    /// Parse a raw Accept-Encoding header value.
+    fn parse(raw: &str) -> AcceptEncoding {
+        let items = raw
             .split(',')
-            .map(|l| AcceptEncoding::new(l))
-            .collect();
-        encodings.sort();
-
-        for enc in encodings {
-            if let Some(enc) = enc {
-                if encoding == ContentEncoding::Auto {
-                    return enc.encoding;
-                } else if encoding == enc.encoding {
-                    return encoding;
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let coding = parts.next()?.trim().to_ascii_lowercase();
+                if coding.is_empty() {
+                    return None;
+                }
+
+                let mut quality = 1.0;
+                for param in parts.map(|p| p.trim()) {
+                    if param.starts_with("q=") || param.starts_with("Q=") {
+                        // coding with invalid quality is not acceptable
+                        quality = f64::from_str(param[2..].trim())
+                            .ok()
+                            .filter(|q| *q >= 0.0 && *q <= 1.0)
+                            .unwrap_or(0.0);
+                    }
                 }
+                Some((coding, quality))
+            })
+            .collect();
+
+        AcceptEncoding { items }
+    }
+
+    /// Quality of content coding, `0` if coding is not acceptable.
+    fn quality(&self, coding: &str) -> f64 {
+        self.find(coding).unwrap_or(0.0)
+    }
+
+    fn find(&self, coding: &str) -> Option<f64> {
+        self.items
+            .iter()
+            .find(|(c, _)| c == coding)
+            .or_else(|| self.items.iter().find(|(c, _)| c == "*"))
+            .map(|(_, q)| *q)
+    }
+
+    /// Select content encoding.
+    ///
+    /// Encoding with higher quality wins, on equal quality compression is
+    /// preferred and `Auto` encodings are preferred in order of `preference`.
+    /// Unless listed, `identity` is used only if no other encoding is
+    /// acceptable.
+    fn negotiate(
+        &self,
+        encoding: ContentEncoding,
+        preference: &[ContentEncoding],
+    ) -> ContentEncoding {
+        let candidates = match encoding {
+            ContentEncoding::Auto => preference.to_vec(),
+            ContentEncoding::Identity => Vec::new(),
+            enc => vec![enc],
+        };
+
+        let mut selected = ContentEncoding::Identity;
+        let mut quality = self.quality("identity");
+        for enc in candidates {
+            let q = self.quality(enc.as_str());
+            if q > 0.0
+                && (q > quality || (q == quality && selected == ContentEncoding::Identity))
+            {
+                selected = enc;
+                quality = q;
             }
         }
-        ContentEncoding::Identity
+
+        // if no coding is acceptable, even if identity is excluded,
+        // response is sent without content coding
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dev::BodyPreEncoding;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[cfg(feature = "compress-dictionary")]
+    const CONTENT: &str = "function app() { return 'actix-web dictionary'; }\n";
+
+    #[cfg(feature = "compress-dictionary")]
+    fn available(dict: &Dictionary) -> String {
+        format!(":{}:", base64::encode(dict.hash()))
+    }
+
+    fn negotiate(raw: &str) -> ContentEncoding {
+        AcceptEncoding::parse(raw)
+            .negotiate(ContentEncoding::Auto, &Compress::default().preference)
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate("gzip, deflate, br"), ContentEncoding::Br);
+        assert_eq!(negotiate("gzip, deflate"), ContentEncoding::Gzip);
+        assert_eq!(negotiate("br;q=0.5, gzip;q=0.8"), ContentEncoding::Gzip);
+        assert_eq!(negotiate("br;Q=0.5, gzip;q=0.8"), ContentEncoding::Gzip);
+        assert_eq!(negotiate("gzip;q=0.5, identity"), ContentEncoding::Identity);
+        assert_eq!(negotiate("gzip;q=0, identity;q=0.1"), ContentEncoding::Identity);
+        assert_eq!(negotiate("deflate;q=0.1, identity;q=0"), ContentEncoding::Deflate);
+        assert_eq!(negotiate("*"), ContentEncoding::Br);
+        #[cfg(not(feature = "compress-zstd"))]
+        assert_eq!(negotiate("br;q=0, *;q=0.5"), ContentEncoding::Gzip);
+        #[cfg(feature = "compress-zstd")]
+        assert_eq!(negotiate("br;q=0, *;q=0.5"), ContentEncoding::Zstd);
+        assert_eq!(negotiate("*;q=0"), ContentEncoding::Identity);
+        assert_eq!(negotiate("gzip;q=2, deflate;q=abc"), ContentEncoding::Identity);
+        assert_eq!(negotiate(""), ContentEncoding::Identity);
+        assert_eq!(negotiate("unknown"), ContentEncoding::Identity);
+        #[cfg(feature = "compress-zstd")]
+        assert_eq!(negotiate("gzip, zstd"), ContentEncoding::Zstd);
+        #[cfg(not(feature = "compress-zstd"))]
+        assert_eq!(negotiate("gzip, zstd"), ContentEncoding::Gzip);
+
+        let accept = AcceptEncoding::parse("br, gzip;q=0.8");
+        assert_eq!(
+            accept.negotiate(ContentEncoding::Gzip, &[]),
+            ContentEncoding::Gzip
+        );
+        assert_eq!(
+            accept.negotiate(ContentEncoding::Deflate, &[]),
+            ContentEncoding::Identity
+        );
+        assert_eq!(
+            accept.negotiate(ContentEncoding::Identity, &[]),
+            ContentEncoding::Identity
+        );
+
+        // preference order
+        let preference = Compress::default()
+            .preference(&[
+                ContentEncoding::Gzip,
+                ContentEncoding::Identity,
+                ContentEncoding::Br,
+            ])
+            .preference;
+        assert_eq!(
+            *preference,
+            vec![ContentEncoding::Gzip, ContentEncoding::Br]
+        );
+        let accept = AcceptEncoding::parse("br, gzip, deflate");
+        assert_eq!(
+            accept.negotiate(ContentEncoding::Auto, &preference),
+            ContentEncoding::Gzip
+        );
+        let accept = AcceptEncoding::parse("br, gzip;q=0.5, deflate");
+        assert_eq!(
+            accept.negotiate(ContentEncoding::Auto, &preference),
+            ContentEncoding::Br
+        );
+        let accept = AcceptEncoding::parse("deflate");
+        assert_eq!(
+            accept.negotiate(ContentEncoding::Auto, &preference),
+            ContentEncoding::Identity
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_level_and_min_size() {
+        use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+        use std::io::{Read, Write};
+
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    Compress::default()
+                        .level(ContentEncoding::Gzip, 9)
+                        .min_size(1024),
+                )
+                .route(
+                    "/small",
+                    web::get().to(|| HttpResponse::Ok().body("s".repeat(1023))),
+                )
+                .route(
+                    "/exact",
+                    web::get().to(|| HttpResponse::Ok().body("e".repeat(1024))),
+                )
+                .route(
+                    "/large",
+                    web::get().to(|| HttpResponse::Ok().body("large ".repeat(1024))),
+                ),
+        )
+        .await;
+
+        // bodies smaller than min size are not compressed
+        let req = TestRequest::with_uri("/small")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(!resp.headers().contains_key(CONTENT_ENCODING));
+        assert_eq!(resp.headers().get(VARY).unwrap(), "accept-encoding");
+        assert_eq!(
+            resp.request().extensions().get::<NegotiatedEncoding>(),
+            Some(&NegotiatedEncoding(ContentEncoding::Identity))
+        );
+        assert_eq!(read_body(resp).await, "s".repeat(1023));
+
+        let req = TestRequest::with_uri("/exact")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+
+        let req = TestRequest::with_uri("/large")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(
+            resp.request().extensions().get::<NegotiatedEncoding>(),
+            Some(&NegotiatedEncoding(ContentEncoding::Gzip))
+        );
+        let body = read_body(resp).await;
+
+        // body is encoded with the configured level, not the default one
+        let encode = |level| {
+            let mut enc = GzEncoder::new(Vec::new(), Compression::new(level));
+            enc.write_all("large ".repeat(1024).as_bytes()).unwrap();
+            enc.finish().unwrap()
+        };
+        assert_eq!(body, encode(9));
+        assert_ne!(body, encode(1));
+        // extra flags of gzip header, 2 is the maximum compression
+        assert_eq!(body[8], 2);
+
+        let mut decoded = String::new();
+        GzDecoder::new(&body[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "large ".repeat(1024));
+    }
+
+    #[actix_rt::test]
+    async fn test_negotiated_encoding() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(Compress::default())
+                .route(
+                    "/",
+                    web::get().to(|enc: NegotiatedEncoding| {
+                        HttpResponse::Ok().body(enc.to_string())
+                    }),
+                )
+                .route(
+                    "/pre",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .pre_encoded(ContentEncoding::Gzip)
+                            .body("gzipped")
+                    }),
+                )
+                .route(
+                    "/events",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .content_type("text/event-stream")
+                            .no_compression()
+                            .body("data: event")
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .header(ACCEPT_ENCODING, "gzip, identity;q=0")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+
+        let req = TestRequest::default()
+            .header(ACCEPT_ENCODING, "gzip;q=0.5, identity")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        assert_eq!(resp.headers().get(VARY).unwrap(), "accept-encoding");
+        assert_eq!(read_body(resp).await, "identity");
+
+        let req = TestRequest::with_uri("/pre")
+            .header(ACCEPT_ENCODING, "gzip, br")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+        assert!(resp.headers().get(VARY).is_none());
+        assert_eq!(read_body(resp).await, "gzipped");
+
+        let req = TestRequest::with_uri("/events")
+            .header(ACCEPT_ENCODING, "gzip, br")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        assert!(resp.headers().get(VARY).is_none());
+        assert_eq!(
+            resp.request().extensions().get::<NegotiatedEncoding>(),
+            Some(&NegotiatedEncoding(ContentEncoding::Identity))
+        );
+        assert_eq!(read_body(resp).await, "data: event");
+
+        // extractor requires middleware
+        let mut srv = init_service(App::new().route(
+            "/",
+            web::get().to(|_: NegotiatedEncoding| HttpResponse::Ok()),
+        ))
+        .await;
+        let resp = call_service(&mut srv, TestRequest::default().to_request()).await;
+        assert_eq!(
+            resp.status(),
+            crate::http::StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_prime() {
+        use std::cell::RefCell;
+        use std::io::Read;
+
+        let primed = Rc::new(RefCell::new(Vec::new()));
+        let store = primed.clone();
+        let content = "actix-web primed variants ".repeat(100);
+        let body = content.clone();
+        let mut srv =
+            init_service(
+                App::new()
+                    .wrap(Compress::default().prime(
+                        &[ContentEncoding::Gzip, ContentEncoding::Br],
+                        move |res| store.borrow_mut().push(res),
+                    ))
+                    .route(
+                        "/",
+                        web::get().to(move || {
+                            let chunk = ok::<_, Error>(bytes::Bytes::from(body.clone()));
+                            HttpResponse::Ok().streaming(futures::stream::once(chunk))
+                        }),
+                    )
+                    .route(
+                        "/private",
+                        web::get().to(|| {
+                            HttpResponse::Ok()
+                                .header(CACHE_CONTROL, "max-age=60, private")
+                                .body("private")
+                        }),
+                    )
+                    .route(
+                        "/cookie",
+                        web::get().to(|| {
+                            HttpResponse::Ok()
+                                .header(SET_COOKIE, "session=secret")
+                                .body("cookie")
+                        }),
+                    )
+                    .route(
+                        "/vary",
+                        web::get().to(|| {
+                            HttpResponse::Ok().header(VARY, "*").body("vary")
+                        }),
+                    ),
+            )
+            .await;
+
+        let req = TestRequest::with_uri("/?q=1")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+        let sent = read_body(resp).await;
+
+        let res = primed.borrow_mut().remove(0);
+        assert_eq!(res.path, "/?q=1");
+        assert_eq!(res.status, StatusCode::OK);
+        assert!(res.headers.get(CONTENT_ENCODING).is_none());
+        assert_eq!(res.variants.identity(), content.as_bytes());
+        assert_eq!(res.variants.iter().count(), 2);
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(
+            &res.variants.get(ContentEncoding::Gzip).unwrap()[..],
+        )
+        .read_to_string(&mut decoded)
+        .unwrap();
+        assert_eq!(decoded, content);
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&sent[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, content);
+
+        let mut decoded = String::new();
+        brotli2::read::BrotliDecoder::new(
+            &res.variants.get(ContentEncoding::Br).unwrap()[..],
+        )
+        .read_to_string(&mut decoded)
+        .unwrap();
+        assert_eq!(decoded, content);
+
+        // identity client primes variants as well
+        let req = TestRequest::default().to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, content.as_bytes());
+        assert_eq!(primed.borrow_mut().remove(0).variants.iter().count(), 2);
+
+        let req = TestRequest::with_uri("/private").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, "private");
+        for path in &["/cookie", "/vary"] {
+            let req = TestRequest::with_uri(path).to_request();
+            let resp = call_service(&mut srv, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            let _ = read_body(resp).await;
+        }
+        for (name, value) in &[(AUTHORIZATION, "Bearer token"), (COOKIE, "id=1")] {
+            let req = TestRequest::default()
+                .header(name.clone(), *value)
+                .to_request();
+            assert_eq!(read_body(call_service(&mut srv, req).await).await, content);
+        }
+        let req = TestRequest::post().uri("/").to_request();
+        let _ = read_body(call_service(&mut srv, req).await).await;
+        assert!(primed.borrow().is_empty());
+    }
+
+    #[cfg(feature = "compress-dictionary")]
+    #[actix_rt::test]
+    async fn test_dictionary() {
+        let dict = Dictionary::new(CONTENT.repeat(10)).id("v1");
+        let mut srv = init_service(
+            App::new()
+                .wrap(Compress::default().dictionary(dict.clone()))
+                .route(
+                    "/",
+                    web::get().to(|| HttpResponse::Ok().body(CONTENT.repeat(11))),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .header(ACCEPT_ENCODING, "gzip, br, dcb")
+            .header("available-dictionary", available(&dict))
+            .header("dictionary-id", "\"v1\"")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), DCB);
+        assert_eq!(
+            resp.headers().get(VARY).unwrap(),
+            "accept-encoding, available-dictionary"
+        );
+        let body = read_body(resp).await;
+        assert_eq!(&body[..4], &[0xff, 0x44, 0x43, 0x42]);
+        assert_eq!(&body[4..36], dict.hash());
+        assert!(body.len() < CONTENT.len());
+
+        // id mismatch
+        let req = TestRequest::default()
+            .header(ACCEPT_ENCODING, "dcb")
+            .header("available-dictionary", available(&dict))
+            .header("dictionary-id", "\"v2\"")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+
+        // dcb is not accepted
+        let req = TestRequest::default()
+            .header(ACCEPT_ENCODING, "br, dcb;q=0")
+            .header("available-dictionary", available(&dict))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "br");
+
+        // unknown dictionary
+        let req = TestRequest::default()
+            .header(ACCEPT_ENCODING, "dcb")
+            .header("available-dictionary", available(&Dictionary::new("other")))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        assert_eq!(read_body(resp).await, CONTENT.repeat(11).as_bytes());
+
+        // negotiated encoding has higher quality
+        let req = TestRequest::default()
+            .header(ACCEPT_ENCODING, "br, dcb;q=0.5")
+            .header("available-dictionary", available(&dict))
+            .header("dictionary-id", "\"v1\"")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "br");
+
+        // identity is preferred
+        let req = TestRequest::default()
+            .header(ACCEPT_ENCODING, "identity, dcb;q=0.5")
+            .header("available-dictionary", available(&dict))
+            .header("dictionary-id", "\"v1\"")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+
+        // compression is disabled
+        let mut srv = init_service(
+            App::new()
+                .wrap(Compress::new(ContentEncoding::Identity).dictionary(dict.clone()))
+                .route(
+                    "/",
+                    web::get().to(|| HttpResponse::Ok().body(CONTENT.repeat(11))),
+                ),
+        )
+        .await;
+        let req = TestRequest::default()
+            .header(ACCEPT_ENCODING, "dcb")
+            .header("available-dictionary", available(&dict))
+            .header("dictionary-id", "\"v1\"")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
     }
 }
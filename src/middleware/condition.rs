@@ -1,9 +1,17 @@
 //! `Middleware` for conditionally enables another middleware.
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
+use actix_router::ResourceDef;
 use actix_service::{Service, Transform};
 use futures::future::{ok, Either, FutureExt, LocalBoxFuture};
 
+use crate::guard::Guard;
+use crate::service::ServiceRequest;
+
 /// `Middleware` for conditionally enables another middleware.
 /// The controlled middleware must not change the `Service` interfaces.
 /// This means you cannot control such middlewares like `Logger` or `Compress`.
@@ -20,6 +28,26 @@ use futures::future::{ok, Either, FutureExt, LocalBoxFuture};
 ///     .wrap(Condition::new(enable_normalize, NormalizePath));
 /// # }
 /// ```
+///
+/// Middleware could be enabled per request as well, with a predicate that
+/// is evaluated for every request.
+///
+/// ```rust
+/// use actix_web::middleware::{Condition, DefaultHeaders};
+/// use actix_web::{guard, App};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(Condition::pattern(
+///         "/api/{tail}*",
+///         DefaultHeaders::new().header("cache-control", "no-store"),
+///     ))
+///     .wrap(Condition::guard(
+///         guard::Header("x-debug", "1"),
+///         DefaultHeaders::new().header("x-debug", "1"),
+///     ));
+/// # }
+/// ```
 pub struct Condition<T> {
     trans: T,
     enable: bool,
@@ -29,6 +57,34 @@ impl<T> Condition<T> {
     pub fn new(enable: bool, trans: T) -> Self {
         Self { trans, enable }
     }
+
+    /// Enable middleware for requests that match predicate.
+    pub fn when<F>(predicate: F, trans: T) -> ConditionWhen<T>
+    where
+        F: Fn(&ServiceRequest) -> bool + 'static,
+    {
+        ConditionWhen {
+            trans,
+            predicate: Rc::new(predicate),
+        }
+    }
+
+    /// Enable middleware for requests that match guard, i.e. a header guard.
+    pub fn guard<G: Guard + 'static>(guard: G, trans: T) -> ConditionWhen<T> {
+        Self::when(move |req| guard.check(req.head()), trans)
+    }
+
+    /// Enable middleware for requests with path that matches resource
+    /// pattern, i.e. `/api/{tail}*`.
+    pub fn pattern(pattern: &str, trans: T) -> ConditionWhen<T> {
+        let rdef = ResourceDef::new(pattern);
+        Self::when(move |req| rdef.is_match(req.path()), trans)
+    }
+
+    /// Enable middleware while flag is set, flag could be changed at runtime.
+    pub fn flag(flag: Arc<AtomicBool>, trans: T) -> ConditionWhen<T> {
+        Self::when(move |_| flag.load(Ordering::Relaxed), trans)
+    }
 }
 
 impl<S, T> Transform<S> for Condition<T>
@@ -93,6 +149,103 @@ where
     }
 }
 
+/// `Middleware` for enabling another middleware per request.
+///
+/// Created with `Condition::{when, guard, pattern, flag}`.
+pub struct ConditionWhen<T> {
+    trans: T,
+    predicate: Rc<dyn Fn(&ServiceRequest) -> bool>,
+}
+
+impl<S, T> Transform<S> for ConditionWhen<T>
+where
+    S: Service<Request = ServiceRequest> + 'static,
+    T: Transform<
+        ConditionService<S>,
+        Request = ServiceRequest,
+        Response = S::Response,
+        Error = S::Error,
+    >,
+    T::Future: 'static,
+    T::InitError: 'static,
+    T::Transform: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = S::Response;
+    type Error = S::Error;
+    type InitError = T::InitError;
+    type Transform = ConditionWhenMiddleware<T::Transform, S>;
+    type Future = LocalBoxFuture<'static, Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let service = Rc::new(RefCell::new(service));
+        let predicate = self.predicate.clone();
+
+        self.trans
+            .new_transform(ConditionService(service.clone()))
+            .map(move |res| {
+                res.map(|enabled| ConditionWhenMiddleware {
+                    enabled,
+                    service,
+                    predicate,
+                })
+            })
+            .boxed_local()
+    }
+}
+
+/// Service shared by enabled and disabled paths of `ConditionWhen`.
+#[doc(hidden)]
+pub struct ConditionService<S>(Rc<RefCell<S>>);
+
+impl<S: Service> Service for ConditionService<S> {
+    type Request = S::Request;
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: S::Request) -> Self::Future {
+        self.0.borrow_mut().call(req)
+    }
+}
+
+#[doc(hidden)]
+pub struct ConditionWhenMiddleware<E, S> {
+    enabled: E,
+    service: Rc<RefCell<S>>,
+    predicate: Rc<dyn Fn(&ServiceRequest) -> bool>,
+}
+
+impl<E, S> Service for ConditionWhenMiddleware<E, S>
+where
+    E: Service<Request = ServiceRequest>,
+    S: Service<Request = ServiceRequest, Response = E::Response, Error = E::Error>,
+{
+    type Request = ServiceRequest;
+    type Response = E::Response;
+    type Error = E::Error;
+    type Future = Either<E::Future, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.service.borrow_mut().poll_ready(cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        self.enabled.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if (self.predicate)(&req) {
+            Either::Left(self.enabled.call(req))
+        } else {
+            Either::Right(self.service.borrow_mut().call(req))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use actix_service::IntoService;
@@ -148,4 +301,52 @@ mod tests {
             test::call_service(&mut mw, TestRequest::default().to_srv_request()).await;
         assert_eq!(resp.headers().get(CONTENT_TYPE), None);
     }
+
+    #[actix_rt::test]
+    async fn test_handler_when() {
+        let srv = |req: ServiceRequest| {
+            ok(req.into_response(HttpResponse::InternalServerError().finish()))
+        };
+
+        let mw =
+            ErrorHandlers::new().handler(StatusCode::INTERNAL_SERVER_ERROR, render_500);
+
+        let mut mw = Condition::pattern("/api/{tail}*", mw)
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::with_uri("/api/v1/test").to_srv_request();
+        let resp = test::call_service(&mut mw, req).await;
+        assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "0001");
+
+        let req = TestRequest::with_uri("/test").to_srv_request();
+        let resp = test::call_service(&mut mw, req).await;
+        assert_eq!(resp.headers().get(CONTENT_TYPE), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_handler_flag() {
+        let srv = |req: ServiceRequest| {
+            ok(req.into_response(HttpResponse::InternalServerError().finish()))
+        };
+
+        let mw =
+            ErrorHandlers::new().handler(StatusCode::INTERNAL_SERVER_ERROR, render_500);
+
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut mw = Condition::flag(flag.clone(), mw)
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let resp =
+            test::call_service(&mut mw, TestRequest::default().to_srv_request()).await;
+        assert_eq!(resp.headers().get(CONTENT_TYPE), None);
+
+        flag.store(true, Ordering::Relaxed);
+        let resp =
+            test::call_service(&mut mw, TestRequest::default().to_srv_request()).await;
+        assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "0001");
+    }
 }
@@ -0,0 +1,288 @@
+//! `Middleware` for rendering detailed error pages during development
+use std::fmt::Write;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_service::{Service, Transform};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::dev::{Body, ResponseBody};
+use crate::error::Error;
+use crate::http::header::{self, HeaderName, HeaderValue};
+use crate::http::StatusCode;
+use crate::service::{ServiceRequest, ServiceResponse};
+
+/// `Middleware` for rendering detailed error pages during development.
+///
+/// Bodies of `500 Internal Server Error` responses caused by an error are
+/// replaced with an html page that shows the error, route pattern, matched
+/// path parameters and request headers. Values of `Authorization`,
+/// `Proxy-Authorization` and `Cookie` headers are redacted, more headers can
+/// be added with [`redact`](#method.redact).
+///
+/// Errors do not capture a backtrace when they are constructed, so the page
+/// does not contain one.
+///
+/// Middleware is only active in debug builds, error pages have to be
+/// [`forced`](#method.force) to be shown in release builds.
+///
+/// ```rust
+/// use actix_web::{error, middleware, web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(middleware::DevErrors::new().redact("x-api-key"))
+///     .route(
+///         "/",
+///         web::get().to(|| async {
+///             Err::<HttpResponse, _>(error::ErrorInternalServerError("oops"))
+///         }),
+///     );
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DevErrors {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    enabled: bool,
+    redact: Vec<HeaderName>,
+}
+
+impl DevErrors {
+    /// Construct `DevErrors` middleware.
+    pub fn new() -> Self {
+        DevErrors {
+            inner: Rc::new(Inner {
+                enabled: cfg!(debug_assertions),
+                redact: default_redact(),
+            }),
+        }
+    }
+
+    /// Show error pages in release builds as well.
+    pub fn force(mut self) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .enabled = true;
+        self
+    }
+
+    /// Redact value of the header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    pub fn redact(mut self, name: &str) -> Self {
+        let name = HeaderName::from_bytes(name.as_bytes()).expect("Invalid header name");
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .redact
+            .push(name);
+        self
+    }
+}
+
+impl Default for DevErrors {
+    fn default() -> Self {
+        DevErrors::new()
+    }
+}
+
+impl<S, B> Transform<S> for DevErrors
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = DevErrorsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(DevErrorsMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct DevErrorsMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for DevErrorsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if !self.inner.enabled {
+            return self.service.call(req).boxed_local();
+        }
+
+        let inner = self.inner.clone();
+        let fut = self.service.call(req);
+
+        async move { Ok(error_page(fut.await?, &inner.redact)) }.boxed_local()
+    }
+}
+
+/// Headers redacted by default.
+pub(super) fn default_redact() -> Vec<HeaderName> {
+    vec![
+        header::AUTHORIZATION,
+        header::PROXY_AUTHORIZATION,
+        header::COOKIE,
+    ]
+}
+
+/// Replace body of `500 Internal Server Error` response caused by an error
+/// with the error page.
+pub(super) fn error_page<B>(
+    res: ServiceResponse<B>,
+    redact: &[HeaderName],
+) -> ServiceResponse<B> {
+    if res.status() != StatusCode::INTERNAL_SERVER_ERROR {
+        return res;
+    }
+
+    let page = match res.response().error() {
+        Some(err) => render(&res, err, redact),
+        None => return res,
+    };
+
+    let mut res = res.map_body(|head, _| {
+        head.headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        ResponseBody::Other(Body::from(page))
+    });
+    res.headers_mut().remove(header::CONTENT_LENGTH);
+    res
+}
+
+fn render<B>(res: &ServiceResponse<B>, err: &Error, redact: &[HeaderName]) -> String {
+    let req = res.request();
+    let mut page = String::new();
+
+    let _ = write!(
+        page,
+        "<!DOCTYPE html>\n<html><head><title>{status}</title></head><body>\n\
+         <h1>{status}</h1>\n<h2>{method} {path}</h2>\n\
+         <h3>Error</h3>\n<pre>{error}</pre>\n<pre>{debug}</pre>\n",
+        status = res.status(),
+        method = req.method(),
+        path = escape(&req.uri().to_string()),
+        error = escape(&err.to_string()),
+        debug = escape(&format!("{:?}", err)),
+    );
+
+    let pattern = req.match_pattern().unwrap_or_else(|| "-".to_owned());
+    let _ = write!(page, "<h3>Route</h3>\n<pre>{}</pre>\n", escape(&pattern));
+
+    page.push_str("<h3>Path parameters</h3>\n<table>\n");
+    for (name, value) in req.match_info().iter() {
+        let _ = writeln!(
+            page,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            escape(name),
+            escape(value)
+        );
+    }
+    page.push_str("</table>\n");
+
+    page.push_str("<h3>Headers</h3>\n<table>\n");
+    for (name, value) in req.headers().iter() {
+        let value = if redact.contains(name) {
+            "[redacted]".to_owned()
+        } else {
+            escape(&String::from_utf8_lossy(value.as_bytes()))
+        };
+        let _ = writeln!(page, "<tr><td>{}</td><td>{}</td></tr>", name, value);
+    }
+    page.push_str("</table>\n</body></html>\n");
+    page
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorInternalServerError;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn test_dev_errors() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(DevErrors::new().force().redact("x-api-key"))
+                .route(
+                    "/user/{id}",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(ErrorInternalServerError("<db failure>"))
+                    }),
+                )
+                .route(
+                    "/",
+                    web::get().to(|| HttpResponse::InternalServerError().body("plain")),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/user/42")
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .header("x-api-key", "secret")
+            .header("x-request-id", "abc")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        let body = read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("&lt;db failure&gt;"));
+        assert!(body.contains("/user/{id}"));
+        assert!(body.contains("<tr><td>id</td><td>42</td></tr>"));
+        assert!(body.contains("abc"));
+        assert!(!body.contains("secret"));
+
+        // responses without error are not changed
+        let req = TestRequest::default().to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(read_body(res).await, "plain");
+    }
+}
@@ -0,0 +1,383 @@
+//! `Middleware` for rejecting cross-site requests with fetch metadata
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::error::{Error, ErrorForbidden};
+use actix_http::http::Method;
+use actix_http::RequestHead;
+use actix_service::{Service, Transform};
+use futures::future::{ok, Either, Ready};
+
+use crate::service::{ServiceRequest, ServiceResponse};
+
+/// Relation of request initiator to the target origin, `Sec-Fetch-Site`
+/// header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FetchSite {
+    /// Request is initiated by a different site
+    CrossSite,
+    /// Request is initiated by the same site, but different origin
+    SameSite,
+    /// Request is initiated by the same origin
+    SameOrigin,
+    /// Request is initiated by the user, i.e. typed url or bookmark
+    None,
+}
+
+/// Mode of the request, `Sec-Fetch-Mode` header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FetchMode {
+    Cors,
+    Navigate,
+    NoCors,
+    SameOrigin,
+    Websocket,
+}
+
+/// Fetch metadata request headers, sent by browsers with every request.
+///
+/// ```rust
+/// use actix_web::middleware::{FetchMetadata, FetchSite};
+/// use actix_web::{HttpRequest, HttpResponse};
+///
+/// async fn index(req: HttpRequest) -> HttpResponse {
+///     match FetchMetadata::from_head(req.head()) {
+///         Some(ref meta) if meta.site() == FetchSite::CrossSite => {
+///             HttpResponse::Forbidden().finish()
+///         }
+///         _ => HttpResponse::Ok().finish(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchMetadata {
+    site: FetchSite,
+    mode: Option<FetchMode>,
+    dest: Option<String>,
+    user: bool,
+}
+
+impl FetchMetadata {
+    /// Parse fetch metadata of the request.
+    ///
+    /// Returns `None` if request does not have valid `Sec-Fetch-Site` header,
+    /// like requests of non-browser clients or older browsers.
+    pub fn from_head(head: &RequestHead) -> Option<FetchMetadata> {
+        let site = match header(head, "sec-fetch-site")? {
+            "cross-site" => FetchSite::CrossSite,
+            "same-site" => FetchSite::SameSite,
+            "same-origin" => FetchSite::SameOrigin,
+            "none" => FetchSite::None,
+            _ => return None,
+        };
+        let mode = header(head, "sec-fetch-mode").and_then(|mode| match mode {
+            "cors" => Some(FetchMode::Cors),
+            "navigate" => Some(FetchMode::Navigate),
+            "no-cors" => Some(FetchMode::NoCors),
+            "same-origin" => Some(FetchMode::SameOrigin),
+            "websocket" => Some(FetchMode::Websocket),
+            _ => None,
+        });
+
+        Some(FetchMetadata {
+            site,
+            mode,
+            dest: header(head, "sec-fetch-dest").map(|dest| dest.to_owned()),
+            user: header(head, "sec-fetch-user") == Some("?1"),
+        })
+    }
+
+    /// `Sec-Fetch-Site` header value
+    pub fn site(&self) -> FetchSite {
+        self.site
+    }
+
+    /// `Sec-Fetch-Mode` header value
+    pub fn mode(&self) -> Option<FetchMode> {
+        self.mode
+    }
+
+    /// `Sec-Fetch-Dest` header value, i.e. `document`, `image` or `script`
+    pub fn dest(&self) -> Option<&str> {
+        self.dest.as_ref().map(String::as_str)
+    }
+
+    /// Navigation is triggered by user activation, `Sec-Fetch-User` header
+    pub fn is_user_activated(&self) -> bool {
+        self.user
+    }
+}
+
+fn header<'a>(head: &'a RequestHead, name: &'static str) -> Option<&'a str> {
+    head.headers
+        .get(name)
+        .and_then(|val| val.to_str().ok())
+        .map(|val| val.trim())
+}
+
+/// `Middleware` for rejecting cross-site requests using fetch metadata.
+///
+/// Implements resource isolation policy: requests from the same origin,
+/// same site and requests initiated by the user are allowed, cross-site
+/// requests are allowed only for top-level navigations with `GET` method.
+/// Other cross-site requests, like form posts or `no-cors` subresource
+/// loads from other sites, are rejected with `403 Forbidden` response.
+/// This protects state-changing endpoints from CSRF without tokens.
+///
+/// Requests without `Sec-Fetch-Site` header are allowed, so non-browser
+/// clients and older browsers keep working.
+///
+/// ```rust
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(
+///         middleware::FetchPolicy::default()
+///             // public api is available to other sites
+///             .exempt("/api/public"),
+///     )
+///     .route("/transfer", web::post().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct FetchPolicy {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    same_site: bool,
+    safe_methods: bool,
+    exempt: Vec<String>,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        FetchPolicy {
+            inner: Rc::new(Inner {
+                same_site: true,
+                safe_methods: false,
+                exempt: Vec::new(),
+            }),
+        }
+    }
+}
+
+impl FetchPolicy {
+    /// Allow requests from other origins of the same site.
+    ///
+    /// By default same-site requests are allowed.
+    pub fn same_site(mut self, allow: bool) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .same_site = allow;
+        self
+    }
+
+    /// Allow cross-site requests with safe methods, `GET`, `HEAD` and
+    /// `OPTIONS`, so only state-changing requests are rejected.
+    ///
+    /// By default cross-site subresource loads are rejected as well.
+    pub fn safe_methods(mut self, allow: bool) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .safe_methods = allow;
+        self
+    }
+
+    /// Do not check requests with the path prefix, i.e. endpoints that
+    /// are meant to be used by other sites.
+    pub fn exempt(mut self, prefix: &str) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .exempt
+            .push(prefix.to_owned());
+        self
+    }
+}
+
+impl Inner {
+    fn allows(&self, meta: &FetchMetadata, req: &ServiceRequest) -> bool {
+        match meta.site {
+            FetchSite::SameOrigin | FetchSite::None => return true,
+            FetchSite::SameSite if self.same_site => return true,
+            _ => (),
+        }
+
+        let method = req.method();
+        if self.safe_methods
+            && (method == Method::GET
+                || method == Method::HEAD
+                || method == Method::OPTIONS)
+        {
+            return true;
+        }
+
+        // top-level navigation, embedding with object and embed
+        // elements is not a navigation
+        if meta.mode == Some(FetchMode::Navigate)
+            && method == Method::GET
+            && meta.dest() != Some("object")
+            && meta.dest() != Some("embed")
+        {
+            return true;
+        }
+
+        let path = req.path();
+        self.exempt
+            .iter()
+            .any(|prefix| path.starts_with(prefix.as_str()))
+    }
+}
+
+impl<S, B> Transform<S> for FetchPolicy
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = FetchPolicyMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(FetchPolicyMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct FetchPolicyMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for FetchPolicyMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if let Some(meta) = FetchMetadata::from_head(req.head()) {
+            if !self.inner.allows(&meta, &req) {
+                log::debug!(
+                    "Rejected {:?} request from {:?} to {:?}",
+                    meta.mode,
+                    meta.site,
+                    req.path()
+                );
+                return Either::Right(ok(
+                    req.error_response(ErrorForbidden("Cross-site request is rejected"))
+                ));
+            }
+        }
+        Either::Left(self.service.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[test]
+    fn test_metadata() {
+        let req = TestRequest::default()
+            .header("sec-fetch-site", "cross-site")
+            .header("sec-fetch-mode", "navigate")
+            .header("sec-fetch-dest", "document")
+            .header("sec-fetch-user", "?1")
+            .to_http_request();
+        let meta = FetchMetadata::from_head(req.head()).unwrap();
+        assert_eq!(meta.site(), FetchSite::CrossSite);
+        assert_eq!(meta.mode(), Some(FetchMode::Navigate));
+        assert_eq!(meta.dest(), Some("document"));
+        assert!(meta.is_user_activated());
+
+        let req = TestRequest::default()
+            .header("sec-fetch-site", "same-origin")
+            .header("sec-fetch-mode", "unknown")
+            .to_http_request();
+        let meta = FetchMetadata::from_head(req.head()).unwrap();
+        assert_eq!(meta.site(), FetchSite::SameOrigin);
+        assert_eq!(meta.mode(), None);
+        assert!(!meta.is_user_activated());
+
+        let req = TestRequest::default().to_http_request();
+        assert!(FetchMetadata::from_head(req.head()).is_none());
+        let req = TestRequest::with_header("sec-fetch-site", "other").to_http_request();
+        assert!(FetchMetadata::from_head(req.head()).is_none());
+    }
+
+    fn request(method: Method, path: &str, meta: &str) -> actix_http::Request {
+        // site, mode and dest separated by spaces
+        let mut req = TestRequest::default().method(method).uri(path);
+        let names = ["sec-fetch-site", "sec-fetch-mode", "sec-fetch-dest"];
+        for (name, value) in names.iter().zip(meta.split_whitespace()) {
+            req = req.header(*name, value);
+        }
+        req.to_request()
+    }
+
+    #[actix_rt::test]
+    async fn test_policy() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(FetchPolicy::default().same_site(false).exempt("/public"))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let allowed = vec![
+            (Method::POST, "/", ""),
+            (Method::POST, "/", "same-origin cors"),
+            (Method::POST, "/", "none navigate document"),
+            // top-level navigation
+            (Method::GET, "/", "cross-site navigate document"),
+            (Method::POST, "/public/data", "cross-site cors"),
+        ];
+        for (method, path, meta) in allowed {
+            let res = call_service(&mut srv, request(method, path, meta)).await;
+            assert_eq!(res.status(), StatusCode::OK, "{}", meta);
+        }
+
+        let rejected = vec![
+            (Method::POST, "/", "same-site cors"),
+            (Method::POST, "/", "cross-site no-cors"),
+            (Method::GET, "/", "cross-site no-cors image"),
+            (Method::POST, "/", "cross-site navigate document"),
+            (Method::GET, "/", "cross-site navigate object"),
+        ];
+        for (method, path, meta) in rejected {
+            let res = call_service(&mut srv, request(method, path, meta)).await;
+            assert_eq!(res.status(), StatusCode::FORBIDDEN, "{}", meta);
+        }
+
+        // only state-changing requests are rejected
+        let mut srv = init_service(
+            App::new()
+                .wrap(FetchPolicy::default().safe_methods(true))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+        let req = request(Method::GET, "/", "cross-site no-cors image");
+        assert_eq!(call_service(&mut srv, req).await.status(), StatusCode::OK);
+        let req = request(Method::DELETE, "/", "cross-site cors");
+        assert_eq!(
+            call_service(&mut srv, req).await.status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+}
@@ -0,0 +1,567 @@
+//! Flash messages support.
+//!
+//! Flash messages are short notifications that are set while handling one
+//! request and displayed while handling the next one, typically in a
+//! redirect-then-render flow.
+//!
+//! [`FlashMessages`](struct.FlashMessages.html) middleware loads messages
+//! received with the request and persists newly added ones after the handler
+//! completes. [`Flash`](struct.Flash.html) extractor gives handlers access to
+//! both.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use actix_web::middleware::flash::{Flash, FlashMessages};
+//! use actix_web::{http, web, App, HttpResponse};
+//!
+//! async fn save(flash: Flash) -> HttpResponse {
+//!     flash.success("Settings saved");
+//!     HttpResponse::SeeOther()
+//!         .header(http::header::LOCATION, "/")
+//!         .finish()
+//! }
+//!
+//! async fn index(flash: Flash) -> String {
+//!     flash
+//!         .messages()
+//!         .iter()
+//!         .map(|msg| format!("{}: {}\n", msg.level(), msg.message()))
+//!         .collect()
+//! }
+//!
+//! fn main() {
+//!     let app = App::new()
+//!         .wrap(FlashMessages::default())
+//!         .route("/", web::get().to(index))
+//!         .route("/save", web::post().to(save));
+//! }
+//! ```
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::cookie::Cookie;
+#[cfg(feature = "secure-cookies")]
+use actix_http::cookie::{CookieJar, KeyRing};
+use actix_service::{Service, Transform};
+use futures::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+use crate::dev::{Payload, ResponseHead, ServiceRequest, ServiceResponse};
+use crate::error::{Error, ErrorInternalServerError};
+use crate::extract::FromRequest;
+use crate::http::header::{HeaderValue, SET_COOKIE};
+use crate::{HttpMessage, HttpRequest};
+
+/// Flash message level.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Success => "success",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Single flash message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlashMessage {
+    level: Level,
+    message: String,
+}
+
+impl FlashMessage {
+    /// Create new flash message.
+    pub fn new<T: Into<String>>(level: Level, message: T) -> Self {
+        FlashMessage {
+            level,
+            message: message.into(),
+        }
+    }
+
+    /// Message level.
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Message text.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Storage backend for flash messages.
+///
+/// Messages are loaded once per request, before the handler is called, and
+/// stored after the handler completes. Implement this trait to keep messages
+/// in a server side session instead of a cookie.
+pub trait FlashStore {
+    /// Load messages received with the request.
+    fn load(&self, req: &HttpRequest) -> Vec<FlashMessage>;
+
+    /// Store messages for the next request.
+    ///
+    /// An empty list means stored messages have been consumed and should be
+    /// removed.
+    fn store(
+        &self,
+        messages: &[FlashMessage],
+        req: &HttpRequest,
+        res: &mut ResponseHead,
+    );
+}
+
+/// Cookie based flash message storage.
+///
+/// Messages are stored as percent encoded json in a cookie (`_flash` by
+/// default). The cookie can be signed with
+/// [`CookieFlashStore::signed`](#method.signed) when `secure-cookies` feature
+/// is enabled.
+pub struct CookieFlashStore {
+    name: String,
+    path: String,
+    #[cfg(feature = "secure-cookies")]
+    keys: Option<KeyRing>,
+}
+
+impl CookieFlashStore {
+    /// Create new cookie storage.
+    pub fn new() -> Self {
+        CookieFlashStore::default()
+    }
+
+    /// Set cookie name.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_owned();
+        self
+    }
+
+    /// Set cookie path.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.to_owned();
+        self
+    }
+
+    /// Sign flash cookie with the key ring.
+    ///
+    /// Cookies that fail verification are ignored.
+    #[cfg(feature = "secure-cookies")]
+    pub fn signed<K: Into<KeyRing>>(mut self, keys: K) -> Self {
+        self.keys = Some(keys.into());
+        self
+    }
+
+    #[cfg(feature = "secure-cookies")]
+    fn verify(&self, cookie: Cookie<'static>) -> Option<Cookie<'static>> {
+        if let Some(ref keys) = self.keys {
+            let mut jar = CookieJar::new();
+            jar.add_original(cookie);
+            jar.signed_with(keys).get(&self.name)
+        } else {
+            Some(cookie)
+        }
+    }
+
+    #[cfg(not(feature = "secure-cookies"))]
+    fn verify(&self, cookie: Cookie<'static>) -> Option<Cookie<'static>> {
+        Some(cookie)
+    }
+
+    #[cfg(feature = "secure-cookies")]
+    fn sign(&self, cookie: Cookie<'static>) -> Cookie<'static> {
+        if let Some(ref keys) = self.keys {
+            let mut jar = CookieJar::new();
+            jar.signed_with(keys).add(cookie);
+            jar.get(&self.name).unwrap().clone()
+        } else {
+            cookie
+        }
+    }
+
+    #[cfg(not(feature = "secure-cookies"))]
+    fn sign(&self, cookie: Cookie<'static>) -> Cookie<'static> {
+        cookie
+    }
+}
+
+impl Default for CookieFlashStore {
+    fn default() -> Self {
+        CookieFlashStore {
+            name: "_flash".to_owned(),
+            path: "/".to_owned(),
+            #[cfg(feature = "secure-cookies")]
+            keys: None,
+        }
+    }
+}
+
+impl FlashStore for CookieFlashStore {
+    fn load(&self, req: &HttpRequest) -> Vec<FlashMessage> {
+        req.cookie(&self.name)
+            .and_then(|cookie| self.verify(cookie))
+            .and_then(|cookie| serde_json::from_str(cookie.value()).ok())
+            .unwrap_or_default()
+    }
+
+    fn store(&self, messages: &[FlashMessage], _: &HttpRequest, res: &mut ResponseHead) {
+        let cookie = if messages.is_empty() {
+            Cookie::build(self.name.clone(), "")
+                .path(self.path.clone())
+                .max_age(0)
+                .expires(OffsetDateTime::now_utc() - Duration::days(365))
+                .finish()
+        } else {
+            let value = match serde_json::to_string(messages) {
+                Ok(value) => value,
+                Err(e) => {
+                    log::error!("Can not serialize flash messages: {}", e);
+                    return;
+                }
+            };
+            let cookie = Cookie::build(self.name.clone(), value)
+                .path(self.path.clone())
+                .http_only(true)
+                .finish();
+            self.sign(cookie)
+        };
+
+        match HeaderValue::from_str(&cookie.encoded().to_string()) {
+            Ok(val) => res.headers.append(SET_COOKIE, val),
+            Err(e) => log::error!("Can not set flash cookie: {}", e),
+        }
+    }
+}
+
+/// `Middleware` for flash messages support.
+///
+/// Messages received with the request are available through the
+/// [`Flash`](struct.Flash.html) extractor. Received messages are removed from
+/// storage once a handler reads them, unread messages are kept for the next
+/// request. Messages added by a handler are persisted for the next request.
+#[derive(Clone)]
+pub struct FlashMessages {
+    store: Rc<dyn FlashStore>,
+}
+
+impl FlashMessages {
+    /// Create `FlashMessages` middleware with custom storage.
+    pub fn new<T: FlashStore + 'static>(store: T) -> Self {
+        FlashMessages {
+            store: Rc::new(store),
+        }
+    }
+}
+
+impl Default for FlashMessages {
+    /// Create `FlashMessages` middleware with cookie storage.
+    fn default() -> Self {
+        FlashMessages::new(CookieFlashStore::default())
+    }
+}
+
+impl<S, B> Transform<S> for FlashMessages
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = FlashMessagesMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(FlashMessagesMiddleware {
+            service,
+            store: self.store.clone(),
+        })
+    }
+}
+
+pub struct FlashMessagesMiddleware<S> {
+    service: S,
+    store: Rc<dyn FlashStore>,
+}
+
+impl<S, B> Service for FlashMessagesMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let store = self.store.clone();
+        let inner = Rc::new(RefCell::new(FlashInner {
+            incoming: store.load(req.request()),
+            outgoing: Vec::new(),
+            consumed: false,
+        }));
+        req.extensions_mut().insert(Flash(inner.clone()));
+
+        let fut = self.service.call(req);
+
+        async move {
+            let mut res = fut.await?;
+
+            let inner = inner.borrow();
+            if !inner.outgoing.is_empty() {
+                let mut messages = Vec::new();
+                if !inner.consumed {
+                    messages.extend(inner.incoming.iter().cloned());
+                }
+                messages.extend(inner.outgoing.iter().cloned());
+
+                let (req, res_mut) = (res.request().clone(), res.response_mut());
+                store.store(&messages, &req, res_mut.head_mut());
+            } else if inner.consumed && !inner.incoming.is_empty() {
+                let (req, res_mut) = (res.request().clone(), res.response_mut());
+                store.store(&[], &req, res_mut.head_mut());
+            }
+            drop(inner);
+
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+struct FlashInner {
+    incoming: Vec<FlashMessage>,
+    outgoing: Vec<FlashMessage>,
+    consumed: bool,
+}
+
+/// Flash messages extractor.
+///
+/// Requires [`FlashMessages`](struct.FlashMessages.html) middleware to be
+/// registered.
+#[derive(Clone)]
+pub struct Flash(Rc<RefCell<FlashInner>>);
+
+impl Flash {
+    /// Messages received with the request.
+    ///
+    /// Reading messages marks them as consumed, so they are not delivered
+    /// again with the next request.
+    pub fn messages(&self) -> Vec<FlashMessage> {
+        let mut inner = self.0.borrow_mut();
+        inner.consumed = true;
+        inner.incoming.clone()
+    }
+
+    /// Check if request carries flash messages, without consuming them.
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().incoming.is_empty()
+    }
+
+    /// Add message for the next request.
+    pub fn add<T: Into<String>>(&self, level: Level, message: T) {
+        self.0
+            .borrow_mut()
+            .outgoing
+            .push(FlashMessage::new(level, message));
+    }
+
+    /// Add message with `Debug` level.
+    pub fn debug<T: Into<String>>(&self, message: T) {
+        self.add(Level::Debug, message)
+    }
+
+    /// Add message with `Info` level.
+    pub fn info<T: Into<String>>(&self, message: T) {
+        self.add(Level::Info, message)
+    }
+
+    /// Add message with `Success` level.
+    pub fn success<T: Into<String>>(&self, message: T) {
+        self.add(Level::Success, message)
+    }
+
+    /// Add message with `Warning` level.
+    pub fn warning<T: Into<String>>(&self, message: T) {
+        self.add(Level::Warning, message)
+    }
+
+    /// Add message with `Error` level.
+    pub fn error<T: Into<String>>(&self, message: T) {
+        self.add(Level::Error, message)
+    }
+}
+
+impl FromRequest for Flash {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Flash, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(flash) = req.extensions().get::<Flash>() {
+            ok(flash.clone())
+        } else {
+            log::debug!(
+                "Failed to construct Flash extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            err(ErrorInternalServerError(
+                "Flash messages are not configured, to configure use \
+                 App::wrap(FlashMessages::default())",
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::test::{self, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    fn flash_cookie(res: &ServiceResponse) -> Cookie<'static> {
+        let val = res.headers().get(header::SET_COOKIE).unwrap();
+        Cookie::parse_encoded(val.to_str().unwrap().to_owned()).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_flash_messages() {
+        let mut srv = test::init_service(
+            App::new()
+                .wrap(FlashMessages::default())
+                .route(
+                    "/set",
+                    web::get().to(|flash: Flash| {
+                        flash.success("saved");
+                        flash.add(Level::Warning, "careful");
+                        HttpResponse::Ok()
+                    }),
+                )
+                .route(
+                    "/get",
+                    web::get().to(|flash: Flash| {
+                        let msgs = flash.messages();
+                        async move {
+                            msgs.iter()
+                                .map(|m| format!("{}:{};", m.level(), m.message()))
+                                .collect::<String>()
+                        }
+                    }),
+                )
+                .route("/skip", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        let res =
+            test::call_service(&mut srv, TestRequest::with_uri("/set").to_request())
+                .await;
+        let cookie = flash_cookie(&res);
+        assert_eq!(cookie.name(), "_flash");
+
+        // messages survive requests that do not read them
+        let req = TestRequest::with_uri("/skip")
+            .cookie(cookie.clone())
+            .to_request();
+        let res = test::call_service(&mut srv, req).await;
+        assert!(res.headers().get(header::SET_COOKIE).is_none());
+
+        // reading messages removes the cookie
+        let req = TestRequest::with_uri("/get").cookie(cookie).to_request();
+        let res = test::call_service(&mut srv, req).await;
+        let removal = flash_cookie(&res);
+        assert_eq!(removal.max_age(), Some(Duration::zero()));
+        let body = test::read_body(res).await;
+        assert_eq!(body, "success:saved;warning:careful;");
+    }
+
+    #[actix_rt::test]
+    async fn test_unread_messages_are_kept() {
+        let mut srv =
+            test::init_service(App::new().wrap(FlashMessages::default()).route(
+                "/",
+                web::get().to(|flash: Flash| {
+                    flash.error("failed");
+                    HttpResponse::Ok()
+                }),
+            ))
+            .await;
+
+        let cookie = Cookie::new("_flash", r#"[{"level":"info","message":"hello"}]"#);
+        let req = TestRequest::default().cookie(cookie).to_request();
+        let res = test::call_service(&mut srv, req).await;
+
+        let msgs: Vec<FlashMessage> =
+            serde_json::from_str(flash_cookie(&res).value()).unwrap();
+        assert_eq!(
+            msgs,
+            vec![
+                FlashMessage::new(Level::Info, "hello"),
+                FlashMessage::new(Level::Error, "failed")
+            ]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_not_configured() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let res = Flash::from_request(&req, &mut pl).await;
+        assert_eq!(
+            res.err().unwrap().as_response_error().status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[cfg(feature = "secure-cookies")]
+    #[actix_rt::test]
+    async fn test_signed_store() {
+        use actix_http::cookie::Key;
+
+        let store = CookieFlashStore::new().signed(Key::generate());
+        let req = TestRequest::default()
+            .cookie(Cookie::new(
+                "_flash",
+                r#"[{"level":"info","message":"forged"}]"#,
+            ))
+            .to_http_request();
+        assert!(store.load(&req).is_empty());
+
+        let mut res = HttpResponse::Ok().finish();
+        store.store(
+            &[FlashMessage::new(Level::Info, "hi")],
+            &req,
+            res.head_mut(),
+        );
+        let val = res.headers().get(header::SET_COOKIE).unwrap();
+        let cookie = Cookie::parse_encoded(val.to_str().unwrap().to_owned()).unwrap();
+
+        let req = TestRequest::default().cookie(cookie).to_http_request();
+        assert_eq!(store.load(&req), vec![FlashMessage::new(Level::Info, "hi")]);
+    }
+}
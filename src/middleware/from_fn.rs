@@ -0,0 +1,189 @@
+//! `Middleware` from an async function
+use std::cell::RefCell;
+use std::future::Future;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_service::boxed::{self, BoxService};
+use actix_service::{Service, Transform};
+use futures::future::{ok, Ready};
+
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::Error;
+
+/// Create middleware from an async function.
+///
+/// Function receives request and `Next` service. Calling `Next::call()`
+/// passes request to the rest of the middleware chain and the handler.
+/// Response, or an error, is returned from the function, so function could
+/// act before and after the wrapped service.
+///
+/// ```rust
+/// use actix_web::dev::{MessageBody, ServiceRequest, ServiceResponse};
+/// use actix_web::http::{HeaderName, HeaderValue};
+/// use actix_web::middleware::{self, Next};
+/// use actix_web::{web, App, Error, HttpResponse};
+///
+/// async fn timing<B: MessageBody>(
+///     req: ServiceRequest,
+///     next: Next<B>,
+/// ) -> Result<ServiceResponse<B>, Error> {
+///     let start = std::time::Instant::now();
+///     let mut res = next.call(req).await?;
+///     let elapsed = start.elapsed().as_millis().to_string();
+///     res.headers_mut().insert(
+///         HeaderName::from_static("x-response-time"),
+///         HeaderValue::from_str(&elapsed).unwrap(),
+///     );
+///     Ok(res)
+/// }
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(middleware::from_fn(timing))
+///     .route("/", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+pub fn from_fn<F>(f: F) -> MiddlewareFn<F> {
+    MiddlewareFn { f: Rc::new(f) }
+}
+
+/// Middleware created with [`from_fn`](fn.from_fn.html).
+pub struct MiddlewareFn<F> {
+    f: Rc<F>,
+}
+
+impl<F> Clone for MiddlewareFn<F> {
+    fn clone(&self) -> Self {
+        MiddlewareFn { f: self.f.clone() }
+    }
+}
+
+impl<S, F, R, B, B1> Transform<S> for MiddlewareFn<F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: 'static,
+    F: Fn(ServiceRequest, Next<B>) -> R + 'static,
+    R: Future<Output = Result<ServiceResponse<B1>, Error>>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B1>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MiddlewareFnService<F, B>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(MiddlewareFnService {
+            f: self.f.clone(),
+            service: Rc::new(RefCell::new(boxed::service(service))),
+        })
+    }
+}
+
+type NextService<B> = BoxService<ServiceRequest, ServiceResponse<B>, Error>;
+
+/// Rest of the middleware chain passed to middleware function.
+pub struct Next<B> {
+    service: Rc<RefCell<NextService<B>>>,
+}
+
+impl<B> Next<B> {
+    /// Call the rest of the middleware chain and the handler.
+    pub async fn call(self, req: ServiceRequest) -> Result<ServiceResponse<B>, Error> {
+        let fut = self.service.borrow_mut().call(req);
+        fut.await
+    }
+}
+
+#[doc(hidden)]
+pub struct MiddlewareFnService<F, B> {
+    f: Rc<F>,
+    service: Rc<RefCell<NextService<B>>>,
+}
+
+impl<F, R, B, B1> Service for MiddlewareFnService<F, B>
+where
+    F: Fn(ServiceRequest, Next<B>) -> R,
+    R: Future<Output = Result<ServiceResponse<B1>, Error>>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B1>;
+    type Error = Error;
+    type Future = R;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        (self.f)(
+            req,
+            Next {
+                service: self.service.clone(),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_service::IntoService;
+
+    use super::*;
+    use crate::dev::MessageBody;
+    use crate::http::{header::CONTENT_TYPE, HeaderValue, StatusCode};
+    use crate::test::{self, TestRequest};
+    use crate::HttpResponse;
+
+    async fn add_header<B: MessageBody>(
+        req: ServiceRequest,
+        next: Next<B>,
+    ) -> Result<ServiceResponse<B>, Error> {
+        if req.path() == "/forbidden" {
+            return Err(crate::error::ErrorForbidden("forbidden"));
+        }
+
+        let mut res = next.call(req).await?;
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("0001"));
+        Ok(res)
+    }
+
+    #[actix_rt::test]
+    async fn test_from_fn() {
+        let srv =
+            |req: ServiceRequest| ok(req.into_response(HttpResponse::Ok().finish()));
+
+        let mut mw = from_fn(add_header)
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let resp =
+            test::call_service(&mut mw, TestRequest::default().to_srv_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "0001");
+
+        let req = TestRequest::with_uri("/forbidden").to_srv_request();
+        let err = mw.call(req).await.err().unwrap();
+        assert_eq!(err.as_response_error().status_code(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_rt::test]
+    async fn test_from_fn_app() {
+        let mut srv = test::init_service(
+            crate::App::new()
+                .wrap(from_fn(add_header))
+                .route("/", crate::web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        let resp =
+            test::call_service(&mut srv, TestRequest::default().to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "0001");
+    }
+}
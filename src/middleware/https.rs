@@ -0,0 +1,391 @@
+//! `Middleware` for https enforcement
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_service::{Service, Transform};
+use futures::future::{ok, Either, FutureExt, LocalBoxFuture, Ready};
+
+use crate::dev::{Body, ResponseBody};
+use crate::error::Error;
+use crate::http::header::{self, HeaderValue};
+use crate::http::{Method, StatusCode};
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::HttpResponse;
+
+/// `Middleware` that redirects plaintext requests to https.
+///
+/// `GET` and `HEAD` requests are redirected with *301 Moved Permanently*
+/// status, other requests with *308 Permanent Redirect*, so clients repeat
+/// them with the same method and body. Port of the host is dropped, unless
+/// it is mapped to a https port with `map_port()`.
+///
+/// By default a request is secure if it is received over a tls connection
+/// or has an absolute https uri. Handlers behind a tls terminating proxy
+/// trust `Forwarded` and `X-Forwarded-Proto` headers of the proxy, see
+/// `trust_forwarded()` and `trusted_proxy()`.
+///
+/// ```rust
+/// use actix_web::middleware::RedirectHttps;
+/// use actix_web::{web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(
+///         RedirectHttps::new()
+///             .map_port(8080, 8443)
+///             .hsts(Some(31_536_000)),
+///     )
+///     .route("/", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RedirectHttps {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    ports: Vec<(u16, u16)>,
+    trust_forwarded: bool,
+    proxies: Vec<IpAddr>,
+    hsts: Option<u64>,
+    hsts_subdomains: bool,
+    hsts_preload: bool,
+}
+
+impl Default for RedirectHttps {
+    fn default() -> Self {
+        RedirectHttps {
+            inner: Rc::new(Inner {
+                ports: Vec::new(),
+                trust_forwarded: false,
+                proxies: Vec::new(),
+                hsts: None,
+                hsts_subdomains: false,
+                hsts_preload: false,
+            }),
+        }
+    }
+}
+
+impl RedirectHttps {
+    /// Construct `RedirectHttps` middleware.
+    pub fn new() -> Self {
+        RedirectHttps::default()
+    }
+
+    /// Redirect requests of the `http` port to the `https` port.
+    ///
+    /// Ports without mapping are dropped from the redirect location.
+    pub fn map_port(mut self, http: u16, https: u16) -> Self {
+        self.inner_mut().ports.push((http, https));
+        self
+    }
+
+    /// Resolve scheme and host of requests with `Forwarded` and
+    /// `X-Forwarded-*` headers.
+    ///
+    /// Enable only if clients can not reach the server directly, otherwise
+    /// they could skip the redirect by sending the headers.
+    pub fn trust_forwarded(mut self, enabled: bool) -> Self {
+        self.inner_mut().trust_forwarded = enabled;
+        self
+    }
+
+    /// Trust forwarded headers of requests from the proxy address only.
+    ///
+    /// Could be called multiple times, enables `trust_forwarded()`.
+    pub fn trusted_proxy(mut self, addr: IpAddr) -> Self {
+        let inner = self.inner_mut();
+        inner.trust_forwarded = true;
+        inner.proxies.push(addr);
+        self
+    }
+
+    /// Set `Strict-Transport-Security` header with max age in seconds to
+    /// responses of secure requests, `None` disables the header.
+    ///
+    /// By default the header is not set.
+    pub fn hsts(mut self, max_age: Option<u64>) -> Self {
+        self.inner_mut().hsts = max_age;
+        self
+    }
+
+    /// Add `includeSubDomains` directive to `Strict-Transport-Security`
+    /// header.
+    pub fn hsts_subdomains(mut self, enabled: bool) -> Self {
+        self.inner_mut().hsts_subdomains = enabled;
+        self
+    }
+
+    /// Add `preload` directive to `Strict-Transport-Security` header.
+    pub fn hsts_preload(mut self, enabled: bool) -> Self {
+        self.inner_mut().hsts_preload = enabled;
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+}
+
+impl Inner {
+    fn hsts_value(&self) -> Option<HeaderValue> {
+        self.hsts.map(|age| {
+            let mut value = format!("max-age={}", age);
+            if self.hsts_subdomains {
+                value.push_str("; includeSubDomains");
+            }
+            if self.hsts_preload {
+                value.push_str("; preload");
+            }
+            HeaderValue::from_str(&value).unwrap()
+        })
+    }
+
+    fn is_trusted(&self, req: &ServiceRequest) -> bool {
+        self.trust_forwarded
+            && (self.proxies.is_empty()
+                || req
+                    .peer_addr()
+                    .map(|addr| self.proxies.contains(&addr.ip()))
+                    == Some(true))
+    }
+
+    /// Https url of the plaintext request.
+    fn location(&self, req: &ServiceRequest, host: &str) -> String {
+        let (name, port) = split_port(host);
+        let port = port.and_then(|port| {
+            self.ports
+                .iter()
+                .find(|(http, _)| *http == port)
+                .map(|(_, https)| *https)
+                .filter(|https| *https != 443)
+        });
+        let path = req
+            .uri()
+            .path_and_query()
+            .map(|pq| pq.as_str())
+            .unwrap_or("/");
+
+        match port {
+            Some(port) => format!("https://{}:{}{}", name, port, path),
+            None => format!("https://{}{}", name, path),
+        }
+    }
+}
+
+/// Split host into name and port, ipv6 addresses are kept in brackets.
+fn split_port(host: &str) -> (&str, Option<u16>) {
+    match host.rfind(':') {
+        Some(idx) if !host[idx..].contains(']') => {
+            (&host[..idx], host[idx + 1..].parse().ok())
+        }
+        _ => (host, None),
+    }
+}
+
+impl<S, B> Transform<S> for RedirectHttps
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RedirectHttpsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RedirectHttpsMiddleware {
+            service,
+            hsts: self.inner.hsts_value(),
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct RedirectHttpsMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+    hsts: Option<HeaderValue>,
+}
+
+impl<S, B> Service for RedirectHttpsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<
+        Ready<Result<Self::Response, Self::Error>>,
+        LocalBoxFuture<'static, Result<Self::Response, Self::Error>>,
+    >;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let (secure, host) = if self.inner.is_trusted(&req) {
+            let info = req.connection_info();
+            (info.scheme() == "https", info.host().to_owned())
+        } else {
+            let secure =
+                req.app_config().secure() || req.uri().scheme_str() == Some("https");
+            let host = req
+                .headers()
+                .get(header::HOST)
+                .and_then(|h| h.to_str().ok())
+                .or_else(|| req.uri().authority().map(|a| a.as_str()))
+                .unwrap_or_else(|| req.app_config().host())
+                .to_owned();
+            (secure, host)
+        };
+
+        if !secure {
+            let status = if req.method() == Method::GET || req.method() == Method::HEAD {
+                StatusCode::MOVED_PERMANENTLY
+            } else {
+                StatusCode::PERMANENT_REDIRECT
+            };
+            let location = self.inner.location(&req, &host);
+            let res = req
+                .into_response(
+                    HttpResponse::build(status)
+                        .header(header::LOCATION, location)
+                        .finish(),
+                )
+                .map_body(|_, _| ResponseBody::Other(Body::Empty));
+            return Either::Left(ok(res));
+        }
+
+        let hsts = self.hsts.clone();
+        let fut = self.service.call(req);
+        Either::Right(
+            async move {
+                let mut res = fut.await?;
+                if let Some(hsts) = hsts {
+                    let headers = res.headers_mut();
+                    if !headers.contains_key(header::STRICT_TRANSPORT_SECURITY) {
+                        headers.insert(header::STRICT_TRANSPORT_SECURITY, hsts);
+                    }
+                }
+                Ok(res)
+            }
+            .boxed_local(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{call_service, init_service, TestRequest};
+    use crate::{web, App};
+
+    fn location(res: &ServiceResponse) -> &str {
+        res.headers()
+            .get(header::LOCATION)
+            .unwrap()
+            .to_str()
+            .unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_redirect() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(RedirectHttps::new().map_port(8080, 8443).map_port(80, 443))
+                .route("/", web::to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/?a=1")
+            .header(header::HOST, "example.com:8080")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(location(&res), "https://example.com:8443/?a=1");
+
+        let req = TestRequest::post()
+            .uri("/")
+            .header(header::HOST, "example.com:80")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(location(&res), "https://example.com/");
+
+        let req = TestRequest::with_uri("/")
+            .header(header::HOST, "[::1]:9000")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(location(&res), "https://[::1]/");
+
+        // forwarded headers are not trusted by default
+        let req = TestRequest::with_uri("/")
+            .header(header::HOST, "example.com")
+            .header("x-forwarded-proto", "https")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+
+        let req = TestRequest::with_uri("https://example.com/").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res
+            .headers()
+            .contains_key(header::STRICT_TRANSPORT_SECURITY));
+    }
+
+    #[actix_rt::test]
+    async fn test_trusted_proxy() {
+        let proxy = "10.0.0.1".parse().unwrap();
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    RedirectHttps::new()
+                        .trusted_proxy(proxy)
+                        .hsts(Some(3600))
+                        .hsts_subdomains(true),
+                )
+                .route("/", web::to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/")
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .header(header::FORWARDED, "proto=https; host=example.com")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()
+                .get(header::STRICT_TRANSPORT_SECURITY)
+                .unwrap(),
+            "max-age=3600; includeSubDomains"
+        );
+
+        let req = TestRequest::with_uri("/")
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .header("x-forwarded-proto", "http")
+            .header("x-forwarded-host", "example.com")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(location(&res), "https://example.com/");
+
+        // headers of other peers are ignored
+        let req = TestRequest::with_uri("/")
+            .peer_addr("10.0.0.2:4000".parse().unwrap())
+            .header(header::HOST, "example.com")
+            .header(header::FORWARDED, "proto=https")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+    }
+}
@@ -0,0 +1,318 @@
+//! `Middleware` for streaming payload inspection
+use std::cell::RefCell;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::error::PayloadError;
+use actix_service::{Service, Transform};
+use bytes::Bytes;
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use futures::Stream;
+
+use crate::dev::Payload;
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::{Error, HttpMessage};
+
+/// Request payload inspector.
+///
+/// Inspector is created for every request and receives payload chunks as
+/// they are read by the handler. Returning an error from any of the methods
+/// vetoes the request: payload stream fails and the error is used as a
+/// response.
+pub trait PayloadInspector: 'static {
+    /// The future returned by inspection methods.
+    type Future: Future<Output = Result<(), Error>>;
+
+    /// Inspect payload chunk before it is passed to the handler.
+    fn on_chunk(&mut self, chunk: Bytes) -> Self::Future;
+
+    /// Called after the last chunk has been inspected.
+    fn on_complete(&mut self) -> Self::Future;
+}
+
+/// `Middleware` for streaming inspection of request payloads.
+///
+/// Useful for content scanning of uploads, i.e. antivirus integration.
+/// Payload is not buffered, every chunk is inspected before it reaches the
+/// handler, so the request can be rejected mid-stream.
+///
+/// ```rust
+/// use actix_web::middleware::{InspectPayload, PayloadInspector};
+/// use actix_web::{error, web, App, Error, HttpResponse};
+/// use futures::future::{ok, err, Ready};
+///
+/// struct NoEicar;
+///
+/// impl PayloadInspector for NoEicar {
+///     type Future = Ready<Result<(), Error>>;
+///
+///     fn on_chunk(&mut self, chunk: web::Bytes) -> Self::Future {
+///         if chunk.starts_with(b"X5O!P%@AP") {
+///             err(error::ErrorForbidden("Infected upload"))
+///         } else {
+///             ok(())
+///         }
+///     }
+///
+///     fn on_complete(&mut self) -> Self::Future {
+///         ok(())
+///     }
+/// }
+///
+/// # fn main() {
+/// let app = App::new().service(
+///     web::scope("/upload")
+///         .wrap(InspectPayload::new(|_| NoEicar))
+///         .route("", web::post().to(|body: web::Bytes| HttpResponse::Ok())),
+/// );
+/// # }
+/// ```
+pub struct InspectPayload<F> {
+    factory: Rc<F>,
+}
+
+impl<F, I> InspectPayload<F>
+where
+    F: Fn(&ServiceRequest) -> I + 'static,
+    I: PayloadInspector,
+{
+    /// Construct `InspectPayload` middleware with inspector factory.
+    pub fn new(factory: F) -> Self {
+        InspectPayload {
+            factory: Rc::new(factory),
+        }
+    }
+}
+
+impl<S, B, F, I> Transform<S> for InspectPayload<F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+    F: Fn(&ServiceRequest) -> I + 'static,
+    I: PayloadInspector,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = InspectPayloadMiddleware<S, F>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(InspectPayloadMiddleware {
+            service,
+            factory: self.factory.clone(),
+        })
+    }
+}
+
+pub struct InspectPayloadMiddleware<S, F> {
+    service: S,
+    factory: Rc<F>,
+}
+
+impl<S, B, F, I> Service for InspectPayloadMiddleware<S, F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+    F: Fn(&ServiceRequest) -> I + 'static,
+    I: PayloadInspector,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let veto = Rc::new(RefCell::new(None));
+        let payload = InspectedPayload {
+            payload: req.take_payload(),
+            inspector: (self.factory)(&req),
+            veto: veto.clone(),
+            pending: None,
+            done: false,
+        };
+        req.set_payload(Payload::Stream(Box::pin(payload)));
+
+        let fut = self.service.call(req);
+        async move {
+            let res = fut.await;
+            let veto = veto.borrow_mut().take();
+            match (res, veto) {
+                (Ok(res), Some(e)) => Ok(res.error_response(e)),
+                (Err(_), Some(e)) => Err(e),
+                (res, None) => res,
+            }
+        }
+        .boxed_local()
+    }
+}
+
+struct InspectedPayload<I: PayloadInspector> {
+    payload: Payload,
+    inspector: I,
+    veto: Rc<RefCell<Option<Error>>>,
+    pending: Option<(Pin<Box<I::Future>>, Option<Bytes>)>,
+    done: bool,
+}
+
+// inspector is never pinned, pending futures are boxed
+impl<I: PayloadInspector> Unpin for InspectedPayload<I> {}
+
+impl<I: PayloadInspector> Stream for InspectedPayload<I> {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            if let Some((ref mut fut, ref mut chunk)) = this.pending {
+                match fut.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok(())) => {
+                        let chunk = chunk.take();
+                        this.pending = None;
+                        return match chunk {
+                            Some(chunk) => Poll::Ready(Some(Ok(chunk))),
+                            None => {
+                                this.done = true;
+                                Poll::Ready(None)
+                            }
+                        };
+                    }
+                    Poll::Ready(Err(e)) => {
+                        log::debug!("Request payload is rejected by inspector: {}", e);
+                        *this.veto.borrow_mut() = Some(e);
+                        this.pending = None;
+                        this.done = true;
+                        return Poll::Ready(Some(Err(PayloadError::Io(
+                            io::Error::new(
+                                io::ErrorKind::Other,
+                                "Payload is rejected by inspector",
+                            ),
+                        ))));
+                    }
+                }
+            }
+
+            match Pin::new(&mut this.payload).poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(chunk))) => {
+                    let fut = this.inspector.on_chunk(chunk.clone());
+                    this.pending = Some((Box::pin(fut), Some(chunk)));
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Ready(None) => {
+                    let fut = this.inspector.on_complete();
+                    this.pending = Some((Box::pin(fut), None));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::{err, ok, Ready};
+
+    use super::*;
+    use crate::error::{ErrorForbidden, ErrorPayloadTooLarge};
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    struct Scanner {
+        size: usize,
+        limit: usize,
+    }
+
+    impl PayloadInspector for Scanner {
+        type Future = Ready<Result<(), Error>>;
+
+        fn on_chunk(&mut self, chunk: Bytes) -> Self::Future {
+            self.size += chunk.len();
+            if chunk.windows(5).any(|w| w == b"virus") {
+                err(ErrorForbidden("infected"))
+            } else {
+                ok(())
+            }
+        }
+
+        fn on_complete(&mut self) -> Self::Future {
+            if self.size > self.limit {
+                err(ErrorPayloadTooLarge("too large"))
+            } else {
+                ok(())
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_inspect_payload() {
+        let mut srv = init_service(
+            App::new()
+                .service(
+                    web::scope("/upload")
+                        .wrap(InspectPayload::new(|_| Scanner { size: 0, limit: 10 }))
+                        .route(
+                            "",
+                            web::post().to(|body: Bytes| HttpResponse::Ok().body(body)),
+                        ),
+                )
+                .route(
+                    "/",
+                    web::post().to(|body: Bytes| HttpResponse::Ok().body(body)),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/upload")
+            .set_payload("clean")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "clean");
+
+        let req = TestRequest::post()
+            .uri("/upload")
+            .set_payload("virus")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let req = TestRequest::post()
+            .uri("/upload")
+            .set_payload("clean but long")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // other routes are not inspected
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload("virus")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,434 @@
+//! `Middleware` for locale negotiation
+use std::cmp::Ordering;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::http::{PathAndQuery, Uri};
+use actix_service::{Service, Transform};
+use bytes::Bytes;
+use futures::future::{ok, Ready};
+use pin_project::pin_project;
+
+use crate::dev::VaryOn;
+use crate::http::header::{ACCEPT_LANGUAGE, COOKIE};
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::types::LocaleSource;
+use crate::{web, Error, HttpMessage};
+
+/// `Middleware` for negotiating request locale.
+///
+/// Locale is taken from the first source that yields one of the supported
+/// locales. By default sources are checked in the following order:
+///
+/// - leading path segment, i.e. `/de/about`
+/// - locale cookie (`locale` by default)
+/// - `Accept-Language` header
+///
+/// If none of them matches, default locale is used. Negotiated locale is
+/// available to handlers via [`web::Locale`](../web/struct.Locale.html)
+/// extractor.
+///
+/// Supported locales are matched case-insensitively. Cookie and header values
+/// also match supported locales by primary language subtag, so `de-AT` is
+/// resolved to `de`. Path prefix has to match supported locale exactly.
+///
+/// ```rust
+/// use actix_web::{middleware, web, App, HttpResponse};
+/// use actix_web::web::LocaleSource;
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(
+///         middleware::Locale::new(&["en", "de", "fr"])
+///             .sources(&[LocaleSource::Cookie, LocaleSource::Header])
+///             .cookie_name("lang"),
+///     )
+///     .route("/", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Locale {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    supported: Vec<String>,
+    default: String,
+    sources: Vec<LocaleSource>,
+    cookie_name: String,
+    strip_prefix: bool,
+}
+
+impl Locale {
+    /// Construct `Locale` middleware for the list of supported locales.
+    ///
+    /// First locale of the list is used as default one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if list of supported locales is empty.
+    pub fn new(supported: &[&str]) -> Self {
+        assert!(!supported.is_empty(), "At least one locale is required");
+
+        Locale {
+            inner: Rc::new(Inner {
+                supported: supported.iter().map(|s| (*s).to_owned()).collect(),
+                default: supported[0].to_owned(),
+                sources: vec![
+                    LocaleSource::Path,
+                    LocaleSource::Cookie,
+                    LocaleSource::Header,
+                ],
+                cookie_name: "locale".to_owned(),
+                strip_prefix: false,
+            }),
+        }
+    }
+
+    /// Set default locale, used when negotiation fails.
+    pub fn default_locale(mut self, locale: &str) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .default = locale.to_owned();
+        self
+    }
+
+    /// Set negotiation sources and the order they are checked in.
+    pub fn sources(mut self, sources: &[LocaleSource]) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .sources = sources.to_vec();
+        self
+    }
+
+    /// Set name of the locale cookie (default: `locale`)
+    pub fn cookie_name(mut self, name: &str) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .cookie_name = name.to_owned();
+        self
+    }
+
+    /// Strip negotiated locale prefix from the request path.
+    ///
+    /// With this option `/de/about` is routed as `/about`, so resources do
+    /// not need to be registered for every locale.
+    pub fn strip_path_prefix(mut self) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .strip_prefix = true;
+        self
+    }
+}
+
+impl Inner {
+    /// Find supported locale matching `tag` exactly.
+    fn find(&self, tag: &str) -> Option<&str> {
+        self.supported
+            .iter()
+            .find(|s| s.eq_ignore_ascii_case(tag))
+            .map(|s| s.as_str())
+    }
+
+    /// Find supported locale matching `tag` exactly or by primary subtag.
+    fn lookup(&self, tag: &str) -> Option<&str> {
+        self.find(tag).or_else(|| {
+            let primary = tag.split('-').next().unwrap_or(tag);
+            self.supported
+                .iter()
+                .find(|s| {
+                    let s = s.split('-').next().unwrap_or(s);
+                    s.eq_ignore_ascii_case(primary)
+                })
+                .map(|s| s.as_str())
+        })
+    }
+
+    fn path_locale(&self, path: &str) -> Option<&str> {
+        let segment = path.trim_start_matches('/').split('/').next()?;
+        self.find(segment)
+    }
+
+    fn header_locale(&self, value: &str) -> Option<&str> {
+        let mut tags: Vec<(&str, f32)> = value
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let tag = parts.next()?.trim();
+                let mut q = 1.0;
+                for param in parts {
+                    let mut kv = param.splitn(2, '=');
+                    if kv.next().map(str::trim) == Some("q") {
+                        q = kv.next()?.trim().parse().ok()?;
+                    }
+                }
+                if tag.is_empty() || q <= 0.0 {
+                    None
+                } else {
+                    Some((tag, q))
+                }
+            })
+            .collect();
+        tags.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+
+        tags.into_iter().find_map(|(tag, _)| {
+            if tag == "*" {
+                Some(self.default.as_str())
+            } else {
+                self.lookup(tag)
+            }
+        })
+    }
+
+    fn negotiate(&self, req: &ServiceRequest) -> web::Locale {
+        for source in &self.sources {
+            let locale = match source {
+                LocaleSource::Path => self.path_locale(req.path()),
+                LocaleSource::Cookie => req
+                    .cookie(&self.cookie_name)
+                    .and_then(|c| self.lookup(c.value())),
+                LocaleSource::Header => req
+                    .headers()
+                    .get(&ACCEPT_LANGUAGE)
+                    .and_then(|hdr| hdr.to_str().ok())
+                    .and_then(|hdr| self.header_locale(hdr)),
+            };
+            if let Some(locale) = locale {
+                return web::Locale::new(locale, Some(*source));
+            }
+        }
+        web::Locale::new(&self.default, None)
+    }
+}
+
+impl<S, B> Transform<S> for Locale
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = LocaleMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(LocaleMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct LocaleMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for LocaleMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocaleResponse<S>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let locale = self.inner.negotiate(&req);
+
+        // response depends on sources checked before negotiated one
+        let mut vary_cookie = false;
+        let mut vary_header = false;
+        for source in &self.inner.sources {
+            match source {
+                LocaleSource::Cookie => vary_cookie = true,
+                LocaleSource::Header => vary_header = true,
+                LocaleSource::Path => (),
+            }
+            if Some(*source) == locale.source() {
+                break;
+            }
+        }
+
+        if self.inner.strip_prefix && locale.source() == Some(LocaleSource::Path) {
+            let head = req.head_mut();
+            let path = head.uri.path();
+            let prefix_len = path.len() - path.trim_start_matches('/').len();
+            let rest = &path[prefix_len + locale.len()..];
+            let path = if rest.is_empty() { "/" } else { rest };
+
+            let mut parts = head.uri.clone().into_parts();
+            let pq = parts.path_and_query.as_ref().unwrap();
+
+            let path = if let Some(q) = pq.query() {
+                Bytes::from(format!("{}?{}", path, q))
+            } else {
+                Bytes::copy_from_slice(path.as_bytes())
+            };
+            parts.path_and_query = Some(PathAndQuery::from_maybe_shared(path).unwrap());
+
+            let uri = Uri::from_parts(parts).unwrap();
+            req.match_info_mut().get_mut().update(&uri);
+            req.head_mut().uri = uri;
+        }
+
+        req.extensions_mut().insert(locale);
+        LocaleResponse {
+            fut: self.service.call(req),
+            vary_cookie,
+            vary_header,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[pin_project]
+pub struct LocaleResponse<S: Service> {
+    #[pin]
+    fut: S::Future,
+    vary_cookie: bool,
+    vary_header: bool,
+}
+
+impl<S, B> Future for LocaleResponse<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = Result<ServiceResponse<B>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        let mut res = futures::ready!(this.fut.poll(cx))?;
+        if *this.vary_cookie {
+            res.response_mut().vary_on(COOKIE);
+        }
+        if *this.vary_header {
+            res.response_mut().vary_on(ACCEPT_LANGUAGE);
+        }
+        Poll::Ready(Ok(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Cookie;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App};
+
+    async fn index(locale: web::Locale) -> String {
+        format!("{:?} {}", locale.source(), locale)
+    }
+
+    #[actix_rt::test]
+    async fn test_negotiation_order() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(Locale::new(&["en", "de", "fr-CA"]))
+                .route("/", web::get().to(index))
+                .route("/{tail}/", web::get().to(index)),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(
+            res.headers().get(crate::http::header::VARY).unwrap(),
+            "cookie, accept-language"
+        );
+        assert_eq!(read_body(res).await, "None en");
+
+        let req = TestRequest::default()
+            .header(ACCEPT_LANGUAGE, "ru, de-AT;q=0.8, fr;q=0.9")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(read_body(res).await, "Some(Header) fr-CA");
+
+        let req = TestRequest::default()
+            .header(ACCEPT_LANGUAGE, "de")
+            .cookie(Cookie::new("locale", "EN"))
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.headers().get(crate::http::header::VARY).unwrap(), "cookie");
+        assert_eq!(read_body(res).await, "Some(Cookie) en");
+
+        let req = TestRequest::with_uri("/de/")
+            .header(ACCEPT_LANGUAGE, "fr")
+            .cookie(Cookie::new("locale", "en"))
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert!(res.headers().get(crate::http::header::VARY).is_none());
+        assert_eq!(read_body(res).await, "Some(Path) de");
+    }
+
+    #[actix_rt::test]
+    async fn test_custom_sources() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    Locale::new(&["en", "de"])
+                        .default_locale("de")
+                        .sources(&[LocaleSource::Header, LocaleSource::Cookie])
+                        .cookie_name("lang"),
+                )
+                .route("/{tail}/", web::get().to(index)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/en/")
+            .cookie(Cookie::new("locale", "en"))
+            .header(ACCEPT_LANGUAGE, "ru, *;q=0.5")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(read_body(res).await, "Some(Header) de");
+
+        let req = TestRequest::with_uri("/en/")
+            .cookie(Cookie::new("lang", "en-GB"))
+            .header(ACCEPT_LANGUAGE, "ru, en;q=0")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(read_body(res).await, "Some(Cookie) en");
+    }
+
+    #[actix_rt::test]
+    async fn test_strip_path_prefix() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(Locale::new(&["en", "de"]).strip_path_prefix())
+                .route("/", web::get().to(index))
+                .route("/about", web::get().to(index)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/de/about?x=1").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(read_body(res).await, "Some(Path) de");
+
+        let req = TestRequest::with_uri("/en").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(read_body(res).await, "Some(Path) en");
+
+        let req = TestRequest::with_uri("/about").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(read_body(res).await, "None en");
+    }
+
+    #[actix_rt::test]
+    async fn test_not_configured() {
+        let mut srv = init_service(App::new().route("/", web::get().to(index))).await;
+
+        let req = TestRequest::default().to_request();
+        let res = call_service(&mut srv, req).await;
+        assert!(res.status().is_server_error());
+    }
+}
@@ -18,7 +18,7 @@ use time::OffsetDateTime;
 
 use crate::dev::{BodySize, MessageBody, ResponseBody};
 use crate::error::{Error, Result};
-use crate::http::{HeaderName, StatusCode};
+use crate::http::{HeaderMap, HeaderName, StatusCode};
 use crate::service::{ServiceRequest, ServiceResponse};
 use crate::HttpResponse;
 
@@ -280,6 +280,9 @@ impl<B: MessageBody> MessageBody for StreamLog<B> {
             val => val,
         }
     }
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        self.project().body.take_trailers()
+    }
 }
 
 /// A formatting style for the `Logger`, consisting of multiple
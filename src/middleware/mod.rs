@@ -3,15 +3,55 @@
 #[cfg(feature = "compress")]
 mod compress;
 #[cfg(feature = "compress")]
-pub use self::compress::Compress;
+pub use self::compress::{Compress, NegotiatedEncoding, PrimedResponse};
+#[cfg(feature = "compress")]
+pub use actix_http::encoding::{CompressionLevels, Variants};
+#[cfg(feature = "compress-dictionary")]
+pub use actix_http::encoding::Dictionary;
 
+mod allowed_hosts;
+mod blocking;
+mod client_hints;
 mod condition;
 mod defaultheaders;
+mod deverrors;
 pub mod errhandlers;
+mod fetch;
+pub mod flash;
+mod from_fn;
+mod https;
+mod inspect;
+mod locale;
 mod logger;
 mod normalize;
+mod profile;
+mod registry;
+mod response_limit;
+mod shadow;
+mod signature;
+mod single_flight;
+mod sniff;
+mod tenant;
 
-pub use self::condition::Condition;
+pub use self::allowed_hosts::AllowedHosts;
+pub use self::blocking::{BlockedPoll, DetectBlocking};
+pub use self::client_hints::ClientHints;
+pub use self::condition::{Condition, ConditionWhen};
 pub use self::defaultheaders::DefaultHeaders;
+pub use self::deverrors::DevErrors;
+pub use self::fetch::{FetchMetadata, FetchMode, FetchPolicy, FetchSite};
+pub use self::flash::FlashMessages;
+pub use self::from_fn::{from_fn, MiddlewareFn, Next};
+pub use self::https::RedirectHttps;
+pub use self::inspect::{InspectPayload, PayloadInspector};
+pub use self::locale::Locale;
 pub use self::logger::Logger;
 pub use self::normalize::NormalizePath;
+pub use self::profile::{Profile, ProfileSettings};
+pub use self::registry::Registry;
+pub use self::response_limit::{LimitPolicy, ResponseLimit};
+pub use self::shadow::Shadow;
+pub use self::signature::VerifySignature;
+pub use self::single_flight::SingleFlight;
+pub use self::sniff::ContentSniffer;
+pub use self::tenant::{TenantConfig, Tenants};
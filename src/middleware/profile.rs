@@ -0,0 +1,420 @@
+//! `Middleware` for environment profile defaults
+use std::env;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_service::{Service, Transform};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use super::deverrors::{default_redact, error_page};
+use crate::dev::{Body, ResponseBody};
+use crate::error::Error;
+use crate::http::header::{self, HeaderName, HeaderValue};
+use crate::http::Method;
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::HttpResponse;
+
+/// Deployment profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    /// Development profile: verbose error pages, permissive CORS, no
+    /// security headers.
+    Dev,
+    /// Production profile: plain error responses, no CORS, HSTS and
+    /// security headers.
+    Prod,
+}
+
+impl Profile {
+    /// Select profile with `ACTIX_PROFILE` environment variable.
+    ///
+    /// Variable accepts `dev` and `prod` values, if it is not set or has
+    /// unknown value `Prod` profile is used, so development profile is only
+    /// enabled explicitly.
+    pub fn from_env() -> Self {
+        match env::var("ACTIX_PROFILE").as_ref().map(|s| s.as_str()) {
+            Ok("dev") | Ok("development") => Profile::Dev,
+            Ok("prod") | Ok("production") => Profile::Prod,
+            Ok(val) => {
+                log::warn!("Unknown ACTIX_PROFILE value: {:?}", val);
+                Profile::Prod
+            }
+            Err(_) => Profile::Prod,
+        }
+    }
+
+    /// Get default settings of the profile.
+    pub fn settings(self) -> ProfileSettings {
+        ProfileSettings::new(self)
+    }
+}
+
+/// `Middleware` that applies bundle of profile settings.
+///
+/// Settings are initialized with profile defaults, every item can be
+/// overridden.
+///
+/// | Setting          | `Dev`       | `Prod`                                |
+/// |------------------|-------------|---------------------------------------|
+/// | verbose errors   | enabled     | disabled                              |
+/// | permissive CORS  | enabled     | disabled                              |
+/// | HSTS             | disabled    | `max-age=31536000; includeSubDomains` |
+/// | nosniff          | disabled    | enabled                               |
+/// | frame options    | disabled    | `DENY`                                |
+///
+/// Verbose errors render error pages for `500 Internal Server Error`
+/// responses, see [`DevErrors`](struct.DevErrors.html). Permissive CORS
+/// answers all preflight requests and allows any origin, credentialed
+/// requests are not allowed.
+/// HSTS header is only set for https requests.
+///
+/// ```rust
+/// use actix_web::middleware::Profile;
+/// use actix_web::{web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .profile(Profile::from_env().settings().frame_options(None))
+///     .route("/", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ProfileSettings {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    profile: Profile,
+    verbose_errors: bool,
+    permissive_cors: bool,
+    hsts: Option<HeaderValue>,
+    nosniff: bool,
+    frame_options: Option<HeaderValue>,
+    redact: Vec<HeaderName>,
+}
+
+impl ProfileSettings {
+    /// Construct settings with profile defaults.
+    pub fn new(profile: Profile) -> Self {
+        let dev = profile == Profile::Dev;
+
+        ProfileSettings {
+            inner: Rc::new(Inner {
+                profile,
+                verbose_errors: dev,
+                permissive_cors: dev,
+                hsts: if dev {
+                    None
+                } else {
+                    Some(HeaderValue::from_static(
+                        "max-age=31536000; includeSubDomains",
+                    ))
+                },
+                nosniff: !dev,
+                frame_options: if dev {
+                    None
+                } else {
+                    Some(HeaderValue::from_static("DENY"))
+                },
+                redact: default_redact(),
+            }),
+        }
+    }
+
+    /// Get profile of the settings.
+    pub fn profile(&self) -> Profile {
+        self.inner.profile
+    }
+
+    /// Render detailed error pages.
+    pub fn verbose_errors(mut self, enabled: bool) -> Self {
+        self.inner_mut().verbose_errors = enabled;
+        self
+    }
+
+    /// Allow cross origin requests without credentials from any origin.
+    pub fn permissive_cors(mut self, enabled: bool) -> Self {
+        self.inner_mut().permissive_cors = enabled;
+        self
+    }
+
+    /// Set `Strict-Transport-Security` max age in seconds, `None` disables
+    /// the header.
+    pub fn hsts(mut self, max_age: Option<u64>) -> Self {
+        self.inner_mut().hsts = max_age.map(|age| {
+            HeaderValue::from_str(&format!("max-age={}; includeSubDomains", age))
+                .unwrap()
+        });
+        self
+    }
+
+    /// Set `X-Content-Type-Options: nosniff` header.
+    pub fn nosniff(mut self, enabled: bool) -> Self {
+        self.inner_mut().nosniff = enabled;
+        self
+    }
+
+    /// Set `X-Frame-Options` header value, `None` disables the header.
+    pub fn frame_options(mut self, value: Option<&'static str>) -> Self {
+        self.inner_mut().frame_options = value.map(HeaderValue::from_static);
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+}
+
+impl From<Profile> for ProfileSettings {
+    fn from(profile: Profile) -> Self {
+        ProfileSettings::new(profile)
+    }
+}
+
+impl<S, B> Transform<S> for ProfileSettings
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ProfileMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ProfileMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct ProfileMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for ProfileMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let inner = self.inner.clone();
+        let origin = if inner.permissive_cors {
+            req.headers().get(header::ORIGIN).cloned()
+        } else {
+            None
+        };
+
+        // answer cors preflight requests
+        if let Some(ref origin) = origin {
+            if req.method() == Method::OPTIONS
+                && req
+                    .headers()
+                    .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+            {
+                let mut res = HttpResponse::NoContent();
+                res.header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone())
+                    .header(header::ACCESS_CONTROL_MAX_AGE, "3600")
+                    .header(header::VARY, "Origin");
+                if let Some(method) =
+                    req.headers().get(header::ACCESS_CONTROL_REQUEST_METHOD)
+                {
+                    res.header(header::ACCESS_CONTROL_ALLOW_METHODS, method.clone());
+                }
+                if let Some(headers) =
+                    req.headers().get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+                {
+                    res.header(header::ACCESS_CONTROL_ALLOW_HEADERS, headers.clone());
+                }
+
+                let res = req
+                    .into_response(res.finish())
+                    .map_body(|_, _| ResponseBody::Other(Body::Empty));
+                return ok(res).boxed_local();
+            }
+        }
+
+        let secure = req.connection_info().scheme() == "https";
+        let fut = self.service.call(req);
+
+        async move {
+            let mut res = fut.await?;
+            if inner.verbose_errors {
+                res = error_page(res, &inner.redact);
+            }
+
+            let headers = res.headers_mut();
+            if let Some(origin) = origin {
+                headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+                headers.append(header::VARY, HeaderValue::from_static("Origin"));
+            }
+            if let Some(ref hsts) = inner.hsts {
+                if secure && !headers.contains_key(header::STRICT_TRANSPORT_SECURITY) {
+                    headers.insert(header::STRICT_TRANSPORT_SECURITY, hsts.clone());
+                }
+            }
+            if inner.nosniff && !headers.contains_key(header::X_CONTENT_TYPE_OPTIONS) {
+                headers.insert(
+                    header::X_CONTENT_TYPE_OPTIONS,
+                    HeaderValue::from_static("nosniff"),
+                );
+            }
+            if let Some(ref value) = inner.frame_options {
+                if !headers.contains_key(header::X_FRAME_OPTIONS) {
+                    headers.insert(header::X_FRAME_OPTIONS, value.clone());
+                }
+            }
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorInternalServerError;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App};
+
+    #[actix_rt::test]
+    async fn test_dev_profile() {
+        let mut srv = init_service(
+            App::new()
+                .profile(Profile::Dev)
+                .route("/", web::get().to(|| HttpResponse::Ok()))
+                .route(
+                    "/err",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(ErrorInternalServerError("<oops>"))
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/")
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "http://localhost:3000")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "x-token")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "http://localhost:3000"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_METHODS)
+                .unwrap(),
+            "PUT"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_HEADERS)
+                .unwrap(),
+            "x-token"
+        );
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+
+        let req = TestRequest::with_uri("/")
+            .header(header::ORIGIN, "http://localhost:3000")
+            .header("x-forwarded-proto", "https")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+        assert!(!res
+            .headers()
+            .contains_key(header::STRICT_TRANSPORT_SECURITY));
+        assert!(!res.headers().contains_key(header::X_FRAME_OPTIONS));
+
+        let req = TestRequest::with_uri("/err").to_request();
+        let res = call_service(&mut srv, req).await;
+        let body = read_body(res).await;
+        assert!(std::str::from_utf8(&body).unwrap().contains("&lt;oops&gt;"));
+    }
+
+    #[actix_rt::test]
+    async fn test_prod_profile() {
+        let mut srv = init_service(
+            App::new()
+                .profile(Profile::Prod.settings().frame_options(Some("SAMEORIGIN")))
+                .route("/", web::get().to(|| HttpResponse::Ok()))
+                .route(
+                    "/err",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(ErrorInternalServerError("<oops>"))
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/")
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "http://localhost:3000")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        let req = TestRequest::with_uri("/")
+            .header("x-forwarded-proto", "https")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(
+            res.headers()
+                .get(header::STRICT_TRANSPORT_SECURITY)
+                .unwrap(),
+            "max-age=31536000; includeSubDomains"
+        );
+        assert_eq!(
+            res.headers().get(header::X_CONTENT_TYPE_OPTIONS).unwrap(),
+            "nosniff"
+        );
+        assert_eq!(
+            res.headers().get(header::X_FRAME_OPTIONS).unwrap(),
+            "SAMEORIGIN"
+        );
+
+        // hsts is not set for plain http
+        let req = TestRequest::with_uri("/").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert!(!res
+            .headers()
+            .contains_key(header::STRICT_TRANSPORT_SECURITY));
+
+        let req = TestRequest::with_uri("/err").to_request();
+        let res = call_service(&mut srv, req).await;
+        let body = read_body(res).await;
+        assert!(!std::str::from_utf8(&body).unwrap().contains("<html>"));
+    }
+}
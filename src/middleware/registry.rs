@@ -0,0 +1,390 @@
+//! `Middleware` registry with named and ordered middleware
+use std::fmt;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::body::{Body, MessageBody, ResponseBody};
+use actix_service::boxed::{self, BoxService};
+use actix_service::{Service, Transform};
+use futures::future::{FutureExt, LocalBoxFuture};
+
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::Error;
+
+type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
+type TransformFn =
+    Rc<dyn Fn(HttpService) -> LocalBoxFuture<'static, Result<HttpService, ()>>>;
+
+/// Registry of named middleware.
+///
+/// Middleware are ordered by priority, lower priority middleware processes
+/// request first, middleware with same priority are ordered by registration.
+/// Middleware could also be inserted before or after already registered
+/// middleware, so libraries do not depend on order of `.wrap()` calls.
+/// Effective order could be inspected with `Registry::names()`.
+///
+/// Registry is registered with `App::wrap()` and behaves as a single
+/// middleware, response body of the registry is `dev::Body`.
+///
+/// ```rust
+/// use actix_web::middleware::{DefaultHeaders, Logger, Registry};
+/// use actix_web::{web, App, HttpResponse};
+///
+/// # fn main() {
+/// let registry = Registry::new()
+///     .register("logger", Logger::default())
+///     .register_priority("headers", 10, DefaultHeaders::new().header("x-version", "1.2"))
+///     .insert_after("logger", "normalize", actix_web::middleware::NormalizePath);
+///
+/// assert_eq!(registry.names(), vec!["logger", "normalize", "headers"]);
+///
+/// let app = App::new()
+///     .wrap(registry)
+///     .route("/", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone)]
+struct Entry {
+    name: String,
+    priority: i32,
+    transform: TransformFn,
+}
+
+impl Registry {
+    /// Create empty registry.
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Register middleware with default priority `0`.
+    pub fn register<N, M, B>(self, name: N, mw: M) -> Self
+    where
+        N: Into<String>,
+        M: Transform<
+                HttpService,
+                Request = ServiceRequest,
+                Response = ServiceResponse<B>,
+                Error = Error,
+            > + 'static,
+        M::Future: 'static,
+        M::Transform: 'static,
+        M::InitError: fmt::Debug,
+        <M::Transform as Service>::Future: 'static,
+        B: MessageBody + Unpin + 'static,
+    {
+        self.register_priority(name, 0, mw)
+    }
+
+    /// Register middleware with priority.
+    pub fn register_priority<N, M, B>(mut self, name: N, priority: i32, mw: M) -> Self
+    where
+        N: Into<String>,
+        M: Transform<
+                HttpService,
+                Request = ServiceRequest,
+                Response = ServiceResponse<B>,
+                Error = Error,
+            > + 'static,
+        M::Future: 'static,
+        M::Transform: 'static,
+        M::InitError: fmt::Debug,
+        <M::Transform as Service>::Future: 'static,
+        B: MessageBody + Unpin + 'static,
+    {
+        let entry = Entry::new(name.into(), priority, mw);
+        self.entries.push(entry);
+        self
+    }
+
+    /// Register middleware that processes request right before middleware
+    /// `before`.
+    ///
+    /// Inserted middleware gets priority of `before` middleware. If `before`
+    /// is not registered, middleware is registered with default priority.
+    pub fn insert_before<N, M, B>(self, before: &str, name: N, mw: M) -> Self
+    where
+        N: Into<String>,
+        M: Transform<
+                HttpService,
+                Request = ServiceRequest,
+                Response = ServiceResponse<B>,
+                Error = Error,
+            > + 'static,
+        M::Future: 'static,
+        M::Transform: 'static,
+        M::InitError: fmt::Debug,
+        <M::Transform as Service>::Future: 'static,
+        B: MessageBody + Unpin + 'static,
+    {
+        self.insert(before, 0, name.into(), mw)
+    }
+
+    /// Register middleware that processes request right after middleware
+    /// `after`.
+    ///
+    /// Inserted middleware gets priority of `after` middleware. If `after`
+    /// is not registered, middleware is registered with default priority.
+    pub fn insert_after<N, M, B>(self, after: &str, name: N, mw: M) -> Self
+    where
+        N: Into<String>,
+        M: Transform<
+                HttpService,
+                Request = ServiceRequest,
+                Response = ServiceResponse<B>,
+                Error = Error,
+            > + 'static,
+        M::Future: 'static,
+        M::Transform: 'static,
+        M::InitError: fmt::Debug,
+        <M::Transform as Service>::Future: 'static,
+        B: MessageBody + Unpin + 'static,
+    {
+        self.insert(after, 1, name.into(), mw)
+    }
+
+    fn insert<M, B>(mut self, anchor: &str, offset: usize, name: String, mw: M) -> Self
+    where
+        M: Transform<
+                HttpService,
+                Request = ServiceRequest,
+                Response = ServiceResponse<B>,
+                Error = Error,
+            > + 'static,
+        M::Future: 'static,
+        M::Transform: 'static,
+        M::InitError: fmt::Debug,
+        <M::Transform as Service>::Future: 'static,
+        B: MessageBody + Unpin + 'static,
+    {
+        if let Some(idx) = self.entries.iter().position(|e| e.name == anchor) {
+            let entry = Entry::new(name, self.entries[idx].priority, mw);
+            self.entries.insert(idx + offset, entry);
+            self
+        } else {
+            self.register_priority(name, 0, mw)
+        }
+    }
+
+    /// Check if middleware is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.iter().any(|e| e.name == name)
+    }
+
+    /// Names of registered middleware in order of request processing.
+    pub fn names(&self) -> Vec<&str> {
+        self.ordered().map(|e| e.name.as_str()).collect()
+    }
+
+    fn ordered(&self) -> impl Iterator<Item = &Entry> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        // stable sort keeps registration order
+        entries.sort_by_key(|e| e.priority);
+        entries.into_iter()
+    }
+}
+
+impl Entry {
+    fn new<M, B>(name: String, priority: i32, mw: M) -> Self
+    where
+        M: Transform<
+                HttpService,
+                Request = ServiceRequest,
+                Response = ServiceResponse<B>,
+                Error = Error,
+            > + 'static,
+        M::Future: 'static,
+        M::Transform: 'static,
+        M::InitError: fmt::Debug,
+        <M::Transform as Service>::Future: 'static,
+        B: MessageBody + Unpin + 'static,
+    {
+        let mw = Rc::new(mw);
+        let mw_name = name.clone();
+        let transform: TransformFn = Rc::new(move |service| {
+            let name = mw_name.clone();
+            mw.new_transform(service)
+                .map(move |res| match res {
+                    Ok(srv) => Ok(boxed::service(BodyService(srv))),
+                    Err(e) => {
+                        log::error!("Can not construct middleware {}: {:?}", name, e);
+                        Err(())
+                    }
+                })
+                .boxed_local()
+        });
+
+        Entry {
+            name,
+            priority,
+            transform,
+        }
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.ordered().map(|e| (&e.name, e.priority)))
+            .finish()
+    }
+}
+
+impl<S, B> Transform<S> for Registry
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: MessageBody + Unpin + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RegistryMiddleware;
+    type Future = LocalBoxFuture<'static, Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        // innermost middleware is created first
+        let transforms: Vec<_> = self.ordered().map(|e| e.transform.clone()).collect();
+
+        async move {
+            let mut service = boxed::service(BodyService(service));
+            for transform in transforms.iter().rev() {
+                service = transform(service).await?;
+            }
+            Ok(RegistryMiddleware { service })
+        }
+        .boxed_local()
+    }
+}
+
+#[doc(hidden)]
+pub struct RegistryMiddleware {
+    service: HttpService,
+}
+
+impl Service for RegistryMiddleware {
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<ServiceResponse, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        self.service.call(req)
+    }
+}
+
+/// Converts response body of middleware to `Body`.
+struct BodyService<S>(S);
+
+impl<S, B> Service for BodyService<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + Unpin + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<ServiceResponse, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        self.0
+            .call(req)
+            .map(|res| {
+                res.map(|res| {
+                    res.map_body(|_, body| ResponseBody::Other(Body::from_message(body)))
+                })
+            })
+            .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dev::MessageBody;
+    use crate::middleware::{from_fn, DefaultHeaders, Next};
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    fn trace(
+        name: &'static str,
+    ) -> impl Fn(
+        ServiceRequest,
+        Next<Body>,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse<Body>, Error>> {
+        move |req, next| {
+            async move {
+                let mut res = next.call(req).await?;
+                let mut trace = res
+                    .headers()
+                    .get("x-trace")
+                    .map(|v| v.to_str().unwrap().to_owned())
+                    .unwrap_or_default();
+                trace.push_str(name);
+                res.headers_mut()
+                    .insert("x-trace".parse().unwrap(), trace.parse().unwrap());
+                Ok(res)
+            }
+            .boxed_local()
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let registry = Registry::new()
+            .register("a", from_fn(trace("a")))
+            .register_priority("z", -1, from_fn(trace("z")))
+            .register("b", from_fn(trace("b")))
+            .insert_before("a", "c", from_fn(trace("c")))
+            .insert_after("z", "d", from_fn(trace("d")))
+            .insert_after("missing", "e", from_fn(trace("e")));
+
+        assert_eq!(registry.names(), vec!["z", "d", "c", "a", "b", "e"]);
+        assert!(registry.contains("d"));
+        assert!(!registry.contains("missing"));
+        assert_eq!(
+            format!("{:?}", registry),
+            r#"[("z", -1), ("d", -1), ("c", 0), ("a", 0), ("b", 0), ("e", 0)]"#
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_registry() {
+        let registry = Registry::new()
+            .register("a", from_fn(trace("a")))
+            .register("headers", DefaultHeaders::new().header("x-test", "1"))
+            .insert_before("a", "b", from_fn(trace("b")));
+
+        let mut srv = init_service(
+            App::new()
+                .wrap(registry)
+                .route("/", web::get().to(|| HttpResponse::Ok().body("test"))),
+        )
+        .await;
+
+        let resp = call_service(&mut srv, TestRequest::default().to_request()).await;
+        // innermost middleware modifies response first
+        assert_eq!(resp.headers().get("x-trace").unwrap(), "ab");
+        assert_eq!(resp.headers().get("x-test").unwrap(), "1");
+        assert_eq!(
+            resp.response().body().size(),
+            actix_http::body::BodySize::Sized(4)
+        );
+        assert_eq!(read_body(resp).await, "test");
+    }
+}
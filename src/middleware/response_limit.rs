@@ -0,0 +1,367 @@
+//! `Middleware` for limiting response body size
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::body::{Body, BodySize, MessageBody, ResponseBody};
+use actix_http::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use actix_http::http::{HeaderMap, StatusCode};
+use actix_service::{Service, Transform};
+use bytes::Bytes;
+use futures::future::{ok, Ready};
+use pin_project::pin_project;
+
+use crate::error::{Error, ErrorInternalServerError};
+use crate::request::HttpRequest;
+use crate::service::{ServiceRequest, ServiceResponse};
+
+/// What to do with response that exceeds the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitPolicy {
+    /// Abort response. Responses of known size are replaced with
+    /// *INTERNAL SERVER ERROR* response, streaming responses are
+    /// interrupted with an error and connection is closed.
+    Abort,
+    /// Truncate response body to the limit.
+    Truncate,
+}
+
+/// `Middleware` for limiting response body size.
+///
+/// Protects clients and proxies from oversized responses of buggy
+/// handlers. Exceeded limit is logged with `warn` level and reported to
+/// `on_exceeded` callback, if it is set.
+///
+/// ```rust
+/// use actix_web::middleware::{LimitPolicy, ResponseLimit};
+/// use actix_web::{web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(
+///         ResponseLimit::new(16 * 1024 * 1024)
+///             .policy(LimitPolicy::Truncate)
+///             .on_exceeded(|req, limit| {
+///                 eprintln!("{} response exceeds {} bytes", req.path(), limit);
+///             }),
+///     )
+///     .route("/", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ResponseLimit {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    limit: u64,
+    policy: LimitPolicy,
+    on_exceeded: Option<Box<dyn Fn(&HttpRequest, u64)>>,
+}
+
+impl ResponseLimit {
+    /// Create middleware with limit in bytes, responses that exceed the
+    /// limit are aborted.
+    pub fn new(limit: u64) -> Self {
+        ResponseLimit {
+            inner: Rc::new(Inner {
+                limit,
+                policy: LimitPolicy::Abort,
+                on_exceeded: None,
+            }),
+        }
+    }
+
+    /// Set policy for responses that exceed the limit.
+    pub fn policy(mut self, policy: LimitPolicy) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .policy = policy;
+        self
+    }
+
+    /// Set callback, that is called when response exceeds the limit.
+    pub fn on_exceeded<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HttpRequest, u64) + 'static,
+    {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .on_exceeded = Some(Box::new(f));
+        self
+    }
+}
+
+impl Inner {
+    fn exceeded(&self, req: &HttpRequest) {
+        log::warn!(
+            "Response size limit of {} bytes is exceeded: {} {}",
+            self.limit,
+            req.method(),
+            req.path()
+        );
+        if let Some(ref f) = self.on_exceeded {
+            f(req, self.limit);
+        }
+    }
+}
+
+impl<S, B> Transform<S> for ResponseLimit
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<LimitedBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ResponseLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ResponseLimitMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct ResponseLimitMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for ResponseLimitMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<LimitedBody<B>>;
+    type Error = Error;
+    type Future = ResponseLimitResponse<S, B>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        ResponseLimitResponse {
+            fut: self.service.call(req),
+            inner: self.inner.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[pin_project]
+pub struct ResponseLimitResponse<S, B>
+where
+    S: Service,
+    B: MessageBody,
+{
+    #[pin]
+    fut: S::Future,
+    inner: Rc<Inner>,
+    _t: PhantomData<B>,
+}
+
+impl<S, B> Future for ResponseLimitResponse<S, B>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Output = Result<ServiceResponse<LimitedBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        let res = futures::ready!(this.fut.poll(cx))?;
+        let inner = this.inner.clone();
+        let req = res.request().clone();
+
+        let size = match res.response().body().size() {
+            BodySize::Sized(size) => Some(size as u64),
+            BodySize::Sized64(size) => Some(size),
+            _ => None,
+        };
+        let exceeded = size.map_or(false, |size| size > inner.limit);
+
+        if exceeded {
+            inner.exceeded(&req);
+            if inner.policy == LimitPolicy::Abort {
+                return Poll::Ready(Ok(res.map_body(|head, _| {
+                    head.status = StatusCode::INTERNAL_SERVER_ERROR;
+                    head.headers_mut().remove(CONTENT_TYPE);
+                    head.headers_mut().remove(CONTENT_LENGTH);
+                    ResponseBody::Other(Body::Empty)
+                })));
+            }
+        }
+
+        Poll::Ready(Ok(res.map_body(move |_, body| {
+            ResponseBody::Body(LimitedBody {
+                body,
+                remaining: inner.limit,
+                // exceeded limit of sized body is already reported
+                req: if exceeded { None } else { Some(req) },
+                inner,
+                done: false,
+            })
+        })))
+    }
+}
+
+/// Response body with size limit.
+#[pin_project]
+pub struct LimitedBody<B> {
+    #[pin]
+    body: ResponseBody<B>,
+    remaining: u64,
+    req: Option<HttpRequest>,
+    inner: Rc<Inner>,
+    done: bool,
+}
+
+impl<B: MessageBody> MessageBody for LimitedBody<B> {
+    fn size(&self) -> BodySize {
+        match self.body.size() {
+            BodySize::Sized(size) if size as u64 > self.inner.limit => {
+                BodySize::Sized64(self.inner.limit)
+            }
+            BodySize::Sized64(size) if size > self.inner.limit => {
+                BodySize::Sized64(self.inner.limit)
+            }
+            size => size,
+        }
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        let this = self.project();
+
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        match futures::ready!(this.body.poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                if chunk.len() as u64 <= *this.remaining {
+                    *this.remaining -= chunk.len() as u64;
+                    return Poll::Ready(Some(Ok(chunk)));
+                }
+
+                *this.done = true;
+                if let Some(req) = this.req.take() {
+                    this.inner.exceeded(&req);
+                }
+
+                match this.inner.policy {
+                    LimitPolicy::Abort => Poll::Ready(Some(Err(ErrorInternalServerError(
+                        "Response size limit is exceeded",
+                    )))),
+                    LimitPolicy::Truncate => {
+                        let remaining = *this.remaining as usize;
+                        *this.remaining = 0;
+                        if remaining == 0 {
+                            Poll::Ready(None)
+                        } else {
+                            Poll::Ready(Some(Ok(chunk.slice(..remaining))))
+                        }
+                    }
+                }
+            }
+            item => Poll::Ready(item),
+        }
+    }
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        let this = self.project();
+        // truncated body is not complete, trailers do not describe it
+        if *this.done {
+            None
+        } else {
+            this.body.take_trailers()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use futures::stream::{self, StreamExt};
+
+    use super::*;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    fn chunks() -> HttpResponse {
+        HttpResponse::Ok().streaming(
+            stream::iter(vec!["1234", "5678", "90"])
+                .map(|s| Ok::<_, Error>(Bytes::from_static(s.as_bytes()))),
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_abort() {
+        let count = Rc::new(Cell::new(0));
+        let count2 = count.clone();
+        let mut srv = init_service(
+            App::new()
+                .wrap(ResponseLimit::new(6).on_exceeded(move |req, limit| {
+                    assert_eq!(limit, 6);
+                    assert!(req.path().starts_with("/big"));
+                    count2.set(count2.get() + 1);
+                }))
+                .route("/", web::get().to(|| HttpResponse::Ok().body("small")))
+                .route("/big", web::get().to(|| HttpResponse::Ok().body("1234567")))
+                .route("/big/stream", web::get().to(chunks)),
+        )
+        .await;
+
+        let resp = call_service(&mut srv, TestRequest::default().to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, "small");
+        assert_eq!(count.get(), 0);
+
+        let req = TestRequest::with_uri("/big").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(count.get(), 1);
+
+        let req = TestRequest::with_uri("/big/stream").to_request();
+        let mut resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let mut body = resp.take_body();
+        assert_eq!(body.next().await.unwrap().unwrap(), "1234");
+        assert!(body.next().await.unwrap().is_err());
+        assert!(body.next().await.is_none());
+        assert_eq!(count.get(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_truncate() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(ResponseLimit::new(6).policy(LimitPolicy::Truncate))
+                .route("/", web::get().to(|| HttpResponse::Ok().body("1234567")))
+                .route("/stream", web::get().to(chunks)),
+        )
+        .await;
+
+        let resp = call_service(&mut srv, TestRequest::default().to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.response().body().size(), BodySize::Sized64(6));
+        assert_eq!(read_body(resp).await, "123456");
+
+        let req = TestRequest::with_uri("/stream").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, "123456");
+    }
+}
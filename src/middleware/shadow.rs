@@ -0,0 +1,397 @@
+//! `Middleware` for mirroring requests to a shadow upstream
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use actix_http::error::PayloadError;
+use actix_service::{Service, Transform};
+use awc::Client;
+use bytes::{Bytes, BytesMut};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use futures::stream::{self, StreamExt};
+
+use crate::dev::Payload;
+use crate::http::header::{self, HeaderName};
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::{Error, HttpMessage};
+
+/// Headers that are not forwarded to the shadow upstream.
+const SKIP_HEADERS: &[HeaderName] = &[
+    header::CONNECTION,
+    header::CONTENT_LENGTH,
+    header::HOST,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+/// Headers that are forwarded only if
+/// [`forward_credentials`](struct.Shadow.html#method.forward_credentials)
+/// is enabled.
+const CREDENTIAL_HEADERS: &[HeaderName] = &[header::AUTHORIZATION, header::COOKIE];
+
+/// `Middleware` for mirroring requests to a shadow upstream.
+///
+/// A sample of requests is duplicated to the shadow upstream, i.e. a new
+/// version of a backend, in background. Shadow responses are discarded and
+/// failures are only logged, so the primary response is never affected.
+///
+/// Request body is buffered before calling the primary service. Requests
+/// with bodies larger than the limit (default: 64kB) are not mirrored,
+/// primary service receives the body unchanged.
+///
+/// `Authorization` and `Cookie` headers are not forwarded by default, so the
+/// shadow upstream does not receive credentials of users.
+///
+/// ```rust
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(middleware::Shadow::new("http://127.0.0.1:8081").sample(0.1))
+///     .route("/", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Shadow {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    upstream: String,
+    sample: f64,
+    limit: usize,
+    timeout: Duration,
+    credentials: bool,
+}
+
+impl Shadow {
+    /// Construct `Shadow` middleware mirroring requests to `upstream`.
+    ///
+    /// Upstream is a base url, request path and query are appended to it.
+    pub fn new(upstream: &str) -> Self {
+        Shadow {
+            inner: Rc::new(Inner {
+                upstream: upstream.trim_end_matches('/').to_owned(),
+                sample: 1.0,
+                limit: 65_536,
+                timeout: Duration::from_secs(5),
+                credentials: false,
+            }),
+        }
+    }
+
+    /// Set fraction of requests to mirror, from `0.0` to `1.0` (default).
+    ///
+    /// Mirrored requests are spread evenly, i.e. `0.25` mirrors every fourth
+    /// request handled by a worker.
+    pub fn sample(mut self, sample: f64) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .sample = sample;
+        self
+    }
+
+    /// Set maximum size of mirrored request body (default: 64kB)
+    pub fn limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .limit = limit;
+        self
+    }
+
+    /// Set shadow request timeout (default: 5 seconds)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .timeout = timeout;
+        self
+    }
+
+    /// Forward `Authorization` and `Cookie` headers to the shadow upstream
+    /// (default: false)
+    pub fn forward_credentials(mut self, forward: bool) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .credentials = forward;
+        self
+    }
+}
+
+impl<S, B> Transform<S> for Shadow
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ShadowMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ShadowMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.inner.clone(),
+            client: Client::build().timeout(self.inner.timeout).finish(),
+            acc: 0.0,
+        })
+    }
+}
+
+pub struct ShadowMiddleware<S> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner>,
+    client: Client,
+    acc: f64,
+}
+
+impl<S> ShadowMiddleware<S> {
+    fn sampled(&mut self) -> bool {
+        self.acc += self.inner.sample;
+        if self.acc >= 1.0 {
+            self.acc -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<S, B> Service for ShadowMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        if !self.sampled() {
+            return self.service.borrow_mut().call(req).boxed_local();
+        }
+
+        let srv = self.service.clone();
+        let inner = self.inner.clone();
+        let client = self.client.clone();
+
+        async move {
+            // buffer request body up to the limit
+            let mut payload = req.take_payload();
+            let mut chunks: Vec<Result<Bytes, PayloadError>> = Vec::new();
+            let mut size = 0;
+            let mut complete = false;
+            while size <= inner.limit {
+                match payload.next().await {
+                    Some(Ok(chunk)) => {
+                        size += chunk.len();
+                        chunks.push(Ok(chunk));
+                    }
+                    Some(Err(e)) => {
+                        chunks.push(Err(e));
+                        break;
+                    }
+                    None => {
+                        complete = true;
+                        break;
+                    }
+                }
+            }
+
+            if complete && size <= inner.limit {
+                let mut body = BytesMut::with_capacity(size);
+                for chunk in chunks.iter().flatten() {
+                    body.extend_from_slice(chunk);
+                }
+
+                let url = format!(
+                    "{}{}",
+                    inner.upstream,
+                    req.uri()
+                        .path_and_query()
+                        .map(|pq| pq.as_str())
+                        .unwrap_or("/")
+                );
+                let mut shadow = client.request(req.method().clone(), url.as_str());
+                for (name, value) in req.headers().iter() {
+                    if !SKIP_HEADERS.contains(name)
+                        && (inner.credentials || !CREDENTIAL_HEADERS.contains(name))
+                    {
+                        shadow.headers_mut().append(name.clone(), value.clone());
+                    }
+                }
+
+                actix_rt::spawn(async move {
+                    if let Err(e) = shadow.send_body(body.freeze()).await {
+                        log::debug!("Shadow request to {:?} failed: {}", url, e);
+                    }
+                });
+            } else {
+                log::trace!(
+                    "Request body is too large or can not be read, skip mirroring"
+                );
+            }
+
+            let payload = stream::iter(chunks).chain(payload);
+            req.set_payload(Payload::Stream(Box::pin(payload)));
+
+            let fut = srv.borrow_mut().call(req);
+            fut.await
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::test::{self, call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpRequest, HttpResponse};
+
+    type Mirrored = Arc<Mutex<Vec<(String, Bytes)>>>;
+
+    fn shadow_server(mirrored: Mirrored) -> test::TestServer {
+        test::start(move || {
+            let mirrored = mirrored.clone();
+            App::new().default_service(web::to(move |req: HttpRequest, body: Bytes| {
+                mirrored.lock().unwrap().push((req.uri().to_string(), body));
+                HttpResponse::InternalServerError()
+            }))
+        })
+    }
+
+    async fn wait_for(mirrored: &Mirrored, count: usize) {
+        for _ in 0..100 {
+            if mirrored.lock().unwrap().len() >= count {
+                return;
+            }
+            actix_rt::time::delay_for(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_shadow() {
+        let mirrored = Mirrored::default();
+        let upstream = shadow_server(mirrored.clone());
+
+        let mut srv = init_service(
+            App::new()
+                .wrap(Shadow::new(&upstream.url("/")).limit(8))
+                .route(
+                    "/echo",
+                    web::post().to(|body: Bytes| HttpResponse::Ok().body(body)),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/echo?a=1")
+            .set_payload("payload")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert!(res.status().is_success());
+        assert_eq!(read_body(res).await, "payload");
+
+        // body is larger than the limit
+        let req = TestRequest::post()
+            .uri("/echo")
+            .set_payload("large payload")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(read_body(res).await, "large payload");
+
+        wait_for(&mirrored, 1).await;
+        actix_rt::time::delay_for(Duration::from_millis(50)).await;
+        let mirrored = mirrored.lock().unwrap();
+        assert_eq!(
+            *mirrored,
+            vec![("/echo?a=1".to_owned(), Bytes::from_static(b"payload"))]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_sample() {
+        let mirrored = Mirrored::default();
+        let upstream = shadow_server(mirrored.clone());
+
+        let mut srv = init_service(
+            App::new()
+                .wrap(Shadow::new(&upstream.url("/")).sample(0.5))
+                .route("/", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        for _ in 0..4 {
+            let req = TestRequest::default().to_request();
+            let res = call_service(&mut srv, req).await;
+            assert!(res.status().is_success());
+        }
+
+        wait_for(&mirrored, 2).await;
+        actix_rt::time::delay_for(Duration::from_millis(50)).await;
+        assert_eq!(mirrored.lock().unwrap().len(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_credentials() {
+        let mirrored = Mirrored::default();
+        let upstream = {
+            let mirrored = mirrored.clone();
+            test::start(move || {
+                let mirrored = mirrored.clone();
+                App::new().default_service(web::to(move |req: HttpRequest| {
+                    let mut names: Vec<_> = [header::AUTHORIZATION, header::COOKIE]
+                        .iter()
+                        .filter(|name| req.headers().contains_key(*name))
+                        .map(|name| name.as_str())
+                        .collect();
+                    names.push(
+                        req.headers()
+                            .get("x-request-id")
+                            .map_or("", |v| v.to_str().unwrap()),
+                    );
+                    let names = names.join(",");
+                    mirrored.lock().unwrap().push((names, Bytes::new()));
+                    HttpResponse::Ok()
+                }))
+            })
+        };
+
+        for (idx, forward) in [false, true].iter().enumerate() {
+            let mut srv = init_service(
+                App::new()
+                    .wrap(Shadow::new(&upstream.url("/")).forward_credentials(*forward))
+                    .route("/", web::get().to(|| HttpResponse::Ok())),
+            )
+            .await;
+            let req = TestRequest::default()
+                .header(header::AUTHORIZATION, "Bearer secret")
+                .header(header::COOKIE, "session=secret")
+                .header("x-request-id", "abc")
+                .to_request();
+            let res = call_service(&mut srv, req).await;
+            assert!(res.status().is_success());
+            wait_for(&mirrored, idx + 1).await;
+        }
+
+        let mirrored = mirrored.lock().unwrap();
+        assert_eq!(mirrored[0].0, "abc");
+        assert_eq!(mirrored[1].0, "authorization,cookie,abc");
+    }
+}
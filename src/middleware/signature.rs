@@ -0,0 +1,395 @@
+//! `Middleware` for verifying HTTP message signatures
+use std::convert::TryFrom;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use actix_http::error::Error;
+use actix_http::http::signature::{self, Component, SignatureError, SignatureParams};
+use actix_http::http::Uri;
+use actix_http::HttpMessage;
+use actix_service::{Service, Transform};
+use futures::future::{ok, Either, Ready};
+
+use crate::service::{ServiceRequest, ServiceResponse};
+
+/// `Middleware` for verifying HTTP message signatures
+/// ([RFC9421](https://tools.ietf.org/html/rfc9421)).
+///
+/// Key resolution callback receives parameters of the signature, like
+/// `keyid` and `alg`, and returns verification callback of the key or `None`
+/// if the key is unknown. Verification callback receives signature base and
+/// signature bytes.
+///
+/// Request passes if any of its signatures covers required components, is
+/// not expired and verifies with the resolved key. By default signatures
+/// have to cover `@method`, `@authority` and `@path` components and have
+/// `created` parameter, `@target-uri` covers all parts of the uri.
+/// Signatures that cover no components are rejected.
+/// Parameters of the
+/// verified signature are stored in request extensions as
+/// `SignatureParams`. Other requests are rejected with `401 Unauthorized`, or
+/// `400 Bad Request` if signature headers are malformed.
+///
+/// Request body is not covered by signatures, sign `Content-Digest` header
+/// and check the digest in the handler.
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use std::rc::Rc;
+/// use std::time::Duration;
+///
+/// use actix_web::http::signature::{Component, SignatureParams};
+/// use actix_web::http::HeaderName;
+/// use actix_web::middleware::VerifySignature;
+/// use actix_web::{web, App, HttpRequest, HttpResponse};
+///
+/// # fn verify_ed25519(_: &[u8], _: &[u8], _: &[u8]) -> bool { true }
+/// fn main() {
+///     let mut keys: HashMap<String, Vec<u8>> = HashMap::new();
+///     keys.insert("partner-a".to_owned(), b"public key".to_vec());
+///     let keys = Rc::new(keys);
+///
+///     let verifier = VerifySignature::new(move |params: &SignatureParams| {
+///         let key = keys.get(params.keyid.as_ref()?)?.clone();
+///         Some(move |base: &[u8], sig: &[u8]| verify_ed25519(&key, base, sig))
+///     })
+///     .require(Component::Header(HeaderName::from_static("content-digest")))
+///     .max_age(Duration::from_secs(300));
+///
+///     let app = App::new().wrap(verifier).route(
+///         "/inbox",
+///         web::post().to(|req: HttpRequest| {
+///             let keyid = req
+///                 .extensions()
+///                 .get::<SignatureParams>()
+///                 .and_then(|params| params.keyid.clone());
+///             HttpResponse::Ok().body(format!("{:?}", keyid))
+///         }),
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct VerifySignature {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    verify: Box<dyn Fn(&SignatureParams, &[u8], &[u8]) -> Option<bool>>,
+    required: Vec<Component>,
+    require_created: bool,
+    max_age: Option<u64>,
+    leeway: u64,
+}
+
+impl VerifySignature {
+    /// Construct `VerifySignature` middleware with key resolution callback.
+    pub fn new<F, K>(resolve: F) -> Self
+    where
+        F: Fn(&SignatureParams) -> Option<K> + 'static,
+        K: Fn(&[u8], &[u8]) -> bool,
+    {
+        VerifySignature {
+            inner: Rc::new(Inner {
+                verify: Box::new(move |params, base, sig| {
+                    resolve(params).map(|key| key(base, sig))
+                }),
+                required: vec![Component::Method, Component::Authority, Component::Path],
+                require_created: true,
+                max_age: None,
+                leeway: 60,
+            }),
+        }
+    }
+
+    /// Require the component to be covered by the signature.
+    ///
+    /// `@method`, `@authority` and `@path` components are always required.
+    pub fn require(mut self, component: Component) -> Self {
+        let inner = Rc::get_mut(&mut self.inner).expect("Multiple copies exist");
+        if !inner.required.contains(&component) {
+            inner.required.push(component);
+        }
+        self
+    }
+
+    /// Require signatures to have `created` parameter.
+    ///
+    /// By default signatures without `created` parameter are rejected.
+    pub fn require_created(mut self, required: bool) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .require_created = required;
+        self
+    }
+
+    /// Reject signatures created earlier than `max_age` ago.
+    ///
+    /// Signatures without `created` parameter are rejected as well.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .max_age = Some(max_age.as_secs());
+        self
+    }
+
+    /// Set tolerated clock skew for `created` and `expires` parameters.
+    ///
+    /// By default skew of 60 seconds is tolerated.
+    pub fn leeway(mut self, leeway: Duration) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .leeway = leeway.as_secs();
+        self
+    }
+}
+
+impl Inner {
+    fn verify(&self, req: &ServiceRequest) -> Result<SignatureParams, SignatureError> {
+        let signatures = signature::signatures(req.headers())?;
+
+        let uri = {
+            let info = req.connection_info();
+            let target = req
+                .uri()
+                .path_and_query()
+                .map(|pq| pq.as_str())
+                .unwrap_or("/");
+            Uri::try_from(format!("{}://{}{}", info.scheme(), info.host(), target))
+                .map_err(|_| SignatureError::Malformed)?
+        };
+
+        let mut err = SignatureError::Missing;
+        for sig in signatures {
+            if sig.params.components.is_empty() {
+                err = SignatureError::Invalid;
+                continue;
+            }
+            if let Some(c) = self.required.iter().find(|c| !covers(&sig.params, c)) {
+                err = SignatureError::MissingComponent(c.name().to_owned());
+                continue;
+            }
+            if self.require_created && sig.params.created.is_none() {
+                err = SignatureError::MissingComponent("created".to_owned());
+                continue;
+            }
+            if let Err(e) = sig.check_time(self.max_age, self.leeway) {
+                err = e;
+                continue;
+            }
+            let base = sig.signature_base(req.method(), &uri, req.headers())?;
+            match (self.verify)(&sig.params, base.as_bytes(), &sig.value) {
+                Some(true) => return Ok(sig.params),
+                Some(false) | None => err = SignatureError::Invalid,
+            }
+        }
+        Err(err)
+    }
+}
+
+/// Uri components are covered by `@target-uri` as well.
+fn covers(params: &SignatureParams, component: &Component) -> bool {
+    params.covers(component)
+        || match component {
+            Component::Authority
+            | Component::Scheme
+            | Component::RequestTarget
+            | Component::Path
+            | Component::Query => params.covers(&Component::TargetUri),
+            _ => false,
+        }
+}
+
+impl<S, B> Transform<S> for VerifySignature
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = VerifySignatureMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(VerifySignatureMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct VerifySignatureMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for VerifySignatureMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        match self.inner.verify(&req) {
+            Ok(params) => {
+                req.extensions_mut().insert(params);
+                Either::Left(self.service.call(req))
+            }
+            Err(e) => {
+                log::debug!("Rejected request to {:?}: {}", req.path(), e);
+                Either::Right(ok(req.error_response(e)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+    use crate::http::{HeaderMap, Method, StatusCode};
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpRequest, HttpResponse};
+
+    fn signed(params: &SignatureParams, key: &'static [u8]) -> TestRequest {
+        let uri = Uri::from_static("http://localhost:8080/inbox?page=1");
+        let mut headers = HeaderMap::new();
+        signature::sign("sig", params, &Method::POST, &uri, &mut headers, |base| {
+            [key, base].concat()
+        })
+        .unwrap();
+
+        let mut req = TestRequest::post().uri("/inbox?page=1");
+        for (name, value) in headers.iter() {
+            req = req.header(name.clone(), value.clone());
+        }
+        req
+    }
+
+    #[actix_rt::test]
+    async fn test_verify_signature() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    VerifySignature::new(|params: &SignatureParams| {
+                        match params.keyid.as_ref()?.as_str() {
+                            "key" => Some(|base: &[u8], sig: &[u8]| {
+                                sig == [&b"secret"[..], base].concat().as_slice()
+                            }),
+                            _ => None,
+                        }
+                    })
+                    .require(Component::Method)
+                    .max_age(Duration::from_secs(300)),
+                )
+                .route(
+                    "/inbox",
+                    web::post().to(|req: HttpRequest| {
+                        let extensions = req.extensions();
+                        let params = extensions.get::<SignatureParams>().unwrap();
+                        HttpResponse::Ok().body(params.keyid.clone().unwrap())
+                    }),
+                ),
+        )
+        .await;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut params = SignatureParams::new(vec![
+            Component::Method,
+            Component::Authority,
+            Component::Path,
+            Component::Query,
+        ]);
+        params.created = Some(now);
+        params.keyid = Some("key".to_owned());
+
+        let req = signed(&params, b"secret").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, "key");
+
+        // wrong key
+        let req = signed(&params, b"other").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // unknown key
+        let mut unknown = params.clone();
+        unknown.keyid = Some("unknown".to_owned());
+        let req = signed(&unknown, b"secret").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // too old
+        let mut old = params.clone();
+        old.created = Some(now - 3600);
+        let req = signed(&old, b"secret").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // signature without created parameter
+        let mut undated = params.clone();
+        undated.created = None;
+        let req = signed(&undated, b"secret").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // default required components are not covered
+        let mut partial = params.clone();
+        partial.components = vec![Component::Method, Component::Query];
+        let req = signed(&partial, b"secret").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let mut empty = params.clone();
+        empty.components = Vec::new();
+        let req = signed(&empty, b"secret").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = TestRequest::post().uri("/inbox").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = TestRequest::post()
+            .uri("/inbox")
+            .header("signature-input", "sig=(")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_created_optional() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    VerifySignature::new(|_: &SignatureParams| {
+                        Some(|base: &[u8], sig: &[u8]| {
+                            sig == [&b"secret"[..], base].concat().as_slice()
+                        })
+                    })
+                    .require_created(false),
+                )
+                .route("/inbox", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let params = SignatureParams::new(vec![Component::Method, Component::TargetUri]);
+        let req = signed(&params, b"secret").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,549 @@
+//! `Middleware` for coalescing concurrent identical requests
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::body::{Body, BodySize, MessageBody, ResponseBody};
+use actix_http::Response;
+use actix_service::{Service, Transform};
+use bytes::{Bytes, BytesMut};
+use futures::channel::oneshot;
+use futures::future::{ok, poll_fn, FutureExt, LocalBoxFuture, Ready};
+
+use crate::dev::{ServiceRequest, ServiceResponse};
+use crate::http::header::{
+    HeaderName, AUTHORIZATION, CACHE_CONTROL, COOKIE, PROXY_AUTHORIZATION, SET_COOKIE,
+    VARY,
+};
+use crate::http::{HeaderMap, Method, StatusCode};
+use crate::Error;
+
+type Flights = Rc<RefCell<HashMap<Vec<u8>, Vec<oneshot::Sender<Shared>>>>>;
+
+/// `Middleware` for coalescing concurrent identical `GET` requests.
+///
+/// While a request is handled, identical requests received by the same
+/// worker wait for its response instead of calling the handler again, the
+/// buffered response is sent to all of them. Expensive endpoints are
+/// protected from bursts of requests for the same resource, i.e. after a
+/// cache expiration.
+///
+/// Requests are identical if they have the same host, path, query and
+/// values of the [`key headers`](#method.key_header). Requests with
+/// `Authorization`, `Cookie` or `Proxy-Authorization` header are not
+/// coalesced, unless the header is a key header. Responses with
+/// `Set-Cookie` header, with `private` or `no-store` cache directives, that
+/// vary on headers other than key headers, streaming responses and
+/// responses larger than the limit (default: 1MB) are not shared, waiting
+/// requests call the handler themselves.
+///
+/// ```rust
+/// use actix_web::http::header;
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(middleware::SingleFlight::new().key_header(header::ACCEPT_LANGUAGE))
+///     .route("/report", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SingleFlight {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    headers: Vec<HeaderName>,
+    limit: usize,
+}
+
+impl Default for SingleFlight {
+    fn default() -> Self {
+        SingleFlight::new()
+    }
+}
+
+impl SingleFlight {
+    /// Construct `SingleFlight` middleware.
+    pub fn new() -> Self {
+        SingleFlight {
+            inner: Rc::new(Inner {
+                headers: Vec::new(),
+                limit: 1_048_576,
+            }),
+        }
+    }
+
+    /// Add request header to the key of identical requests.
+    ///
+    /// Headers that select representation of the response, like
+    /// `Accept-Language`, have to be added to the key. Adding
+    /// `Authorization` or `Cookie` header allows coalescing of requests with
+    /// the same credentials.
+    pub fn key_header(mut self, name: HeaderName) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .headers
+            .push(name);
+        self
+    }
+
+    /// Set maximum size of shared response body (default: 1MB)
+    pub fn limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .limit = limit;
+        self
+    }
+}
+
+impl<S, B> Transform<S> for SingleFlight
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<ResponseBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = SingleFlightMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(SingleFlightMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.inner.clone(),
+            flights: Rc::new(RefCell::new(HashMap::new())),
+        })
+    }
+}
+
+pub struct SingleFlightMiddleware<S> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner>,
+    flights: Flights,
+}
+
+impl<S> SingleFlightMiddleware<S> {
+    fn key(&self, req: &ServiceRequest) -> Vec<u8> {
+        let mut key = Vec::new();
+        key.extend_from_slice(req.connection_info().host().as_bytes());
+        key.extend_from_slice(
+            req.uri()
+                .path_and_query()
+                .map(|pq| pq.as_str())
+                .unwrap_or("/")
+                .as_bytes(),
+        );
+        for name in &self.inner.headers {
+            for value in req.headers().get_all(name) {
+                key.push(b'\n');
+                key.extend_from_slice(value.as_bytes());
+            }
+            key.push(b'\0');
+        }
+        key
+    }
+
+    /// Request carries credentials that are not part of the key.
+    fn has_credentials(&self, req: &ServiceRequest) -> bool {
+        [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION]
+            .iter()
+            .any(|name| {
+                req.headers().contains_key(name) && !self.inner.headers.contains(name)
+            })
+    }
+}
+
+impl<S, B> Service for SingleFlightMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<ResponseBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if req.method() != Method::GET || self.has_credentials(&req) {
+            return passthrough(self.service.borrow_mut().call(req));
+        }
+
+        let key = self.key(&req);
+        let waiting = self.flights.borrow_mut().get_mut(&key).map(|waiters| {
+            let (tx, rx) = oneshot::channel();
+            waiters.push(tx);
+            rx
+        });
+
+        // identical request is in flight, wait for its response
+        if let Some(rx) = waiting {
+            let srv = self.service.clone();
+            return async move {
+                match rx.await {
+                    Ok(shared) => Ok(req.into_response(shared.into_response())),
+                    Err(_) => {
+                        let fut = srv.borrow_mut().call(req);
+                        fut.await
+                            .map(|res| res.map_body(|_, body| ResponseBody::Body(body)))
+                    }
+                }
+            }
+            .boxed_local();
+        }
+
+        self.flights.borrow_mut().insert(key.clone(), Vec::new());
+        let mut flight = Flight {
+            key: Some(key),
+            flights: self.flights.clone(),
+        };
+        let inner = self.inner.clone();
+        let fut = self.service.borrow_mut().call(req);
+
+        async move {
+            let mut res = match fut.await {
+                Ok(res) => res,
+                Err(e) => {
+                    let shared = Shared::from_error(&e);
+                    for tx in flight.land() {
+                        let _ = tx.send(shared.clone());
+                    }
+                    return Err(e);
+                }
+            };
+
+            // requests received from now on start new flight, waiters of
+            // unshared response are dropped and call the service themselves
+            let waiters = flight.land();
+            if waiters.is_empty() || !shareable(&res, &inner) {
+                return Ok(res.map_body(|_, body| ResponseBody::Body(body)));
+            }
+
+            let mut body = Box::pin(res.take_body());
+            let mut buf = BytesMut::new();
+            while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                buf.extend_from_slice(&chunk?);
+            }
+            let body = buf.freeze();
+
+            let shared = Shared {
+                status: res.status(),
+                headers: res.headers().clone(),
+                body: body.clone(),
+            };
+            for tx in waiters {
+                let _ = tx.send(shared.clone());
+            }
+            Ok(res.map_body(move |_, _| ResponseBody::Other(Body::Bytes(body))))
+        }
+        .boxed_local()
+    }
+}
+
+fn passthrough<F, B>(
+    fut: F,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<ResponseBody<B>>, Error>>
+where
+    F: std::future::Future<Output = Result<ServiceResponse<B>, Error>> + 'static,
+    B: 'static,
+{
+    fut.map(|res| res.map(|res| res.map_body(|_, body| ResponseBody::Body(body))))
+        .boxed_local()
+}
+
+fn shareable<B: MessageBody>(res: &ServiceResponse<B>, inner: &Inner) -> bool {
+    let headers = res.headers();
+    if headers.contains_key(SET_COOKIE) {
+        return false;
+    }
+
+    for value in headers.get_all(&CACHE_CONTROL) {
+        let private = value.to_str().map(|value| {
+            value.split(',').any(|directive| {
+                let directive = directive.trim().to_ascii_lowercase();
+                directive == "no-store" || directive.starts_with("private")
+            })
+        });
+        if private.unwrap_or(true) {
+            return false;
+        }
+    }
+
+    // response could differ for requests with the same key
+    for value in headers.get_all(&VARY) {
+        let covered = value.to_str().map(|value| {
+            value.split(',').all(|name| {
+                let name = name.trim();
+                name.is_empty()
+                    || inner
+                        .headers
+                        .iter()
+                        .any(|key| key.as_str().eq_ignore_ascii_case(name))
+            })
+        });
+        if !covered.unwrap_or(false) {
+            return false;
+        }
+    }
+
+    match res.response().body().size() {
+        BodySize::None | BodySize::Empty => true,
+        BodySize::Sized(size) => size <= inner.limit,
+        BodySize::Sized64(size) => size <= inner.limit as u64,
+        BodySize::Stream => false,
+    }
+}
+
+/// Request in flight, waiters are dropped if the request is cancelled.
+struct Flight {
+    key: Option<Vec<u8>>,
+    flights: Flights,
+}
+
+impl Flight {
+    /// Finish the flight, returns waiters of the response.
+    fn land(&mut self) -> Vec<oneshot::Sender<Shared>> {
+        match self.key.take() {
+            Some(key) => self.flights.borrow_mut().remove(&key).unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Drop for Flight {
+    fn drop(&mut self) {
+        let _ = self.land();
+    }
+}
+
+/// Buffered response shared with waiters.
+#[derive(Clone)]
+struct Shared {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Shared {
+    fn from_error(err: &Error) -> Self {
+        let res = err.as_response_error().error_response();
+        let body = match res.body() {
+            ResponseBody::Body(Body::Bytes(ref b)) => b.clone(),
+            _ => Bytes::new(),
+        };
+
+        Shared {
+            status: res.status(),
+            headers: res.headers().clone(),
+            body,
+        }
+    }
+
+    fn into_response<B>(self) -> Response<ResponseBody<B>> {
+        let body = self.body;
+        let mut res = Response::new(self.status)
+            .map_body(move |_, _| ResponseBody::Other(Body::Bytes(body)));
+        *res.headers_mut() = self.headers;
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::future::join_all;
+
+    use super::*;
+    use crate::http::header;
+    use crate::test::{init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn test_single_flight() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let mut srv = init_service(
+            App::new()
+                .wrap(SingleFlight::new().key_header(header::ACCEPT_LANGUAGE))
+                .route(
+                    "/",
+                    web::to(move || {
+                        let counter = counter.clone();
+                        async move {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            actix_rt::time::delay_for(Duration::from_millis(50)).await;
+                            HttpResponse::Ok().body("data")
+                        }
+                    }),
+                ),
+        )
+        .await;
+
+        let futs = (0..3)
+            .map(|_| srv.call(TestRequest::with_uri("/").to_request()))
+            .collect::<Vec<_>>();
+        for res in join_all(futs).await {
+            let res = res.unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(read_body(res).await, Bytes::from_static(b"data"));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // different key or method
+        let futs = vec![
+            srv.call(TestRequest::with_uri("/").to_request()),
+            srv.call(
+                TestRequest::with_uri("/")
+                    .header(header::ACCEPT_LANGUAGE, "de")
+                    .to_request(),
+            ),
+            srv.call(TestRequest::post().uri("/").to_request()),
+        ];
+        for res in join_all(futs).await {
+            assert_eq!(res.unwrap().status(), StatusCode::OK);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[actix_rt::test]
+    async fn test_single_flight_unshared() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let mut srv = init_service(App::new().wrap(SingleFlight::new()).route(
+            "/",
+            web::get().to(move || {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    actix_rt::time::delay_for(Duration::from_millis(50)).await;
+                    HttpResponse::Ok()
+                        .header(header::SET_COOKIE, "id=1")
+                        .finish()
+                }
+            }),
+        ))
+        .await;
+
+        let futs = (0..2)
+            .map(|_| srv.call(TestRequest::with_uri("/").to_request()))
+            .collect::<Vec<_>>();
+        for res in join_all(futs).await {
+            let res = res.unwrap();
+            assert_eq!(res.headers().get(header::SET_COOKIE).unwrap(), "id=1");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_single_flight_private() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let mut srv = init_service(
+            App::new()
+                .wrap(SingleFlight::new().key_header(header::ACCEPT_LANGUAGE))
+                .route(
+                    "/{kind}",
+                    web::get().to(move |kind: web::Path<String>| {
+                        let counter = counter.clone();
+                        async move {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            actix_rt::time::delay_for(Duration::from_millis(50)).await;
+                            let mut res = HttpResponse::Ok();
+                            match kind.as_str() {
+                                "private" => {
+                                    res.header(header::CACHE_CONTROL, "private")
+                                }
+                                "no-store" => res.header(
+                                    header::CACHE_CONTROL,
+                                    "max-age=0, no-store",
+                                ),
+                                "vary" => res.header(header::VARY, "accept-encoding"),
+                                "vary-any" => res.header(header::VARY, "*"),
+                                "vary-key" => {
+                                    res.header(header::VARY, "Accept-Language")
+                                }
+                                _ => &mut res,
+                            };
+                            res.body("data")
+                        }
+                    }),
+                ),
+        )
+        .await;
+
+        let mut concurrent = |path: &str, auth: Option<&str>| {
+            let futs = (0..2)
+                .map(|_| {
+                    let mut req = TestRequest::with_uri(path);
+                    if let Some(auth) = auth {
+                        req = req.header(header::AUTHORIZATION, auth);
+                    }
+                    srv.call(req.to_request())
+                })
+                .collect::<Vec<_>>();
+            join_all(futs)
+        };
+
+        for path in &["/private", "/no-store", "/vary", "/vary-any"] {
+            calls.store(0, Ordering::SeqCst);
+            for res in concurrent(path, None).await {
+                assert_eq!(res.unwrap().status(), StatusCode::OK);
+            }
+            assert_eq!(calls.load(Ordering::SeqCst), 2, "{}", path);
+        }
+
+        calls.store(0, Ordering::SeqCst);
+        concurrent("/vary-key", None).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // credentials are not part of the key
+        calls.store(0, Ordering::SeqCst);
+        concurrent("/public", Some("Bearer a")).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // requests with the same credentials are coalesced
+        calls.store(0, Ordering::SeqCst);
+        let counter = calls.clone();
+        let mut srv = init_service(
+            App::new()
+                .wrap(SingleFlight::new().key_header(header::AUTHORIZATION))
+                .route(
+                    "/",
+                    web::get().to(move || {
+                        let counter = counter.clone();
+                        async move {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            actix_rt::time::delay_for(Duration::from_millis(50)).await;
+                            HttpResponse::Ok().body("data")
+                        }
+                    }),
+                ),
+        )
+        .await;
+        let futs = (0..2)
+            .map(|_| {
+                let req =
+                    TestRequest::with_uri("/").header(header::AUTHORIZATION, "Bearer a");
+                srv.call(req.to_request())
+            })
+            .collect::<Vec<_>>();
+        join_all(futs).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
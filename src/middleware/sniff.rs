@@ -0,0 +1,308 @@
+//! `Middleware` for verifying upload content types
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::error::PayloadError;
+use actix_service::{Service, Transform};
+use bytes::{Bytes, BytesMut};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use futures::stream::{self, StreamExt};
+
+use crate::dev::Payload;
+use crate::error::{Error, ErrorUnsupportedMediaType};
+use crate::http::header::{HeaderValue, X_CONTENT_TYPE_OPTIONS};
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::HttpMessage;
+
+/// `Middleware` for verifying declared content type of uploads.
+///
+/// Leading bytes of the request body are compared with magic byte signatures
+/// registered for the declared `Content-Type`. Requests that do not match are
+/// rejected with `415 Unsupported Media Type`. Content types without
+/// registered signatures are accepted, unless strict mode is enabled.
+///
+/// Default signatures cover `image/png`, `image/jpeg`, `image/gif`,
+/// `application/pdf` and `application/zip`. Multipart bodies are not
+/// inspected.
+///
+/// ```rust
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(
+///         middleware::ContentSniffer::default()
+///             .signature("image/webp", b"RIFF")
+///             .nosniff(),
+///     )
+///     .route("/upload", web::post().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ContentSniffer {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    signatures: Vec<(String, Vec<u8>)>,
+    strict: bool,
+    nosniff: bool,
+}
+
+impl ContentSniffer {
+    /// Construct `ContentSniffer` middleware without signatures.
+    pub fn new() -> Self {
+        ContentSniffer {
+            inner: Rc::new(Inner {
+                signatures: Vec::new(),
+                strict: false,
+                nosniff: false,
+            }),
+        }
+    }
+
+    /// Register magic byte signature for the content type.
+    ///
+    /// Content type may have multiple signatures, body has to start with
+    /// any of them.
+    pub fn signature(mut self, content_type: &str, magic: &[u8]) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .signatures
+            .push((content_type.to_ascii_lowercase(), magic.to_vec()));
+        self
+    }
+
+    /// Reject bodies that match signature of a content type other than the
+    /// declared one, even if declared type has no registered signatures.
+    pub fn strict(mut self) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .strict = true;
+        self
+    }
+
+    /// Set `X-Content-Type-Options: nosniff` on responses lacking it.
+    pub fn nosniff(mut self) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .nosniff = true;
+        self
+    }
+}
+
+impl Default for ContentSniffer {
+    fn default() -> Self {
+        ContentSniffer::new()
+            .signature("image/png", b"\x89PNG\r\n\x1a\n")
+            .signature("image/jpeg", b"\xff\xd8\xff")
+            .signature("image/gif", b"GIF87a")
+            .signature("image/gif", b"GIF89a")
+            .signature("application/pdf", b"%PDF-")
+            .signature("application/zip", b"PK\x03\x04")
+            .signature("application/zip", b"PK\x05\x06")
+    }
+}
+
+impl Inner {
+    fn max_len(&self) -> usize {
+        self.signatures
+            .iter()
+            .map(|(_, m)| m.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Check that body prefix matches declared content type.
+    fn verify(&self, content_type: &str, prefix: &[u8]) -> bool {
+        let mut declared = false;
+        let mut other = false;
+
+        for (ct, magic) in &self.signatures {
+            if ct == content_type {
+                if prefix.starts_with(magic) {
+                    return true;
+                }
+                declared = true;
+            } else if prefix.starts_with(magic) {
+                other = true;
+            }
+        }
+        !(declared || self.strict && other)
+    }
+
+    fn set_nosniff<B>(&self, mut res: ServiceResponse<B>) -> ServiceResponse<B> {
+        if self.nosniff && !res.headers().contains_key(&X_CONTENT_TYPE_OPTIONS) {
+            res.headers_mut()
+                .insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+        }
+        res
+    }
+}
+
+impl<S, B> Transform<S> for ContentSniffer
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ContentSnifferMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ContentSnifferMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct ContentSnifferMiddleware<S> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for ContentSnifferMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let srv = self.service.clone();
+        let inner = self.inner.clone();
+        let content_type = req.content_type().to_ascii_lowercase();
+
+        async move {
+            if !content_type.is_empty() && !content_type.starts_with("multipart/") {
+                // read enough of the body to match the longest signature
+                let max_len = inner.max_len();
+                let mut payload = req.take_payload();
+                let mut chunks: Vec<Result<Bytes, PayloadError>> = Vec::new();
+                let mut prefix = BytesMut::new();
+                while prefix.len() < max_len {
+                    match payload.next().await {
+                        Some(Ok(chunk)) => {
+                            prefix.extend_from_slice(&chunk);
+                            chunks.push(Ok(chunk));
+                        }
+                        Some(Err(e)) => {
+                            chunks.push(Err(e));
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+
+                if !prefix.is_empty() && !inner.verify(&content_type, &prefix) {
+                    log::debug!(
+                        "Request body does not match declared content type {:?}",
+                        content_type
+                    );
+                    let res = req.error_response(ErrorUnsupportedMediaType(
+                        "Request body does not match declared content type",
+                    ));
+                    return Ok(inner.set_nosniff(res));
+                }
+
+                let payload = stream::iter(chunks).chain(payload);
+                req.set_payload(Payload::Stream(Box::pin(payload)));
+            }
+
+            let fut = srv.borrow_mut().call(req);
+            Ok(inner.set_nosniff(fut.await?))
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header::CONTENT_TYPE, StatusCode};
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[actix_rt::test]
+    async fn test_content_sniffer() {
+        let mut srv = init_service(App::new().wrap(ContentSniffer::default()).route(
+            "/",
+            web::post().to(|body: Bytes| HttpResponse::Ok().body(body)),
+        ))
+        .await;
+
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "image/png")
+            .set_payload(PNG)
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, PNG);
+
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "image/png")
+            .set_payload("<script>")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        // content type without signatures
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "text/plain")
+            .set_payload(PNG)
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(X_CONTENT_TYPE_OPTIONS));
+    }
+
+    #[actix_rt::test]
+    async fn test_strict_nosniff() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(ContentSniffer::default().strict().nosniff())
+                .route("/", web::post().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "text/plain")
+            .set_payload(PNG)
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(
+            res.headers().get(X_CONTENT_TYPE_OPTIONS).unwrap(),
+            HeaderValue::from_static("nosniff")
+        );
+
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "text/plain")
+            .set_payload("text")
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(X_CONTENT_TYPE_OPTIONS).unwrap(),
+            HeaderValue::from_static("nosniff")
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! `Middleware` for resolving request tenant
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::error::{Error, ErrorNotFound, ErrorTooManyRequests};
+use actix_http::Extensions;
+use actix_service::{Service, Transform};
+use futures::future::{ok, Either, Ready};
+
+use crate::http::header::{HeaderValue, RETRY_AFTER};
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::types::{Tenant, TenantData, TenantSource};
+use crate::HttpMessage;
+
+/// Configuration of a tenant, registered with
+/// [`Tenants::tenant()`](struct.Tenants.html#method.tenant).
+#[derive(Default)]
+pub struct TenantConfig {
+    data: Extensions,
+    features: HashSet<String>,
+    rate_limit: Option<u32>,
+}
+
+impl TenantConfig {
+    /// Create empty tenant configuration.
+    pub fn new() -> Self {
+        TenantConfig::default()
+    }
+
+    /// Set data of the tenant, extracted by handlers with
+    /// [`web::TenantData<T>`](../web/struct.TenantData.html).
+    ///
+    /// Data of the same type replaces previously set one.
+    pub fn data<T: 'static>(mut self, data: T) -> Self {
+        self.data.insert(TenantData::new(data));
+        self
+    }
+
+    /// Enable feature flag for the tenant, see
+    /// [`Tenant::has_feature()`](../web/struct.Tenant.html#method.has_feature).
+    pub fn feature(mut self, name: &str) -> Self {
+        self.features.insert(name.to_owned());
+        self
+    }
+
+    /// Limit tenant to `per_second` requests per second.
+    ///
+    /// Limit is applied per worker thread, requests above the limit are
+    /// rejected with `429 Too Many Requests` response.
+    pub fn rate_limit(mut self, per_second: u32) -> Self {
+        self.rate_limit = Some(per_second);
+        self
+    }
+
+    fn into_tenant(self, id: String) -> Tenant {
+        Tenant::new(id, self.data, self.features, self.rate_limit)
+    }
+}
+
+/// `Middleware` for resolving request tenant.
+///
+/// Tenant id is taken from the request host, header or leading path
+/// segment, depending on [`TenantSource`](../web/enum.TenantSource.html).
+/// Host ids are matched case-insensitively and without port, header and
+/// path ids are matched exactly. Requests of unknown tenants are rejected
+/// with `404 Not Found` response, unless default tenant is configured.
+///
+/// Resolved tenant is available to handlers via
+/// [`web::Tenant`](../web/struct.Tenant.html) and
+/// [`web::TenantData<T>`](../web/struct.TenantData.html) extractors.
+///
+/// ```rust
+/// use actix_web::middleware::{TenantConfig, Tenants};
+/// use actix_web::web::TenantSource;
+/// use actix_web::{web, App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new()
+///     .wrap(
+///         Tenants::new(TenantSource::PathPrefix)
+///             .tenant("acme", TenantConfig::new().rate_limit(100))
+///             .tenant("globex", TenantConfig::new().feature("beta")),
+///     )
+///     .route("/{tenant}/orders", web::get().to(|| HttpResponse::Ok()));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Tenants {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    source: TenantSource,
+    tenants: HashMap<String, Tenant>,
+    default: Option<Tenant>,
+}
+
+impl Tenants {
+    /// Construct `Tenants` middleware resolving tenant id from `source`.
+    pub fn new(source: TenantSource) -> Self {
+        Tenants {
+            inner: Rc::new(Inner {
+                source,
+                tenants: HashMap::new(),
+                default: None,
+            }),
+        }
+    }
+
+    /// Register tenant with the id.
+    pub fn tenant(mut self, id: &str, config: TenantConfig) -> Self {
+        let inner = Rc::get_mut(&mut self.inner).expect("Multiple copies exist");
+        let id = match inner.source {
+            TenantSource::Host => id.to_ascii_lowercase(),
+            _ => id.to_owned(),
+        };
+        inner.tenants.insert(id.clone(), config.into_tenant(id));
+        self
+    }
+
+    /// Set configuration of requests with unknown or missing tenant id.
+    ///
+    /// Default tenant has an empty id.
+    pub fn default_tenant(mut self, config: TenantConfig) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .default = Some(config.into_tenant(String::new()));
+        self
+    }
+}
+
+impl Inner {
+    fn resolve(&self, req: &ServiceRequest) -> Option<&Tenant> {
+        let tenant = match self.source {
+            TenantSource::Host => {
+                let info = req.connection_info();
+                let host = info.host();
+                let host = match host.rfind(':') {
+                    // keep ipv6 literals, i.e. `[::1]`
+                    Some(idx) if !host[idx..].contains(']') => &host[..idx],
+                    _ => host,
+                };
+                self.tenants.get(&host.to_ascii_lowercase())
+            }
+            TenantSource::Header(ref name) => req
+                .headers()
+                .get(name)
+                .and_then(|val| val.to_str().ok())
+                .and_then(|id| self.tenants.get(id.trim())),
+            TenantSource::PathPrefix => req
+                .path()
+                .split('/')
+                .nth(1)
+                .and_then(|id| self.tenants.get(id)),
+        };
+        tenant.or(self.default.as_ref())
+    }
+}
+
+impl<S, B> Transform<S> for Tenants
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = TenantsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(TenantsMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct TenantsMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for TenantsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let tenant = match self.inner.resolve(&req) {
+            Some(tenant) => tenant.clone(),
+            None => {
+                log::debug!("Unknown tenant of request to {:?}", req.path());
+                return Either::Right(ok(
+                    req.error_response(ErrorNotFound("Unknown tenant"))
+                ));
+            }
+        };
+
+        if !tenant.acquire() {
+            log::debug!("Rate limit of tenant {:?} is exceeded", tenant.id());
+            let mut res =
+                req.error_response(ErrorTooManyRequests("Rate limit is exceeded"));
+            res.headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+            return Either::Right(ok(res));
+        }
+
+        req.extensions_mut().insert(tenant);
+        Either::Left(self.service.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::HeaderName;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    struct Db(&'static str);
+
+    async fn index(tenant: web::Tenant, db: web::TenantData<Db>) -> HttpResponse {
+        HttpResponse::Ok().body(format!(
+            "{} {} {}",
+            tenant.id(),
+            db.0,
+            tenant.has_feature("beta")
+        ))
+    }
+
+    #[actix_rt::test]
+    async fn test_tenants_header() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    Tenants::new(TenantSource::Header(HeaderName::from_static(
+                        "x-tenant",
+                    )))
+                    .tenant("acme", TenantConfig::new().data(Db("acme-db")))
+                    .tenant(
+                        "globex",
+                        TenantConfig::new().data(Db("globex-db")).feature("beta"),
+                    ),
+                )
+                .route("/", web::get().to(index)),
+        )
+        .await;
+
+        let req = TestRequest::with_header("x-tenant", "acme").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, "acme acme-db false");
+
+        let req = TestRequest::with_header("x-tenant", "globex").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, "globex globex-db true");
+
+        let req = TestRequest::with_header("x-tenant", "initech").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::default().to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_tenants_host_and_path() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    Tenants::new(TenantSource::Host)
+                        .tenant("Acme.example.com", TenantConfig::new().data(Db("acme")))
+                        .default_tenant(TenantConfig::new().data(Db("shared"))),
+                )
+                .route("/", web::get().to(index)),
+        )
+        .await;
+
+        let req = TestRequest::with_header("host", "acme.EXAMPLE.com:8080").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, "acme.example.com acme false");
+
+        let req = TestRequest::with_header("host", "other.example.com").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, " shared false");
+
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    Tenants::new(TenantSource::PathPrefix)
+                        .tenant("acme", TenantConfig::new().feature("beta")),
+                )
+                .route("/{tenant}/", web::get().to(index)),
+        )
+        .await;
+
+        // tenant without data
+        let req = TestRequest::with_uri("/acme/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let req = TestRequest::with_uri("/globex/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_tenants_rate_limit() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    Tenants::new(TenantSource::PathPrefix)
+                        .tenant("acme", TenantConfig::new().rate_limit(2))
+                        .tenant("globex", TenantConfig::new()),
+                )
+                .route("/{tenant}", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let req = TestRequest::with_uri("/acme").to_request();
+            let resp = call_service(&mut srv, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        let req = TestRequest::with_uri("/acme").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers().get(RETRY_AFTER).unwrap(), "1");
+
+        // limits are per tenant
+        let req = TestRequest::with_uri("/globex").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_tenant_not_configured() {
+        let mut srv = init_service(
+            App::new().route("/", web::get().to(|_: web::Tenant| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
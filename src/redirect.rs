@@ -0,0 +1,353 @@
+use std::fmt;
+use std::rc::Rc;
+
+use actix_http::error::{Error, ErrorInternalServerError};
+use actix_http::http::{header, HeaderValue, StatusCode};
+use actix_http::Response;
+use futures::future::{ready, Ready};
+use url::Url;
+
+use crate::dev::{AppService, HttpServiceFactory};
+use crate::request::HttpRequest;
+use crate::resource::Resource;
+use crate::responder::Responder;
+
+/// Redirect responder and service.
+///
+/// `Redirect::to()` creates responder that redirects to the target, target
+/// could be an absolute url, an absolute path or a path relative to the
+/// path of the request, i.e. `edit` redirects `/users/1/view` to
+/// `/users/1/edit`. `Redirect::to_route()` redirects to the url of a named
+/// resource. Redirects use *307 Temporary Redirect* status by default, so
+/// clients repeat the request with the same method and body.
+///
+/// ```rust
+/// use actix_web::{web, App, Responder};
+///
+/// async fn login() -> impl Responder {
+///     web::Redirect::to("/dashboard").see_other()
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .route("/login", web::post().to(login))
+///         // service redirecting requests of `/old` with query string
+///         .service(web::redirect("/old", "/new").permanent().preserve_query());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Redirect {
+    from: String,
+    target: Target,
+    status: StatusCode,
+    query: Query,
+}
+
+#[derive(Clone)]
+enum Target {
+    Url(String),
+    Route { name: String, elements: Vec<String> },
+}
+
+#[derive(Clone)]
+enum Query {
+    Drop,
+    Preserve,
+    Rewrite(Rc<dyn Fn(&str) -> String>),
+}
+
+impl Redirect {
+    /// Create redirect service that redirects requests of the `from` path to
+    /// the target.
+    pub fn new<F: Into<String>, T: Into<String>>(from: F, to: T) -> Self {
+        let mut redirect = Redirect::to(to);
+        redirect.from = from.into();
+        redirect
+    }
+
+    /// Create redirect responder for the target.
+    pub fn to<T: Into<String>>(to: T) -> Self {
+        Redirect {
+            from: String::new(),
+            target: Target::Url(to.into()),
+            status: StatusCode::TEMPORARY_REDIRECT,
+            query: Query::Drop,
+        }
+    }
+
+    /// Create redirect responder for the url of a named resource.
+    ///
+    /// Elements in form of `{name}` are replaced by matched path
+    /// parameters of the request, other elements are used as is.
+    pub fn to_route<U, I>(name: &str, elements: U) -> Self
+    where
+        U: IntoIterator<Item = I>,
+        I: AsRef<str>,
+    {
+        Redirect {
+            from: String::new(),
+            target: Target::Route {
+                name: name.to_owned(),
+                elements: elements
+                    .into_iter()
+                    .map(|el| el.as_ref().to_owned())
+                    .collect(),
+            },
+            status: StatusCode::TEMPORARY_REDIRECT,
+            query: Query::Drop,
+        }
+    }
+
+    /// Use *308 Permanent Redirect* status, method of the request is
+    /// preserved.
+    pub fn permanent(self) -> Self {
+        self.using_status_code(StatusCode::PERMANENT_REDIRECT)
+    }
+
+    /// Use *307 Temporary Redirect* status, method of the request is
+    /// preserved.
+    pub fn temporary(self) -> Self {
+        self.using_status_code(StatusCode::TEMPORARY_REDIRECT)
+    }
+
+    /// Use *303 See Other* status, clients follow redirect with `GET`
+    /// request.
+    pub fn see_other(self) -> Self {
+        self.using_status_code(StatusCode::SEE_OTHER)
+    }
+
+    /// Use custom redirect status.
+    ///
+    /// Panics if status is not a redirection status.
+    pub fn using_status_code(mut self, status: StatusCode) -> Self {
+        assert!(status.is_redirection(), "Not a redirection status");
+        self.status = status;
+        self
+    }
+
+    /// Append query string of the request to the target.
+    pub fn preserve_query(mut self) -> Self {
+        self.query = Query::Preserve;
+        self
+    }
+
+    /// Append query string returned by the function to the target, function
+    /// receives query string of the request. Empty query string is not
+    /// appended.
+    pub fn rewrite_query<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> String + 'static,
+    {
+        self.query = Query::Rewrite(Rc::new(f));
+        self
+    }
+
+    fn location(&self, req: &HttpRequest) -> Result<String, Error> {
+        let mut location = match self.target {
+            Target::Url(ref url) => resolve(req.path(), url)?,
+            Target::Route {
+                ref name,
+                ref elements,
+            } => {
+                let elements = elements.iter().map(|el| {
+                    if el.starts_with('{') && el.ends_with('}') {
+                        req.match_info().get(&el[1..el.len() - 1]).unwrap_or(el)
+                    } else {
+                        el.as_str()
+                    }
+                });
+                req.url_for(name, elements)?.into()
+            }
+        };
+
+        let query = match self.query {
+            Query::Drop => None,
+            Query::Preserve => Some(req.query_string().to_owned()),
+            Query::Rewrite(ref f) => Some(f(req.query_string())),
+        };
+        if let Some(query) = query {
+            append_query(&mut location, &query);
+        }
+        Ok(location)
+    }
+}
+
+/// Resolve target relative to the path of the request, absolute urls and
+/// paths are returned as is.
+fn resolve(path: &str, target: &str) -> Result<String, Error> {
+    if target.starts_with('/') || Url::parse(target).is_ok() {
+        return Ok(target.to_owned());
+    }
+
+    let base = Url::parse("http://localhost")
+        .and_then(|url| url.join(path))
+        .map_err(ErrorInternalServerError)?;
+    let url = base.join(target).map_err(ErrorInternalServerError)?;
+    Ok(url[url::Position::BeforePath..].to_owned())
+}
+
+fn append_query(location: &mut String, query: &str) {
+    if query.is_empty() {
+        return;
+    }
+    let fragment = location.find('#').map(|idx| location.split_off(idx));
+    location.push(if location.contains('?') { '&' } else { '?' });
+    location.push_str(query);
+    if let Some(fragment) = fragment {
+        location.push_str(&fragment);
+    }
+}
+
+impl fmt::Debug for Redirect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut dbg = f.debug_struct("Redirect");
+        match self.target {
+            Target::Url(ref url) => dbg.field("to", url),
+            Target::Route { ref name, .. } => dbg.field("route", name),
+        };
+        dbg.field("status", &self.status).finish()
+    }
+}
+
+impl Responder for Redirect {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let res = self.location(req).and_then(|location| {
+            let location =
+                HeaderValue::from_str(&location).map_err(ErrorInternalServerError)?;
+            Ok(Response::build(self.status)
+                .header(header::LOCATION, location)
+                .finish())
+        });
+        ready(res)
+    }
+}
+
+impl HttpServiceFactory for Redirect {
+    fn register(self, config: &mut AppService) {
+        Resource::new(self.from.clone())
+            .to(move || ready(self.clone()))
+            .register(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Method;
+    use crate::test::{call_service, init_service, TestRequest};
+    use crate::{web, App};
+
+    fn location(res: &crate::dev::ServiceResponse) -> &str {
+        res.headers()
+            .get(header::LOCATION)
+            .unwrap()
+            .to_str()
+            .unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_redirect_service() {
+        let mut srv = init_service(
+            App::new()
+                .service(web::redirect("/old", "/new"))
+                .service(
+                    web::redirect("/moved", "/new#top")
+                        .permanent()
+                        .preserve_query(),
+                )
+                .service(
+                    web::redirect("/search", "https://example.com/?lang=en")
+                        .rewrite_query(|q| q.replace("query=", "q=")),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post().uri("/old?a=1").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(location(&res), "/new");
+
+        let req = TestRequest::with_uri("/moved?a=1&b=2").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(location(&res), "/new?a=1&b=2#top");
+
+        let req = TestRequest::with_uri("/moved").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(location(&res), "/new#top");
+
+        let req = TestRequest::with_uri("/search?query=rust").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(location(&res), "https://example.com/?lang=en&q=rust");
+    }
+
+    #[actix_rt::test]
+    async fn test_relative_target() {
+        let mut srv = init_service(
+            App::new()
+                .route(
+                    "/users/{id}/view",
+                    web::get().to(|| async { Redirect::to("edit").see_other() }),
+                )
+                .route(
+                    "/users/{id}/up",
+                    web::get().to(|| async { Redirect::to("../../index.html") }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/users/1/view").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(location(&res), "/users/1/edit");
+
+        let req = TestRequest::with_uri("/users/1/up").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(location(&res), "/index.html");
+    }
+
+    #[actix_rt::test]
+    async fn test_redirect_to_route() {
+        let mut srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/v2/users/{id}")
+                        .name("user")
+                        .to(|| async { "" }),
+                )
+                .service(web::resource("/v1/users/{id}").redirect_to("user", &["{id}"]))
+                .service(web::resource("/me").route(
+                    web::method(Method::PUT).to(|| async {
+                        Redirect::to_route("user", &["me"]).permanent()
+                    }),
+                ))
+                .service(web::resource("/gone").redirect_to("missing", &["x"])),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/v1/users/42").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(location(&res), "http://localhost:8080/v2/users/42");
+
+        let req = TestRequest::with_uri("/me")
+            .method(Method::PUT)
+            .to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(location(&res), "http://localhost:8080/v2/users/me");
+
+        let req = TestRequest::with_uri("/gone").to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not a redirection status")]
+    fn test_status_check() {
+        let _ = Redirect::to("/").using_status_code(StatusCode::OK);
+    }
+}
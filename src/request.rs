@@ -2,6 +2,8 @@ use std::cell::{Ref, RefCell, RefMut};
 use std::rc::Rc;
 use std::{fmt, net};
 
+use actix_http::h1::Informational;
+use actix_http::h2::ServerPush;
 use actix_http::http::{HeaderMap, Method, Uri, Version};
 use actix_http::{Error, Extensions, HttpMessage, Message, Payload, RequestHead};
 use actix_router::{Path, Url};
@@ -10,7 +12,9 @@ use futures::future::{ok, Ready};
 use crate::config::AppConfig;
 use crate::error::UrlGenerationError;
 use crate::extract::FromRequest;
-use crate::info::ConnectionInfo;
+#[cfg(unix)]
+use crate::info::PeerCredentials;
+use crate::info::{ConnectionInfo, PeerCertificate};
 use crate::rmap::ResourceMap;
 
 #[derive(Clone)]
@@ -174,6 +178,12 @@ impl HttpRequest {
         self.url_for(name, &NO_PARAMS)
     }
 
+    /// Get pattern of the resource that matches request path, i.e.
+    /// `/user/{id}`.
+    pub fn match_pattern(&self) -> Option<String> {
+        self.0.rmap.match_pattern(self.path())
+    }
+
     #[inline]
     /// Get a reference to a `ResourceMap` of current application.
     pub fn resource_map(&self) -> &ResourceMap {
@@ -200,6 +210,69 @@ impl HttpRequest {
         ConnectionInfo::get(self.head(), &*self.app_config())
     }
 
+    /// Get certificate presented by tls client of the connection.
+    ///
+    /// Certificate is available only for connections of openssl and rustls
+    /// listeners with client authentication.
+    #[inline]
+    pub fn peer_certificate(&self) -> Option<PeerCertificate> {
+        PeerCertificate::get(self.head())
+    }
+
+    /// Get credentials of the process connected to unix domain socket.
+    ///
+    /// Credentials are available only for connections of `HttpServer::bind_uds()`
+    /// and `HttpServer::listen_uds()` listeners.
+    #[cfg(unix)]
+    #[inline]
+    pub fn peer_credentials(&self) -> Option<PeerCredentials> {
+        PeerCredentials::get(self.head())
+    }
+
+    /// Push resource of the `path` to the client along with the response.
+    ///
+    /// Pushed request is a `GET` request of the same app, it is handled as a
+    /// regular request. Returns false if the peer is not connected over
+    /// http/2, the path is not valid or the response is already sent.
+    ///
+    /// ```rust
+    /// use actix_web::{HttpRequest, HttpResponse};
+    ///
+    /// fn index(req: HttpRequest) -> HttpResponse {
+    ///     req.push("/style.css");
+    ///     HttpResponse::Ok().body("<link rel=\"stylesheet\" href=\"/style.css\">")
+    /// }
+    /// ```
+    pub fn push(&self, path: &str) -> bool {
+        match self.extensions().get::<ServerPush>() {
+            Some(push) => push.push(path),
+            None => false,
+        }
+    }
+
+    /// Send `103 Early Hints` response with `Link` header for each of the
+    /// links, before the final response.
+    ///
+    /// Returns false if the peer is not connected over http/1.1, a link is
+    /// not valid or the response is already sent. Use `dev::Informational`
+    /// from request extensions to send other informational responses.
+    ///
+    /// ```rust
+    /// use actix_web::{HttpRequest, HttpResponse};
+    ///
+    /// async fn index(req: HttpRequest) -> HttpResponse {
+    ///     req.early_hints(&["</style.css>; rel=preload; as=style"]);
+    ///     // render the page
+    ///     HttpResponse::Ok().body("<link rel=\"stylesheet\" href=\"/style.css\">")
+    /// }
+    /// ```
+    pub fn early_hints<S: AsRef<str>>(&self, links: &[S]) -> bool {
+        match self.extensions().get::<Informational>() {
+            Some(info) => info.early_hints(links),
+            None => false,
+        }
+    }
+
     /// App config
     #[inline]
     pub fn app_config(&self) -> &AppConfig {
@@ -402,6 +475,11 @@ mod tests {
         rmap.add(&mut res, None);
         assert!(rmap.has_resource("/user/test.html"));
         assert!(!rmap.has_resource("/test/unknown"));
+        assert_eq!(
+            rmap.match_pattern("/user/test.html"),
+            Some("/user/{name}.{ext}".to_owned())
+        );
+        assert_eq!(rmap.match_pattern("/test/unknown"), None);
 
         let req = TestRequest::with_header(header::HOST, "www.rust-lang.org")
             .rmap(rmap)
@@ -1,25 +1,27 @@
 use std::cell::RefCell;
+use std::convert::TryFrom;
 use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
-use actix_http::{Error, Extensions, Response};
+use actix_http::{http::header, Error, Extensions, Response};
 use actix_router::IntoPattern;
 use actix_service::boxed::{self, BoxService, BoxServiceFactory};
 use actix_service::{
     apply, apply_fn_factory, IntoServiceFactory, Service, ServiceFactory, Transform,
 };
-use futures::future::{ok, Either, LocalBoxFuture, Ready};
+use futures::future::{ok, ready, Either, LocalBoxFuture, Ready};
 
 use crate::data::Data;
 use crate::dev::{insert_slash, AppService, HttpServiceFactory, ResourceDef};
 use crate::extract::FromRequest;
 use crate::guard::Guard;
 use crate::handler::Factory;
+use crate::redirect::Redirect;
 use crate::responder::Responder;
-use crate::route::{CreateRouteService, Route, RouteService};
+use crate::route::{CreateRouteService, HeaderContract, Route, RouteService};
 use crate::service::{ServiceRequest, ServiceResponse};
 
 type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
@@ -54,6 +56,7 @@ pub struct Resource<T = ResourceEndpoint> {
     routes: Vec<Route>,
     data: Option<Extensions>,
     guards: Vec<Box<dyn Guard>>,
+    contract: HeaderContract,
     default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
     factory_ref: Rc<RefCell<Option<ResourceFactory>>>,
 }
@@ -69,6 +72,7 @@ impl Resource {
             endpoint: ResourceEndpoint::new(fref.clone()),
             factory_ref: fref,
             guards: Vec::new(),
+            contract: HeaderContract::default(),
             data: None,
             default: Rc::new(RefCell::new(None)),
         }
@@ -126,6 +130,41 @@ where
         self
     }
 
+    /// Require request header for all routes of the resource.
+    ///
+    /// Requests without the header are rejected with *400 Bad Request*
+    /// response. See [`Route::require_header()`](struct.Route.html#method.require_header).
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::resource("/orders")
+    ///             .require_header("idempotency-key")
+    ///             .guarantee_header("location")
+    ///             .route(web::post().to(|| {
+    ///                 HttpResponse::Created().header("location", "/orders/1").finish()
+    ///             }))
+    ///     );
+    /// }
+    /// ```
+    pub fn require_header(mut self, name: &'static str) -> Self {
+        self.contract
+            .require(header::HeaderName::try_from(name).unwrap());
+        self
+    }
+
+    /// Declare response header that successful responses of all routes of
+    /// the resource contain, checked in debug builds.
+    ///
+    /// See [`Route::guarantee_header()`](struct.Route.html#method.guarantee_header).
+    pub fn guarantee_header(mut self, name: &'static str) -> Self {
+        self.contract
+            .guarantee(header::HeaderName::try_from(name).unwrap());
+        self
+    }
+
     /// Register a new route.
     ///
     /// ```rust
@@ -240,6 +279,29 @@ where
         self
     }
 
+    /// Redirect requests of the resource to the url of a named resource.
+    ///
+    /// Elements in form of `{name}` are replaced by matched path parameters
+    /// of the request. Requests are redirected with *307 Temporary Redirect*
+    /// status, use [`Redirect::to_route()`](web/struct.Redirect.html) handler
+    /// for other statuses.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// App::new()
+    ///     .service(web::resource("/v2/users/{id}").name("user").to(|| HttpResponse::Ok()))
+    ///     .service(web::resource("/v1/users/{id}").redirect_to("user", &["{id}"]));
+    /// ```
+    pub fn redirect_to<U, I>(self, name: &str, elements: U) -> Self
+    where
+        U: IntoIterator<Item = I>,
+        I: AsRef<str>,
+    {
+        let redirect = Redirect::to_route(name, elements);
+        self.to(move || ready(redirect.clone()))
+    }
+
     /// Register a resource middleware.
     ///
     /// This is similar to `App's` middlewares, but middleware get invoked on resource level.
@@ -273,6 +335,7 @@ where
             rdef: self.rdef,
             name: self.name,
             guards: self.guards,
+            contract: self.contract,
             routes: self.routes,
             default: self.default,
             data: self.data,
@@ -335,6 +398,7 @@ where
             rdef: self.rdef,
             name: self.name,
             guards: self.guards,
+            contract: self.contract,
             routes: self.routes,
             default: self.default,
             data: self.data,
@@ -344,7 +408,8 @@ where
 
     /// Default service to be used if no matching route could be found.
     /// By default *405* response get returned. Resource does not use
-    /// default handler from `App` or `Scope`.
+    /// default handler from `App` or `Scope`, but uses method not allowed
+    /// handler of `App` or `Scope` if one is registered.
     pub fn default_service<F, U>(mut self, f: F) -> Self
     where
         F: IntoServiceFactory<U>,
@@ -378,6 +443,11 @@ where
         > + 'static,
 {
     fn register(mut self, config: &mut AppService) {
+        // use method not allowed service of parent if needed
+        if self.default.borrow().is_none() {
+            *self.default.borrow_mut() = config.method_not_allowed_service();
+        }
+
         let guards = if self.guards.is_empty() {
             None
         } else {
@@ -410,7 +480,12 @@ where
         InitError = (),
     >,
 {
-    fn into_factory(self) -> T {
+    fn into_factory(mut self) -> T {
+        if !self.contract.is_empty() {
+            for route in self.routes.iter_mut() {
+                route.extend_contract(&self.contract);
+            }
+        }
         *self.factory_ref.borrow_mut() = Some(ResourceFactory {
             routes: self.routes,
             data: self.data.map(Rc::new),
@@ -890,4 +965,43 @@ mod tests {
         let resp = call_service(&mut srv, req).await;
         assert_eq!(resp.status(), StatusCode::OK);
     }
+
+    #[actix_rt::test]
+    async fn test_resource_header_contract() {
+        let mut srv = init_service(
+            App::new().service(
+                web::resource("/test")
+                    .route(web::post().to(HttpResponse::Created))
+                    .require_header("idempotency-key")
+                    .route(web::put().require_header("if-match").to(HttpResponse::Ok)),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/test")
+            .header("idempotency-key", "1")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let req = TestRequest::post().uri("/test").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::put()
+            .uri("/test")
+            .header("idempotency-key", "1")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::put()
+            .uri("/test")
+            .header("idempotency-key", "1")
+            .header("if-match", "*")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
 }
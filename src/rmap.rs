@@ -95,6 +95,71 @@ impl ResourceMap {
         false
     }
 
+    /// Get pattern of the resource matching the path.
+    ///
+    /// Patterns of nested scopes are concatenated, route guards are not
+    /// checked.
+    pub fn match_pattern(&self, path: &str) -> Option<String> {
+        let path = if path.is_empty() { "/" } else { path };
+
+        for (pattern, rmap) in &self.patterns {
+            if let Some(ref rmap) = rmap {
+                if let Some(plen) = pattern.is_prefix_match(path) {
+                    return rmap
+                        .match_pattern(&path[plen..])
+                        .map(|p| format!("{}{}", pattern.pattern(), p));
+                }
+            } else if pattern.is_match(path) {
+                return Some(pattern.pattern().to_owned());
+            }
+        }
+        None
+    }
+
+    /// Get patterns of the nested scopes and of the resource matching the
+    /// path, outermost first.
+    pub(crate) fn match_patterns(&self, path: &str) -> Option<Vec<String>> {
+        let path = if path.is_empty() { "/" } else { path };
+
+        for (pattern, rmap) in &self.patterns {
+            if let Some(ref rmap) = rmap {
+                if let Some(plen) = pattern.is_prefix_match(path) {
+                    return rmap.match_patterns(&path[plen..]).map(|mut p| {
+                        p.insert(0, pattern.pattern().to_owned());
+                        p
+                    });
+                }
+            } else if pattern.is_match(path) {
+                return Some(vec![pattern.pattern().to_owned()]);
+            }
+        }
+        None
+    }
+
+    /// Get patterns of all resources of the application.
+    ///
+    /// Patterns of nested scopes are concatenated, in registration order.
+    pub fn patterns(&self) -> Vec<String> {
+        if let Some(ref parent) = *self.parent.borrow() {
+            return parent.patterns();
+        }
+
+        let mut patterns = Vec::new();
+        self.collect_patterns("", &mut patterns);
+        patterns
+    }
+
+    fn collect_patterns(&self, prefix: &str, patterns: &mut Vec<String>) {
+        for (pattern, rmap) in &self.patterns {
+            let path = format!("{}{}", prefix, pattern.pattern());
+            if let Some(ref rmap) = rmap {
+                rmap.collect_patterns(&path, patterns);
+            } else {
+                patterns.push(path);
+            }
+        }
+    }
+
     fn patterns_for<U, I>(
         &self,
         name: &str,
@@ -1,14 +1,17 @@
+use std::cell::RefCell;
+use std::convert::TryFrom;
 use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
-use actix_http::{http::Method, Error};
+use actix_http::error::{Error, ErrorBadRequest};
+use actix_http::http::{header::HeaderName, Method};
 use actix_service::{Service, ServiceFactory};
 use futures::future::{ready, FutureExt, LocalBoxFuture};
 
 use crate::extract::FromRequest;
-use crate::guard::{self, Guard};
+use crate::guard::{self, AsyncGuard, Guard};
 use crate::handler::{Extract, Factory, Handler};
 use crate::responder::Responder;
 use crate::service::{ServiceRequest, ServiceResponse};
@@ -42,6 +45,8 @@ type BoxedRouteNewService<Req, Res> = Box<
 pub struct Route {
     service: BoxedRouteNewService<ServiceRequest, ServiceResponse>,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    async_guards: Rc<Vec<Box<dyn AsyncGuard>>>,
+    contract: Rc<HeaderContract>,
 }
 
 impl Route {
@@ -52,12 +57,18 @@ impl Route {
                 ready(HttpResponse::NotFound())
             })))),
             guards: Rc::new(Vec::new()),
+            async_guards: Rc::new(Vec::new()),
+            contract: Rc::new(HeaderContract::default()),
         }
     }
 
     pub(crate) fn take_guards(&mut self) -> Vec<Box<dyn Guard>> {
         std::mem::replace(Rc::get_mut(&mut self.guards).unwrap(), Vec::new())
     }
+
+    pub(crate) fn extend_contract(&mut self, contract: &HeaderContract) {
+        Rc::get_mut(&mut self.contract).unwrap().extend(contract);
+    }
 }
 
 impl ServiceFactory for Route {
@@ -73,6 +84,8 @@ impl ServiceFactory for Route {
         CreateRouteService {
             fut: self.service.new_service(()),
             guards: self.guards.clone(),
+            async_guards: self.async_guards.clone(),
+            contract: self.contract.clone(),
         }
     }
 }
@@ -87,6 +100,8 @@ pub struct CreateRouteService {
     #[pin]
     fut: RouteFuture,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    async_guards: Rc<Vec<Box<dyn AsyncGuard>>>,
+    contract: Rc<HeaderContract>,
 }
 
 impl Future for CreateRouteService {
@@ -96,10 +111,24 @@ impl Future for CreateRouteService {
         let this = self.project();
 
         match this.fut.poll(cx)? {
-            Poll::Ready(service) => Poll::Ready(Ok(RouteService {
-                service,
-                guards: this.guards.clone(),
-            })),
+            Poll::Ready(mut service) => {
+                if !this.contract.is_empty() {
+                    service = Box::new(ContractService {
+                        service: Rc::new(RefCell::new(service)),
+                        contract: this.contract.clone(),
+                    });
+                }
+                if !this.async_guards.is_empty() {
+                    service = Box::new(AsyncGuardService {
+                        service: Rc::new(RefCell::new(service)),
+                        guards: this.async_guards.clone(),
+                    });
+                }
+                Poll::Ready(Ok(RouteService {
+                    service,
+                    guards: this.guards.clone(),
+                }))
+            }
             Poll::Pending => Poll::Pending,
         }
     }
@@ -175,6 +204,89 @@ impl Route {
         self
     }
 
+    /// Add async guard to the route.
+    ///
+    /// Async guards are checked in order of registration after the route
+    /// is selected by the router. If any of async guards does not match,
+    /// *404 Not Found* response is returned and other routes are not tried.
+    ///
+    /// ```rust
+    /// # use actix_web::*;
+    /// # fn main() {
+    /// App::new().service(web::resource("/path").route(
+    ///     web::get()
+    ///         .async_guard(guard::fn_async_guard(|req| {
+    ///             let allowed = req.headers().contains_key("x-token");
+    ///             async move { allowed }
+    ///         }))
+    ///         .to(|req: HttpRequest| HttpResponse::Ok()))
+    /// );
+    /// # }
+    /// ```
+    pub fn async_guard<F: AsyncGuard + 'static>(mut self, f: F) -> Self {
+        Rc::get_mut(&mut self.async_guards)
+            .unwrap()
+            .push(Box::new(f));
+        self
+    }
+
+    /// Require request header for the route.
+    ///
+    /// Requests without the header are rejected with *400 Bad Request*
+    /// response. Unlike guards, required headers are checked after the route
+    /// is selected, so other routes are not tried.
+    ///
+    /// ```rust
+    /// # use actix_web::*;
+    /// # fn main() {
+    /// App::new().service(web::resource("/path").route(
+    ///     web::post()
+    ///         .require_header("x-request-id")
+    ///         .to(|req: HttpRequest| HttpResponse::Ok()))
+    /// );
+    /// # }
+    /// ```
+    pub fn require_header(mut self, name: &'static str) -> Self {
+        Rc::get_mut(&mut self.contract)
+            .unwrap()
+            .require(HeaderName::try_from(name).unwrap());
+        self
+    }
+
+    /// Declare response header that successful responses of the route
+    /// always contain.
+    ///
+    /// In debug builds successful responses without the header are replaced
+    /// with *500 Internal Server Error* response, release builds do not
+    /// check guaranteed headers.
+    ///
+    /// ```rust
+    /// # use actix_web::*;
+    /// # fn main() {
+    /// App::new().service(web::resource("/path").route(
+    ///     web::get()
+    ///         .guarantee_header("etag")
+    ///         .to(|| HttpResponse::Ok().header("etag", "\"1\"").finish()))
+    /// );
+    /// # }
+    /// ```
+    pub fn guarantee_header(mut self, name: &'static str) -> Self {
+        Rc::get_mut(&mut self.contract)
+            .unwrap()
+            .guarantee(HeaderName::try_from(name).unwrap());
+        self
+    }
+
+    /// Request headers required by the route.
+    pub fn required_headers(&self) -> &[HeaderName] {
+        &self.contract.required
+    }
+
+    /// Response headers guaranteed by the route.
+    pub fn guaranteed_headers(&self) -> &[HeaderName] {
+        &self.contract.guaranteed
+    }
+
     /// Set handler function, use request extractors for parameters.
     ///
     /// ```rust
@@ -236,6 +348,126 @@ impl Route {
     }
 }
 
+struct AsyncGuardService {
+    service: Rc<RefCell<BoxedRouteService<ServiceRequest, ServiceResponse>>>,
+    guards: Rc<Vec<Box<dyn AsyncGuard>>>,
+}
+
+impl Service for AsyncGuardService {
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let guards = self.guards.clone();
+
+        async move {
+            for guard in guards.iter() {
+                if !guard.check(req.request()).await {
+                    return Ok(req.into_response(HttpResponse::NotFound().finish()));
+                }
+            }
+            let fut = service.borrow_mut().call(req);
+            fut.await
+        }
+        .boxed_local()
+    }
+}
+
+/// Request and response headers declared for a route.
+#[derive(Clone, Default)]
+pub(crate) struct HeaderContract {
+    required: Vec<HeaderName>,
+    guaranteed: Vec<HeaderName>,
+}
+
+impl HeaderContract {
+    pub(crate) fn require(&mut self, name: HeaderName) {
+        if !self.required.contains(&name) {
+            self.required.push(name);
+        }
+    }
+
+    pub(crate) fn guarantee(&mut self, name: HeaderName) {
+        if !self.guaranteed.contains(&name) {
+            self.guaranteed.push(name);
+        }
+    }
+
+    pub(crate) fn extend(&mut self, other: &HeaderContract) {
+        for name in other.required.iter() {
+            self.require(name.clone());
+        }
+        for name in other.guaranteed.iter() {
+            self.guarantee(name.clone());
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.guaranteed.is_empty()
+    }
+}
+
+struct ContractService {
+    service: Rc<RefCell<BoxedRouteService<ServiceRequest, ServiceResponse>>>,
+    contract: Rc<HeaderContract>,
+}
+
+impl Service for ContractService {
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        for name in self.contract.required.iter() {
+            if !req.headers().contains_key(name) {
+                let err = ErrorBadRequest(format!("Missing required header: {}", name));
+                return ready(Ok(req.error_response(err))).boxed_local();
+            }
+        }
+
+        let fut = self.service.borrow_mut().call(req);
+        #[cfg(debug_assertions)]
+        {
+            let contract = self.contract.clone();
+            async move {
+                let res = fut.await?;
+                if !res.status().is_success() {
+                    return Ok(res);
+                }
+                for name in contract.guaranteed.iter() {
+                    if !res.headers().contains_key(name) {
+                        log::error!(
+                            "Response of {} is missing guaranteed header: {}",
+                            res.request().path(),
+                            name
+                        );
+                        return Ok(res.into_response(
+                            HttpResponse::InternalServerError()
+                                .body(format!("Missing guaranteed header: {}", name)),
+                        ));
+                    }
+                }
+                Ok(res)
+            }
+            .boxed_local()
+        }
+        #[cfg(not(debug_assertions))]
+        fut
+    }
+}
+
 struct RouteNewService<T>
 where
     T: ServiceFactory<Request = ServiceRequest, Error = (Error, ServiceRequest)>,
@@ -348,7 +580,7 @@ mod tests {
 
     use crate::http::{Method, StatusCode};
     use crate::test::{call_service, init_service, read_body, TestRequest};
-    use crate::{error, web, App, HttpResponse};
+    use crate::{error, guard, web, App, HttpResponse};
 
     #[derive(Serialize, PartialEq, Debug)]
     struct MyObject {
@@ -428,4 +660,95 @@ mod tests {
         let body = read_body(resp).await;
         assert_eq!(body, Bytes::from_static(b"{\"name\":\"test\"}"));
     }
+
+    #[actix_rt::test]
+    async fn test_async_guard() {
+        let mut srv = init_service(
+            App::new().service(
+                web::resource("/test")
+                    .route(
+                        web::get()
+                            .async_guard(guard::fn_async_guard(|req| {
+                                let allowed = req.headers().contains_key("x-token");
+                                async move {
+                                    delay_for(Duration::from_millis(10)).await;
+                                    allowed
+                                }
+                            }))
+                            .to(|| HttpResponse::Ok()),
+                    )
+                    .route(web::get().to(|| HttpResponse::Created())),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .header("x-token", "1")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // async guards do not fall through to next route
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_header_contract() {
+        let mut srv = init_service(
+            App::new().service(
+                web::resource("/test")
+                    .route(
+                        web::get()
+                            .require_header("X-Request-Id")
+                            .guarantee_header("etag")
+                            .to(|req: crate::HttpRequest| {
+                                if req.headers().contains_key("x-etag") {
+                                    HttpResponse::Ok().header("etag", "\"1\"").finish()
+                                } else {
+                                    HttpResponse::Ok().finish()
+                                }
+                            }),
+                    )
+                    .route(web::post().to(HttpResponse::Created)),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .header("x-request-id", "1")
+            .header("x-etag", "1")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"Missing required header: x-request-id")
+        );
+
+        // guaranteed headers are validated in debug builds
+        let req = TestRequest::with_uri("/test")
+            .header("x-request-id", "1")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        if cfg!(debug_assertions) {
+            assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        } else {
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+
+        // other routes are not affected
+        let req = TestRequest::post().uri("/test").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let route = web::get().require_header("x-a").guarantee_header("x-b");
+        assert_eq!(route.required_headers(), &["x-a"]);
+        assert_eq!(route.guaranteed_headers(), &["x-b"]);
+    }
 }
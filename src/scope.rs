@@ -65,6 +65,8 @@ pub struct Scope<T = ScopeEndpoint> {
     services: Vec<Box<dyn AppServiceFactory>>,
     guards: Vec<Box<dyn Guard>>,
     default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
+    fall_through: Vec<Box<dyn Guard>>,
+    method_not_allowed: Option<Rc<HttpNewService>>,
     external: Vec<ResourceDef>,
     factory_ref: Rc<RefCell<Option<ScopeFactory>>>,
 }
@@ -80,6 +82,8 @@ impl Scope {
             guards: Vec::new(),
             services: Vec::new(),
             default: Rc::new(RefCell::new(None)),
+            fall_through: Vec::new(),
+            method_not_allowed: None,
             external: Vec::new(),
             factory_ref: fref,
         }
@@ -202,12 +206,13 @@ where
         self.services.extend(cfg.services);
         self.external.extend(cfg.external);
 
-        if !cfg.data.is_empty() {
+        if !cfg.data.is_empty() || !cfg.extensions.is_empty() {
             let mut data = self.data.unwrap_or_else(Extensions::new);
 
             for value in cfg.data.iter() {
                 value.create(&mut data);
             }
+            data.extend(cfg.extensions);
 
             self.data = Some(data);
         }
@@ -303,6 +308,58 @@ where
         self
     }
 
+    /// Pass requests that do not match any route of the scope and match
+    /// supplied guard to the default service of the parent scope or app,
+    /// instead of the scope's default service.
+    ///
+    /// Could be called multiple times, request falls through if any of the
+    /// guards matches. Has no effect if scope does not have default service.
+    ///
+    /// ```rust
+    /// use actix_web::{guard, web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .service(
+    ///             web::scope("/api")
+    ///                 .default_service(web::to(|| HttpResponse::NotFound().json("not found")))
+    ///                 // browsers get html page of the app
+    ///                 .fall_through(guard::HeaderPrefix("accept", "text/html"))
+    ///         )
+    ///         .default_service(web::to(|| HttpResponse::NotFound().body("<h1>Not found</h1>")));
+    /// }
+    /// ```
+    pub fn fall_through<G: Guard + 'static>(mut self, guard: G) -> Self {
+        self.fall_through.push(Box::new(guard));
+        self
+    }
+
+    /// Service to be used if request matches resource path, but none of
+    /// the resource routes.
+    ///
+    /// Applies to resources of the scope and of nested scopes, that do not
+    /// have default service. If not registered, method not allowed service
+    /// of the parent scope or app is being used, otherwise *405* response
+    /// get returned.
+    pub fn method_not_allowed_service<F, U>(mut self, f: F) -> Self
+    where
+        F: IntoServiceFactory<U>,
+        U: ServiceFactory<
+                Config = (),
+                Request = ServiceRequest,
+                Response = ServiceResponse,
+                Error = Error,
+            > + 'static,
+        U::InitError: fmt::Debug,
+    {
+        self.method_not_allowed =
+            Some(Rc::new(boxed::factory(f.into_factory().map_init_err(
+                |e| log::error!("Can not construct method not allowed service: {:?}", e),
+            ))));
+
+        self
+    }
+
     /// Registers middleware, in the form of a middleware component (type),
     /// that runs during inbound processing in the request
     /// life-cycle (request -> response), modifying request as
@@ -340,6 +397,8 @@ where
             guards: self.guards,
             services: self.services,
             default: self.default,
+            fall_through: self.fall_through,
+            method_not_allowed: self.method_not_allowed,
             external: self.external,
             factory_ref: self.factory_ref,
         }
@@ -400,6 +459,8 @@ where
             guards: self.guards,
             services: self.services,
             default: self.default,
+            fall_through: self.fall_through,
+            method_not_allowed: self.method_not_allowed,
             external: self.external,
             factory_ref: self.factory_ref,
         }
@@ -418,12 +479,19 @@ where
 {
     fn register(mut self, config: &mut AppService) {
         // update default resource if needed
-        if self.default.borrow().is_none() {
+        let parent_default = if self.default.borrow().is_none() {
             *self.default.borrow_mut() = Some(config.default_service());
-        }
+            None
+        } else if !self.fall_through.is_empty() {
+            Some(config.fall_through_service())
+        } else {
+            None
+        };
 
         // register nested services
         let mut cfg = config.clone_config();
+        cfg.set_scope_default_service(self.default.borrow().clone().unwrap());
+        cfg.set_method_not_allowed_service(self.method_not_allowed.take());
         self.services
             .into_iter()
             .for_each(|mut srv| srv.register(&mut cfg));
@@ -444,6 +512,8 @@ where
         *self.factory_ref.borrow_mut() = Some(ScopeFactory {
             data: self.data.take().map(Rc::new),
             default: self.default.clone(),
+            parent_default,
+            fall_through: Rc::new(std::mem::replace(&mut self.fall_through, Vec::new())),
             services: Rc::new(
                 cfg.into_services()
                     .1
@@ -477,6 +547,8 @@ pub struct ScopeFactory {
     data: Option<Rc<Extensions>>,
     services: Rc<Vec<(ResourceDef, HttpNewService, RefCell<Option<Guards>>)>>,
     default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
+    parent_default: Option<Rc<HttpNewService>>,
+    fall_through: Rc<Guards>,
 }
 
 impl ServiceFactory for ScopeFactory {
@@ -510,6 +582,12 @@ impl ServiceFactory for ScopeFactory {
             default: None,
             data: self.data.clone(),
             default_fut,
+            parent_default: None,
+            parent_default_fut: self
+                .parent_default
+                .as_ref()
+                .map(|default| default.new_service(())),
+            fall_through: self.fall_through.clone(),
         }
     }
 }
@@ -522,6 +600,9 @@ pub struct ScopeFactoryResponse {
     data: Option<Rc<Extensions>>,
     default: Option<HttpService>,
     default_fut: Option<LocalBoxFuture<'static, Result<HttpService, ()>>>,
+    parent_default: Option<HttpService>,
+    parent_default_fut: Option<LocalBoxFuture<'static, Result<HttpService, ()>>>,
+    fall_through: Rc<Guards>,
 }
 
 type HttpServiceFut = LocalBoxFuture<'static, Result<HttpService, ()>>;
@@ -544,6 +625,13 @@ impl Future for ScopeFactoryResponse {
             }
         }
 
+        if let Some(ref mut fut) = self.parent_default_fut {
+            match Pin::new(fut).poll(cx)? {
+                Poll::Ready(default) => self.parent_default = Some(default),
+                Poll::Pending => done = false,
+            }
+        }
+
         // poll http services
         for item in &mut self.fut {
             let res = match item {
@@ -585,6 +673,8 @@ impl Future for ScopeFactoryResponse {
                 data: self.data.clone(),
                 router: router.finish(),
                 default: self.default.take(),
+                parent_default: self.parent_default.take(),
+                fall_through: self.fall_through.clone(),
                 _ready: None,
             }))
         } else {
@@ -597,6 +687,8 @@ pub struct ScopeService {
     data: Option<Rc<Extensions>>,
     router: Router<HttpService, Vec<Box<dyn Guard>>>,
     default: Option<HttpService>,
+    parent_default: Option<HttpService>,
+    fall_through: Rc<Guards>,
     _ready: Option<(ServiceRequest, ResourceInfo)>,
 }
 
@@ -627,6 +719,11 @@ impl Service for ScopeService {
                 req.set_data_container(data.clone());
             }
             Either::Left(srv.call(req))
+        } else if self.parent_default.is_some()
+            && self.fall_through.iter().any(|f| f.check(req.head()))
+        {
+            // parent's default service uses parent's data
+            Either::Left(self.parent_default.as_mut().unwrap().call(req))
         } else if let Some(ref mut default) = self.default {
             if let Some(ref data) = self.data {
                 req.set_data_container(data.clone());
@@ -1042,6 +1139,104 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
     }
 
+    #[actix_rt::test]
+    async fn test_default_resource_fall_through() {
+        let mut srv =
+            init_service(
+                App::new()
+                    .service(
+                        web::scope("/app")
+                            .service(web::scope("/nested").service(
+                                web::resource("/path1").to(|| HttpResponse::Ok()),
+                            ))
+                            .service(
+                                web::scope("/inner")
+                                    .default_service(web::to(|| HttpResponse::Gone()))
+                                    .fall_through(guard::Header("accept", "text/plain")),
+                            )
+                            .default_service(web::to(|| HttpResponse::BadRequest()))
+                            .fall_through(guard::Header("accept", "text/html")),
+                    )
+                    .default_service(web::to(|| HttpResponse::NotFound())),
+            )
+            .await;
+
+        let req = TestRequest::with_uri("/app/non-exist").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/app/non-exist")
+            .header(header::ACCEPT, "text/html")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // nested scope without default service uses app's default service
+        let req = TestRequest::with_uri("/app/nested/non-exist").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // nested scope falls through to default service of the parent scope
+        let req = TestRequest::with_uri("/app/inner/non-exist").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::GONE);
+        let req = TestRequest::with_uri("/app/inner/non-exist")
+            .header(header::ACCEPT, "text/plain")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_method_not_allowed_service() {
+        let mut srv = init_service(
+            App::new()
+                .service(
+                    web::scope("/app")
+                        .service(
+                            web::resource("/path1")
+                                .route(web::get().to(|| HttpResponse::Ok())),
+                        )
+                        .service(
+                            web::resource("/path2")
+                                .route(web::get().to(|| HttpResponse::Ok()))
+                                .default_service(web::to(|| HttpResponse::Conflict())),
+                        )
+                        .method_not_allowed_service(web::to(|| {
+                            HttpResponse::BadRequest()
+                        }))
+                        .default_service(web::to(|| HttpResponse::Gone())),
+                )
+                .service(
+                    web::resource("/path3").route(web::get().to(|| HttpResponse::Ok())),
+                )
+                .method_not_allowed_service(web::to(|| HttpResponse::Forbidden())),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/app/path1")
+            .method(Method::POST)
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/app/path2")
+            .method(Method::POST)
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let req = TestRequest::with_uri("/app/non-exist").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::GONE);
+
+        let req = TestRequest::with_uri("/path3")
+            .method(Method::POST)
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
     #[actix_rt::test]
     async fn test_middleware() {
         let mut srv =
@@ -1,14 +1,23 @@
 use std::marker::PhantomData;
+#[cfg(feature = "rustls")]
+use std::sync::RwLock;
 use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
 use std::{fmt, io, net};
 
-use actix_http::{body::MessageBody, Error, HttpService, KeepAlive, Request, Response};
+use actix_http::{
+    body::MessageBody, BandwidthLimit, BufferPool, Error, HealthMonitor, HttpService,
+    KeepAlive, LoadShedPolicy, Request, RequestHead, Response,
+};
+use actix_rt::net::TcpStream;
 use actix_server::{Server, ServerBuilder};
 use actix_service::{map_config, IntoServiceFactory, Service, ServiceFactory};
+use futures::future::{ready, Ready};
 
 use net2::TcpBuilder;
 
-#[cfg(unix)]
+#[cfg(any(unix, feature = "openssl"))]
 use actix_http::Protocol;
 #[cfg(unix)]
 use actix_service::pipeline_factory;
@@ -19,8 +28,18 @@ use futures::future::ok;
 use actix_tls::openssl::{AlpnError, SslAcceptor, SslAcceptorBuilder};
 #[cfg(feature = "rustls")]
 use actix_tls::rustls::ServerConfig as RustlsServerConfig;
+#[cfg(feature = "rustls")]
+use rust_tls::{sign::CertifiedKey, ResolvesServerCert};
 
+use crate::bind::{BindPolicy, BindReport};
 use crate::config::AppConfig;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use crate::info::PeerCertificate;
+#[cfg(unix)]
+use crate::info::PeerCredentials;
+use crate::server_config::{ServerConfigFile, TlsSection};
+use crate::sticky::{StickyFactory, StickyRouting};
+use crate::worker::{WorkerAffinity, Workers};
 
 struct Socket {
     scheme: &'static str,
@@ -32,6 +51,38 @@ struct Config {
     keep_alive: KeepAlive,
     client_timeout: u64,
     client_shutdown: u64,
+    read_buf_limit: usize,
+    write_coalesce_limit: usize,
+    date_header: bool,
+    max_headers: usize,
+    max_header_size: usize,
+    max_request_line: usize,
+    strict_parsing: bool,
+    http10_keep_alive: bool,
+    http10_chunked: bool,
+    http10_empty_length: bool,
+    h2_prior_knowledge: bool,
+    proxy_protocol: bool,
+    rate_limit: u32,
+    rate_limit_close: bool,
+    bandwidth_limit: Option<BandwidthLimit>,
+    buffer_pool: Option<BufferPool>,
+    h2_streams: usize,
+    h2_peer_streams: usize,
+    h2_concurrent_streams: Option<u32>,
+    h2_window: Option<u32>,
+    h2_conn_window: Option<u32>,
+    h2_frame_size: Option<u32>,
+    h2_ping_interval: Option<Duration>,
+    h2_ping_timeout: Duration,
+    load_shed: Option<LoadShedPolicy>,
+    health_monitor: Option<HealthMonitor>,
+    expect: Option<Arc<ExpectFn>>,
+    on_connect: Option<Arc<ConnectFn>>,
+    #[cfg(feature = "openssl")]
+    alpn_select: Option<Arc<AlpnFn>>,
+    workers: Workers,
+    sticky: Option<StickyRouting>,
 }
 
 /// An HTTP Server.
@@ -77,6 +128,8 @@ where
     S::Error: Into<Error> + 'static,
     S::InitError: fmt::Debug,
     S::Response: Into<Response<B>> + 'static,
+    S::Future: 'static,
+    S::Service: 'static,
     <S::Service as Service>::Future: 'static,
     B: MessageBody + 'static,
 {
@@ -89,6 +142,38 @@ where
                 keep_alive: KeepAlive::Timeout(5),
                 client_timeout: 5000,
                 client_shutdown: 5000,
+                read_buf_limit: 32_768,
+                write_coalesce_limit: 4096,
+                date_header: true,
+                max_headers: 96,
+                max_header_size: 131_072,
+                max_request_line: 65_536,
+                strict_parsing: false,
+                http10_keep_alive: true,
+                http10_chunked: true,
+                http10_empty_length: true,
+                h2_prior_knowledge: false,
+                proxy_protocol: false,
+                rate_limit: 0,
+                rate_limit_close: false,
+                bandwidth_limit: None,
+                buffer_pool: None,
+                h2_streams: 0,
+                h2_peer_streams: 0,
+                h2_concurrent_streams: None,
+                h2_window: None,
+                h2_conn_window: None,
+                h2_frame_size: None,
+                h2_ping_interval: None,
+                h2_ping_timeout: Duration::from_secs(20),
+                load_shed: None,
+                health_monitor: None,
+                expect: None,
+                on_connect: None,
+                #[cfg(feature = "openssl")]
+                alpn_select: None,
+                workers: Workers::default(),
+                sticky: None,
             })),
             backlog: 1024,
             sockets: Vec::new(),
@@ -97,6 +182,145 @@ where
         }
     }
 
+    /// Create new http server with application factory and settings of the
+    /// configuration file.
+    ///
+    /// Listeners of the configuration file are bound, tls listeners require
+    /// `rustls` or `openssl` feature.
+    ///
+    /// ```rust,no_run
+    /// use actix_web::{web, App, HttpResponse, HttpServer, ServerConfigFile};
+    ///
+    /// #[actix_rt::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     let config = ServerConfigFile::load("server.toml")?;
+    ///     let limits = config.limits();
+    ///
+    ///     HttpServer::from_config(
+    ///         move || App::new()
+    ///             .configure(limits.clone())
+    ///             .service(web::resource("/").to(|| HttpResponse::Ok())),
+    ///         &config,
+    ///     )?
+    ///     .run()
+    ///     .await
+    /// }
+    /// ```
+    pub fn from_config(factory: F, config: &ServerConfigFile) -> io::Result<Self> {
+        let mut srv = HttpServer::new(factory);
+
+        if let Some(num) = config.workers {
+            srv = srv.workers(num);
+        }
+        if let Some(backlog) = config.backlog {
+            srv = srv.backlog(backlog);
+        }
+        if let Some(num) = config.max_connections {
+            srv = srv.maxconn(num);
+        }
+        if let Some(num) = config.max_connection_rate {
+            srv = srv.maxconnrate(num);
+        }
+        if let Some(secs) = config.keep_alive {
+            srv = srv.keep_alive(if secs == 0 {
+                KeepAlive::Disabled
+            } else {
+                KeepAlive::Timeout(secs)
+            });
+        }
+        if let Some(val) = config.client_timeout {
+            srv = srv.client_timeout(val);
+        }
+        if let Some(val) = config.client_shutdown {
+            srv = srv.client_shutdown(val);
+        }
+        if let Some(secs) = config.shutdown_timeout {
+            srv = srv.shutdown_timeout(secs);
+        }
+        if let Some(limit) = config.read_buf_limit {
+            srv = srv.read_buf_limit(limit);
+        }
+        if let Some(ref host) = config.hostname {
+            srv = srv.server_hostname(host);
+        }
+
+        for addr in &config.bind {
+            srv = srv.bind(addr.as_str())?;
+        }
+        if let Some(ref tls) = config.tls {
+            srv = srv.bind_tls_section(tls)?;
+        }
+        Ok(srv)
+    }
+
+    #[cfg(feature = "rustls")]
+    fn bind_tls_section(mut self, tls: &TlsSection) -> io::Result<Self> {
+        use rust_tls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+        use rust_tls::NoClientAuth;
+        use std::fs::File;
+        use std::io::BufReader;
+
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let certs = certs(&mut BufReader::new(File::open(&tls.cert)?))
+            .map_err(|_| invalid("Invalid certificate file"))?;
+        let mut keys = pkcs8_private_keys(&mut BufReader::new(File::open(&tls.key)?))
+            .map_err(|_| invalid("Invalid private key file"))?;
+        if keys.is_empty() {
+            keys = rsa_private_keys(&mut BufReader::new(File::open(&tls.key)?))
+                .map_err(|_| invalid("Invalid private key file"))?;
+        }
+        if keys.is_empty() {
+            return Err(invalid("Private key file has no keys"));
+        }
+
+        let mut config = RustlsServerConfig::new(NoClientAuth::new());
+        config
+            .set_single_cert(certs, keys.remove(0))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        for addr in &tls.bind {
+            self = self.bind_rustls(addr.as_str(), config.clone())?;
+        }
+        Ok(self)
+    }
+
+    #[cfg(all(feature = "openssl", not(feature = "rustls")))]
+    fn bind_tls_section(mut self, tls: &TlsSection) -> io::Result<Self> {
+        use open_ssl::ssl::{SslFiletype, SslMethod};
+
+        let tls_err = |e| io::Error::new(io::ErrorKind::Other, e);
+
+        let mut builder =
+            SslAcceptor::mozilla_intermediate(SslMethod::tls()).map_err(tls_err)?;
+        builder
+            .set_private_key_file(&tls.key, SslFiletype::PEM)
+            .map_err(tls_err)?;
+        builder
+            .set_certificate_chain_file(&tls.cert)
+            .map_err(tls_err)?;
+        let acceptor = openssl_acceptor(builder, self.alpn_selector())?;
+
+        for addr in &tls.bind {
+            for lst in self.bind2(addr.as_str())? {
+                self = self.listen_ssl_inner(lst, acceptor.clone())?;
+            }
+        }
+        Ok(self)
+    }
+
+    #[cfg(not(any(feature = "openssl", feature = "rustls")))]
+    fn bind_tls_section(self, tls: &TlsSection) -> io::Result<Self> {
+        if tls.bind.is_empty() {
+            Ok(self)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Tls listeners require `openssl` or `rustls` feature",
+            ))
+        }
+    }
+
     /// Set number of workers to start.
     ///
     /// By default http server uses number of available logical cpu as threads
@@ -106,6 +330,40 @@ where
         self
     }
 
+    /// Pin worker threads to CPU cores.
+    ///
+    /// Workers are shared by all listeners, worker count can not be set per
+    /// listener.
+    ///
+    /// Worker index is available to handlers via
+    /// [`WorkerIndex`](web/struct.WorkerIndex.html) extractor.
+    ///
+    /// ```rust,no_run
+    /// use actix_web::{web, App, HttpServer, WorkerAffinity};
+    ///
+    /// #[actix_rt::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     HttpServer::new(|| App::new().route("/", web::to(|| async { "ok" })))
+    ///         .workers(4)
+    ///         .worker_affinity(WorkerAffinity::Core)
+    ///         .bind("127.0.0.1:8080")?
+    ///         .run()
+    ///         .await
+    /// }
+    /// ```
+    pub fn worker_affinity(self, affinity: WorkerAffinity) -> Self {
+        self.config.lock().unwrap().workers.set_affinity(affinity);
+        self
+    }
+
+    /// Route requests with the same key to the same worker.
+    ///
+    /// See [`StickyRouting`](struct.StickyRouting.html) for details.
+    pub fn sticky(self, routing: StickyRouting) -> Self {
+        self.config.lock().unwrap().sticky = Some(routing);
+        self
+    }
+
     /// Set the maximum number of pending connections.
     ///
     /// This refers to the number of clients that can be waiting to be served.
@@ -179,6 +437,413 @@ where
         self
     }
 
+    /// Set maximum size of a single read from the connection.
+    ///
+    /// Read buffer grows up to this limit on large requests and is released
+    /// when connection becomes idle.
+    ///
+    /// By default limit is set to 32kB, limit is at least 4kB.
+    pub fn read_buf_limit(self, limit: usize) -> Self {
+        self.config.lock().unwrap().read_buf_limit = limit;
+        self
+    }
+
+    /// Set size of response payload chunks that are written without copying.
+    ///
+    /// Smaller chunks are copied to write buffer, larger ones are written
+    /// together with response head using vectored writes. Zero copies all
+    /// chunks.
+    ///
+    /// By default limit is set to 4kB.
+    pub fn write_coalesce_limit(self, limit: usize) -> Self {
+        self.config.lock().unwrap().write_coalesce_limit = limit;
+        self
+    }
+
+    /// Insert `Date` header to responses without one.
+    ///
+    /// Disable if the header is set by a gateway in front of the server,
+    /// responses could still set the header with `HttpResponseBuilder::date()`.
+    ///
+    /// By default the header is inserted.
+    pub fn date_header(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().date_header = enabled;
+        self
+    }
+
+    /// Set maximum number of request headers.
+    ///
+    /// Requests with more headers are rejected with
+    /// *REQUEST HEADER FIELDS TOO LARGE* response.
+    ///
+    /// By default limit is set to 96 headers.
+    pub fn max_headers(self, num: usize) -> Self {
+        self.config.lock().unwrap().max_headers = num;
+        self
+    }
+
+    /// Set maximum size of request head in bytes, request line included.
+    ///
+    /// Requests with larger head are rejected with
+    /// *REQUEST HEADER FIELDS TOO LARGE* response.
+    ///
+    /// By default limit is set to 128kB.
+    pub fn max_header_size(self, size: usize) -> Self {
+        self.config.lock().unwrap().max_header_size = size;
+        self
+    }
+
+    /// Set maximum length of request line in bytes.
+    ///
+    /// Requests with longer request line are rejected with *URI TOO LONG*
+    /// response.
+    ///
+    /// By default limit is set to 64kB.
+    pub fn max_request_line(self, size: usize) -> Self {
+        self.config.lock().unwrap().max_request_line = size;
+        self
+    }
+
+    /// Reject ambiguous requests that could be used for request smuggling.
+    ///
+    /// Requests with both `Transfer-Encoding` and `Content-Length` headers,
+    /// with duplicate `Content-Length` or `Transfer-Encoding` headers, with
+    /// `Transfer-Encoding` other than `chunked` or with obsolete line folding
+    /// of header values are rejected with *BAD REQUEST* response.
+    ///
+    /// By default strict parsing is disabled.
+    pub fn strict_parsing(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().strict_parsing = enabled;
+        self
+    }
+
+    /// Keep connections of http/1.0 requests with `Connection: keep-alive`
+    /// header open.
+    ///
+    /// By default keep-alive of http/1.0 clients is honored.
+    pub fn http10_keep_alive(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().http10_keep_alive = enabled;
+        self
+    }
+
+    /// Use chunked transfer encoding for http/1.0 responses with body of
+    /// unknown length, otherwise body is delimited by closing the connection.
+    ///
+    /// By default chunked encoding is used.
+    pub fn http10_chunked(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().http10_chunked = enabled;
+        self
+    }
+
+    /// Send `Content-Length: 0` header with empty bodies of http/1.0
+    /// responses, otherwise connection is closed after the response.
+    ///
+    /// By default the header is sent.
+    pub fn http10_empty_length(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().http10_empty_length = enabled;
+        self
+    }
+
+    /// Set hook for requests with `Expect: 100-continue` header.
+    ///
+    /// Hook is called with request head before server replies with
+    /// *100 Continue* and client sends request body. Error returned by the
+    /// hook is sent as response instead, i.e. *EXPECTATION FAILED* or
+    /// *PAYLOAD TOO LARGE*, and request is not passed to the application.
+    ///
+    /// By default all expectations are accepted. Only http/1 connections
+    /// are affected.
+    ///
+    /// ```rust
+    /// use actix_web::{error, http::header, web, App, HttpResponse, HttpServer};
+    ///
+    /// # fn main() {
+    /// let srv = HttpServer::new(|| {
+    ///     App::new().route("/", web::post().to(|| HttpResponse::Ok()))
+    /// })
+    /// .expect(|head| {
+    ///     let len = head
+    ///         .headers
+    ///         .get(header::CONTENT_LENGTH)
+    ///         .and_then(|len| len.to_str().ok())
+    ///         .and_then(|len| len.parse::<u64>().ok());
+    ///     match len {
+    ///         Some(len) if len <= 1_048_576 => Ok(()),
+    ///         Some(_) => Err(error::ErrorPayloadTooLarge("Payload is too large")),
+    ///         None => Err(error::ErrorExpectationFailed("Length is required")),
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn expect<H>(self, hook: H) -> Self
+    where
+        H: Fn(&RequestHead) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        self.config.lock().unwrap().expect = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set callback that can reject connections.
+    ///
+    /// Callback is called once per connection of every listener before any
+    /// request is read, with peer address of the connection. Peer address is
+    /// the client address from PROXY protocol header if it is enabled, and
+    /// `None` for unix domain sockets. If callback returns error, connection
+    /// is closed, i.e. for banned peer addresses.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, HttpResponse, HttpServer};
+    ///
+    /// # fn main() {
+    /// let srv = HttpServer::new(|| App::new().route("/", web::to(|| HttpResponse::Ok())))
+    ///     .on_connect_ext(|addr| match addr {
+    ///         Some(addr) if addr.ip().is_loopback() => Ok(()),
+    ///         _ => Err("peer is not allowed"),
+    ///     });
+    /// # }
+    /// ```
+    pub fn on_connect_ext<C, E>(self, hook: C) -> Self
+    where
+        C: Fn(Option<net::SocketAddr>) -> Result<(), E> + Send + Sync + 'static,
+        E: fmt::Display,
+    {
+        self.config.lock().unwrap().on_connect =
+            Some(Arc::new(move |addr| hook(addr).map_err(|e| e.to_string())));
+        self
+    }
+
+    /// Accept HTTP/2 connections with prior knowledge on plain tcp listeners.
+    ///
+    /// Use it for internal services behind a trusted load balancer that
+    /// speaks HTTP/2 without tls. HTTP/1 clients are served as usual,
+    /// `Upgrade: h2c` requests are not switched to HTTP/2.
+    ///
+    /// By default it is disabled.
+    pub fn h2_prior_knowledge(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().h2_prior_knowledge = enabled;
+        self
+    }
+
+    /// Read PROXY protocol header of connections accepted on tcp listeners.
+    ///
+    /// Source address from the header is exposed as peer address of
+    /// requests, by `HttpRequest::peer_addr()` and `ConnectionInfo::remote()`.
+    /// Connections without the header are closed, so enable it only when all
+    /// clients connect through a proxy like HAProxy or AWS network load
+    /// balancer. Unix socket listeners are not affected.
+    ///
+    /// By default PROXY protocol is disabled.
+    pub fn proxy_protocol(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().proxy_protocol = enabled;
+        self
+    }
+
+    /// Set maximum number of requests per second of a single connection.
+    ///
+    /// Requests over the limit, pipelined http/1 requests or http/2 streams,
+    /// are responded with `429 Too Many Requests` by the connection
+    /// dispatcher, before routing and middlewares.
+    ///
+    /// By default requests are not limited, set to `0` to disable.
+    pub fn max_requests_per_second(self, limit: u32) -> Self {
+        self.config.lock().unwrap().rate_limit = limit;
+        self
+    }
+
+    /// Close connections that exceed request rate.
+    ///
+    /// By default connections are kept open after `429 Too Many Requests`
+    /// response.
+    pub fn close_on_rate_limit(self, close: bool) -> Self {
+        self.config.lock().unwrap().rate_limit_close = close;
+        self
+    }
+
+    /// Limit write bandwidth of a single connection.
+    ///
+    /// Limit of a single response is set by inserting `BandwidthLimit` to
+    /// response extensions, i.e. by a handler of large downloads.
+    ///
+    /// ```rust
+    /// use actix_web::dev::BandwidthLimit;
+    /// use actix_web::HttpResponse;
+    ///
+    /// async fn download() -> HttpResponse {
+    ///     let mut res = HttpResponse::Ok().body(vec![0; 1024 * 1024]);
+    ///     res.extensions_mut().insert(BandwidthLimit::new(128 * 1024));
+    ///     res
+    /// }
+    /// ```
+    ///
+    /// By default bandwidth is not limited.
+    pub fn bandwidth_limit(self, limit: BandwidthLimit) -> Self {
+        self.config.lock().unwrap().bandwidth_limit = Some(limit);
+        self
+    }
+
+    /// Take read and write buffers of http/1 connections from a pool.
+    ///
+    /// Every worker keeps own pool, so connection churn reuses buffers
+    /// instead of allocating them for every connection.
+    ///
+    /// ```rust
+    /// use actix_web::dev::BufferPool;
+    /// use actix_web::{App, HttpServer};
+    ///
+    /// let srv = HttpServer::new(|| App::new())
+    ///     .buffer_pool(BufferPool::new(1024).chunk_size(16 * 1024));
+    /// ```
+    ///
+    /// By default buffers are not pooled.
+    pub fn buffer_pool(self, pool: BufferPool) -> Self {
+        self.config.lock().unwrap().buffer_pool = Some(pool);
+        self
+    }
+
+    /// Set maximum number of concurrently processed streams of a http/2
+    /// connection.
+    ///
+    /// Streams over the limit are queued until earlier streams complete, up
+    /// to `limit` streams are queued and the rest are reset with
+    /// `REFUSED_STREAM` error. Pushed streams are counted as well.
+    ///
+    /// By default streams are not limited, set to `0` to disable.
+    pub fn h2_max_streams(self, limit: usize) -> Self {
+        self.config.lock().unwrap().h2_streams = limit;
+        self
+    }
+
+    /// Set maximum number of concurrently processed http/2 streams of a
+    /// single client address.
+    ///
+    /// Limit is counted per worker, over all connections of the client,
+    /// including pushed streams. Streams over the limit are reset with
+    /// `REFUSED_STREAM` error.
+    ///
+    /// By default streams are not limited, set to `0` to disable.
+    pub fn h2_max_peer_streams(self, limit: usize) -> Self {
+        self.config.lock().unwrap().h2_peer_streams = limit;
+        self
+    }
+
+    /// Set `SETTINGS_MAX_CONCURRENT_STREAMS` advertised to http/2 clients.
+    ///
+    /// Streams over the advertised limit are reset with `REFUSED_STREAM`
+    /// error. By default number of streams is not advertised.
+    pub fn h2_max_concurrent_streams(self, max: u32) -> Self {
+        self.config.lock().unwrap().h2_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Set initial flow control window of http/2 streams in bytes.
+    ///
+    /// By default window is 64kB. Panics if `size` is over 2<sup>31</sup>-1.
+    pub fn h2_initial_window_size(self, size: u32) -> Self {
+        assert!(size < 1 << 31, "Window size is over 2^31-1");
+        self.config.lock().unwrap().h2_window = Some(size);
+        self
+    }
+
+    /// Set initial flow control window of http/2 connections in bytes.
+    ///
+    /// By default window is 64kB. Panics if `size` is over 2<sup>31</sup>-1.
+    pub fn h2_initial_connection_window_size(self, size: u32) -> Self {
+        assert!(size < 1 << 31, "Window size is over 2^31-1");
+        self.config.lock().unwrap().h2_conn_window = Some(size);
+        self
+    }
+
+    /// Set maximum size of http/2 frames accepted by the server in bytes.
+    ///
+    /// By default frames are up to 16kB. Panics if `size` is not within 16kB
+    /// and 16MB-1.
+    pub fn h2_max_frame_size(self, size: u32) -> Self {
+        assert!(
+            (16_384..=16_777_215).contains(&size),
+            "Frame size is not within 16kB and 16MB-1"
+        );
+        self.config.lock().unwrap().h2_frame_size = Some(size);
+        self
+    }
+
+    /// Send http/2 `PING` frames to clients in the interval.
+    ///
+    /// Connections of clients that do not answer a ping within
+    /// `h2_keep_alive_timeout()` are closed. By default pings are not sent.
+    pub fn h2_keep_alive_interval(self, interval: Duration) -> Self {
+        self.config.lock().unwrap().h2_ping_interval = Some(interval);
+        self
+    }
+
+    /// Set time to wait for answer of http/2 keep-alive ping.
+    ///
+    /// By default timeout is 20 seconds.
+    pub fn h2_keep_alive_timeout(self, timeout: Duration) -> Self {
+        self.config.lock().unwrap().h2_ping_timeout = timeout;
+        self
+    }
+
+    /// Reject new requests of overloaded workers.
+    ///
+    /// Policy receives load of the worker, i.e. number of in-flight requests
+    /// and event loop lag, and returns `Retry-After` delay for requests that
+    /// have to be rejected with `503 Service Unavailable` response.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use actix_web::dev::LoadShedPolicy;
+    /// use actix_web::{web, App, HttpResponse, HttpServer};
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let policy = LoadShedPolicy::new(|load| {
+    ///         if load.loop_lag > Duration::from_millis(100) {
+    ///             Some(Duration::from_secs(10))
+    ///         } else {
+    ///             None
+    ///         }
+    ///     });
+    ///
+    ///     HttpServer::new(|| {
+    ///         App::new().route("/", web::get().to(|| HttpResponse::Ok()))
+    ///     })
+    ///     .load_shed(policy)
+    ///     .bind("127.0.0.1:0")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// By default requests are not rejected.
+    pub fn load_shed(self, policy: LoadShedPolicy) -> Self {
+        self.config.lock().unwrap().load_shed = Some(policy);
+        self
+    }
+
+    /// Log unhealthy runtime of workers.
+    ///
+    /// Event loop lag is sampled on every worker regardless of the monitor,
+    /// samples are available as `Load::current()` signals. Event loop lag
+    /// and queue of the blocking thread pool, used by `web::block()`, are
+    /// logged if they exceed thresholds of the monitor.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use actix_web::dev::HealthMonitor;
+    /// use actix_web::{web, App, HttpResponse, HttpServer};
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     HttpServer::new(|| {
+    ///         App::new().route("/", web::get().to(|| HttpResponse::Ok()))
+    ///     })
+    ///     .health_monitor(HealthMonitor::new().loop_lag(Duration::from_millis(50)))
+    ///     .bind("127.0.0.1:0")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn health_monitor(self, monitor: HealthMonitor) -> Self {
+        self.config.lock().unwrap().health_monitor = Some(monitor);
+        self
+    }
+
     /// Set server host name.
     ///
     /// Host name is used by application router as a hostname for url generation.
@@ -248,6 +913,8 @@ where
             lst,
             move || {
                 let c = cfg.lock().unwrap();
+                c.workers.start();
+                let hook = ConnectHook(c.on_connect.clone());
                 let cfg = AppConfig::new(
                     false,
                     addr,
@@ -257,8 +924,39 @@ where
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
+                    .read_buf_limit(c.read_buf_limit)
+                    .write_coalesce_limit(c.write_coalesce_limit)
+                    .date_header(c.date_header)
+                    .max_headers(c.max_headers)
+                    .max_header_size(c.max_header_size)
+                    .max_request_line(c.max_request_line)
+                    .strict_parsing(c.strict_parsing)
+                    .http10_keep_alive(c.http10_keep_alive)
+                    .http10_chunked(c.http10_chunked)
+                    .http10_empty_length(c.http10_empty_length)
+                    .max_requests_per_second(c.rate_limit)
+                    .close_on_rate_limit(c.rate_limit_close)
+                    .bandwidth_limit(c.bandwidth_limit)
+                    .buffer_pool(c.buffer_pool)
+                    .h2_max_streams(c.h2_streams)
+                    .h2_max_peer_streams(c.h2_peer_streams)
+                    .h2_max_concurrent_streams(c.h2_concurrent_streams)
+                    .h2_initial_window_size(c.h2_window)
+                    .h2_initial_connection_window_size(c.h2_conn_window)
+                    .h2_max_frame_size(c.h2_frame_size)
+                    .h2_keep_alive_interval(c.h2_ping_interval)
+                    .h2_keep_alive_timeout(c.h2_ping_timeout)
+                    .load_shed(c.load_shed.clone())
+                    .health_monitor(c.health_monitor)
+                    .proxy_protocol(c.proxy_protocol)
+                    .h2_prior_knowledge(c.h2_prior_knowledge)
                     .local_addr(addr)
-                    .finish(map_config(factory(), move |_| cfg.clone()))
+                    .expect(ExpectHook(c.expect.clone()))
+                    .on_connect_ext(move |_: &TcpStream, addr| hook.check(addr))
+                    .finish(StickyFactory::new(
+                        c.sticky.clone(),
+                        map_config(factory(), move |_| cfg.clone()),
+                    ))
                     .tcp()
             },
         )?;
@@ -269,12 +967,61 @@ where
     /// Use listener for accepting incoming tls connection requests
     ///
     /// This method sets alpn protocols to "h2" and "http/1.1"
+    ///
+    /// Certificates of the acceptor can not be replaced while server is
+    /// running, use [`RustlsReload`](struct.RustlsReload.html) with rustls
+    /// listeners for reloading certificates without restart.
     pub fn listen_openssl(
         self,
         lst: net::TcpListener,
         builder: SslAcceptorBuilder,
     ) -> io::Result<Self> {
-        self.listen_ssl_inner(lst, openssl_acceptor(builder)?)
+        let acceptor = openssl_acceptor(builder, self.alpn_selector())?;
+        self.listen_ssl_inner(lst, acceptor)
+    }
+
+    #[cfg(feature = "openssl")]
+    /// Set callback selecting protocol of tls connections.
+    ///
+    /// Callback inspects server name and protocols offered by client with
+    /// alpn, it returns protocol of the connection or `None` to reject the
+    /// connection. Callback is called with empty protocol list for clients
+    /// without alpn, such connections are closed after tls handshake if
+    /// callback rejects them. Connections with selected protocol that is not
+    /// offered by client are served over http/1.1. By default http/2 is
+    /// preferred.
+    ///
+    /// Callback applies to openssl listeners bound after the call. Rustls
+    /// listeners negotiate protocols of `ServerConfig`, callback is not
+    /// called for them and rustls connections without alpn are not rejected.
+    ///
+    /// ```rust
+    /// use actix_web::dev::{ClientHello, Protocol};
+    /// use actix_web::{web, App, HttpResponse, HttpServer};
+    ///
+    /// fn select(hello: &ClientHello<'_>) -> Option<Protocol> {
+    ///     match hello.server_name() {
+    ///         Some("legacy.example.com") => Some(Protocol::Http1),
+    ///         Some(_) if hello.offers(Protocol::Http2) => Some(Protocol::Http2),
+    ///         Some(_) => Some(Protocol::Http1),
+    ///         None => None,
+    ///     }
+    /// }
+    ///
+    /// let srv = HttpServer::new(|| App::new().route("/", web::to(|| HttpResponse::Ok())))
+    ///     .alpn_select(select);
+    /// ```
+    pub fn alpn_select<P>(self, f: P) -> Self
+    where
+        P: Fn(&ClientHello<'_>) -> Option<Protocol> + Send + Sync + 'static,
+    {
+        self.config.lock().unwrap().alpn_select = Some(Arc::new(f));
+        self
+    }
+
+    #[cfg(feature = "openssl")]
+    fn alpn_selector(&self) -> Option<Arc<AlpnFn>> {
+        self.config.lock().unwrap().alpn_select.clone()
     }
 
     #[cfg(feature = "openssl")]
@@ -285,6 +1032,7 @@ where
     ) -> io::Result<Self> {
         let factory = self.factory.clone();
         let cfg = self.config.clone();
+        let select = self.alpn_selector();
         let addr = lst.local_addr().unwrap();
         self.sockets.push(Socket {
             addr,
@@ -296,6 +1044,9 @@ where
             lst,
             move || {
                 let c = cfg.lock().unwrap();
+                c.workers.start();
+                let select = select.clone();
+                let hook = ConnectHook(c.on_connect.clone());
                 let cfg = AppConfig::new(
                     true,
                     addr,
@@ -304,8 +1055,42 @@ where
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
+                    .read_buf_limit(c.read_buf_limit)
+                    .write_coalesce_limit(c.write_coalesce_limit)
+                    .date_header(c.date_header)
+                    .max_headers(c.max_headers)
+                    .max_header_size(c.max_header_size)
+                    .max_request_line(c.max_request_line)
+                    .strict_parsing(c.strict_parsing)
+                    .http10_keep_alive(c.http10_keep_alive)
+                    .http10_chunked(c.http10_chunked)
+                    .http10_empty_length(c.http10_empty_length)
+                    .max_requests_per_second(c.rate_limit)
+                    .close_on_rate_limit(c.rate_limit_close)
+                    .bandwidth_limit(c.bandwidth_limit)
+                    .buffer_pool(c.buffer_pool)
+                    .h2_max_streams(c.h2_streams)
+                    .h2_max_peer_streams(c.h2_peer_streams)
+                    .h2_max_concurrent_streams(c.h2_concurrent_streams)
+                    .h2_initial_window_size(c.h2_window)
+                    .h2_initial_connection_window_size(c.h2_conn_window)
+                    .h2_max_frame_size(c.h2_frame_size)
+                    .h2_keep_alive_interval(c.h2_ping_interval)
+                    .h2_keep_alive_timeout(c.h2_ping_timeout)
+                    .load_shed(c.load_shed.clone())
+                    .health_monitor(c.health_monitor)
+                    .proxy_protocol(c.proxy_protocol)
                     .client_disconnect(c.client_shutdown)
-                    .finish(map_config(factory(), move |_| cfg.clone()))
+                    .expect(ExpectHook(c.expect.clone()))
+                    .on_connect_ext(move |io, addr| {
+                        hook.check(addr)?;
+                        select_without_alpn(io, select.as_ref().map(|f| &**f))?;
+                        Ok::<_, String>(PeerCertificate::from_openssl(io))
+                    })
+                    .finish(StickyFactory::new(
+                        c.sticky.clone(),
+                        map_config(factory(), move |_| cfg.clone()),
+                    ))
                     .openssl(acceptor.clone())
             },
         )?;
@@ -343,6 +1128,8 @@ where
             lst,
             move || {
                 let c = cfg.lock().unwrap();
+                c.workers.start();
+                let hook = ConnectHook(c.on_connect.clone());
                 let cfg = AppConfig::new(
                     true,
                     addr,
@@ -351,8 +1138,41 @@ where
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
+                    .read_buf_limit(c.read_buf_limit)
+                    .write_coalesce_limit(c.write_coalesce_limit)
+                    .date_header(c.date_header)
+                    .max_headers(c.max_headers)
+                    .max_header_size(c.max_header_size)
+                    .max_request_line(c.max_request_line)
+                    .strict_parsing(c.strict_parsing)
+                    .http10_keep_alive(c.http10_keep_alive)
+                    .http10_chunked(c.http10_chunked)
+                    .http10_empty_length(c.http10_empty_length)
+                    .max_requests_per_second(c.rate_limit)
+                    .close_on_rate_limit(c.rate_limit_close)
+                    .bandwidth_limit(c.bandwidth_limit)
+                    .buffer_pool(c.buffer_pool)
+                    .h2_max_streams(c.h2_streams)
+                    .h2_max_peer_streams(c.h2_peer_streams)
+                    .h2_max_concurrent_streams(c.h2_concurrent_streams)
+                    .h2_initial_window_size(c.h2_window)
+                    .h2_initial_connection_window_size(c.h2_conn_window)
+                    .h2_max_frame_size(c.h2_frame_size)
+                    .h2_keep_alive_interval(c.h2_ping_interval)
+                    .h2_keep_alive_timeout(c.h2_ping_timeout)
+                    .load_shed(c.load_shed.clone())
+                    .health_monitor(c.health_monitor)
+                    .proxy_protocol(c.proxy_protocol)
                     .client_disconnect(c.client_shutdown)
-                    .finish(map_config(factory(), move |_| cfg.clone()))
+                    .expect(ExpectHook(c.expect.clone()))
+                    .on_connect_ext(move |io, addr| {
+                        hook.check(addr)?;
+                        Ok::<_, String>(PeerCertificate::from_rustls(io))
+                    })
+                    .finish(StickyFactory::new(
+                        c.sticky.clone(),
+                        map_config(factory(), move |_| cfg.clone()),
+                    ))
                     .rustls(config.clone())
             },
         )?;
@@ -372,6 +1192,154 @@ where
         Ok(self)
     }
 
+    /// Bind all addresses and report addresses that could not be bound.
+    ///
+    /// Unlike [`bind`](#method.bind), failure to bind an address does not
+    /// abort binding of the rest of addresses. Server is returned if bound
+    /// addresses satisfy the `policy`, report of the failed binds is returned
+    /// otherwise.
+    ///
+    /// ```rust,no_run
+    /// use actix_web::dev::BindPolicy;
+    /// use actix_web::{web, App, HttpResponse, HttpServer};
+    ///
+    /// #[actix_rt::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     let (srv, report) = HttpServer::new(|| App::new())
+    ///         .try_bind_all(&["10.0.0.1:8080", "192.168.0.1:8080"], BindPolicy::Any)?;
+    ///
+    ///     for failure in report.failed() {
+    ///         eprintln!("{}", failure);
+    ///     }
+    ///     srv.run().await
+    /// }
+    /// ```
+    pub fn try_bind_all<T, A>(
+        self,
+        addrs: T,
+        policy: BindPolicy,
+    ) -> Result<(Self, BindReport), BindReport>
+    where
+        T: IntoIterator<Item = A>,
+        A: net::ToSocketAddrs + fmt::Display,
+    {
+        let targets = addrs
+            .into_iter()
+            .map(|target| {
+                let addrs = target.to_socket_addrs().map(|addrs| addrs.collect());
+                (target.to_string(), addrs)
+            })
+            .collect();
+
+        self.bind_targets(targets, policy, false)
+    }
+
+    /// Bind all addresses of the host for both ipv4 and ipv6 families.
+    ///
+    /// Host name is resolved and all of the returned addresses are bound,
+    /// `"*"` host binds all interfaces, i.e. `0.0.0.0` and `[::]`. IPv6
+    /// sockets only accept ipv6 connections, so both families can listen on
+    /// the same port. Server is returned if at least one address is bound,
+    /// failed binds are listed in the report.
+    ///
+    /// ```rust,no_run
+    /// use actix_web::{web, App, HttpResponse, HttpServer};
+    ///
+    /// #[actix_rt::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     let (srv, report) = HttpServer::new(|| App::new())
+    ///         .bind_dual_stack("localhost", 8080)?;
+    ///
+    ///     if !report.has_ipv6() {
+    ///         eprintln!("IPv6 is not available: {}", report);
+    ///     }
+    ///     srv.run().await
+    /// }
+    /// ```
+    pub fn bind_dual_stack(
+        self,
+        host: &str,
+        port: u16,
+    ) -> Result<(Self, BindReport), BindReport> {
+        let target = if host.contains(':') {
+            format!("[{}]:{}", host, port)
+        } else {
+            format!("{}:{}", host, port)
+        };
+        let addrs = if host == "*" {
+            Ok(vec![
+                (net::Ipv4Addr::UNSPECIFIED, port).into(),
+                (net::Ipv6Addr::UNSPECIFIED, port).into(),
+            ])
+        } else {
+            net::ToSocketAddrs::to_socket_addrs(&(host, port)).map(|addrs| {
+                let mut unique = Vec::new();
+                for addr in addrs {
+                    if !unique.contains(&addr) {
+                        unique.push(addr);
+                    }
+                }
+                unique
+            })
+        };
+
+        self.bind_targets(vec![(target, addrs)], BindPolicy::Any, true)
+    }
+
+    fn bind_targets(
+        mut self,
+        targets: Vec<(String, io::Result<Vec<net::SocketAddr>>)>,
+        policy: BindPolicy,
+        only_v6: bool,
+    ) -> Result<(Self, BindReport), BindReport> {
+        let mut report = BindReport::default();
+
+        for (target, addrs) in targets {
+            let addrs = match addrs {
+                Ok(addrs) => addrs,
+                Err(e) => {
+                    report.fail(&target, None, e);
+                    continue;
+                }
+            };
+            if addrs.is_empty() {
+                report.fail(
+                    &target,
+                    None,
+                    io::Error::new(io::ErrorKind::Other, "Address is not resolved"),
+                );
+            }
+
+            for addr in addrs {
+                let lst = match create_listener(addr, self.backlog, only_v6) {
+                    Ok(lst) => lst,
+                    Err(e) => {
+                        report.fail(&target, Some(addr), e);
+                        continue;
+                    }
+                };
+                // report actual address, i.e. port assigned for port 0
+                let local_addr = lst.local_addr().unwrap_or(addr);
+                match self.listen(lst) {
+                    Ok(srv) => {
+                        self = srv;
+                        report.bound.push(local_addr);
+                    }
+                    Err(e) => {
+                        report.fail(&target, Some(addr), e);
+                        return Err(report);
+                    }
+                }
+            }
+        }
+
+        if report.satisfies(policy) {
+            Ok((self, report))
+        } else {
+            Err(report)
+        }
+    }
+
     fn bind2<A: net::ToSocketAddrs>(
         &self,
         addr: A,
@@ -407,6 +1375,10 @@ where
     /// Start listening for incoming tls connections.
     ///
     /// This method sets alpn protocols to "h2" and "http/1.1"
+    ///
+    /// Certificates of the acceptor can not be replaced while server is
+    /// running, use [`RustlsReload`](struct.RustlsReload.html) with rustls
+    /// listeners for reloading certificates without restart.
     pub fn bind_openssl<A>(
         mut self,
         addr: A,
@@ -416,7 +1388,7 @@ where
         A: net::ToSocketAddrs,
     {
         let sockets = self.bind2(addr)?;
-        let acceptor = openssl_acceptor(builder)?;
+        let acceptor = openssl_acceptor(builder, self.alpn_selector())?;
 
         for lst in sockets {
             self = self.listen_ssl_inner(lst, acceptor.clone())?;
@@ -464,16 +1436,50 @@ where
 
         self.builder = self.builder.listen_uds(addr, lst, move || {
             let c = cfg.lock().unwrap();
+            c.workers.start();
+            let hook = ConnectHook(c.on_connect.clone());
             let config = AppConfig::new(
                 false,
                 socket_addr,
-                c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
+                c.host.clone().unwrap_or_else(|| "localhost".to_owned()),
             );
             pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None))).and_then(
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
-                    .finish(map_config(factory(), move |_| config.clone())),
+                    .read_buf_limit(c.read_buf_limit)
+                    .write_coalesce_limit(c.write_coalesce_limit)
+                    .date_header(c.date_header)
+                    .max_headers(c.max_headers)
+                    .max_header_size(c.max_header_size)
+                    .max_request_line(c.max_request_line)
+                    .strict_parsing(c.strict_parsing)
+                    .http10_keep_alive(c.http10_keep_alive)
+                    .http10_chunked(c.http10_chunked)
+                    .http10_empty_length(c.http10_empty_length)
+                    .max_requests_per_second(c.rate_limit)
+                    .close_on_rate_limit(c.rate_limit_close)
+                    .bandwidth_limit(c.bandwidth_limit)
+                    .buffer_pool(c.buffer_pool)
+                    .h2_max_streams(c.h2_streams)
+                    .h2_max_peer_streams(c.h2_peer_streams)
+                    .h2_max_concurrent_streams(c.h2_concurrent_streams)
+                    .h2_initial_window_size(c.h2_window)
+                    .h2_initial_connection_window_size(c.h2_conn_window)
+                    .h2_max_frame_size(c.h2_frame_size)
+                    .h2_keep_alive_interval(c.h2_ping_interval)
+                    .h2_keep_alive_timeout(c.h2_ping_timeout)
+                    .load_shed(c.load_shed.clone())
+                    .health_monitor(c.health_monitor)
+                    .expect(ExpectHook(c.expect.clone()))
+                    .on_connect_ext(move |io, addr| {
+                        hook.check(addr)?;
+                        Ok::<_, String>(PeerCredentials::from_unix(io))
+                    })
+                    .finish(StickyFactory::new(
+                        c.sticky.clone(),
+                        map_config(factory(), move |_| config.clone()),
+                    )),
             )
         })?;
         Ok(self)
@@ -503,17 +1509,51 @@ where
             addr,
             move || {
                 let c = cfg.lock().unwrap();
+                c.workers.start();
+                let hook = ConnectHook(c.on_connect.clone());
                 let config = AppConfig::new(
                     false,
                     socket_addr,
-                    c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
+                    c.host.clone().unwrap_or_else(|| "localhost".to_owned()),
                 );
                 pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None)))
                     .and_then(
                         HttpService::build()
                             .keep_alive(c.keep_alive)
                             .client_timeout(c.client_timeout)
-                            .finish(map_config(factory(), move |_| config.clone())),
+                            .read_buf_limit(c.read_buf_limit)
+                            .write_coalesce_limit(c.write_coalesce_limit)
+                            .date_header(c.date_header)
+                            .max_headers(c.max_headers)
+                            .max_header_size(c.max_header_size)
+                            .max_request_line(c.max_request_line)
+                            .strict_parsing(c.strict_parsing)
+                            .http10_keep_alive(c.http10_keep_alive)
+                            .http10_chunked(c.http10_chunked)
+                            .http10_empty_length(c.http10_empty_length)
+                            .max_requests_per_second(c.rate_limit)
+                            .close_on_rate_limit(c.rate_limit_close)
+                            .bandwidth_limit(c.bandwidth_limit)
+                            .buffer_pool(c.buffer_pool)
+                            .h2_max_streams(c.h2_streams)
+                            .h2_max_peer_streams(c.h2_peer_streams)
+                            .h2_max_concurrent_streams(c.h2_concurrent_streams)
+                            .h2_initial_window_size(c.h2_window)
+                            .h2_initial_connection_window_size(c.h2_conn_window)
+                            .h2_max_frame_size(c.h2_frame_size)
+                            .h2_keep_alive_interval(c.h2_ping_interval)
+                            .h2_keep_alive_timeout(c.h2_ping_timeout)
+                            .load_shed(c.load_shed.clone())
+                            .health_monitor(c.health_monitor)
+                            .expect(ExpectHook(c.expect.clone()))
+                            .on_connect_ext(move |io, addr| {
+                                hook.check(addr)?;
+                                Ok::<_, String>(PeerCredentials::from_unix(io))
+                            })
+                            .finish(StickyFactory::new(
+                                c.sticky.clone(),
+                                map_config(factory(), move |_| config.clone()),
+                            )),
                     )
             },
         )?;
@@ -561,10 +1601,24 @@ where
 fn create_tcp_listener(
     addr: net::SocketAddr,
     backlog: i32,
+) -> io::Result<net::TcpListener> {
+    create_listener(addr, backlog, false)
+}
+
+fn create_listener(
+    addr: net::SocketAddr,
+    backlog: i32,
+    only_v6: bool,
 ) -> io::Result<net::TcpListener> {
     let builder = match addr {
         net::SocketAddr::V4(_) => TcpBuilder::new_v4()?,
-        net::SocketAddr::V6(_) => TcpBuilder::new_v6()?,
+        net::SocketAddr::V6(_) => {
+            let builder = TcpBuilder::new_v6()?;
+            if only_v6 {
+                builder.only_v6(true)?;
+            }
+            builder
+        }
     };
     builder.reuse_address(true)?;
     builder.bind(addr)?;
@@ -573,14 +1627,29 @@ fn create_tcp_listener(
 
 #[cfg(feature = "openssl")]
 /// Configure `SslAcceptorBuilder` with custom server flags.
-fn openssl_acceptor(mut builder: SslAcceptorBuilder) -> io::Result<SslAcceptor> {
-    builder.set_alpn_select_callback(|_, protos| {
-        const H2: &[u8] = b"\x02h2";
-        const H11: &[u8] = b"\x08http/1.1";
-        if protos.windows(3).any(|window| window == H2) {
+fn openssl_acceptor(
+    mut builder: SslAcceptorBuilder,
+    select: Option<Arc<AlpnFn>>,
+) -> io::Result<SslAcceptor> {
+    use open_ssl::ssl::NameType;
+
+    builder.set_alpn_select_callback(move |ssl, protos| {
+        let hello = ClientHello {
+            server_name: ssl.servername(NameType::HOST_NAME),
+            protocols: protos,
+        };
+        let proto = match select {
+            Some(ref select) => (*select)(&hello).ok_or(AlpnError::ALERT_FATAL)?,
+            None if hello.offers(Protocol::Http2) => Protocol::Http2,
+            None => Protocol::Http1,
+        };
+        if hello.offers(proto) && proto == Protocol::Http2 {
             Ok(b"h2")
-        } else if protos.windows(9).any(|window| window == H11) {
+        } else if hello.offers(Protocol::Http1) {
             Ok(b"http/1.1")
+        } else if select.is_some() {
+            // connection without alpn would be checked by callback again
+            Err(AlpnError::ALERT_FATAL)
         } else {
             Err(AlpnError::NOACK)
         }
@@ -589,3 +1658,205 @@ fn openssl_acceptor(mut builder: SslAcceptorBuilder) -> io::Result<SslAcceptor>
 
     Ok(builder.build())
 }
+
+#[cfg(feature = "openssl")]
+type AlpnFn = dyn Fn(&ClientHello<'_>) -> Option<Protocol> + Send + Sync;
+
+#[cfg(feature = "openssl")]
+/// Call protocol selection callback for connections of clients without alpn.
+fn select_without_alpn(
+    io: &actix_tls::openssl::SslStream<TcpStream>,
+    select: Option<&AlpnFn>,
+) -> Result<(), &'static str> {
+    use open_ssl::ssl::NameType;
+
+    let ssl = io.ssl();
+    match select {
+        Some(select) if ssl.selected_alpn_protocol().is_none() => {
+            let hello = ClientHello {
+                server_name: ssl.servername(NameType::HOST_NAME),
+                protocols: b"",
+            };
+            match select(&hello) {
+                Some(_) => Ok(()),
+                None => Err("Tls connection without alpn is rejected"),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "openssl")]
+/// Tls client hello of a connection, see `HttpServer::alpn_select()`.
+pub struct ClientHello<'a> {
+    server_name: Option<&'a str>,
+    protocols: &'a [u8],
+}
+
+#[cfg(feature = "openssl")]
+impl<'a> ClientHello<'a> {
+    /// Server name requested by client.
+    pub fn server_name(&self) -> Option<&'a str> {
+        self.server_name
+    }
+
+    /// Client offers the protocol with alpn.
+    pub fn offers(&self, protocol: Protocol) -> bool {
+        let name: &[u8] = match protocol {
+            Protocol::Http1 => b"http/1.1",
+            Protocol::Http2 => b"h2",
+        };
+
+        // length prefixed protocol names
+        let mut protos = self.protocols;
+        while let Some((&len, rest)) = protos.split_first() {
+            let len = len as usize;
+            if rest.len() < len {
+                break;
+            }
+            if &rest[..len] == name {
+                return true;
+            }
+            protos = &rest[len..];
+        }
+        false
+    }
+}
+
+#[cfg(feature = "rustls")]
+/// Handle for replacing certificates of running rustls listeners.
+///
+/// Listeners bound with [`config()`](#method.config) resolve certificates
+/// of new connections with the current configuration of the handle, so
+/// renewed certificates are used without restart. Established connections
+/// keep their certificates.
+///
+/// Only rustls listeners are reloaded. Listeners bound with
+/// [`bind_openssl()`](struct.HttpServer.html#method.bind_openssl) or
+/// [`listen_openssl()`](struct.HttpServer.html#method.listen_openssl) keep
+/// certificates of their acceptor until the server is restarted.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use actix_web::{web, App, HttpResponse, HttpServer, RustlsReload};
+/// # fn load_config() -> rust_tls::ServerConfig { unimplemented!() }
+///
+/// #[actix_rt::main]
+/// async fn main() -> std::io::Result<()> {
+///     let tls = RustlsReload::new(load_config());
+///
+///     let reload = tls.clone();
+///     actix_rt::spawn(async move {
+///         loop {
+///             actix_rt::time::delay_for(Duration::from_secs(3600)).await;
+///             reload.reload(&load_config());
+///         }
+///     });
+///
+///     HttpServer::new(|| App::new().route("/", web::get().to(|| HttpResponse::Ok())))
+///         .bind_rustls("127.0.0.1:8443", tls.config())?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct RustlsReload {
+    config: RustlsServerConfig,
+    current: Arc<RwLock<Arc<dyn ResolvesServerCert>>>,
+}
+
+#[cfg(feature = "rustls")]
+impl RustlsReload {
+    /// Create handle with initial configuration.
+    pub fn new(config: RustlsServerConfig) -> Self {
+        RustlsReload {
+            current: Arc::new(RwLock::new(config.cert_resolver.clone())),
+            config,
+        }
+    }
+
+    /// Configuration for listeners, certificates are resolved by the handle.
+    ///
+    /// Other settings, like protocol versions and client authentication, are
+    /// settings of the initial configuration.
+    pub fn config(&self) -> RustlsServerConfig {
+        let mut config = self.config.clone();
+        config.cert_resolver = Arc::new(ReloadResolver(self.current.clone()));
+        config
+    }
+
+    /// Use certificates of the configuration for new connections.
+    pub fn reload(&self, config: &RustlsServerConfig) {
+        *self.current.write().unwrap() = config.cert_resolver.clone();
+    }
+}
+
+#[cfg(feature = "rustls")]
+impl fmt::Debug for RustlsReload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RustlsReload")
+    }
+}
+
+#[cfg(feature = "rustls")]
+struct ReloadResolver(Arc<RwLock<Arc<dyn ResolvesServerCert>>>);
+
+#[cfg(feature = "rustls")]
+impl ResolvesServerCert for ReloadResolver {
+    fn resolve(&self, hello: rust_tls::ClientHello<'_>) -> Option<CertifiedKey> {
+        let current = self.0.read().unwrap().clone();
+        current.resolve(hello)
+    }
+}
+
+type ConnectFn = dyn Fn(Option<net::SocketAddr>) -> Result<(), String> + Send + Sync;
+
+/// On-connect handler that runs configured callback.
+struct ConnectHook(Option<Arc<ConnectFn>>);
+
+impl ConnectHook {
+    fn check(&self, addr: Option<net::SocketAddr>) -> Result<(), String> {
+        match self.0 {
+            Some(ref hook) => hook(addr),
+            None => Ok(()),
+        }
+    }
+}
+
+type ExpectFn = dyn Fn(&RequestHead) -> Result<(), Error> + Send + Sync;
+
+/// `Expect: 100-continue` handler that runs configured hook.
+#[derive(Clone)]
+struct ExpectHook(Option<Arc<ExpectFn>>);
+
+impl ServiceFactory for ExpectHook {
+    type Config = ();
+    type Request = Request;
+    type Response = Request;
+    type Error = Error;
+    type Service = ExpectHook;
+    type InitError = ();
+    type Future = Ready<Result<ExpectHook, ()>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        ready(Ok(self.clone()))
+    }
+}
+
+impl Service for ExpectHook {
+    type Request = Request;
+    type Response = Request;
+    type Error = Error;
+    type Future = Ready<Result<Request, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        match self.0 {
+            Some(ref hook) => ready(hook(req.head()).map(|_| req)),
+            None => ready(Ok(req)),
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::config::ServiceConfig;
+use crate::types::{FormConfig, JsonConfig, PayloadConfig};
+
+/// Http server configuration file.
+///
+/// Configuration can be loaded from `toml` or `yaml` files, loaders are
+/// enabled by the `toml` and `yaml` features. All settings are optional,
+/// server defaults are used for missing ones. Server settings are applied
+/// with [`HttpServer::from_config`](struct.HttpServer.html#method.from_config),
+/// limits and compression settings are applied per application.
+///
+/// ```toml
+/// bind = ["127.0.0.1:8080"]
+/// workers = 4
+/// keep_alive = 75
+/// client_timeout = 5000
+///
+/// [tls]
+/// bind = ["127.0.0.1:8443"]
+/// cert = "cert.pem"
+/// key = "key.pem"
+///
+/// [limits]
+/// payload = 262144
+/// json = 32768
+///
+/// [compression]
+/// enabled = true
+/// encoding = "gzip"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfigFile {
+    /// Addresses to listen for plain http connections.
+    pub bind: Vec<String>,
+    /// Tls listeners.
+    pub tls: Option<TlsSection>,
+    /// Number of workers.
+    pub workers: Option<usize>,
+    /// Maximum number of pending connections.
+    pub backlog: Option<i32>,
+    /// Maximum number of concurrent connections per worker.
+    pub max_connections: Option<usize>,
+    /// Maximum number of concurrent tls handshakes per worker.
+    pub max_connection_rate: Option<usize>,
+    /// Keep-alive timeout in seconds, `0` disables keep-alive.
+    pub keep_alive: Option<usize>,
+    /// Client request timeout in milliseconds.
+    pub client_timeout: Option<u64>,
+    /// Client connection shutdown timeout in milliseconds.
+    pub client_shutdown: Option<u64>,
+    /// Graceful workers shutdown timeout in seconds.
+    pub shutdown_timeout: Option<u64>,
+    /// Maximum read buffer size of http/1 connections.
+    pub read_buf_limit: Option<usize>,
+    /// Server host name.
+    pub hostname: Option<String>,
+    /// Request payload limits.
+    pub limits: LimitsSection,
+    /// Response compression settings.
+    pub compression: CompressionSection,
+}
+
+/// Tls section of the server configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsSection {
+    /// Addresses to listen for tls connections.
+    pub bind: Vec<String>,
+    /// Path to pem encoded certificate chain.
+    pub cert: PathBuf,
+    /// Path to pem encoded private key.
+    pub key: PathBuf,
+}
+
+/// Limits section of the server configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsSection {
+    /// `Bytes` and `String` payload limit.
+    pub payload: Option<usize>,
+    /// `Json` payload limit.
+    pub json: Option<usize>,
+    /// `Form` payload limit.
+    pub form: Option<usize>,
+}
+
+/// Compression section of the server configuration file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompressionSection {
+    /// Compress responses, enabled by default.
+    pub enabled: bool,
+    /// Content encoding, `auto` selects encoding supported by the client.
+    pub encoding: String,
+}
+
+impl Default for CompressionSection {
+    fn default() -> Self {
+        CompressionSection {
+            enabled: true,
+            encoding: "auto".to_owned(),
+        }
+    }
+}
+
+impl ServerConfigFile {
+    /// Load configuration file, format is selected by the file extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => ServerConfigFile::from_toml(&std::fs::read_to_string(path)?),
+            #[cfg(feature = "yaml")]
+            Some("yaml") | Some("yml") => {
+                ServerConfigFile::from_yaml(&std::fs::read_to_string(path)?)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unsupported configuration file format: {:?}", path),
+            )),
+        }
+    }
+
+    /// Parse `toml` configuration.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(s: &str) -> io::Result<Self> {
+        toml::from_str(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Parse `yaml` configuration.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(s: &str) -> io::Result<Self> {
+        serde_yaml::from_str(s)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Application configuration function that applies payload limits.
+    ///
+    /// ```rust
+    /// use actix_web::{App, ServerConfigFile};
+    ///
+    /// let config = ServerConfigFile::default();
+    /// let app = App::new().configure(config.limits());
+    /// ```
+    pub fn limits(&self) -> impl Fn(&mut ServiceConfig) + Clone {
+        let limits = self.limits.clone();
+
+        move |cfg: &mut ServiceConfig| {
+            if let Some(limit) = limits.payload {
+                cfg.app_data(PayloadConfig::new(limit));
+            }
+            if let Some(limit) = limits.json {
+                cfg.app_data(JsonConfig::default().limit(limit));
+            }
+            if let Some(limit) = limits.form {
+                cfg.app_data(FormConfig::default().limit(limit));
+            }
+        }
+    }
+
+    /// Compression middleware configured by compression settings.
+    ///
+    /// Disabled compression uses `ContentEncoding::Identity` encoding. Returns
+    /// error if encoding is not one of `auto`, `br`, `gzip`, `deflate`, `zstd`
+    /// or `identity`.
+    ///
+    /// ```rust
+    /// use actix_web::{App, ServerConfigFile};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let config = ServerConfigFile::default();
+    /// let app = App::new().wrap(config.compress()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "compress")]
+    pub fn compress(&self) -> io::Result<crate::middleware::Compress> {
+        use crate::http::header::ContentEncoding;
+
+        let name = self.compression.encoding.trim().to_ascii_lowercase();
+        let encoding = match name.as_str() {
+            "auto" => ContentEncoding::Auto,
+            "br" | "gzip" | "deflate" | "zstd" | "identity" => {
+                ContentEncoding::from(name.as_str())
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Unknown compression encoding: {:?}",
+                        self.compression.encoding
+                    ),
+                ))
+            }
+        };
+        let encoding = if self.compression.enabled {
+            encoding
+        } else {
+            ContentEncoding::Identity
+        };
+        Ok(crate::middleware::Compress::new(encoding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml() {
+        let config = ServerConfigFile::from_toml(
+            r#"
+            bind = ["127.0.0.1:8080"]
+            workers = 2
+            keep_alive = 0
+
+            [tls]
+            bind = ["127.0.0.1:8443"]
+            cert = "cert.pem"
+            key = "key.pem"
+
+            [limits]
+            json = 1024
+
+            [compression]
+            enabled = false
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.bind, vec!["127.0.0.1:8080".to_owned()]);
+        assert_eq!(config.workers, Some(2));
+        assert_eq!(config.keep_alive, Some(0));
+        assert_eq!(config.tls.unwrap().key, PathBuf::from("key.pem"));
+        assert_eq!(config.limits.json, Some(1024));
+        assert_eq!(config.limits.payload, None);
+        assert!(!config.compression.enabled);
+        assert_eq!(config.compression.encoding, "auto");
+
+        assert!(ServerConfigFile::from_toml("unknown = 1").is_err());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_from_yaml() {
+        let config = ServerConfigFile::from_yaml(
+            "bind:\n  - 127.0.0.1:8080\nworkers: 2\nlimits:\n  payload: 512\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.bind, vec!["127.0.0.1:8080".to_owned()]);
+        assert_eq!(config.workers, Some(2));
+        assert_eq!(config.limits.payload, Some(512));
+        assert!(config.compression.enabled);
+    }
+
+    #[test]
+    fn test_load_unsupported() {
+        let path = std::env::temp_dir().join("actix-web-server-config.ini");
+        std::fs::write(&path, "bind = []").unwrap();
+        let err = ServerConfigFile::load(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[cfg(feature = "compress")]
+    #[test]
+    fn test_compress_encoding() {
+        let mut config = ServerConfigFile::default();
+        assert!(config.compress().is_ok());
+        config.compression.encoding = "Gzip".to_owned();
+        assert!(config.compress().is_ok());
+
+        config.compression.encoding = "gzipp".to_owned();
+        let err = config.compress().err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("gzipp"));
+    }
+}
@@ -108,6 +108,12 @@ impl ServiceRequest {
         ServiceResponse::new(self.0, res.into_body())
     }
 
+    /// Get reference to inner request
+    #[inline]
+    pub fn request(&self) -> &HttpRequest {
+        &self.0
+    }
+
     /// This method returns reference to the request head
     #[inline]
     pub fn head(&self) -> &RequestHead {
@@ -410,7 +416,11 @@ impl<B: MessageBody> fmt::Debug for ServiceResponse<B> {
             "\nServiceResponse {:?} {}{}",
             self.response.head().version,
             self.response.head().status,
-            self.response.head().reason.unwrap_or(""),
+            self.response
+                .head()
+                .reason
+                .as_ref()
+                .map_or("", |r| r.as_ref()),
         );
         let _ = writeln!(f, "  headers:");
         for (key, val) in self.response.head().headers.iter() {
@@ -0,0 +1,364 @@
+use std::fmt;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_http::Error;
+use actix_service::boxed::{self, BoxService, BoxServiceFactory};
+use actix_service::{IntoServiceFactory, Service, ServiceFactory};
+use futures::future::{FutureExt, LocalBoxFuture};
+
+use crate::http::header::HeaderName;
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::HttpMessage;
+
+type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
+type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>;
+
+/// Service that routes requests to one of the weighted variants.
+///
+/// Without a key requests are spread between variants proportionally to their
+/// weights. If a key is configured, i.e. a user id cookie, variant is selected
+/// by the hash of the key value, so requests with the same key always end up in
+/// the same variant. Key hash is stable across builds and toolchains, so
+/// assignments survive restarts and upgrades. Requests without the key fall
+/// back to weighted distribution.
+///
+/// Name of the selected variant is stored in request extensions as
+/// [`SplitVariant`](struct.SplitVariant.html).
+///
+/// ```rust
+/// use actix_web::{web, App, HttpResponse};
+///
+/// async fn stable() -> HttpResponse {
+///     HttpResponse::Ok().body("stable")
+/// }
+///
+/// async fn canary() -> HttpResponse {
+///     HttpResponse::Ok().body("canary")
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::service("/checkout").finish(
+///             web::split()
+///                 .variant("stable", 90, web::to(stable))
+///                 .variant("canary", 10, web::to(canary))
+///                 .cookie("user-id"),
+///         ),
+///     );
+/// }
+/// ```
+pub struct Split {
+    variants: Vec<(Rc<str>, u32, HttpNewService)>,
+    key: Option<SplitKey>,
+}
+
+#[derive(Clone)]
+enum SplitKey {
+    Cookie(String),
+    Header(HeaderName),
+}
+
+impl Split {
+    pub(crate) fn new() -> Self {
+        Split {
+            variants: Vec::new(),
+            key: None,
+        }
+    }
+
+    /// Register named variant service with the weight.
+    pub fn variant<F, U>(mut self, name: &str, weight: u32, service: F) -> Self
+    where
+        F: IntoServiceFactory<U>,
+        U: ServiceFactory<
+                Config = (),
+                Request = ServiceRequest,
+                Response = ServiceResponse,
+                Error = Error,
+            > + 'static,
+        U::InitError: fmt::Debug,
+    {
+        let factory = boxed::factory(service.into_factory().map_init_err(|e| {
+            log::error!("Can not construct split variant service: {:?}", e)
+        }));
+        self.variants.push((name.into(), weight, factory));
+        self
+    }
+
+    /// Select variant by the hash of the cookie value.
+    pub fn cookie(mut self, name: &str) -> Self {
+        self.key = Some(SplitKey::Cookie(name.to_owned()));
+        self
+    }
+
+    /// Select variant by the hash of the header value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    pub fn header(mut self, name: &str) -> Self {
+        let name = HeaderName::from_bytes(name.as_bytes()).expect("Invalid header name");
+        self.key = Some(SplitKey::Header(name));
+        self
+    }
+}
+
+/// Name of the variant selected by [`Split`](struct.Split.html) service.
+///
+/// ```rust
+/// use actix_web::{web, HttpMessage, HttpRequest};
+///
+/// async fn index(req: HttpRequest) -> String {
+///     match req.extensions().get::<web::SplitVariant>() {
+///         Some(variant) => format!("variant: {}", variant.name()),
+///         None => "no variant".to_owned(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitVariant(Rc<str>);
+
+impl SplitVariant {
+    /// Variant name
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for SplitVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl ServiceFactory for Split {
+    type Config = ();
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type InitError = ();
+    type Service = SplitService;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let futs: Vec<_> = self
+            .variants
+            .iter()
+            .map(|(name, weight, factory)| {
+                let name = name.clone();
+                let weight = *weight;
+                factory
+                    .new_service(())
+                    .map(move |res| res.map(|s| (name, weight, s)))
+            })
+            .collect();
+        let key = self.key.clone();
+
+        async move {
+            if futs.is_empty() {
+                log::error!("Split service requires at least one variant");
+                return Err(());
+            }
+
+            let mut variants = Vec::with_capacity(futs.len());
+            for fut in futs {
+                variants.push(fut.await?);
+            }
+            let total = variants.iter().map(|(_, w, _)| u64::from(*w)).sum();
+            let current = vec![0; variants.len()];
+
+            Ok(SplitService {
+                variants,
+                key,
+                total,
+                current,
+            })
+        }
+        .boxed_local()
+    }
+}
+
+pub struct SplitService {
+    variants: Vec<(Rc<str>, u32, HttpService)>,
+    key: Option<SplitKey>,
+    total: u64,
+    current: Vec<i64>,
+}
+
+impl SplitService {
+    /// Select variant from the hash of the key.
+    fn by_hash(&self, key: &[u8]) -> usize {
+        if self.total == 0 {
+            return 0;
+        }
+
+        let mut point = fnv1a(key) % self.total;
+
+        for (idx, (_, weight, _)) in self.variants.iter().enumerate() {
+            let weight = u64::from(*weight);
+            if point < weight {
+                return idx;
+            }
+            point -= weight;
+        }
+        0
+    }
+
+    /// Select variant with smooth weighted round-robin.
+    fn by_weight(&mut self) -> usize {
+        let mut selected = 0;
+        for (idx, (_, weight, _)) in self.variants.iter().enumerate() {
+            self.current[idx] += i64::from(*weight);
+            if self.current[idx] > self.current[selected] {
+                selected = idx;
+            }
+        }
+        self.current[selected] -= self.total as i64;
+        selected
+    }
+
+    fn select(&mut self, req: &ServiceRequest) -> usize {
+        let key = match self.key {
+            Some(SplitKey::Cookie(ref name)) => {
+                req.cookie(name).map(|c| c.value().as_bytes().to_vec())
+            }
+            Some(SplitKey::Header(ref name)) => {
+                req.headers().get(name).map(|v| v.as_bytes().to_vec())
+            }
+            None => None,
+        };
+
+        match key {
+            Some(key) => self.by_hash(&key),
+            None => self.by_weight(),
+        }
+    }
+}
+
+impl Service for SplitService {
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<ServiceResponse, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut ready = true;
+        for (_, _, srv) in self.variants.iter_mut() {
+            if srv.poll_ready(cx)?.is_pending() {
+                ready = false;
+            }
+        }
+        if ready {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let idx = self.select(&req);
+        let (name, _, srv) = &mut self.variants[idx];
+        req.extensions_mut().insert(SplitVariant(name.clone()));
+        srv.call(req)
+    }
+}
+
+/// 64-bit FNV-1a hash of the key.
+fn fnv1a(key: &[u8]) -> u64 {
+    key.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Cookie;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpRequest, HttpResponse};
+
+    async fn variant(req: HttpRequest) -> HttpResponse {
+        let name = req.extensions().get::<SplitVariant>().unwrap().to_string();
+        HttpResponse::Ok().body(name)
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[actix_rt::test]
+    async fn test_weighted() {
+        let mut srv = init_service(
+            App::new().service(
+                web::service("/").finish(
+                    web::split()
+                        .variant("a", 3, web::to(variant))
+                        .variant("b", 1, web::to(variant))
+                        .variant("c", 0, web::to(variant)),
+                ),
+            ),
+        )
+        .await;
+
+        let mut res = Vec::new();
+        for _ in 0..8 {
+            let req = TestRequest::default().to_request();
+            let resp = call_service(&mut srv, req).await;
+            res.push(read_body(resp).await);
+        }
+        assert_eq!(res.iter().filter(|b| *b == "a").count(), 6);
+        assert_eq!(res.iter().filter(|b| *b == "b").count(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_sticky_key() {
+        let mut srv = init_service(
+            App::new().service(
+                web::service("/").finish(
+                    web::split()
+                        .variant("a", 1, web::to(variant))
+                        .variant("b", 1, web::to(variant))
+                        .cookie("uid"),
+                ),
+            ),
+        )
+        .await;
+
+        for uid in &["1", "2", "3", "4"] {
+            let req = TestRequest::default()
+                .cookie(Cookie::new("uid", *uid))
+                .to_request();
+            let first = read_body(call_service(&mut srv, req).await).await;
+            for _ in 0..3 {
+                let req = TestRequest::default()
+                    .cookie(Cookie::new("uid", *uid))
+                    .to_request();
+                let resp = call_service(&mut srv, req).await;
+                assert_eq!(read_body(resp).await, first);
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_header_key() {
+        let mut srv = init_service(
+            App::new().service(
+                web::service("/").finish(
+                    web::split()
+                        .variant("a", 1, web::to(variant))
+                        .variant("b", 0, web::to(variant))
+                        .header("x-user"),
+                ),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::default().header("x-user", "42").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, "a");
+    }
+}
@@ -0,0 +1,664 @@
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::{fmt, io, net};
+
+use actix_http::body::{
+    Body, BodySize, BodyStream, MessageBody, ResponseBody, SizedStream,
+};
+use actix_http::error::{Error, ErrorServiceUnavailable, PayloadError};
+use actix_http::http::{HeaderMap, HeaderName, Method, StatusCode, Uri, Version};
+use actix_http::{
+    Extensions, HttpMessage, Payload, PayloadStream, Request, RequestHead, Response,
+};
+use actix_service::{Service, ServiceFactory};
+use bytes::Bytes;
+use futures::channel::{mpsc, oneshot};
+use futures::future::{poll_fn, FutureExt, LocalBoxFuture};
+use futures::{SinkExt, StreamExt};
+
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use crate::info::PeerCertificate;
+#[cfg(unix)]
+use crate::info::PeerCredentials;
+use crate::worker::WorkerIndex;
+
+/// Number of body chunks buffered between workers.
+const BODY_BUFFER: usize = 16;
+
+type KeyFn = dyn Fn(&RequestHead) -> Option<String> + Send + Sync;
+type SetExtension = Box<dyn FnOnce(&mut Extensions) + Send>;
+type ExtensionFn = dyn Fn(&Extensions) -> Option<SetExtension> + Send + Sync;
+
+/// Routing of requests with the same key to the same worker.
+///
+/// Requests are accepted by any worker, request with a key is forwarded to
+/// the worker selected by hash of the key, so per-worker in-memory caches
+/// and session registries see all requests of the key. Requests without a
+/// key are handled by the accepting worker.
+///
+/// Bodies of forwarded requests and responses are streamed between workers,
+/// so payload limits of the application apply and streaming responses are
+/// not buffered. Upgrade requests, like websocket handshakes, are never
+/// forwarded. Connection data of request extensions, like peer certificate
+/// or credentials, is copied to the forwarded request, other types have to
+/// be registered with [`extension()`](#method.extension).
+///
+/// ```rust,no_run
+/// use actix_web::{web, App, HttpResponse, HttpServer, StickyRouting};
+///
+/// #[actix_rt::main]
+/// async fn main() -> std::io::Result<()> {
+///     HttpServer::new(|| App::new().route("/", web::get().to(|| HttpResponse::Ok())))
+///         .workers(4)
+///         .sticky(StickyRouting::cookie("session"))
+///         .bind("127.0.0.1:59090")?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct StickyRouting {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    key: Box<KeyFn>,
+    extensions: Vec<Box<ExtensionFn>>,
+    workers: Mutex<BTreeMap<usize, mpsc::UnboundedSender<Forwarded>>>,
+}
+
+impl StickyRouting {
+    /// Route requests by key computed by the function.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&RequestHead) -> Option<String> + Send + Sync + 'static,
+    {
+        let mut extensions: Vec<Box<ExtensionFn>> = Vec::new();
+        #[cfg(any(feature = "openssl", feature = "rustls"))]
+        extensions.push(Box::new(|ext| {
+            // certificate is shared with `Rc`, chain is copied instead
+            let chain = ext
+                .get::<Option<PeerCertificate>>()?
+                .as_ref()
+                .map(|cert| cert.chain().to_vec());
+            Some(Box::new(move |ext| {
+                ext.insert(chain.and_then(PeerCertificate::new))
+            }))
+        }));
+        #[cfg(unix)]
+        extensions.push(copy_extension::<Option<PeerCredentials>>());
+
+        StickyRouting {
+            inner: Arc::new(Inner {
+                key: Box::new(f),
+                extensions,
+                workers: Mutex::new(BTreeMap::new()),
+            }),
+        }
+    }
+
+    /// Copy request extension of type `T` to forwarded requests, i.e. data
+    /// of the `on_connect` callback.
+    pub fn extension<T: Clone + Send + 'static>(mut self) -> Self {
+        Arc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .extensions
+            .push(copy_extension::<T>());
+        self
+    }
+
+    /// Route requests by ip address of the peer.
+    pub fn peer_ip() -> Self {
+        StickyRouting::new(|head| head.peer_addr.map(|addr| addr.ip().to_string()))
+    }
+
+    /// Route requests by value of the request header.
+    pub fn header(name: HeaderName) -> Self {
+        StickyRouting::new(move |head| {
+            head.headers
+                .get(&name)
+                .and_then(|val| val.to_str().ok())
+                .map(|val| val.to_owned())
+        })
+    }
+
+    /// Route requests by value of the cookie.
+    pub fn cookie<N: Into<String>>(name: N) -> Self {
+        let name = name.into();
+        StickyRouting::new(move |head| {
+            head.headers
+                .get_all(actix_http::http::header::COOKIE)
+                .filter_map(|val| val.to_str().ok())
+                .flat_map(|val| val.split(';'))
+                .filter_map(|pair| {
+                    let mut pair = pair.splitn(2, '=');
+                    match (pair.next(), pair.next()) {
+                        (Some(n), Some(v)) if n.trim() == name => {
+                            Some(v.trim().to_owned())
+                        }
+                        _ => None,
+                    }
+                })
+                .next()
+        })
+    }
+
+    /// Index of the worker request has to be forwarded to.
+    fn target(&self, head: &RequestHead) -> Option<mpsc::UnboundedSender<Forwarded>> {
+        let current = WorkerIndex::current()?;
+        if head.upgrade() {
+            return None;
+        }
+        let key = (self.inner.key)(head)?;
+
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+
+        let workers = self.inner.workers.lock().unwrap();
+        if workers.is_empty() {
+            return None;
+        }
+        let n = (hasher.finish() % workers.len() as u64) as usize;
+        let (idx, tx) = workers.iter().nth(n).unwrap();
+        if *idx == *current || tx.is_closed() {
+            None
+        } else {
+            Some(tx.clone())
+        }
+    }
+
+    /// Register current worker, forwarded requests are handled by `service`.
+    fn register<S, B>(&self, service: Rc<RefCell<S>>)
+    where
+        S: Service<Request = Request> + 'static,
+        S::Response: Into<Response<B>>,
+        S::Error: Into<Error>,
+        B: MessageBody + 'static,
+    {
+        let idx = match WorkerIndex::current() {
+            Some(idx) => idx.into_inner(),
+            None => return,
+        };
+
+        let mut workers = self.inner.workers.lock().unwrap();
+        // every listener creates service, first one handles forwarded requests
+        if workers.get(&idx).map_or(false, |tx| !tx.is_closed()) {
+            return;
+        }
+        let (tx, mut rx) = mpsc::unbounded::<Forwarded>();
+        workers.insert(idx, tx);
+
+        actix_rt::spawn(async move {
+            while let Some(fwd) = rx.next().await {
+                let Forwarded { head, tx } = fwd;
+                let ready = poll_fn(|cx| service.borrow_mut().poll_ready(cx)).await;
+                if let Err(e) = ready {
+                    let _ = tx.send(Parts::from_error(e.into()));
+                    continue;
+                }
+
+                let fut = service.borrow_mut().call(head.into_request());
+                actix_rt::spawn(async move {
+                    match fut.await {
+                        Ok(res) => Parts::send_response(res.into(), tx).await,
+                        Err(e) => {
+                            let _ = tx.send(Parts::from_error(e.into()));
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
+
+impl fmt::Debug for StickyRouting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StickyRouting")
+            .field("workers", &self.inner.workers.lock().unwrap().len())
+            .finish()
+    }
+}
+
+fn copy_extension<T: Clone + Send + 'static>() -> Box<ExtensionFn> {
+    Box::new(|ext| {
+        let val = ext.get::<T>()?.clone();
+        Some(Box::new(move |ext| ext.insert(val)))
+    })
+}
+
+/// Request forwarded to other worker.
+struct Forwarded {
+    head: Head,
+    tx: oneshot::Sender<Parts>,
+}
+
+/// Owned request head.
+struct Head {
+    method: Method,
+    uri: Uri,
+    version: Version,
+    headers: HeaderMap,
+    peer_addr: Option<net::SocketAddr>,
+    extensions: Vec<SetExtension>,
+    body: mpsc::Receiver<Result<Bytes, PayloadError>>,
+}
+
+impl Head {
+    /// Take head of the request, payload is streamed by a task of the
+    /// current worker.
+    fn new(req: &mut Request, routing: &StickyRouting) -> Self {
+        let extensions = {
+            let ext = req.extensions();
+            routing
+                .inner
+                .extensions
+                .iter()
+                .filter_map(|f| f(&ext))
+                .collect()
+        };
+
+        let (mut tx, body) = mpsc::channel(BODY_BUFFER);
+        match req.take_payload() {
+            Payload::None => (),
+            mut payload => actix_rt::spawn(async move {
+                while let Some(chunk) = payload.next().await {
+                    let is_err = chunk.is_err();
+                    // receiver is dropped if handler does not read the body
+                    if tx.send(chunk).await.is_err() || is_err {
+                        break;
+                    }
+                }
+            }),
+        }
+
+        let head = req.head();
+        Head {
+            method: head.method.clone(),
+            uri: head.uri.clone(),
+            version: head.version,
+            headers: head.headers.clone(),
+            peer_addr: head.peer_addr,
+            extensions,
+            body,
+        }
+    }
+
+    fn into_request(self) -> Request {
+        let body: PayloadStream = Box::pin(self.body);
+        let mut req = Request::with_payload(Payload::Stream(body));
+        for set in self.extensions {
+            set(&mut req.extensions_mut());
+        }
+        let head = req.head_mut();
+        head.method = self.method;
+        head.uri = self.uri;
+        head.version = self.version;
+        head.headers = self.headers;
+        head.peer_addr = self.peer_addr;
+        req
+    }
+}
+
+/// Response of the other worker, body is streamed over the channel.
+struct Parts {
+    status: StatusCode,
+    headers: HeaderMap,
+    size: BodySize,
+    body: Option<mpsc::Receiver<Result<Bytes, io::Error>>>,
+}
+
+impl Parts {
+    /// Send head of the response and stream its body.
+    async fn send_response<B: MessageBody>(
+        mut res: Response<B>,
+        tx: oneshot::Sender<Parts>,
+    ) {
+        let mut body = Box::pin(res.take_body());
+        let size = body.size();
+        let (mut body_tx, body_rx) = mpsc::channel(BODY_BUFFER);
+        let parts = Parts {
+            status: res.status(),
+            headers: res.headers().clone(),
+            size,
+            body: match size {
+                BodySize::None | BodySize::Empty => None,
+                _ => Some(body_rx),
+            },
+        };
+        if tx.send(parts).is_err() || body_tx.is_closed() {
+            return;
+        }
+
+        while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+            let chunk =
+                chunk.map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()));
+            let is_err = chunk.is_err();
+            // receiver is dropped once client is gone
+            if body_tx.send(chunk).await.is_err() || is_err {
+                break;
+            }
+        }
+    }
+
+    fn from_error(err: Error) -> Self {
+        let res = Response::from(err);
+        let body = match res.body() {
+            ResponseBody::Body(Body::Bytes(ref b)) => b.clone(),
+            _ => Bytes::new(),
+        };
+        let (mut tx, rx) = mpsc::channel(1);
+        let _ = tx.try_send(Ok(body.clone()));
+
+        Parts {
+            status: res.status(),
+            headers: res.headers().clone(),
+            size: BodySize::Sized(body.len()),
+            body: Some(rx),
+        }
+    }
+
+    fn into_response<B>(self) -> Response<ResponseBody<B>> {
+        let size = self.size;
+        let body = match self.body {
+            None => match size {
+                BodySize::Empty => Body::Empty,
+                _ => Body::None,
+            },
+            Some(rx) => {
+                let rx = rx.map(|chunk| chunk.map_err(Error::from));
+                match size {
+                    BodySize::Sized(n) => {
+                        Body::from_message(SizedStream::new(n as u64, rx))
+                    }
+                    BodySize::Sized64(n) => Body::from_message(SizedStream::new(n, rx)),
+                    _ => Body::from_message(BodyStream::new(rx)),
+                }
+            }
+        };
+        let mut res =
+            Response::new(self.status).map_body(move |_, _| ResponseBody::Other(body));
+        *res.headers_mut() = self.headers;
+        res
+    }
+}
+
+/// Service factory that forwards requests according to sticky routing.
+pub(crate) struct StickyFactory<T, B> {
+    factory: T,
+    routing: Option<StickyRouting>,
+    _t: PhantomData<B>,
+}
+
+impl<T, B> StickyFactory<T, B> {
+    pub(crate) fn new(routing: Option<StickyRouting>, factory: T) -> Self {
+        StickyFactory {
+            factory,
+            routing,
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T, B> ServiceFactory for StickyFactory<T, B>
+where
+    T: ServiceFactory<Config = (), Request = Request>,
+    T::Future: 'static,
+    T::Service: 'static,
+    T::Response: Into<Response<B>>,
+    T::Error: Into<Error>,
+    <T::Service as Service>::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Config = ();
+    type Request = Request;
+    type Response = Response<ResponseBody<B>>;
+    type Error = Error;
+    type InitError = T::InitError;
+    type Service = StickyService<T::Service, B>;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let fut = self.factory.new_service(());
+        let routing = self.routing.clone();
+
+        async move {
+            let service = Rc::new(RefCell::new(fut.await?));
+            if let Some(ref routing) = routing {
+                routing.register(service.clone());
+            }
+            Ok(StickyService {
+                service,
+                routing,
+                _t: PhantomData,
+            })
+        }
+        .boxed_local()
+    }
+}
+
+#[doc(hidden)]
+pub struct StickyService<S, B> {
+    service: Rc<RefCell<S>>,
+    routing: Option<StickyRouting>,
+    _t: PhantomData<B>,
+}
+
+impl<S, B> Service for StickyService<S, B>
+where
+    S: Service<Request = Request> + 'static,
+    S::Response: Into<Response<B>>,
+    S::Error: Into<Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = Request;
+    type Response = Response<ResponseBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.service
+            .borrow_mut()
+            .poll_ready(cx)
+            .map_err(|e| e.into())
+    }
+
+    fn call(&mut self, mut req: Request) -> Self::Future {
+        let (routing, target) = match self.routing {
+            Some(ref routing) => match routing.target(req.head()) {
+                Some(target) => (routing, target),
+                None => return local(self.service.borrow_mut().call(req)),
+            },
+            None => return local(self.service.borrow_mut().call(req)),
+        };
+
+        let head = Head::new(&mut req, routing);
+        let service = self.service.clone();
+        async move {
+            let (tx, rx) = oneshot::channel();
+            let fwd = Forwarded { head, tx };
+            if let Err(e) = target.unbounded_send(fwd) {
+                // target worker is stopped, handle request locally
+                let Forwarded { head, .. } = e.into_inner();
+                return local(service.borrow_mut().call(head.into_request())).await;
+            }
+
+            match rx.await {
+                Ok(parts) => Ok(parts.into_response()),
+                Err(_) => Ok(Parts::from_error(ErrorServiceUnavailable(
+                    "Request is not handled by the worker",
+                ))
+                .into_response()),
+            }
+        }
+        .boxed_local()
+    }
+}
+
+fn local<F, R, E, B>(
+    fut: F,
+) -> LocalBoxFuture<'static, Result<Response<ResponseBody<B>>, Error>>
+where
+    F: Future<Output = Result<R, E>> + 'static,
+    R: Into<Response<B>>,
+    E: Into<Error>,
+    B: MessageBody + 'static,
+{
+    fut.map(|res| match res {
+        Ok(res) => Ok(res.into().map_body(|_, body| ResponseBody::Body(body))),
+        Err(e) => Err(e.into()),
+    })
+    .boxed_local()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc as std_mpsc;
+    use std::thread;
+
+    use actix_service::fn_service;
+
+    use super::*;
+    use crate::test::TestRequest;
+    use crate::worker::Workers;
+
+    fn key(routing: &StickyRouting, req: TestRequest) -> Option<String> {
+        (routing.inner.key)(req.to_request().head())
+    }
+
+    fn worker(key: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % 2) as usize
+    }
+
+    fn app() -> impl ServiceFactory<
+        Config = (),
+        Request = Request,
+        Response = Response,
+        Error = Error,
+        InitError = (),
+    > {
+        fn_service(|mut req: Request| {
+            let payload = req.take_payload();
+            async move {
+                let size = payload
+                    .fold(0, |size, chunk| async move { size + chunk.unwrap().len() })
+                    .await;
+                let worker = WorkerIndex::current().unwrap();
+                let ext = req.extensions().get::<usize>().cloned();
+                let body = format!("{}:{}:{:?}", worker, size, ext);
+                if req.path() == "/stream" {
+                    let chunks = body
+                        .into_bytes()
+                        .into_iter()
+                        .map(|b| Ok::<_, Error>(Bytes::from(vec![b])));
+                    Ok(Response::Ok().streaming(futures::stream::iter(chunks)))
+                } else {
+                    Ok(Response::Ok().body(body))
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_keys() {
+        let routing = StickyRouting::cookie("session");
+        let req = TestRequest::default().header("cookie", "a=1; session=abc");
+        assert_eq!(key(&routing, req), Some("abc".to_owned()));
+        assert_eq!(key(&routing, TestRequest::default()), None);
+
+        let routing = StickyRouting::header(HeaderName::from_static("x-user"));
+        let req = TestRequest::default().header("x-user", "alice");
+        assert_eq!(key(&routing, req), Some("alice".to_owned()));
+
+        let routing = StickyRouting::peer_ip();
+        let req = TestRequest::default().peer_addr("127.0.0.1:8080".parse().unwrap());
+        assert_eq!(key(&routing, req), Some("127.0.0.1".to_owned()));
+    }
+
+    #[test]
+    fn test_forward() {
+        let routing = StickyRouting::header(HeaderName::from_static("x-user"))
+            .extension::<usize>();
+        let workers = Arc::new(Workers::default());
+        let (started_tx, started_rx) = std_mpsc::channel();
+        let (done_tx, done_rx) = oneshot::channel::<()>();
+
+        // worker #0 only handles forwarded requests
+        let remote = {
+            let routing = routing.clone();
+            let workers = workers.clone();
+            thread::spawn(move || {
+                actix_rt::System::new("worker").block_on(async move {
+                    workers.start();
+                    let _srv = StickyFactory::new(Some(routing), app())
+                        .new_service(())
+                        .await
+                        .unwrap();
+                    started_tx.send(()).unwrap();
+                    let _ = done_rx.await;
+                })
+            })
+        };
+        started_rx.recv().unwrap();
+
+        thread::spawn(move || {
+            actix_rt::System::new("worker").block_on(async move {
+                workers.start();
+                let mut srv = StickyFactory::new(Some(routing), app())
+                    .new_service(())
+                    .await
+                    .unwrap();
+
+                let keys = ["a", "b", "c", "d", "e", "f", "g", "h"];
+                assert!(keys.iter().any(|key| worker(key) == 0));
+                for path in &["/", "/stream"] {
+                    for key in keys.iter() {
+                        let req = TestRequest::with_uri(path)
+                            .header("x-user", *key)
+                            .set_payload("abc")
+                            .to_request();
+                        req.extensions_mut().insert(7usize);
+                        let mut res = srv.call(req).await.unwrap();
+                        assert_eq!(res.status(), StatusCode::OK);
+
+                        let mut body = Box::pin(res.take_body());
+                        let expected = format!("{}:3:Some(7)", worker(key));
+                        if *path == "/" {
+                            let len = expected.len();
+                            match body.size() {
+                                BodySize::Sized(n) => assert_eq!(n, len),
+                                BodySize::Sized64(n) => assert_eq!(n, len as u64),
+                                size => panic!("{:?}", size),
+                            }
+                        }
+                        let mut buf = Vec::new();
+                        while let Some(chunk) =
+                            poll_fn(|cx| body.as_mut().poll_next(cx)).await
+                        {
+                            buf.extend_from_slice(&chunk.unwrap());
+                        }
+                        assert_eq!(buf, expected.as_bytes());
+                    }
+                }
+
+                // requests without key are handled locally
+                let req = TestRequest::default().to_request();
+                let res = srv.call(req).await.unwrap();
+                assert_eq!(res.status(), StatusCode::OK);
+                done_tx.send(()).unwrap();
+            })
+        })
+        .join()
+        .unwrap();
+        remote.join().unwrap();
+    }
+}
@@ -0,0 +1,401 @@
+//! Server side template rendering.
+//!
+//! [`Template`](trait.Template.html) is implemented by renderable templates,
+//! [`Render`](struct.Render.html) and [`RenderStream`](struct.RenderStream.html)
+//! responders turn them into http responses with matching content type.
+//!
+//! Adapters for template engines are available behind features:
+//!
+//! * `askama` - every `askama::Template` implements `Template`
+//! * `tera` - [`TeraTemplate`](struct.TeraTemplate.html)
+//! * `minijinja` - [`MiniJinjaTemplate`](struct.MiniJinjaTemplate.html)
+//!
+//! Rendering errors are turned into responses by the error handler registered
+//! with [`TemplateConfig`](struct.TemplateConfig.html), which defaults to an
+//! empty `500 Internal Server Error` response.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use std::io;
+//! use actix_web::template::{Render, Template, TemplateError};
+//! use actix_web::{web, App, Responder};
+//!
+//! struct Hello {
+//!     name: String,
+//! }
+//!
+//! impl Template for Hello {
+//!     fn render_to(&self, out: &mut dyn io::Write) -> Result<(), TemplateError> {
+//!         write!(out, "<h1>Hello {}!</h1>", self.name)?;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! async fn index(name: web::Path<String>) -> impl Responder {
+//!     Render(Hello { name: name.into_inner() })
+//! }
+//!
+//! fn main() {
+//!     let app = App::new().route("/{name}", web::get().to(index));
+//! }
+//! ```
+use std::io;
+use std::sync::Arc;
+
+use actix_http::error::BlockingError;
+use actix_http::{Error, Response};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+pub use crate::error::TemplateError;
+use crate::http::StatusCode;
+use crate::request::HttpRequest;
+use crate::responder::Responder;
+
+/// Initial size of the buffer `RenderStream` renders to.
+const BUFFER_SIZE: usize = 8192;
+
+/// Trait implemented by types that can be rendered to a http response body.
+pub trait Template {
+    /// Render template to the writer.
+    fn render_to(&self, out: &mut dyn io::Write) -> Result<(), TemplateError>;
+
+    /// Render template to a string.
+    fn render(&self) -> Result<String, TemplateError> {
+        let mut buf = Vec::new();
+        self.render_to(&mut buf)?;
+        String::from_utf8(buf).map_err(TemplateError::new)
+    }
+
+    /// Content type of the rendered template.
+    ///
+    /// Default content type is `text/html; charset=utf-8`.
+    fn content_type(&self) -> &str {
+        "text/html; charset=utf-8"
+    }
+}
+
+/// Template responder that renders template in place.
+///
+/// Suitable for small templates, use [`RenderStream`](struct.RenderStream.html)
+/// for large ones.
+pub struct Render<T>(pub T);
+
+impl<T: Template> Responder for Render<T> {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let mut buf = Vec::new();
+        let res = match self.0.render_to(&mut buf) {
+            Ok(_) => Response::build(StatusCode::OK)
+                .content_type(self.0.content_type())
+                .body(buf),
+            Err(e) => TemplateConfig::handle_error(e, req),
+        };
+        ok(res)
+    }
+}
+
+/// Template responder that renders template on the thread pool.
+///
+/// Large pages are rendered to a buffer without blocking the worker, the
+/// response is sent once rendering is finished, so a slow client does not
+/// hold a thread of the pool. Errors are handled by the error handler of
+/// [`TemplateConfig`](struct.TemplateConfig.html).
+pub struct RenderStream<T>(pub T);
+
+impl<T> Responder for RenderStream<T>
+where
+    T: Template + Send + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let req = req.clone();
+        let content_type = self.0.content_type().to_owned();
+
+        let render = actix_http::run_blocking(move || {
+            let mut buf = Vec::with_capacity(BUFFER_SIZE);
+            self.0.render_to(&mut buf).map(|_| buf)
+        });
+
+        async move {
+            let res = match render.await {
+                Ok(body) => Response::build(StatusCode::OK)
+                    .content_type(content_type)
+                    .body(body),
+                Err(BlockingError::Error(e)) => TemplateConfig::handle_error(e, &req),
+                Err(BlockingError::Canceled) => TemplateConfig::handle_error(
+                    TemplateError::new("Thread pool is gone"),
+                    &req,
+                ),
+            };
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+/// Template rendering configuration
+///
+/// ```rust
+/// use actix_web::template::TemplateConfig;
+/// use actix_web::{App, HttpResponse};
+///
+/// # fn main() {
+/// let app = App::new().app_data(
+///     TemplateConfig::default().error_handler(|err, req| {
+///         HttpResponse::InternalServerError()
+///             .content_type("text/html")
+///             .body("<h1>Something went wrong</h1>")
+///     }),
+/// );
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TemplateConfig {
+    ehandler: Option<Arc<dyn Fn(TemplateError, &HttpRequest) -> Response + Send + Sync>>,
+}
+
+impl TemplateConfig {
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(TemplateError, &HttpRequest) -> Response + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    fn handle_error(err: TemplateError, req: &HttpRequest) -> Response {
+        log::error!(
+            "Failed to render template: {}. Request path: {:?}",
+            err,
+            req.path()
+        );
+
+        let handler = req
+            .app_data::<TemplateConfig>()
+            .and_then(|cfg| cfg.ehandler.clone());
+
+        if let Some(handler) = handler {
+            (handler)(err, req)
+        } else {
+            Response::new(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+impl Default for TemplateConfig {
+    fn default() -> Self {
+        TemplateConfig { ehandler: None }
+    }
+}
+
+#[cfg(feature = "askama")]
+impl<T: askama::Template> Template for T {
+    fn render_to(&self, out: &mut dyn io::Write) -> Result<(), TemplateError> {
+        let s = askama::Template::render(self).map_err(TemplateError::new)?;
+        out.write_all(s.as_bytes())?;
+        Ok(())
+    }
+
+    fn content_type(&self) -> &str {
+        match T::EXTENSION {
+            Some("html") | Some("htm") => "text/html; charset=utf-8",
+            Some("txt") => "text/plain; charset=utf-8",
+            Some("json") => "application/json",
+            Some("xml") => "application/xml",
+            _ => "text/html; charset=utf-8",
+        }
+    }
+}
+
+/// Template rendered with `tera` engine.
+#[cfg(feature = "tera")]
+pub struct TeraTemplate {
+    tera: Arc<tera::Tera>,
+    name: String,
+    context: tera::Context,
+}
+
+#[cfg(feature = "tera")]
+impl TeraTemplate {
+    /// Create template `name` of the `tera` instance with `context`.
+    pub fn new(tera: Arc<tera::Tera>, name: &str, context: tera::Context) -> Self {
+        TeraTemplate {
+            tera,
+            name: name.to_owned(),
+            context,
+        }
+    }
+}
+
+#[cfg(feature = "tera")]
+impl Template for TeraTemplate {
+    fn render_to(&self, out: &mut dyn io::Write) -> Result<(), TemplateError> {
+        self.tera
+            .render_to(&self.name, &self.context, out)
+            .map_err(TemplateError::new)
+    }
+}
+
+/// Template rendered with `minijinja` engine.
+#[cfg(feature = "minijinja")]
+pub struct MiniJinjaTemplate {
+    env: Arc<minijinja::Environment<'static>>,
+    name: String,
+    context: minijinja::Value,
+}
+
+#[cfg(feature = "minijinja")]
+impl MiniJinjaTemplate {
+    /// Create template `name` of the `env` environment with `context`.
+    ///
+    /// Context can be created with `minijinja::context!` macro or converted
+    /// from any serializable value with `minijinja::Value::from_serialize`.
+    pub fn new(
+        env: Arc<minijinja::Environment<'static>>,
+        name: &str,
+        context: minijinja::Value,
+    ) -> Self {
+        MiniJinjaTemplate {
+            env,
+            name: name.to_owned(),
+            context,
+        }
+    }
+}
+
+#[cfg(feature = "minijinja")]
+impl Template for MiniJinjaTemplate {
+    fn render_to(&self, out: &mut dyn io::Write) -> Result<(), TemplateError> {
+        let tpl = self
+            .env
+            .get_template(&self.name)
+            .map_err(TemplateError::new)?;
+        tpl.render_captured_to(&self.context, out)
+            .map(|_| ())
+            .map_err(TemplateError::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header;
+    use crate::test::{self, TestRequest};
+    use crate::HttpResponse;
+
+    struct Page {
+        size: usize,
+        fail: bool,
+    }
+
+    impl Template for Page {
+        fn render_to(&self, out: &mut dyn io::Write) -> Result<(), TemplateError> {
+            if self.fail {
+                return Err(TemplateError::new("broken template"));
+            }
+            for _ in 0..self.size {
+                out.write_all(b"<p>")?;
+            }
+            Ok(())
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_render() {
+        let req = TestRequest::default().to_http_request();
+        let resp = Render(Page {
+            size: 2,
+            fail: false,
+        })
+        .respond_to(&req)
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        let body = test::read_body(crate::dev::ServiceResponse::new(req, resp)).await;
+        assert_eq!(body, "<p><p>");
+    }
+
+    #[actix_rt::test]
+    async fn test_render_stream() {
+        let req = TestRequest::default().to_http_request();
+        let resp = RenderStream(Page {
+            size: 10_000,
+            fail: false,
+        })
+        .respond_to(&req)
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = test::read_body(crate::dev::ServiceResponse::new(req, resp)).await;
+        assert_eq!(body.len(), 30_000);
+    }
+
+    #[actix_rt::test]
+    async fn test_error_handler() {
+        let req = TestRequest::default().to_http_request();
+        let resp = Render(Page {
+            size: 0,
+            fail: true,
+        })
+        .respond_to(&req)
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let req = TestRequest::default()
+            .app_data(TemplateConfig::default().error_handler(|err, _| {
+                HttpResponse::ServiceUnavailable().body(err.to_string())
+            }))
+            .to_http_request();
+        let resp = RenderStream(Page {
+            size: 0,
+            fail: true,
+        })
+        .respond_to(&req)
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = test::read_body(crate::dev::ServiceResponse::new(req, resp)).await;
+        assert_eq!(body, "Template render error: broken template");
+    }
+
+    #[cfg(feature = "tera")]
+    #[test]
+    fn test_tera_template() {
+        let mut tera = tera::Tera::default();
+        tera.add_raw_template("hello", "Hello {{ name }}!").unwrap();
+        let mut ctx = tera::Context::new();
+        ctx.insert("name", "actix");
+
+        let tpl = TeraTemplate::new(Arc::new(tera), "hello", ctx);
+        assert_eq!(tpl.render().unwrap(), "Hello actix!");
+    }
+
+    #[cfg(feature = "minijinja")]
+    #[test]
+    fn test_minijinja_template() {
+        let mut env = minijinja::Environment::new();
+        env.add_template("hello", "Hello {{ name }}!").unwrap();
+
+        let ctx = minijinja::context!(name => "actix");
+        let tpl = MiniJinjaTemplate::new(Arc::new(env), "hello", ctx);
+        assert_eq!(tpl.render().unwrap(), "Hello actix!");
+
+        let tpl = MiniJinjaTemplate::new(
+            Arc::new(minijinja::Environment::new()),
+            "missing",
+            minijinja::Value::UNDEFINED,
+        );
+        assert!(tpl.render().is_err());
+    }
+}
@@ -16,7 +16,7 @@ use actix_service::{
     map_config, IntoService, IntoServiceFactory, Service, ServiceFactory,
 };
 use awc::error::PayloadError;
-use awc::{Client, ClientRequest, ClientResponse, Connector};
+use awc::{Client, ClientRequest, ClientResponse, Connector, TypedRoute};
 use bytes::{Bytes, BytesMut};
 use futures::future::ok;
 use futures::stream::{Stream, StreamExt};
@@ -985,6 +985,21 @@ impl TestServer {
         self.client.request(method, path.as_ref())
     }
 
+    /// Create `GET` request of a typed route
+    pub fn get_route<R: TypedRoute>(&self, params: &R::Params) -> ClientRequest {
+        self.request_route::<R>(Method::GET, params)
+    }
+
+    /// Create request of a typed route
+    pub fn request_route<R: TypedRoute>(
+        &self,
+        method: Method,
+        params: &R::Params,
+    ) -> ClientRequest {
+        self.client
+            .request_route::<R>(method, &self.url("/"), params)
+    }
+
     pub async fn load_body<S>(
         &mut self,
         mut response: ClientResponse<S>,
@@ -1279,6 +1294,36 @@ mod tests {
         assert!(res.status().is_success());
     }
 
+    #[actix_rt::test]
+    async fn test_typed_route() {
+        struct GetUser;
+
+        impl TypedRoute for GetUser {
+            const NAME: &'static str = "get_user";
+            const PATH: &'static str = "/orgs/{org}/users/{id}";
+            type Params = (String, u32);
+        }
+
+        let srv = start(|| {
+            App::new().service(web::resource_for::<GetUser>().to(
+                |req: HttpRequest, params: web::Path<(String, u32)>| {
+                    let url = req.url_for(GetUser::NAME, &[&params.0, "7"]).unwrap();
+                    HttpResponse::Ok().body(format!("{} {} {}", params.0, params.1, url))
+                },
+            ))
+        });
+
+        let mut res = srv
+            .get_route::<GetUser>(&("rust lang".to_owned(), 42))
+            .send()
+            .await
+            .unwrap();
+        assert!(res.status().is_success());
+        let body = res.body().await.unwrap();
+        let url = srv.url("/orgs/rust%20lang/users/7");
+        assert_eq!(body, format!("rust lang 42 {}", url));
+    }
+
     /*
 
         Comment out until actix decoupled of actix-http:
@@ -0,0 +1,250 @@
+//! Request scoped event bus
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use actix_http::error::Error;
+use actix_http::HttpMessage;
+use futures::future::{ok, Ready};
+
+use crate::dev::Payload;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+type Subscriber = Rc<dyn Fn(&dyn Any)>;
+
+/// Typed publish/subscribe bus scoped to a single request.
+///
+/// Bus is stored in request extensions, so middleware and handlers get the
+/// same bus for the request. Published events are delivered to subscribers of
+/// the event type and are retained until the request is dropped, which lets
+/// later consumers read events published before they were called.
+///
+/// ```rust
+/// use actix_web::dev::Service;
+/// use actix_web::{web, App};
+///
+/// #[derive(Clone)]
+/// enum CacheStatus {
+///     Hit,
+///     Miss,
+/// }
+///
+/// async fn index(bus: web::RequestBus) -> &'static str {
+///     bus.publish(CacheStatus::Miss);
+///     "Welcome!"
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap_fn(|req, srv| {
+///             let bus = web::RequestBus::of(&req);
+///             let fut = srv.call(req);
+///             async move {
+///                 let res = fut.await?;
+///                 if let Some(CacheStatus::Miss) = bus.last::<CacheStatus>() {
+///                     // record cache miss
+///                 }
+///                 Ok(res)
+///             }
+///         })
+///         .route("/", web::get().to(index));
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct RequestBus {
+    inner: Rc<RefCell<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    events: HashMap<TypeId, Box<dyn Any>>,
+    subscribers: HashMap<TypeId, Vec<Subscriber>>,
+}
+
+impl RequestBus {
+    /// Get bus of the request, bus is created on first use.
+    pub fn of<M: HttpMessage>(msg: &M) -> RequestBus {
+        if let Some(bus) = msg.extensions().get::<RequestBus>() {
+            return bus.clone();
+        }
+
+        let bus = RequestBus::default();
+        msg.extensions_mut().insert(bus.clone());
+        bus
+    }
+
+    /// Publish event.
+    ///
+    /// Subscribers of the event type are called before this method returns.
+    pub fn publish<T: 'static>(&self, event: T) {
+        let subscribers = self
+            .inner
+            .borrow()
+            .subscribers
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .unwrap_or_default();
+
+        // subscribers are called without holding the borrow, so they can use
+        // the bus as well
+        for subscriber in subscribers {
+            subscriber(&event);
+        }
+
+        self.inner
+            .borrow_mut()
+            .events
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Vec::<T>::new()))
+            .downcast_mut::<Vec<T>>()
+            .unwrap()
+            .push(event);
+    }
+
+    /// Subscribe to events of type `T`.
+    ///
+    /// Subscriber receives events published after subscription.
+    pub fn subscribe<T, F>(&self, f: F)
+    where
+        T: 'static,
+        F: Fn(&T) + 'static,
+    {
+        let subscriber: Subscriber = Rc::new(move |event: &dyn Any| {
+            if let Some(event) = event.downcast_ref::<T>() {
+                f(event)
+            }
+        });
+        self.inner
+            .borrow_mut()
+            .subscribers
+            .entry(TypeId::of::<T>())
+            .or_insert_with(Vec::new)
+            .push(subscriber);
+    }
+
+    /// Get published events of type `T`.
+    pub fn events<T: Clone + 'static>(&self) -> Vec<T> {
+        self.inner
+            .borrow()
+            .events
+            .get(&TypeId::of::<T>())
+            .and_then(|events| events.downcast_ref::<Vec<T>>())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Get last published event of type `T`.
+    pub fn last<T: Clone + 'static>(&self) -> Option<T> {
+        self.inner
+            .borrow()
+            .events
+            .get(&TypeId::of::<T>())
+            .and_then(|events| events.downcast_ref::<Vec<T>>())
+            .and_then(|events| events.last().cloned())
+    }
+
+    /// Remove and return published events of type `T`.
+    pub fn take<T: 'static>(&self) -> Vec<T> {
+        self.inner
+            .borrow_mut()
+            .events
+            .remove(&TypeId::of::<T>())
+            .and_then(|events| events.downcast::<Vec<T>>().ok())
+            .map(|events| *events)
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for RequestBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.borrow();
+        f.debug_struct("RequestBus")
+            .field("events", &inner.events.len())
+            .field("subscribers", &inner.subscribers.len())
+            .finish()
+    }
+}
+
+impl FromRequest for RequestBus {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<RequestBus, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(RequestBus::of(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::dev::Service;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Auth(&'static str);
+
+    #[test]
+    fn test_publish_subscribe() {
+        let req = TestRequest::default().to_http_request();
+        let bus = RequestBus::of(&req);
+
+        let seen = Rc::new(Cell::new(0));
+        let seen2 = seen.clone();
+        RequestBus::of(&req).subscribe(move |_: &Auth| seen2.set(seen2.get() + 1));
+        bus.subscribe(|_: &u32| panic!("wrong event type"));
+
+        bus.publish(Auth("alice"));
+        bus.publish(Auth("bob"));
+        assert_eq!(seen.get(), 2);
+        assert_eq!(bus.last::<Auth>(), Some(Auth("bob")));
+        assert_eq!(bus.events::<Auth>(), vec![Auth("alice"), Auth("bob")]);
+        assert_eq!(bus.take::<Auth>().len(), 2);
+        assert!(bus.events::<Auth>().is_empty());
+        assert_eq!(bus.last::<String>(), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_middleware_to_handler() {
+        let mut srv = init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    let bus = RequestBus::of(&req);
+                    bus.publish(Auth("alice"));
+                    let fut = srv.call(req);
+                    async move {
+                        let mut res = fut.await?;
+                        let n = bus.events::<u32>().len();
+                        res.headers_mut().insert(
+                            "x-events".parse().unwrap(),
+                            n.to_string().parse().unwrap(),
+                        );
+                        Ok(res)
+                    }
+                })
+                .route(
+                    "/",
+                    web::get().to(|bus: RequestBus| {
+                        bus.publish(1u32);
+                        bus.publish(2u32);
+                        HttpResponse::Ok().body(bus.last::<Auth>().unwrap().0)
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("x-events").unwrap(), "2");
+        assert_eq!(read_body(res).await, "alice");
+    }
+}
@@ -0,0 +1,300 @@
+//! Request scoped extractor cache
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::{fmt, ops};
+
+use actix_http::error::Error;
+use actix_http::HttpMessage;
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::dev::Payload;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+/// Cache of extracted values of a request, keyed by value type.
+///
+/// Cache is stored in request extensions and is filled by
+/// [`Cached`](struct.Cached.html) extractors. Middleware can insert values
+/// as well, i.e. resolved auth principal, then handlers extracting
+/// `Cached<T>` get the inserted value instead of running extractor of `T`.
+///
+/// ```rust
+/// use actix_web::dev::Service;
+/// use actix_web::{web, App};
+///
+/// struct User(String);
+/// # impl actix_web::FromRequest for User {
+/// #     type Config = ();
+/// #     type Error = actix_web::Error;
+/// #     type Future = futures::future::Ready<Result<User, actix_web::Error>>;
+/// #     fn from_request(_: &actix_web::HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+/// #         futures::future::ok(User("guest".to_owned()))
+/// #     }
+/// # }
+///
+/// async fn index(user: web::Cached<User>) -> String {
+///     format!("Welcome, {}!", user.0)
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap_fn(|req, srv| {
+///             web::ExtractCache::of(&req).insert(User("alice".to_owned()));
+///             srv.call(req)
+///         })
+///         .route("/", web::get().to(index));
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct ExtractCache {
+    items: Rc<RefCell<HashMap<TypeId, Rc<dyn Any>>>>,
+}
+
+impl ExtractCache {
+    /// Get cache of the request, cache is created on first use.
+    pub fn of<M: HttpMessage>(msg: &M) -> ExtractCache {
+        if let Some(cache) = msg.extensions().get::<ExtractCache>() {
+            return cache.clone();
+        }
+
+        let cache = ExtractCache::default();
+        msg.extensions_mut().insert(cache.clone());
+        cache
+    }
+
+    /// Get cached value of type `T`.
+    pub fn get<T: 'static>(&self) -> Option<Rc<T>> {
+        self.items
+            .borrow()
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .and_then(|item| item.downcast::<T>().ok())
+    }
+
+    /// Insert value of type `T`, replacing previously cached value.
+    pub fn insert<T: 'static>(&self, item: T) {
+        self.put(Rc::new(item));
+    }
+
+    /// Remove cached value of type `T`, next `Cached<T>` extractor runs
+    /// extractor of `T` again.
+    pub fn remove<T: 'static>(&self) -> Option<Rc<T>> {
+        self.items
+            .borrow_mut()
+            .remove(&TypeId::of::<T>())
+            .and_then(|item| item.downcast::<T>().ok())
+    }
+
+    fn put<T: 'static>(&self, item: Rc<T>) {
+        self.items.borrow_mut().insert(TypeId::of::<T>(), item);
+    }
+}
+
+impl fmt::Debug for ExtractCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtractCache")
+            .field("items", &self.items.borrow().len())
+            .finish()
+    }
+}
+
+impl FromRequest for ExtractCache {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<ExtractCache, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(ExtractCache::of(req))
+    }
+}
+
+/// Extract `T` once per request.
+///
+/// First `Cached<T>` extractor of the request runs extractor of `T` and
+/// stores the value in [`ExtractCache`](struct.ExtractCache.html) of the
+/// request, later `Cached<T>` extractors of middleware and handlers get the
+/// stored value. This allows payload extractors, i.e. `Json<T>`, to be used
+/// by middleware and handler of the same request. Errors are not cached.
+///
+/// Caching is opt-in, plain `T` extractors always run extraction. Extractors
+/// of the same `Cached<T>` that run concurrently, i.e. both in a tuple, do
+/// not share the in-flight extraction.
+///
+/// ```rust
+/// use actix_web::web;
+///
+/// async fn index(body: web::Cached<web::Json<serde_json::Value>>) -> String {
+///     format!("Got {}", body.0)
+/// }
+/// ```
+pub struct Cached<T>(Rc<T>);
+
+impl<T> Cached<T> {
+    /// Get reference to inner value.
+    pub fn get_ref(&self) -> &T {
+        self.0.as_ref()
+    }
+
+    /// Convert to the internal Rc<T>
+    pub fn into_inner(self) -> Rc<T> {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Cached<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0.as_ref()
+    }
+}
+
+impl<T> Clone for Cached<T> {
+    fn clone(&self) -> Cached<T> {
+        Cached(self.0.clone())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Cached<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cached({:?})", self.0)
+    }
+}
+
+impl<T> FromRequest for Cached<T>
+where
+    T: FromRequest + 'static,
+    T::Future: 'static,
+{
+    type Config = T::Config;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let cache = ExtractCache::of(req);
+        if let Some(item) = cache.get::<T>() {
+            return ok(Cached(item)).boxed_local();
+        }
+
+        T::from_request(req, payload)
+            .map(move |res| match res {
+                Ok(item) => {
+                    let item = Rc::new(item);
+                    cache.put(item.clone());
+                    Ok(Cached(item))
+                }
+                Err(e) => Err(e.into()),
+            })
+            .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use futures::future::err;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::dev::Service;
+    use crate::error::ErrorBadRequest;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::types::Json;
+    use crate::{web, App, HttpResponse};
+
+    thread_local! {
+        static EXTRACTED: Cell<usize> = Cell::new(0);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Principal(&'static str);
+
+    impl FromRequest for Principal {
+        type Config = ();
+        type Error = Error;
+        type Future = Ready<Result<Principal, Error>>;
+
+        fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+            EXTRACTED.with(|n| n.set(n.get() + 1));
+            if req.headers().contains_key("authorization") {
+                ok(Principal("alice"))
+            } else {
+                err(ErrorBadRequest("no principal"))
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_extract_once() {
+        EXTRACTED.with(|n| n.set(0));
+        let req = TestRequest::default()
+            .header("authorization", "basic")
+            .to_http_request();
+        let p1 = Cached::<Principal>::extract(&req).await.unwrap();
+        let p2 = Cached::<Principal>::extract(&req).await.unwrap();
+        assert_eq!(*p1, Principal("alice"));
+        assert!(Rc::ptr_eq(&p1.into_inner(), &p2.into_inner()));
+        assert_eq!(EXTRACTED.with(|n| n.get()), 1);
+
+        // errors are not cached
+        let req = TestRequest::default().to_http_request();
+        assert!(Cached::<Principal>::extract(&req).await.is_err());
+        assert!(Cached::<Principal>::extract(&req).await.is_err());
+        assert_eq!(EXTRACTED.with(|n| n.get()), 3);
+
+        let cache = ExtractCache::of(&req);
+        cache.insert(Principal("bob"));
+        let p = Cached::<Principal>::extract(&req).await.unwrap();
+        assert_eq!(*p, Principal("bob"));
+        assert_eq!(*cache.remove::<Principal>().unwrap(), Principal("bob"));
+        assert!(cache.get::<Principal>().is_none());
+        assert_eq!(EXTRACTED.with(|n| n.get()), 3);
+    }
+
+    #[actix_rt::test]
+    async fn test_cached_payload() {
+        let (req, mut pl) = TestRequest::default()
+            .set_json(&json!({"name": "test"}))
+            .to_http_parts();
+        let j1 = Cached::<Json<Value>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        // payload is consumed, second extraction gets cached value
+        let j2 = Cached::<Json<Value>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(j1.get_ref().0, json!({"name": "test"}));
+        assert_eq!(j2.get_ref().0, json!({"name": "test"}));
+        assert!(Json::<Value>::from_request(&req, &mut pl).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_middleware_to_handler() {
+        let mut srv = init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    ExtractCache::of(&req).insert(Principal("carol"));
+                    srv.call(req)
+                })
+                .route(
+                    "/",
+                    web::get().to(|p: Cached<Principal>| {
+                        HttpResponse::Ok().body(p.get_ref().0)
+                    }),
+                ),
+        )
+        .await;
+
+        EXTRACTED.with(|n| n.set(0));
+        let req = TestRequest::default().to_request();
+        let res = call_service(&mut srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "carol");
+        assert_eq!(EXTRACTED.with(|n| n.get()), 0);
+    }
+}
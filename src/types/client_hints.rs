@@ -0,0 +1,254 @@
+//! Client hints extractor
+use std::str::FromStr;
+
+use actix_http::error::Error;
+use actix_http::http::header::HeaderName;
+use futures::future::{ok, Ready};
+
+use crate::dev::Payload;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+/// Client hint the server could advertise with `Accept-CH` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClientHint {
+    /// Device pixel ratio, `Sec-CH-DPR`
+    Dpr,
+    /// Intended display width of the image in physical pixels, `Sec-CH-Width`
+    Width,
+    /// Layout viewport width in css pixels, `Sec-CH-Viewport-Width`
+    ViewportWidth,
+    /// Approximate device memory in GiB, `Sec-CH-Device-Memory`
+    DeviceMemory,
+    /// Reduced data usage preference, `Save-Data`
+    SaveData,
+    /// Round trip time in milliseconds, `RTT`
+    Rtt,
+    /// Downlink bandwidth in Mbps, `Downlink`
+    Downlink,
+    /// Effective connection type, `ECT`
+    Ect,
+    /// Mobile user agent, `Sec-CH-UA-Mobile`
+    Mobile,
+    /// Preferred color scheme, `Sec-CH-Prefers-Color-Scheme`
+    PrefersColorScheme,
+}
+
+impl ClientHint {
+    /// Name of the request header the hint is sent with.
+    pub fn header_name(self) -> HeaderName {
+        HeaderName::from_static(self.as_str())
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ClientHint::Dpr => "sec-ch-dpr",
+            ClientHint::Width => "sec-ch-width",
+            ClientHint::ViewportWidth => "sec-ch-viewport-width",
+            ClientHint::DeviceMemory => "sec-ch-device-memory",
+            ClientHint::SaveData => "save-data",
+            ClientHint::Rtt => "rtt",
+            ClientHint::Downlink => "downlink",
+            ClientHint::Ect => "ect",
+            ClientHint::Mobile => "sec-ch-ua-mobile",
+            ClientHint::PrefersColorScheme => "sec-ch-prefers-color-scheme",
+        }
+    }
+
+    /// Header name of the hint before `Sec-CH-` prefix was introduced.
+    fn legacy(self) -> Option<&'static str> {
+        match self {
+            ClientHint::Dpr => Some("dpr"),
+            ClientHint::Width => Some("width"),
+            ClientHint::ViewportWidth => Some("viewport-width"),
+            ClientHint::DeviceMemory => Some("device-memory"),
+            _ => None,
+        }
+    }
+}
+
+/// Marker of requests that extracted client hints.
+pub(crate) struct ClientHintsUsed;
+
+/// Extract client hints of the request.
+///
+/// Hints are sent by clients only after the origin advertised them with
+/// `Accept-CH` header, see
+/// [**middleware::ClientHints**](../middleware/struct.ClientHints.html).
+/// Missing or malformed hints are `None`. Legacy header names without
+/// `Sec-CH-` prefix are accepted as well.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::web::{self, ClientHint};
+/// use actix_web::{middleware, App};
+///
+/// async fn image(hints: web::ClientHints) -> String {
+///     let width = hints.width().unwrap_or(800);
+///     if hints.save_data() {
+///         format!("/img/{}-low.jpg", width)
+///     } else {
+///         format!("/img/{}.jpg", width)
+///     }
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::ClientHints::new(&[
+///             ClientHint::Width,
+///             ClientHint::SaveData,
+///         ]))
+///         .route("/image", web::get().to(image));
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientHints {
+    dpr: Option<f32>,
+    width: Option<u32>,
+    viewport_width: Option<u32>,
+    device_memory: Option<f32>,
+    save_data: bool,
+    rtt: Option<u32>,
+    downlink: Option<f32>,
+    ect: Option<String>,
+    mobile: Option<bool>,
+    prefers_color_scheme: Option<String>,
+}
+
+impl ClientHints {
+    fn parse(req: &HttpRequest) -> Self {
+        let header = |hint| header(req, hint);
+        // structured header strings are quoted
+        let string = |hint| header(hint).map(|val| val.trim_matches('"').to_owned());
+
+        ClientHints {
+            dpr: number(req, ClientHint::Dpr),
+            width: number(req, ClientHint::Width),
+            viewport_width: number(req, ClientHint::ViewportWidth),
+            device_memory: number(req, ClientHint::DeviceMemory),
+            save_data: match header(ClientHint::SaveData) {
+                Some(val) => val.eq_ignore_ascii_case("on"),
+                None => false,
+            },
+            rtt: number(req, ClientHint::Rtt),
+            downlink: number(req, ClientHint::Downlink),
+            ect: string(ClientHint::Ect),
+            mobile: header(ClientHint::Mobile).and_then(|val| match val {
+                "?1" => Some(true),
+                "?0" => Some(false),
+                _ => None,
+            }),
+            prefers_color_scheme: string(ClientHint::PrefersColorScheme),
+        }
+    }
+
+    /// Device pixel ratio
+    pub fn dpr(&self) -> Option<f32> {
+        self.dpr
+    }
+
+    /// Intended display width of the image in physical pixels
+    pub fn width(&self) -> Option<u32> {
+        self.width
+    }
+
+    /// Layout viewport width in css pixels
+    pub fn viewport_width(&self) -> Option<u32> {
+        self.viewport_width
+    }
+
+    /// Approximate device memory in GiB
+    pub fn device_memory(&self) -> Option<f32> {
+        self.device_memory
+    }
+
+    /// Returns true if client prefers reduced data usage
+    pub fn save_data(&self) -> bool {
+        self.save_data
+    }
+
+    /// Round trip time in milliseconds
+    pub fn rtt(&self) -> Option<u32> {
+        self.rtt
+    }
+
+    /// Downlink bandwidth in Mbps
+    pub fn downlink(&self) -> Option<f32> {
+        self.downlink
+    }
+
+    /// Effective connection type, i.e. `4g`
+    pub fn ect(&self) -> Option<&str> {
+        self.ect.as_ref().map(String::as_str)
+    }
+
+    /// Returns `Some(true)` if the user agent runs on a mobile device
+    pub fn mobile(&self) -> Option<bool> {
+        self.mobile
+    }
+
+    /// Preferred color scheme, i.e. `dark`
+    pub fn prefers_color_scheme(&self) -> Option<&str> {
+        self.prefers_color_scheme.as_ref().map(String::as_str)
+    }
+}
+
+impl FromRequest for ClientHints {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<ClientHints, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        req.extensions_mut().insert(ClientHintsUsed);
+        ok(ClientHints::parse(req))
+    }
+}
+
+fn header(req: &HttpRequest, hint: ClientHint) -> Option<&str> {
+    let headers = req.headers();
+    headers
+        .get(hint.as_str())
+        .or_else(|| hint.legacy().and_then(|name| headers.get(name)))
+        .and_then(|val| val.to_str().ok())
+        .map(|val| val.trim())
+}
+
+fn number<T: FromStr>(req: &HttpRequest, hint: ClientHint) -> Option<T> {
+    header(req, hint).and_then(|val| val.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[actix_rt::test]
+    async fn test_client_hints() {
+        let (req, mut pl) = TestRequest::default()
+            .header("sec-ch-dpr", "2.0")
+            .header("width", "640")
+            .header("sec-ch-viewport-width", "abc")
+            .header("save-data", "on")
+            .header("ect", "4g")
+            .header("sec-ch-ua-mobile", "?1")
+            .header("sec-ch-prefers-color-scheme", "\"dark\"")
+            .to_http_parts();
+        let hints = ClientHints::from_request(&req, &mut pl).await.unwrap();
+
+        assert_eq!(hints.dpr(), Some(2.0));
+        assert_eq!(hints.width(), Some(640));
+        assert_eq!(hints.viewport_width(), None);
+        assert_eq!(hints.device_memory(), None);
+        assert!(hints.save_data());
+        assert_eq!(hints.ect(), Some("4g"));
+        assert_eq!(hints.mobile(), Some(true));
+        assert_eq!(hints.prefers_color_scheme(), Some("dark"));
+        assert!(req.extensions().contains::<ClientHintsUsed>());
+
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let hints = ClientHints::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(hints, ClientHints::default());
+    }
+}
@@ -0,0 +1,393 @@
+//! Signed cookie extractor
+
+use std::sync::Arc;
+use std::{fmt, ops};
+
+use actix_http::cookie::{Cookie, CookieJar, KeyRing};
+use actix_http::error::{Error, ErrorInternalServerError};
+use futures::future::{err, ok, Ready};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::dev::Payload;
+use crate::error::SignedCookieError;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+use crate::HttpMessage;
+
+/// Extract typed information from a signed cookie.
+///
+/// The cookie value is verified with the key ring configured via
+/// [**SignedCookieConfig**](struct.SignedCookieConfig.html) and then deserialized
+/// from base64 encoded json. Private mode additionally encrypts the cookie value,
+/// so its content is not visible to the client.
+///
+/// This type is only available when the `secure-cookies` feature is enabled.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{web, App, HttpRequest, HttpResponse};
+/// use actix_web::cookie::{Key, KeyRing};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Session {
+///     user_id: u64,
+/// }
+///
+/// async fn index(session: web::SignedCookie<Session>) -> String {
+///     format!("Welcome {}!", session.user_id)
+/// }
+///
+/// async fn login(req: HttpRequest) -> HttpResponse {
+///     let cfg = req.app_data::<web::SignedCookieConfig>().unwrap();
+///     let cookie = cfg.cookie(&Session { user_id: 1 }).unwrap();
+///     HttpResponse::Ok().cookie(cookie).finish()
+/// }
+///
+/// fn main() {
+///     let keys = KeyRing::new(Key::generate());
+///
+///     let app = App::new()
+///         .app_data(web::SignedCookieConfig::new("session", keys))
+///         .service(web::resource("/").route(web::get().to(index)))
+///         .service(web::resource("/login").route(web::post().to(login)));
+/// }
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub struct SignedCookie<T>(pub T);
+
+impl<T> SignedCookie<T> {
+    /// Deconstruct to a inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for SignedCookie<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for SignedCookie<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SignedCookie<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for SignedCookie<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> FromRequest for SignedCookie<T>
+where
+    T: DeserializeOwned,
+{
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = SignedCookieConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let cfg = if let Some(cfg) = req.app_data::<Self::Config>() {
+            cfg
+        } else {
+            log::debug!(
+                "Failed to construct SignedCookie extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            return err(ErrorInternalServerError(
+                "Signed cookie keys are not configured, \
+                 to configure use App::app_data(SignedCookieConfig::new(..))",
+            ));
+        };
+
+        match cfg.verify(req) {
+            Ok(val) => ok(SignedCookie(val)),
+            Err(e) => {
+                log::debug!(
+                    "Failed during SignedCookie extractor: {}. \
+                     Request path: {:?}",
+                    e,
+                    req.path()
+                );
+
+                let e = if let Some(ref error_handler) = cfg.ehandler {
+                    (error_handler)(e, req)
+                } else {
+                    e.into()
+                };
+                err(e)
+            }
+        }
+    }
+}
+
+/// Signed cookie extractor configuration
+///
+/// Holds the cookie name and the key ring used to sign and verify cookie
+/// values. Cookies are always signed with the primary key of the ring, old keys
+/// can be added as fallbacks to keep accepting cookies issued before a key
+/// rotation.
+///
+/// ```rust
+/// use actix_web::{error, web, App, HttpResponse};
+/// use actix_web::cookie::{Key, KeyRing};
+///
+/// # fn main() {
+/// # let old_key = Key::generate();
+/// let keys = KeyRing::new(Key::generate()).fallback(old_key);
+///
+/// let app = App::new().app_data(
+///     web::SignedCookieConfig::new("auth", keys)
+///         .private()
+///         .error_handler(|err, req| {
+///             error::InternalError::from_response(
+///                 err, HttpResponse::Unauthorized().finish()).into()
+///         }),
+/// );
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SignedCookieConfig {
+    name: String,
+    keys: Option<KeyRing>,
+    private: bool,
+    path: String,
+    ehandler:
+        Option<Arc<dyn Fn(SignedCookieError, &HttpRequest) -> Error + Send + Sync>>,
+}
+
+impl SignedCookieConfig {
+    /// Create new configuration for cookie `name` signed with `keys`.
+    pub fn new<K: Into<KeyRing>>(name: &str, keys: K) -> Self {
+        SignedCookieConfig::default().name(name).keys(keys)
+    }
+
+    /// Set cookie name (default: `actix-signed`)
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_owned();
+        self
+    }
+
+    /// Set the key ring used to sign and verify cookies
+    pub fn keys<K: Into<KeyRing>>(mut self, keys: K) -> Self {
+        self.keys = Some(keys.into());
+        self
+    }
+
+    /// Encrypt cookie value in addition to signing it
+    pub fn private(mut self) -> Self {
+        self.private = true;
+        self
+    }
+
+    /// Set path of the cookies created by this config (default: `/`)
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.to_owned();
+        self
+    }
+
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(SignedCookieError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    /// Create a signed cookie holding base64 encoded json of `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if key ring is not configured.
+    pub fn cookie<T: Serialize>(
+        &self,
+        value: &T,
+    ) -> Result<Cookie<'static>, SignedCookieError> {
+        let keys = self.keys.as_ref().expect("Signed cookie keys are not set");
+        let value = base64::encode(&serde_json::to_vec(value)?);
+        let cookie = Cookie::build(self.name.clone(), value)
+            .path(self.path.clone())
+            .http_only(true)
+            .finish();
+
+        let mut jar = CookieJar::new();
+        if self.private {
+            jar.private_with(keys).add(cookie);
+        } else {
+            jar.signed_with(keys).add(cookie);
+        }
+        Ok(jar.get(&self.name).unwrap().clone())
+    }
+
+    fn verify<T: DeserializeOwned>(
+        &self,
+        req: &HttpRequest,
+    ) -> Result<T, SignedCookieError> {
+        let keys = match self.keys {
+            Some(ref keys) => keys,
+            None => return Err(SignedCookieError::NotConfigured),
+        };
+        let cookie = req.cookie(&self.name).ok_or(SignedCookieError::NotFound)?;
+
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie);
+        let cookie = if self.private {
+            jar.private_with(keys).get(&self.name)
+        } else {
+            jar.signed_with(keys).get(&self.name)
+        };
+
+        match cookie {
+            Some(cookie) => {
+                let value = base64::decode(cookie.value())
+                    .map_err(|_| SignedCookieError::Verification)?;
+                Ok(serde_json::from_slice(&value)?)
+            }
+            None => Err(SignedCookieError::Verification),
+        }
+    }
+}
+
+impl Default for SignedCookieConfig {
+    fn default() -> Self {
+        SignedCookieConfig {
+            name: "actix-signed".to_owned(),
+            keys: None,
+            private: false,
+            path: "/".to_owned(),
+            ehandler: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::cookie::Key;
+    use serde_derive::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::error::InternalError;
+    use crate::http::StatusCode;
+    use crate::test::TestRequest;
+    use crate::HttpResponse;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Session {
+        id: u64,
+    }
+
+    #[actix_rt::test]
+    async fn test_signed_cookie() {
+        let cfg = SignedCookieConfig::new("session", Key::generate());
+        let cookie = cfg.cookie(&Session { id: 10 }).unwrap();
+        assert!(cookie.value().ends_with(&base64::encode(r#"{"id":10}"#)));
+
+        let (req, mut pl) = TestRequest::default()
+            .cookie(cookie)
+            .app_data(cfg)
+            .to_http_parts();
+        let s = SignedCookie::<Session>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.into_inner(), Session { id: 10 });
+    }
+
+    #[actix_rt::test]
+    async fn test_private_cookie() {
+        let cfg = SignedCookieConfig::new("session", Key::generate()).private();
+        let cookie = cfg.cookie(&Session { id: 10 }).unwrap();
+        assert!(!cookie.value().contains("id"));
+
+        let (req, mut pl) = TestRequest::default()
+            .cookie(cookie)
+            .app_data(cfg)
+            .to_http_parts();
+        let s = SignedCookie::<Session>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.id, 10);
+    }
+
+    #[actix_rt::test]
+    async fn test_key_rotation() {
+        let old_key = Key::generate();
+        let cookie = SignedCookieConfig::new("session", old_key.clone())
+            .cookie(&Session { id: 1 })
+            .unwrap();
+
+        // cookie signed with the old key is rejected without fallback
+        let (req, mut pl) = TestRequest::default()
+            .cookie(cookie.clone())
+            .app_data(SignedCookieConfig::new("session", Key::generate()))
+            .to_http_parts();
+        let res = SignedCookie::<Session>::from_request(&req, &mut pl).await;
+        assert_eq!(
+            res.unwrap_err()
+                .as_response_error()
+                .error_response()
+                .status(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let keys = KeyRing::new(Key::generate()).fallback(old_key);
+        let (req, mut pl) = TestRequest::default()
+            .cookie(cookie)
+            .app_data(SignedCookieConfig::new("session", keys))
+            .to_http_parts();
+        let s = SignedCookie::<Session>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.id, 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_custom_error_responder() {
+        let (req, mut pl) = TestRequest::default()
+            .app_data(
+                SignedCookieConfig::new("session", Key::generate()).error_handler(
+                    |e, _| {
+                        let resp = HttpResponse::Unauthorized().finish();
+                        InternalError::from_response(e, resp).into()
+                    },
+                ),
+            )
+            .to_http_parts();
+        let res = SignedCookie::<Session>::from_request(&req, &mut pl).await;
+        assert_eq!(
+            res.unwrap_err()
+                .as_response_error()
+                .error_response()
+                .status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_not_configured() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let res = SignedCookie::<Session>::from_request(&req, &mut pl).await;
+        assert_eq!(
+            res.unwrap_err()
+                .as_response_error()
+                .error_response()
+                .status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -4,10 +4,13 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::{fmt, ops};
+use std::time::{Duration, Instant};
+use std::{fmt, io, ops, thread};
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
+use futures::channel::mpsc;
 use futures::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
+use futures::task::{waker, ArcWake};
 use futures::StreamExt;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -88,6 +91,30 @@ impl<T> Json<T> {
     }
 }
 
+impl<T: Serialize + Send + 'static> Json<T> {
+    /// Create responder that serializes value to response body in chunks.
+    ///
+    /// Serialization runs on the blocking thread pool and is throttled by
+    /// the connection, so large values are sent without materializing whole
+    /// json document in memory. Response status is sent before
+    /// serialization completes, serialization error aborts response, as
+    /// does a client that does not read the body for 30 seconds.
+    ///
+    /// ```rust
+    /// use actix_web::web;
+    ///
+    /// async fn index() -> web::JsonStream<Vec<u64>> {
+    ///     web::Json::stream((0..1_000_000).collect())
+    /// }
+    /// ```
+    pub fn stream(value: T) -> JsonStream<T> {
+        JsonStream {
+            value,
+            chunk_size: 32_768,
+        }
+    }
+}
+
 impl<T> ops::Deref for Json<T> {
     type Target = T;
 
@@ -136,6 +163,121 @@ impl<T: Serialize> Responder for Json<T> {
     }
 }
 
+/// Json responder that serializes value in chunks, created by
+/// [`Json::stream()`](struct.Json.html#method.stream).
+pub struct JsonStream<T> {
+    value: T,
+    chunk_size: usize,
+}
+
+impl<T> JsonStream<T> {
+    /// Set size of response body chunks, default is 32Kb.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size;
+        self
+    }
+}
+
+impl<T: Serialize + Send + 'static> Responder for JsonStream<T> {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let JsonStream { value, chunk_size } = self;
+
+        // serialized json is sent to response body, serialization stops if
+        // response is dropped or client does not read it in time
+        let (tx, rx) = mpsc::channel(1);
+        let serialize = actix_http::run_blocking(move || {
+            let mut writer = ChunkWriter {
+                buf: BytesMut::with_capacity(chunk_size),
+                chunk_size,
+                tx,
+                timeout: STREAM_SEND_TIMEOUT,
+            };
+            match serde_json::to_writer(&mut writer, &value) {
+                Ok(()) => {
+                    let chunk = writer.buf.split().freeze();
+                    writer.send(Ok(chunk))
+                }
+                Err(e) if e.is_io() => Ok(()),
+                Err(e) => writer.send(Err(e)),
+            }
+        });
+        actix_rt::spawn(serialize.map(|_| ()));
+
+        ok(Response::build(StatusCode::OK)
+            .content_type("application/json")
+            .streaming(rx))
+    }
+}
+
+/// Time client has for reading a chunk before serialization is aborted.
+const STREAM_SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sends serialized json to response body in chunks.
+struct ChunkWriter {
+    buf: BytesMut,
+    chunk_size: usize,
+    tx: mpsc::Sender<Result<Bytes, serde_json::Error>>,
+    timeout: Duration,
+}
+
+/// Unparks serialization thread when response body has room for a chunk.
+struct ThreadWaker(thread::Thread);
+
+impl ArcWake for ThreadWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.unpark();
+    }
+}
+
+impl ChunkWriter {
+    fn send(&mut self, item: Result<Bytes, serde_json::Error>) -> io::Result<()> {
+        let waker = waker(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            match self.tx.poll_ready(&mut cx) {
+                Poll::Ready(Ok(())) => {
+                    return self.tx.start_send(item).map_err(|_| dropped());
+                }
+                Poll::Ready(Err(_)) => return Err(dropped()),
+                Poll::Pending => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "Response body is not read",
+                        ));
+                    }
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        }
+    }
+}
+
+fn dropped() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "Response is dropped")
+}
+
+impl io::Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        if self.buf.len() >= self.chunk_size {
+            let chunk = self.buf.split().freeze();
+            self.send(Ok(chunk))?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 /// Json extractor. Allow to extract typed information from request's
 /// payload.
 ///
@@ -462,6 +604,64 @@ mod tests {
         assert_eq!(resp.body().bin_ref(), b"{\"name\":\"test\"}");
     }
 
+    #[actix_rt::test]
+    async fn test_stream_responder() {
+        let req = TestRequest::default().to_http_request();
+
+        let value: Vec<_> = (0..1000)
+            .map(|i| MyObject {
+                name: format!("test{}", i),
+            })
+            .collect();
+        let expected = serde_json::to_vec(&value).unwrap();
+
+        let mut resp = Json::stream(value)
+            .chunk_size(1024)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            header::HeaderValue::from_static("application/json")
+        );
+
+        let mut body = resp.take_body();
+        let mut chunks = 0;
+        let mut result = BytesMut::new();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.unwrap();
+            assert!(chunk.len() < 1024 + 32);
+            result.extend_from_slice(&chunk);
+            chunks += 1;
+        }
+        assert!(chunks > 10);
+        assert_eq!(&result[..], &expected[..]);
+    }
+
+    #[test]
+    fn test_stream_send_timeout() {
+        let (tx, rx) = mpsc::channel(0);
+        let mut writer = ChunkWriter {
+            buf: BytesMut::new(),
+            chunk_size: 16,
+            tx,
+            timeout: Duration::from_millis(50),
+        };
+
+        // channel has room for single chunk, body is never read
+        writer.send(Ok(Bytes::from_static(b"[1,"))).unwrap();
+        let err = writer.send(Ok(Bytes::from_static(b"2]"))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        drop(rx);
+        let (tx, rx) = mpsc::channel(0);
+        drop(rx);
+        writer.tx = tx;
+        let err = writer.send(Ok(Bytes::new())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
     #[actix_rt::test]
     async fn test_custom_error_responder() {
         let (req, mut pl) = TestRequest::default()
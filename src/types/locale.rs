@@ -0,0 +1,108 @@
+//! Negotiated locale extractor
+use std::{fmt, ops};
+
+use actix_http::error::{Error, ErrorInternalServerError};
+use futures::future::{err, ok, Ready};
+
+use crate::dev::Payload;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+/// Source the request locale has been negotiated from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LocaleSource {
+    /// Leading path segment, i.e. `/de/about`
+    Path,
+    /// Locale cookie
+    Cookie,
+    /// `Accept-Language` header
+    Header,
+}
+
+/// Extract locale negotiated by
+/// [**middleware::Locale**](../middleware/struct.Locale.html).
+///
+/// Locale tag is always one of the locales supported by the middleware. If
+/// none of the configured sources matches, default locale is used.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::{middleware, web, App};
+///
+/// async fn index(locale: web::Locale) -> String {
+///     match locale.as_str() {
+///         "de" => "Hallo!".to_owned(),
+///         _ => "Hello!".to_owned(),
+///     }
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::Locale::new(&["en", "de"]))
+///         .route("/", web::get().to(index));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    tag: String,
+    source: Option<LocaleSource>,
+}
+
+impl Locale {
+    pub(crate) fn new(tag: &str, source: Option<LocaleSource>) -> Self {
+        Locale {
+            tag: tag.to_owned(),
+            source,
+        }
+    }
+
+    /// Locale tag, i.e. `en-US`
+    pub fn as_str(&self) -> &str {
+        &self.tag
+    }
+
+    /// Source the locale has been negotiated from.
+    ///
+    /// Returns `None` if default locale is used.
+    pub fn source(&self) -> Option<LocaleSource> {
+        self.source
+    }
+}
+
+impl ops::Deref for Locale {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.tag
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.tag)
+    }
+}
+
+impl FromRequest for Locale {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Locale, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(locale) = req.extensions().get::<Locale>() {
+            ok(locale.clone())
+        } else {
+            log::debug!(
+                "Failed to construct Locale extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            err(ErrorInternalServerError(
+                "Locale negotiation is not configured, to configure use \
+                 App::wrap(middleware::Locale::new(..))",
+            ))
+        }
+    }
+}
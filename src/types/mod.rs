@@ -1,15 +1,32 @@
 //! Helper types
 
+mod bus;
+mod cached;
+mod client_hints;
+#[cfg(feature = "secure-cookies")]
+mod cookie;
 pub(crate) mod form;
 pub(crate) mod json;
+mod locale;
 mod path;
 pub(crate) mod payload;
+mod precondition;
 mod query;
 pub(crate) mod readlines;
+mod tenant;
 
+pub use self::bus::RequestBus;
+pub use self::cached::{Cached, ExtractCache};
+pub(crate) use self::client_hints::ClientHintsUsed;
+pub use self::client_hints::{ClientHint, ClientHints};
+#[cfg(feature = "secure-cookies")]
+pub use self::cookie::{SignedCookie, SignedCookieConfig};
 pub use self::form::{Form, FormConfig};
-pub use self::json::{Json, JsonConfig};
+pub use self::json::{Json, JsonConfig, JsonStream};
+pub use self::locale::{Locale, LocaleSource};
 pub use self::path::{Path, PathConfig};
-pub use self::payload::{Payload, PayloadConfig};
+pub use self::payload::{BytesConfig, Payload, PayloadConfig, PayloadStream};
+pub use self::precondition::{Preconditions, PreconditionsConfig};
 pub use self::query::{Query, QueryConfig};
 pub use self::readlines::Readlines;
+pub use self::tenant::{Tenant, TenantData, TenantSource};
@@ -2,6 +2,7 @@
 use std::future::Future;
 use std::pin::Pin;
 use std::str;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use actix_http::error::{Error, ErrorBadRequest, PayloadError};
@@ -19,6 +20,9 @@ use crate::request::HttpRequest;
 
 /// Payload extractor returns request 's payload stream.
 ///
+/// Payload size is not limited, use [**PayloadStream**](struct.PayloadStream.html)
+/// for stream of limited size.
+///
 /// ## Example
 ///
 /// ```rust
@@ -107,7 +111,7 @@ impl FromRequest for Payload {
 ///
 /// Loads request's payload and construct Bytes instance.
 ///
-/// [**PayloadConfig**](struct.PayloadConfig.html) allows to configure
+/// [**BytesConfig**](struct.BytesConfig.html) allows to configure
 /// extraction process.
 ///
 /// ## Example
@@ -129,7 +133,7 @@ impl FromRequest for Payload {
 /// }
 /// ```
 impl FromRequest for Bytes {
-    type Config = PayloadConfig;
+    type Config = BytesConfig;
     type Error = Error;
     type Future = Either<
         LocalBoxFuture<'static, Result<Bytes, Error>>,
@@ -138,21 +142,17 @@ impl FromRequest for Bytes {
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
-        let tmp;
-        let cfg = if let Some(cfg) = req.app_data::<PayloadConfig>() {
-            cfg
-        } else {
-            tmp = PayloadConfig::default();
-            &tmp
-        };
+        let cfg = BytesConfig::from_req(req);
 
-        if let Err(e) = cfg.check_mimetype(req) {
+        if let Err(e) = check_mimetype(&cfg.mimetype, req) {
             return Either::Right(err(e));
         }
 
-        let limit = cfg.limit;
-        let fut = HttpMessageBody::new(req, payload).limit(limit);
-        Either::Left(async move { Ok(fut.await?) }.boxed_local())
+        let req = req.clone();
+        let fut = HttpMessageBody::new(&req, payload).limit(cfg.limit);
+        Either::Left(
+            async move { fut.await.map_err(|e| cfg.error(e, &req)) }.boxed_local(),
+        )
     }
 }
 
@@ -160,7 +160,7 @@ impl FromRequest for Bytes {
 ///
 /// Text extractor automatically decode body according to the request's charset.
 ///
-/// [**PayloadConfig**](struct.PayloadConfig.html) allows to configure
+/// [**BytesConfig**](struct.BytesConfig.html) allows to configure
 /// extraction process.
 ///
 /// ## Example
@@ -184,7 +184,7 @@ impl FromRequest for Bytes {
 /// }
 /// ```
 impl FromRequest for String {
-    type Config = PayloadConfig;
+    type Config = BytesConfig;
     type Error = Error;
     type Future = Either<
         LocalBoxFuture<'static, Result<String, Error>>,
@@ -193,16 +193,10 @@ impl FromRequest for String {
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
-        let tmp;
-        let cfg = if let Some(cfg) = req.app_data::<PayloadConfig>() {
-            cfg
-        } else {
-            tmp = PayloadConfig::default();
-            &tmp
-        };
+        let cfg = BytesConfig::from_req(req);
 
         // check content-type
-        if let Err(e) = cfg.check_mimetype(req) {
+        if let Err(e) = check_mimetype(&cfg.mimetype, req) {
             return Either::Right(err(e));
         }
 
@@ -211,12 +205,12 @@ impl FromRequest for String {
             Ok(enc) => enc,
             Err(e) => return Either::Right(err(e.into())),
         };
-        let limit = cfg.limit;
-        let fut = HttpMessageBody::new(req, payload).limit(limit);
+        let req = req.clone();
+        let fut = HttpMessageBody::new(&req, payload).limit(cfg.limit);
 
         Either::Left(
             async move {
-                let body = fut.await?;
+                let body = fut.await.map_err(|e| cfg.error(e, &req))?;
 
                 if encoding == UTF_8 {
                     Ok(str::from_utf8(body.as_ref())
@@ -260,26 +254,26 @@ impl PayloadConfig {
         self.mimetype = Some(mt);
         self
     }
+}
 
-    fn check_mimetype(&self, req: &HttpRequest) -> Result<(), Error> {
-        // check content-type
-        if let Some(ref mt) = self.mimetype {
-            match req.mime_type() {
-                Ok(Some(ref req_mt)) => {
-                    if mt != req_mt {
-                        return Err(ErrorBadRequest("Unexpected Content-Type"));
-                    }
-                }
-                Ok(None) => {
-                    return Err(ErrorBadRequest("Content-Type is expected"));
-                }
-                Err(err) => {
-                    return Err(err.into());
+fn check_mimetype(mimetype: &Option<Mime>, req: &HttpRequest) -> Result<(), Error> {
+    // check content-type
+    if let Some(ref mt) = mimetype {
+        match req.mime_type() {
+            Ok(Some(ref req_mt)) => {
+                if mt != req_mt {
+                    return Err(ErrorBadRequest("Unexpected Content-Type"));
                 }
             }
+            Ok(None) => {
+                return Err(ErrorBadRequest("Content-Type is expected"));
+            }
+            Err(err) => {
+                return Err(err.into());
+            }
         }
-        Ok(())
     }
+    Ok(())
 }
 
 impl Default for PayloadConfig {
@@ -291,6 +285,208 @@ impl Default for PayloadConfig {
     }
 }
 
+/// `Bytes`, `String` and `PayloadStream` extractors configuration.
+///
+/// `PayloadConfig` limit and mime type are used if `BytesConfig` is not
+/// configured.
+///
+/// ```rust
+/// use actix_web::{error, web, App, FromRequest, HttpResponse};
+///
+/// async fn index(body: web::Bytes) -> String {
+///     format!("Body {:?}!", body)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             .app_data(web::BytesConfig::new(4096).error_handler(|err, _req| {
+///                 error::InternalError::from_response(
+///                     err,
+///                     HttpResponse::PayloadTooLarge().finish(),
+///                 )
+///                 .into()
+///             }))
+///             .route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct BytesConfig {
+    limit: usize,
+    mimetype: Option<Mime>,
+    ehandler: Option<Arc<dyn Fn(PayloadError, &HttpRequest) -> Error + Send + Sync>>,
+}
+
+impl BytesConfig {
+    /// Create `BytesConfig` instance and set max size of payload.
+    pub fn new(limit: usize) -> Self {
+        BytesConfig::default().limit(limit)
+    }
+
+    /// Change max size of payload. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set required mime-type of the request. By default mime type is not
+    /// enforced.
+    pub fn mimetype(mut self, mt: Mime) -> Self {
+        self.mimetype = Some(mt);
+        self
+    }
+
+    /// Set custom error handler of payload errors
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(PayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> Self {
+        if let Some(cfg) = req.app_data::<BytesConfig>() {
+            cfg.clone()
+        } else if let Some(cfg) = req.app_data::<PayloadConfig>() {
+            BytesConfig {
+                limit: cfg.limit,
+                mimetype: cfg.mimetype.clone(),
+                ehandler: None,
+            }
+        } else {
+            BytesConfig::default()
+        }
+    }
+
+    fn error(&self, e: PayloadError, req: &HttpRequest) -> Error {
+        if let Some(ref ehandler) = self.ehandler {
+            (*ehandler)(e, req)
+        } else {
+            e.into()
+        }
+    }
+}
+
+impl Default for BytesConfig {
+    fn default() -> Self {
+        BytesConfig {
+            limit: 262_144,
+            mimetype: None,
+            ehandler: None,
+        }
+    }
+}
+
+/// Payload stream extractor with limit of aggregate payload size.
+///
+/// Payload is decoded according to `Content-Encoding` header. Stream fails
+/// with `PayloadError::Overflow` once more than the
+/// [**BytesConfig**](struct.BytesConfig.html) limit is received, requests
+/// with larger `Content-Length` are rejected by the extractor.
+///
+/// ## Example
+///
+/// ```rust
+/// use futures::StreamExt;
+/// use actix_web::{web, App, Error, HttpResponse};
+///
+/// async fn upload(mut body: web::PayloadStream) -> Result<HttpResponse, Error> {
+///     let mut size = 0;
+///     while let Some(chunk) = body.next().await {
+///         size += chunk?.len();
+///     }
+///     Ok(HttpResponse::Ok().body(format!("Uploaded {} bytes", size)))
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/upload")
+///             .app_data(web::BytesConfig::new(16 * 1024 * 1024))
+///             .route(web::post().to(upload))
+///     );
+/// }
+/// ```
+pub struct PayloadStream {
+    #[cfg(feature = "compress")]
+    stream: dev::Decompress<dev::Payload>,
+    #[cfg(not(feature = "compress"))]
+    stream: dev::Payload,
+    received: usize,
+    done: bool,
+    cfg: BytesConfig,
+    req: HttpRequest,
+}
+
+impl Stream for PayloadStream {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let e = match futures::ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                self.received += chunk.len();
+                if self.received <= self.cfg.limit {
+                    return Poll::Ready(Some(Ok(chunk)));
+                }
+                PayloadError::Overflow
+            }
+            Some(Err(e)) => e,
+            None => {
+                self.done = true;
+                return Poll::Ready(None);
+            }
+        };
+        self.done = true;
+        Poll::Ready(Some(Err(self.cfg.error(e, &self.req))))
+    }
+}
+
+impl FromRequest for PayloadStream {
+    type Config = BytesConfig;
+    type Error = Error;
+    type Future = Ready<Result<PayloadStream, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let cfg = BytesConfig::from_req(req);
+
+        if let Err(e) = check_mimetype(&cfg.mimetype, req) {
+            return err(e);
+        }
+
+        if let Some(len) = req.headers().get(&header::CONTENT_LENGTH) {
+            match len.to_str().ok().and_then(|s| s.parse::<usize>().ok()) {
+                Some(len) if len > cfg.limit => {
+                    return err(cfg.error(PayloadError::Overflow, req));
+                }
+                Some(_) => (),
+                None => return err(cfg.error(PayloadError::UnknownLength, req)),
+            }
+        }
+
+        #[cfg(feature = "compress")]
+        let stream = dev::Decompress::from_headers(payload.take(), req.headers());
+        #[cfg(not(feature = "compress"))]
+        let stream = payload.take();
+
+        ok(PayloadStream {
+            stream,
+            received: 0,
+            done: false,
+            cfg,
+            req: req.clone(),
+        })
+    }
+}
+
 /// Future that resolves to a complete http message body.
 ///
 /// Load http message body.
@@ -402,25 +598,27 @@ mod tests {
     use bytes::Bytes;
 
     use super::*;
-    use crate::http::header;
+    use crate::error::InternalError;
+    use crate::http::{header, StatusCode};
     use crate::test::TestRequest;
+    use crate::HttpResponse;
 
     #[actix_rt::test]
     async fn test_payload_config() {
         let req = TestRequest::default().to_http_request();
         let cfg = PayloadConfig::default().mimetype(mime::APPLICATION_JSON);
-        assert!(cfg.check_mimetype(&req).is_err());
+        assert!(check_mimetype(&cfg.mimetype, &req).is_err());
 
         let req = TestRequest::with_header(
             header::CONTENT_TYPE,
             "application/x-www-form-urlencoded",
         )
         .to_http_request();
-        assert!(cfg.check_mimetype(&req).is_err());
+        assert!(check_mimetype(&cfg.mimetype, &req).is_err());
 
         let req = TestRequest::with_header(header::CONTENT_TYPE, "application/json")
             .to_http_request();
-        assert!(cfg.check_mimetype(&req).is_ok());
+        assert!(check_mimetype(&cfg.mimetype, &req).is_ok());
     }
 
     #[actix_rt::test]
@@ -443,6 +641,71 @@ mod tests {
         assert_eq!(s, "hello=world");
     }
 
+    #[actix_rt::test]
+    async fn test_bytes_config() {
+        let (req, mut pl) = TestRequest::default()
+            .app_data(BytesConfig::new(5).error_handler(|err, _| {
+                InternalError::from_response(
+                    err,
+                    HttpResponse::PayloadTooLarge().finish(),
+                )
+                .into()
+            }))
+            .set_payload(Bytes::from_static(b"hello=world"))
+            .to_http_parts();
+        let res = Bytes::from_request(&req, &mut pl).await;
+        assert_eq!(
+            res.err()
+                .unwrap()
+                .as_response_error()
+                .error_response()
+                .status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .app_data(BytesConfig::new(5))
+            .set_payload(Bytes::from_static(b"hello=world"))
+            .to_http_parts();
+        assert!(String::from_request(&req, &mut pl).await.is_err());
+
+        // payload config is used without bytes config
+        let (req, mut pl) = TestRequest::default()
+            .app_data(PayloadConfig::new(5))
+            .set_payload(Bytes::from_static(b"hello=world"))
+            .to_http_parts();
+        assert!(Bytes::from_request(&req, &mut pl).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_payload_stream() {
+        let (req, mut pl) = TestRequest::default()
+            .set_payload(Bytes::from_static(b"hello=world"))
+            .to_http_parts();
+        let stream = PayloadStream::from_request(&req, &mut pl).await.unwrap();
+        let chunks = stream.collect::<Vec<_>>().await;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(
+            chunks[0].as_ref().unwrap(),
+            &Bytes::from_static(b"hello=world")
+        );
+
+        let (req, mut pl) = TestRequest::with_header(header::CONTENT_LENGTH, "11")
+            .app_data(BytesConfig::new(5))
+            .set_payload(Bytes::from_static(b"hello=world"))
+            .to_http_parts();
+        assert!(PayloadStream::from_request(&req, &mut pl).await.is_err());
+
+        // aggregate size is limited without content length
+        let (req, mut pl) = TestRequest::default()
+            .app_data(BytesConfig::new(5))
+            .set_payload(Bytes::from_static(b"hello=world"))
+            .to_http_parts();
+        let mut stream = PayloadStream::from_request(&req, &mut pl).await.unwrap();
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+    }
+
     #[actix_rt::test]
     async fn test_message_body() {
         let (req, mut pl) = TestRequest::with_header(header::CONTENT_LENGTH, "xxxx")
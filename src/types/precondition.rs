@@ -0,0 +1,185 @@
+//! Entity tag preconditions extractor
+use actix_http::error::Error;
+use futures::future::{ok, Ready};
+
+use crate::dev::Payload;
+use crate::error::PreconditionError;
+use crate::extract::FromRequest;
+use crate::http::header::{EntityTag, IfMatch, IfNoneMatch};
+use crate::request::HttpRequest;
+use crate::HttpMessage;
+
+/// Extract `If-Match` and `If-None-Match` preconditions of the request.
+///
+/// Preconditions protect writable resources from lost updates. Handler of
+/// `PUT` or `DELETE` request computes current entity tag of the resource
+/// and checks preconditions before the change is applied. Client that sent
+/// `If-Match` with an outdated entity tag receives *PRECONDITION FAILED*
+/// response.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::http::header::EntityTag;
+/// use actix_web::{web, App, Error, HttpResponse};
+///
+/// async fn update(body: String, pre: web::Preconditions) -> Result<HttpResponse, Error> {
+///     let current = EntityTag::from_data(b"current document");
+///     pre.check(Some(&current))?;
+///
+///     // store new document
+///     Ok(HttpResponse::NoContent()
+///         .set_header("etag", EntityTag::from_data(body.as_bytes()))
+///         .finish())
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         // reject updates without `If-Match` or `If-None-Match` header
+///         .app_data(web::PreconditionsConfig::default().required(true))
+///         .route("/document", web::put().to(update));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preconditions {
+    if_match: Option<IfMatch>,
+    if_none_match: Option<IfNoneMatch>,
+    required: bool,
+}
+
+impl Preconditions {
+    /// `If-Match` header of the request
+    pub fn if_match(&self) -> Option<&IfMatch> {
+        self.if_match.as_ref()
+    }
+
+    /// `If-None-Match` header of the request
+    pub fn if_none_match(&self) -> Option<&IfNoneMatch> {
+        self.if_none_match.as_ref()
+    }
+
+    /// Check preconditions against current entity tag of the resource,
+    /// `None` if resource does not exist.
+    ///
+    /// `If-None-Match: *` could be used by clients to create resource only
+    /// if it does not exist yet.
+    pub fn check(&self, current: Option<&EntityTag>) -> Result<(), PreconditionError> {
+        if self.required && self.if_match.is_none() && self.if_none_match.is_none() {
+            return Err(PreconditionError::Required);
+        }
+
+        if let Some(ref if_match) = self.if_match {
+            if !if_match.matches(current) {
+                return Err(PreconditionError::Failed);
+            }
+        }
+        if let Some(ref if_none_match) = self.if_none_match {
+            if if_none_match.matches(current) {
+                return Err(PreconditionError::Failed);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromRequest for Preconditions {
+    type Config = PreconditionsConfig;
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let required = req
+            .app_data::<Self::Config>()
+            .map_or(false, |cfg| cfg.required);
+
+        ok(Preconditions {
+            if_match: req.get_header::<IfMatch>(),
+            if_none_match: req.get_header::<IfNoneMatch>(),
+            required,
+        })
+    }
+}
+
+/// Preconditions extractor configuration
+#[derive(Debug, Clone, Default)]
+pub struct PreconditionsConfig {
+    required: bool,
+}
+
+impl PreconditionsConfig {
+    /// Require precondition headers.
+    ///
+    /// Requests without `If-Match` and `If-None-Match` headers are rejected
+    /// with *PRECONDITION REQUIRED* response. By default preconditions are
+    /// optional.
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header;
+    use crate::test::TestRequest;
+
+    async fn extract(req: TestRequest) -> Preconditions {
+        let (req, mut pl) = req.to_http_parts();
+        Preconditions::from_request(&req, &mut pl).await.unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_if_match() {
+        let current = EntityTag::from_data(b"data");
+
+        let pre = extract(TestRequest::default()).await;
+        assert_eq!(pre.check(Some(&current)), Ok(()));
+        assert_eq!(pre.check(None), Ok(()));
+
+        let req = TestRequest::default().header(header::IF_MATCH, current.to_string());
+        let pre = extract(req).await;
+        assert_eq!(pre.check(Some(&current)), Ok(()));
+        assert_eq!(
+            pre.check(Some(&EntityTag::from_data(b"other"))),
+            Err(PreconditionError::Failed)
+        );
+        assert_eq!(pre.check(None), Err(PreconditionError::Failed));
+
+        // weak tags never match
+        let req = TestRequest::default()
+            .header(header::IF_MATCH, format!("W/{}", current.to_string()));
+        let pre = extract(req).await;
+        assert_eq!(pre.check(Some(&current)), Err(PreconditionError::Failed));
+
+        let req = TestRequest::default().header(header::IF_MATCH, "*");
+        let pre = extract(req).await;
+        assert_eq!(pre.check(Some(&current)), Ok(()));
+        assert_eq!(pre.check(None), Err(PreconditionError::Failed));
+    }
+
+    #[actix_rt::test]
+    async fn test_if_none_match() {
+        let current = EntityTag::from_data(b"data");
+
+        let req = TestRequest::default().header(header::IF_NONE_MATCH, "*");
+        let pre = extract(req).await;
+        assert_eq!(pre.check(None), Ok(()));
+        assert_eq!(pre.check(Some(&current)), Err(PreconditionError::Failed));
+    }
+
+    #[actix_rt::test]
+    async fn test_required() {
+        let req = TestRequest::default()
+            .app_data(PreconditionsConfig::default().required(true));
+        let pre = extract(req).await;
+        assert_eq!(pre.check(None), Err(PreconditionError::Required));
+
+        let req = TestRequest::default()
+            .app_data(PreconditionsConfig::default().required(true))
+            .header(header::IF_MATCH, "*");
+        let pre = extract(req).await;
+        assert_eq!(pre.check(None), Err(PreconditionError::Failed));
+    }
+}
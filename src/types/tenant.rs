@@ -0,0 +1,242 @@
+//! Resolved tenant extractors
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{fmt, ops};
+
+use actix_http::error::{Error, ErrorInternalServerError};
+use actix_http::Extensions;
+use futures::future::{err, ok, Ready};
+
+use crate::dev::Payload;
+use crate::extract::FromRequest;
+use crate::http::header::HeaderName;
+use crate::request::HttpRequest;
+
+/// Source the request tenant is resolved from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TenantSource {
+    /// Host of the request without port, i.e. `acme.example.com`
+    Host,
+    /// Value of the request header
+    Header(HeaderName),
+    /// Leading path segment, i.e. `/acme/orders`
+    PathPrefix,
+}
+
+/// Extract tenant resolved by
+/// [**middleware::Tenants**](../middleware/struct.Tenants.html).
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::middleware::{TenantConfig, Tenants};
+/// use actix_web::{web, App};
+///
+/// async fn index(tenant: web::Tenant) -> String {
+///     if tenant.has_feature("beta") {
+///         format!("Welcome to beta, {}!", tenant.id())
+///     } else {
+///         format!("Welcome, {}!", tenant.id())
+///     }
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(
+///             Tenants::new(web::TenantSource::Host)
+///                 .tenant("acme.example.com", TenantConfig::new().feature("beta")),
+///         )
+///         .route("/", web::get().to(index));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Tenant(Rc<Inner>);
+
+struct Inner {
+    id: String,
+    data: Extensions,
+    features: HashSet<String>,
+    rate_limit: Option<u32>,
+    window: Cell<(Instant, u32)>,
+}
+
+impl Tenant {
+    pub(crate) fn new(
+        id: String,
+        data: Extensions,
+        features: HashSet<String>,
+        rate_limit: Option<u32>,
+    ) -> Self {
+        Tenant(Rc::new(Inner {
+            id,
+            data,
+            features,
+            rate_limit,
+            window: Cell::new((Instant::now(), 0)),
+        }))
+    }
+
+    /// Tenant id, i.e. host name or header value of the tenant.
+    pub fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    /// Feature flag is enabled for the tenant.
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.0.features.contains(name)
+    }
+
+    /// Get data of the tenant, see [`TenantData`](struct.TenantData.html).
+    pub fn data<T: 'static>(&self) -> Option<TenantData<T>> {
+        self.0.data.get::<TenantData<T>>().cloned()
+    }
+
+    /// Count request against the rate limit of the current second, returns
+    /// false if the limit is exceeded.
+    pub(crate) fn acquire(&self) -> bool {
+        let limit = match self.0.rate_limit {
+            Some(limit) => limit,
+            None => return true,
+        };
+
+        let now = Instant::now();
+        let (start, count) = self.0.window.get();
+        if now - start >= Duration::from_secs(1) {
+            self.0.window.set((now, 1));
+            true
+        } else if count < limit {
+            self.0.window.set((start, count + 1));
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl fmt::Debug for Tenant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Tenant").field(&self.0.id).finish()
+    }
+}
+
+impl FromRequest for Tenant {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Tenant, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(tenant) = req.extensions().get::<Tenant>() {
+            ok(tenant.clone())
+        } else {
+            log::debug!(
+                "Failed to construct Tenant extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            err(ErrorInternalServerError(
+                "Tenant resolution is not configured, to configure use \
+                 App::wrap(middleware::Tenants::new(..))",
+            ))
+        }
+    }
+}
+
+/// Data container of the request tenant.
+///
+/// Data is registered per tenant with `TenantConfig::data()`, handlers
+/// extract data of the tenant resolved by
+/// [**middleware::Tenants**](../middleware/struct.Tenants.html), like
+/// connection pools or settings of the tenant.
+///
+/// ## Example
+///
+/// ```rust
+/// use actix_web::middleware::{TenantConfig, Tenants};
+/// use actix_web::{web, App};
+///
+/// struct Database(&'static str);
+///
+/// async fn index(db: web::TenantData<Database>) -> String {
+///     format!("Connected to {}", db.0)
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(
+///             Tenants::new(web::TenantSource::Header("x-tenant".parse().unwrap()))
+///                 .tenant("acme", TenantConfig::new().data(Database("acme-db")))
+///                 .tenant("globex", TenantConfig::new().data(Database("globex-db"))),
+///         )
+///         .route("/", web::get().to(index));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TenantData<T>(Arc<T>);
+
+impl<T> TenantData<T> {
+    /// Create new `TenantData` instance.
+    pub fn new(data: T) -> Self {
+        TenantData(Arc::new(data))
+    }
+
+    /// Get reference to inner data
+    pub fn get_ref(&self) -> &T {
+        self.0.as_ref()
+    }
+
+    /// Convert to the internal Arc<T>
+    pub fn into_inner(self) -> Arc<T> {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for TenantData<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0.as_ref()
+    }
+}
+
+impl<T> Clone for TenantData<T> {
+    fn clone(&self) -> TenantData<T> {
+        TenantData(self.0.clone())
+    }
+}
+
+impl<T> From<Arc<T>> for TenantData<T> {
+    fn from(arc: Arc<T>) -> Self {
+        TenantData(arc)
+    }
+}
+
+impl<T: 'static> FromRequest for TenantData<T> {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let data = req
+            .extensions()
+            .get::<Tenant>()
+            .and_then(|tenant| tenant.data::<T>());
+        if let Some(data) = data {
+            ok(data)
+        } else {
+            log::debug!(
+                "Failed to construct TenantData extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            err(ErrorInternalServerError(
+                "Tenant data is not configured, to configure use \
+                 TenantConfig::data()",
+            ))
+        }
+    }
+}
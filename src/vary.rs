@@ -0,0 +1,146 @@
+use actix_http::http::header::{HeaderName, HeaderValue, VARY};
+use actix_http::{Extensions, Response, ResponseBuilder, ResponseHead};
+
+/// Request headers response depends on.
+struct VaryHeaders(Vec<HeaderName>);
+
+/// Helper trait that allows to declare request headers response depends on.
+///
+/// Declared headers are merged with `Vary` headers of the response, response
+/// is sent with single deduplicated `Vary` header.
+///
+/// ```rust
+/// use actix_web::dev::VaryOn;
+/// use actix_web::http::header;
+/// use actix_web::HttpResponse;
+///
+/// async fn index() -> HttpResponse {
+///     HttpResponse::Ok()
+///         .vary_on(header::ACCEPT_LANGUAGE)
+///         .vary_on(header::AUTHORIZATION)
+///         .body("hello")
+/// }
+/// ```
+pub trait VaryOn {
+    /// Declare request header response depends on
+    fn vary_on(&mut self, header: HeaderName) -> &mut Self;
+}
+
+impl VaryOn for Extensions {
+    fn vary_on(&mut self, header: HeaderName) -> &mut Self {
+        if let Some(vary) = self.get_mut::<VaryHeaders>() {
+            if !vary.0.contains(&header) {
+                vary.0.push(header);
+            }
+        } else {
+            self.insert(VaryHeaders(vec![header]));
+        }
+        self
+    }
+}
+
+impl VaryOn for ResponseBuilder {
+    fn vary_on(&mut self, header: HeaderName) -> &mut Self {
+        self.extensions_mut().vary_on(header);
+        self
+    }
+}
+
+impl<B> VaryOn for Response<B> {
+    fn vary_on(&mut self, header: HeaderName) -> &mut Self {
+        self.extensions_mut().vary_on(header);
+        self
+    }
+}
+
+/// Merge declared and existing `Vary` headers.
+pub(crate) fn merge(head: &mut ResponseHead) {
+    let declared = head.extensions_mut().remove::<VaryHeaders>();
+    if declared.is_none() && head.headers().get_all(&VARY).nth(1).is_none() {
+        return;
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    let mut any = false;
+    let existing = head
+        .headers()
+        .get_all(&VARY)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(|name| name.trim().to_ascii_lowercase());
+    let declared = declared
+        .into_iter()
+        .flat_map(|vary| vary.0)
+        .map(|name| name.as_str().to_owned());
+
+    for name in existing.chain(declared) {
+        if name == "*" {
+            any = true;
+        } else if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    let value = if any { "*".to_owned() } else { names.join(", ") };
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        head.headers_mut().insert(VARY, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::{ACCEPT_ENCODING, ACCEPT_LANGUAGE, AUTHORIZATION};
+    use crate::test::{call_service, init_service, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn test_vary_on() {
+        let mut srv = init_service(
+            App::new()
+                .route(
+                    "/",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .header(VARY, "Accept-Encoding, Origin")
+                            .vary_on(ACCEPT_LANGUAGE)
+                            .vary_on(ACCEPT_ENCODING)
+                            .finish()
+                    }),
+                )
+                .route(
+                    "/any",
+                    web::get().to(|| {
+                        let mut res = HttpResponse::Ok().header(VARY, "*").finish();
+                        res.vary_on(AUTHORIZATION);
+                        res
+                    }),
+                )
+                .route(
+                    "/multi",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .header(VARY, "origin")
+                            .header(VARY, "cookie")
+                            .finish()
+                    }),
+                ),
+        )
+        .await;
+
+        let resp = call_service(&mut srv, TestRequest::default().to_request()).await;
+        assert_eq!(
+            resp.headers().get(VARY).unwrap(),
+            "accept-encoding, origin, accept-language"
+        );
+
+        let req = TestRequest::with_uri("/any").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get(VARY).unwrap(), "*");
+
+        let req = TestRequest::with_uri("/multi").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.headers().get_all(VARY).count(), 1);
+        assert_eq!(resp.headers().get(VARY).unwrap(), "cookie, origin");
+    }
+}
@@ -1,6 +1,7 @@
 //! Essentials helper functions and types for application registration.
 use actix_http::http::Method;
 use actix_router::IntoPattern;
+use awc::TypedRoute;
 use futures::Future;
 
 pub use actix_http::Response as HttpResponse;
@@ -15,11 +16,23 @@ use crate::responder::Responder;
 use crate::route::Route;
 use crate::scope::Scope;
 use crate::service::WebService;
+use crate::split::Split;
 
+#[cfg(feature = "admin")]
+pub use crate::admin::{Admin, Authorizer, BearerToken, Maintenance};
+pub use crate::batch::Batch;
+pub use crate::broadcast::{ServerBroadcast, Subscription};
 pub use crate::config::ServiceConfig;
 pub use crate::data::Data;
+pub use crate::info::PeerCertificate;
+#[cfg(unix)]
+pub use crate::info::PeerCredentials;
+pub use crate::local::LocalClient;
+pub use crate::redirect::Redirect;
 pub use crate::request::HttpRequest;
+pub use crate::split::SplitVariant;
 pub use crate::types::*;
+pub use crate::worker::WorkerIndex;
 
 /// Create resource for a specific path.
 ///
@@ -55,6 +68,34 @@ pub fn resource<T: IntoPattern>(path: T) -> Resource {
     Resource::new(path)
 }
 
+/// Create resource of a typed route.
+///
+/// Resource is registered with path pattern and name of the route, so
+/// `HttpRequest::url_for()` and clients of the route build the same urls.
+/// Parameters of the route are extracted with `web::Path<R::Params>`.
+///
+/// ```rust
+/// use actix_web::client::TypedRoute;
+/// use actix_web::{web, App, HttpResponse};
+///
+/// struct GetUser;
+///
+/// impl TypedRoute for GetUser {
+///     const NAME: &'static str = "get_user";
+///     const PATH: &'static str = "/users/{id}";
+///     type Params = u32;
+/// }
+///
+/// async fn get_user(id: web::Path<u32>) -> HttpResponse {
+///     HttpResponse::Ok().body(format!("user {}", id))
+/// }
+///
+/// let app = App::new().service(web::resource_for::<GetUser>().to(get_user));
+/// ```
+pub fn resource_for<R: TypedRoute>() -> Resource {
+    Resource::new(R::PATH).name(R::NAME)
+}
+
 /// Configure scope for common root path.
 ///
 /// Scopes collect multiple paths under a common path prefix.
@@ -272,13 +313,87 @@ pub fn service<T: IntoPattern>(path: T) -> WebService {
     WebService::new(path)
 }
 
+/// Create service that redirects requests of the `from` path to the target,
+/// see [`Redirect`](struct.Redirect.html).
+///
+/// ```rust
+/// use actix_web::{web, App};
+///
+/// let app = App::new().service(web::redirect("/docs", "/docs/index.html"));
+/// ```
+pub fn redirect<F: Into<String>, T: Into<String>>(from: F, to: T) -> Redirect {
+    Redirect::new(from, to)
+}
+
+/// Create service that routes requests to one of the weighted variants.
+///
+/// ```rust
+/// use actix_web::{web, App, HttpResponse};
+///
+/// let app = App::new().service(
+///     web::service("/").finish(
+///         web::split()
+///             .variant("a", 50, web::to(|| HttpResponse::Ok().body("a")))
+///             .variant("b", 50, web::to(|| HttpResponse::Ok().body("b")))
+///             .header("x-user-id"),
+///     ),
+/// );
+/// ```
+pub fn split() -> Split {
+    Split::new()
+}
+
+/// Create batch request service.
+///
+/// Service accepts json array of sub-requests, dispatches them through the
+/// application and responds with json array of sub-responses.
+///
+/// ```rust
+/// use actix_web::{web, App};
+///
+/// let app = App::new().service(
+///     web::service("/batch").finish(web::batch().concurrency(4).max_requests(16)),
+/// );
+/// ```
+pub fn batch() -> Batch {
+    Batch::new()
+}
+
+/// Create admin scope for the path, see [`Admin`](struct.Admin.html) for
+/// the list of endpoints.
+///
+/// ```rust
+/// use actix_web::web::{self, Admin, BearerToken};
+/// use actix_web::App;
+///
+/// let admin = Admin::new(BearerToken::new("secret"));
+/// let app = App::new().service(web::admin("/admin", &admin));
+/// ```
+#[cfg(feature = "admin")]
+pub fn admin(
+    path: &str,
+    admin: &Admin,
+) -> Scope<
+    impl actix_service::ServiceFactory<
+        Config = (),
+        Request = crate::dev::ServiceRequest,
+        Response = crate::dev::ServiceResponse,
+        Error = crate::Error,
+        InitError = (),
+    >,
+> {
+    admin.scope(path)
+}
+
 /// Execute blocking function on a thread pool, returns future that resolves
 /// to result of the function execution.
+///
+/// Jobs are counted in `dev::Load` signals of the worker.
 pub async fn block<F, I, E>(f: F) -> Result<I, BlockingError<E>>
 where
     F: FnOnce() -> Result<I, E> + Send + 'static,
     I: Send + 'static,
     E: Send + std::fmt::Debug + 'static,
 {
-    actix_threadpool::run(f).await
+    actix_http::run_blocking(f).await
 }
@@ -0,0 +1,298 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{fmt, ops};
+
+use actix_http::error::{Error, ErrorInternalServerError};
+use futures::future::{err, ok, Ready};
+
+use crate::dev::Payload;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+
+thread_local! {
+    static WORKER_INDEX: Cell<Option<usize>> = Cell::new(None);
+}
+
+/// Worker threads CPU affinity policy.
+///
+/// Affinity is only supported on Linux, on other platforms workers are not
+/// pinned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerAffinity {
+    /// Pin every worker to its own core, worker `n` is pinned to `n`-th core
+    /// available to the process.
+    Core,
+    /// Pin workers to listed cores, worker `n` is pinned to
+    /// `cores[n % cores.len()]`.
+    Cores(Vec<usize>),
+    /// Spread workers between NUMA nodes, worker may run on any core of its
+    /// node.
+    NumaNode,
+}
+
+impl WorkerAffinity {
+    /// Cores worker with the `index` has to be pinned to.
+    fn cores(&self, index: usize) -> Vec<usize> {
+        match self {
+            WorkerAffinity::Core => {
+                let cores = sys::available_cores();
+                if cores.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![cores[index % cores.len()]]
+                }
+            }
+            WorkerAffinity::Cores(ref cores) => {
+                if cores.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![cores[index % cores.len()]]
+                }
+            }
+            WorkerAffinity::NumaNode => {
+                let nodes = sys::numa_nodes();
+                if nodes.is_empty() {
+                    Vec::new()
+                } else {
+                    nodes[index % nodes.len()].clone()
+                }
+            }
+        }
+    }
+}
+
+/// Per server worker registry.
+#[derive(Default)]
+pub(crate) struct Workers {
+    started: AtomicUsize,
+    affinity: Option<WorkerAffinity>,
+}
+
+impl Workers {
+    pub(crate) fn set_affinity(&mut self, affinity: WorkerAffinity) {
+        self.affinity = Some(affinity);
+    }
+
+    /// Register current thread as a worker.
+    ///
+    /// Service factories are created once per listener on every worker
+    /// thread, worker gets index and is pinned on the first call only.
+    pub(crate) fn start(&self) {
+        WORKER_INDEX.with(|idx| {
+            if idx.get().is_none() {
+                let index = self.started.fetch_add(1, Ordering::SeqCst);
+                idx.set(Some(index));
+
+                if let Some(ref affinity) = self.affinity {
+                    let cores = affinity.cores(index);
+                    if let Err(e) = sys::set_affinity(&cores) {
+                        log::warn!("Can not pin worker {} to {:?}: {}", index, cores, e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Index of the worker that handles request.
+///
+/// Workers are numbered from zero in order they are started, index is
+/// stable for the worker lifetime and can be used for per-core sharding of
+/// application state.
+///
+/// ```rust
+/// use actix_web::web;
+///
+/// async fn index(worker: web::WorkerIndex) -> String {
+///     format!("worker #{}", worker)
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WorkerIndex(usize);
+
+impl WorkerIndex {
+    /// Get index of the current worker, `None` if current thread is not a
+    /// http server worker.
+    pub fn current() -> Option<WorkerIndex> {
+        WORKER_INDEX.with(|idx| idx.get()).map(WorkerIndex)
+    }
+
+    /// Deconstruct to the inner value
+    pub fn into_inner(self) -> usize {
+        self.0
+    }
+}
+
+impl ops::Deref for WorkerIndex {
+    type Target = usize;
+
+    fn deref(&self) -> &usize {
+        &self.0
+    }
+}
+
+impl fmt::Display for WorkerIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromRequest for WorkerIndex {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<WorkerIndex, Error>>;
+
+    #[inline]
+    fn from_request(_: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match WorkerIndex::current() {
+            Some(idx) => ok(idx),
+            None => {
+                log::debug!("Request is not handled by a http server worker");
+                err(ErrorInternalServerError(
+                    "Worker index is only available in http server workers",
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::{fs, io, mem};
+
+    const CPU_SETSIZE: usize = libc::CPU_SETSIZE as usize;
+
+    pub(super) fn available_cores() -> Vec<usize> {
+        unsafe {
+            let mut set: libc::cpu_set_t = mem::zeroed();
+            if libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut set)
+                != 0
+            {
+                return Vec::new();
+            }
+            (0..CPU_SETSIZE)
+                .filter(|cpu| libc::CPU_ISSET(*cpu, &set))
+                .collect()
+        }
+    }
+
+    pub(super) fn numa_nodes() -> Vec<Vec<usize>> {
+        let mut nodes = Vec::new();
+        for node in 0.. {
+            let path = format!("/sys/devices/system/node/node{}/cpulist", node);
+            match fs::read_to_string(path) {
+                Ok(list) => nodes.push(super::parse_cpu_list(&list)),
+                Err(_) => break,
+            }
+        }
+        nodes
+    }
+
+    pub(super) fn set_affinity(cores: &[usize]) -> io::Result<()> {
+        if cores.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::Other, "No cores available"));
+        }
+        if let Some(core) = cores.iter().find(|core| **core >= CPU_SETSIZE) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Core {} is out of cpu set range", core),
+            ));
+        }
+
+        unsafe {
+            let mut set: libc::cpu_set_t = mem::zeroed();
+            for core in cores {
+                libc::CPU_SET(*core, &mut set);
+            }
+            if libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::io;
+
+    pub(super) fn available_cores() -> Vec<usize> {
+        Vec::new()
+    }
+
+    pub(super) fn numa_nodes() -> Vec<Vec<usize>> {
+        Vec::new()
+    }
+
+    pub(super) fn set_affinity(_: &[usize]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Worker affinity is not supported on this platform",
+        ))
+    }
+}
+
+/// Parse cpu list in the `0-3,8,10-11` format.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|p| !p.is_empty()) {
+        let mut range = part.splitn(2, '-');
+        let start = range.next().and_then(|s| s.trim().parse::<usize>().ok());
+        let end = range.next().and_then(|s| s.trim().parse::<usize>().ok());
+        match (start, end) {
+            (Some(start), Some(end)) => cpus.extend(start..=end),
+            (Some(cpu), None) => cpus.push(cpu),
+            _ => (),
+        }
+    }
+    cpus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list(""), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_affinity_cores() {
+        let affinity = WorkerAffinity::Cores(vec![2, 4]);
+        assert_eq!(affinity.cores(0), vec![2]);
+        assert_eq!(affinity.cores(3), vec![4]);
+        assert!(WorkerAffinity::Cores(Vec::new()).cores(1).is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_affinity_out_of_range() {
+        let err = sys::set_affinity(&[0, 1 << 20]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_worker_index() {
+        std::thread::spawn(|| {
+            let (req, mut pl) = TestRequest::default().to_http_parts();
+            let res =
+                futures::executor::block_on(WorkerIndex::from_request(&req, &mut pl));
+            assert!(res.is_err());
+
+            let workers = Workers::default();
+            workers.start();
+            workers.start();
+            assert_eq!(WorkerIndex::current(), Some(WorkerIndex(0)));
+
+            let res =
+                futures::executor::block_on(WorkerIndex::from_request(&req, &mut pl));
+            assert_eq!(res.unwrap().into_inner(), 0);
+        })
+        .join()
+        .unwrap();
+    }
+}
@@ -5,7 +5,7 @@ use std::{net, thread, time::Duration};
 #[cfg(feature = "openssl")]
 use open_ssl::ssl::SslAcceptorBuilder;
 
-use actix_web::{web, App, HttpResponse, HttpServer};
+use actix_web::{web, App, HttpResponse, HttpServer, ServerConfigFile, WorkerAffinity};
 
 fn unused_addr() -> net::SocketAddr {
     let addr: net::SocketAddr = "127.0.0.1:0".parse().unwrap();
@@ -73,6 +73,322 @@ async fn test_start() {
     let _ = sys.stop();
 }
 
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_worker_affinity() {
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new("test");
+
+        let srv = HttpServer::new(|| {
+            App::new().route(
+                "/",
+                web::to(|worker: web::WorkerIndex| async move { worker.to_string() }),
+            )
+        })
+        .workers(1)
+        .worker_affinity(WorkerAffinity::Core)
+        .disable_signals()
+        .bind(format!("{}", addr))
+        .unwrap()
+        .run();
+
+        let _ = tx.send((srv, actix_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let mut response = awc::Client::new()
+        .get(format!("http://{}", addr))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.body().await.unwrap(), "0");
+
+    // stop
+    let _ = srv.stop(false);
+
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_from_config() {
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let mut config = ServerConfigFile::default();
+    config.bind = vec![addr.to_string()];
+    config.workers = Some(1);
+    config.keep_alive = Some(0);
+    config.limits.payload = Some(4);
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new("test");
+
+        let limits = config.limits();
+        let srv = HttpServer::from_config(
+            move || {
+                App::new().configure(limits.clone()).route(
+                    "/",
+                    web::post().to(|body: web::Bytes| HttpResponse::Ok().body(body)),
+                )
+            },
+            &config,
+        )
+        .unwrap()
+        .disable_signals()
+        .run();
+
+        let _ = tx.send((srv, actix_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let client = awc::Client::new();
+    let response = client
+        .post(format!("http://{}", addr))
+        .send_body("test")
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get("connection").unwrap(), "close");
+
+    let response = client
+        .post(format!("http://{}", addr))
+        .send_body("too long")
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 413);
+
+    // stop
+    let _ = srv.stop(false);
+
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
+
+#[actix_rt::test]
+async fn test_try_bind_all() {
+    use actix_web::dev::BindPolicy;
+
+    let busy = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let busy_addr = busy.local_addr().unwrap();
+    let addr = unused_addr();
+    let addrs = vec![
+        busy_addr.to_string(),
+        addr.to_string(),
+        "invalid".to_owned(),
+    ];
+
+    let report = HttpServer::new(|| App::new())
+        .try_bind_all(&addrs, BindPolicy::All)
+        .err()
+        .unwrap();
+    assert_eq!(report.bound(), &[addr]);
+    assert_eq!(report.failed().len(), 2);
+    assert!(report.failed()[0].is_addr_in_use());
+    assert_eq!(report.failed()[0].addr(), Some(busy_addr));
+    assert_eq!(report.failed()[1].target(), "invalid");
+    assert_eq!(report.failed()[1].addr(), None);
+
+    let (srv, report) = HttpServer::new(|| App::new())
+        .try_bind_all(&addrs, BindPolicy::Any)
+        .unwrap();
+    assert_eq!(srv.addrs(), vec![addr]);
+    assert!(!report.is_complete());
+
+    let err: std::io::Error = HttpServer::new(|| App::new())
+        .try_bind_all(&addrs[..1], BindPolicy::Any)
+        .err()
+        .unwrap()
+        .into();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+
+    // assigned port is reported for port 0
+    let (srv, report) = HttpServer::new(|| App::new())
+        .try_bind_all(&["127.0.0.1:0"], BindPolicy::All)
+        .unwrap();
+    assert_ne!(report.bound()[0].port(), 0);
+    assert_eq!(srv.addrs(), report.bound());
+}
+
+#[actix_rt::test]
+async fn test_bind_dual_stack() {
+    let port = unused_addr().port();
+
+    let (srv, report) = HttpServer::new(|| App::new())
+        .bind_dual_stack("*", port)
+        .unwrap();
+    assert!(report.has_ipv4());
+    assert!(report
+        .bound()
+        .contains(&(net::Ipv4Addr::UNSPECIFIED, port).into()));
+    // ipv6 may be unavailable, but it never conflicts with ipv4 listener
+    assert!(report
+        .failed()
+        .iter()
+        .all(|failure| !failure.is_addr_in_use()));
+    assert_eq!(srv.addrs().len(), report.bound().len());
+
+    let (_, report) = HttpServer::new(|| App::new())
+        .bind_dual_stack("localhost", unused_addr().port())
+        .unwrap();
+    assert!(report.has_ipv4());
+
+    let report = HttpServer::new(|| App::new())
+        .bind_dual_stack("*", port)
+        .err()
+        .unwrap();
+    assert!(report.bound().is_empty());
+    assert!(report.failed()[0].is_addr_in_use());
+    drop(srv);
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_expect() {
+    use std::io::{Read, Write};
+
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new("test");
+
+        let srv = HttpServer::new(|| {
+            App::new().route(
+                "/",
+                web::post().to(|body: String| HttpResponse::Ok().body(body)),
+            )
+        })
+        .workers(1)
+        .expect(|head| {
+            if head.headers.contains_key("x-allowed") {
+                Ok(())
+            } else {
+                Err(actix_web::error::ErrorExpectationFailed("not allowed"))
+            }
+        })
+        .disable_signals()
+        .bind(format!("{}", addr))
+        .unwrap()
+        .run();
+
+        let _ = tx.send((srv, actix_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let mut stream = net::TcpStream::connect(addr).unwrap();
+    let _ = stream.write_all(
+        b"POST / HTTP/1.1\r\nexpect: 100-continue\r\ncontent-length: 4\r\n\
+          connection: close\r\n\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 417 Expectation Failed\r\n"));
+
+    let mut stream = net::TcpStream::connect(addr).unwrap();
+    let _ = stream.write_all(
+        b"POST / HTTP/1.1\r\nexpect: 100-continue\r\ncontent-length: 4\r\n\
+          x-allowed: 1\r\nconnection: close\r\n\r\n",
+    );
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert_eq!(&data[..n], b"HTTP/1.1 100 Continue\r\n\r\n");
+    let _ = stream.write_all(b"data");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(data.ends_with("data"));
+
+    let _ = srv.stop(false);
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_on_connect_ext() {
+    use std::io::{Read, Write};
+
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new("test");
+
+        let srv = HttpServer::new(|| {
+            App::new().route(
+                "/",
+                web::get().to(|req: actix_web::HttpRequest| {
+                    HttpResponse::Ok().body(format!("{:?}", req.peer_addr()))
+                }),
+            )
+        })
+        .workers(1)
+        .proxy_protocol(true)
+        .on_connect_ext(|addr| match addr {
+            Some(addr) if addr.ip().is_loopback() => Err("loopback peer"),
+            _ => Ok(()),
+        })
+        .disable_signals()
+        .bind(format!("{}", addr))
+        .unwrap()
+        .run();
+
+        let _ = tx.send((srv, actix_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    // callback is called with client address of the header
+    let mut stream = net::TcpStream::connect(addr).unwrap();
+    let _ = stream.write_all(
+        b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n\
+          GET / HTTP/1.1\r\nconnection: close\r\n\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(data.ends_with("Some(192.168.0.1:56324)"));
+
+    let mut stream = net::TcpStream::connect(addr).unwrap();
+    let _ = stream.write_all(
+        b"PROXY TCP4 127.0.0.1 192.168.0.11 56324 443\r\n\
+          GET / HTTP/1.1\r\nconnection: close\r\n\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.is_empty());
+
+    let _ = srv.stop(false);
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
+
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+#[actix_rt::test]
+async fn test_from_config_tls() {
+    let mut config = ServerConfigFile::default();
+    config.tls = Some(actix_web::dev::TlsSection {
+        bind: vec![unused_addr().to_string()],
+        cert: "tests/cert.pem".into(),
+        key: "tests/key.pem".into(),
+    });
+
+    let srv = HttpServer::from_config(|| App::new(), &config).unwrap();
+    assert_eq!(srv.addrs_with_scheme()[0].1, "https");
+
+    config.tls.as_mut().unwrap().key = "tests/missing.pem".into();
+    assert!(HttpServer::from_config(|| App::new(), &config).is_err());
+}
+
 #[cfg(feature = "openssl")]
 fn ssl_acceptor() -> std::io::Result<SslAcceptorBuilder> {
     use open_ssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
@@ -144,3 +460,277 @@ async fn test_start_ssl() {
     thread::sleep(Duration::from_millis(100));
     let _ = sys.stop();
 }
+
+#[actix_rt::test]
+#[cfg(feature = "openssl")]
+async fn test_ssl_peer_certificate() {
+    use actix_web::web::PeerCertificate;
+    use open_ssl::ssl::{SslConnector, SslFiletype, SslMethod, SslVerifyMode};
+    use open_ssl::x509::X509;
+
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new("test");
+        let mut builder = ssl_acceptor().unwrap();
+        // request client certificate, accept any
+        builder.set_verify_callback(SslVerifyMode::PEER, |_, _| true);
+
+        let srv = HttpServer::new(|| {
+            App::new().route(
+                "/",
+                web::get().to(|cert: Option<PeerCertificate>| {
+                    let body = cert.map(|cert| cert.der().to_vec()).unwrap_or_default();
+                    HttpResponse::Ok().body(body)
+                }),
+            )
+        })
+        .workers(1)
+        .shutdown_timeout(1)
+        .system_exit()
+        .disable_signals()
+        .bind_openssl(format!("{}", addr), builder)
+        .unwrap()
+        .run();
+
+        let _ = tx.send((srv, actix_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+    builder.set_verify(SslVerifyMode::NONE);
+    builder
+        .set_certificate_file("tests/cert.pem", SslFiletype::PEM)
+        .unwrap();
+    builder
+        .set_private_key_file("tests/key.pem", SslFiletype::PEM)
+        .unwrap();
+    let _ = builder
+        .set_alpn_protos(b"\x08http/1.1")
+        .map_err(|e| log::error!("Can not set alpn protocol: {:?}", e));
+
+    let client = awc::Client::build()
+        .connector(
+            awc::Connector::new()
+                .ssl(builder.build())
+                .timeout(Duration::from_millis(500))
+                .finish(),
+        )
+        .finish();
+
+    let mut response = client
+        .get(format!("https://{}", addr))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body = response.body().await.unwrap();
+    let cert = X509::from_pem(&std::fs::read("tests/cert.pem").unwrap()).unwrap();
+    assert_eq!(&body[..], &cert.to_der().unwrap()[..]);
+
+    // stop
+    let _ = srv.stop(false);
+
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
+
+#[actix_rt::test]
+#[cfg(feature = "openssl")]
+async fn test_ssl_alpn_select() {
+    use actix_web::dev::Protocol;
+    use open_ssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+    use std::io::{Read, Write};
+
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new("test");
+        let builder = ssl_acceptor().unwrap();
+
+        let srv = HttpServer::new(|| {
+            App::new().route("/", web::to(|| HttpResponse::Ok().body("test")))
+        })
+        .workers(1)
+        .shutdown_timeout(1)
+        .system_exit()
+        .disable_signals()
+        .alpn_select(|hello| match hello.server_name() {
+            Some("h1.localhost") if hello.offers(Protocol::Http1) => {
+                Some(Protocol::Http1)
+            }
+            Some("h2.localhost") => Some(Protocol::Http2),
+            _ => None,
+        })
+        .bind_openssl(format!("{}", addr), builder)
+        .unwrap()
+        .run();
+
+        let _ = tx.send((srv, actix_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let connect = |domain: &str| {
+        let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        builder.set_alpn_protos(b"\x02h2\x08http/1.1").unwrap();
+        let stream = net::TcpStream::connect(addr).unwrap();
+        builder
+            .build()
+            .connect(domain, stream)
+            .ok()
+            .map(|stream| stream.ssl().selected_alpn_protocol().map(|p| p.to_vec()))
+    };
+    assert_eq!(connect("h1.localhost"), Some(Some(b"http/1.1".to_vec())));
+    assert_eq!(connect("h2.localhost"), Some(Some(b"h2".to_vec())));
+    assert_eq!(connect("other.localhost"), None);
+
+    // clients without alpn are checked by the callback as well
+    let request = |domain: &str| {
+        let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        let stream = net::TcpStream::connect(addr).unwrap();
+        let mut stream = builder.build().connect(domain, stream).unwrap();
+        let _ = stream.write_all(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n");
+        let mut data = Vec::new();
+        let _ = stream.read_to_end(&mut data);
+        data
+    };
+    assert!(request("h2.localhost").starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(request("h1.localhost").is_empty());
+
+    // stop
+    let _ = srv.stop(false);
+
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
+
+#[actix_rt::test]
+#[cfg(all(feature = "openssl", feature = "rustls"))]
+async fn test_rustls_reload() {
+    use actix_web::RustlsReload;
+    use open_ssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+    use rust_tls::internal::pemfile::{certs, pkcs8_private_keys};
+    use rust_tls::{NoClientAuth, ServerConfig};
+    use std::fs::File;
+    use std::io::BufReader;
+
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    let cert_file = &mut BufReader::new(File::open("tests/cert.pem").unwrap());
+    let key_file = &mut BufReader::new(File::open("tests/key.pem").unwrap());
+    let cert_chain = certs(cert_file).unwrap();
+    let mut keys = pkcs8_private_keys(key_file).unwrap();
+    config.set_single_cert(cert_chain, keys.remove(0)).unwrap();
+    let tls = RustlsReload::new(config.clone());
+
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+    let listener_config = tls.config();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new("test");
+
+        let srv = HttpServer::new(|| {
+            App::new().route("/", web::to(|| HttpResponse::Ok().body("test")))
+        })
+        .workers(1)
+        .shutdown_timeout(1)
+        .system_exit()
+        .disable_signals()
+        .bind_rustls(format!("{}", addr), listener_config)
+        .unwrap()
+        .run();
+
+        let _ = tx.send((srv, actix_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let connect = || {
+        let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        let stream = net::TcpStream::connect(addr).unwrap();
+        builder.build().connect("localhost", stream).is_ok()
+    };
+    assert!(connect());
+
+    // configuration without certificates aborts handshakes
+    tls.reload(&ServerConfig::new(NoClientAuth::new()));
+    assert!(!connect());
+
+    tls.reload(&config);
+    assert!(connect());
+
+    // stop
+    let _ = srv.stop(false);
+
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
+
+#[cfg(target_os = "linux")]
+#[actix_rt::test]
+async fn test_uds_peer_credentials() {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let path =
+        std::env::temp_dir().join(format!("actix-uds-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (tx, rx) = mpsc::channel();
+
+    let uds = path.clone();
+    thread::spawn(move || {
+        let sys = actix_rt::System::new("test");
+
+        let srv = HttpServer::new(|| {
+            App::new().route(
+                "/",
+                web::get().to(
+                    |cred: web::PeerCredentials, req: actix_web::HttpRequest| {
+                        let info = req.connection_info();
+                        HttpResponse::Ok().body(format!(
+                            "{:?} {} {} {}",
+                            cred.pid(),
+                            cred.uid(),
+                            info.remote().unwrap(),
+                            info.host()
+                        ))
+                    },
+                ),
+            )
+        })
+        .workers(1)
+        .disable_signals()
+        .bind_uds(&uds)
+        .unwrap()
+        .run();
+
+        let _ = tx.send((srv, actix_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let mut stream = UnixStream::connect(&path).unwrap();
+    stream.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    let mut data = String::new();
+    stream.read_to_string(&mut data).unwrap();
+
+    let pid = std::process::id();
+    let uid = unsafe { libc::getuid() };
+    assert!(data.starts_with("HTTP/1.0 200 OK"));
+    assert!(data.ends_with(&format!(
+        "Some({}) {} unix:pid={},uid={} localhost",
+        pid, uid, pid, uid
+    )));
+
+    let _ = srv.stop(false);
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+    let _ = std::fs::remove_file(&path);
+}